@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::path::PathBuf;
 
 use playground_systems_logic::{World, SystemsManager, System, handle, shared};
 use playground_core_types::Handle;
@@ -14,6 +15,16 @@ use playground_plugins_chat_assistant::ChatAssistantPlugin;
 use playground_plugins_version_control::VersionControlPlugin;
 use playground_plugins_theme_manager::ThemeManagerPlugin;
 
+/// Where theme files live: `PLAYGROUND_THEMES_DIR`, or a `themes`
+/// directory next to the executable. None leaves only the built-in themes.
+fn themes_directory() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("PLAYGROUND_THEMES_DIR") {
+        return Some(PathBuf::from(path));
+    }
+    let directory = std::env::current_exe().ok()?.parent()?.join("themes");
+    directory.is_dir().then_some(directory)
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging with simple setup
@@ -95,6 +106,14 @@ async fn main() -> Result<()> {
     // Now that all plugins are registered, the NetworkingSystem can build
     // a complete channel manifest
     eprintln!("[EDITOR] Phase 2: Initializing core engine systems...");
+
+    // Every browser and MCP client must authenticate: pair with the code
+    // printed at startup, or use a pre-shared token file
+    systems.set_server_option("auth_enabled", serde_json::json!(true)).await;
+    if let Ok(path) = std::env::var("PLAYGROUND_TOKEN_FILE") {
+        systems.set_server_option("auth_token_file", serde_json::json!(path)).await;
+    }
+
     systems.initialize_all().await?;
     eprintln!("[EDITOR] Phase 2 complete: Core engine systems initialized!");
 
@@ -107,7 +126,9 @@ async fn main() -> Result<()> {
     
     // 1. UI Framework Plugin
     {
-        let mut plugin = UiFrameworkPlugin::new(systems.clone());
+        // Agents act only through the token they were issued, never as
+        // whoever happens to connect without one
+        let mut plugin = UiFrameworkPlugin::new(systems.clone()).with_session_auth(true);
         plugin.initialize(&*world_handle).await?;
         eprintln!("[EDITOR] ✓ UiFrameworkPlugin initialized");
         
@@ -253,7 +274,16 @@ async fn main() -> Result<()> {
     // 9. Theme Manager Plugin
     {
         let plugin_world = world_handle.clone();
+        // Theme files load on top of the built-in themes, and edits reload
+        // while the editor runs
         let mut plugin = ThemeManagerPlugin::new(systems.clone());
+        match themes_directory() {
+            Some(directory) => {
+                eprintln!("[EDITOR] Themes from {}", directory.display());
+                plugin = plugin.with_themes(directory);
+            }
+            None => eprintln!("[EDITOR] No themes directory, using the built-in themes"),
+        }
         plugin.initialize(&*world_handle).await?;
         eprintln!("[EDITOR] ✓ ThemeManagerPlugin initialized");
         
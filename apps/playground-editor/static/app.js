@@ -26,6 +26,18 @@ class UIFrameworkClient {
         // Will be set dynamically from manifest
         this.UI_FRAMEWORK_CHANNEL = null;
         
+        // Resumable session (systems/networking/src/session.rs). Data
+        // packets are numbered from 1 in the order they arrive; on reconnect
+        // the server replays everything after the last one we acknowledged.
+        this.sessionToken = null;
+        this.receivedSequence = 0;
+        this.ackedSequence = 0;
+        this.ackTimer = null;
+        this.SESSION_INFO = 20;
+        this.SESSION_ACK = 21;
+        this.ACK_INTERVAL = 250;  // ms
+        this.ACK_BATCH = 128;     // packets
+        
         // Message types for UI Framework
         this.MSG_TYPES = {
             // Incoming from server
@@ -97,6 +109,14 @@ class UIFrameworkClient {
         window.addEventListener('keydown', (e) => this.handleKeyboard(e, 'down'));
         window.addEventListener('keyup', (e) => this.handleKeyboard(e, 'up'));
         
+        // A phone may drop the socket once the page is hidden, so let the
+        // server release what we already have first
+        document.addEventListener('visibilitychange', () => {
+            if (document.visibilityState === 'hidden') {
+                this.sendSessionAck();
+            }
+        });
+        
         // Prevent default gestures
         document.addEventListener('gesturestart', (e) => e.preventDefault());
         document.addEventListener('gesturechange', (e) => e.preventDefault());
@@ -105,7 +125,16 @@ class UIFrameworkClient {
     
     connect() {
         const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-        const wsUrl = `${protocol}//${window.location.host}/ws`;
+        // Auth token from ?token= (e.g. after pairing) or remembered from a previous visit
+        const pageToken = new URLSearchParams(window.location.search).get('token');
+        if (pageToken) {
+            localStorage.setItem('playgroundToken', pageToken);
+        }
+        const token = pageToken || localStorage.getItem('playgroundToken') || '';
+        let wsUrl = `${protocol}//${window.location.host}/ws?token=${encodeURIComponent(token)}`;
+        if (this.sessionToken) {
+            wsUrl += `&session=${encodeURIComponent(this.sessionToken)}&ack=${this.receivedSequence}`;
+        }
         
         console.log(`=== WebSocket Connection Attempt ===`);
         console.log(`URL: ${wsUrl}`);
@@ -221,6 +250,14 @@ class UIFrameworkClient {
         
         console.log(`Received binary message: channel=${channelId}, type=${packetType}, size=${payloadSize}`);
 
+        if (channelId === 0 && packetType === this.SESSION_INFO) {
+            this.handleSessionInfo(data);
+            return;
+        }
+        // Everything else the session numbers and may replay
+        this.receivedSequence++;
+        this.scheduleSessionAck();
+
         // Check if this is a control channel message
         if (channelId === 0) {
             if (packetType === 9) { // ChannelManifest
@@ -250,6 +287,8 @@ class UIFrameworkClient {
                 this.loadTexture(payload);
             } else if (packetType === 110) { // UnloadResource
                 this.unloadResource(payload);
+            } else if (packetType === 111) { // AccessibilityTree
+                this.updateAccessibilityTree(payload);
             } else {
                 this.sendLog('warning', `Unknown UI packet type ${packetType} on channel ${channelId}`);
             }
@@ -276,6 +315,53 @@ class UIFrameworkClient {
         }
     }
 
+    handleSessionInfo(data) {
+        // 12-byte header, then bincode SessionInfo { token, resumed, next_sequence }
+        const view = new DataView(data, 12);
+        const tokenLength = Number(view.getBigUint64(0, true));
+        const token = new TextDecoder().decode(new Uint8Array(data, 20, tokenLength));
+        const resumed = view.getUint8(8 + tokenLength) !== 0;
+        const nextSequence = Number(view.getBigUint64(9 + tokenLength, true));
+        
+        if (this.sessionToken && !resumed) {
+            // Expired or too far behind: channels and resources start over
+            this.sendLog('warn', 'Session could not be resumed, starting a new one');
+        } else if (resumed) {
+            this.sendLog('info', `Session resumed at packet ${nextSequence}`);
+        }
+        
+        this.sessionToken = token;
+        this.receivedSequence = nextSequence - 1;
+        this.ackedSequence = this.receivedSequence;
+    }
+    
+    scheduleSessionAck() {
+        if (this.receivedSequence - this.ackedSequence >= this.ACK_BATCH) {
+            this.sendSessionAck();
+        } else if (!this.ackTimer) {
+            this.ackTimer = setTimeout(() => this.sendSessionAck(), this.ACK_INTERVAL);
+        }
+    }
+    
+    sendSessionAck() {
+        clearTimeout(this.ackTimer);
+        this.ackTimer = null;
+        if (!this.ws || this.ws.readyState !== WebSocket.OPEN || this.receivedSequence === this.ackedSequence) {
+            return;
+        }
+        
+        const packet = new ArrayBuffer(12 + 8);
+        const view = new DataView(packet);
+        view.setUint16(0, 0, true);                 // Control channel
+        view.setUint16(2, this.SESSION_ACK, true);
+        view.setUint8(4, 2);                        // Priority high
+        view.setUint32(8, 8, true);
+        view.setBigUint64(12, BigInt(this.receivedSequence), true);
+        
+        this.ws.send(packet);
+        this.ackedSequence = this.receivedSequence;
+    }
+
     handleJsonMessage(msg) {
         // Handle control messages
         if (msg.type === 'channel_registered') {
@@ -814,6 +900,9 @@ class UIFrameworkClient {
     onDisconnect() {
         console.log('Disconnected from UI Framework');
         document.body.classList.remove('ui-ready');
+        // The reconnect acknowledges through `?ack=` instead
+        clearTimeout(this.ackTimer);
+        this.ackTimer = null;
         
         // Try to reconnect
         if (this.reconnectAttempts < this.maxReconnectAttempts) {
@@ -978,6 +1067,100 @@ class UIFrameworkClient {
             this.sendLog('error', `Failed to unload resource: ${e.message}`);
         }
     }
+    
+    // Mirror the server's accessibility tree as invisible DOM over the canvas,
+    // so screen readers can browse it and activate elements
+    updateAccessibilityTree(payload) {
+        try {
+            const message = JSON.parse(new TextDecoder().decode(payload));
+            
+            if (!this.a11yRoot) {
+                this.a11yRoot = document.createElement('div');
+                this.a11yRoot.id = 'a11y-tree';
+                document.getElementById('app').appendChild(this.a11yRoot);
+            }
+            
+            // Node bounds are in canvas pixels, the overlay in CSS pixels
+            const rect = this.canvas.getBoundingClientRect();
+            const scale = rect.width / this.canvas.width;
+            Object.assign(this.a11yRoot.style, {
+                left: `${rect.left}px`,
+                top: `${rect.top}px`,
+                width: `${rect.width}px`,
+                height: `${rect.height}px`,
+            });
+            
+            this.a11yRoot.replaceChildren(
+                ...message.nodes.map(node => this.buildAccessibilityNode(node, 0, 0, scale))
+            );
+            
+            const focused = this.a11yRoot.querySelector('[data-focused]');
+            if (focused && document.activeElement !== focused) {
+                focused.focus({ preventScroll: true });
+            }
+        } catch (e) {
+            this.sendLog('error', `Failed to update accessibility tree: ${e.message}`);
+        }
+    }
+    
+    buildAccessibilityNode(node, originX, originY, scale) {
+        const el = document.createElement(node.tag);
+        for (const [name, value] of Object.entries(node.attributes)) {
+            el.setAttribute(name, value);
+        }
+        if (node.text) {
+            el.textContent = node.text;
+        }
+        if (node.focused) {
+            el.dataset.focused = '';
+        }
+        
+        // Positioned relative to the parent node
+        const [x, y, width, height] = node.bounds;
+        Object.assign(el.style, {
+            left: `${(x - originX) * scale}px`,
+            top: `${(y - originY) * scale}px`,
+            width: `${width * scale}px`,
+            height: `${height * scale}px`,
+        });
+        
+        el.addEventListener('click', (e) => {
+            e.stopPropagation();
+            this.sendAccessibilityAction(node.element, 'Tap');
+        });
+        el.addEventListener('focus', () => {
+            // Focus the tree moved here itself is already known to the server
+            if (!node.focused) {
+                this.sendAccessibilityAction(node.element, 'Focus');
+            }
+        });
+        
+        for (const child of node.children) {
+            el.appendChild(this.buildAccessibilityNode(child, x, y, scale));
+        }
+        return el;
+    }
+    
+    sendAccessibilityAction(element, action) {
+        const channel = this.UI_FRAMEWORK_CHANNEL;
+        if (!this.ws || this.ws.readyState !== WebSocket.OPEN || !channel) {
+            return;
+        }
+        
+        const payloadBytes = new TextEncoder().encode(JSON.stringify({ element, action }));
+        const packet = new ArrayBuffer(12 + payloadBytes.length);
+        const view = new DataView(packet);
+        
+        // 12-byte header as the server parses it (little-endian), three
+        // reserved bytes after the priority
+        view.setUint16(0, channel, true);
+        view.setUint16(2, 8, true); // PACKET_TYPE_ACCESSIBILITY_ACTION
+        view.setUint8(4, 2); // Priority: High
+        view.setUint32(8, payloadBytes.length, true);
+        new Uint8Array(packet, 12).set(payloadBytes);
+        
+        this.ws.send(packet);
+    }
 }
 
 // Global reconnect function
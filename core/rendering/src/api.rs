@@ -2,12 +2,27 @@
 //!
 //! These functions work with entities and components in the ECS,
 //! similar to how core/server and core/client work.
-
-use playground_core_types::CoreResult;
+//!
+//! Resource functions allocate IDs, validate descriptions against the
+//! renderer's capabilities and track every live resource in the renderer's
+//! storage components. The actual work is delegated over the VTable to the
+//! active backend system on the `renderer.<backend>` channel (for example
+//! `renderer.software`). A renderer without a backend only does the bookkeeping.
+
+use bytes::Bytes;
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::sync::OwnedMutexGuard;
+use playground_core_types::{CoreResult, CoreError};
 use playground_core_ecs::{Entity, EntityRef, get_world};
 use crate::types::*;
 use crate::components::*;
 use crate::commands::RenderCommand;
+use crate::validation;
+use crate::locks::RENDERER_LOCKS;
+#[cfg(feature = "batching")]
+use crate::locks::ATLAS_LOCKS;
+#[cfg(feature = "framegraphs")]
+use crate::graph::{self, CompiledRenderGraph, RenderGraph};
 
 /// Create a renderer entity with the given configuration
 /// Returns the renderer entity
@@ -20,6 +35,10 @@ pub async fn create_renderer(config: RendererConfig) -> CoreResult<Entity> {
     renderer_entity.add_component(RendererStatsComponent::default()).await?;
     renderer_entity.add_component(RendererCapabilitiesComponent::default()).await?;
     renderer_entity.add_component(RendererBackend::default()).await?;
+    renderer_entity.add_component(ResourceAllocator::default()).await?;
+    RENDERER_LOCKS.reset(&renderer_entity);
+    #[cfg(feature = "batching")]
+    ATLAS_LOCKS.reset(&renderer_entity);
 
     // Add OPTIONAL components based on features
     #[cfg(feature = "targets")]
@@ -49,167 +68,935 @@ pub async fn create_renderer(config: RendererConfig) -> CoreResult<Entity> {
     #[cfg(feature = "passes")]
     renderer_entity.add_component(RenderPassStorage::default()).await?;
 
+    #[cfg(feature = "framegraphs")]
+    renderer_entity.add_component(TransientTargetPool::default()).await?;
+
+    #[cfg(feature = "batching")]
+    renderer_entity.add_component(SpriteAtlasStorage::default()).await?;
+
     Ok(renderer_entity)
 }
 
 /// Initialize a renderer entity
-pub async fn initialize_renderer(_renderer: EntityRef, _config: RendererConfig) -> CoreResult<()> {
-    // Stub - systems/webgl does actual initialization
+pub async fn initialize_renderer(renderer: EntityRef, config: RendererConfig) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+
+    let mut backend = entity.get_component::<RendererBackend>().await?;
+    if backend.is_initialized {
+        return Err(CoreError::AlreadyInitialized);
+    }
+
+    entity.remove_component::<RendererConfigComponent>().await?;
+    entity.add_component(RendererConfigComponent(config.clone())).await?;
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "initialize", &config).await?;
+        let capabilities: RendererCapabilities = decode(send(&channel, "get_capabilities", &()).await?)?;
+        entity.remove_component::<RendererCapabilitiesComponent>().await?;
+        entity.add_component(RendererCapabilitiesComponent(capabilities)).await?;
+    }
+
+    backend.is_initialized = true;
+    entity.remove_component::<RendererBackend>().await?;
+    entity.add_component(backend).await?;
     Ok(())
 }
 
-/// Shutdown a renderer entity
-pub async fn shutdown_renderer(_renderer: EntityRef) -> CoreResult<()> {
-    // Stub - systems/webgl does actual shutdown
+/// Shutdown a renderer entity. Every resource it owned is released.
+pub async fn shutdown_renderer(renderer: EntityRef) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+
+    let mut backend = entity.get_component::<RendererBackend>().await?;
+    if !backend.is_initialized {
+        return Err(CoreError::NotInitialized);
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "shutdown", &()).await?;
+    }
+
+    #[cfg(feature = "targets")]
+    {
+        entity.remove_component::<RenderTargetStorage>().await?;
+        entity.add_component(RenderTargetStorage::default()).await?;
+    }
+
+    #[cfg(feature = "framegraphs")]
+    {
+        entity.remove_component::<TransientTargetPool>().await?;
+        entity.add_component(TransientTargetPool::default()).await?;
+    }
+
+    #[cfg(feature = "batching")]
+    {
+        entity.remove_component::<SpriteAtlasStorage>().await?;
+        entity.add_component(SpriteAtlasStorage::default()).await?;
+    }
+
+    #[cfg(feature = "shaders")]
+    {
+        entity.remove_component::<ShaderStorage>().await?;
+        entity.add_component(ShaderStorage::default()).await?;
+    }
+
+    #[cfg(feature = "textures")]
+    {
+        entity.remove_component::<TextureStorage>().await?;
+        entity.add_component(TextureStorage::default()).await?;
+    }
+
+    #[cfg(feature = "buffers")]
+    {
+        entity.remove_component::<BufferStorage>().await?;
+        entity.add_component(BufferStorage::default()).await?;
+    }
+
+    #[cfg(feature = "pipelines")]
+    {
+        entity.remove_component::<PipelineStorage>().await?;
+        entity.add_component(PipelineStorage::default()).await?;
+    }
+
+    #[cfg(feature = "commands")]
+    {
+        entity.remove_component::<CommandBufferStorage>().await?;
+        entity.add_component(CommandBufferStorage::default()).await?;
+    }
+
+    backend.is_initialized = false;
+    entity.remove_component::<RendererBackend>().await?;
+    entity.add_component(backend).await?;
     Ok(())
 }
 
 /// Get renderer capabilities
-pub async fn get_capabilities(_renderer: EntityRef) -> CoreResult<RendererCapabilities> {
-    // Stub - systems/webgl maintains actual capabilities
-    Ok(RendererCapabilities::default())
+pub async fn get_capabilities(renderer: EntityRef) -> CoreResult<RendererCapabilities> {
+    let entity = resolve(&renderer)?;
+    Ok(entity.get_component::<RendererCapabilitiesComponent>().await?.0)
 }
 
-/// Get renderer statistics
-pub async fn get_stats(_renderer: EntityRef) -> CoreResult<RendererStats> {
-    // Stub - systems/webgl maintains actual statistics
-    Ok(RendererStats::default())
+/// Get renderer statistics. Frame counters come from the backend;
+/// resource counts and memory come from the tracked resources.
+pub async fn get_stats(renderer: EntityRef) -> CoreResult<RendererStats> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+
+    let mut stats = entity.get_component::<RendererStatsComponent>().await?.0;
+    let backend = entity.get_component::<RendererBackend>().await?;
+    if backend.is_initialized {
+        if let Some(channel) = backend_channel(&backend) {
+            stats = decode(send(&channel, "get_stats", &()).await?)?;
+        }
+    }
+
+    stats.resource_memory = 0;
+
+    #[cfg(feature = "textures")]
+    {
+        let storage = entity.get_component::<TextureStorage>().await?;
+        stats.texture_count = storage.textures.len() as UInt;
+        stats.resource_memory += storage.textures.values().map(validation::texture_memory).sum::<usize>();
+    }
+
+    #[cfg(feature = "buffers")]
+    {
+        let storage = entity.get_component::<BufferStorage>().await?;
+        stats.buffer_count = storage.buffers.len() as UInt;
+        stats.resource_memory += storage.buffers.values().map(|info| info.size).sum::<usize>();
+    }
+
+    #[cfg(feature = "shaders")]
+    {
+        let storage = entity.get_component::<ShaderStorage>().await?;
+        stats.shader_count = storage.shaders.len() as UInt;
+    }
+
+    #[cfg(feature = "pipelines")]
+    {
+        let storage = entity.get_component::<PipelineStorage>().await?;
+        stats.pipeline_count = storage.pipelines.len() as UInt;
+    }
+
+    #[cfg(feature = "targets")]
+    {
+        let storage = entity.get_component::<RenderTargetStorage>().await?;
+        stats.render_target_count = storage.targets.len() as UInt;
+        stats.resource_memory += storage.targets.values().map(validation::render_target_memory).sum::<usize>();
+    }
+
+    entity.remove_component::<RendererStatsComponent>().await?;
+    entity.add_component(RendererStatsComponent(stats.clone())).await?;
+    Ok(stats)
 }
 
-/// Switch to a different rendering backend
-pub async fn switch_backend(_renderer: EntityRef, _backend: &str) -> CoreResult<()> {
-    // Stub - systems/webgl or systems/vulkan handle backend switching
+/// Switch to a different rendering backend.
+/// If the renderer is initialized, the new backend is initialized and every
+/// tracked resource is recreated on it. Texture and buffer contents are not
+/// kept by the API and must be uploaded again.
+pub async fn switch_backend(renderer: EntityRef, backend_name: &str) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+
+    let mut backend = entity.get_component::<RendererBackend>().await?;
+    if backend.active_backend == backend_name {
+        return Ok(());
+    }
+
+    if backend.is_initialized {
+        if let Some(channel) = backend_channel(&backend) {
+            send(&channel, "shutdown", &()).await?;
+        }
+    }
+
+    backend.active_backend = backend_name.to_string();
+
+    if backend.is_initialized {
+        if let Some(channel) = backend_channel(&backend) {
+            let config = entity.get_component::<RendererConfigComponent>().await?.0;
+            send(&channel, "initialize", &config).await?;
+
+            let capabilities: RendererCapabilities = decode(send(&channel, "get_capabilities", &()).await?)?;
+            entity.remove_component::<RendererCapabilitiesComponent>().await?;
+            entity.add_component(RendererCapabilitiesComponent(capabilities)).await?;
+
+            recreate_resources(&entity, &channel).await?;
+        }
+    }
+
+    entity.remove_component::<RendererBackend>().await?;
+    entity.add_component(backend).await?;
     Ok(())
 }
 
 /// Submit a frame worth of commands
-pub async fn submit_frame(_renderer: EntityRef, _commands: Vec<RenderCommand>) -> CoreResult<()> {
-    // Stub - systems/webgl does actual rendering
+pub async fn submit_frame(renderer: EntityRef, commands: Vec<RenderCommand>) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    validate_commands(&entity, &commands).await?;
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "submit_frame", &commands).await?;
+    }
     Ok(())
 }
 
 /// Present the current frame
-pub async fn present(_renderer: EntityRef) -> CoreResult<()> {
-    // Stub - systems/webgl does actual presentation
+pub async fn present(renderer: EntityRef) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "present", &()).await?;
+    }
     Ok(())
 }
 
 // Command buffer operations
 #[cfg(feature = "commands")]
-pub async fn create_command_buffer(_renderer: EntityRef) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl creates actual command buffer
-    Ok(0)
+pub async fn create_command_buffer(renderer: EntityRef) -> CoreResult<ResourceId> {
+    use crate::commands::{CommandBufferInfo, CommandBufferState, CommandBufferLevel};
+
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let id = allocate_id(&entity).await?;
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "create_command_buffer", &id).await?;
+    }
+
+    let mut storage = entity.get_component::<CommandBufferStorage>().await?;
+    storage.command_buffers.insert(id, CommandBufferInfo {
+        id,
+        state: CommandBufferState::Initial,
+        level: CommandBufferLevel::Primary,
+    });
+    entity.remove_component::<CommandBufferStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
 #[cfg(feature = "commands")]
-pub async fn begin_recording(_renderer: EntityRef, _buffer_id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl handles recording
+pub async fn begin_recording(renderer: EntityRef, buffer_id: ResourceId) -> CoreResult<()> {
+    use crate::commands::CommandBufferState;
+
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<CommandBufferStorage>().await?;
+    if let Some(recording) = storage.recording_buffer {
+        return Err(CoreError::InvalidState(format!("Command buffer {} is already recording", recording)));
+    }
+    let buffer = storage.command_buffers.get_mut(&buffer_id).ok_or_else(|| missing("Command buffer", buffer_id))?;
+    if !matches!(buffer.state, CommandBufferState::Initial | CommandBufferState::Executable) {
+        return Err(CoreError::InvalidState(format!("Command buffer {} can't start recording in state {:?}", buffer_id, buffer.state)));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "begin_recording", &buffer_id).await?;
+    }
+
+    buffer.state = CommandBufferState::Recording;
+    storage.recording_buffer = Some(buffer_id);
+    entity.remove_component::<CommandBufferStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
 #[cfg(feature = "commands")]
-pub async fn end_recording(_renderer: EntityRef, _buffer_id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl handles recording
+pub async fn end_recording(renderer: EntityRef, buffer_id: ResourceId) -> CoreResult<()> {
+    use crate::commands::CommandBufferState;
+
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<CommandBufferStorage>().await?;
+    let buffer = storage.command_buffers.get_mut(&buffer_id).ok_or_else(|| missing("Command buffer", buffer_id))?;
+    if !matches!(buffer.state, CommandBufferState::Recording) {
+        return Err(CoreError::InvalidState(format!("Command buffer {} is not recording", buffer_id)));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "end_recording", &buffer_id).await?;
+    }
+
+    buffer.state = CommandBufferState::Executable;
+    storage.recording_buffer = None;
+    entity.remove_component::<CommandBufferStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
 #[cfg(feature = "commands")]
-pub async fn submit_commands(_renderer: EntityRef, _buffer_id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl executes commands
+pub async fn submit_commands(renderer: EntityRef, buffer_id: ResourceId) -> CoreResult<()> {
+    use crate::commands::CommandBufferState;
+
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let storage = entity.get_component::<CommandBufferStorage>().await?;
+    let buffer = storage.command_buffers.get(&buffer_id).ok_or_else(|| missing("Command buffer", buffer_id))?;
+    if !matches!(buffer.state, CommandBufferState::Executable) {
+        return Err(CoreError::InvalidState(format!("Command buffer {} has not finished recording", buffer_id)));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "submit_commands", &buffer_id).await?;
+    }
     Ok(())
 }
 
 // Render target operations
 #[cfg(feature = "targets")]
-pub async fn create_render_target(_renderer: EntityRef, _info: crate::resources::RenderTargetInfo) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl creates actual render target
-    Ok(0)
+pub async fn create_render_target(renderer: EntityRef, mut info: crate::resources::RenderTargetInfo) -> CoreResult<ResourceId> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    let mut storage = entity.get_component::<RenderTargetStorage>().await?;
+    validation::validate_render_target(&info, &capabilities, storage.targets.len())?;
+
+    info.id = allocate_id(&entity).await?;
+    info.is_default = false;
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "create_render_target", &info).await?;
+    }
+
+    let id = info.id;
+    storage.targets.insert(id, info);
+    entity.remove_component::<RenderTargetStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
 #[cfg(feature = "targets")]
-pub async fn destroy_render_target(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl destroys render target
+pub async fn destroy_render_target(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<RenderTargetStorage>().await?;
+    if !storage.targets.contains_key(&id) {
+        return Err(missing("Render target", id));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "destroy_render_target", &id).await?;
+    }
+
+    storage.targets.remove(&id);
+    if storage.current_target == Some(id) {
+        storage.current_target = None;
+    }
+    entity.remove_component::<RenderTargetStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
+/// Set the active render target; `None` selects the default framebuffer
 #[cfg(feature = "targets")]
-pub async fn set_render_target(_renderer: EntityRef, _id: Option<ResourceId>) -> CoreResult<()> {
-    // Stub - systems/webgl sets active render target
+pub async fn set_render_target(renderer: EntityRef, id: Option<ResourceId>) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<RenderTargetStorage>().await?;
+    if let Some(id) = id {
+        if !storage.targets.contains_key(&id) {
+            return Err(missing("Render target", id));
+        }
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "set_render_target", &id).await?;
+    }
+
+    storage.current_target = id;
+    entity.remove_component::<RenderTargetStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
 #[cfg(feature = "targets")]
-pub async fn resize_render_target(_renderer: EntityRef, _id: ResourceId, _width: UInt, _height: UInt) -> CoreResult<()> {
-    // Stub - systems/webgl resizes render target
+pub async fn resize_render_target(renderer: EntityRef, id: ResourceId, width: UInt, height: UInt) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    let mut storage = entity.get_component::<RenderTargetStorage>().await?;
+    let mut resized = storage.targets.get(&id).cloned().ok_or_else(|| missing("Render target", id))?;
+    resized.width = width;
+    resized.height = height;
+    // The target being resized doesn't count against the limit
+    validation::validate_render_target(&resized, &capabilities, storage.targets.len() - 1)?;
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "resize_render_target", &(id, width, height)).await?;
+    }
+
+    storage.targets.insert(id, resized);
+    entity.remove_component::<RenderTargetStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
+// Render graph operations
+/// Compile a render graph against this renderer
+///
+/// The active backend orders and culls the passes and decides which
+/// transient targets share storage. Physical targets are bound to render
+/// targets in the renderer's `TransientTargetPool` with the same shape;
+/// missing ones are created and kept for later frames. Returns the command
+/// stream for `submit_frame`.
+#[cfg(feature = "framegraphs")]
+pub async fn compile_render_graph(renderer: EntityRef, graph: &RenderGraph) -> CoreResult<Vec<RenderCommand>> {
+    let entity = resolve(&renderer)?;
+    let backend = initialized_backend(&entity).await?;
+    let channel = backend_channel(&backend)
+        .ok_or_else(|| CoreError::InvalidState("Render graphs need an active backend to compile".to_string()))?;
+    let compiled: CompiledRenderGraph = decode(send(&channel, "compile_render_graph", graph).await?)?;
+
+    let storage = entity.get_component::<RenderTargetStorage>().await?;
+    // Pool entries destroyed or resized through the target API are read back from storage
+    let available: Vec<crate::resources::RenderTargetInfo> = entity.get_component::<TransientTargetPool>().await?
+        .targets.iter()
+        .filter_map(|target| storage.targets.get(&target.id).cloned())
+        .collect();
+
+    let mut taken = vec![false; available.len()];
+    let mut ids = Vec::with_capacity(compiled.physical_targets.len());
+    for info in &compiled.physical_targets {
+        let existing = (0..available.len())
+            .find(|&index| !taken[index] && graph::same_shape(&available[index], info));
+        match existing {
+            Some(index) => {
+                taken[index] = true;
+                ids.push(available[index].id);
+            }
+            None => ids.push(create_render_target(renderer.clone(), info.clone()).await?),
+        }
+    }
+
+    {
+        let _guard = lock_renderer(&entity).await;
+        let storage = entity.get_component::<RenderTargetStorage>().await?;
+        let mut pool = entity.get_component::<TransientTargetPool>().await?;
+        pool.targets.retain(|target| storage.targets.contains_key(&target.id));
+        for id in &ids {
+            if !pool.targets.iter().any(|target| target.id == *id)
+                && let Some(info) = storage.targets.get(id)
+            {
+                pool.targets.push(info.clone());
+            }
+        }
+        entity.remove_component::<TransientTargetPool>().await?;
+        entity.add_component(pool).await?;
+    }
+
+    decode(send(&channel, "render_graph_commands", &(graph, &compiled, &ids)).await?)
+}
+
 // Shader operations
 #[cfg(feature = "shaders")]
-pub async fn compile_shader(_renderer: EntityRef, _source: String, _stage: crate::resources::ShaderStage) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl compiles actual shader
-    Ok(0)
+pub async fn compile_shader(renderer: EntityRef, source: String, stage: crate::resources::ShaderStage) -> CoreResult<ResourceId> {
+    use crate::resources::{ShaderInfo, ShaderSourceType};
+
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    validation::validate_shader(&source, stage, &capabilities)?;
+
+    let info = ShaderInfo {
+        id: allocate_id(&entity).await?,
+        stage,
+        source_type: ShaderSourceType::GLSL,
+        entry_point: "main".to_string(),
+    };
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "compile_shader", &(&info, &source)).await?;
+    }
+
+    let id = info.id;
+    let mut storage = entity.get_component::<ShaderStorage>().await?;
+    storage.shaders.insert(id, info);
+    storage.sources.insert(id, source);
+    entity.remove_component::<ShaderStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
+/// Destroy a shader. Fails while a pipeline still uses it.
 #[cfg(feature = "shaders")]
-pub async fn destroy_shader(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl destroys shader
+pub async fn destroy_shader(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<ShaderStorage>().await?;
+    if !storage.shaders.contains_key(&id) {
+        return Err(missing("Shader", id));
+    }
+
+    #[cfg(feature = "pipelines")]
+    {
+        let pipelines = entity.get_component::<PipelineStorage>().await?;
+        if let Some(user) = pipelines.pipelines.values()
+            .find(|pipeline| pipeline.vertex_shader == id || pipeline.fragment_shader == Some(id))
+        {
+            return Err(CoreError::InvalidState(format!("Shader {} is still used by pipeline {}", id, user.id)));
+        }
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "destroy_shader", &id).await?;
+    }
+
+    storage.shaders.remove(&id);
+    storage.sources.remove(&id);
+    entity.remove_component::<ShaderStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
 // Texture operations
 #[cfg(feature = "textures")]
-pub async fn create_texture(_renderer: EntityRef, _info: crate::resources::TextureInfo) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl creates actual texture
-    Ok(0)
+pub async fn create_texture(renderer: EntityRef, mut info: crate::resources::TextureInfo) -> CoreResult<ResourceId> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    validation::validate_texture(&info, &capabilities)?;
+
+    info.id = allocate_id(&entity).await?;
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "create_texture", &info).await?;
+    }
+
+    let id = info.id;
+    let mut storage = entity.get_component::<TextureStorage>().await?;
+    storage.textures.insert(id, info);
+    entity.remove_component::<TextureStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
 #[cfg(feature = "textures")]
-pub async fn destroy_texture(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl destroys texture
+pub async fn destroy_texture(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<TextureStorage>().await?;
+    if !storage.textures.contains_key(&id) {
+        return Err(missing("Texture", id));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "destroy_texture", &id).await?;
+    }
+
+    storage.textures.remove(&id);
+    entity.remove_component::<TextureStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
+/// Replace a texture's contents. `data` must hold the base mip level of every layer.
 #[cfg(feature = "textures")]
-pub async fn update_texture(_renderer: EntityRef, _id: ResourceId, _data: Vec<u8>) -> CoreResult<()> {
-    // Stub - systems/webgl updates texture data
+pub async fn update_texture(renderer: EntityRef, id: ResourceId, data: Vec<u8>) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let storage = entity.get_component::<TextureStorage>().await?;
+    let info = storage.textures.get(&id).ok_or_else(|| missing("Texture", id))?;
+    if !info.usage.copy_dst {
+        return Err(CoreError::InvalidInput(format!("Texture {} was not created with copy_dst usage", id)));
+    }
+    let expected = validation::texture_upload_bytes(info);
+    if data.len() != expected {
+        return Err(CoreError::InvalidInput(format!("Texture {} expects {} bytes, got {}", id, expected, data.len())));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "update_texture", &(id, data)).await?;
+    }
     Ok(())
 }
 
 // Buffer operations
 #[cfg(feature = "buffers")]
-pub async fn create_buffer(_renderer: EntityRef, _info: crate::resources::BufferInfo) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl creates actual buffer
-    Ok(0)
+pub async fn create_buffer(renderer: EntityRef, mut info: crate::resources::BufferInfo) -> CoreResult<ResourceId> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    validation::validate_buffer(&info, &capabilities)?;
+
+    info.id = allocate_id(&entity).await?;
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "create_buffer", &info).await?;
+    }
+
+    let id = info.id;
+    let mut storage = entity.get_component::<BufferStorage>().await?;
+    storage.buffers.insert(id, info);
+    entity.remove_component::<BufferStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
 #[cfg(feature = "buffers")]
-pub async fn destroy_buffer(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl destroys buffer
+pub async fn destroy_buffer(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<BufferStorage>().await?;
+    if !storage.buffers.contains_key(&id) {
+        return Err(missing("Buffer", id));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "destroy_buffer", &id).await?;
+    }
+
+    storage.buffers.remove(&id);
+    entity.remove_component::<BufferStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
+/// Write `data` at the start of a buffer
 #[cfg(feature = "buffers")]
-pub async fn update_buffer(_renderer: EntityRef, _id: ResourceId, _data: Vec<u8>) -> CoreResult<()> {
-    // Stub - systems/webgl updates buffer data
+pub async fn update_buffer(renderer: EntityRef, id: ResourceId, data: Vec<u8>) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let storage = entity.get_component::<BufferStorage>().await?;
+    let info = storage.buffers.get(&id).ok_or_else(|| missing("Buffer", id))?;
+    validation::validate_buffer_update(info, data.len())?;
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "update_buffer", &(id, data)).await?;
+    }
     Ok(())
 }
 
 // Pipeline operations
 #[cfg(feature = "pipelines")]
-pub async fn create_pipeline(_renderer: EntityRef, _info: crate::resources::PipelineInfo) -> CoreResult<ResourceId> {
-    // Stub - systems/webgl creates actual pipeline
-    Ok(0)
+pub async fn create_pipeline(renderer: EntityRef, mut info: crate::resources::PipelineInfo) -> CoreResult<ResourceId> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+    let capabilities = entity.get_component::<RendererCapabilitiesComponent>().await?.0;
+
+    let shaders = entity.get_component::<ShaderStorage>().await?;
+    validation::validate_pipeline(&info, &shaders.shaders, &capabilities)?;
+
+    info.id = allocate_id(&entity).await?;
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "create_pipeline", &info).await?;
+    }
+
+    let id = info.id;
+    let mut storage = entity.get_component::<PipelineStorage>().await?;
+    storage.pipelines.insert(id, info);
+    entity.remove_component::<PipelineStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(id)
 }
 
 #[cfg(feature = "pipelines")]
-pub async fn destroy_pipeline(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl destroys pipeline
+pub async fn destroy_pipeline(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<PipelineStorage>().await?;
+    if !storage.pipelines.contains_key(&id) {
+        return Err(missing("Pipeline", id));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "destroy_pipeline", &id).await?;
+    }
+
+    storage.pipelines.remove(&id);
+    if storage.current_pipeline == Some(id) {
+        storage.current_pipeline = None;
+    }
+    entity.remove_component::<PipelineStorage>().await?;
+    entity.add_component(storage).await?;
     Ok(())
 }
 
 #[cfg(feature = "pipelines")]
-pub async fn bind_pipeline(_renderer: EntityRef, _id: ResourceId) -> CoreResult<()> {
-    // Stub - systems/webgl binds pipeline
+pub async fn bind_pipeline(renderer: EntityRef, id: ResourceId) -> CoreResult<()> {
+    let entity = resolve(&renderer)?;
+    let _guard = lock_renderer(&entity).await;
+    let backend = initialized_backend(&entity).await?;
+
+    let mut storage = entity.get_component::<PipelineStorage>().await?;
+    if !storage.pipelines.contains_key(&id) {
+        return Err(missing("Pipeline", id));
+    }
+
+    if let Some(channel) = backend_channel(&backend) {
+        send(&channel, "bind_pipeline", &id).await?;
+    }
+
+    storage.current_pipeline = Some(id);
+    entity.remove_component::<PipelineStorage>().await?;
+    entity.add_component(storage).await?;
+    Ok(())
+}
+
+/// Take the lock held while packing a renderer's sprite atlases. Packing
+/// creates and uploads textures, which take the renderer's own lock, so
+/// it has a lock of its own.
+#[cfg(feature = "batching")]
+pub async fn lock_sprite_atlas(renderer: EntityRef) -> CoreResult<OwnedMutexGuard<()>> {
+    let entity = resolve(&renderer)?;
+    Ok(ATLAS_LOCKS.lock(&entity).await)
+}
+
+// Internal helpers
+
+fn resolve(renderer: &EntityRef) -> CoreResult<Entity> {
+    renderer.upgrade().ok_or(CoreError::InvalidEntity)
+}
+
+fn missing(kind: &str, id: ResourceId) -> CoreError {
+    CoreError::NotFound(format!("{} {} does not exist", kind, id))
+}
+
+/// VTable channel of the active backend, if one has been selected
+fn backend_channel(backend: &RendererBackend) -> Option<String> {
+    if backend.active_backend.is_empty() {
+        None
+    } else {
+        Some(format!("renderer.{}", backend.active_backend))
+    }
+}
+
+/// Take the renderer's update lock for a read-modify-write of its
+/// components or a call into its backend
+async fn lock_renderer(entity: &Entity) -> OwnedMutexGuard<()> {
+    RENDERER_LOCKS.lock(entity).await
+}
+
+async fn initialized_backend(entity: &Entity) -> CoreResult<RendererBackend> {
+    let backend = entity.get_component::<RendererBackend>().await?;
+    if !backend.is_initialized {
+        return Err(CoreError::NotInitialized);
+    }
+    Ok(backend)
+}
+
+async fn allocate_id(entity: &Entity) -> CoreResult<ResourceId> {
+    let mut allocator = entity.get_component::<ResourceAllocator>().await?;
+    let id = allocator.next_id;
+    allocator.next_id = id.checked_add(1)
+        .ok_or_else(|| CoreError::InvalidState("Renderer ran out of resource IDs".to_string()))?;
+    entity.remove_component::<ResourceAllocator>().await?;
+    entity.add_component(allocator).await?;
+    Ok(id)
+}
+
+/// Send one operation to a backend and return its response payload
+async fn send<T: Serialize>(channel: &str, operation: &str, args: &T) -> CoreResult<Option<Bytes>> {
+    let payload = bincode::serialize(args)
+        .map_err(|e| CoreError::SerializationError(e.to_string()))?;
+
+    let world = get_world().await?;
+    let response = world.vtable.send_command(
+        channel,
+        operation.to_string(),
+        Bytes::from(payload)
+    ).await?;
+
+    if !response.success {
+        return Err(CoreError::Generic(
+            response.error.unwrap_or_else(|| format!("Renderer backend failed: {}", operation))
+        ));
+    }
+
+    Ok(response.payload)
+}
+
+fn decode<T: DeserializeOwned>(payload: Option<Bytes>) -> CoreResult<T> {
+    let payload = payload.ok_or(CoreError::UnexpectedResponse)?;
+    bincode::deserialize(&payload).map_err(|e| CoreError::DeserializationError(e.to_string()))
+}
+
+/// Reject commands that reference resources this renderer doesn't own
+async fn validate_commands(entity: &Entity, commands: &[RenderCommand]) -> CoreResult<()> {
+    #[cfg(all(feature = "core-2d", feature = "textures"))]
+    {
+        let storage = entity.get_component::<TextureStorage>().await?;
+        for command in commands {
+            if let RenderCommand::DrawImage { texture_id, .. } = command {
+                if !storage.textures.contains_key(texture_id) {
+                    return Err(missing("Texture", *texture_id));
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "pipelines")]
+    {
+        let storage = entity.get_component::<PipelineStorage>().await?;
+        for command in commands {
+            if let RenderCommand::SetPipeline { pipeline } = command {
+                if !storage.pipelines.contains_key(pipeline) {
+                    return Err(missing("Pipeline", *pipeline));
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "core-3d", feature = "buffers"))]
+    {
+        let storage = entity.get_component::<BufferStorage>().await?;
+        for command in commands {
+            if let RenderCommand::DrawMesh { vertex_buffer, index_buffer, .. } = command {
+                for buffer in std::iter::once(vertex_buffer).chain(index_buffer.as_ref()) {
+                    if !storage.buffers.contains_key(buffer) {
+                        return Err(missing("Buffer", *buffer));
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "targets")]
+    {
+        let storage = entity.get_component::<RenderTargetStorage>().await?;
+        for command in commands {
+            if let RenderCommand::SetRenderTarget { target: Some(target) } = command {
+                if !storage.targets.contains_key(target) {
+                    return Err(missing("Render target", *target));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Recreate every tracked resource on a newly selected backend
+async fn recreate_resources(entity: &Entity, channel: &str) -> CoreResult<()> {
+    #[cfg(feature = "targets")]
+    {
+        let storage = entity.get_component::<RenderTargetStorage>().await?;
+        for info in storage.targets.values() {
+            send(channel, "create_render_target", info).await?;
+        }
+        if storage.current_target.is_some() {
+            send(channel, "set_render_target", &storage.current_target).await?;
+        }
+    }
+
+    #[cfg(feature = "shaders")]
+    {
+        let storage = entity.get_component::<ShaderStorage>().await?;
+        for (id, info) in &storage.shaders {
+            let source = storage.sources.get(id).cloned().unwrap_or_default();
+            send(channel, "compile_shader", &(info, &source)).await?;
+        }
+    }
+
+    #[cfg(feature = "textures")]
+    {
+        let storage = entity.get_component::<TextureStorage>().await?;
+        for info in storage.textures.values() {
+            send(channel, "create_texture", info).await?;
+        }
+    }
+
+    // Atlas pages are the only texture contents the renderer keeps
+    #[cfg(feature = "batching")]
+    {
+        let atlases = entity.get_component::<SpriteAtlasStorage>().await?;
+        for page in &atlases.pages {
+            send(channel, "update_texture", &(page.texture_id, &page.pixels)).await?;
+        }
+    }
+
+    #[cfg(feature = "buffers")]
+    {
+        let storage = entity.get_component::<BufferStorage>().await?;
+        for info in storage.buffers.values() {
+            send(channel, "create_buffer", info).await?;
+        }
+    }
+
+    #[cfg(feature = "pipelines")]
+    {
+        let storage = entity.get_component::<PipelineStorage>().await?;
+        for info in storage.pipelines.values() {
+            send(channel, "create_pipeline", info).await?;
+        }
+        if let Some(current) = storage.current_pipeline {
+            send(channel, "bind_pipeline", &current).await?;
+        }
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,76 @@
+//! Sprite batching and runtime texture atlases (batching feature)
+//!
+//! Small sprite textures are packed into shared atlas pages so sprites that
+//! were drawn from different textures can be drawn from one. The batcher in
+//! the rendering system then sorts sprites by layer, sort order, material and
+//! texture, and merges each run with the same texture and material into one
+//! `DrawSprites` command. This module only holds the data both sides share.
+
+use serde::{Serialize, Deserialize};
+use crate::types::{Int, ResourceId, UInt, Vec2};
+use crate::commands::SpriteQuad;
+
+/// Width and height of atlas pages, clamped to the backend's texture limit
+pub const ATLAS_PAGE_SIZE: UInt = 2048;
+
+/// Texels around each packed image, filled by extruding its edges so
+/// filtering at region borders doesn't bleed neighbouring images in
+pub const ATLAS_PADDING: UInt = 1;
+
+/// Quads per batch, so four vertices per quad stay addressable with u16 indices
+pub const MAX_SPRITES_PER_BATCH: usize = 16384;
+
+/// One row of a shelf-packed page. Shelves stack top to bottom, each as tall
+/// as the first image placed in it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AtlasShelf {
+    pub y: UInt,
+    pub height: UInt,
+    pub used_width: UInt,
+}
+
+/// One atlas texture. Pixels stay on the CPU so the page can be re-uploaded
+/// after new images are packed or the backend changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AtlasPage {
+    pub texture_id: ResourceId,
+    pub width: UInt,
+    pub height: UInt,
+    /// Straight RGBA8, row-major
+    pub pixels: Vec<u8>,
+    pub shelves: Vec<AtlasShelf>,
+}
+
+impl AtlasPage {
+    pub fn new(texture_id: ResourceId, width: UInt, height: UInt) -> Self {
+        Self {
+            texture_id,
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+            shelves: Vec::new(),
+        }
+    }
+}
+
+/// Where a packed texture lives
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct AtlasRegion {
+    /// Texture of the atlas page
+    pub texture_id: ResourceId,
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    /// Size of the original texture in texels
+    pub width: UInt,
+    pub height: UInt,
+}
+
+/// A sprite waiting to be batched
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpriteDraw {
+    pub layer: UInt,
+    pub sort_order: Int,
+    pub material: ResourceId,
+    pub texture_id: Option<ResourceId>,
+    pub quad: SpriteQuad,
+}
@@ -37,6 +37,95 @@ pub enum RenderCommand {
 
     RenderAllEntities,
 
+    // Immediate 2D drawing (emitted by the UI system)
+    #[cfg(feature = "core-2d")]
+    DrawQuad {
+        position: Vec2,
+        size: Vec2,
+        color: ColorRGBA,
+    },
+
+    #[cfg(feature = "core-2d")]
+    DrawText {
+        text: String,
+        position: Vec2,
+        size: Float,
+        color: ColorRGBA,
+    },
+
+    #[cfg(feature = "core-2d")]
+    DrawImage {
+        texture_id: ResourceId,
+        position: Vec2,
+        size: Vec2,
+        uv_min: Vec2,
+        uv_max: Vec2,
+    },
+
+    #[cfg(feature = "core-2d")]
+    DrawLine {
+        start: Vec2,
+        end: Vec2,
+        width: Float,
+        color: ColorRGBA,
+    },
+
+    #[cfg(feature = "core-2d")]
+    DrawCircle {
+        center: Vec2,
+        radius: Float,
+        color: ColorRGBA,
+        filled: bool,
+    },
+
+    // 2D state stacks
+    #[cfg(feature = "core-2d")]
+    SetClipRect {
+        position: Vec2,
+        size: Vec2,
+    },
+
+    #[cfg(feature = "core-2d")]
+    ClearClipRect,
+
+    #[cfg(feature = "core-2d")]
+    SetTransform {
+        matrix: Mat3, // Row-major, translation in the last column
+    },
+
+    #[cfg(feature = "core-2d")]
+    ResetTransform,
+
+    #[cfg(feature = "core-2d")]
+    PushState,
+
+    #[cfg(feature = "core-2d")]
+    PopState,
+
+    // 3D forward rendering (emitted by the rendering system's scene extraction)
+    #[cfg(feature = "core-3d")]
+    SetCamera3D {
+        view: Mat4,       // Row-major, world to view
+        projection: Mat4, // Row-major, view to clip (OpenGL clip space)
+        position: Vec3,   // World-space eye position, for specular
+    },
+
+    #[cfg(feature = "core-3d")]
+    SetLights {
+        ambient: ColorRGB,
+        lights: Vec<SceneLight>,
+    },
+
+    #[cfg(all(feature = "core-3d", feature = "buffers"))]
+    DrawMesh {
+        vertex_buffer: ResourceId, // MESH_VERTEX_STRIDE bytes per vertex
+        index_buffer: Option<ResourceId>, // u32 indices; None draws vertices in order
+        vertex_count: UInt,
+        index_count: UInt,
+        model: Mat4, // Row-major, local to world
+        material: MeshMaterial,
+    },
+
     // State changes
     SetViewport {
         viewport: Viewport,
@@ -101,6 +190,14 @@ pub enum RenderCommand {
     #[cfg(feature = "batching")]
     FlushBatches,
 
+    /// One draw call for many textured quads sharing a texture and material
+    #[cfg(feature = "batching")]
+    DrawSprites {
+        texture_id: Option<ResourceId>, // None draws untextured quads
+        material: ResourceId,           // Pipeline, 0 for the backend's sprite pipeline
+        sprites: Vec<SpriteQuad>,
+    },
+
     // Debug rendering
     #[cfg(feature = "debug")]
     DebugDrawLine {
@@ -160,6 +257,70 @@ pub enum BatchType {
     UI,
 }
 
+/// A sprite within a `DrawSprites` batch
+#[cfg(feature = "batching")]
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct SpriteQuad {
+    pub position: Vec2, // Top-left corner before rotation
+    pub size: Vec2,
+    pub rotation: Float, // Radians, around the quad's center
+    pub uv_min: Vec2,
+    pub uv_max: Vec2,
+    pub color: ColorRGBA, // Multiplies the texture
+}
+
+/// Bytes per mesh vertex: position, normal and UV as little-endian f32s
+#[cfg(all(feature = "core-3d", feature = "buffers"))]
+pub const MESH_VERTEX_STRIDE: usize = 32;
+
+/// A light in world space with intensity folded into its color.
+/// Angles are half-angles of the cone, in radians.
+#[cfg(feature = "core-3d")]
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub enum SceneLight {
+    Directional {
+        direction: Vec3,
+        color: ColorRGB,
+    },
+    Point {
+        position: Vec3,
+        range: Float,
+        attenuation: Vec3, // Constant, linear, quadratic
+        color: ColorRGB,
+    },
+    Spot {
+        position: Vec3,
+        direction: Vec3,
+        range: Float,
+        inner_angle: Float,
+        outer_angle: Float,
+        attenuation: Vec3,
+        color: ColorRGB,
+    },
+}
+
+/// Blinn-Phong surface parameters for `DrawMesh`
+#[cfg(all(feature = "core-3d", feature = "buffers"))]
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
+pub struct MeshMaterial {
+    pub base_color: ColorRGBA,
+    pub emissive: ColorRGB,
+    pub specular: Float,
+    pub shininess: Float,
+}
+
+#[cfg(all(feature = "core-3d", feature = "buffers"))]
+impl Default for MeshMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: [1.0, 1.0, 1.0, 1.0],
+            emissive: [0.0, 0.0, 0.0],
+            specular: 0.5,
+            shininess: 32.0,
+        }
+    }
+}
+
 // Command buffer support
 #[cfg(feature = "commands")]
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -12,6 +12,8 @@ pub struct Sprite {
     pub flip_y: bool,
     pub source_rect: Option<Rect>,
     pub sort_order: Int,
+    /// Pipeline to draw with; None uses the backend's sprite pipeline
+    pub material: Option<ResourceId>,
 }
 
 impl Default for Sprite {
@@ -23,6 +25,7 @@ impl Default for Sprite {
             flip_y: false,
             source_rect: None,
             sort_order: 0,
+            material: None,
         }
     }
 }
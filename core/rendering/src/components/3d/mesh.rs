@@ -10,7 +10,10 @@ use crate::types::*;
 #[cfg(all(feature = "core-3d", feature = "buffers"))]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Mesh {
+    /// Vertex buffer, laid out as `MESH_VERTEX_STRIDE`-byte vertices
     pub gpu_resource_id: ResourceId,
+    /// u32 index buffer; None draws vertices in order
+    pub index_buffer: Option<ResourceId>,
     pub vertex_count: UInt,
     pub index_count: UInt,
     pub bounds: BoundingBox,
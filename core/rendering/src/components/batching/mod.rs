@@ -0,0 +1,7 @@
+//! Batching components - OPTIONAL (batching feature)
+
+#[cfg(feature = "batching")]
+pub mod shared;
+
+#[cfg(feature = "batching")]
+pub use shared::*;
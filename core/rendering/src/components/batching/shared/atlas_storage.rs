@@ -0,0 +1,24 @@
+//! Sprite atlas storage component - OPTIONAL (batching feature)
+
+#[cfg(feature = "batching")]
+use std::collections::HashMap;
+#[cfg(feature = "batching")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "batching")]
+use playground_core_ecs::impl_component_data;
+#[cfg(feature = "batching")]
+use crate::types::ResourceId;
+#[cfg(feature = "batching")]
+use crate::batching::{AtlasPage, AtlasRegion};
+
+/// Runtime atlas pages and where each packed texture ended up
+#[cfg(feature = "batching")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpriteAtlasStorage {
+    pub pages: Vec<AtlasPage>,
+    /// Keyed by the ID of the original texture
+    pub regions: HashMap<ResourceId, AtlasRegion>,
+}
+
+#[cfg(feature = "batching")]
+impl_component_data!(SpriteAtlasStorage);
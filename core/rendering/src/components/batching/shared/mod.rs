@@ -0,0 +1,5 @@
+//! Shared batching components
+
+pub mod atlas_storage;
+
+pub use atlas_storage::*;
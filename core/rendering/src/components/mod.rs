@@ -46,6 +46,9 @@ pub mod commands;
 #[cfg(feature = "passes")]
 pub mod passes;
 
+#[cfg(feature = "batching")]
+pub mod batching;
+
 // Re-export MANDATORY shared components at root level
 pub use shared::*;
 
@@ -83,4 +86,7 @@ pub use pipelines::*;
 pub use commands::*;
 
 #[cfg(feature = "passes")]
-pub use passes::*;
\ No newline at end of file
+pub use passes::*;
+
+#[cfg(feature = "batching")]
+pub use batching::*;
\ No newline at end of file
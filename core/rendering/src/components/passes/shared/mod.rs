@@ -1,5 +1,7 @@
 //! Shared passes components
 
 pub mod storage;
+pub mod transient_pool;
 
 pub use storage::*;
+pub use transient_pool::*;
@@ -0,0 +1,19 @@
+//! Transient render target pool component - OPTIONAL (framegraphs feature)
+
+#[cfg(feature = "framegraphs")]
+use serde::{Serialize, Deserialize};
+#[cfg(feature = "framegraphs")]
+use playground_core_ecs::impl_component_data;
+#[cfg(feature = "framegraphs")]
+use crate::resources::RenderTargetInfo;
+
+/// Render targets created for render graph transients, reused across frames.
+/// Each compiled graph binds its physical targets to entries with the same shape.
+#[cfg(feature = "framegraphs")]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransientTargetPool {
+    pub targets: Vec<RenderTargetInfo>,
+}
+
+#[cfg(feature = "framegraphs")]
+impl_component_data!(TransientTargetPool);
@@ -16,6 +16,8 @@ use crate::resources::ShaderInfo;
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ShaderStorage {
     pub shaders: HashMap<ResourceId, ShaderInfo>,
+    /// Kept so shaders can be recompiled when the backend is switched
+    pub sources: HashMap<ResourceId, String>,
 }
 
 #[cfg(feature = "shaders")]
@@ -11,6 +11,7 @@ pub mod renderer_config;
 pub mod renderer_stats;
 pub mod renderer_capabilities;
 pub mod renderer_backend;
+pub mod resource_allocator;
 
 // Always available component exports
 pub use camera::Camera;
@@ -22,4 +23,5 @@ pub use light::{Light, LightType};
 pub use renderer_config::RendererConfigComponent;
 pub use renderer_stats::RendererStatsComponent;
 pub use renderer_capabilities::RendererCapabilitiesComponent;
-pub use renderer_backend::RendererBackend;
\ No newline at end of file
+pub use renderer_backend::RendererBackend;
+pub use resource_allocator::ResourceAllocator;
@@ -0,0 +1,21 @@
+//! Resource ID allocator component - MANDATORY
+
+use serde::{Serialize, Deserialize};
+use playground_core_ecs::impl_component_data;
+use crate::types::ResourceId;
+
+/// Hands out resource IDs for a renderer entity.
+/// IDs are never reused, so a stale ID can't alias a newer resource.
+/// 0 is reserved for "no resource" / the default target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceAllocator {
+    pub next_id: ResourceId,
+}
+
+impl Default for ResourceAllocator {
+    fn default() -> Self {
+        Self { next_id: 1 }
+    }
+}
+
+impl_component_data!(ResourceAllocator);
@@ -0,0 +1,164 @@
+//! Declarative render graph (framegraphs feature)
+//!
+//! Features declare targets and passes. Each pass names the targets it samples
+//! (`inputs`) and the targets its `RenderPassInfo` attachments write (`outputs`).
+//! The active backend system compiles the graph - ordering, culling and
+//! aliasing transient targets - into a `CompiledRenderGraph`, and
+//! `api::compile_render_graph` binds its physical targets to real render
+//! targets owned by the renderer.
+
+use serde::{Serialize, Deserialize};
+use crate::types::{ColorRGBA, Float, ResourceId};
+use crate::commands::RenderCommand;
+use crate::resources::{RenderPassInfo, RenderTargetInfo};
+
+/// Handle to a target declared in a `RenderGraph`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TargetHandle(pub usize);
+
+/// Where a graph target's storage comes from
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum TargetSource {
+    /// Owned by the graph, only valid while the passes using it run
+    Transient(RenderTargetInfo),
+    /// Owned by the caller; `None` is the default framebuffer.
+    /// Writing an imported target is what keeps a pass alive.
+    Imported(Option<ResourceId>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphTarget {
+    pub name: String,
+    pub source: TargetSource,
+}
+
+/// One pass in the graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphPass {
+    pub name: String,
+    pub info: RenderPassInfo,
+    /// Target for each entry of `info.attachments`, in order
+    pub outputs: Vec<TargetHandle>,
+    /// Targets sampled by the pass
+    pub inputs: Vec<TargetHandle>,
+    /// Commands for each entry of `info.subpasses`; a pass without subpasses
+    /// runs `commands[0]` against its first attachment
+    pub commands: Vec<Vec<RenderCommand>>,
+    pub clear_color: ColorRGBA,
+    pub clear_depth: Float,
+    /// Never culled, even if nothing reads its outputs
+    pub side_effects: bool,
+}
+
+impl GraphPass {
+    pub fn new(name: impl Into<String>, info: RenderPassInfo) -> Self {
+        Self {
+            name: name.into(),
+            info,
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+            commands: Vec::new(),
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            clear_depth: 1.0,
+            side_effects: false,
+        }
+    }
+
+    pub fn read(mut self, target: TargetHandle) -> Self {
+        self.inputs.push(target);
+        self
+    }
+
+    pub fn write(mut self, target: TargetHandle) -> Self {
+        self.outputs.push(target);
+        self
+    }
+
+    pub fn commands(mut self, commands: Vec<RenderCommand>) -> Self {
+        self.commands.push(commands);
+        self
+    }
+
+    pub fn clear(mut self, color: ColorRGBA, depth: Float) -> Self {
+        self.clear_color = color;
+        self.clear_depth = depth;
+        self
+    }
+
+    pub fn with_side_effects(mut self) -> Self {
+        self.side_effects = true;
+        self
+    }
+}
+
+/// A frame's worth of passes and targets
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RenderGraph {
+    pub targets: Vec<GraphTarget>,
+    pub passes: Vec<GraphPass>,
+}
+
+/// Storage a compiled pass writes to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetBinding {
+    /// Index into `CompiledRenderGraph::physical_targets`
+    Physical(usize),
+    Imported(Option<ResourceId>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompiledPass {
+    pub name: String,
+    /// Index of the pass in `RenderGraph::passes`
+    pub index: usize,
+    pub outputs: Vec<TargetBinding>,
+}
+
+/// Result of compiling a `RenderGraph`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CompiledRenderGraph {
+    /// Surviving passes in execution order
+    pub passes: Vec<CompiledPass>,
+    /// Names of culled passes
+    pub culled: Vec<String>,
+    /// Render targets the graph needs allocated; transient targets share these
+    pub physical_targets: Vec<RenderTargetInfo>,
+    /// Binding of each graph target, `None` if no surviving pass uses it
+    pub bindings: Vec<Option<TargetBinding>>,
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a target owned by the graph
+    pub fn create_target(&mut self, name: impl Into<String>, info: RenderTargetInfo) -> TargetHandle {
+        self.push_target(name.into(), TargetSource::Transient(info))
+    }
+
+    /// Declare a target owned by the caller (`None` for the default framebuffer)
+    pub fn import_target(&mut self, name: impl Into<String>, target: Option<ResourceId>) -> TargetHandle {
+        self.push_target(name.into(), TargetSource::Imported(target))
+    }
+
+    pub fn add_pass(&mut self, pass: GraphPass) -> usize {
+        self.passes.push(pass);
+        self.passes.len() - 1
+    }
+
+    fn push_target(&mut self, name: String, source: TargetSource) -> TargetHandle {
+        self.targets.push(GraphTarget { name, source });
+        TargetHandle(self.targets.len() - 1)
+    }
+}
+
+/// Transient targets can alias when every allocation parameter matches
+pub fn same_shape(a: &RenderTargetInfo, b: &RenderTargetInfo) -> bool {
+    a.width == b.width
+        && a.height == b.height
+        && a.format == b.format
+        && a.samples == b.samples
+        && a.has_depth == b.has_depth
+        && a.has_stencil == b.has_stencil
+}
@@ -14,6 +14,14 @@ pub mod resources;
 pub mod commands;
 pub mod api;
 pub mod error;
+mod validation;
+mod locks;
+
+#[cfg(feature = "framegraphs")]
+pub mod graph;
+
+#[cfg(feature = "batching")]
+pub mod batching;
 
 // Re-export type aliases and data structures
 pub use types::{
@@ -37,6 +45,29 @@ pub use commands::RenderCommand;
 #[cfg(feature = "commands")]
 pub use commands::{CommandBufferInfo, CommandBufferState};
 
+#[cfg(feature = "batching")]
+pub use commands::SpriteQuad;
+
+#[cfg(feature = "core-3d")]
+pub use commands::SceneLight;
+
+#[cfg(all(feature = "core-3d", feature = "buffers"))]
+pub use commands::{MeshMaterial, MESH_VERTEX_STRIDE};
+
+// Re-export sprite batching types
+#[cfg(feature = "batching")]
+pub use batching::{
+    SpriteDraw, AtlasPage, AtlasShelf, AtlasRegion,
+    ATLAS_PAGE_SIZE, ATLAS_PADDING, MAX_SPRITES_PER_BATCH,
+};
+
+// Re-export render graph types
+#[cfg(feature = "framegraphs")]
+pub use graph::{
+    RenderGraph, GraphPass, GraphTarget, TargetHandle, TargetSource,
+    CompiledRenderGraph, CompiledPass, TargetBinding,
+};
+
 // Re-export API functions
 pub use api::{
     create_renderer,
@@ -90,4 +121,11 @@ pub use api::{
     create_pipeline,
     destroy_pipeline,
     bind_pipeline,
-};
\ No newline at end of file
+};
+
+#[cfg(feature = "framegraphs")]
+pub use api::compile_render_graph;
+
+#[cfg(feature = "batching")]
+pub use api::lock_sprite_atlas;
+
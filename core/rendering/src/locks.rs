@@ -0,0 +1,50 @@
+//! Per-renderer locks
+//!
+//! Components are copied in and out of the world, so a mutex stored in one
+//! would not be shared between callers. The locks live here instead, keyed
+//! by the renderer's entity, and renderers never wait on each other.
+
+use std::collections::HashMap;
+use std::sync::{Mutex as SyncMutex, PoisonError};
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use playground_core_types::{Handle, handle};
+use playground_core_ecs::{Entity, EntityId};
+
+/// Serializes read-modify-write updates of a renderer's components and the
+/// backend calls that go with them
+pub(crate) static RENDERER_LOCKS: EntityLocks = EntityLocks::new();
+
+/// Held while packing sprite atlases, which creates and uploads textures
+/// as it goes
+#[cfg(feature = "batching")]
+pub(crate) static ATLAS_LOCKS: EntityLocks = EntityLocks::new();
+
+/// One mutex per entity, created on first use
+pub(crate) struct EntityLocks {
+    locks: Lazy<SyncMutex<HashMap<EntityId, Handle<Mutex<()>>>>>,
+}
+
+impl EntityLocks {
+    const fn new() -> Self {
+        Self {
+            locks: Lazy::new(|| SyncMutex::new(HashMap::new())),
+        }
+    }
+
+    pub(crate) async fn lock(&self, entity: &Entity) -> OwnedMutexGuard<()> {
+        let lock = self.locks.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(entity.id())
+            .or_insert_with(|| handle(Mutex::new(())))
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// Forget the lock of an entity whose ID is being reused
+    pub(crate) fn reset(&self, entity: &Entity) {
+        self.locks.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&entity.id());
+    }
+}
@@ -627,14 +627,11 @@ mod placeholder {
     }
 }
 
-// Use directives for pipelines/samplers that need CompareFunc
+// CompareFunc lives with samplers; pipelines need it even without them
 #[cfg(all(feature = "pipelines", not(feature = "samplers")))]
 pub use compare_placeholder::CompareFunc;
 
-#[cfg(all(feature = "samplers", not(feature = "pipelines")))]
-pub use compare_placeholder::CompareFunc;
-
-#[cfg(all(not(feature = "samplers"), not(feature = "pipelines")))]
+#[cfg(all(feature = "pipelines", not(feature = "samplers")))]
 mod compare_placeholder {
     use serde::{Serialize, Deserialize};
 
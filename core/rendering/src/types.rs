@@ -187,7 +187,14 @@ pub struct RendererStats {
     pub triangles_per_frame: UInt,
     pub state_changes: u64,
     pub state_changes_per_frame: UInt,
+    pub batches_per_frame: UInt,
+    pub batched_sprites_per_frame: UInt,
     pub resource_memory: usize,
+    pub texture_count: UInt,
+    pub buffer_count: UInt,
+    pub shader_count: UInt,
+    pub pipeline_count: UInt,
+    pub render_target_count: UInt,
     pub last_frame_time_ms: Float,
     pub average_frame_time_ms: Float,
     pub min_frame_time_ms: Float,
@@ -0,0 +1,303 @@
+//! Validation and size accounting for renderer resources
+//!
+//! Used by the API functions before anything reaches a backend, so every
+//! backend sees the same rules and errors.
+
+#[cfg(feature = "pipelines")]
+use std::collections::HashMap;
+use playground_core_types::{CoreResult, CoreError};
+use crate::types::RendererCapabilities;
+#[cfg(feature = "pipelines")]
+use crate::types::ResourceId;
+use crate::resources::*;
+
+// Textures
+
+/// (block width, block height, bytes per block); uncompressed formats use 1x1 blocks
+#[cfg(feature = "textures")]
+pub(crate) fn texture_block(format: TextureFormat) -> (u32, u32, usize) {
+    use TextureFormat::*;
+    match format {
+        R8 => (1, 1, 1),
+        RG8 => (1, 1, 2),
+        RGB8 => (1, 1, 3),
+        RGBA8 | SRGBA8 => (1, 1, 4),
+        R16F => (1, 1, 2),
+        RG16F => (1, 1, 4),
+        RGB16F => (1, 1, 6),
+        RGBA16F => (1, 1, 8),
+        R32F => (1, 1, 4),
+        RG32F => (1, 1, 8),
+        RGB32F => (1, 1, 12),
+        RGBA32F => (1, 1, 16),
+        Depth24 | Depth32F | Depth24Stencil8 => (1, 1, 4),
+        BC1 | BC4 | ETC2_RGB => (4, 4, 8),
+        BC2 | BC3 | BC5 | BC6H | BC7 | ETC2_RGBA | ASTC_4x4 => (4, 4, 16),
+        ASTC_8x8 => (8, 8, 16),
+    }
+}
+
+#[cfg(feature = "textures")]
+fn is_depth_format(format: TextureFormat) -> bool {
+    matches!(format, TextureFormat::Depth24 | TextureFormat::Depth32F | TextureFormat::Depth24Stencil8)
+}
+
+#[cfg(feature = "textures")]
+fn is_compressed_format(format: TextureFormat) -> bool {
+    texture_block(format).0 > 1
+}
+
+/// Bytes for one mip level of one array layer
+#[cfg(feature = "textures")]
+fn texture_level_bytes(info: &TextureInfo, level: u32) -> usize {
+    let (block_w, block_h, block_bytes) = texture_block(info.format);
+    let width = (info.width >> level).max(1);
+    let height = (info.height >> level).max(1);
+    let depth = (info.depth >> level).max(1);
+    width.div_ceil(block_w) as usize * height.div_ceil(block_h) as usize * depth as usize * block_bytes
+}
+
+/// Size of the data expected by `update_texture`: the base level of every layer
+#[cfg(feature = "textures")]
+pub(crate) fn texture_upload_bytes(info: &TextureInfo) -> usize {
+    texture_level_bytes(info, 0) * info.array_layers as usize
+}
+
+/// Estimated memory held by a texture, including mips, layers and samples
+#[cfg(feature = "textures")]
+pub(crate) fn texture_memory(info: &TextureInfo) -> usize {
+    let per_layer: usize = (0..info.mip_levels).map(|level| texture_level_bytes(info, level)).sum();
+    per_layer * info.array_layers as usize * info.sample_count.max(1) as usize
+}
+
+#[cfg(feature = "textures")]
+pub(crate) fn validate_texture(info: &TextureInfo, caps: &RendererCapabilities) -> CoreResult<()> {
+    if info.width == 0 || info.height == 0 || info.depth == 0 {
+        return Err(invalid(format!("Texture dimensions must be non-zero, got {}x{}x{}", info.width, info.height, info.depth)));
+    }
+    let largest = info.width.max(info.height).max(info.depth);
+    if largest > caps.max_texture_size {
+        return Err(invalid(format!("Texture size {} exceeds the backend limit of {}", largest, caps.max_texture_size)));
+    }
+    if info.array_layers == 0 {
+        return Err(invalid("Texture must have at least one array layer".to_string()));
+    }
+
+    let max_levels = 32 - largest.leading_zeros();
+    if info.mip_levels == 0 || info.mip_levels > max_levels {
+        return Err(invalid(format!("Texture mip levels must be in 1..={}, got {}", max_levels, info.mip_levels)));
+    }
+
+    if info.sample_count == 0 || !info.sample_count.is_power_of_two() {
+        return Err(invalid(format!("Texture sample count must be a power of two, got {}", info.sample_count)));
+    }
+    if info.sample_count > 1 {
+        if !caps.supports_multisample || info.sample_count > caps.max_sample_count {
+            return Err(invalid(format!("Backend does not support {}x multisampled textures", info.sample_count)));
+        }
+        if info.mip_levels > 1 {
+            return Err(invalid("Multisampled textures can't have mip levels".to_string()));
+        }
+    }
+
+    if is_compressed_format(info.format) {
+        let (block_w, block_h, _) = texture_block(info.format);
+        if !info.width.is_multiple_of(block_w) || !info.height.is_multiple_of(block_h) {
+            return Err(invalid(format!(
+                "{:?} textures must be a multiple of {}x{}, got {}x{}",
+                info.format, block_w, block_h, info.width, info.height
+            )));
+        }
+        if info.usage.render_target || info.usage.storage {
+            return Err(invalid(format!("{:?} textures can't be render or storage targets", info.format)));
+        }
+    }
+
+    if is_depth_format(info.format) {
+        if info.usage.storage {
+            return Err(invalid("Depth textures can't be used as storage".to_string()));
+        }
+        if !caps.supports_depth_texture && info.usage.sample {
+            return Err(invalid("Backend can't sample depth textures".to_string()));
+        }
+    }
+
+    if !(info.usage.sample || info.usage.render_target || info.usage.storage || info.usage.copy_src || info.usage.copy_dst) {
+        return Err(invalid("Texture usage is empty".to_string()));
+    }
+
+    Ok(())
+}
+
+// Buffers
+
+#[cfg(feature = "buffers")]
+pub(crate) fn validate_buffer(info: &BufferInfo, caps: &RendererCapabilities) -> CoreResult<()> {
+    if info.size == 0 {
+        return Err(invalid("Buffer size must be non-zero".to_string()));
+    }
+
+    let usage = &info.usage;
+    if !(usage.vertex || usage.index || usage.uniform || usage.storage || usage.indirect || usage.copy_src || usage.copy_dst) {
+        return Err(invalid("Buffer usage is empty".to_string()));
+    }
+    if usage.uniform && info.size > caps.max_uniform_buffer_size as usize {
+        return Err(invalid(format!(
+            "Uniform buffer of {} bytes exceeds the backend limit of {}",
+            info.size, caps.max_uniform_buffer_size
+        )));
+    }
+    if usage.indirect && !caps.supports_indirect {
+        return Err(invalid("Backend does not support indirect buffers".to_string()));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "buffers")]
+pub(crate) fn validate_buffer_update(info: &BufferInfo, data_len: usize) -> CoreResult<()> {
+    if !info.usage.copy_dst {
+        return Err(invalid(format!("Buffer {} was not created with copy_dst usage", info.id)));
+    }
+    if data_len > info.size {
+        return Err(invalid(format!("{} bytes don't fit in buffer {} of {} bytes", data_len, info.id, info.size)));
+    }
+    Ok(())
+}
+
+// Shaders
+
+#[cfg(feature = "shaders")]
+pub(crate) fn validate_shader(source: &str, stage: ShaderStage, caps: &RendererCapabilities) -> CoreResult<()> {
+    if source.trim().is_empty() {
+        return Err(invalid("Shader source is empty".to_string()));
+    }
+
+    let supported = match stage {
+        ShaderStage::Vertex | ShaderStage::Fragment => true,
+        ShaderStage::Compute => caps.supports_compute,
+        ShaderStage::Geometry => caps.supports_geometry_shaders,
+        ShaderStage::TessellationControl | ShaderStage::TessellationEvaluation => caps.supports_tessellation,
+    };
+    if !supported {
+        return Err(invalid(format!("Backend does not support {:?} shaders", stage)));
+    }
+
+    Ok(())
+}
+
+// Pipelines
+
+#[cfg(all(feature = "pipelines", feature = "buffers"))]
+fn vertex_format_size(format: VertexFormat) -> u32 {
+    use VertexFormat::*;
+    match format {
+        Float | Int | UInt | Byte4 | UByte4 | Short2 | UShort2 => 4,
+        Float2 | Int2 | UInt2 | Short4 | UShort4 => 8,
+        Float3 | Int3 | UInt3 => 12,
+        Float4 | Int4 | UInt4 => 16,
+    }
+}
+
+#[cfg(all(feature = "pipelines", not(feature = "buffers")))]
+fn vertex_format_size(format: VertexFormat) -> u32 {
+    match format {
+        VertexFormat::Float2 => 8,
+        VertexFormat::Float3 => 12,
+        VertexFormat::Float4 => 16,
+    }
+}
+
+#[cfg(feature = "pipelines")]
+pub(crate) fn validate_pipeline(
+    info: &PipelineInfo,
+    shaders: &HashMap<ResourceId, ShaderInfo>,
+    caps: &RendererCapabilities,
+) -> CoreResult<()> {
+    let vertex = shaders.get(&info.vertex_shader)
+        .ok_or_else(|| CoreError::NotFound(format!("Vertex shader {} does not exist", info.vertex_shader)))?;
+    if !matches!(vertex.stage, ShaderStage::Vertex) {
+        return Err(invalid(format!("Shader {} is a {:?} shader, not a vertex shader", info.vertex_shader, vertex.stage)));
+    }
+
+    if let Some(fragment_id) = info.fragment_shader {
+        let fragment = shaders.get(&fragment_id)
+            .ok_or_else(|| CoreError::NotFound(format!("Fragment shader {} does not exist", fragment_id)))?;
+        if !matches!(fragment.stage, ShaderStage::Fragment) {
+            return Err(invalid(format!("Shader {} is a {:?} shader, not a fragment shader", fragment_id, fragment.stage)));
+        }
+    }
+
+    let layout = &info.vertex_layout;
+    if layout.attributes.len() > caps.max_vertex_attributes as usize {
+        return Err(invalid(format!(
+            "{} vertex attributes exceed the backend limit of {}",
+            layout.attributes.len(), caps.max_vertex_attributes
+        )));
+    }
+
+    let mut locations = std::collections::HashSet::new();
+    for attribute in &layout.attributes {
+        if !locations.insert(attribute.location) {
+            return Err(invalid(format!("Vertex attribute location {} is used twice", attribute.location)));
+        }
+        if attribute.location >= caps.max_vertex_attributes {
+            return Err(invalid(format!("Vertex attribute location {} is out of range", attribute.location)));
+        }
+        let end = attribute.offset + vertex_format_size(attribute.format);
+        if layout.stride > 0 && end > layout.stride {
+            return Err(invalid(format!(
+                "Vertex attribute '{}' ends at byte {} past the stride of {}",
+                attribute.semantic, end, layout.stride
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+// Render targets
+
+/// Bytes per pixel for the backend-agnostic render target format names
+#[cfg(feature = "targets")]
+fn render_target_format_bytes(format: &str) -> Option<usize> {
+    match format {
+        "R8" => Some(1),
+        "RG8" | "R16F" => Some(2),
+        "RGBA8" | "SRGBA8" | "BGRA8" | "RG16F" | "R32F" | "RGB10A2" => Some(4),
+        "RGBA16F" | "RG32F" => Some(8),
+        "RGBA32F" => Some(16),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "targets")]
+pub(crate) fn render_target_memory(info: &RenderTargetInfo) -> usize {
+    let color = render_target_format_bytes(&info.format).unwrap_or(4);
+    let depth_stencil = if info.has_depth || info.has_stencil { 4 } else { 0 };
+    info.width as usize * info.height as usize * (color + depth_stencil) * info.samples.max(1) as usize
+}
+
+#[cfg(feature = "targets")]
+pub(crate) fn validate_render_target(info: &RenderTargetInfo, caps: &RendererCapabilities, existing: usize) -> CoreResult<()> {
+    if info.width == 0 || info.height == 0 {
+        return Err(invalid(format!("Render target dimensions must be non-zero, got {}x{}", info.width, info.height)));
+    }
+    if info.width.max(info.height) > caps.max_texture_size {
+        return Err(invalid(format!("Render target exceeds the backend limit of {}", caps.max_texture_size)));
+    }
+    if render_target_format_bytes(&info.format).is_none() {
+        return Err(invalid(format!("Unknown render target format '{}'", info.format)));
+    }
+    if info.samples == 0 || !info.samples.is_power_of_two() || info.samples > caps.max_sample_count.max(1) {
+        return Err(invalid(format!("Unsupported render target sample count {}", info.samples)));
+    }
+    if existing >= caps.max_render_targets as usize {
+        return Err(invalid(format!("Backend supports at most {} render targets", caps.max_render_targets)));
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> CoreError {
+    CoreError::InvalidInput(message)
+}
@@ -1,4 +1,5 @@
 use crate::types::ElementId;
+use crate::traits::AnimationId;
 use serde::{Serialize, Deserialize};
 
 /// UI events that can be handled
@@ -159,6 +160,17 @@ pub enum UiEvent {
         element: ElementId,
     },
     
+    /// Animation events
+    AnimationCompleted {
+        element: ElementId,
+        animation: AnimationId,
+    },
+    
+    AnimationCancelled {
+        element: ElementId,
+        animation: AnimationId,
+    },
+    
     /// Accessibility events
     AccessibilityAction {
         element: ElementId,
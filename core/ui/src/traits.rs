@@ -161,7 +161,7 @@ pub struct Animation {
 }
 
 /// Animation ID
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct AnimationId(pub uuid::Uuid);
 
 /// Properties that can be animated
@@ -169,6 +169,7 @@ pub struct AnimationId(pub uuid::Uuid);
 pub enum AnimatedProperty {
     Opacity(f32, f32),           // from, to
     Position(f32, f32, f32, f32), // from_x, from_y, to_x, to_y
+    Size(f32, f32, f32, f32),     // from_width, from_height, to_width, to_height
     Scale(f32, f32),              // from, to
     Rotation(f32, f32),           // from, to (in radians)
     Color([f32; 4], [f32; 4]),   // from, to
@@ -188,7 +189,7 @@ pub enum EasingFunction {
 #[derive(Debug, Clone, Copy)]
 pub enum AnimationRepeat {
     None,
-    Count(u32),
+    Count(u32),    // Total plays
     Infinite,
-    PingPong(u32),
+    PingPong(u32), // There-and-back cycles, ending at the start values
 }
\ No newline at end of file
@@ -1,13 +1,114 @@
+use ropey::{Rope, RopeSlice};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Changes kept for `changes_since`; older versions need a full resync
+const CHANGE_LOG_LIMIT: usize = 1024;
+
+/// Undo steps kept
+const HISTORY_LIMIT: usize = 1000;
 
 /// Text buffer for editing
+///
+/// The text is a rope, so edits anywhere in a large file are cheap. Text is
+/// addressed by char index, or by line and column where columns count chars
+/// from the start of the line. Every edit goes through `replace`, which
+/// bumps `version` and records the edit for undo and for `changes_since`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextBuffer {
     pub path: String,
-    pub content: Vec<String>, // Lines of text
-    pub version: u32,
-    pub modified: bool,
+    #[serde(with = "rope_text")]
+    text: Rope,
+    version: u32,
     pub language: String,
+    #[serde(skip)]
+    history: History,
+    #[serde(skip)]
+    changes: Vec<TextChange>,
+}
+
+/// One edit as seen by something keeping a copy of the buffer in sync,
+/// such as a language server. Start and old end are positions before the
+/// edit; new end is after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChange {
+    /// Version the buffer reached with this change
+    pub version: u32,
+    pub start: Position,
+    pub old_end: Position,
+    pub new_end: Position,
+    pub text: String,
+}
+
+/// A place in the buffer, with the column in each unit consumers count in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    pub char_index: usize,
+    pub byte_index: usize,
+    pub line: usize,
+    /// Chars from the start of the line
+    pub column: usize,
+    /// UTF-16 code units from the start of the line, as LSP counts
+    pub utf16_column: usize,
+    /// Bytes from the start of the line, as tree-sitter counts
+    pub byte_column: usize,
+}
+
+/// An edit in a form that can be reversed
+#[derive(Debug, Clone)]
+struct Edit {
+    start: usize,
+    deleted: String,
+    inserted: String,
+}
+
+#[derive(Debug, Clone)]
+struct Transaction {
+    id: u64,
+    edits: Vec<Edit>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct History {
+    undo: Vec<Transaction>,
+    redo: Vec<Transaction>,
+    /// Edits since `begin_transaction`
+    open: Option<Transaction>,
+    depth: usize,
+    next_id: u64,
+    /// Top of the undo stack when last saved
+    saved: Option<u64>,
+}
+
+impl History {
+    fn new_transaction(&mut self) -> Transaction {
+        self.next_id += 1;
+        Transaction { id: self.next_id, edits: Vec::new() }
+    }
+
+    fn record(&mut self, edit: Edit) {
+        self.redo.clear();
+        match &mut self.open {
+            Some(transaction) => transaction.edits.push(edit),
+            None => {
+                let mut transaction = self.new_transaction();
+                transaction.edits.push(edit);
+                self.push_undo(transaction);
+            }
+        }
+    }
+
+    fn push_undo(&mut self, transaction: Transaction) {
+        if self.undo.len() == HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(transaction);
+    }
+
+    fn top(&self) -> Option<u64> {
+        self.undo.last().map(|transaction| transaction.id)
+    }
 }
 
 impl Default for TextBuffer {
@@ -18,247 +119,363 @@ impl Default for TextBuffer {
 
 impl std::fmt::Display for TextBuffer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.get_text())
+        write!(f, "{}", self.text)
     }
 }
 
 impl TextBuffer {
     pub fn new() -> Self {
-        Self {
-            path: String::new(),
-            content: vec![String::new()],
-            version: 0,
-            modified: false,
-            language: "text".to_string(),
-        }
+        Self::from_string(String::new())
     }
-    
+
     pub fn from_string(content: String) -> Self {
-        let lines: Vec<String> = if content.is_empty() {
-            vec![String::new()]
-        } else {
-            content.lines().map(|s| s.to_string()).collect()
-        };
-        
         Self {
             path: String::new(),
-            content: lines,
+            text: Rope::from_str(&content),
             version: 0,
-            modified: false,
             language: "text".to_string(),
+            history: History::default(),
+            changes: Vec::new(),
         }
     }
+
     pub fn with_path(path: String, content: String) -> Self {
-        let lines: Vec<String> = if content.is_empty() {
-            vec![String::new()]
-        } else {
-            content.lines().map(|s| s.to_string()).collect()
-        };
-        
         let language = Self::detect_language(&path);
-        
         Self {
             path,
-            content: lines,
-            version: 0,
-            modified: false,
             language,
+            ..Self::from_string(content)
         }
     }
-    
+
+    pub fn rope(&self) -> &Rope {
+        &self.text
+    }
+
+    /// Increases with every change, undo and redo included
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Whether the text differs from when it was last saved
+    pub fn is_modified(&self) -> bool {
+        self.history.open.as_ref().is_some_and(|open| !open.edits.is_empty())
+            || self.history.top() != self.history.saved
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.text.len_chars()
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.text.len_bytes()
+    }
+
     /// Get line count
     pub fn line_count(&self) -> usize {
-        self.content.len()
+        self.text.len_lines()
+    }
+
+    pub fn char_to_byte(&self, char_index: usize) -> usize {
+        self.text.char_to_byte(char_index.min(self.len_chars()))
+    }
+
+    pub fn byte_to_char(&self, byte_index: usize) -> usize {
+        self.text.byte_to_char(byte_index.min(self.len_bytes()))
     }
-    
-    /// Get a line by index
-    pub fn get_line(&self, line: usize) -> &str {
-        self.content.get(line).map(|s| s.as_str()).unwrap_or("")
+
+    pub fn char_to_line(&self, char_index: usize) -> usize {
+        self.text.char_to_line(char_index.min(self.len_chars()))
+    }
+
+    /// First char of `line`; the end of the text past the last line
+    pub fn line_to_char(&self, line: usize) -> usize {
+        if line >= self.line_count() {
+            return self.len_chars();
+        }
+        self.text.line_to_char(line)
+    }
+
+    /// Char index of `column` on `line`, clamped to the line's text
+    pub fn position_to_char(&self, line: usize, column: usize) -> usize {
+        if line >= self.line_count() {
+            return self.len_chars();
+        }
+        self.text.line_to_char(line) + column.min(self.line_length(line))
+    }
+
+    /// Char index of a UTF-16 column on `line`, as LSP positions count,
+    /// clamped to the line's text
+    pub fn utf16_position_to_char(&self, line: usize, utf16_column: usize) -> usize {
+        if line >= self.line_count() {
+            return self.len_chars();
+        }
+        let line_start = self.text.line_to_char(line);
+        let line_end = self.text.char_to_utf16_cu(line_start + self.line_length(line));
+        let target = (self.text.char_to_utf16_cu(line_start) + utf16_column).min(line_end);
+        self.text.utf16_cu_to_char(target)
+    }
+
+    /// `(line, column)` of a char index
+    pub fn char_to_position(&self, char_index: usize) -> (usize, usize) {
+        let char_index = char_index.min(self.len_chars());
+        let line = self.text.char_to_line(char_index);
+        (line, char_index - self.text.line_to_char(line))
+    }
+
+    /// Full position of a char index
+    pub fn position(&self, char_index: usize) -> Position {
+        let char_index = char_index.min(self.len_chars());
+        let line = self.text.char_to_line(char_index);
+        let line_char = self.text.line_to_char(line);
+        let byte_index = self.text.char_to_byte(char_index);
+        Position {
+            char_index,
+            byte_index,
+            line,
+            column: char_index - line_char,
+            utf16_column: self.text.char_to_utf16_cu(char_index) - self.text.char_to_utf16_cu(line_char),
+            byte_column: byte_index - self.text.line_to_byte(line),
+        }
+    }
+
+    /// Get a line by index, without its line break
+    pub fn get_line(&self, line: usize) -> Cow<'_, str> {
+        match self.line_slice(line) {
+            Some(slice) => slice.into(),
+            None => Cow::Borrowed(""),
+        }
     }
-    
-    /// Get line length
+
+    /// Get line length in chars, without the line break
     pub fn line_length(&self, line: usize) -> usize {
-        self.content.get(line).map(|s| s.len()).unwrap_or(0)
+        self.line_slice(line).map_or(0, |slice| slice.len_chars())
+    }
+
+    fn line_slice(&self, line: usize) -> Option<RopeSlice<'_>> {
+        let slice = self.text.get_line(line)?;
+        let mut len = slice.len_chars();
+        while len > 0 && matches!(slice.char(len - 1), '\n' | '\r') {
+            len -= 1;
+        }
+        Some(slice.slice(..len))
+    }
+
+    /// Text in a char range
+    pub fn slice(&self, range: Range<usize>) -> String {
+        let range = self.clamp(range);
+        self.text.slice(range).to_string()
+    }
+
+    pub fn char_at(&self, char_index: usize) -> Option<char> {
+        self.text.get_char(char_index)
     }
-    
+
+    /// Replace a char range with `text`. Every edit comes through here.
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        let range = self.clamp(range);
+        if range.is_empty() && text.is_empty() {
+            return;
+        }
+        let edit = self.apply(range.start, range.end, text);
+        self.history.record(edit);
+    }
+
+    pub fn insert_at(&mut self, char_index: usize, text: &str) {
+        self.replace(char_index..char_index, text);
+    }
+
+    pub fn remove(&mut self, range: Range<usize>) {
+        self.replace(range, "");
+    }
+
     /// Delete a line
     pub fn delete_line(&mut self, line: usize) {
-        if line < self.content.len() {
-            self.content.remove(line);
-            if self.content.is_empty() {
-                self.content.push(String::new());
-            }
-            self.version += 1;
-            self.modified = true;
+        if line >= self.line_count() {
+            return;
         }
+        let start = self.text.line_to_char(line);
+        let end = self.line_to_char(line + 1);
+        // The last line has no break of its own; take the one before it
+        let start = if line + 1 == self.line_count() && line > 0 {
+            self.position_to_char(line - 1, usize::MAX)
+        } else {
+            start
+        };
+        self.remove(start..end);
     }
-    
+
     /// Insert a line
     pub fn insert_line(&mut self, line: usize, content: String) {
-        if line <= self.content.len() {
-            self.content.insert(line, content);
-            self.version += 1;
-            self.modified = true;
+        if line < self.line_count() {
+            let at = self.text.line_to_char(line);
+            self.insert_at(at, &format!("{}\n", content));
+        } else if line == self.line_count() {
+            let at = self.len_chars();
+            self.insert_at(at, &format!("\n{}", content));
         }
     }
-    
+
     /// Insert a character at position
     pub fn insert_char(&mut self, line: usize, column: usize, ch: char) {
-        if line < self.content.len() {
-            let line_content = &mut self.content[line];
-            if column <= line_content.len() {
-                line_content.insert(column, ch);
-                self.version += 1;
-                self.modified = true;
-            }
+        if line < self.line_count() && column <= self.line_length(line) {
+            let at = self.position_to_char(line, column);
+            self.insert_at(at, ch.encode_utf8(&mut [0; 4]));
         }
     }
-    
+
     /// Delete a character at position
     pub fn delete_char(&mut self, line: usize, column: usize) {
-        if line < self.content.len() {
-            let line_content = &mut self.content[line];
-            if column < line_content.len() {
-                line_content.remove(column);
-                self.version += 1;
-                self.modified = true;
-            }
+        if line < self.line_count() && column < self.line_length(line) {
+            let at = self.position_to_char(line, column);
+            self.remove(at..at + 1);
         }
     }
-    
+
     /// Split a line at position
     pub fn split_line(&mut self, line: usize, column: usize) {
-        if line < self.content.len() {
-            let current_line = self.content[line].clone();
-            let (before, after) = current_line.split_at(column.min(current_line.len()));
-            self.content[line] = before.to_string();
-            self.content.insert(line + 1, after.to_string());
-            self.version += 1;
-            self.modified = true;
+        if line < self.line_count() {
+            let at = self.position_to_char(line, column);
+            self.insert_at(at, "\n");
         }
     }
-    
+
     /// Insert text at position
     pub fn insert(&mut self, line: usize, column: usize, text: &str) {
-        if line >= self.content.len() {
-            return;
-        }
-        
-        let current_line = &mut self.content[line];
-        
-        if text.contains('\n') {
-            // Multi-line insert
-            let lines: Vec<&str> = text.split('\n').collect();
-            let (before, after) = current_line.split_at(column.min(current_line.len()));
-            
-            // First line gets the before part + first new line
-            let first_line = format!("{}{}", before, lines[0]);
-            
-            // Last line gets last new line + after part
-            let last_line = format!("{}{}", lines[lines.len() - 1], after);
-            
-            // Build new content
-            let mut new_content = Vec::new();
-            
-            // Add lines before insertion
-            for i in 0..line {
-                new_content.push(self.content[i].clone());
-            }
-            
-            // Add first line
-            new_content.push(first_line);
-            
-            // Add middle lines
-            for i in 1..lines.len() - 1 {
-                new_content.push(lines[i].to_string());
-            }
-            
-            // Add last line if different from first
-            if lines.len() > 1 {
-                new_content.push(last_line);
-            }
-            
-            // Add lines after insertion
-            for i in line + 1..self.content.len() {
-                new_content.push(self.content[i].clone());
-            }
-            
-            self.content = new_content;
-        } else {
-            // Single line insert
-            current_line.insert_str(column.min(current_line.len()), text);
+        if line < self.line_count() {
+            let at = self.position_to_char(line, column);
+            self.insert_at(at, text);
         }
-        
-        self.version += 1;
-        self.modified = true;
     }
-    
+
     /// Delete text in range
     pub fn delete(&mut self, start_line: usize, start_col: usize, end_line: usize, end_col: usize) {
-        if start_line >= self.content.len() || end_line >= self.content.len() {
+        if start_line >= self.line_count() || end_line >= self.line_count() {
             return;
         }
-        
-        if start_line == end_line {
-            // Single line delete
-            let line = &mut self.content[start_line];
-            let start = start_col.min(line.len());
-            let end = end_col.min(line.len());
-            line.replace_range(start..end, "");
-        } else {
-            // Multi-line delete
-            let start_text = self.content[start_line][..start_col.min(self.content[start_line].len())].to_string();
-            let end_text = self.content[end_line][end_col.min(self.content[end_line].len())..].to_string();
-            
-            let merged_line = format!("{}{}", start_text, end_text);
-            
-            // Build new content
-            let mut new_content = Vec::new();
-            
-            // Add lines before deletion
-            for i in 0..start_line {
-                new_content.push(self.content[i].clone());
-            }
-            
-            // Add merged line
-            new_content.push(merged_line);
-            
-            // Add lines after deletion
-            for i in end_line + 1..self.content.len() {
-                new_content.push(self.content[i].clone());
-            }
-            
-            self.content = new_content;
-        }
-        
-        self.version += 1;
-        self.modified = true;
+        let start = self.position_to_char(start_line, start_col);
+        let end = self.position_to_char(end_line, end_col);
+        self.remove(start..end.max(start));
     }
-    
+
     /// Get full text content
     pub fn get_text(&self) -> String {
-        self.content.join("\n")
+        self.text.to_string()
     }
-    
-    /// Replace entire content
+
+    /// Replace entire content, as one undoable edit
     pub fn set_text(&mut self, text: String) {
-        self.content = if text.is_empty() {
-            vec![String::new()]
-        } else {
-            text.lines().map(|s| s.to_string()).collect()
-        };
-        
-        self.version += 1;
-        self.modified = true;
+        let end = self.len_chars();
+        self.replace(0..end, &text);
     }
-    
+
     /// Mark as saved
     pub fn mark_saved(&mut self) {
-        self.modified = false;
+        self.commit_all();
+        self.history.saved = self.history.top();
+    }
+
+    /// Group the edits until the matching `commit_transaction` into one
+    /// undo step. Transactions nest; only the outermost one counts.
+    pub fn begin_transaction(&mut self) {
+        if self.history.depth == 0 {
+            self.history.open = Some(self.history.new_transaction());
+        }
+        self.history.depth += 1;
+    }
+
+    pub fn commit_transaction(&mut self) {
+        match self.history.depth {
+            0 => {}
+            1 => self.commit_all(),
+            _ => self.history.depth -= 1,
+        }
+    }
+
+    fn commit_all(&mut self) {
+        self.history.depth = 0;
+        if let Some(transaction) = self.history.open.take() && !transaction.edits.is_empty() {
+            self.history.push_undo(transaction);
+        }
     }
-    
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.undo.is_empty() || self.history.open.as_ref().is_some_and(|open| !open.edits.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.redo.is_empty()
+    }
+
+    /// Revert the last undo step. Returns where it started, for the cursor.
+    pub fn undo(&mut self) -> Option<usize> {
+        self.commit_all();
+        let transaction = self.history.undo.pop()?;
+        for edit in transaction.edits.iter().rev() {
+            let end = edit.start + edit.inserted.chars().count();
+            self.apply(edit.start, end, &edit.deleted);
+        }
+        let cursor = transaction.edits.first().map(|edit| edit.start);
+        self.history.redo.push(transaction);
+        cursor
+    }
+
+    /// Reapply the last undone step. Returns where it started, for the
+    /// cursor.
+    pub fn redo(&mut self) -> Option<usize> {
+        self.commit_all();
+        let transaction = self.history.redo.pop()?;
+        for edit in &transaction.edits {
+            let end = edit.start + edit.deleted.chars().count();
+            self.apply(edit.start, end, &edit.inserted);
+        }
+        let cursor = transaction.edits.first().map(|edit| edit.start);
+        self.history.push_undo(transaction);
+        cursor
+    }
+
+    /// Changes after `version`, oldest first, or None when they are no
+    /// longer kept and the whole text must be resent
+    pub fn changes_since(&self, version: u32) -> Option<&[TextChange]> {
+        if version == self.version {
+            return Some(&[]);
+        }
+        let first = self.changes.iter().position(|change| change.version == version + 1)?;
+        Some(&self.changes[first..])
+    }
+
+    fn apply(&mut self, start: usize, end: usize, text: &str) -> Edit {
+        let start_position = self.position(start);
+        let old_end = self.position(end);
+        let deleted = self.text.slice(start..end).to_string();
+
+        self.text.remove(start..end);
+        self.text.insert(start, text);
+        self.version += 1;
+
+        let new_end = self.position(start + text.chars().count());
+        if self.changes.len() == CHANGE_LOG_LIMIT {
+            self.changes.remove(0);
+        }
+        self.changes.push(TextChange {
+            version: self.version,
+            start: start_position,
+            old_end,
+            new_end,
+            text: text.to_string(),
+        });
+
+        Edit { start, deleted, inserted: text.to_string() }
+    }
+
+    fn clamp(&self, range: Range<usize>) -> Range<usize> {
+        let len = self.len_chars();
+        let end = range.end.min(len);
+        range.start.min(end)..end
+    }
+
     fn detect_language(path: &str) -> String {
-        match path.split('.').last() {
+        match path.split('.').next_back() {
             Some("rs") => "rust",
             Some("js") => "javascript",
             Some("ts") => "typescript",
@@ -274,4 +491,167 @@ impl TextBuffer {
             _ => "text",
         }.to_string()
     }
-}
\ No newline at end of file
+}
+
+/// Ropes serialize as their text
+mod rope_text {
+    use ropey::Rope;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(rope: &Rope, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(rope)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rope, D::Error> {
+        String::deserialize(deserializer).map(|text| Rope::from_str(&text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_indexes_multibyte_text() {
+        let buffer = TextBuffer::from_string("héllo\n😀 wörld\n".to_string());
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!(buffer.len_chars(), 14);
+        assert_eq!(buffer.len_bytes(), 19);
+        assert_eq!(buffer.get_line(1), "😀 wörld");
+        assert_eq!(buffer.line_length(1), 7);
+
+        let start = buffer.line_to_char(1);
+        assert_eq!(start, 6);
+        assert_eq!(buffer.char_to_byte(start + 1), 11);
+        assert_eq!(buffer.byte_to_char(11), start + 1);
+        assert_eq!(buffer.char_to_position(start + 3), (1, 3));
+        assert_eq!(buffer.position_to_char(1, 100), start + 7);
+        // The emoji is two UTF-16 code units
+        assert_eq!(buffer.utf16_position_to_char(1, 3), start + 2);
+
+        let position = buffer.position(start + 3);
+        assert_eq!(position.utf16_column, 4);
+        assert_eq!(position.byte_column, 6);
+    }
+
+    #[test]
+    fn buffer_line_edits() {
+        let mut buffer = TextBuffer::from_string("one\ntwo\nthree".to_string());
+        buffer.delete_line(2);
+        assert_eq!(buffer.to_string(), "one\ntwo");
+        buffer.insert_line(0, "zero".to_string());
+        buffer.split_line(1, 1);
+        buffer.insert_char(3, 3, '!');
+        assert_eq!(buffer.to_string(), "zero\no\nne\ntwo!");
+        buffer.delete(1, 1, 2, 1);
+        assert_eq!(buffer.to_string(), "zero\noe\ntwo!");
+    }
+
+    #[test]
+    fn buffer_undo_and_redo_single_edits() {
+        let mut buffer = TextBuffer::from_string("abc".to_string());
+        buffer.insert_at(3, "d");
+        buffer.remove(0..1);
+        assert_eq!(buffer.to_string(), "bcd");
+
+        assert_eq!(buffer.undo(), Some(0));
+        assert_eq!(buffer.to_string(), "abcd");
+        assert_eq!(buffer.undo(), Some(3));
+        assert_eq!(buffer.to_string(), "abc");
+        assert!(!buffer.can_undo());
+        assert_eq!(buffer.undo(), None);
+
+        assert_eq!(buffer.redo(), Some(3));
+        assert_eq!(buffer.to_string(), "abcd");
+        // A new edit drops what could be redone
+        buffer.insert_at(0, "x");
+        assert!(!buffer.can_redo());
+        assert_eq!(buffer.to_string(), "xabcd");
+    }
+
+    #[test]
+    fn buffer_transactions_undo_as_one_step() {
+        let mut buffer = TextBuffer::from_string("hello".to_string());
+        buffer.begin_transaction();
+        buffer.insert_at(5, " world");
+        buffer.begin_transaction();
+        buffer.remove(0..1);
+        buffer.insert_at(0, "J");
+        buffer.commit_transaction();
+        // Still open until the outermost commit
+        assert!(buffer.can_undo());
+        buffer.insert_at(11, "!");
+        buffer.commit_transaction();
+        assert_eq!(buffer.to_string(), "Jello world!");
+
+        assert_eq!(buffer.undo(), Some(5));
+        assert_eq!(buffer.to_string(), "hello");
+        assert!(!buffer.can_undo());
+        buffer.redo();
+        assert_eq!(buffer.to_string(), "Jello world!");
+    }
+
+    #[test]
+    fn buffer_empty_transaction_is_not_an_undo_step() {
+        let mut buffer = TextBuffer::from_string("a".to_string());
+        buffer.begin_transaction();
+        buffer.commit_transaction();
+        assert!(!buffer.can_undo());
+    }
+
+    #[test]
+    fn buffer_tracks_modification_against_save() {
+        let mut buffer = TextBuffer::from_string("a".to_string());
+        assert!(!buffer.is_modified());
+        buffer.insert_at(1, "b");
+        assert!(buffer.is_modified());
+        buffer.mark_saved();
+        assert!(!buffer.is_modified());
+        buffer.undo();
+        assert!(buffer.is_modified());
+        buffer.redo();
+        assert!(!buffer.is_modified());
+    }
+
+    #[test]
+    fn buffer_version_counts_every_change() {
+        let mut buffer = TextBuffer::from_string("a".to_string());
+        assert_eq!(buffer.version(), 0);
+        buffer.insert_at(1, "b");
+        buffer.replace(0..0, "");
+        assert_eq!(buffer.version(), 1);
+        buffer.undo();
+        buffer.redo();
+        assert_eq!(buffer.version(), 3);
+    }
+
+    #[test]
+    fn buffer_changes_since_reports_positions() {
+        let mut buffer = TextBuffer::from_string("fn a() {}\n".to_string());
+        let start = buffer.version();
+        buffer.replace(3..4, "ä");
+        buffer.insert_at(9, "\n    x\n");
+
+        let changes = buffer.changes_since(start).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].version, start + 1);
+        assert_eq!(changes[0].start.column, 3);
+        assert_eq!(changes[0].old_end.byte_index, 4);
+        assert_eq!(changes[0].new_end.byte_index, 5);
+        assert_eq!(changes[1].text, "\n    x\n");
+        assert_eq!((changes[1].new_end.line, changes[1].new_end.column), (2, 0));
+
+        assert_eq!(buffer.changes_since(start + 1).unwrap().len(), 1);
+        assert!(buffer.changes_since(buffer.version()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn buffer_changes_since_forgets_old_versions() {
+        let mut buffer = TextBuffer::new();
+        for _ in 0..CHANGE_LOG_LIMIT + 1 {
+            buffer.insert_at(0, "x");
+        }
+        assert!(buffer.changes_since(0).is_none());
+        assert!(buffer.changes_since(1).is_some());
+    }
+}
@@ -0,0 +1,1483 @@
+use std::ops::Range;
+
+use crate::buffer::TextBuffer;
+use crate::ex::{self, Address, ExCommand, LineRange};
+use crate::motion;
+use crate::search::{SearchOptions, SearchQuery};
+use crate::vim::{Direction, Motion, TextObject, TextObjectKind, VimCommand, VimMode, VimState};
+
+/// Something the host has to do after a command. The editor does no IO of
+/// its own, so saving and closing are left to whoever owns the buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorAction {
+    /// Save the buffer, to `path` when given
+    Write { path: Option<String> },
+    /// Close the buffer; `force` discards unsaved changes
+    Quit { force: bool },
+    /// Status line text
+    Message(String),
+    Error(String),
+}
+
+/// A change `.` can repeat
+#[derive(Debug, Clone)]
+struct RepeatableChange {
+    command: VimCommand,
+    register: Option<char>,
+    /// Keys typed in insert or replace mode after the command
+    typed: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct VisualSelection {
+    /// The end that stays put while the cursor moves
+    anchor: usize,
+    linewise: bool,
+}
+
+/// Everything that differs between cursors
+#[derive(Debug, Clone, Copy)]
+struct CursorState {
+    cursor: usize,
+    selection: Option<VisualSelection>,
+    preferred_column: Option<usize>,
+}
+
+impl CursorState {
+    /// Follow an edit that replaced `start..old_end` with text ending at
+    /// `new_end`
+    fn map_through(&mut self, start: usize, old_end: usize, new_end: usize) {
+        let map = |index: usize| {
+            if index >= old_end {
+                index - old_end + new_end
+            } else if index > start {
+                index.min(new_end)
+            } else {
+                index
+            }
+        };
+        self.cursor = map(self.cursor);
+        if let Some(selection) = &mut self.selection {
+            selection.anchor = map(selection.anchor);
+        }
+    }
+}
+
+/// The last search, for `n`, `N` and match highlighting
+#[derive(Debug, Clone)]
+struct SearchState {
+    query: SearchQuery,
+    forward: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Delete,
+    Yank,
+    Change,
+}
+
+/// A buffer with cursors, driven by Vim keys
+///
+/// `process_key` feeds keys through `VimState` and executes the commands it
+/// parses; `execute` runs a command directly. Registers and marks live in
+/// the `VimState`. Each change is one undo step, including everything typed
+/// in the insert that follows it.
+///
+/// Besides the primary cursor there can be any number of secondary ones.
+/// Commands run at each cursor in turn, each with its own visual selection,
+/// and the other cursors follow the edits. Undo, redo, searches and `:`
+/// commands run once, at the primary cursor.
+pub struct Editor {
+    buffer: TextBuffer,
+    vim: VimState,
+    /// Char index
+    cursor: usize,
+    selection: Option<VisualSelection>,
+    /// Column `j` and `k` return to after passing shorter lines
+    preferred_column: Option<usize>,
+    secondary: Vec<CursorState>,
+    /// A secondary cursor is running the command; typing is recorded for
+    /// `.` only once
+    secondary_turn: bool,
+    last_change: Option<RepeatableChange>,
+    /// Change whose insert is being typed
+    recording: Option<RepeatableChange>,
+    repeating: bool,
+    search: Option<SearchState>,
+    /// Cursor and search from before the `/` being typed, restored if it
+    /// is cancelled
+    search_origin: Option<(usize, Option<SearchState>)>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new(TextBuffer::new())
+    }
+}
+
+impl Editor {
+    pub fn new(buffer: TextBuffer) -> Self {
+        Self {
+            buffer,
+            vim: VimState::new(),
+            cursor: 0,
+            selection: None,
+            preferred_column: None,
+            secondary: Vec::new(),
+            secondary_turn: false,
+            last_change: None,
+            recording: None,
+            repeating: false,
+            search: None,
+            search_origin: None,
+        }
+    }
+
+    pub fn buffer(&self) -> &TextBuffer {
+        &self.buffer
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut TextBuffer {
+        &mut self.buffer
+    }
+
+    pub fn vim(&self) -> &VimState {
+        &self.vim
+    }
+
+    pub fn mode(&self) -> VimMode {
+        self.vim.mode()
+    }
+
+    /// Char index of the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// `(line, column)` of the cursor
+    pub fn cursor_position(&self) -> (usize, usize) {
+        self.buffer.char_to_position(self.cursor)
+    }
+
+    pub fn set_cursor_position(&mut self, line: usize, column: usize) {
+        self.cursor = self.buffer.position_to_char(line, column);
+        self.preferred_column = None;
+        self.clamp_cursor();
+    }
+
+    /// Selected char range in visual modes, whole lines in visual line mode
+    pub fn selection(&self) -> Option<Range<usize>> {
+        self.selection_range().map(|(range, _)| range)
+    }
+
+    /// What has been typed after `:`, while in command mode
+    pub fn command_line(&self) -> Option<&str> {
+        (self.vim.mode() == VimMode::Command).then_some(self.vim.command_buffer.as_str())
+    }
+
+    /// Handle one key. Escape is `'\x1b'`, backspace `'\x08'`, enter `'\n'`.
+    pub fn process_key(&mut self, key: char) -> Vec<EditorAction> {
+        let command = self.vim.process_key(key);
+        // Searches move the cursor as they are typed
+        if command == VimCommand::None && self.vim.mode() == VimMode::Command {
+            let line = self.vim.command_buffer.clone();
+            if let Some(pattern) = line.strip_prefix('/') {
+                self.preview_search(pattern, true);
+            } else if let Some(pattern) = line.strip_prefix('?') {
+                self.preview_search(pattern, false);
+            }
+        }
+        self.execute(command)
+    }
+
+    /// Run a command against the buffer
+    pub fn execute(&mut self, command: VimCommand) -> Vec<EditorAction> {
+        // Keys that only start a command leave the register for it
+        if command == VimCommand::None {
+            return Vec::new();
+        }
+        let register = self.vim.take_register();
+        let mut actions = Vec::new();
+
+        if command == VimCommand::Normal && self.vim.mode() == VimMode::Normal && !self.secondary.is_empty() {
+            self.clear_cursors();
+        } else if self.secondary.is_empty() || Self::runs_once(&command) {
+            let version = self.buffer.version();
+            self.apply(command, register, &mut actions);
+            self.settle_cursor();
+            self.map_secondary(version);
+        } else {
+            self.for_each_cursor(|editor| {
+                editor.apply(command.clone(), register, &mut actions);
+                editor.settle_cursor();
+            });
+        }
+        actions
+    }
+
+    /// Commands that act on the buffer or editor as a whole
+    fn runs_once(command: &VimCommand) -> bool {
+        matches!(
+            command,
+            VimCommand::Undo | VimCommand::Redo | VimCommand::ExecuteCommand(_)
+                | VimCommand::EnterCommandMode | VimCommand::ExitCommandMode
+                | VimCommand::SearchNext(_) | VimCommand::SearchPrevious(_)
+        )
+    }
+
+    fn settle_cursor(&mut self) {
+        if self.selection.is_some() && !matches!(self.vim.mode(), VimMode::Visual | VimMode::VisualLine) {
+            self.leave_visual();
+        }
+        self.clamp_cursor();
+    }
+
+    fn primary_state(&self) -> CursorState {
+        CursorState { cursor: self.cursor, selection: self.selection, preferred_column: self.preferred_column }
+    }
+
+    fn load_state(&mut self, state: CursorState) {
+        self.cursor = state.cursor;
+        self.selection = state.selection;
+        self.preferred_column = state.preferred_column;
+    }
+
+    /// Run `f` at every cursor, primary first, moving the others along with
+    /// each edit
+    fn for_each_cursor(&mut self, mut f: impl FnMut(&mut Self)) {
+        let mut states = vec![self.primary_state()];
+        states.append(&mut self.secondary);
+        for index in 0..states.len() {
+            self.load_state(states[index]);
+            self.secondary_turn = index > 0;
+            let version = self.buffer.version();
+            f(self);
+            states[index] = self.primary_state();
+            for (other, state) in states.iter_mut().enumerate() {
+                if other != index {
+                    self.follow_changes(state, version);
+                }
+            }
+        }
+        self.secondary_turn = false;
+        self.load_state(states[0]);
+        self.secondary = states.split_off(1);
+        self.merge_cursors();
+    }
+
+    fn follow_changes(&self, state: &mut CursorState, version: u32) {
+        if let Some(changes) = self.buffer.changes_since(version) {
+            for change in changes {
+                state.map_through(change.start.char_index, change.old_end.char_index, change.new_end.char_index);
+            }
+        }
+    }
+
+    fn map_secondary(&mut self, version: u32) {
+        let mut secondary = std::mem::take(&mut self.secondary);
+        for state in &mut secondary {
+            self.follow_changes(state, version);
+            state.cursor = state.cursor.min(self.buffer.len_chars());
+        }
+        self.secondary = secondary;
+        self.merge_cursors();
+    }
+
+    /// Cursors that ended up in the same place become one
+    fn merge_cursors(&mut self) {
+        let primary = self.cursor;
+        let mut seen = vec![primary];
+        self.secondary.retain(|state| {
+            if seen.contains(&state.cursor) {
+                false
+            } else {
+                seen.push(state.cursor);
+                true
+            }
+        });
+    }
+
+    /// Add a cursor at `(line, column)`
+    pub fn add_cursor(&mut self, line: usize, column: usize) {
+        let cursor = self.buffer.position_to_char(line, column);
+        self.secondary.push(CursorState { cursor, selection: None, preferred_column: None });
+        self.merge_cursors();
+    }
+
+    /// Add a cursor on the line above the topmost cursor, or below the
+    /// bottommost one, in the same column
+    pub fn add_cursor_vertical(&mut self, below: bool) {
+        let lines = self.cursors().into_iter().map(|(cursor, _)| self.buffer.char_to_position(cursor));
+        let edge = if below { lines.max() } else { lines.min() };
+        let Some((line, _)) = edge else {
+            return;
+        };
+        let target = if below { line + 1 } else { line.wrapping_sub(1) };
+        if target > self.last_line() {
+            return;
+        }
+        let column = self.preferred_column.unwrap_or_else(|| self.cursor_position().1);
+        let cursor = self.buffer.position_to_char(target, column);
+        self.secondary.push(CursorState { cursor, selection: None, preferred_column: Some(column) });
+        self.merge_cursors();
+    }
+
+    /// Put a cursor on every line from `anchor` to `head`, each selecting
+    /// the columns between them. Lines too short to reach the columns are
+    /// left out. The cursor on `head`'s line is the primary one.
+    pub fn column_select(&mut self, anchor: (usize, usize), head: (usize, usize)) {
+        let last_line = self.last_line();
+        let (anchor_line, head_line) = (anchor.0.min(last_line), head.0.min(last_line));
+        let left = anchor.1.min(head.1);
+        let block = anchor.1 != head.1;
+
+        let mut states = Vec::new();
+        for line in anchor_line.min(head_line)..=anchor_line.max(head_line) {
+            if left > 0 && self.buffer.line_length(line) < left + 1 && line != head_line {
+                continue;
+            }
+            // Columns past the end of a line stop at its last character
+            let last_column = self.buffer.line_length(line).saturating_sub(1);
+            let cursor = self.buffer.position_to_char(line, head.1.min(last_column));
+            let selection = block.then(|| VisualSelection {
+                anchor: self.buffer.position_to_char(line, anchor.1.min(last_column)),
+                linewise: false,
+            });
+            let state = CursorState { cursor, selection, preferred_column: Some(head.1) };
+            if line == head_line {
+                states.insert(0, state);
+            } else {
+                states.push(state);
+            }
+        }
+
+        self.leave_visual();
+        self.load_state(states[0]);
+        self.secondary = states.split_off(1);
+        self.vim.set_mode(if block { VimMode::Visual } else { VimMode::Normal });
+        self.merge_cursors();
+    }
+
+    /// Add a cursor selecting the next occurrence of the primary selection,
+    /// or with nothing selected, select the word under the cursor. Returns
+    /// false when there is nothing more to add.
+    pub fn add_cursor_at_next_match(&mut self) -> bool {
+        let (needle, whole_word) = match self.selection_range() {
+            Some((range, false)) => (self.buffer.slice(range), false),
+            Some((_, true)) => return false,
+            None => {
+                let word = TextObject { kind: TextObjectKind::Word, inner: true };
+                let Some((range, _)) = motion::text_object(&self.buffer, self.cursor, word) else {
+                    return false;
+                };
+                if self.buffer.slice(range.clone()).trim().is_empty() {
+                    return false;
+                }
+                // The first press selects the word itself
+                self.selection = Some(VisualSelection { anchor: range.start, linewise: false });
+                self.cursor = range.end - 1;
+                self.vim.set_mode(VimMode::Visual);
+                return true;
+            }
+        };
+
+        let options = SearchOptions { regex: false, case_sensitive: true, whole_word };
+        let Ok(query) = SearchQuery::new(&needle, options) else {
+            return false;
+        };
+        let taken: Vec<usize> = self.cursors().into_iter()
+            .filter_map(|(_, selection)| selection.map(|range| range.start))
+            .collect();
+        // Continue after the most recently added cursor
+        let from = self.cursors().pop()
+            .map_or(self.cursor, |(cursor, selection)| selection.map_or(cursor, |range| range.end));
+
+        let matches = query.find_all(&self.buffer);
+        let next = matches.iter()
+            .filter(|found| found.start >= from)
+            .chain(matches.iter())
+            .find(|found| !taken.contains(&found.start));
+        let Some(found) = next.cloned() else {
+            return false;
+        };
+        self.secondary.push(CursorState {
+            cursor: found.end - 1,
+            selection: Some(VisualSelection { anchor: found.start, linewise: false }),
+            preferred_column: None,
+        });
+        self.vim.set_mode(VimMode::Visual);
+        self.merge_cursors();
+        true
+    }
+
+    /// Drop the secondary cursors
+    pub fn clear_cursors(&mut self) {
+        self.secondary.clear();
+    }
+
+    /// Every cursor with its selection, primary first
+    pub fn cursors(&self) -> Vec<(usize, Option<Range<usize>>)> {
+        let mut cursors = vec![(self.cursor, self.selection())];
+        for state in &self.secondary {
+            let range = state.selection.map(|selection| self.visual_range(selection, state.cursor).0);
+            cursors.push((state.cursor, range));
+        }
+        cursors
+    }
+
+    /// Search for `pattern` and move to the first match from the cursor.
+    /// Returns how many matches there are.
+    pub fn search(&mut self, pattern: &str, options: SearchOptions, forward: bool) -> Result<usize, String> {
+        let query = SearchQuery::new(pattern, options)?;
+        let matches = query.find_all(&self.buffer);
+        self.search = Some(SearchState { query, forward });
+        self.jump_to_match(&matches, self.cursor, forward, true);
+        Ok(matches.len())
+    }
+
+    /// Move to the next match of the last search; `forward` false goes the
+    /// other way. False without a search or matches.
+    pub fn search_next(&mut self, forward: bool) -> bool {
+        let Some(search) = &self.search else {
+            return false;
+        };
+        let matches = search.query.find_all(&self.buffer);
+        let forward = search.forward == forward;
+        self.jump_to_match(&matches, self.cursor, forward, false)
+    }
+
+    /// Pattern of the last search
+    pub fn search_pattern(&self) -> Option<&str> {
+        self.search.as_ref().map(|search| search.query.pattern())
+    }
+
+    /// Stop highlighting the last search
+    pub fn clear_search(&mut self) {
+        self.search = None;
+    }
+
+    /// Char ranges of the last search's matches
+    pub fn search_matches(&self) -> Vec<Range<usize>> {
+        self.search.as_ref().map_or_else(Vec::new, |search| search.query.find_all(&self.buffer))
+    }
+
+    /// Replace the match under the cursor and move to the next one. False
+    /// when the cursor is not on a match.
+    pub fn replace_current(&mut self, replacement: &str) -> bool {
+        let Some(search) = self.search.clone() else {
+            return false;
+        };
+        let text = self.buffer.get_text();
+        let start = self.buffer.char_to_byte(self.cursor);
+        let Some((found, expanded)) = search.query.replacement_at(&text, start, replacement) else {
+            return false;
+        };
+        let end = self.buffer.byte_to_char(found.end);
+        self.buffer.replace(self.cursor..end, &expanded);
+        self.cursor += expanded.chars().count();
+        self.search_next(true);
+        true
+    }
+
+    /// Replace every match of the last search as one undo step. Returns how
+    /// many were replaced.
+    pub fn replace_all(&mut self, replacement: &str) -> usize {
+        match self.search.clone() {
+            Some(search) => self.replace_matches(&search.query, replacement),
+            None => 0,
+        }
+    }
+
+    /// Replace every match of `query` as one undo step, leaving the last
+    /// search alone. Returns how many were replaced.
+    pub fn replace_matches(&mut self, query: &SearchQuery, replacement: &str) -> usize {
+        let text = self.buffer.get_text();
+        let edits = query.find_in(&text).into_iter()
+            .filter_map(|found| {
+                let (_, expanded) = query.replacement_at(&text, found.start, replacement)?;
+                Some((self.buffer.byte_to_char(found.start)..self.buffer.byte_to_char(found.end), expanded))
+            })
+            .collect();
+        self.apply_edits(edits)
+    }
+
+    /// Apply edits to the current text as one undo step, with the cursors
+    /// following them. Ranges must not overlap; inserts at the same place
+    /// go in the order given, as LSP text edits do. Returns how many were
+    /// applied.
+    pub fn apply_edits(&mut self, mut edits: Vec<(Range<usize>, String)>) -> usize {
+        let version = self.buffer.version();
+        edits.sort_by_key(|(range, _)| range.start);
+        self.buffer.begin_transaction();
+        // Back to front, so earlier edits keep their offsets
+        for (range, text) in edits.iter().rev() {
+            self.buffer.replace(range.clone(), text);
+        }
+        self.buffer.commit_transaction();
+        let mut primary = self.primary_state();
+        self.follow_changes(&mut primary, version);
+        self.load_state(primary);
+        self.map_secondary(version);
+        self.clamp_cursor();
+        edits.len()
+    }
+
+    fn jump_to_match(&mut self, matches: &[Range<usize>], from: usize, forward: bool, inclusive: bool) -> bool {
+        let found = if forward {
+            matches.iter().find(|found| found.start > from || inclusive && found.start == from).or(matches.first())
+        } else {
+            matches.iter().rev().find(|found| found.start < from).or(matches.last())
+        };
+        match found {
+            Some(found) => {
+                self.cursor = found.start;
+                self.preferred_column = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Follow a `/` or `?` search as it is typed
+    fn preview_search(&mut self, pattern: &str, forward: bool) {
+        let (origin, _) = self.search_origin.get_or_insert_with(|| (self.cursor, self.search.clone()));
+        let origin = *origin;
+        self.cursor = origin;
+        if pattern.is_empty() {
+            return;
+        }
+        // Half-typed patterns are often invalid; keep the last good one
+        if let Ok(query) = vim_search_query(pattern) {
+            let matches = query.find_all(&self.buffer);
+            self.jump_to_match(&matches, origin, forward, forward);
+            self.search = Some(SearchState { query, forward });
+        }
+    }
+
+    fn apply(&mut self, command: VimCommand, register: Option<char>, actions: &mut Vec<EditorAction>) {
+        let vertical = matches!(
+            command,
+            VimCommand::MoveUp(_) | VimCommand::MoveDown(_) | VimCommand::Move(Direction::Up | Direction::Down)
+                | VimCommand::ExtendSelectionUp | VimCommand::ExtendSelectionDown
+        );
+        if !vertical {
+            self.preferred_column = None;
+        }
+
+        match command {
+            VimCommand::None => {}
+
+            // Commands from `handle_normal_key` and `handle_visual_key`
+            VimCommand::Move(direction) => {
+                let command = match direction {
+                    Direction::Up => VimCommand::MoveUp(1),
+                    Direction::Down => VimCommand::MoveDown(1),
+                    Direction::Left => VimCommand::MoveLeft(1),
+                    Direction::Right => VimCommand::MoveRight(1),
+                };
+                self.apply(command, register, actions);
+            }
+            VimCommand::Insert => self.apply(VimCommand::EnterInsertMode, register, actions),
+            VimCommand::Visual => self.apply(VimCommand::EnterVisualMode, register, actions),
+            VimCommand::Paste => self.apply(VimCommand::PasteAfter, register, actions),
+            VimCommand::Normal => {
+                if self.recording.is_some() {
+                    self.finish_insert();
+                }
+                self.leave_visual();
+                self.vim.set_mode(VimMode::Normal);
+            }
+            VimCommand::Delete(motion) | VimCommand::Yank(motion) => {
+                let operator = if matches!(command, VimCommand::Delete(_)) { Operator::Delete } else { Operator::Yank };
+                let (range, linewise) = self.motion_range(motion);
+                self.leave_visual();
+                self.vim.set_mode(VimMode::Normal);
+                self.operate(operator, range, linewise, register);
+                if operator == Operator::Delete {
+                    self.record(command, register);
+                }
+            }
+
+            // Movement
+            VimCommand::MoveLeft(count) => {
+                let (line, column) = self.cursor_position();
+                self.cursor = self.buffer.position_to_char(line, column.saturating_sub(count as usize));
+            }
+            VimCommand::MoveRight(count) => {
+                let (line, column) = self.cursor_position();
+                self.cursor = self.buffer.position_to_char(line, column + count as usize);
+            }
+            VimCommand::MoveUp(count) => self.move_vertical(-(count as isize)),
+            VimCommand::MoveDown(count) => self.move_vertical(count as isize),
+            VimCommand::ExtendSelectionLeft => self.apply(VimCommand::MoveLeft(1), register, actions),
+            VimCommand::ExtendSelectionRight => self.apply(VimCommand::MoveRight(1), register, actions),
+            VimCommand::ExtendSelectionUp => self.move_vertical(-1),
+            VimCommand::ExtendSelectionDown => self.move_vertical(1),
+            VimCommand::MoveWordForward(count) => {
+                self.cursor = motion::word_forward(&self.buffer, self.cursor, count);
+            }
+            VimCommand::MoveWordBackward(count) => {
+                self.cursor = motion::word_backward(&self.buffer, self.cursor, count);
+            }
+            VimCommand::MoveWordEnd(count) => {
+                self.cursor = motion::word_end(&self.buffer, self.cursor, count);
+            }
+            VimCommand::MoveLineStart => self.cursor = self.buffer.line_to_char(self.line()),
+            VimCommand::MoveLineEnd => {
+                self.cursor = motion::line_end(&self.buffer, self.line());
+                // `$` then `j` stays at the end of each line
+                self.preferred_column = Some(usize::MAX);
+            }
+            VimCommand::GoToLine(line) => self.go_to_line(line.saturating_sub(1)),
+            VimCommand::GoToFirstLine => self.go_to_line(0),
+            VimCommand::GoToLastLine => self.go_to_line(self.last_line()),
+
+            // Modes
+            VimCommand::EnterInsertMode => self.start_insert(command, register, VimMode::Insert),
+            VimCommand::AppendMode => {
+                self.start_insert(command, register, VimMode::Insert);
+                if self.buffer.line_length(self.line()) > 0 {
+                    self.cursor = (self.cursor + 1).min(motion::line_end(&self.buffer, self.line()));
+                }
+            }
+            VimCommand::InsertLineStart => {
+                self.start_insert(command, register, VimMode::Insert);
+                self.cursor = motion::first_non_blank(&self.buffer, self.line());
+            }
+            VimCommand::AppendLineEnd => {
+                self.start_insert(command, register, VimMode::Insert);
+                self.cursor = motion::line_end(&self.buffer, self.line());
+            }
+            VimCommand::OpenLineBelow => {
+                self.start_insert(command, register, VimMode::Insert);
+                let end = motion::line_end(&self.buffer, self.line());
+                self.buffer.insert_at(end, "\n");
+                self.cursor = end + 1;
+            }
+            VimCommand::OpenLineAbove => {
+                self.start_insert(command, register, VimMode::Insert);
+                let start = self.buffer.line_to_char(self.line());
+                self.buffer.insert_at(start, "\n");
+                self.cursor = start;
+            }
+            VimCommand::EnterReplaceMode => self.start_insert(command, register, VimMode::Replace),
+            VimCommand::ExitInsertMode | VimCommand::ExitReplaceMode => self.finish_insert(),
+            VimCommand::EnterVisualMode | VimCommand::EnterVisualLineMode => {
+                let linewise = command == VimCommand::EnterVisualLineMode;
+                let anchor = self.selection.map_or(self.cursor, |selection| selection.anchor);
+                self.selection = Some(VisualSelection { anchor, linewise });
+                self.vim.set_mode(if linewise { VimMode::VisualLine } else { VimMode::Visual });
+            }
+            VimCommand::SwapSelectionEnds => {
+                if let Some(selection) = &mut self.selection {
+                    std::mem::swap(&mut selection.anchor, &mut self.cursor);
+                }
+            }
+            VimCommand::SelectTextObject(object) => {
+                if let Some((range, linewise)) = motion::text_object(&self.buffer, self.cursor, object)
+                    && !range.is_empty()
+                {
+                    self.selection = Some(VisualSelection { anchor: range.start, linewise });
+                    self.cursor = range.end - 1;
+                    self.vim.set_mode(if linewise { VimMode::VisualLine } else { VimMode::Visual });
+                }
+            }
+            VimCommand::InsertBeforeSelection | VimCommand::AppendAfterSelection => {
+                let Some((range, linewise)) = self.selection_range() else {
+                    self.vim.set_mode(VimMode::Normal);
+                    return;
+                };
+                self.leave_visual();
+                self.cursor = match command {
+                    VimCommand::InsertBeforeSelection => range.start,
+                    // Lines selected whole append before the last line break
+                    _ if linewise => motion::line_end(&self.buffer, self.buffer.char_to_line(range.end - 1)),
+                    _ => range.end,
+                };
+                // `.` inserts the same text again at the cursor
+                self.start_insert(VimCommand::EnterInsertMode, register, VimMode::Insert);
+            }
+            VimCommand::ExitVisualMode => {
+                self.leave_visual();
+                self.vim.set_mode(VimMode::Normal);
+            }
+            VimCommand::EnterCommandMode => {
+                // Records the `'<` and `'>` marks a visual `:` range refers to
+                self.leave_visual();
+                self.vim.set_mode(VimMode::Command);
+            }
+            VimCommand::ExitCommandMode => {
+                // A cancelled search puts the cursor and old search back
+                if let Some((cursor, search)) = self.search_origin.take() {
+                    self.cursor = cursor;
+                    self.search = search;
+                }
+                self.vim.set_mode(VimMode::Normal);
+            }
+            VimCommand::ExecuteCommand(line) => {
+                self.vim.set_mode(VimMode::Normal);
+                let search = line.strip_prefix('/').map(|pattern| (pattern, true))
+                    .or_else(|| line.strip_prefix('?').map(|pattern| (pattern, false)));
+                match search {
+                    Some((pattern, forward)) => {
+                        if let Some(action) = self.accept_search(pattern, forward) {
+                            actions.push(action);
+                        }
+                    }
+                    None => {
+                        self.vim.last_command = Some(line.clone());
+                        actions.extend(self.run_ex(&line));
+                    }
+                }
+            }
+            VimCommand::SearchNext(count) | VimCommand::SearchPrevious(count) => {
+                let forward = matches!(command, VimCommand::SearchNext(_));
+                if self.search.is_none() {
+                    actions.push(EditorAction::Error("No previous search pattern".to_string()));
+                }
+                for _ in 0..count {
+                    if !self.search_next(forward) {
+                        let pattern = self.search.as_ref().map(|search| search.query.pattern().to_string());
+                        if let Some(pattern) = pattern {
+                            actions.push(EditorAction::Error(format!("Pattern not found: {}", pattern)));
+                        }
+                        break;
+                    }
+                }
+            }
+
+            // Operators
+            VimCommand::DeleteChar(count) => {
+                let end = (self.cursor + count as usize).min(motion::line_end(&self.buffer, self.line()));
+                if end > self.cursor {
+                    self.operate(Operator::Delete, self.cursor..end, false, register);
+                    self.record(command, register);
+                }
+            }
+            VimCommand::DeleteLine(count) | VimCommand::YankLine(count) => {
+                let line = self.line();
+                let range = self.buffer.line_to_char(line)..self.buffer.line_to_char(line + count as usize);
+                if matches!(command, VimCommand::DeleteLine(_)) {
+                    self.operate(Operator::Delete, range, true, register);
+                    self.record(command, register);
+                } else {
+                    self.operate(Operator::Yank, range, true, register);
+                }
+            }
+            VimCommand::DeleteWord(count) => {
+                let range = motion::delete_word_range(&self.buffer, self.cursor, count);
+                self.operate(Operator::Delete, range, false, register);
+                self.record(command, register);
+            }
+            VimCommand::YankWord(count) => {
+                let range = motion::delete_word_range(&self.buffer, self.cursor, count);
+                self.operate(Operator::Yank, range, false, register);
+            }
+            VimCommand::DeleteToLineEnd => {
+                let range = self.cursor..motion::line_end(&self.buffer, self.line());
+                self.operate(Operator::Delete, range, false, register);
+                self.record(command, register);
+            }
+            VimCommand::DeleteToLineStart => {
+                let range = self.buffer.line_to_char(self.line())..self.cursor;
+                self.operate(Operator::Delete, range, false, register);
+                self.record(command, register);
+            }
+            VimCommand::DeleteSelection | VimCommand::YankSelection | VimCommand::ChangeSelection => {
+                let Some((range, linewise)) = self.selection_range() else {
+                    return;
+                };
+                self.leave_visual();
+                self.vim.set_mode(VimMode::Normal);
+                match command {
+                    VimCommand::DeleteSelection => self.operate(Operator::Delete, range, linewise, register),
+                    VimCommand::YankSelection => self.operate(Operator::Yank, range, linewise, register),
+                    _ => {
+                        // Repeating a visual change would need the selection's
+                        // shape, so `.` does not replay it
+                        self.repeating = true;
+                        self.start_insert(command, register, VimMode::Insert);
+                        self.repeating = false;
+                        self.operate(Operator::Change, range, linewise, register);
+                    }
+                }
+            }
+            VimCommand::ChangeLine(count) => {
+                let line = self.line();
+                let range = self.buffer.line_to_char(line)..self.buffer.line_to_char(line + count as usize);
+                self.start_insert(command, register, VimMode::Insert);
+                self.operate(Operator::Change, range, true, register);
+            }
+            VimCommand::ChangeWord(count) => {
+                let range = motion::change_word_range(&self.buffer, self.cursor, count);
+                self.start_insert(command, register, VimMode::Insert);
+                self.operate(Operator::Change, range, false, register);
+            }
+            VimCommand::DeleteTextObject(object) | VimCommand::YankTextObject(object) | VimCommand::ChangeTextObject(object) => {
+                let Some((range, linewise)) = motion::text_object(&self.buffer, self.cursor, object) else {
+                    // Not inside one; nothing happens, as in Vim
+                    self.vim.set_mode(VimMode::Normal);
+                    return;
+                };
+                match command {
+                    VimCommand::DeleteTextObject(_) => {
+                        self.operate(Operator::Delete, range, linewise, register);
+                        self.record(command, register);
+                    }
+                    VimCommand::YankTextObject(_) => self.operate(Operator::Yank, range, linewise, register),
+                    _ => {
+                        self.start_insert(command, register, VimMode::Insert);
+                        self.operate(Operator::Change, range, linewise, register);
+                    }
+                }
+            }
+            VimCommand::PasteAfter | VimCommand::PasteBefore => {
+                self.paste(command == VimCommand::PasteAfter, register);
+                self.record(command, register);
+            }
+
+            // Typing
+            VimCommand::InsertChar(ch) => {
+                if let Some(change) = self.recording.as_mut().filter(|_| !self.secondary_turn) {
+                    change.typed.push(ch);
+                }
+                self.type_char(ch);
+            }
+            VimCommand::ReplaceChar(ch) => {
+                if let Some(change) = self.recording.as_mut().filter(|_| !self.secondary_turn) {
+                    change.typed.push(ch);
+                }
+                self.overtype_char(ch);
+            }
+
+            // History
+            VimCommand::Undo => match self.buffer.undo() {
+                Some(at) => self.cursor = at,
+                None => actions.push(EditorAction::Message("Already at oldest change".to_string())),
+            },
+            VimCommand::Redo => match self.buffer.redo() {
+                Some(at) => self.cursor = at,
+                None => actions.push(EditorAction::Message("Already at newest change".to_string())),
+            },
+            VimCommand::RepeatLastChange(count) => self.repeat_last_change(count, actions),
+        }
+    }
+
+    /// Enter on a `/` or `?` line. An empty pattern repeats the last search.
+    fn accept_search(&mut self, pattern: &str, forward: bool) -> Option<EditorAction> {
+        let origin = self.search_origin.take().map_or(self.cursor, |(cursor, _)| cursor);
+        let query = if pattern.is_empty() {
+            match &self.search {
+                Some(search) => search.query.clone(),
+                None => return Some(EditorAction::Error("No previous search pattern".to_string())),
+            }
+        } else {
+            match vim_search_query(pattern) {
+                Ok(query) => query,
+                Err(e) => return Some(EditorAction::Error(e)),
+            }
+        };
+        let matches = query.find_all(&self.buffer);
+        let pattern = query.pattern().to_string();
+        self.search = Some(SearchState { query, forward });
+        self.cursor = origin;
+        if self.jump_to_match(&matches, origin, forward, false) {
+            None
+        } else {
+            Some(EditorAction::Error(format!("Pattern not found: {}", pattern)))
+        }
+    }
+
+    fn line(&self) -> usize {
+        self.buffer.char_to_line(self.cursor)
+    }
+
+    /// Last line with text; a final line break ends the last line rather
+    /// than starting another
+    fn last_line(&self) -> usize {
+        let lines = self.buffer.line_count();
+        if lines > 1 && self.buffer.line_length(lines - 1) == 0 {
+            lines - 2
+        } else {
+            lines - 1
+        }
+    }
+
+    fn go_to_line(&mut self, line: usize) {
+        self.cursor = motion::first_non_blank(&self.buffer, line.min(self.last_line()));
+    }
+
+    fn move_vertical(&mut self, delta: isize) {
+        let (line, column) = self.cursor_position();
+        let column = self.preferred_column.unwrap_or(column);
+        let target = line.saturating_add_signed(delta).min(self.last_line());
+        self.cursor = self.buffer.position_to_char(target, column);
+        self.preferred_column = Some(column);
+    }
+
+    /// Keep the cursor on a character outside insert and replace mode
+    fn clamp_cursor(&mut self) {
+        self.cursor = self.cursor.min(self.buffer.len_chars());
+        if matches!(self.vim.mode(), VimMode::Insert | VimMode::Replace) {
+            return;
+        }
+        let (line, column) = self.cursor_position();
+        let line = line.min(self.last_line());
+        let length = self.buffer.line_length(line);
+        self.cursor = self.buffer.line_to_char(line) + column.min(length.saturating_sub(1));
+    }
+
+    fn selection_range(&self) -> Option<(Range<usize>, bool)> {
+        self.selection.map(|selection| self.visual_range(selection, self.cursor))
+    }
+
+    fn visual_range(&self, selection: VisualSelection, cursor: usize) -> (Range<usize>, bool) {
+        let start = selection.anchor.min(cursor);
+        let end = selection.anchor.max(cursor);
+        if selection.linewise {
+            let first = self.buffer.char_to_line(start);
+            let last = self.buffer.char_to_line(end);
+            (self.buffer.line_to_char(first)..self.buffer.line_to_char(last + 1), true)
+        } else {
+            (start..(end + 1).min(self.buffer.len_chars()), false)
+        }
+    }
+
+    fn leave_visual(&mut self) {
+        if let Some((range, _)) = self.selection_range() {
+            let start = self.buffer.char_to_position(range.start);
+            let end = self.buffer.char_to_position(range.end.saturating_sub(1).max(range.start));
+            self.vim.marks.insert('<', start);
+            self.vim.marks.insert('>', end);
+        }
+        self.selection = None;
+    }
+
+    /// Region of a `Motion` from `handle_normal_key` or `handle_visual_key`
+    fn motion_range(&self, motion: Motion) -> (Range<usize>, bool) {
+        if let Some(selection) = self.selection_range() {
+            return selection;
+        }
+        let line = self.line();
+        let line_start = self.buffer.line_to_char(line);
+        let line_end = motion::line_end(&self.buffer, line);
+        match motion {
+            Motion::Line => (line_start..self.buffer.line_to_char(line + 1), true),
+            Motion::Word => (motion::delete_word_range(&self.buffer, self.cursor, 1), false),
+            Motion::Character => (self.cursor..(self.cursor + 1).min(line_end), false),
+            Motion::ToLineEnd => (self.cursor..line_end, false),
+            Motion::ToLineStart => (line_start..self.cursor, false),
+        }
+    }
+
+    fn start_insert(&mut self, command: VimCommand, register: Option<char>, mode: VimMode) {
+        // The command's own edit and the typing after it undo together
+        self.buffer.begin_transaction();
+        self.vim.set_mode(mode);
+        if !self.repeating {
+            self.recording = Some(RepeatableChange { command, register, typed: String::new() });
+        }
+    }
+
+    fn finish_insert(&mut self) {
+        self.buffer.commit_transaction();
+        if let Some(change) = self.recording.take() {
+            self.last_change = Some(change);
+        }
+        self.vim.set_mode(VimMode::Normal);
+        // Leaving insert mode steps back onto the last character typed
+        let (_, column) = self.cursor_position();
+        if column > 0 {
+            self.cursor -= 1;
+        }
+    }
+
+    fn record(&mut self, command: VimCommand, register: Option<char>) {
+        if !self.repeating {
+            self.last_change = Some(RepeatableChange { command, register, typed: String::new() });
+        }
+    }
+
+    fn repeat_last_change(&mut self, count: u32, actions: &mut Vec<EditorAction>) {
+        let Some(change) = self.last_change.clone() else {
+            return;
+        };
+        self.repeating = true;
+        for _ in 0..count {
+            self.apply(change.command.clone(), change.register, actions);
+            let mode = self.vim.mode();
+            if matches!(mode, VimMode::Insert | VimMode::Replace) {
+                for ch in change.typed.chars() {
+                    if mode == VimMode::Insert {
+                        self.type_char(ch);
+                    } else {
+                        self.overtype_char(ch);
+                    }
+                }
+                self.finish_insert();
+            }
+            self.clamp_cursor();
+        }
+        self.repeating = false;
+    }
+
+    fn type_char(&mut self, ch: char) {
+        match ch {
+            '\x08' => {
+                if self.cursor > 0 {
+                    self.buffer.remove(self.cursor - 1..self.cursor);
+                    self.cursor -= 1;
+                }
+            }
+            '\r' | '\n' => {
+                self.buffer.insert_at(self.cursor, "\n");
+                self.cursor += 1;
+            }
+            ch if ch.is_control() && ch != '\t' => {}
+            ch => {
+                self.buffer.insert_at(self.cursor, ch.encode_utf8(&mut [0; 4]));
+                self.cursor += 1;
+            }
+        }
+    }
+
+    /// Replace mode typing: overwrite the character under the cursor,
+    /// extending the line at its end
+    fn overtype_char(&mut self, ch: char) {
+        let line_start = self.buffer.line_to_char(self.line());
+        let line_end = motion::line_end(&self.buffer, self.line());
+        match ch {
+            '\x08' => {
+                if self.cursor > line_start {
+                    self.cursor -= 1;
+                }
+            }
+            '\r' | '\n' => {
+                self.buffer.insert_at(self.cursor, "\n");
+                self.cursor += 1;
+            }
+            ch if ch.is_control() && ch != '\t' => {}
+            ch => {
+                let end = if self.cursor < line_end { self.cursor + 1 } else { self.cursor };
+                self.buffer.replace(self.cursor..end, ch.encode_utf8(&mut [0; 4]));
+                self.cursor += 1;
+            }
+        }
+    }
+
+    /// Delete, yank or change a region. Linewise regions cover whole lines
+    /// and go to registers with a trailing line break, which is how `p`
+    /// knows to paste them as lines.
+    fn operate(&mut self, operator: Operator, range: Range<usize>, linewise: bool, register: Option<char>) {
+        if !linewise {
+            if range.is_empty() {
+                return;
+            }
+            let text = self.buffer.slice(range.clone());
+            self.store(register, text, operator != Operator::Yank);
+            if operator != Operator::Yank {
+                self.buffer.remove(range.clone());
+            }
+            self.cursor = range.start;
+            return;
+        }
+
+        let first = self.buffer.char_to_line(range.start);
+        let last = if range.end > range.start { self.buffer.char_to_line(range.end - 1) } else { first };
+        let last = last.max(first);
+        let text_end = motion::line_end(&self.buffer, last);
+        let mut text = self.buffer.slice(self.buffer.line_to_char(first)..text_end);
+        text.push('\n');
+        self.store(register, text, operator != Operator::Yank);
+
+        match operator {
+            Operator::Delete => {
+                let mut start = self.buffer.line_to_char(first);
+                let end = self.buffer.line_to_char(last + 1);
+                // The last line has no break of its own; take the one before it
+                if last + 1 >= self.buffer.line_count() && first > 0 {
+                    start = motion::line_end(&self.buffer, first - 1);
+                }
+                self.buffer.remove(start..end);
+                self.cursor = motion::first_non_blank(&self.buffer, first.min(self.last_line()));
+            }
+            Operator::Yank => {
+                if self.line() != first {
+                    self.cursor = self.buffer.line_to_char(first);
+                }
+            }
+            Operator::Change => {
+                // The lines stay, emptied, for the insert
+                let start = self.buffer.line_to_char(first);
+                self.buffer.remove(start..text_end);
+                self.cursor = start;
+            }
+        }
+    }
+
+    /// Put text in registers the way Vim does: the named register when one
+    /// was given, `"` always, and otherwise `0` for yanks, `1`-`9` for
+    /// deleted lines and `-` for smaller deletes. `_` discards.
+    fn store(&mut self, register: Option<char>, text: String, deleted: bool) {
+        match register {
+            Some('_') => return,
+            Some(name) if name.is_ascii_uppercase() => {
+                let name = name.to_ascii_lowercase();
+                let mut content = self.vim.get_register(name).unwrap_or_default();
+                content.push_str(&text);
+                self.vim.set_register(name, content.clone());
+                self.vim.set_register('"', content);
+                return;
+            }
+            Some(name) if name != '"' => self.vim.set_register(name, text.clone()),
+            _ if !deleted => self.vim.set_register('0', text.clone()),
+            _ if text.contains('\n') => {
+                for n in (1..9).rev() {
+                    let from = char::from_digit(n, 10).unwrap_or('1');
+                    let to = char::from_digit(n + 1, 10).unwrap_or('9');
+                    if let Some(content) = self.vim.registers.remove(&from) {
+                        self.vim.set_register(to, content);
+                    }
+                }
+                self.vim.set_register('1', text.clone());
+            }
+            _ => self.vim.set_register('-', text.clone()),
+        }
+        self.vim.set_register('"', text);
+    }
+
+    fn paste(&mut self, after: bool, register: Option<char>) {
+        let name = register.unwrap_or('"').to_ascii_lowercase();
+        let Some(text) = self.vim.get_register(name).filter(|text| !text.is_empty()) else {
+            return;
+        };
+        let line = self.line();
+
+        if let Some(lines) = text.strip_suffix('\n') {
+            if !after {
+                self.buffer.insert_at(self.buffer.line_to_char(line), &text);
+                self.cursor = motion::first_non_blank(&self.buffer, line);
+            } else if line + 1 < self.buffer.line_count() {
+                self.buffer.insert_at(self.buffer.line_to_char(line + 1), &text);
+                self.cursor = motion::first_non_blank(&self.buffer, line + 1);
+            } else {
+                // Below a last line without a break of its own
+                let end = motion::line_end(&self.buffer, line);
+                self.buffer.insert_at(end, &format!("\n{}", lines));
+                self.cursor = motion::first_non_blank(&self.buffer, line + 1);
+            }
+            return;
+        }
+
+        let at = if after && self.buffer.line_length(line) > 0 { self.cursor + 1 } else { self.cursor };
+        self.buffer.insert_at(at, &text);
+        // The cursor ends on the last pasted character
+        self.cursor = at + text.chars().count() - 1;
+    }
+
+    fn run_ex(&mut self, line: &str) -> Vec<EditorAction> {
+        let command = match ex::parse(line) {
+            Ok(command) => command,
+            Err(e) => return vec![EditorAction::Error(e)],
+        };
+        let result = match command {
+            ExCommand::GoTo(address) => self.resolve(address).map(|line| {
+                self.go_to_line(line);
+                Vec::new()
+            }),
+            ExCommand::Write { path, quit } => {
+                let mut actions = vec![EditorAction::Write { path }];
+                if quit {
+                    actions.push(EditorAction::Quit { force: false });
+                }
+                Ok(actions)
+            }
+            ExCommand::Quit { force } => {
+                if !force && self.buffer.is_modified() {
+                    Err("No write since last change (add ! to override)".to_string())
+                } else {
+                    Ok(vec![EditorAction::Quit { force }])
+                }
+            }
+            ExCommand::Delete { range, register } | ExCommand::Yank { range, register } => {
+                let operator = if matches!(command, ExCommand::Delete { .. }) { Operator::Delete } else { Operator::Yank };
+                self.resolve_range(range).map(|(first, last)| {
+                    let lines = self.buffer.line_to_char(first)..self.buffer.line_to_char(last + 1);
+                    self.operate(operator, lines, true, register);
+                    Vec::new()
+                })
+            }
+            ExCommand::Substitute { range, substitution } => self.resolve_range(range).and_then(|(first, last)| {
+                self.buffer.begin_transaction();
+                let mut replaced = 0;
+                let mut changed_lines = 0;
+                let (mut line, mut last) = (first, last);
+                let mut last_changed = None;
+                while line <= last && line < self.buffer.line_count() {
+                    let text = self.buffer.get_line(line).into_owned();
+                    if let Some((new_text, count)) = substitution.apply(&text) {
+                        let start = self.buffer.line_to_char(line);
+                        self.buffer.replace(start..start + text.chars().count(), &new_text);
+                        replaced += count;
+                        changed_lines += 1;
+                        // Line breaks in the replacement push later lines down
+                        let added = new_text.matches('\n').count();
+                        line += added;
+                        last += added;
+                        last_changed = Some(line);
+                    }
+                    line += 1;
+                }
+                self.buffer.commit_transaction();
+
+                match last_changed {
+                    Some(line) => {
+                        self.cursor = motion::first_non_blank(&self.buffer, line);
+                        Ok(vec![EditorAction::Message(format!(
+                            "{} substitution{} on {} line{}",
+                            replaced, if replaced == 1 { "" } else { "s" },
+                            changed_lines, if changed_lines == 1 { "" } else { "s" },
+                        ))])
+                    }
+                    None => Err(format!("Pattern not found: {}", substitution.pattern())),
+                }
+            }),
+            ExCommand::Undo => Ok(self.execute(VimCommand::Undo)),
+            ExCommand::Redo => Ok(self.execute(VimCommand::Redo)),
+        };
+        result.unwrap_or_else(|e| vec![EditorAction::Error(e)])
+    }
+
+    /// 0-based line of an ex address
+    fn resolve(&self, address: Address) -> Result<usize, String> {
+        match address {
+            Address::Line(line) => Ok(line.saturating_sub(1).min(self.last_line())),
+            Address::Current => Ok(self.line()),
+            Address::Last => Ok(self.last_line()),
+            Address::Mark(mark) => self.vim.marks.get(&mark)
+                .map(|(line, _)| (*line).min(self.last_line()))
+                .ok_or_else(|| format!("Mark not set: {}", mark)),
+        }
+    }
+
+    fn resolve_range(&self, range: LineRange) -> Result<(usize, usize), String> {
+        let start = self.resolve(range.start)?;
+        let end = self.resolve(range.end)?;
+        Ok((start.min(end), start.max(end)))
+    }
+}
+
+/// A search typed after `/`: a regex, with `\c` anywhere making it ignore
+/// case, and `\<` and `\>` for word boundaries
+fn vim_search_query(pattern: &str) -> Result<SearchQuery, String> {
+    let case_sensitive = !pattern.contains("\\c");
+    let pattern = ex::translate_pattern(&pattern.replace("\\c", "").replace("\\C", ""));
+    SearchQuery::new(&pattern, SearchOptions { regex: true, case_sensitive, whole_word: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> Editor {
+        Editor::new(TextBuffer::from_string(text.to_string()))
+    }
+
+    fn keys(editor: &mut Editor, keys: &str) -> Vec<EditorAction> {
+        keys.chars().flat_map(|key| editor.process_key(key)).collect()
+    }
+
+    fn text(editor: &Editor) -> String {
+        editor.buffer().to_string()
+    }
+
+    #[test]
+    fn vim_delete_line_and_undo() {
+        let mut editor = editor("a\nb\nc\n");
+        keys(&mut editor, "dd");
+        assert_eq!(text(&editor), "b\nc\n");
+        keys(&mut editor, "2dd");
+        assert_eq!(text(&editor), "");
+        keys(&mut editor, "u");
+        assert_eq!(text(&editor), "b\nc\n");
+        keys(&mut editor, "u");
+        assert_eq!(text(&editor), "a\nb\nc\n");
+        keys(&mut editor, "\x12");
+        assert_eq!(text(&editor), "b\nc\n");
+    }
+
+    #[test]
+    fn vim_change_word_repeats_with_dot() {
+        let mut editor = editor("foo bar baz\n");
+        keys(&mut editor, "cwxx\x1bw.");
+        assert_eq!(text(&editor), "xx xx baz\n");
+        // The change and what was typed after it undo together
+        keys(&mut editor, "u");
+        assert_eq!(text(&editor), "xx bar baz\n");
+    }
+
+    #[test]
+    fn vim_counts_multiply_across_operator_and_motion() {
+        let mut editor = editor("a b c d e f g\n");
+        keys(&mut editor, "2d2w");
+        assert_eq!(text(&editor), "e f g\n");
+    }
+
+    #[test]
+    fn vim_yank_and_paste_lines() {
+        let mut editor = editor("1\n2\n3\n4\n");
+        keys(&mut editor, "2yyjjp");
+        assert_eq!(text(&editor), "1\n2\n3\n1\n2\n4\n");
+        keys(&mut editor, "ggP");
+        assert_eq!(text(&editor), "1\n2\n1\n2\n3\n1\n2\n4\n");
+    }
+
+    #[test]
+    fn vim_named_registers() {
+        let mut editor = editor("one two\n");
+        keys(&mut editor, "\"ayw");
+        keys(&mut editor, "dw$\"ap");
+        assert_eq!(text(&editor), "twoone \n");
+        assert_eq!(editor.vim().get_register('a').as_deref(), Some("one "));
+    }
+
+    #[test]
+    fn vim_text_objects() {
+        let mut editor = editor("say \"hi there\" (a, b) ok\n");
+        // The quotes are found ahead of the cursor on its line
+        keys(&mut editor, "ci\"yo\x1b");
+        assert_eq!(text(&editor), "say \"yo\" (a, b) ok\n");
+        editor.set_cursor_position(0, 10);
+        keys(&mut editor, "da(");
+        assert_eq!(text(&editor), "say \"yo\"  ok\n");
+        keys(&mut editor, "0diw");
+        assert_eq!(text(&editor), " \"yo\"  ok\n");
+    }
+
+    #[test]
+    fn vim_insert_and_append_at_line_ends() {
+        let mut editor = editor("    one\ntwo\n");
+        keys(&mut editor, "$I[\x1bA]\x1bj.");
+        assert_eq!(text(&editor), "    [one]\ntwo]\n");
+    }
+
+    #[test]
+    fn vim_visual_text_objects() {
+        let mut editor = editor("call(\"a b\", c)\n\nnext\n");
+        keys(&mut editor, "wwviw");
+        assert_eq!(editor.selection(), Some(6..7));
+        keys(&mut editor, "a\"");
+        assert_eq!(editor.selection(), Some(5..10));
+        keys(&mut editor, "\x1bvi(d");
+        assert_eq!(text(&editor), "call()\n\nnext\n");
+        keys(&mut editor, "vipd");
+        assert_eq!(text(&editor), "\nnext\n");
+    }
+
+    #[test]
+    fn vim_visual_swap_ends() {
+        let mut editor = editor("abcdef\n");
+        keys(&mut editor, "lvllohd");
+        assert_eq!(text(&editor), "ef\n");
+    }
+
+    #[test]
+    fn vim_visual_insert_and_append() {
+        let mut editor = editor("one two\n");
+        keys(&mut editor, "wviwA!\x1b");
+        assert_eq!(text(&editor), "one two!\n");
+        keys(&mut editor, "0vlI<\x1b");
+        assert_eq!(text(&editor), "<one two!\n");
+        keys(&mut editor, "VA;\x1b");
+        assert_eq!(text(&editor), "<one two!;\n");
+    }
+
+    #[test]
+    fn vim_visual_delete() {
+        let mut editor = editor("hello world\n");
+        keys(&mut editor, "vld");
+        assert_eq!(text(&editor), "llo world\n");
+        keys(&mut editor, "Vd");
+        assert_eq!(text(&editor), "");
+    }
+
+    #[test]
+    fn vim_substitute_is_one_undo_step() {
+        let mut editor = editor("a a\na\n");
+        keys(&mut editor, ":%s/a/b/g\n");
+        assert_eq!(text(&editor), "b b\nb\n");
+        keys(&mut editor, "u");
+        assert_eq!(text(&editor), "a a\na\n");
+    }
+
+    #[test]
+    fn vim_ex_write_and_go_to_line() {
+        let mut editor = editor("1\n2\n3\n");
+        let actions = keys(&mut editor, ":w out.txt\n");
+        assert_eq!(actions, vec![EditorAction::Write { path: Some("out.txt".to_string()) }]);
+        keys(&mut editor, ":3\n");
+        assert_eq!(editor.cursor_position(), (2, 0));
+    }
+
+    #[test]
+    fn vim_search_moves_as_typed_and_escape_restores() {
+        let mut editor = editor("ab\ncd ab\nab\n");
+        keys(&mut editor, "j/ab");
+        assert_eq!(editor.cursor_position(), (1, 3));
+        keys(&mut editor, "\x1b");
+        assert_eq!(editor.cursor_position(), (1, 0));
+        keys(&mut editor, "/ab\nn");
+        assert_eq!(editor.cursor_position(), (2, 0));
+    }
+
+    #[test]
+    fn vim_commands_on_an_empty_buffer() {
+        let mut editor = editor("");
+        keys(&mut editor, "ddxdwGggcw\x1b.u");
+        assert_eq!(text(&editor), "");
+        keys(&mut editor, "yyp");
+        assert_eq!(text(&editor), "\n");
+    }
+
+    #[test]
+    fn vim_multiple_cursors_edit_together() {
+        let mut editor = editor("abc\ndef\nghi\n");
+        editor.add_cursor(1, 0);
+        editor.add_cursor(2, 0);
+        keys(&mut editor, "iX\x1b");
+        assert_eq!(text(&editor), "Xabc\nXdef\nXghi\n");
+        keys(&mut editor, "u");
+        assert_eq!(text(&editor), "abc\ndef\nghi\n");
+    }
+
+    #[test]
+    fn vim_next_match_after_visual_word() {
+        let mut editor = editor("foo x foo y foo\n");
+        keys(&mut editor, "viw");
+        assert!(editor.add_cursor_at_next_match());
+        assert!(editor.add_cursor_at_next_match());
+        keys(&mut editor, "cbar\x1b");
+        assert_eq!(text(&editor), "bar x bar y bar\n");
+    }
+
+    #[test]
+    fn vim_column_selection_appends_on_every_line() {
+        let mut editor = editor("héllo\nwörld\n");
+        editor.column_select((0, 0), (1, 1));
+        keys(&mut editor, "A|\x1b");
+        assert_eq!(text(&editor), "hé|llo\nwö|rld\n");
+        editor.clear_cursors();
+        editor.add_cursor(0, 0);
+        keys(&mut editor, "A!\x1b");
+        assert_eq!(text(&editor), "hé|llo!\nwö|rld!\n");
+    }
+
+    #[test]
+    fn vim_column_selection_deletes_a_block() {
+        let mut editor = editor("abcd\nefgh\nijkl\n");
+        editor.column_select((0, 1), (2, 2));
+        keys(&mut editor, "d");
+        assert_eq!(text(&editor), "ad\neh\nil\n");
+    }
+}
@@ -11,17 +11,18 @@ use uuid::Uuid;
 use std::collections::HashMap;
 
 use crate::buffer::TextBuffer;
-use crate::vim::{VimMode, VimState, VimCommand};
+use crate::editor::{Editor, EditorAction};
+use crate::syntax::{HighlightSpan, SyntaxHighlighter, TokenKind};
+use crate::vim::VimMode;
 
 /// Visual representation of a code editor
 pub struct EditorView {
     id: Uuid,
     position: Vector2<f32>,
     size: Vector2<f32>,
-    buffer: TextBuffer,
-    vim_state: VimState,
-    cursor_position: (usize, usize), // (line, column)
-    selection: Option<Selection>,
+    editor: Editor,
+    /// Saves, closes and messages from ex commands, for the host to act on
+    actions: Vec<EditorAction>,
     scroll_offset: Vector2<f32>,
     line_height: f32,
     char_width: f32,
@@ -29,17 +30,13 @@ pub struct EditorView {
     line_number_width: f32,
     theme: Theme,
     syntax_highlights: HashMap<usize, Vec<Highlight>>,
+    /// Produces `syntax_highlights` for files opened with `set_file`
+    syntax: Option<SyntaxHighlighter>,
     dirty: bool,
     visible: bool,
     children: Vec<ElementId>,
 }
 
-#[derive(Debug, Clone)]
-pub struct Selection {
-    pub start: (usize, usize),
-    pub end: (usize, usize),
-}
-
 #[derive(Debug, Clone)]
 pub struct Highlight {
     pub start_col: usize,
@@ -62,10 +59,8 @@ impl EditorView {
             id: Uuid::new_v4(),
             position: Vector2::zeros(),
             size: Vector2::zeros(),
-            buffer: TextBuffer::new(),
-            vim_state: VimState::new(),
-            cursor_position: (0, 0),
-            selection: None,
+            editor: Editor::default(),
+            actions: Vec::new(),
             scroll_offset: Vector2::zeros(),
             line_height: 20.0,
             char_width: 10.0,
@@ -73,6 +68,7 @@ impl EditorView {
             line_number_width: 50.0,
             theme: Theme::dark(),
             syntax_highlights: HashMap::new(),
+            syntax: None,
             dirty: true,
             visible: true,
             children: Vec::new(),
@@ -80,165 +76,110 @@ impl EditorView {
     }
 
     pub fn set_content(&mut self, content: String) {
-        self.buffer = TextBuffer::from_string(content);
-        self.cursor_position = (0, 0);
-        self.selection = None;
+        self.editor = Editor::new(TextBuffer::from_string(content));
+        self.syntax = None;
+        self.syntax_highlights.clear();
         self.scroll_offset = Vector2::zeros();
         self.dirty = true;
     }
 
-    pub fn get_content(&self) -> String {
-        self.buffer.to_string()
-    }
-
-    pub fn set_theme(&mut self, theme: Theme) {
-        self.theme = theme;
+    /// Show a file, highlighted if its language has a grammar
+    pub fn set_file(&mut self, path: String, content: String) {
+        self.editor = Editor::new(TextBuffer::with_path(path, content));
+        self.syntax = SyntaxHighlighter::for_buffer(self.editor.buffer());
+        self.syntax_highlights.clear();
+        self.scroll_offset = Vector2::zeros();
+        self.refresh_syntax();
         self.dirty = true;
     }
 
-    pub fn set_syntax_highlights(&mut self, highlights: HashMap<usize, Vec<Highlight>>) {
-        self.syntax_highlights = highlights;
-        self.dirty = true;
+    pub fn get_content(&self) -> String {
+        self.editor.buffer().to_string()
     }
 
-    fn handle_vim_command(&mut self, command: VimCommand) {
-        match command {
-            VimCommand::Move(direction) => {
-                self.move_cursor(direction);
-            }
-            VimCommand::Insert => {
-                self.vim_state.set_mode(VimMode::Insert);
-            }
-            VimCommand::Normal => {
-                self.vim_state.set_mode(VimMode::Normal);
-            }
-            VimCommand::Visual => {
-                self.vim_state.set_mode(VimMode::Visual);
-                self.start_selection();
-            }
-            VimCommand::Delete(motion) => {
-                self.delete_with_motion(motion);
-            }
-            VimCommand::Yank(motion) => {
-                self.yank_with_motion(motion);
-            }
-            VimCommand::Paste => {
-                self.paste();
-            }
-            // Handle all other command variants
-            VimCommand::None | 
-            VimCommand::MoveLeft(_) | VimCommand::MoveRight(_) |
-            VimCommand::MoveUp(_) | VimCommand::MoveDown(_) |
-            VimCommand::MoveWordForward(_) | VimCommand::MoveWordBackward(_) |
-            VimCommand::MoveWordEnd(_) | VimCommand::MoveLineStart |
-            VimCommand::MoveLineEnd | VimCommand::GoToLine(_) |
-            VimCommand::GoToFirstLine | VimCommand::EnterInsertMode |
-            VimCommand::ExitInsertMode | VimCommand::AppendMode |
-            VimCommand::OpenLineBelow | VimCommand::OpenLineAbove |
-            VimCommand::EnterVisualMode | VimCommand::EnterVisualLineMode |
-            VimCommand::EnterReplaceMode | VimCommand::ExitVisualMode |
-            VimCommand::ExitReplaceMode | VimCommand::EnterCommandMode |
-            VimCommand::ExitCommandMode | VimCommand::DeleteChar(_) |
-            VimCommand::DeleteLine(_) | VimCommand::DeleteWord(_) |
-            VimCommand::DeleteToLineEnd | VimCommand::DeleteToLineStart |
-            VimCommand::DeleteSelection | VimCommand::YankLine(_) |
-            VimCommand::YankWord(_) | VimCommand::YankSelection |
-            VimCommand::ChangeLine(_) | VimCommand::ChangeWord(_) |
-            VimCommand::ChangeSelection | VimCommand::PasteAfter |
-            VimCommand::PasteBefore | VimCommand::InsertChar(_) |
-            VimCommand::ReplaceChar(_) | VimCommand::ExtendSelectionLeft |
-            VimCommand::ExtendSelectionRight | VimCommand::ExtendSelectionUp |
-            VimCommand::ExtendSelectionDown | VimCommand::ExecuteCommand(_) |
-            VimCommand::Undo | VimCommand::Redo => {}
-        }
-        self.dirty = true;
+    pub fn editor(&self) -> &Editor {
+        &self.editor
     }
 
-    fn move_cursor(&mut self, direction: crate::vim::Direction) {
-        let (mut line, mut col) = self.cursor_position;
-        let line_count = self.buffer.line_count();
-        
-        match direction {
-            crate::vim::Direction::Up => {
-                if line > 0 {
-                    line -= 1;
-                    col = col.min(self.buffer.line_length(line));
-                }
-            }
-            crate::vim::Direction::Down => {
-                if line < line_count - 1 {
-                    line += 1;
-                    col = col.min(self.buffer.line_length(line));
-                }
-            }
-            crate::vim::Direction::Left => {
-                if col > 0 {
-                    col -= 1;
-                } else if line > 0 {
-                    line -= 1;
-                    col = self.buffer.line_length(line);
-                }
-            }
-            crate::vim::Direction::Right => {
-                let line_len = self.buffer.line_length(line);
-                if col < line_len {
-                    col += 1;
-                } else if line < line_count - 1 {
-                    line += 1;
-                    col = 0;
-                }
-            }
-        }
-        
-        self.cursor_position = (line, col);
-        self.ensure_cursor_visible();
-        
-        if self.vim_state.mode() == VimMode::Visual {
-            self.update_selection();
-        }
+    pub fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.editor
     }
 
-    fn start_selection(&mut self) {
-        self.selection = Some(Selection {
-            start: self.cursor_position,
-            end: self.cursor_position,
-        });
+    /// Actions from ex commands since the last call, such as `:w`
+    pub fn take_actions(&mut self) -> Vec<EditorAction> {
+        std::mem::take(&mut self.actions)
     }
 
-    fn update_selection(&mut self) {
-        if let Some(ref mut selection) = self.selection {
-            selection.end = self.cursor_position;
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        // Colors come from the theme, so every line changes
+        if let Some(syntax) = &self.syntax {
+            let highlights = (0..syntax.highlights().len())
+                .map(|line| (line, self.line_highlights(syntax.line_highlights(line))))
+                .collect();
+            self.syntax_highlights = highlights;
         }
+        self.dirty = true;
     }
 
-    fn delete_with_motion(&mut self, _motion: crate::vim::Motion) {
-        // Simplified delete - just delete current line
-        if self.buffer.line_count() > 1 {
-            self.buffer.delete_line(self.cursor_position.0);
-            if self.cursor_position.0 >= self.buffer.line_count() {
-                self.cursor_position.0 = self.buffer.line_count() - 1;
-            }
-            self.cursor_position.1 = 0;
-        }
+    /// Folds and bracket matching for the file, when it is highlighted
+    pub fn syntax(&self) -> Option<&SyntaxHighlighter> {
+        self.syntax.as_ref()
     }
 
-    fn yank_with_motion(&mut self, _motion: crate::vim::Motion) {
-        // Simplified yank - just copy current line
-        let line = self.buffer.get_line(self.cursor_position.0).to_string();
-        self.vim_state.set_register('0', line);
+    pub fn set_syntax_highlights(&mut self, highlights: HashMap<usize, Vec<Highlight>>) {
+        self.syntax_highlights = highlights;
+        self.dirty = true;
     }
 
-    fn paste(&mut self) {
-        if let Some(content) = self.vim_state.get_register('0') {
-            self.buffer.insert_line(self.cursor_position.0 + 1, content);
-            self.cursor_position.0 += 1;
-            self.cursor_position.1 = 0;
+    /// Re-highlight what the edits since the last refresh changed
+    fn refresh_syntax(&mut self) {
+        let Some(syntax) = &mut self.syntax else {
+            return;
+        };
+        let line_count = self.syntax_highlights.len();
+        let changed = syntax.update(self.editor.buffer());
+        if changed.is_empty() {
+            return;
         }
+        let syntax = self.syntax.as_ref().expect("checked above");
+        // Lines added or removed move every line after them
+        let lines: Vec<usize> = if syntax.highlights().len() == line_count {
+            changed.into_iter().flatten().collect()
+        } else {
+            (0..syntax.highlights().len()).collect()
+        };
+        let mut highlights = std::mem::take(&mut self.syntax_highlights);
+        highlights.retain(|line, _| *line < syntax.highlights().len());
+        for line in lines {
+            highlights.insert(line, self.line_highlights(syntax.line_highlights(line)));
+        }
+        self.set_syntax_highlights(highlights);
+    }
+
+    fn line_highlights(&self, spans: &[HighlightSpan]) -> Vec<Highlight> {
+        let colors = &self.theme.colors;
+        spans.iter()
+            .map(|span| Highlight {
+                start_col: span.start,
+                end_col: span.end,
+                color: span.kind.theme_tokens().iter()
+                    .find_map(|token| colors.get(token))
+                    .unwrap_or(colors.text),
+                style: match span.kind {
+                    TokenKind::Heading | TokenKind::Strong => HighlightStyle::Bold,
+                    TokenKind::Emphasis | TokenKind::Comment => HighlightStyle::Italic,
+                    TokenKind::Link => HighlightStyle::Underline,
+                    _ => HighlightStyle::Normal,
+                },
+            })
+            .collect()
     }
 
     fn ensure_cursor_visible(&mut self) {
-        let cursor_y = self.cursor_position.0 as f32 * self.line_height;
-        let cursor_x = self.cursor_position.1 as f32 * self.char_width + self.line_number_width;
+        let (line, column) = self.editor.cursor_position();
+        let cursor_y = line as f32 * self.line_height;
+        let cursor_x = column as f32 * self.char_width + self.line_number_width;
         
         // Vertical scrolling
         if cursor_y < self.scroll_offset.y {
@@ -256,40 +197,14 @@ impl EditorView {
     }
 
     fn handle_key_input(&mut self, key: Key, modifiers: Modifiers) -> InputResult {
-        match self.vim_state.mode() {
-            VimMode::Normal => {
-                if let Some(command) = self.vim_state.handle_normal_key(key) {
-                    self.handle_vim_command(command);
-                }
-            }
-            VimMode::Insert => {
-                if key == Key::Escape {
-                    self.vim_state.set_mode(VimMode::Normal);
-                } else if let Some(ch) = key_to_char(key) {
-                    self.buffer.insert_char(self.cursor_position.0, self.cursor_position.1, ch);
-                    self.cursor_position.1 += 1;
-                } else if key == Key::Backspace {
-                    if self.cursor_position.1 > 0 {
-                        self.cursor_position.1 -= 1;
-                        self.buffer.delete_char(self.cursor_position.0, self.cursor_position.1);
-                    }
-                } else if key == Key::Enter {
-                    self.buffer.split_line(self.cursor_position.0, self.cursor_position.1);
-                    self.cursor_position.0 += 1;
-                    self.cursor_position.1 = 0;
-                }
-                self.dirty = true;
-            }
-            VimMode::Visual => {
-                if key == Key::Escape {
-                    self.vim_state.set_mode(VimMode::Normal);
-                    self.selection = None;
-                } else if let Some(command) = self.vim_state.handle_visual_key(key) {
-                    self.handle_vim_command(command);
-                }
-            }
-            _ => {}
-        }
+        let Some(ch) = key_to_char(key, modifiers) else {
+            return InputResult { handled: EventHandled::No, request_focus: false };
+        };
+        let actions = self.editor.process_key(ch);
+        self.actions.extend(actions);
+        self.refresh_syntax();
+        self.ensure_cursor_visible();
+        self.dirty = true;
         
         InputResult { handled: EventHandled::Yes, request_focus: true }
     }
@@ -310,11 +225,11 @@ impl EditorView {
         let start_line = (self.scroll_offset.y / self.line_height) as usize;
         let end_line = ((self.scroll_offset.y + self.size.y) / self.line_height) as usize + 1;
         
-        for line in start_line..end_line.min(self.buffer.line_count()) {
+        for line in start_line..end_line.min(self.editor.buffer().line_count()) {
             let y = line as f32 * self.line_height - self.scroll_offset.y;
             
             // Highlight current line number
-            if line == self.cursor_position.0 {
+            if line == self.editor.cursor_position().0 {
                 data.add_quad(
                     Vector2::new(self.position.x, self.position.y + y),
                     Vector2::new(self.line_number_width, self.line_height),
@@ -328,13 +243,17 @@ impl EditorView {
         let start_line = (self.scroll_offset.y / self.line_height) as usize;
         let end_line = ((self.scroll_offset.y + self.size.y) / self.line_height) as usize + 1;
         
-        for line in start_line..end_line.min(self.buffer.line_count()) {
+        let buffer = self.editor.buffer();
+        let selected_lines = self.editor.selection().map(|range| {
+            buffer.char_to_line(range.start)..=buffer.char_to_line(range.end.saturating_sub(1).max(range.start))
+        });
+        
+        for line in start_line..end_line.min(buffer.line_count()) {
             let y = line as f32 * self.line_height - self.scroll_offset.y;
             
             // Render selection if applicable
-            if let Some(ref selection) = self.selection {
-                if line >= selection.start.0.min(selection.end.0) &&
-                   line <= selection.start.0.max(selection.end.0) {
+            if let Some(ref lines) = selected_lines {
+                if lines.contains(&line) {
                     data.add_quad(
                         Vector2::new(
                             self.position.x + self.line_number_width,
@@ -349,22 +268,40 @@ impl EditorView {
             // Text would be rendered here with proper font rendering
             // For now, we'll just skip actual text rendering
         }
+
+        // Outline the bracket paired with the one under the cursor
+        let bracket = self.syntax.as_ref()
+            .and_then(|syntax| syntax.matching_bracket(buffer, self.editor.cursor()));
+        if let Some(bracket) = bracket {
+            let (line, column) = buffer.char_to_position(bracket);
+            if (start_line..end_line).contains(&line) {
+                data.add_quad(
+                    Vector2::new(
+                        self.position.x + self.line_number_width + column as f32 * self.char_width - self.scroll_offset.x,
+                        self.position.y + line as f32 * self.line_height - self.scroll_offset.y,
+                    ),
+                    Vector2::new(self.char_width, self.line_height),
+                    self.theme.colors.highlight,
+                );
+            }
+        }
     }
 
     fn render_cursor(&self, data: &mut RenderData) {
+        let (line, column) = self.editor.cursor_position();
         let cursor_x = self.position.x + self.line_number_width + 
-                      (self.cursor_position.1 as f32 * self.char_width) - self.scroll_offset.x;
+                      (column as f32 * self.char_width) - self.scroll_offset.x;
         let cursor_y = self.position.y + 
-                      (self.cursor_position.0 as f32 * self.line_height) - self.scroll_offset.y;
+                      (line as f32 * self.line_height) - self.scroll_offset.y;
         
-        let cursor_color = match self.vim_state.mode() {
+        let cursor_color = match self.editor.mode() {
             VimMode::Normal => Vector4::new(1.0, 1.0, 1.0, 1.0),
             VimMode::Insert => Vector4::new(0.0, 1.0, 0.0, 1.0),
             VimMode::Visual => Vector4::new(0.0, 0.5, 1.0, 1.0),
             _ => Vector4::new(0.5, 0.5, 0.5, 1.0),
         };
         
-        let cursor_width = match self.vim_state.mode() {
+        let cursor_width = match self.editor.mode() {
             VimMode::Insert => 2.0,
             _ => self.char_width,
         };
@@ -399,7 +336,7 @@ impl Element for EditorView {
             InputEvent::Scroll { delta, .. } => {
                 self.scroll_offset.y = (self.scroll_offset.y - delta.y * 20.0)
                     .max(0.0)
-                    .min((self.buffer.line_count() as f32 * self.line_height - self.size.y).max(0.0));
+                    .min((self.editor.buffer().line_count() as f32 * self.line_height - self.size.y).max(0.0));
                 self.dirty = true;
                 InputResult { handled: EventHandled::Yes, request_focus: false }
             }
@@ -478,35 +415,60 @@ impl Element for EditorView {
     }
 }
 
-fn key_to_char(key: Key) -> Option<char> {
-    match key {
-        Key::A => Some('a'),
-        Key::B => Some('b'),
-        Key::C => Some('c'),
-        Key::D => Some('d'),
-        Key::E => Some('e'),
-        Key::F => Some('f'),
-        Key::G => Some('g'),
-        Key::H => Some('h'),
-        Key::I => Some('i'),
-        Key::J => Some('j'),
-        Key::K => Some('k'),
-        Key::L => Some('l'),
-        Key::M => Some('m'),
-        Key::N => Some('n'),
-        Key::O => Some('o'),
-        Key::P => Some('p'),
-        Key::Q => Some('q'),
-        Key::R => Some('r'),
-        Key::S => Some('s'),
-        Key::T => Some('t'),
-        Key::U => Some('u'),
-        Key::V => Some('v'),
-        Key::W => Some('w'),
-        Key::X => Some('x'),
-        Key::Y => Some('y'),
-        Key::Z => Some('z'),
-        Key::Space => Some(' '),
-        _ => None,
-    }
-}
\ No newline at end of file
+/// The character Vim sees for a key: Escape, Enter and Backspace as their
+/// control characters, Ctrl-R for redo
+fn key_to_char(key: Key, modifiers: Modifiers) -> Option<char> {
+    let letter = match key {
+        Key::A => 'a',
+        Key::B => 'b',
+        Key::C => 'c',
+        Key::D => 'd',
+        Key::E => 'e',
+        Key::F => 'f',
+        Key::G => 'g',
+        Key::H => 'h',
+        Key::I => 'i',
+        Key::J => 'j',
+        Key::K => 'k',
+        Key::L => 'l',
+        Key::M => 'm',
+        Key::N => 'n',
+        Key::O => 'o',
+        Key::P => 'p',
+        Key::Q => 'q',
+        Key::R => 'r',
+        Key::S => 's',
+        Key::T => 't',
+        Key::U => 'u',
+        Key::V => 'v',
+        Key::W => 'w',
+        Key::X => 'x',
+        Key::Y => 'y',
+        Key::Z => 'z',
+        // Counts, and `$` on a US layout
+        Key::Num4 if modifiers.shift => return Some('$'),
+        Key::Num0 => return Some('0'),
+        Key::Num1 => return Some('1'),
+        Key::Num2 => return Some('2'),
+        Key::Num3 => return Some('3'),
+        Key::Num4 => return Some('4'),
+        Key::Num5 => return Some('5'),
+        Key::Num6 => return Some('6'),
+        Key::Num7 => return Some('7'),
+        Key::Num8 => return Some('8'),
+        Key::Num9 => return Some('9'),
+        Key::Space => return Some(' '),
+        Key::Escape => return Some('\x1b'),
+        Key::Enter => return Some('\n'),
+        Key::Backspace => return Some('\x08'),
+        Key::Tab => return Some('\t'),
+        _ => return None,
+    };
+    if modifiers.control && letter == 'r' {
+        Some('\x12')
+    } else if modifiers.shift {
+        Some(letter.to_ascii_uppercase())
+    } else {
+        Some(letter)
+    }
+}
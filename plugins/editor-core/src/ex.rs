@@ -0,0 +1,255 @@
+use regex::{Regex, RegexBuilder};
+
+/// A line in an ex range, before it is resolved against the buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Address {
+    /// 1-based line number
+    Line(usize),
+    /// `.`
+    Current,
+    /// `$`
+    Last,
+    /// `'x`, including `'<` and `'>` from the last visual selection
+    Mark(char),
+}
+
+/// Lines an ex command applies to, inclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineRange {
+    pub start: Address,
+    pub end: Address,
+}
+
+impl LineRange {
+    pub fn current() -> Self {
+        Self { start: Address::Current, end: Address::Current }
+    }
+
+    pub fn whole_file() -> Self {
+        Self { start: Address::Line(1), end: Address::Last }
+    }
+}
+
+/// A parsed `:` command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExCommand {
+    /// `:{n}`, `:$`
+    GoTo(Address),
+    /// `:w [path]`, `:wq`, `:x`
+    Write { path: Option<String>, quit: bool },
+    /// `:q`, `:q!`
+    Quit { force: bool },
+    /// `:[range]s/pattern/replacement/[flags]`
+    Substitute { range: LineRange, substitution: Substitution },
+    /// `:[range]d [x]`
+    Delete { range: LineRange, register: Option<char> },
+    /// `:[range]y [x]`
+    Yank { range: LineRange, register: Option<char> },
+    Undo,
+    Redo,
+}
+
+/// The pattern and replacement of a `:s` command
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    regex: Regex,
+    /// In `regex` replacement syntax
+    replacement: String,
+    global: bool,
+}
+
+impl PartialEq for Substitution {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str()
+            && self.replacement == other.replacement
+            && self.global == other.global
+    }
+}
+
+impl Substitution {
+    /// The pattern as compiled
+    pub fn pattern(&self) -> &str {
+        self.regex.as_str()
+    }
+
+    /// `line` with the pattern replaced, and how many matches were
+    /// replaced. None when nothing matched.
+    pub fn apply(&self, line: &str) -> Option<(String, usize)> {
+        if !self.regex.is_match(line) {
+            return None;
+        }
+        let matches = if self.global { self.regex.find_iter(line).count() } else { 1 };
+        let limit = if self.global { 0 } else { 1 };
+        let replaced = self.regex.replacen(line, limit, self.replacement.as_str()).into_owned();
+        Some((replaced, matches))
+    }
+}
+
+/// Parse a command line as typed after `:`
+pub fn parse(input: &str) -> Result<ExCommand, String> {
+    let input = input.trim();
+    let (range, rest) = parse_range(input)?;
+    let rest = rest.trim_start();
+
+    // A bare address jumps to it
+    if rest.is_empty() {
+        return match range {
+            Some(range) => Ok(ExCommand::GoTo(range.end)),
+            None => Err("Empty command".to_string()),
+        };
+    }
+
+    let name_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    let (name, args) = rest.split_at(name_len);
+    let (name, bang, args) = match args.strip_prefix('!') {
+        Some(args) => (name, true, args.trim()),
+        None => (name, false, args),
+    };
+    let argument = || {
+        let args = args.trim();
+        (!args.is_empty()).then(|| args.to_string())
+    };
+    let register = || args.trim().chars().next();
+    let range = range.unwrap_or_else(LineRange::current);
+
+    match name {
+        "w" | "write" => Ok(ExCommand::Write { path: argument(), quit: false }),
+        "wq" | "x" | "xit" => Ok(ExCommand::Write { path: argument(), quit: true }),
+        "q" | "quit" => Ok(ExCommand::Quit { force: bang }),
+        "d" | "delete" => Ok(ExCommand::Delete { range, register: register() }),
+        "y" | "yank" => Ok(ExCommand::Yank { range, register: register() }),
+        "u" | "undo" => Ok(ExCommand::Undo),
+        "red" | "redo" => Ok(ExCommand::Redo),
+        "s" | "substitute" if !args.is_empty() => {
+            Ok(ExCommand::Substitute { range, substitution: parse_substitution(args)? })
+        }
+        _ => Err(format!("Not an editor command: {}", input)),
+    }
+}
+
+fn parse_range(input: &str) -> Result<(Option<LineRange>, &str), String> {
+    if let Some(rest) = input.strip_prefix('%') {
+        return Ok((Some(LineRange::whole_file()), rest));
+    }
+    let (start, rest) = match parse_address(input)? {
+        Some(parsed) => parsed,
+        None => return Ok((None, input)),
+    };
+    match rest.strip_prefix(',') {
+        Some(rest) => match parse_address(rest)? {
+            Some((end, rest)) => Ok((Some(LineRange { start, end }), rest)),
+            None => Err("Missing end of range".to_string()),
+        },
+        None => Ok((Some(LineRange { start, end: start }), rest)),
+    }
+}
+
+fn parse_address(input: &str) -> Result<Option<(Address, &str)>, String> {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some('.') => Ok(Some((Address::Current, chars.as_str()))),
+        Some('$') => Ok(Some((Address::Last, chars.as_str()))),
+        Some('\'') => match chars.next() {
+            Some(mark) => Ok(Some((Address::Mark(mark), chars.as_str()))),
+            None => Err("Missing mark name".to_string()),
+        },
+        Some(c) if c.is_ascii_digit() => {
+            let digits = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+            let line = input[..digits].parse().map_err(|_| "Invalid line number".to_string())?;
+            Ok(Some((Address::Line(line), &input[digits..])))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// `/pattern/replacement/flags`, with any punctuation as the delimiter
+fn parse_substitution(args: &str) -> Result<Substitution, String> {
+    let mut chars = args.chars();
+    let delimiter = chars.next().filter(|c| !c.is_alphanumeric() && *c != '\\' && *c != ' ')
+        .ok_or_else(|| "Invalid substitute delimiter".to_string())?;
+    let (pattern, rest) = split_delimited(chars.as_str(), delimiter);
+    let (replacement, flags) = match rest {
+        Some(rest) => {
+            let (replacement, flags) = split_delimited(rest, delimiter);
+            (replacement, flags.unwrap_or(""))
+        }
+        None => (String::new(), ""),
+    };
+    if pattern.is_empty() {
+        return Err("Empty search pattern".to_string());
+    }
+
+    let mut global = false;
+    let mut ignore_case = false;
+    for flag in flags.trim().chars() {
+        match flag {
+            'g' => global = true,
+            'i' => ignore_case = true,
+            'I' => ignore_case = false,
+            // Confirmation is left to the caller's UI
+            'c' => {}
+            other => return Err(format!("Unknown substitute flag: {}", other)),
+        }
+    }
+
+    let regex = RegexBuilder::new(&translate_pattern(&pattern))
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))?;
+    Ok(Substitution { regex, replacement: translate_replacement(&replacement), global })
+}
+
+/// Text up to an unescaped `delimiter`, with `\delimiter` unescaped, and
+/// what follows the delimiter
+fn split_delimited(input: &str, delimiter: char) -> (String, Option<&str>) {
+    let mut text = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == delimiter {
+            return (text, Some(&input[i + c.len_utf8()..]));
+        }
+        if c == '\\' {
+            match chars.next() {
+                Some((_, next)) if next == delimiter => text.push(next),
+                Some((_, next)) => {
+                    text.push('\\');
+                    text.push(next);
+                }
+                None => text.push('\\'),
+            }
+        } else {
+            text.push(c);
+        }
+    }
+    (text, None)
+}
+
+/// Patterns are `regex` syntax, plus vim's `\<` and `\>` word boundaries
+pub(crate) fn translate_pattern(pattern: &str) -> String {
+    pattern.replace("\\<", "\\b").replace("\\>", "\\b")
+}
+
+/// Vim replacement syntax (`&`, `\1`, `\n`) to `regex` syntax
+fn translate_replacement(replacement: &str) -> String {
+    let mut out = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => out.push_str("${0}"),
+            '$' => out.push_str("$$"),
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => {
+                    out.push_str("${");
+                    out.push(digit);
+                    out.push('}');
+                }
+                Some('n') | Some('r') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
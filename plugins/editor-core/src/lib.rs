@@ -2,10 +2,23 @@ mod plugin;
 mod state;
 mod buffer;
 mod vim;
+mod motion;
+mod ex;
+mod editor;
+mod search;
+mod project;
+mod packets;
+mod syntax;
+mod lsp;
 // mod editor_view; // TODO: Update to use new UI APIs
 
 pub use plugin::EditorCorePlugin;
 pub use state::{EditorState, OpenFile, CursorPosition};
-pub use buffer::TextBuffer;
-pub use vim::{VimState, VimMode, VimCommand, Direction, Motion};
+pub use buffer::{TextBuffer, TextChange, Position};
+pub use vim::{VimState, VimMode, VimCommand, Direction, Motion, TextObject, TextObjectKind};
+pub use editor::{Editor, EditorAction};
+pub use ex::{ExCommand, Address, LineRange, Substitution};
+pub use search::{SearchOptions, SearchQuery};
+pub use project::{ProjectSearch, ProjectSearchResults, FileMatches, LineMatch};
+pub use syntax::{SyntaxHighlighter, SyntaxLanguage, TokenKind, HighlightSpan, FoldRange};
 // pub use editor_view::EditorView;
\ No newline at end of file
@@ -0,0 +1,175 @@
+//! Conversions between the editor and the language server protocol. LSP
+//! columns count UTF-16 code units where the editor counts chars.
+
+use lsp_types::{
+    CompletionItem, CompletionTextEdit, Diagnostic, DiagnosticSeverity, DocumentChangeOperation, DocumentChanges,
+    Documentation, Hover, HoverContents, MarkedString, NumberOrString, OneOf, Position, Range as LspRange,
+    TextDocumentContentChangeEvent, TextEdit, Url, WorkspaceEdit,
+};
+use std::ops::Range;
+
+use crate::buffer::{TextBuffer, TextChange};
+use crate::packets::{CompletionItemMessage, DiagnosticMessage, PositionMessage, RangeMessage, Severity};
+
+pub fn lsp_position(buffer: &TextBuffer, char_index: usize) -> Position {
+    let position = buffer.position(char_index);
+    Position { line: position.line as u32, character: position.utf16_column as u32 }
+}
+
+pub fn char_index(buffer: &TextBuffer, position: Position) -> usize {
+    buffer.utf16_position_to_char(position.line as usize, position.character as usize)
+}
+
+/// Buffer changes as incremental LSP changes. Each change's start and old
+/// end are where the text was before it, as LSP expects.
+pub fn lsp_changes(changes: &[TextChange]) -> Vec<TextDocumentContentChangeEvent> {
+    changes.iter()
+        .map(|change| TextDocumentContentChangeEvent {
+            range: Some(LspRange {
+                start: Position { line: change.start.line as u32, character: change.start.utf16_column as u32 },
+                end: Position { line: change.old_end.line as u32, character: change.old_end.utf16_column as u32 },
+            }),
+            range_length: None,
+            text: change.text.clone(),
+        })
+        .collect()
+}
+
+/// The whole text, for when the changes are no longer kept
+pub fn full_text(buffer: &TextBuffer) -> TextDocumentContentChangeEvent {
+    TextDocumentContentChangeEvent { range: None, range_length: None, text: buffer.get_text() }
+}
+
+pub fn range_message(buffer: &TextBuffer, range: LspRange) -> RangeMessage {
+    let position = |position| {
+        let (line, column) = buffer.char_to_position(char_index(buffer, position));
+        PositionMessage { line, column }
+    };
+    RangeMessage { start: position(range.start), end: position(range.end) }
+}
+
+/// A range in text that is not open in the editor
+pub fn text_range_message(text: &str, range: LspRange) -> RangeMessage {
+    let position = |position: Position| {
+        let line = text.split('\n').nth(position.line as usize).unwrap_or_default();
+        PositionMessage { line: position.line as usize, column: utf16_to_column(line, position.character as usize) }
+    };
+    RangeMessage { start: position(range.start), end: position(range.end) }
+}
+
+/// Text edits as char ranges of the buffer they were made for
+pub fn buffer_edits(buffer: &TextBuffer, edits: &[TextEdit]) -> Vec<(Range<usize>, String)> {
+    edits.iter()
+        .map(|edit| {
+            let range = char_index(buffer, edit.range.start)..char_index(buffer, edit.range.end);
+            (range, edit.new_text.clone())
+        })
+        .collect()
+}
+
+/// Text edits applied to text that is not open in the editor
+pub fn apply_text_edits(text: &str, edits: &[TextEdit]) -> String {
+    let mut buffer = TextBuffer::from_string(text.to_string());
+    let mut edits = buffer_edits(&buffer, edits);
+    // Back to front, so earlier edits keep their offsets; inserts at the
+    // same place stay in the order given
+    edits.sort_by_key(|(range, _)| range.start);
+    for (range, new_text) in edits.into_iter().rev() {
+        buffer.replace(range, &new_text);
+    }
+    buffer.get_text()
+}
+
+/// The text edits of a workspace edit by file. Creating, renaming and
+/// deleting files is not supported; the count of such operations comes
+/// back so they can be reported.
+pub fn workspace_edits(edit: WorkspaceEdit) -> (Vec<(Url, Vec<TextEdit>)>, usize) {
+    let mut files: Vec<(Url, Vec<TextEdit>)> = edit.changes.unwrap_or_default().into_iter().collect();
+    let mut skipped = 0;
+    let document_edits = match edit.document_changes {
+        Some(DocumentChanges::Edits(edits)) => edits,
+        Some(DocumentChanges::Operations(operations)) => operations.into_iter()
+            .filter_map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => Some(edit),
+                DocumentChangeOperation::Op(_) => {
+                    skipped += 1;
+                    None
+                }
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    for edit in document_edits {
+        let edits = edit.edits.into_iter()
+            .map(|edit| match edit {
+                OneOf::Left(edit) => edit,
+                OneOf::Right(annotated) => annotated.text_edit,
+            })
+            .collect();
+        files.push((edit.text_document.uri, edits));
+    }
+    (files, skipped)
+}
+
+pub fn hover_text(hover: &Hover) -> String {
+    let marked = |marked: &MarkedString| match marked {
+        MarkedString::String(text) => text.clone(),
+        MarkedString::LanguageString(code) => format!("```{}\n{}\n```", code.language, code.value),
+    };
+    match &hover.contents {
+        HoverContents::Scalar(text) => marked(text),
+        HoverContents::Array(texts) => texts.iter().map(marked).collect::<Vec<_>>().join("\n\n"),
+        HoverContents::Markup(markup) => markup.value.clone(),
+    }
+}
+
+pub fn diagnostic_message(buffer: &TextBuffer, diagnostic: Diagnostic) -> DiagnosticMessage {
+    let severity = match diagnostic.severity {
+        Some(DiagnosticSeverity::WARNING) => Severity::Warning,
+        Some(DiagnosticSeverity::INFORMATION) => Severity::Information,
+        Some(DiagnosticSeverity::HINT) => Severity::Hint,
+        // Servers that leave it out mean errors
+        _ => Severity::Error,
+    };
+    DiagnosticMessage {
+        range: range_message(buffer, diagnostic.range),
+        severity,
+        message: diagnostic.message,
+        source: diagnostic.source,
+        code: diagnostic.code.map(|code| match code {
+            NumberOrString::Number(number) => number.to_string(),
+            NumberOrString::String(code) => code,
+        }),
+    }
+}
+
+pub fn completion_message(buffer: &TextBuffer, item: CompletionItem) -> CompletionItemMessage {
+    let (insert_text, range) = match item.text_edit {
+        Some(CompletionTextEdit::Edit(edit)) => (edit.new_text, Some(edit.range)),
+        Some(CompletionTextEdit::InsertAndReplace(edit)) => (edit.new_text, Some(edit.insert)),
+        None => (item.insert_text.unwrap_or_else(|| item.label.clone()), None),
+    };
+    CompletionItemMessage {
+        // Kinds print as their protocol names, such as `Function`
+        kind: item.kind.map(|kind| format!("{:?}", kind).to_lowercase()),
+        detail: item.detail,
+        documentation: item.documentation.map(|documentation| match documentation {
+            Documentation::String(text) => text,
+            Documentation::MarkupContent(markup) => markup.value,
+        }),
+        insert_text,
+        range: range.map(|range| range_message(buffer, range)),
+        label: item.label,
+    }
+}
+
+/// Char column of a UTF-16 column in a line, clamped to the line
+fn utf16_to_column(line: &str, utf16_column: usize) -> usize {
+    let mut units = 0;
+    line.trim_end_matches('\r').chars()
+        .take_while(|ch| {
+            units += ch.len_utf16();
+            units <= utf16_column
+        })
+        .count()
+}
@@ -0,0 +1,297 @@
+use std::ops::Range;
+
+use crate::buffer::TextBuffer;
+use crate::vim::{TextObject, TextObjectKind};
+
+/// Character classes words are split on: a word is a run of one class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+fn class_of(ch: char) -> CharClass {
+    if ch.is_whitespace() {
+        CharClass::Blank
+    } else if ch.is_alphanumeric() || ch == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+fn class_at(buffer: &TextBuffer, index: usize) -> CharClass {
+    buffer.char_at(index).map_or(CharClass::Blank, class_of)
+}
+
+/// Char index just past the text of `line`, before its line break
+pub fn line_end(buffer: &TextBuffer, line: usize) -> usize {
+    buffer.line_to_char(line) + buffer.line_length(line)
+}
+
+/// Char index of the first non-blank character of `line`
+pub fn first_non_blank(buffer: &TextBuffer, line: usize) -> usize {
+    let start = buffer.line_to_char(line);
+    let indent = buffer.get_line(line).chars().take_while(|c| *c == ' ' || *c == '\t').count();
+    start + indent.min(buffer.line_length(line))
+}
+
+/// `w`: start of the `count`th next word
+pub fn word_forward(buffer: &TextBuffer, from: usize, count: u32) -> usize {
+    let len = buffer.len_chars();
+    let mut index = from;
+    for _ in 0..count {
+        let class = class_at(buffer, index);
+        if class != CharClass::Blank {
+            while index < len && class_at(buffer, index) == class {
+                index += 1;
+            }
+        }
+        while index < len && class_at(buffer, index) == CharClass::Blank {
+            index += 1;
+        }
+    }
+    index.min(len)
+}
+
+/// `b`: start of the `count`th previous word
+pub fn word_backward(buffer: &TextBuffer, from: usize, count: u32) -> usize {
+    let mut index = from;
+    for _ in 0..count {
+        while index > 0 && class_at(buffer, index - 1) == CharClass::Blank {
+            index -= 1;
+        }
+        if index == 0 {
+            break;
+        }
+        let class = class_at(buffer, index - 1);
+        while index > 0 && class_at(buffer, index - 1) == class {
+            index -= 1;
+        }
+    }
+    index
+}
+
+/// `e`: last character of the `count`th next word end
+pub fn word_end(buffer: &TextBuffer, from: usize, count: u32) -> usize {
+    let len = buffer.len_chars();
+    if len == 0 {
+        return 0;
+    }
+    let mut index = from;
+    for _ in 0..count {
+        index += 1;
+        while index < len && class_at(buffer, index) == CharClass::Blank {
+            index += 1;
+        }
+        if index >= len {
+            return len - 1;
+        }
+        let class = class_at(buffer, index);
+        while index + 1 < len && class_at(buffer, index + 1) == class {
+            index += 1;
+        }
+    }
+    index
+}
+
+/// Range `dw` removes. Unlike the motion it stops at the end of the line
+/// the last word is on, so the line break survives.
+pub fn delete_word_range(buffer: &TextBuffer, from: usize, count: u32) -> Range<usize> {
+    let mut end = word_forward(buffer, from, count);
+    let start_line = buffer.char_to_line(from);
+    let end_line = buffer.char_to_line(end.min(buffer.len_chars()));
+    if end_line > start_line {
+        let line_start = buffer.line_to_char(end_line);
+        let only_blanks = buffer.slice(line_start..end).chars().all(char::is_whitespace);
+        if only_blanks {
+            end = line_end(buffer, end_line - 1);
+        }
+    }
+    from..end.max(from)
+}
+
+/// Range `cw` replaces: on a word it changes to the word's end, like `ce`
+/// without skipping to the next word
+pub fn change_word_range(buffer: &TextBuffer, from: usize, count: u32) -> Range<usize> {
+    let class = class_at(buffer, from);
+    if class == CharClass::Blank {
+        return delete_word_range(buffer, from, count);
+    }
+    let len = buffer.len_chars();
+    let mut end = from;
+    while end + 1 < len && class_at(buffer, end + 1) == class {
+        end += 1;
+    }
+    if count > 1 {
+        end = word_end(buffer, end, count - 1);
+    }
+    from..(end + 1).min(len)
+}
+
+/// Region a text object covers around `cursor`, and whether it is linewise.
+/// None when the cursor is not inside one.
+pub fn text_object(buffer: &TextBuffer, cursor: usize, object: TextObject) -> Option<(Range<usize>, bool)> {
+    match object.kind {
+        TextObjectKind::Word => word_object(buffer, cursor, object.inner).map(|r| (r, false)),
+        TextObjectKind::Paragraph => Some((paragraph_object(buffer, cursor, object.inner), true)),
+        TextObjectKind::Quote(quote) => quote_object(buffer, cursor, quote, object.inner).map(|r| (r, false)),
+        TextObjectKind::Bracket(open, close) => {
+            bracket_object(buffer, cursor, open, close, object.inner).map(|r| (r, false))
+        }
+    }
+}
+
+fn word_object(buffer: &TextBuffer, cursor: usize, inner: bool) -> Option<Range<usize>> {
+    let line = buffer.char_to_line(cursor);
+    let line_start = buffer.line_to_char(line);
+    let line_end = line_end(buffer, line);
+    if cursor >= line_end {
+        return None;
+    }
+
+    let class = class_at(buffer, cursor);
+    let mut start = cursor;
+    while start > line_start && class_at(buffer, start - 1) == class {
+        start -= 1;
+    }
+    let mut end = cursor + 1;
+    while end < line_end && class_at(buffer, end) == class {
+        end += 1;
+    }
+    if inner {
+        return Some(start..end);
+    }
+
+    // `aw` takes the blanks after the word, or before it at the line end
+    if class == CharClass::Blank {
+        let mut word_end = end;
+        if word_end < line_end {
+            let next = class_at(buffer, word_end);
+            while word_end < line_end && class_at(buffer, word_end) == next {
+                word_end += 1;
+            }
+        }
+        return Some(start..word_end);
+    }
+    let mut trailing = end;
+    while trailing < line_end && class_at(buffer, trailing) == CharClass::Blank {
+        trailing += 1;
+    }
+    if trailing > end {
+        return Some(start..trailing);
+    }
+    while start > line_start && class_at(buffer, start - 1) == CharClass::Blank {
+        start -= 1;
+    }
+    Some(start..end)
+}
+
+fn is_blank_line(buffer: &TextBuffer, line: usize) -> bool {
+    buffer.get_line(line).trim().is_empty()
+}
+
+fn paragraph_object(buffer: &TextBuffer, cursor: usize, inner: bool) -> Range<usize> {
+    let lines = buffer.line_count();
+    let line = buffer.char_to_line(cursor);
+    let blank = is_blank_line(buffer, line);
+
+    let mut first = line;
+    while first > 0 && is_blank_line(buffer, first - 1) == blank {
+        first -= 1;
+    }
+    let mut last = line;
+    while last + 1 < lines && is_blank_line(buffer, last + 1) == blank {
+        last += 1;
+    }
+    if !inner {
+        // `ap` also takes the blank lines that follow
+        while last + 1 < lines && is_blank_line(buffer, last + 1) != blank {
+            last += 1;
+        }
+    }
+    let end = if last + 1 < lines { buffer.line_to_char(last + 1) } else { buffer.len_chars() };
+    buffer.line_to_char(first)..end
+}
+
+fn quote_object(buffer: &TextBuffer, cursor: usize, quote: char, inner: bool) -> Option<Range<usize>> {
+    let line = buffer.char_to_line(cursor);
+    let line_start = buffer.line_to_char(line);
+    let text: Vec<char> = buffer.get_line(line).chars().collect();
+    let column = cursor - line_start;
+
+    let quotes: Vec<usize> = text.iter().enumerate()
+        .filter(|(i, c)| **c == quote && (*i == 0 || text[i - 1] != '\\'))
+        .map(|(i, _)| i)
+        .collect();
+    // The pair around the cursor, else the first pair after it
+    let pair = quotes.chunks_exact(2)
+        .find(|pair| pair[0] <= column && column <= pair[1])
+        .or_else(|| quotes.chunks_exact(2).find(|pair| pair[0] > column))?;
+
+    let (open, close) = (line_start + pair[0], line_start + pair[1]);
+    if inner {
+        Some(open + 1..close)
+    } else {
+        Some(open..close + 1)
+    }
+}
+
+fn bracket_object(buffer: &TextBuffer, cursor: usize, open: char, close: char, inner: bool) -> Option<Range<usize>> {
+    let len = buffer.len_chars();
+
+    // Unmatched open bracket at or before the cursor
+    let mut depth = 0;
+    let mut index = cursor.min(len.saturating_sub(1)) + 1;
+    let open_at = loop {
+        if index == 0 {
+            return None;
+        }
+        index -= 1;
+        match buffer.char_at(index) {
+            Some(c) if c == close && index != cursor => depth += 1,
+            Some(c) if c == open => {
+                if depth == 0 {
+                    break index;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    };
+
+    let mut depth = 0;
+    let mut index = open_at + 1;
+    let close_at = loop {
+        if index >= len {
+            return None;
+        }
+        match buffer.char_at(index) {
+            Some(c) if c == open => depth += 1,
+            Some(c) if c == close => {
+                if depth == 0 {
+                    break index;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+        index += 1;
+    };
+
+    if !inner {
+        return Some(open_at..close_at + 1);
+    }
+    // A block's contents leave the lines the brackets sit on alone
+    let mut start = open_at + 1;
+    let mut end = close_at;
+    if buffer.char_at(start) == Some('\n') && buffer.char_to_line(close_at) > buffer.char_to_line(open_at) {
+        start += 1;
+        let close_line_start = buffer.line_to_char(buffer.char_to_line(close_at));
+        if buffer.slice(close_line_start..close_at).trim().is_empty() {
+            end = close_line_start;
+        }
+    }
+    Some(start..end.max(start))
+}
@@ -0,0 +1,463 @@
+//! Packet types and messages on the editor-core channel. Messages are JSON.
+//! Files are named by the path they were opened with; lines and columns are
+//! 0-based and columns count chars.
+//!
+//! The LSP client plugin sends diagnostics and answers here, and takes open
+//! files and requests on its own channel. Those messages mirror the ones in
+//! the LSP client's packets module and use its paths and UTF-16 positions.
+
+use lsp_types::{
+    CompletionItem, Diagnostic, FormattingOptions, Hover, Location, Position, TextDocumentContentChangeEvent,
+    TextEdit, WorkspaceEdit,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::project::ProjectSearchResults;
+use crate::search::SearchOptions;
+use crate::syntax::{FoldRange, HighlightSpan, SyntaxLanguage};
+use crate::vim::VimMode;
+
+// Messages from browser to server (1-99)
+/// Open a file, reading it from disk when no content is given
+pub const PACKET_TYPE_OPEN: u16 = 1;
+pub const PACKET_TYPE_CLOSE: u16 = 2;
+/// Keys for the Vim editor, as the characters `Editor::process_key` takes
+pub const PACKET_TYPE_KEYS: u16 = 3;
+pub const PACKET_TYPE_ADD_CURSOR: u16 = 4;
+pub const PACKET_TYPE_ADD_CURSOR_AT_NEXT_MATCH: u16 = 5;
+pub const PACKET_TYPE_COLUMN_SELECT: u16 = 6;
+pub const PACKET_TYPE_CLEAR_CURSORS: u16 = 7;
+/// Sent as the pattern is typed, for incremental search
+pub const PACKET_TYPE_SEARCH: u16 = 8;
+pub const PACKET_TYPE_SEARCH_NEXT: u16 = 9;
+pub const PACKET_TYPE_REPLACE: u16 = 10;
+pub const PACKET_TYPE_PROJECT_SEARCH: u16 = 11;
+pub const PACKET_TYPE_PROJECT_REPLACE: u16 = 12;
+/// Sent on (re)connect to get the full state of every open file
+pub const PACKET_TYPE_ATTACH: u16 = 13;
+/// Language server requests, at the given position or the primary cursor
+pub const PACKET_TYPE_HOVER: u16 = 14;
+pub const PACKET_TYPE_COMPLETION: u16 = 15;
+pub const PACKET_TYPE_DEFINITION: u16 = 16;
+pub const PACKET_TYPE_REFERENCES: u16 = 17;
+pub const PACKET_TYPE_RENAME: u16 = 18;
+pub const PACKET_TYPE_FORMAT: u16 = 19;
+
+// Messages from server to browser (100-199)
+/// Text changes, cursors and mode of a file
+pub const PACKET_TYPE_BUFFER: u16 = 100;
+pub const PACKET_TYPE_SEARCH_RESULTS: u16 = 101;
+pub const PACKET_TYPE_PROJECT_RESULTS: u16 = 102;
+pub const PACKET_TYPE_PROJECT_REPLACED: u16 = 103;
+/// Messages and errors for the status line
+pub const PACKET_TYPE_STATUS: u16 = 104;
+pub const PACKET_TYPE_CLOSED: u16 = 105;
+/// Syntax highlights of changed lines, and code folds
+pub const PACKET_TYPE_HIGHLIGHTS: u16 = 106;
+/// A language server's diagnostics for a file, replacing the ones before
+pub const PACKET_TYPE_DIAGNOSTICS: u16 = 107;
+pub const PACKET_TYPE_HOVER_RESULT: u16 = 108;
+pub const PACKET_TYPE_COMPLETIONS: u16 = 109;
+/// Definitions or references
+pub const PACKET_TYPE_LOCATIONS: u16 = 110;
+
+// Messages to the LSP client plugin, on its channel (200-299)
+pub const PACKET_TYPE_LSP_OPEN: u16 = 200;
+pub const PACKET_TYPE_LSP_CHANGE: u16 = 201;
+pub const PACKET_TYPE_LSP_SAVE: u16 = 202;
+pub const PACKET_TYPE_LSP_CLOSE: u16 = 203;
+pub const PACKET_TYPE_LSP_REQUEST: u16 = 204;
+
+// Messages from the LSP client plugin (300-399)
+pub const PACKET_TYPE_LSP_DIAGNOSTICS: u16 = 300;
+pub const PACKET_TYPE_LSP_RESPONSE: u16 = 301;
+pub const PACKET_TYPE_LSP_ERROR: u16 = 302;
+pub const PACKET_TYPE_LSP_SERVER: u16 = 303;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionMessage {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RangeMessage {
+    pub start: PositionMessage,
+    pub end: PositionMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenMessage {
+    pub path: String,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// Close, clear cursors, add a cursor at the next match; closed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMessage {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeysMessage {
+    pub path: String,
+    pub keys: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddCursorMessage {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnSelectMessage {
+    pub path: String,
+    pub anchor: PositionMessage,
+    pub head: PositionMessage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchMessage {
+    pub path: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+    #[serde(default)]
+    pub backward: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchNextMessage {
+    pub path: String,
+    #[serde(default)]
+    pub backward: bool,
+}
+
+/// Replace the match under the cursor, or every match, of the last search
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplaceMessage {
+    pub path: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// With a replacement, results carry a preview of each changed line
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectSearchMessage {
+    pub pattern: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+    #[serde(default)]
+    pub replacement: Option<String>,
+}
+
+/// Apply a replace to one file from the project results
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectReplaceMessage {
+    /// Relative to the project root, as in the results
+    pub path: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub options: SearchOptions,
+    pub replacement: String,
+}
+
+/// Hover, completion, definition and references
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspPositionMessage {
+    pub path: String,
+    #[serde(default)]
+    pub position: Option<PositionMessage>,
+}
+
+/// Rename the symbol at a position across the project
+#[derive(Debug, Clone, Deserialize)]
+pub struct RenameMessage {
+    pub path: String,
+    #[serde(default)]
+    pub position: Option<PositionMessage>,
+    pub new_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatMessage {
+    pub path: String,
+    #[serde(default = "default_tab_size")]
+    pub tab_size: u32,
+    #[serde(default = "default_insert_spaces")]
+    pub insert_spaces: bool,
+}
+
+fn default_tab_size() -> u32 {
+    4
+}
+
+fn default_insert_spaces() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeMessage {
+    pub start: PositionMessage,
+    /// End of the replaced text, before the change
+    pub end: PositionMessage,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CursorMessage {
+    pub position: PositionMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selection: Option<RangeMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BufferMessage {
+    pub path: String,
+    pub version: u32,
+    /// The whole text, sent instead of changes when the browser has none
+    /// to apply them to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    pub changes: Vec<ChangeMessage>,
+    /// Primary cursor first
+    pub cursors: Vec<CursorMessage>,
+    pub mode: VimMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_line: Option<String>,
+    pub modified: bool,
+    /// The bracket paired with the one under the primary cursor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matching_bracket: Option<PositionMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LineHighlights {
+    pub line: usize,
+    pub spans: Vec<HighlightSpan>,
+}
+
+/// Sent after the buffer update of the same version, for files in a
+/// language with a grammar
+#[derive(Debug, Clone, Serialize)]
+pub struct HighlightsMessage {
+    pub path: String,
+    pub version: u32,
+    pub language: SyntaxLanguage,
+    /// Lines whose highlights changed. Other lines keep theirs, moving with
+    /// the lines added and removed by the buffer's changes.
+    pub lines: Vec<LineHighlights>,
+    pub folds: Vec<FoldRange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResultsMessage {
+    pub path: String,
+    pub matches: Vec<RangeMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectResultsMessage {
+    pub pattern: String,
+    #[serde(flatten)]
+    pub results: ProjectSearchResults,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectReplacedMessage {
+    pub path: String,
+    pub replaced: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusMessage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub message: String,
+    pub error: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticMessage {
+    pub range: RangeMessage,
+    pub severity: Severity,
+    pub message: String,
+    /// What reported it, such as `rustc` or `clippy`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsMessage {
+    pub path: String,
+    pub diagnostics: Vec<DiagnosticMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoverMessage {
+    pub path: String,
+    pub position: PositionMessage,
+    /// Markdown; None when there is nothing to show
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contents: Option<String>,
+    /// What the hover is about, such as the word under the position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<RangeMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionItemMessage {
+    pub label: String,
+    /// Such as `function` or `keyword`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Markdown or plain text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    pub insert_text: String,
+    /// Text the insert replaces; the word before the cursor when None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub range: Option<RangeMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionsMessage {
+    pub path: String,
+    pub position: PositionMessage,
+    pub items: Vec<CompletionItemMessage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationKind {
+    Definition,
+    References,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationMessage {
+    /// Relative to the project root when inside it, otherwise absolute
+    pub path: String,
+    pub range: RangeMessage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocationsMessage {
+    pub path: String,
+    pub kind: LocationKind,
+    pub locations: Vec<LocationMessage>,
+}
+
+/// Open a file in its language's server, by its path on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct LspOpenMessage {
+    pub path: PathBuf,
+    pub language: String,
+    pub text: String,
+}
+
+/// Changes in order, each in the coordinates the one before left
+#[derive(Debug, Clone, Serialize)]
+pub struct LspChangeMessage {
+    pub path: PathBuf,
+    pub changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+/// Save, close
+#[derive(Debug, Clone, Serialize)]
+pub struct LspFileMessage {
+    pub path: PathBuf,
+}
+
+/// A question about an open file; the answer carries `id`
+#[derive(Debug, Clone, Serialize)]
+pub struct LspRequestMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub request: LspRequest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LspRequest {
+    Hover { position: Position },
+    Completion { position: Position },
+    Definition { position: Position },
+    References { position: Position, include_declaration: bool },
+    Rename { position: Position, new_name: String },
+    Formatting { options: FormattingOptions },
+}
+
+/// A server's diagnostics for a file on disk, replacing the ones before
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspDiagnosticsMessage {
+    pub path: PathBuf,
+    pub version: Option<i32>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspResponseMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub response: LspResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "result", rename_all = "snake_case")]
+pub enum LspResponse {
+    Hover(Option<Hover>),
+    Completion(Vec<CompletionItem>),
+    /// Definitions and references
+    Locations(Vec<Location>),
+    /// Renames; empty when there is nothing to change
+    Edit(WorkspaceEdit),
+    /// Formatting, against the text as it was when asked
+    TextEdits(Vec<TextEdit>),
+}
+
+/// A request that failed or could not be sent
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspErrorMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// A language server and how it is doing
+#[derive(Debug, Clone, Deserialize)]
+pub struct LspServerMessage {
+    pub name: String,
+    pub language: String,
+    pub root: PathBuf,
+    pub status: LspServerStatus,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LspServerStatus {
+    Starting,
+    Running,
+    Restarting { exit_code: Option<i32>, attempt: u32 },
+    Failed { message: String },
+}
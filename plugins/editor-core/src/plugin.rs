@@ -1,12 +1,56 @@
 use async_trait::async_trait;
+use crate::buffer::TextBuffer;
+use crate::editor::{Editor, EditorAction};
+use crate::lsp;
+use crate::packets::*;
+use crate::project::{contained_path, ProjectSearch};
+use crate::search::SearchQuery;
 use crate::state::{EditorState, OpenFile, CursorPosition};
-use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle, handle, shared};
+use crate::syntax::SyntaxHighlighter;
+use lsp_types::FormattingOptions;
+use playground_core_types::Priority;
+use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle, LogLevel, handle, shared};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 // Note: Using SystemsManager logging instead of tracing
 
+/// An open file and what the browser has been sent of it
+struct Document {
+    editor: Editor,
+    /// Buffer version the browser has, None until it has the text
+    sent_version: Option<u32>,
+    /// Search pattern and buffer version the browser has matches for
+    sent_search: Option<(String, u32)>,
+    /// None for languages without a grammar
+    syntax: Option<SyntaxHighlighter>,
+    /// Buffer version the language server has, None until it has the file
+    lsp_version: Option<u32>,
+    /// The language server's last diagnostics, for browsers that attach
+    diagnostics: Vec<DiagnosticMessage>,
+}
+
+/// A language server request and what the browser asked for it with
+struct LspPending {
+    path: String,
+    packet_type: u16,
+    position: PositionMessage,
+    version: u32,
+}
+
 pub struct EditorCorePlugin {
     state: playground_systems_logic::Shared<EditorState>,
     channel_id: Option<u16>,
     systems_manager: Handle<SystemsManager>,
+    /// Open files by the path they were opened with
+    documents: HashMap<String, Document>,
+    /// Relative paths and project search start here
+    root_path: PathBuf,
+    /// The LSP client plugin's channel, when that plugin is registered
+    lsp_channel: Option<u16>,
+    lsp_requests: HashMap<u64, LspPending>,
+    next_lsp_id: u64,
 }
 
 impl EditorCorePlugin {
@@ -15,35 +59,51 @@ impl EditorCorePlugin {
             state: shared(EditorState::default()),
             channel_id: None,
             systems_manager,
+            documents: HashMap::new(),
+            root_path: PathBuf::from("."),
+            lsp_channel: None,
+            lsp_requests: HashMap::new(),
+            next_lsp_id: 0,
         }
     }
 
-    pub async fn open_file(&self, path: String, content: String) {
+    /// Directory project search covers, normally the file browser's root
+    pub fn with_root_path(mut self, root_path: PathBuf) -> Self {
+        self.root_path = root_path;
+        self
+    }
+
+    pub async fn open_file(&mut self, path: String, content: String) {
         let mut state = self.state.write().await;
-        
+
         if let Some(index) = state.open_files.iter().position(|f| f.path == path) {
             state.active_file = Some(index);
             return;
         }
-        
+
         let language = Self::detect_language(&path);
-        
+
+        self.documents.insert(path.clone(), Document::new(TextBuffer::with_path(path.clone(), content.clone())));
         state.open_files.push(OpenFile {
             path,
             content,
             language,
             modified: false,
         });
-        
+
         state.active_file = Some(state.open_files.len() - 1);
     }
 
-    pub async fn close_file(&self, path: &str) {
+    pub async fn close_file(&mut self, path: &str) {
         let mut state = self.state.write().await;
-        
+        let closed = self.documents.remove(path);
+        if let Some(Document { lsp_version: Some(_), .. }) = closed {
+            self.send_lsp(PACKET_TYPE_LSP_CLOSE, &LspFileMessage { path: self.root_path.join(path) }).await;
+        }
+
         if let Some(index) = state.open_files.iter().position(|f| f.path == path) {
             state.open_files.remove(index);
-            
+
             if let Some(active) = state.active_file {
                 if active >= state.open_files.len() && !state.open_files.is_empty() {
                     state.active_file = Some(state.open_files.len() - 1);
@@ -59,18 +119,29 @@ impl EditorCorePlugin {
         state.vim_mode = !state.vim_mode;
         let vim_status = state.vim_mode;
         drop(state);
-        self.systems_manager.log_component("plugins/editor-core", 
+        self.systems_manager.log_component("plugins/editor-core",
             playground_systems_logic::LogLevel::Info,
             format!("Vim mode: {}", vim_status)).await;
     }
 
-    pub async fn add_cursor(&self, line: usize, column: usize) {
+    /// Add a cursor to the active file
+    pub async fn add_cursor(&mut self, line: usize, column: usize) {
         let mut state = self.state.write().await;
-        state.cursors.push(CursorPosition { line, column });
+        let active = state.active_file.and_then(|index| state.open_files.get(index)).map(|file| file.path.clone());
+        match active.and_then(|path| self.documents.get_mut(&path)) {
+            Some(document) => {
+                document.editor.add_cursor(line, column);
+                state.cursors = Self::cursor_positions(&document.editor);
+            }
+            None => state.cursors.push(CursorPosition { line, column }),
+        }
     }
 
-    pub async fn clear_cursors(&self) {
+    pub async fn clear_cursors(&mut self) {
         let mut state = self.state.write().await;
+        for document in self.documents.values_mut() {
+            document.editor.clear_cursors();
+        }
         if !state.cursors.is_empty() {
             let primary = state.cursors[0].clone();
             state.cursors = vec![primary];
@@ -94,16 +165,796 @@ impl EditorCorePlugin {
             _ => "text".to_string(),
         }
     }
+
+    fn cursor_positions(editor: &Editor) -> Vec<CursorPosition> {
+        editor.cursors().into_iter()
+            .map(|(cursor, _)| {
+                let (line, column) = editor.buffer().char_to_position(cursor);
+                CursorPosition { line, column }
+            })
+            .collect()
+    }
+
+    /// Where an open document is on disk. Only paths `open` accepted are
+    /// open, so these are under the root.
+    fn resolve(&self, path: &str) -> PathBuf {
+        self.root_path.join(path)
+    }
+}
+
+impl Document {
+    fn new(buffer: TextBuffer) -> Self {
+        let syntax = SyntaxHighlighter::for_buffer(&buffer);
+        Self {
+            editor: Editor::new(buffer),
+            sent_version: None,
+            sent_search: None,
+            syntax,
+            lsp_version: None,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+// Channel protocol
+impl EditorCorePlugin {
+    async fn handle_packet(&mut self, packet_type: u16, data: Vec<u8>) {
+        match packet_type {
+            PACKET_TYPE_OPEN => {
+                if let Some(message) = self.decode::<OpenMessage>(packet_type, &data).await {
+                    self.open(message).await;
+                }
+            }
+            PACKET_TYPE_CLOSE => {
+                if let Some(message) = self.decode::<FileMessage>(packet_type, &data).await {
+                    self.close_file(&message.path).await;
+                    self.send(PACKET_TYPE_CLOSED, &message).await;
+                }
+            }
+            PACKET_TYPE_KEYS => {
+                if let Some(message) = self.decode::<KeysMessage>(packet_type, &data).await {
+                    let Some(document) = self.document(&message.path).await else {
+                        return;
+                    };
+                    let actions: Vec<EditorAction> = message.keys.chars()
+                        .flat_map(|key| document.editor.process_key(key))
+                        .collect();
+                    self.send_buffer(&message.path).await;
+                    self.send_search_results(&message.path, None).await;
+                    self.perform(&message.path, actions).await;
+                }
+            }
+            PACKET_TYPE_ADD_CURSOR => {
+                if let Some(message) = self.decode::<AddCursorMessage>(packet_type, &data).await {
+                    if let Some(document) = self.document(&message.path).await {
+                        document.editor.add_cursor(message.line, message.column);
+                        self.send_buffer(&message.path).await;
+                    }
+                }
+            }
+            PACKET_TYPE_ADD_CURSOR_AT_NEXT_MATCH => {
+                if let Some(message) = self.decode::<FileMessage>(packet_type, &data).await {
+                    if let Some(document) = self.document(&message.path).await {
+                        document.editor.add_cursor_at_next_match();
+                        self.send_buffer(&message.path).await;
+                    }
+                }
+            }
+            PACKET_TYPE_COLUMN_SELECT => {
+                if let Some(message) = self.decode::<ColumnSelectMessage>(packet_type, &data).await {
+                    if let Some(document) = self.document(&message.path).await {
+                        let (anchor, head) = (message.anchor, message.head);
+                        document.editor.column_select((anchor.line, anchor.column), (head.line, head.column));
+                        self.send_buffer(&message.path).await;
+                    }
+                }
+            }
+            PACKET_TYPE_CLEAR_CURSORS => {
+                if let Some(message) = self.decode::<FileMessage>(packet_type, &data).await {
+                    if let Some(document) = self.document(&message.path).await {
+                        document.editor.clear_cursors();
+                        self.send_buffer(&message.path).await;
+                    }
+                }
+            }
+            PACKET_TYPE_SEARCH => {
+                if let Some(message) = self.decode::<SearchMessage>(packet_type, &data).await {
+                    let Some(document) = self.document(&message.path).await else {
+                        return;
+                    };
+                    let result = document.editor.search(&message.pattern, message.options, !message.backward);
+                    self.send_buffer(&message.path).await;
+                    self.send_search_results(&message.path, result.err()).await;
+                }
+            }
+            PACKET_TYPE_SEARCH_NEXT => {
+                if let Some(message) = self.decode::<SearchNextMessage>(packet_type, &data).await {
+                    if let Some(document) = self.document(&message.path).await {
+                        document.editor.search_next(!message.backward);
+                        self.send_buffer(&message.path).await;
+                    }
+                }
+            }
+            PACKET_TYPE_REPLACE => {
+                if let Some(message) = self.decode::<ReplaceMessage>(packet_type, &data).await {
+                    let Some(document) = self.document(&message.path).await else {
+                        return;
+                    };
+                    if message.all {
+                        let replaced = document.editor.replace_all(&message.replacement);
+                        self.send_status(Some(&message.path), format!("{} replacements", replaced), false).await;
+                    } else {
+                        document.editor.replace_current(&message.replacement);
+                    }
+                    self.send_buffer(&message.path).await;
+                    self.send_search_results(&message.path, None).await;
+                }
+            }
+            PACKET_TYPE_PROJECT_SEARCH => {
+                if let Some(message) = self.decode::<ProjectSearchMessage>(packet_type, &data).await {
+                    self.project_search(message).await;
+                }
+            }
+            PACKET_TYPE_PROJECT_REPLACE => {
+                if let Some(message) = self.decode::<ProjectReplaceMessage>(packet_type, &data).await {
+                    self.project_replace(message).await;
+                }
+            }
+            PACKET_TYPE_ATTACH => {
+                let paths: Vec<String> = self.documents.keys().cloned().collect();
+                for path in paths {
+                    if let Some(document) = self.documents.get_mut(&path) {
+                        document.sent_version = None;
+                        document.sent_search = None;
+                    }
+                    self.send_buffer(&path).await;
+                    self.send_search_results(&path, None).await;
+                    self.send_diagnostics(&path).await;
+                }
+            }
+            PACKET_TYPE_HOVER | PACKET_TYPE_COMPLETION | PACKET_TYPE_DEFINITION | PACKET_TYPE_REFERENCES => {
+                if let Some(message) = self.decode::<LspPositionMessage>(packet_type, &data).await {
+                    self.lsp_request(packet_type, &message.path, message.position, |position| match packet_type {
+                        PACKET_TYPE_HOVER => LspRequest::Hover { position },
+                        PACKET_TYPE_COMPLETION => LspRequest::Completion { position },
+                        PACKET_TYPE_DEFINITION => LspRequest::Definition { position },
+                        _ => LspRequest::References { position, include_declaration: true },
+                    }).await;
+                }
+            }
+            PACKET_TYPE_RENAME => {
+                if let Some(message) = self.decode::<RenameMessage>(packet_type, &data).await {
+                    let new_name = message.new_name;
+                    self.lsp_request(packet_type, &message.path, message.position,
+                        |position| LspRequest::Rename { position, new_name }).await;
+                }
+            }
+            PACKET_TYPE_FORMAT => {
+                if let Some(message) = self.decode::<FormatMessage>(packet_type, &data).await {
+                    let options = FormattingOptions {
+                        tab_size: message.tab_size,
+                        insert_spaces: message.insert_spaces,
+                        ..FormattingOptions::default()
+                    };
+                    self.lsp_request(packet_type, &message.path, None, |_| LspRequest::Formatting { options }).await;
+                }
+            }
+            PACKET_TYPE_LSP_DIAGNOSTICS | PACKET_TYPE_LSP_RESPONSE | PACKET_TYPE_LSP_ERROR | PACKET_TYPE_LSP_SERVER => {
+                self.handle_lsp_packet(packet_type, data).await;
+            }
+            _ => self.log(LogLevel::Debug, format!("Unknown packet type {} received on editor-core channel", packet_type)).await,
+        }
+    }
+
+    async fn open(&mut self, message: OpenMessage) {
+        // Paths come from the browser, and an open document's path is later
+        // saved to and handed to language servers, so it stays under the root
+        let Some(file) = contained_path(&self.root_path, &message.path) else {
+            self.send_status(Some(&message.path), format!("Cannot open {}: outside the project", message.path), true).await;
+            return;
+        };
+        let content = match message.content {
+            Some(content) => content,
+            None => match tokio::fs::read_to_string(&file).await {
+                Ok(content) => content,
+                Err(e) => {
+                    self.send_status(Some(&message.path), format!("Cannot open {}: {}", message.path, e), true).await;
+                    return;
+                }
+            },
+        };
+        self.open_file(message.path.clone(), content).await;
+        // The browser may have closed its view of a file that stayed open
+        if let Some(document) = self.documents.get_mut(&message.path) {
+            document.sent_version = None;
+        }
+        self.send_buffer(&message.path).await;
+    }
+
+    /// Carry out what the editor asked for after a command
+    async fn perform(&mut self, path: &str, actions: Vec<EditorAction>) {
+        for action in actions {
+            match action {
+                EditorAction::Write { path: target } => {
+                    let Some(document) = self.documents.get_mut(path) else {
+                        return;
+                    };
+                    let target = target.unwrap_or_else(|| path.to_string());
+                    // `:w` names come from the browser, so they stay under the root
+                    let Some(destination) = contained_path(&self.root_path, &target) else {
+                        self.send_status(Some(path), format!("Cannot write {}: outside the project", target), true).await;
+                        return;
+                    };
+                    let text = document.editor.buffer().get_text();
+                    if let Err(e) = tokio::fs::write(&destination, &text).await {
+                        // Leave the file open when a `:wq` fails to write
+                        self.send_status(Some(path), format!("Cannot write {}: {}", target, e), true).await;
+                        return;
+                    }
+                    if target == path {
+                        document.editor.buffer_mut().mark_saved();
+                        if document.lsp_version.is_some() {
+                            self.send_lsp(PACKET_TYPE_LSP_SAVE, &LspFileMessage { path: self.root_path.join(path) }).await;
+                        }
+                    }
+                    let lines = text.lines().count();
+                    self.send_status(Some(path), format!("\"{}\" {}L, {}B written", target, lines, text.len()), false).await;
+                    self.send_buffer(path).await;
+                }
+                EditorAction::Quit { .. } => {
+                    // The editor already refused to quit with unsaved changes
+                    self.close_file(path).await;
+                    self.send(PACKET_TYPE_CLOSED, &FileMessage { path: path.to_string() }).await;
+                    return;
+                }
+                EditorAction::Message(message) => self.send_status(Some(path), message, false).await,
+                EditorAction::Error(message) => self.send_status(Some(path), message, true).await,
+            }
+        }
+    }
+
+    async fn project_search(&mut self, message: ProjectSearchMessage) {
+        let query = match SearchQuery::new(&message.pattern, message.options) {
+            Ok(query) => query,
+            Err(e) => {
+                self.send_status(None, e, true).await;
+                return;
+            }
+        };
+        // Unsaved edits are searched as they are in the editor
+        let open: HashMap<PathBuf, String> = self.documents.iter()
+            .filter(|(_, document)| document.editor.buffer().is_modified())
+            .map(|(path, document)| (self.resolve(path), document.editor.buffer().get_text()))
+            .collect();
+        let project = ProjectSearch::new(self.root_path.clone());
+        let replacement = message.replacement;
+        let searched = tokio::task::spawn_blocking(move || {
+            project.search(&query, replacement.as_deref(), |path| open.get(path).cloned())
+        }).await;
+
+        match searched {
+            Ok(results) => {
+                let matches: usize = results.files.iter().map(|file| file.matches.len()).sum();
+                self.log(LogLevel::Debug, format!("Project search for '{}': {} matches in {} files",
+                    message.pattern, matches, results.files.len())).await;
+                self.send(PACKET_TYPE_PROJECT_RESULTS, &ProjectResultsMessage { pattern: message.pattern, results }).await;
+            }
+            Err(e) => self.send_status(None, format!("Project search failed: {}", e), true).await,
+        }
+    }
+
+    /// Replace in one file of the project results, in the editor when it is
+    /// open so the change can be undone there, otherwise on disk
+    async fn project_replace(&mut self, message: ProjectReplaceMessage) {
+        let query = match SearchQuery::new(&message.pattern, message.options) {
+            Ok(query) => query,
+            Err(e) => {
+                self.send_status(None, e, true).await;
+                return;
+            }
+        };
+        let project = ProjectSearch::new(self.root_path.clone());
+        let target = project.resolve(&message.path);
+        let open = self.documents.keys().find(|path| self.resolve(path) == target).cloned();
+
+        let result = match &open {
+            Some(open) => {
+                let document = self.documents.get_mut(open).expect("document was just found");
+                Ok(document.editor.replace_matches(&query, &message.replacement))
+            }
+            None => {
+                let (path, replacement) = (message.path.clone(), message.replacement);
+                tokio::task::spawn_blocking(move || project.replace_in_file(&path, &query, &replacement))
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()))
+            }
+        };
+
+        match result {
+            Ok(replaced) => {
+                if let Some(open) = &open {
+                    self.send_buffer(open).await;
+                    self.send_search_results(open, None).await;
+                }
+                self.send(PACKET_TYPE_PROJECT_REPLACED, &ProjectReplacedMessage { path: message.path, replaced }).await;
+            }
+            Err(e) => self.send_status(None, e, true).await,
+        }
+    }
+
+    /// An open file, or an error to the browser
+    async fn document(&mut self, path: &str) -> Option<&mut Document> {
+        if !self.documents.contains_key(path) {
+            self.send_status(Some(path), format!("{} is not open", path), true).await;
+        }
+        self.documents.get_mut(path)
+    }
+
+    /// Changes since the last update, or the whole text, and the cursors,
+    /// followed by the highlights that changed with them
+    async fn send_buffer(&mut self, path: &str) {
+        self.sync_lsp(path).await;
+        let Some(document) = self.documents.get_mut(path) else {
+            return;
+        };
+        let highlighted = match &mut document.syntax {
+            Some(syntax) => syntax.update(document.editor.buffer()),
+            None => Vec::new(),
+        };
+        let editor = &document.editor;
+        let buffer = editor.buffer();
+        let changes = document.sent_version.and_then(|version| buffer.changes_since(version));
+        let (text, changes) = match changes {
+            Some(changes) => {
+                let changes = changes.iter()
+                    .map(|change| ChangeMessage {
+                        start: PositionMessage { line: change.start.line, column: change.start.column },
+                        end: PositionMessage { line: change.old_end.line, column: change.old_end.column },
+                        text: change.text.clone(),
+                    })
+                    .collect();
+                (None, changes)
+            }
+            None => (Some(buffer.get_text()), Vec::new()),
+        };
+        let cursors = editor.cursors().into_iter()
+            .map(|(cursor, selection)| CursorMessage {
+                position: position_message(editor, cursor),
+                selection: selection.map(|range| range_message(editor, range)),
+            })
+            .collect();
+        let message = BufferMessage {
+            path: path.to_string(),
+            version: buffer.version(),
+            text,
+            changes,
+            cursors,
+            mode: editor.mode(),
+            command_line: editor.command_line().map(str::to_string),
+            modified: buffer.is_modified(),
+            matching_bracket: document.syntax.as_ref()
+                .and_then(|syntax| syntax.matching_bracket(buffer, editor.cursor()))
+                .map(|bracket| position_message(editor, bracket)),
+        };
+
+        // A browser starting from the full text needs every line
+        let highlights = document.syntax.as_ref()
+            .filter(|_| message.text.is_some() || !highlighted.is_empty())
+            .map(|syntax| {
+                let lines = if message.text.is_some() { vec![0..buffer.line_count()] } else { highlighted };
+                HighlightsMessage {
+                    path: path.to_string(),
+                    version: message.version,
+                    language: syntax.language(),
+                    lines: lines.into_iter().flatten()
+                        .map(|line| LineHighlights { line, spans: syntax.line_highlights(line).to_vec() })
+                        .collect(),
+                    folds: syntax.folding_ranges(),
+                }
+            });
+        document.sent_version = Some(message.version);
+        self.send(PACKET_TYPE_BUFFER, &message).await;
+        if let Some(highlights) = highlights {
+            self.send(PACKET_TYPE_HIGHLIGHTS, &highlights).await;
+        }
+    }
+
+    /// Matches of the file's search when it or the text changed since they
+    /// were last sent, or with an error when the search failed
+    async fn send_search_results(&mut self, path: &str, error: Option<String>) {
+        let Some(document) = self.documents.get_mut(path) else {
+            return;
+        };
+        let editor = &document.editor;
+        let current = editor.search_pattern().map(|pattern| (pattern.to_string(), editor.buffer().version()));
+        if error.is_none() && current == document.sent_search {
+            return;
+        }
+        let matches = editor.search_matches().into_iter().map(|range| range_message(editor, range)).collect();
+        document.sent_search = current;
+        self.send(PACKET_TYPE_SEARCH_RESULTS, &SearchResultsMessage { path: path.to_string(), matches, error }).await;
+    }
+
+    async fn send_status(&self, path: Option<&str>, message: String, error: bool) {
+        let message = StatusMessage { path: path.map(str::to_string), message, error };
+        self.send(PACKET_TYPE_STATUS, &message).await;
+    }
+
+    async fn send<T: Serialize>(&self, packet_type: u16, message: &T) {
+        if let Some(channel_id) = self.channel_id {
+            self.send_on(channel_id, packet_type, message).await;
+        }
+    }
+
+    /// Requests go straight onto the LSP client's channel, without passing
+    /// through any browser
+    async fn send_lsp<T: Serialize>(&self, packet_type: u16, message: &T) {
+        let Some(channel_id) = self.lsp_channel else {
+            return;
+        };
+        let Some(data) = self.encode(packet_type, message).await else {
+            return;
+        };
+
+        let networking = self.systems_manager.networking();
+        let net = networking.read().await;
+        if let Err(e) = net.send_to_plugin("editor-core", channel_id, packet_type, data).await {
+            self.log(LogLevel::Warning, format!("Failed to send editor packet {} to lsp-client: {}", packet_type, e)).await;
+        }
+    }
+
+    async fn send_on<T: Serialize>(&self, channel_id: u16, packet_type: u16, message: &T) {
+        let Some(data) = self.encode(packet_type, message).await else {
+            return;
+        };
+
+        let networking = self.systems_manager.networking();
+        let net = networking.read().await;
+        if let Err(e) = net.send_packet(channel_id, packet_type, data, Priority::High).await {
+            self.log(LogLevel::Warning, format!("Failed to send editor packet {}: {}", packet_type, e)).await;
+        }
+    }
+
+    async fn encode<T: Serialize>(&self, packet_type: u16, message: &T) -> Option<Vec<u8>> {
+        match serde_json::to_vec(message) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                self.log(LogLevel::Warning, format!("Failed to serialize editor packet {}: {}", packet_type, e)).await;
+                None
+            }
+        }
+    }
+
+    async fn decode<T: DeserializeOwned>(&self, packet_type: u16, data: &[u8]) -> Option<T> {
+        match serde_json::from_slice(data) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                self.log(LogLevel::Warning, format!("Malformed editor packet {}: {}", packet_type, e)).await;
+                None
+            }
+        }
+    }
+
+    async fn log(&self, level: LogLevel, message: String) {
+        self.systems_manager.log_component("plugins/editor-core", level, message).await;
+    }
+}
+
+// Language servers
+impl EditorCorePlugin {
+    /// Give the language server the changes since it last had the file, or
+    /// open the file there
+    async fn sync_lsp(&mut self, path: &str) {
+        if self.lsp_channel.is_none() {
+            return;
+        }
+        let Some(document) = self.documents.get_mut(path) else {
+            return;
+        };
+        let buffer = document.editor.buffer();
+        let (text, changes) = match document.lsp_version {
+            None => (Some(buffer.get_text()), Vec::new()),
+            Some(version) if version == buffer.version() => return,
+            Some(version) => match buffer.changes_since(version) {
+                Some(changes) => (None, lsp::lsp_changes(changes)),
+                None => (None, vec![lsp::full_text(buffer)]),
+            },
+        };
+        document.lsp_version = Some(buffer.version());
+
+        let file = self.root_path.join(path);
+        match text {
+            Some(text) => {
+                let message = LspOpenMessage { path: file, language: Self::detect_language(path), text };
+                self.send_lsp(PACKET_TYPE_LSP_OPEN, &message).await;
+            }
+            None => self.send_lsp(PACKET_TYPE_LSP_CHANGE, &LspChangeMessage { path: file, changes }).await,
+        }
+    }
+
+    /// Ask the language server about a position in a file, the primary
+    /// cursor when the browser gave none
+    async fn lsp_request(
+        &mut self,
+        packet_type: u16,
+        path: &str,
+        position: Option<PositionMessage>,
+        request: impl FnOnce(lsp_types::Position) -> LspRequest,
+    ) {
+        if self.lsp_channel.is_none() {
+            self.send_status(Some(path), "No language servers are available".to_string(), true).await;
+            return;
+        }
+        if self.document(path).await.is_none() {
+            return;
+        }
+        self.sync_lsp(path).await;
+
+        let document = &self.documents[path];
+        let editor = &document.editor;
+        let cursor = match position {
+            Some(position) => editor.buffer().position_to_char(position.line, position.column),
+            None => editor.cursor(),
+        };
+        let pending = LspPending {
+            path: path.to_string(),
+            packet_type,
+            position: position_message(editor, cursor),
+            version: editor.buffer().version(),
+        };
+        let request = request(lsp::lsp_position(editor.buffer(), cursor));
+        let file = self.resolve(path);
+        self.next_lsp_id += 1;
+        let id = self.next_lsp_id;
+        self.lsp_requests.insert(id, pending);
+        self.send_lsp(PACKET_TYPE_LSP_REQUEST, &LspRequestMessage { id, path: file, request }).await;
+    }
+
+    /// Handle what a language server sent back through the LSP client
+    async fn handle_lsp_packet(&mut self, packet_type: u16, data: Vec<u8>) {
+        match packet_type {
+            PACKET_TYPE_LSP_DIAGNOSTICS => {
+                let Some(LspDiagnosticsMessage { path, diagnostics, .. }) = self.decode(packet_type, &data).await else {
+                    return;
+                };
+                let Some(open) = self.open_path(&path) else {
+                    return;
+                };
+                let document = self.documents.get_mut(&open).expect("path was just found");
+                let buffer = document.editor.buffer();
+                document.diagnostics = diagnostics.into_iter()
+                    .map(|diagnostic| lsp::diagnostic_message(buffer, diagnostic))
+                    .collect();
+                self.send_diagnostics(&open).await;
+            }
+            PACKET_TYPE_LSP_RESPONSE => {
+                if let Some(LspResponseMessage { id, response, .. }) = self.decode(packet_type, &data).await
+                    && let Some(pending) = self.lsp_requests.remove(&id)
+                {
+                    self.lsp_response(pending, response).await;
+                }
+            }
+            PACKET_TYPE_LSP_ERROR => {
+                if let Some(LspErrorMessage { id, message, .. }) = self.decode(packet_type, &data).await
+                    && let Some(pending) = self.lsp_requests.remove(&id)
+                {
+                    self.send_status(Some(&pending.path), message, true).await;
+                }
+            }
+            _ => {
+                let Some(server) = self.decode::<LspServerMessage>(packet_type, &data).await else {
+                    return;
+                };
+                match server.status {
+                    LspServerStatus::Restarting { .. } => {
+                        self.send_status(None, format!("{} stopped, restarting it", server.name), true).await;
+                    }
+                    LspServerStatus::Failed { message } => {
+                        self.send_status(None, format!("{}: {}", server.name, message), true).await;
+                    }
+                    status => {
+                        self.log(LogLevel::Debug, format!("{} for {}: {:?}", server.name, server.root.display(), status)).await;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn lsp_response(&mut self, pending: LspPending, response: LspResponse) {
+        let Some(document) = self.documents.get_mut(&pending.path) else {
+            return;
+        };
+        let buffer = document.editor.buffer();
+        // Edits are for the text as it was when they were asked for
+        let stale = buffer.version() != pending.version;
+        let path = pending.path.clone();
+        match response {
+            LspResponse::Hover(hover) => {
+                let message = HoverMessage {
+                    path,
+                    position: pending.position,
+                    contents: hover.as_ref().map(lsp::hover_text).filter(|text| !text.trim().is_empty()),
+                    range: hover.and_then(|hover| hover.range).map(|range| lsp::range_message(buffer, range)),
+                };
+                self.send(PACKET_TYPE_HOVER_RESULT, &message).await;
+            }
+            LspResponse::Completion(items) => {
+                let items = items.into_iter().map(|item| lsp::completion_message(buffer, item)).collect();
+                let message = CompletionsMessage { path, position: pending.position, items };
+                self.send(PACKET_TYPE_COMPLETIONS, &message).await;
+            }
+            LspResponse::Locations(locations) => {
+                let kind = if pending.packet_type == PACKET_TYPE_DEFINITION {
+                    LocationKind::Definition
+                } else {
+                    LocationKind::References
+                };
+                let mut messages = Vec::new();
+                for location in locations {
+                    if let Some(message) = self.location_message(location).await {
+                        messages.push(message);
+                    }
+                }
+                self.send(PACKET_TYPE_LOCATIONS, &LocationsMessage { path, kind, locations: messages }).await;
+            }
+            LspResponse::Edit(edit) => {
+                if stale {
+                    self.send_status(Some(&path), "The file changed before the rename came back".to_string(), true).await;
+                    return;
+                }
+                self.apply_workspace_edit(&path, edit).await;
+            }
+            LspResponse::TextEdits(edits) => {
+                if stale {
+                    self.send_status(Some(&path), "The file changed before formatting came back".to_string(), true).await;
+                    return;
+                }
+                let edits = lsp::buffer_edits(buffer, &edits);
+                document.editor.apply_edits(edits);
+                self.send_buffer(&path).await;
+                self.send_search_results(&path, None).await;
+            }
+        }
+    }
+
+    /// Apply a rename: to open files in the editor, where it can be undone,
+    /// and to other files on disk
+    async fn apply_workspace_edit(&mut self, path: &str, edit: lsp_types::WorkspaceEdit) {
+        let (files, skipped) = lsp::workspace_edits(edit);
+        if files.is_empty() && skipped == 0 {
+            self.send_status(Some(path), "Nothing to rename".to_string(), false).await;
+            return;
+        }
+        let mut changed = 0;
+        for (uri, edits) in files {
+            let Ok(file) = uri.to_file_path() else {
+                self.send_status(Some(path), format!("Cannot edit {}", uri), true).await;
+                continue;
+            };
+            match self.open_path(&file) {
+                Some(open) => {
+                    let document = self.documents.get_mut(&open).expect("path was just found");
+                    let edits = lsp::buffer_edits(document.editor.buffer(), &edits);
+                    document.editor.apply_edits(edits);
+                    self.send_buffer(&open).await;
+                    self.send_search_results(&open, None).await;
+                }
+                None => {
+                    let written = match tokio::fs::read_to_string(&file).await {
+                        Ok(text) => tokio::fs::write(&file, lsp::apply_text_edits(&text, &edits)).await,
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = written {
+                        let message = format!("Cannot edit {}: {}", self.display_path(&file), e);
+                        self.send_status(Some(path), message, true).await;
+                        continue;
+                    }
+                }
+            }
+            changed += 1;
+        }
+        let mut message = format!("Renamed in {} files", changed);
+        if skipped > 0 {
+            message.push_str(&format!(", skipped {} file operations", skipped));
+        }
+        self.send_status(Some(path), message, skipped > 0).await;
+    }
+
+    /// A definition or reference, with columns in chars. Files that are not
+    /// open are read to count them.
+    async fn location_message(&self, location: lsp_types::Location) -> Option<LocationMessage> {
+        let file = location.uri.to_file_path().ok()?;
+        let range = match self.open_path(&file) {
+            Some(open) => lsp::range_message(self.documents[&open].editor.buffer(), location.range),
+            None => match tokio::fs::read_to_string(&file).await {
+                Ok(text) => lsp::text_range_message(&text, location.range),
+                Err(_) => lsp::text_range_message("", location.range),
+            },
+        };
+        Some(LocationMessage { path: self.display_path(&file), range })
+    }
+
+    async fn send_diagnostics(&self, path: &str) {
+        let Some(document) = self.documents.get(path) else {
+            return;
+        };
+        if document.lsp_version.is_none() {
+            return;
+        }
+        let message = DiagnosticsMessage { path: path.to_string(), diagnostics: document.diagnostics.clone() };
+        self.send(PACKET_TYPE_DIAGNOSTICS, &message).await;
+    }
+
+    /// The open file at an absolute path from a language server
+    fn open_path(&self, file: &Path) -> Option<String> {
+        self.documents.keys().find(|path| absolute(&self.resolve(path)) == file).cloned()
+    }
+
+    /// A path for the browser: relative to the project root when inside
+    /// it, with `/` separators
+    fn display_path(&self, file: &Path) -> String {
+        match file.strip_prefix(absolute(&self.root_path)) {
+            Ok(relative) => relative.components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            Err(_) => file.display().to_string(),
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn position_message(editor: &Editor, char_index: usize) -> PositionMessage {
+    let (line, column) = editor.buffer().char_to_position(char_index);
+    PositionMessage { line, column }
+}
+
+fn range_message(editor: &Editor, range: Range<usize>) -> RangeMessage {
+    RangeMessage { start: position_message(editor, range.start), end: position_message(editor, range.end) }
 }
 
 
 // State management methods
 impl EditorCorePlugin {
     pub async fn save_state(&self) -> EditorState {
-        self.state.read().await.clone()
+        let mut state = self.state.read().await.clone();
+        // The editors hold the current text and cursors
+        for file in &mut state.open_files {
+            if let Some(document) = self.documents.get(&file.path) {
+                file.content = document.editor.buffer().get_text();
+                file.modified = document.editor.buffer().is_modified();
+            }
+        }
+        let active = state.active_file.and_then(|index| state.open_files.get(index));
+        if let Some(document) = active.and_then(|file| self.documents.get(&file.path)) {
+            state.cursors = Self::cursor_positions(&document.editor);
+        }
+        state
     }
 
-    pub fn load_state(&mut self, state: EditorState) {
+    pub async fn load_state(&mut self, state: EditorState) {
+        // Files reopen in the language servers as they are next sent
+        for (path, document) in &self.documents {
+            if document.lsp_version.is_some() {
+                self.send_lsp(PACKET_TYPE_LSP_CLOSE, &LspFileMessage { path: self.root_path.join(path) }).await;
+            }
+        }
+        self.documents = state.open_files.iter()
+            .map(|file| (file.path.clone(), Document::new(TextBuffer::with_path(file.path.clone(), file.content.clone()))))
+            .collect();
+        let active = state.active_file.and_then(|index| state.open_files.get(index));
+        if let Some(document) = active.and_then(|file| self.documents.get_mut(&file.path)) {
+            let mut cursors = state.cursors.iter();
+            if let Some(primary) = cursors.next() {
+                document.editor.set_cursor_position(primary.line, primary.column);
+            }
+            for cursor in cursors {
+                document.editor.add_cursor(cursor.line, cursor.column);
+            }
+        }
         self.state = shared(state);
     }
 }
@@ -113,43 +964,55 @@ impl System for EditorCorePlugin {
     fn name(&self) -> &'static str {
         "EditorCorePlugin"
     }
-    
+
     async fn initialize(&mut self, _world: &World) -> LogicResult<()> {
         // Request dynamic channel allocation
         self.channel_id = Some(self.systems_manager.register_plugin("editor-core").await?);
-        
+
         self.systems_manager.log_component("plugins/editor-core",
             playground_systems_logic::LogLevel::Info,
             format!("Editor Core Plugin initialized on dynamic channel {}", self.channel_id.unwrap())).await;
-        
+
+        // Open files and language requests go to the LSP client, if it runs
+        self.lsp_channel = self.systems_manager.get_plugin_channel("lsp-client").await;
+
         // Initialize default editor state
         let mut state = self.state.write().await;
         state.vim_mode = true;  // Enable vim mode by default
-        
+
         Ok(())
     }
-    
+
     async fn run(&mut self, _world: &World, _delta_time: f32) -> LogicResult<()> {
-        // Process any pending editor operations
-        // This would handle vim commands, cursor movements, etc.
+        // Keys, cursor and search requests from the browser
+        if let Some(channel_id) = self.channel_id {
+            let packets = {
+                let networking = self.systems_manager.networking();
+                let net = networking.read().await;
+                net.receive_packets(channel_id).await.unwrap_or_default()
+            };
+            for packet in packets {
+                self.handle_packet(packet.packet_type, packet.data).await;
+            }
+        }
+
         Ok(())
     }
-    
+
     async fn cleanup(&mut self, _world: &World) -> LogicResult<()> {
         self.systems_manager.log_component("plugins/editor-core",
             playground_systems_logic::LogLevel::Info,
             "Editor Core Plugin shutting down".to_string()).await;
-        
+
         // Save any unsaved changes
-        let state = self.state.read().await;
-        for file in &state.open_files {
-            if file.modified {
+        for (path, document) in &self.documents {
+            if document.editor.buffer().is_modified() {
                 self.systems_manager.log_component("plugins/editor-core",
                     playground_systems_logic::LogLevel::Debug,
-                    format!("Warning: Unsaved changes in {}", file.path)).await;
+                    format!("Warning: Unsaved changes in {}", path)).await;
             }
         }
-        
+
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+use crate::search::SearchQuery;
+
+/// Files bigger than this are not searched
+const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Results stop after this many matches
+const MAX_MATCHES: usize = 10_000;
+
+/// Directories that hold build output or dependencies rather than sources
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Where a relative path from the browser is under `root`, or None if it
+/// would leave it. Absolute paths and `..` are refused outright; `.` parts
+/// are dropped before checking the result is still under the root.
+pub fn contained_path(root: &Path, relative: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    path.starts_with(root).then_some(path)
+}
+
+/// One match, with the line it is on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineMatch {
+    /// 0-based
+    pub line: usize,
+    /// Chars from the start of the line
+    pub column: usize,
+    /// In chars; a match running past the line end stops there
+    pub length: usize,
+    pub text: String,
+    /// The line with this match replaced, when previewing a replace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+/// The matches in one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMatches {
+    /// Relative to the search root, with `/` separators
+    pub path: String,
+    pub matches: Vec<LineMatch>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSearchResults {
+    pub files: Vec<FileMatches>,
+    /// Stopped at the match limit
+    pub truncated: bool,
+}
+
+/// Search and replace across the files under a root directory
+///
+/// Hidden files and directories, `target` and `node_modules`, binary files
+/// and files over 4MB are skipped. Files open in the editor are searched as
+/// they are in the editor, through the `open` lookup the caller passes in.
+#[derive(Debug, Clone)]
+pub struct ProjectSearch {
+    root: PathBuf,
+}
+
+impl ProjectSearch {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path of a result's relative path
+    pub fn resolve(&self, relative: &str) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    /// Every match under the root. With a replacement, each match carries a
+    /// preview of its line after replacing.
+    pub fn search(
+        &self,
+        query: &SearchQuery,
+        replacement: Option<&str>,
+        open: impl Fn(&Path) -> Option<String>,
+    ) -> ProjectSearchResults {
+        let mut results = ProjectSearchResults::default();
+        let mut total = 0;
+        for path in self.files() {
+            let Some(text) = open(&path).or_else(|| read_text(&path)) else {
+                continue;
+            };
+            let matches = find_lines(query, &text, replacement);
+            if matches.is_empty() {
+                continue;
+            }
+            total += matches.len();
+            results.files.push(FileMatches { path: self.relative(&path), matches });
+            if total >= MAX_MATCHES {
+                results.truncated = true;
+                break;
+            }
+        }
+        results
+    }
+
+    /// Replace every match in one file on disk. Returns how many were
+    /// replaced.
+    pub fn replace_in_file(&self, relative: &str, query: &SearchQuery, replacement: &str) -> Result<usize, String> {
+        let path = contained_path(&self.root, relative)
+            .ok_or_else(|| format!("{} is outside the project", relative))?;
+        let text = read_text(&path).ok_or_else(|| format!("Cannot read {} as text", relative))?;
+        let (replaced, count) = query.replace_all(&text, replacement);
+        if count > 0 {
+            std::fs::write(&path, replaced).map_err(|e| format!("Failed to write {}: {}", relative, e))?;
+        }
+        Ok(count)
+    }
+
+    /// Searchable files under the root, sorted by path
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if name.starts_with('.') {
+                    continue;
+                }
+                // Symlinks are not followed, so links out of the root and
+                // loops are never searched
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                        pending.push(entry.path());
+                    }
+                } else if file_type.is_file() {
+                    let small = entry.metadata().is_ok_and(|metadata| metadata.len() <= MAX_FILE_BYTES);
+                    if small {
+                        files.push(entry.path());
+                    }
+                }
+            }
+        }
+        files.sort();
+        files
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        relative.components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+/// A file's contents, or None for binary files
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.iter().take(8192).any(|byte| *byte == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn find_lines(query: &SearchQuery, text: &str, replacement: Option<&str>) -> Vec<LineMatch> {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(at, _)| at + 1))
+        .collect();
+
+    query.find_in(text).into_iter()
+        .map(|found| {
+            let line = line_starts.partition_point(|start| *start <= found.start) - 1;
+            let line_start = line_starts[line];
+            let line_end = line_starts.get(line + 1).map_or(text.len(), |next| next - 1);
+            let line_text = text[line_start..line_end].trim_end_matches('\r');
+            let match_end = found.end.min(line_start + line_text.len());
+
+            let preview = replacement.and_then(|replacement| {
+                let (_, expanded) = query.replacement_at(text, found.start, replacement)?;
+                let before = &text[line_start..found.start];
+                let after = text[found.end.min(line_end)..line_end].trim_end_matches('\r');
+                Some(format!("{}{}{}", before, expanded, after))
+            });
+
+            LineMatch {
+                line,
+                column: text[line_start..found.start].chars().count(),
+                length: text[found.start..match_end.max(found.start)].chars().count(),
+                text: line_text.to_string(),
+                preview,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::buffer::TextBuffer;
+
+/// How a search pattern is interpreted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Pattern is a regular expression rather than literal text
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only matches with a word boundary at both ends
+    #[serde(default)]
+    pub whole_word: bool,
+}
+
+/// A compiled search
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    pattern: String,
+    options: SearchOptions,
+    regex: Regex,
+}
+
+impl SearchQuery {
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Empty search pattern".to_string());
+        }
+        let mut source = if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+        if options.whole_word {
+            source = format!(r"\b(?:{})\b", source);
+        }
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            .build()
+            .map_err(|e| format!("Invalid pattern: {}", e))?;
+        Ok(Self { pattern: pattern.to_string(), options, regex })
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// Byte ranges of the matches in `text`, skipping empty ones
+    pub fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex.find_iter(text)
+            .filter(|found| !found.is_empty())
+            .map(|found| found.range())
+            .collect()
+    }
+
+    /// Char ranges of every match in the buffer
+    pub fn find_all(&self, buffer: &TextBuffer) -> Vec<Range<usize>> {
+        let text = buffer.get_text();
+        self.find_in(&text).into_iter()
+            .map(|range| buffer.byte_to_char(range.start)..buffer.byte_to_char(range.end))
+            .collect()
+    }
+
+    /// The first match starting after `from`, or before it going
+    /// backwards, wrapping around the end of the buffer
+    pub fn find_next(&self, buffer: &TextBuffer, from: usize, forward: bool) -> Option<Range<usize>> {
+        let matches = self.find_all(buffer);
+        if forward {
+            matches.iter().find(|found| found.start > from).or(matches.first()).cloned()
+        } else {
+            matches.iter().rev().find(|found| found.start < from).or(matches.last()).cloned()
+        }
+    }
+
+    /// The match starting at byte `start` of `text` and what replaces it.
+    /// With `regex` on, `$1` and `${name}` in the replacement refer to
+    /// capture groups; otherwise it is literal.
+    pub fn replacement_at(&self, text: &str, start: usize, replacement: &str) -> Option<(Range<usize>, String)> {
+        let captures = self.regex.captures_at(text, start)?;
+        let found = captures.get(0)?;
+        if found.start() != start || found.is_empty() {
+            return None;
+        }
+        let mut expanded = String::new();
+        self.expand(&captures, replacement, &mut expanded);
+        Some((found.range(), expanded))
+    }
+
+    /// `text` with every match replaced, and how many there were
+    pub fn replace_all(&self, text: &str, replacement: &str) -> (String, usize) {
+        let mut replaced = String::with_capacity(text.len());
+        let mut copied = 0;
+        let mut count = 0;
+        for captures in self.regex.captures_iter(text) {
+            let Some(found) = captures.get(0).filter(|found| !found.is_empty()) else {
+                continue;
+            };
+            replaced.push_str(&text[copied..found.start()]);
+            self.expand(&captures, replacement, &mut replaced);
+            copied = found.end();
+            count += 1;
+        }
+        replaced.push_str(&text[copied..]);
+        (replaced, count)
+    }
+
+    fn expand(&self, captures: &regex::Captures<'_>, replacement: &str, out: &mut String) {
+        if self.options.regex {
+            captures.expand(replacement, out);
+        } else {
+            out.push_str(replacement);
+        }
+    }
+}
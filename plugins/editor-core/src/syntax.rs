@@ -0,0 +1,594 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::OnceLock;
+use tree_sitter::{InputEdit, Language, Node, Parser, Point, Query, QueryCursor, Tree};
+
+use crate::buffer::{Position, TextBuffer, TextChange};
+
+/// The grammar's own JSON query only colors strings
+const JSON_HIGHLIGHT_QUERY: &str = r#"
+(pair key: (string) @property)
+(string) @string
+(escape_sequence) @escape
+(number) @number
+[(true) (false) (null)] @constant.builtin
+["{" "}" "[" "]"] @punctuation.bracket
+["," ":"] @punctuation.delimiter
+"#;
+
+/// Bracket pairs matched by `matching_bracket` and folded by `folding_ranges`
+const BRACKETS: &[(&str, &str)] = &[("(", ")"), ("[", "]"), ("{", "}"), ("<", ">")];
+
+/// Languages with a grammar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyntaxLanguage {
+    Rust,
+    Toml,
+    JavaScript,
+    Markdown,
+    Json,
+}
+
+impl SyntaxLanguage {
+    /// From a language name as `TextBuffer` detects it
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rust" => Some(Self::Rust),
+            "toml" => Some(Self::Toml),
+            "javascript" => Some(Self::JavaScript),
+            "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::language(),
+            Self::Toml => tree_sitter_toml::language(),
+            Self::JavaScript => tree_sitter_javascript::language(),
+            Self::Markdown => tree_sitter_md::language(),
+            Self::Json => tree_sitter_json::language(),
+        }
+    }
+
+    /// The highlight query, compiled on first use
+    fn query(self) -> &'static Query {
+        static RUST: OnceLock<Query> = OnceLock::new();
+        static TOML: OnceLock<Query> = OnceLock::new();
+        static JAVASCRIPT: OnceLock<Query> = OnceLock::new();
+        static MARKDOWN: OnceLock<Query> = OnceLock::new();
+        static JSON: OnceLock<Query> = OnceLock::new();
+
+        let cell = match self {
+            Self::Rust => &RUST,
+            Self::Toml => &TOML,
+            Self::JavaScript => &JAVASCRIPT,
+            Self::Markdown => &MARKDOWN,
+            Self::Json => &JSON,
+        };
+        cell.get_or_init(|| {
+            let source = match self {
+                Self::Rust => tree_sitter_rust::HIGHLIGHT_QUERY.to_string(),
+                Self::Toml => tree_sitter_toml::HIGHLIGHT_QUERY.to_string(),
+                Self::JavaScript => {
+                    format!("{}\n{}", tree_sitter_javascript::HIGHLIGHT_QUERY, tree_sitter_javascript::JSX_HIGHLIGHT_QUERY)
+                }
+                Self::Markdown => tree_sitter_md::HIGHLIGHTS_QUERY.to_string(),
+                Self::Json => JSON_HIGHLIGHT_QUERY.to_string(),
+            };
+            Query::new(self.grammar(), &source).expect("bundled highlight query is valid")
+        })
+    }
+
+    /// Multi-line nodes that fold besides bracketed ones and comments
+    fn fold_kinds(self) -> &'static [&'static str] {
+        match self {
+            Self::Toml => &["table", "table_array_element"],
+            Self::Markdown => &["fenced_code_block", "indented_code_block", "list", "block_quote", "html_block"],
+            Self::Rust | Self::JavaScript | Self::Json => &[],
+        }
+    }
+}
+
+/// What a highlighted span is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Escape,
+    Number,
+    Constant,
+    Comment,
+    Function,
+    Type,
+    Variable,
+    Property,
+    Attribute,
+    Label,
+    Operator,
+    Punctuation,
+    Heading,
+    Emphasis,
+    Strong,
+    Literal,
+    Link,
+}
+
+impl TokenKind {
+    /// From a query capture name such as `function.method`. None for
+    /// captures that mean no highlight, such as `none` or `embedded`.
+    fn from_capture(name: &str) -> Option<Self> {
+        let mut parts = name.split('.');
+        let kind = match (parts.next()?, parts.next()) {
+            ("string", Some("escape")) | ("escape", _) => Self::Escape,
+            ("string", _) => Self::String,
+            ("keyword", _) => Self::Keyword,
+            ("number", _) | ("float", _) => Self::Number,
+            ("constant", _) | ("boolean", _) => Self::Constant,
+            ("comment", _) => Self::Comment,
+            ("function", _) | ("method", _) | ("constructor", _) => Self::Function,
+            ("type", _) => Self::Type,
+            ("variable", _) => Self::Variable,
+            ("property", _) | ("field", _) => Self::Property,
+            ("attribute", _) => Self::Attribute,
+            ("label", _) => Self::Label,
+            ("operator", _) => Self::Operator,
+            ("punctuation", _) => Self::Punctuation,
+            ("text", Some("title")) => Self::Heading,
+            ("text", Some("emphasis")) => Self::Emphasis,
+            ("text", Some("strong")) => Self::Strong,
+            ("text", Some("literal")) => Self::Literal,
+            ("text", Some("uri")) | ("text", Some("reference")) => Self::Link,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Theme color names to try in order. The first is a `ThemeColors`
+    /// field or a custom color of the same name; the rest are fields to
+    /// fall back on for themes without it.
+    pub fn theme_tokens(self) -> &'static [&'static str] {
+        match self {
+            Self::Keyword => &["keyword"],
+            Self::String => &["string"],
+            Self::Escape => &["escape", "keyword"],
+            Self::Number => &["number"],
+            Self::Constant => &["constant", "number"],
+            Self::Comment => &["comment"],
+            Self::Function => &["function"],
+            Self::Type => &["type_color"],
+            Self::Variable => &["variable", "text"],
+            Self::Property => &["property", "text"],
+            Self::Attribute => &["attribute", "keyword"],
+            Self::Label => &["label", "keyword"],
+            Self::Operator => &["operator", "text"],
+            Self::Punctuation => &["punctuation", "text_secondary"],
+            Self::Heading => &["heading", "keyword"],
+            Self::Emphasis => &["emphasis", "text"],
+            Self::Strong => &["strong", "text"],
+            Self::Literal => &["literal", "string"],
+            Self::Link => &["link", "function"],
+        }
+    }
+}
+
+/// Highlighted chars on one line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    /// Char columns, end exclusive
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+/// Lines that fold together. The first stays visible with the rest hidden
+/// behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldRange {
+    pub start_line: usize,
+    /// Inclusive
+    pub end_line: usize,
+}
+
+/// Incremental syntax highlighting of a buffer with tree-sitter
+///
+/// `update` re-parses only the edits made since the last update, reusing
+/// the old syntax tree, and re-highlights only the lines those edits touch
+/// or whose syntax they changed; other lines keep their cached spans.
+pub struct SyntaxHighlighter {
+    language: SyntaxLanguage,
+    parser: Parser,
+    tree: Option<Tree>,
+    /// Buffer version `tree` and `lines` are for
+    version: u32,
+    /// Token kind of each capture in the query
+    kinds: Vec<Option<TokenKind>>,
+    /// Spans of each line, in order
+    lines: Vec<Vec<HighlightSpan>>,
+}
+
+impl SyntaxHighlighter {
+    pub fn new(language: SyntaxLanguage) -> Self {
+        let mut parser = Parser::new();
+        parser.set_language(language.grammar()).expect("grammar matches the tree-sitter version");
+        let kinds = language.query().capture_names().iter()
+            .map(|name| TokenKind::from_capture(name))
+            .collect();
+        Self { language, parser, tree: None, version: 0, kinds, lines: Vec::new() }
+    }
+
+    /// A highlighter for the buffer's language, if it has a grammar
+    pub fn for_buffer(buffer: &TextBuffer) -> Option<Self> {
+        SyntaxLanguage::from_name(&buffer.language).map(Self::new)
+    }
+
+    pub fn language(&self) -> SyntaxLanguage {
+        self.language
+    }
+
+    /// Catch up with the buffer. Returns the runs of lines whose spans were
+    /// recomputed; lines added or removed by edits shift the others, as in
+    /// the buffer.
+    pub fn update(&mut self, buffer: &TextBuffer) -> Vec<Range<usize>> {
+        let changes = self.tree.as_ref().and_then(|_| buffer.changes_since(self.version));
+        if changes.is_some_and(|changes| changes.is_empty()) {
+            return Vec::new();
+        }
+
+        let mut dirty;
+        // Bytes each edit inserted, in the text as it is now
+        let mut edited: Vec<Range<usize>> = Vec::new();
+        let old_tree = match changes {
+            Some(changes) => {
+                let mut tree = self.tree.take().expect("changes are only looked up with a tree");
+                dirty = vec![false; self.lines.len()];
+                for change in changes {
+                    tree.edit(&input_edit(change));
+                    self.shift_lines(change, &mut dirty);
+                    for range in &mut edited {
+                        *range = map_through(range.start, change)..map_through(range.end, change);
+                    }
+                    edited.push(change.start.byte_index..change.new_end.byte_index);
+                }
+                Some(tree)
+            }
+            None => {
+                dirty = Vec::new();
+                self.lines.clear();
+                None
+            }
+        };
+        // The cache follows the buffer's lines, but a full reparse or a
+        // change log with gaps starts over
+        let line_count = buffer.line_count();
+        self.lines.resize(line_count, Vec::new());
+        dirty.resize(line_count, true);
+
+        let rope = buffer.rope();
+        let len = rope.len_bytes();
+        let parsed = self.parser.parse_with(&mut |byte, _| {
+            if byte >= len {
+                return &[][..];
+            }
+            let (chunk, chunk_start, _, _) = rope.chunk_at_byte(byte);
+            &chunk.as_bytes()[byte - chunk_start..]
+        }, old_tree.as_ref());
+        self.version = buffer.version();
+        let Some(tree) = parsed else {
+            self.tree = None;
+            self.lines.iter_mut().for_each(Vec::clear);
+            return std::iter::once(0..line_count).collect();
+        };
+
+        // Syntax that changed away from the edits, such as the rest of the
+        // file after an opening quote. Tokens that grew or shrank, such as
+        // a comment that lost its end, are not among the changed ranges, so
+        // the tokens at either end of each edit count too.
+        let mut mark = |start: Point, end: Point| {
+            let end = (end.row + 1).min(line_count);
+            dirty[start.row.min(end)..end].fill(true);
+        };
+        if let Some(old_tree) = &old_tree {
+            for range in tree.changed_ranges(old_tree) {
+                mark(range.start_point, range.end_point);
+            }
+        }
+        for byte in edited.into_iter().flat_map(|range| [range.start, range.end]) {
+            let token = tree.root_node().descendant_for_byte_range(byte, byte)
+                .filter(|node| node.child_count() == 0);
+            if let Some(token) = token {
+                mark(token.start_position(), token.end_position());
+            }
+        }
+        self.tree = Some(tree);
+
+        let mut runs = Vec::new();
+        let mut line = 0;
+        while line < line_count {
+            if !dirty[line] {
+                line += 1;
+                continue;
+            }
+            let start = line;
+            while line < line_count && dirty[line] {
+                line += 1;
+            }
+            runs.push(start..line);
+        }
+        for run in &runs {
+            self.highlight(buffer, run.clone());
+        }
+        runs
+    }
+
+    /// Spans of a line, as of the last update
+    pub fn line_highlights(&self, line: usize) -> &[HighlightSpan] {
+        self.lines.get(line).map_or(&[], Vec::as_slice)
+    }
+
+    /// Spans of every line, as of the last update
+    pub fn highlights(&self) -> &[Vec<HighlightSpan>] {
+        &self.lines
+    }
+
+    /// Bracketed blocks, multi-line comments and the language's own blocks
+    /// such as TOML tables, outermost first where several start on a line
+    pub fn folding_ranges(&self) -> Vec<FoldRange> {
+        let Some(tree) = &self.tree else {
+            return Vec::new();
+        };
+        let mut folds = Vec::new();
+        let mut cursor = tree.walk();
+        'walk: loop {
+            let node = cursor.node();
+            // Single-line nodes hold nothing that folds
+            let multi_line = node.end_position().row > node.start_position().row;
+            if multi_line {
+                if let Some(fold) = self.fold(node) {
+                    folds.push(fold);
+                }
+                if cursor.goto_first_child() {
+                    continue;
+                }
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    break 'walk;
+                }
+            }
+        }
+        folds.sort_by_key(|fold| (fold.start_line, std::cmp::Reverse(fold.end_line)));
+        folds.dedup_by_key(|fold| fold.start_line);
+        folds
+    }
+
+    /// The bracket paired with the one at `char_index`, as a char index.
+    /// Brackets in strings and comments, and `<` and `>` used as
+    /// operators, have no pair.
+    pub fn matching_bracket(&self, buffer: &TextBuffer, char_index: usize) -> Option<usize> {
+        let tree = self.tree.as_ref().filter(|_| self.version == buffer.version())?;
+        let c = buffer.char_at(char_index)?;
+        let (open, close, forward) = BRACKETS.iter().find_map(|(open, close)| {
+            if open.starts_with(c) {
+                Some((*open, *close, true))
+            } else if close.starts_with(c) {
+                Some((*open, *close, false))
+            } else {
+                None
+            }
+        })?;
+
+        let byte = buffer.char_to_byte(char_index);
+        let node = tree.root_node().descendant_for_byte_range(byte, byte + c.len_utf8())?;
+        let (same, other) = if forward { (open, close) } else { (close, open) };
+        if node.is_named() || node.kind() != same {
+            return None;
+        }
+
+        let mut sibling = node;
+        let mut depth = 0;
+        loop {
+            sibling = if forward { sibling.next_sibling()? } else { sibling.prev_sibling()? };
+            if sibling.is_named() {
+                continue;
+            }
+            if sibling.kind() == same {
+                depth += 1;
+            } else if sibling.kind() == other {
+                if depth == 0 {
+                    return Some(buffer.byte_to_char(sibling.start_byte()));
+                }
+                depth -= 1;
+            }
+        }
+    }
+
+    /// Move cached lines to follow an edit, marking the edited lines
+    fn shift_lines(&mut self, change: &TextChange, dirty: &mut Vec<bool>) {
+        let (start, old_end, new_end) = (change.start.line, change.old_end.line, change.new_end.line);
+        let removed = (start + 1).min(self.lines.len())..(old_end + 1).min(self.lines.len());
+        let added = new_end - start;
+        self.lines.splice(removed.clone(), std::iter::repeat_with(Vec::new).take(added));
+        dirty.splice(removed, std::iter::repeat_n(true, added));
+        if let Some(line) = dirty.get_mut(start) {
+            *line = true;
+        }
+    }
+
+    /// Recompute the spans of `lines`
+    fn highlight(&mut self, buffer: &TextBuffer, lines: Range<usize>) {
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        let rope = buffer.rope();
+        let start_byte = rope.line_to_byte(lines.start);
+        let end_byte = rope.line_to_byte(lines.end);
+
+        let mut captures = Vec::new();
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(start_byte..end_byte);
+        let text = |node: Node| rope.byte_slice(node.byte_range()).chunks().map(str::as_bytes);
+        for (found, index) in cursor.captures(self.language.query(), tree.root_node(), text) {
+            let capture = found.captures[index];
+            captures.push((capture.node.byte_range(), self.kinds[capture.index as usize]));
+        }
+        // Outer nodes before the nodes inside them, which paint over them.
+        // Of several captures of one node the first pattern wins.
+        captures.sort_by_key(|(range, _)| (range.start, std::cmp::Reverse(range.end)));
+        captures.dedup_by(|later, first| later.0 == first.0);
+
+        for line in lines {
+            let line_start = rope.line_to_byte(line);
+            let line_text = rope.line(line).to_string();
+            let line_text = line_text.trim_end_matches(['\n', '\r']);
+            let line_end = line_start + line_text.len();
+
+            let mut paint = vec![None; line_text.len()];
+            for (range, kind) in &captures {
+                if range.start >= line_end || range.end <= line_start {
+                    continue;
+                }
+                let from = range.start.max(line_start) - line_start;
+                let to = range.end.min(line_end) - line_start;
+                paint[from..to].fill(*kind);
+            }
+
+            let mut spans: Vec<HighlightSpan> = Vec::new();
+            for (column, (byte, _)) in line_text.char_indices().enumerate() {
+                let Some(kind) = paint[byte] else {
+                    continue;
+                };
+                match spans.last_mut() {
+                    Some(span) if span.kind == kind && span.end == column => span.end += 1,
+                    _ => spans.push(HighlightSpan { start: column, end: column + 1, kind }),
+                }
+            }
+            self.lines[line] = spans;
+        }
+    }
+
+    /// A fold for a multi-line node, if it is a kind that folds
+    fn fold(&self, node: Node) -> Option<FoldRange> {
+        let last = node.child_count().checked_sub(1).and_then(|index| node.child(index));
+        let bracketed = match (node.child(0), last) {
+            (Some(first), Some(last)) => BRACKETS.iter().any(|(open, close)| first.kind() == *open && last.kind() == *close),
+            _ => false,
+        };
+        let folds = bracketed || node.kind().ends_with("comment") || self.language.fold_kinds().contains(&node.kind());
+        if !folds {
+            return None;
+        }
+        // Blocks that end with their line break stop on the line before
+        let end = node.end_position();
+        let end_line = if end.column == 0 { end.row - 1 } else { end.row };
+        let start_line = node.start_position().row;
+        (end_line > start_line).then_some(FoldRange { start_line, end_line })
+    }
+}
+
+/// Where a byte from before `change` ended up after it
+fn map_through(byte: usize, change: &TextChange) -> usize {
+    let (start, old_end, new_end) = (change.start.byte_index, change.old_end.byte_index, change.new_end.byte_index);
+    if byte >= old_end {
+        byte - old_end + new_end
+    } else if byte > start {
+        byte.min(new_end)
+    } else {
+        byte
+    }
+}
+
+fn input_edit(change: &TextChange) -> InputEdit {
+    let point = |position: &Position| Point::new(position.line, position.byte_column);
+    InputEdit {
+        start_byte: change.start.byte_index,
+        old_end_byte: change.old_end.byte_index,
+        new_end_byte: change.new_end.byte_index,
+        start_position: point(&change.start),
+        old_end_position: point(&change.old_end),
+        new_end_position: point(&change.new_end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main() {\n    let x = \"hi\";\n}\n";
+
+    fn kinds(highlighter: &SyntaxHighlighter, line: usize) -> Vec<TokenKind> {
+        highlighter.line_highlights(line).iter().map(|span| span.kind).collect()
+    }
+
+    #[test]
+    fn syntax_highlights_every_line_at_first() {
+        let buffer = TextBuffer::from_string(SOURCE.to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        assert_eq!(highlighter.update(&buffer), vec![0..4]);
+        assert_eq!(highlighter.line_highlights(0)[0], HighlightSpan { start: 0, end: 2, kind: TokenKind::Keyword });
+        assert!(kinds(&highlighter, 1).contains(&TokenKind::String));
+        // Nothing changed since
+        assert!(highlighter.update(&buffer).is_empty());
+    }
+
+    #[test]
+    fn syntax_update_rehighlights_only_edited_lines() {
+        let mut buffer = TextBuffer::from_string(SOURCE.to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        highlighter.update(&buffer);
+        let first_line = highlighter.line_highlights(0).to_vec();
+
+        buffer.insert(1, 0, "    // note\n");
+        let runs = highlighter.update(&buffer);
+        assert!(runs.iter().all(|run| run.start >= 1));
+        assert!(runs.iter().any(|run| run.contains(&1)));
+        assert_eq!(highlighter.line_highlights(0), first_line.as_slice());
+        assert_eq!(kinds(&highlighter, 1), vec![TokenKind::Comment]);
+        // The line that moved down keeps its spans
+        assert!(kinds(&highlighter, 2).contains(&TokenKind::String));
+        assert_eq!(highlighter.highlights().len(), buffer.line_count());
+    }
+
+    #[test]
+    fn syntax_update_follows_syntax_changed_away_from_the_edit() {
+        let mut buffer = TextBuffer::from_string("let a = 1; /* x */\nlet b = 2; // */\n".to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        highlighter.update(&buffer);
+        assert!(kinds(&highlighter, 1).contains(&TokenKind::Keyword));
+        // Without its end the block comment runs on into the next line
+        buffer.delete(0, 16, 0, 18);
+        let runs = highlighter.update(&buffer);
+        assert!(runs.iter().any(|run| run.contains(&1)));
+        assert_eq!(kinds(&highlighter, 1), vec![TokenKind::Comment]);
+    }
+
+    #[test]
+    fn syntax_folding_ranges() {
+        let buffer = TextBuffer::from_string("fn a() {\n    if x {\n        y();\n    }\n}\n".to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        highlighter.update(&buffer);
+        assert_eq!(highlighter.folding_ranges(), vec![
+            FoldRange { start_line: 0, end_line: 4 },
+            FoldRange { start_line: 1, end_line: 3 },
+        ]);
+
+        let buffer = TextBuffer::from_string("/* one\n   two */\nfn b() {}\n".to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        highlighter.update(&buffer);
+        assert_eq!(highlighter.folding_ranges(), vec![FoldRange { start_line: 0, end_line: 1 }]);
+    }
+
+    #[test]
+    fn syntax_matching_bracket_skips_strings() {
+        let buffer = TextBuffer::from_string("f(\"(\", [1]);\n".to_string());
+        let mut highlighter = SyntaxHighlighter::new(SyntaxLanguage::Rust);
+        highlighter.update(&buffer);
+        assert_eq!(highlighter.matching_bracket(&buffer, 1), Some(10));
+        assert_eq!(highlighter.matching_bracket(&buffer, 10), Some(1));
+        assert_eq!(highlighter.matching_bracket(&buffer, 7), Some(9));
+        assert_eq!(highlighter.matching_bracket(&buffer, 3), None);
+    }
+}
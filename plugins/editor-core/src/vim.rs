@@ -26,6 +26,10 @@ pub enum VimCommand {
     EnterInsertMode,
     ExitInsertMode,
     AppendMode,
+    /// `I`: insert before the first non-blank character of the line
+    InsertLineStart,
+    /// `A`: append at the end of the line
+    AppendLineEnd,
     OpenLineBelow,
     OpenLineAbove,
     EnterVisualMode,
@@ -55,9 +59,61 @@ pub enum VimCommand {
     ExtendSelectionRight,
     ExtendSelectionUp,
     ExtendSelectionDown,
+    /// `o` in visual mode: move the cursor to the other end of the selection
+    SwapSelectionEnds,
+    /// `iw`, `a"` and so on in visual mode
+    SelectTextObject(TextObject),
+    /// `I` in visual mode: insert before the selection
+    InsertBeforeSelection,
+    /// `A` in visual mode: append after the selection
+    AppendAfterSelection,
     ExecuteCommand(String),
     Undo,
     Redo,
+    GoToLastLine,
+    DeleteTextObject(TextObject),
+    YankTextObject(TextObject),
+    ChangeTextObject(TextObject),
+    /// `.`, repeated this many times
+    RepeatLastChange(u32),
+    /// `n`: next match of the last search, in its direction
+    SearchNext(u32),
+    /// `N`: next match the other way
+    SearchPrevious(u32),
+}
+
+/// Region an operator acts on, as in `diw` or `ci"`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextObject {
+    pub kind: TextObjectKind,
+    /// `i`: contents only; `a`: with the surrounding quotes, brackets or
+    /// whitespace
+    pub inner: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextObjectKind {
+    Word,
+    Paragraph,
+    /// Text between two of this quote character on the line
+    Quote(char),
+    /// Text between an open and its matching close bracket
+    Bracket(char, char),
+}
+
+impl TextObjectKind {
+    fn from_key(key: char) -> Option<Self> {
+        match key {
+            'w' => Some(Self::Word),
+            'p' => Some(Self::Paragraph),
+            '"' | '\'' | '`' => Some(Self::Quote(key)),
+            '(' | ')' | 'b' => Some(Self::Bracket('(', ')')),
+            '[' | ']' => Some(Self::Bracket('[', ']')),
+            '{' | '}' | 'B' => Some(Self::Bracket('{', '}')),
+            '<' | '>' => Some(Self::Bracket('<', '>')),
+            _ => None,
+        }
+    }
 }
 
 /// Movement directions
@@ -101,6 +157,12 @@ pub struct VimState {
     pub last_command: Option<String>,
     pub registers: std::collections::HashMap<char, String>,
     pub marks: std::collections::HashMap<char, (usize, usize)>, // line, column
+    /// Register named with `"x` for the next command
+    #[serde(default)]
+    pub pending_register: Option<char>,
+    /// Count typed before an operator, as the 2 in `2d3w`
+    #[serde(default)]
+    pub operator_count: Option<u32>,
 }
 
 impl Default for VimState {
@@ -113,6 +175,8 @@ impl Default for VimState {
             last_command: None,
             registers: std::collections::HashMap::new(),
             marks: std::collections::HashMap::new(),
+            pending_register: None,
+            operator_count: None,
         }
     }
 }
@@ -138,6 +202,11 @@ impl VimState {
         self.registers.get(&register).cloned()
     }
     
+    /// The register chosen with `"x` for this command, if any
+    pub fn take_register(&mut self) -> Option<char> {
+        self.pending_register.take()
+    }
+    
     pub fn handle_normal_key(&mut self, key: Key) -> Option<VimCommand> {
         match key {
             Key::H => Some(VimCommand::Move(Direction::Left)),
@@ -177,6 +246,13 @@ impl VimState {
     }
     
     fn process_normal_key(&mut self, key: char) -> VimCommand {
+        // The register name after `"`, before digits count as a count
+        if self.pending_operator.as_deref() == Some("\"") {
+            self.pending_operator = None;
+            self.pending_register = Some(key);
+            return VimCommand::None;
+        }
+        
         // Handle repeat count
         if key.is_ascii_digit() && key != '0' || (self.repeat_count.is_some() && key == '0') {
             let count = self.repeat_count.unwrap_or(0) * 10 + (key as u32 - '0' as u32);
@@ -185,16 +261,21 @@ impl VimState {
         }
         
         // Handle operators
-        if let Some(op) = self.pending_operator.clone() {
+        if let Some(op) = self.pending_operator.take() {
+            // `di`, `ca` and so on wait for the text object
+            if matches!(op.as_str(), "d" | "y" | "c") && matches!(key, 'i' | 'a') {
+                self.pending_operator = Some(format!("{}{}", op, key));
+                return VimCommand::None;
+            }
             let command = self.complete_operator(&op, key);
-            self.pending_operator = None;
             self.repeat_count = None;
+            self.operator_count = None;
             return command;
         }
         
         // Process normal mode commands
-        let count = self.repeat_count.unwrap_or(1);
-        self.repeat_count = None;
+        let explicit_count = self.repeat_count.take();
+        let count = explicit_count.unwrap_or(1);
         
         match key {
             // Movement
@@ -209,9 +290,13 @@ impl VimState {
             '$' => VimCommand::MoveLineEnd,
             'g' => {
                 self.pending_operator = Some("g".to_string());
+                self.operator_count = explicit_count;
                 VimCommand::None
             }
-            'G' => VimCommand::GoToLine(count as usize),
+            'G' => match explicit_count {
+                Some(line) => VimCommand::GoToLine(line as usize),
+                None => VimCommand::GoToLastLine,
+            },
             
             // Mode changes
             'i' => {
@@ -222,6 +307,14 @@ impl VimState {
                 self.mode = VimMode::Insert;
                 VimCommand::AppendMode
             }
+            'I' => {
+                self.mode = VimMode::Insert;
+                VimCommand::InsertLineStart
+            }
+            'A' => {
+                self.mode = VimMode::Insert;
+                VimCommand::AppendLineEnd
+            }
             'o' => {
                 self.mode = VimMode::Insert;
                 VimCommand::OpenLineBelow
@@ -247,25 +340,45 @@ impl VimState {
                 self.command_buffer.clear();
                 VimCommand::EnterCommandMode
             }
+            // Searches are typed on the command line after their `/` or `?`
+            '/' | '?' => {
+                self.mode = VimMode::Command;
+                self.command_buffer = key.to_string();
+                VimCommand::EnterCommandMode
+            }
+            'n' => VimCommand::SearchNext(count),
+            'N' => VimCommand::SearchPrevious(count),
+            // Escape in normal mode drops extra cursors
+            '\x1b' => VimCommand::Normal,
             
             // Editing
             'x' => VimCommand::DeleteChar(count),
             'd' => {
                 self.pending_operator = Some("d".to_string());
+                self.operator_count = explicit_count;
                 VimCommand::None
             }
             'y' => {
                 self.pending_operator = Some("y".to_string());
+                self.operator_count = explicit_count;
                 VimCommand::None
             }
             'c' => {
                 self.pending_operator = Some("c".to_string());
+                self.operator_count = explicit_count;
                 VimCommand::None
             }
             'p' => VimCommand::PasteAfter,
             'P' => VimCommand::PasteBefore,
             'u' => VimCommand::Undo,
             '\x12' => VimCommand::Redo, // Ctrl-R
+            '.' => VimCommand::RepeatLastChange(count),
+            '"' => {
+                // Keep the count for `3"ayy`
+                self.repeat_count = explicit_count;
+                self.pending_operator = Some("\"".to_string());
+                VimCommand::None
+            }
             
             _ => VimCommand::None,
         }
@@ -282,6 +395,14 @@ impl VimState {
     }
     
     fn process_visual_key(&mut self, key: char) -> VimCommand {
+        // The object after `i` or `a`; escape drops it and leaves visual mode
+        if let Some(prefix) = self.pending_operator.take() && key != '\x1b' {
+            return match TextObjectKind::from_key(key) {
+                Some(kind) => VimCommand::SelectTextObject(TextObject { kind, inner: prefix == "i" }),
+                None => VimCommand::None,
+            };
+        }
+        
         match key {
             '\x1b' => {
                 // ESC key
@@ -301,11 +422,40 @@ impl VimState {
                 self.mode = VimMode::Insert;
                 VimCommand::ChangeSelection
             }
+            'x' => {
+                self.mode = VimMode::Normal;
+                VimCommand::DeleteSelection
+            }
+            'i' | 'a' => {
+                self.pending_operator = Some(key.to_string());
+                VimCommand::None
+            }
+            'o' | 'O' => VimCommand::SwapSelectionEnds,
+            'I' => {
+                self.mode = VimMode::Insert;
+                VimCommand::InsertBeforeSelection
+            }
+            'A' => {
+                self.mode = VimMode::Insert;
+                VimCommand::AppendAfterSelection
+            }
+            ':' => {
+                // Ex commands from visual mode act on the selected lines
+                self.mode = VimMode::Command;
+                self.command_buffer = "'<,'>".to_string();
+                VimCommand::EnterCommandMode
+            }
             // Movement in visual mode
             'h' => VimCommand::ExtendSelectionLeft,
             'j' => VimCommand::ExtendSelectionDown,
             'k' => VimCommand::ExtendSelectionUp,
             'l' => VimCommand::ExtendSelectionRight,
+            'w' => VimCommand::MoveWordForward(1),
+            'b' => VimCommand::MoveWordBackward(1),
+            'e' => VimCommand::MoveWordEnd(1),
+            '0' => VimCommand::MoveLineStart,
+            '$' => VimCommand::MoveLineEnd,
+            'G' => VimCommand::GoToLastLine,
             _ => VimCommand::None,
         }
     }
@@ -323,8 +473,14 @@ impl VimState {
             self.mode = VimMode::Normal;
             VimCommand::ExitCommandMode
         } else if key == '\x08' {
-            // Backspace
-            self.command_buffer.pop();
+            // Backspace; past the start of the line it cancels
+            let popped = self.command_buffer.pop();
+            let search_prefix = matches!(popped, Some('/' | '?')) && self.command_buffer.is_empty();
+            if popped.is_none() || search_prefix {
+                self.command_buffer.clear();
+                self.mode = VimMode::Normal;
+                return VimCommand::ExitCommandMode;
+            }
             VimCommand::None
         } else {
             self.command_buffer.push(key);
@@ -342,7 +498,24 @@ impl VimState {
     }
     
     fn complete_operator(&mut self, operator: &str, motion: char) -> VimCommand {
-        let count = self.repeat_count.unwrap_or(1);
+        // `2d3w` deletes six words
+        let explicit = self.operator_count.is_some() || self.repeat_count.is_some();
+        let count = self.operator_count.unwrap_or(1) * self.repeat_count.unwrap_or(1);
+        
+        if let Some(kind) = TextObjectKind::from_key(motion) {
+            let object = |inner| TextObject { kind, inner };
+            match operator {
+                "di" => return VimCommand::DeleteTextObject(object(true)),
+                "da" => return VimCommand::DeleteTextObject(object(false)),
+                "yi" => return VimCommand::YankTextObject(object(true)),
+                "ya" => return VimCommand::YankTextObject(object(false)),
+                "ci" | "ca" => {
+                    self.mode = VimMode::Insert;
+                    return VimCommand::ChangeTextObject(object(operator == "ci"));
+                }
+                _ => {}
+            }
+        }
         
         match (operator, motion) {
             // Delete operations
@@ -366,6 +539,7 @@ impl VimState {
             }
             
             // Go commands
+            ("g", 'g') if explicit => VimCommand::GoToLine(count as usize),
             ("g", 'g') => VimCommand::GoToFirstLine,
             
             _ => VimCommand::None,
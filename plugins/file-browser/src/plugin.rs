@@ -1,5 +1,7 @@
 use async_trait::async_trait;
-use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle};
+use playground_systems_logic::{
+    System, World, LogicResult, SystemsManager, Handle, LogLevel, McpPrompt, McpPromptArgument,
+};
 use std::path::PathBuf;
 use tokio::sync::mpsc;
 // Note: Using SystemsManager logging instead of tracing
@@ -102,8 +104,16 @@ impl FileBrowserPlugin {
                 FileTreeEvent::RefreshRequested(path) => {
                 // debug!("Refresh requested: {:?}", path);
                     // Reload directory or file
+                    self.notify_resource_changed(&path).await;
                     self.refresh_path(path).await;
                 }
+                FileTreeEvent::FileCreated(path) | FileTreeEvent::FileDeleted(path) => {
+                    self.notify_resource_changed(&path).await;
+                }
+                FileTreeEvent::FileRenamed { from, to } => {
+                    self.notify_resource_changed(&from).await;
+                    self.notify_resource_changed(&to).await;
+                }
                 _ => {
                 // debug!("File tree event: {:?}", event);
                 }
@@ -145,6 +155,54 @@ impl FileBrowserPlugin {
         }
     }
 
+    /// Expose the browsed directory to MCP clients as read-only `file://` resources
+    /// and publish prompt templates that operate on workspace files
+    async fn register_mcp_resources(&self) -> LogicResult<()> {
+        self.systems_manager.register_mcp_workspace(
+            "file-browser".to_string(),
+            self.root_path.clone(),
+        ).await?;
+        
+        self.systems_manager.register_mcp_prompt(McpPrompt {
+            name: "explain_file".to_string(),
+            description: "Explain what a workspace file does".to_string(),
+            arguments: vec![McpPromptArgument {
+                name: "path".to_string(),
+                description: Some("Path of the file to explain".to_string()),
+                required: true,
+            }],
+            template: "Read the resource file://{{path}} and explain its purpose, main types and how it fits into the project.".to_string(),
+        }).await?;
+        
+        self.systems_manager.register_mcp_prompt(McpPrompt {
+            name: "review_file".to_string(),
+            description: "Review a workspace file for bugs and style issues".to_string(),
+            arguments: vec![
+                McpPromptArgument {
+                    name: "path".to_string(),
+                    description: Some("Path of the file to review".to_string()),
+                    required: true,
+                },
+                McpPromptArgument {
+                    name: "focus".to_string(),
+                    description: Some("Optional area to focus the review on".to_string()),
+                    required: false,
+                },
+            ],
+            template: "Review the resource file://{{path}} for bugs, unsafe patterns and style issues. {{focus}}".to_string(),
+        }).await?;
+        
+        Ok(())
+    }
+
+    /// Tell subscribed MCP clients that a workspace file changed
+    async fn notify_resource_changed(&self, path: &PathBuf) {
+        let path = tokio::fs::canonicalize(path).await.unwrap_or_else(|_| path.clone());
+        self.systems_manager
+            .notify_mcp_resource_updated(&format!("file://{}", path.display()))
+            .await;
+    }
+
     fn send_open_file_message(&self, path: PathBuf) {
         // This would send a message to the editor-core plugin
         // through the networking system on our channel
@@ -169,6 +227,13 @@ impl System for FileBrowserPlugin {
         // Initialize file tree
         self.initialize_file_tree().await?;
         
+        // Publish workspace files and prompts over MCP - the browser still
+        // works without them, so a failure here only costs the MCP exposure
+        if let Err(e) = self.register_mcp_resources().await {
+            self.systems_manager.log_component("plugins/file-browser", LogLevel::Warning,
+                format!("MCP resource registration failed: {}", e)).await;
+        }
+        
                 // info!("File browser plugin loaded successfully");
         Ok(())
     }
@@ -0,0 +1,315 @@
+//! A tiny language server for exercising the client without a real one.
+//!
+//! It speaks LSP over stdio with incremental sync and knows only words:
+//!
+//! - every `error` and `warning` in a file is published as a diagnostic
+//! - hover shows the word under the cursor
+//! - completion offers the words of the file that start with what is typed
+//! - definition is the word's first occurrence in the file, references are
+//!   every occurrence in every open file, and rename replaces them all
+//! - formatting strips trailing whitespace
+//!
+//! A change that inserts `@crash` makes it exit with code 1, so restarts
+//! can be tested; opening a file that contains it does not.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut server = MockServer::default();
+    while let Some(message) = read_message(&mut input) {
+        server.handle(message);
+    }
+    // Stdin closed without an exit notification
+    std::process::exit(1);
+}
+
+#[derive(Default)]
+struct MockServer {
+    /// Open files by URI
+    documents: BTreeMap<String, String>,
+    shutdown: bool,
+}
+
+impl MockServer {
+    fn handle(&mut self, message: Value) {
+        let method = message["method"].as_str().unwrap_or_default().to_string();
+        let params = &message["params"];
+        let Some(id) = message.get("id").cloned() else {
+            self.notification(&method, params);
+            return;
+        };
+        if message.get("method").is_none() {
+            // A response to one of our requests; we make none
+            return;
+        }
+        let result = match method.as_str() {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": { "openClose": true, "change": 2, "save": true },
+                    "hoverProvider": true,
+                    "completionProvider": {},
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "renameProvider": true,
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "mock-lsp-server" },
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => Ok(self.word_at(params).map_or(Value::Null, |(_, word, range)| json!({
+                "contents": { "kind": "markdown", "value": format!("`{}`", word) },
+                "range": range,
+            }))),
+            "textDocument/completion" => Ok(self.completion(params)),
+            "textDocument/definition" => Ok(self.word_at(params).map_or(Value::Null, |(uri, word, _)| {
+                let text = &self.documents[&uri];
+                match words(text).into_iter().find(|(found, _)| *found == word) {
+                    Some((_, range)) => json!({ "uri": uri, "range": range }),
+                    None => Value::Null,
+                }
+            })),
+            "textDocument/references" => Ok(self.word_at(params).map_or(json!([]), |(_, word, _)| {
+                let locations: Vec<Value> = self.occurrences(&word)
+                    .into_iter()
+                    .map(|(uri, range)| json!({ "uri": uri, "range": range }))
+                    .collect();
+                json!(locations)
+            })),
+            "textDocument/rename" => Ok(self.word_at(params).map_or(Value::Null, |(_, word, _)| {
+                let mut changes = BTreeMap::<String, Vec<Value>>::new();
+                for (uri, range) in self.occurrences(&word) {
+                    changes.entry(uri).or_default().push(json!({ "range": range, "newText": params["newName"] }));
+                }
+                json!({ "changes": changes })
+            })),
+            "textDocument/formatting" => Ok(self.formatting(params)),
+            _ => Err(json!({ "code": -32601, "message": format!("Unhandled method {}", method) })),
+        };
+
+        let mut response = json!({ "jsonrpc": "2.0", "id": id });
+        match result {
+            Ok(result) => response["result"] = result,
+            Err(error) => response["error"] = error,
+        }
+        write_message(&response);
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+                self.documents.insert(uri.clone(), text);
+                self.publish_diagnostics(&uri, &params["textDocument"]["version"]);
+            }
+            "textDocument/didChange" => {
+                let Some(text) = self.documents.get_mut(&uri) else {
+                    return;
+                };
+                let changes = params["contentChanges"].as_array().cloned().unwrap_or_default();
+                for change in &changes {
+                    let inserted = change["text"].as_str().unwrap_or_default();
+                    match change.get("range") {
+                        Some(range) => {
+                            let start = offset(text, &range["start"]);
+                            let end = offset(text, &range["end"]).max(start);
+                            text.replace_range(start..end, inserted);
+                        }
+                        None => *text = inserted.to_string(),
+                    }
+                    if inserted.contains("@crash") {
+                        std::process::exit(1);
+                    }
+                }
+                self.publish_diagnostics(&uri, &params["textDocument"]["version"]);
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+            }
+            "exit" => std::process::exit(if self.shutdown { 0 } else { 1 }),
+            _ => {}
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: &str, version: &Value) {
+        let diagnostics: Vec<Value> = words(&self.documents[uri]).into_iter()
+            .filter_map(|(word, range)| {
+                let severity = match word.as_str() {
+                    "error" => 1,
+                    "warning" => 2,
+                    _ => return None,
+                };
+                Some(json!({
+                    "range": range,
+                    "severity": severity,
+                    "source": "mock",
+                    "message": format!("found {}", word),
+                }))
+            })
+            .collect();
+        write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "version": version, "diagnostics": diagnostics },
+        }));
+    }
+
+    /// The file, word and word range at a request's position
+    fn word_at(&self, params: &Value) -> Option<(String, String, Value)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let (line, character) = (params["position"]["line"].as_u64()?, params["position"]["character"].as_u64()?);
+        words(text).into_iter()
+            .find(|(_, range)| {
+                range["start"]["line"] == line
+                    && range["start"]["character"].as_u64() <= Some(character)
+                    && range["end"]["character"].as_u64() >= Some(character)
+            })
+            .map(|(word, range)| (uri.to_string(), word, range))
+    }
+
+    fn occurrences(&self, word: &str) -> Vec<(String, Value)> {
+        self.documents.iter()
+            .flat_map(|(uri, text)| {
+                words(text).into_iter()
+                    .filter(|(found, _)| found == word)
+                    .map(|(_, range)| (uri.clone(), range))
+            })
+            .collect()
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((_, word, range)) = self.word_at(params) else {
+            return json!([]);
+        };
+        // Only what is typed before the cursor counts
+        let typed = params["position"]["character"].as_u64().unwrap_or_default()
+            - range["start"]["character"].as_u64().unwrap_or_default();
+        let mut units = 0;
+        let prefix: String = word.chars()
+            .take_while(|ch| {
+                units += ch.len_utf16() as u64;
+                units <= typed
+            })
+            .collect();
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let mut labels: Vec<String> = words(&self.documents[uri]).into_iter()
+            .map(|(word, _)| word)
+            .filter(|candidate| candidate.starts_with(&prefix) && *candidate != prefix)
+            .collect();
+        labels.sort();
+        labels.dedup();
+        let items: Vec<Value> = labels.into_iter().map(|label| json!({ "label": label, "kind": 1 })).collect();
+        json!(items)
+    }
+
+    fn formatting(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(text) = self.documents.get(uri) else {
+            return Value::Null;
+        };
+        let edits: Vec<Value> = text.split('\n').enumerate()
+            .filter_map(|(line, content)| {
+                let content = content.trim_end_matches('\r');
+                let trimmed = content.trim_end();
+                (trimmed.len() < content.len()).then(|| json!({
+                    "range": {
+                        "start": { "line": line, "character": utf16_len(trimmed) },
+                        "end": { "line": line, "character": utf16_len(content) },
+                    },
+                    "newText": "",
+                }))
+            })
+            .collect();
+        json!(edits)
+    }
+}
+
+/// Words of the text with their LSP ranges
+fn words(text: &str) -> Vec<(String, Value)> {
+    let mut found = Vec::new();
+    for (line, content) in text.split('\n').enumerate() {
+        let mut word = String::new();
+        let mut start = 0;
+        let mut column = 0;
+        for ch in content.chars().chain(std::iter::once(' ')) {
+            if ch.is_alphanumeric() || ch == '_' {
+                if word.is_empty() {
+                    start = column;
+                }
+                word.push(ch);
+            } else if !word.is_empty() {
+                let range = json!({
+                    "start": { "line": line, "character": start },
+                    "end": { "line": line, "character": column },
+                });
+                found.push((std::mem::take(&mut word), range));
+            }
+            column += ch.len_utf16();
+        }
+    }
+    found
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Byte offset of an LSP position, clamped to the text
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+    let Some(line_start) = std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(at, _)| at + 1))
+        .nth(line)
+    else {
+        return text.len();
+    };
+    let content = &text[line_start..];
+    let content = &content[..content.find('\n').unwrap_or(content.len())];
+    let mut units = 0;
+    for (index, ch) in content.char_indices() {
+        if units >= character {
+            return line_start + index;
+        }
+        units += ch.len_utf16();
+    }
+    line_start + content.len()
+}
+
+fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn write_message(message: &Value) {
+    let body = message.to_string();
+    let mut stdout = io::stdout().lock();
+    let _ = write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = stdout.flush();
+}
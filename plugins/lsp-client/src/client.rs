@@ -0,0 +1,655 @@
+use lsp_types::notification::{self, Notification};
+use lsp_types::request;
+use lsp_types::*;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::packets::{LspRequest, LspResponse, ServerInfo, ServerStatus};
+use crate::server::{parse, LanguageServer, ResponseError, ServerConfig, ServerMessage};
+
+/// Restarts of a crashing server before giving up on it
+const MAX_RESTARTS: u32 = 5;
+
+/// Delay before the first restart, doubled for each one after
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+/// A server that ran this long before exiting starts counting restarts
+/// afresh
+const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+
+/// What the editor tells the client. Paths are files on disk; relative
+/// ones are taken from the working directory.
+#[derive(Debug, Clone)]
+pub enum LspCommand {
+    Open { path: PathBuf, language: String, text: String },
+    /// Changes in order, each in the coordinates the one before left
+    Change { path: PathBuf, changes: Vec<TextDocumentContentChangeEvent> },
+    Save { path: PathBuf },
+    Close { path: PathBuf },
+    Request { id: u64, path: PathBuf, request: LspRequest },
+}
+
+/// What the client tells the editor. Paths are absolute.
+#[derive(Debug, Clone)]
+pub enum LspEvent {
+    /// The full set for a file, replacing any sent before
+    Diagnostics { path: PathBuf, version: Option<i32>, diagnostics: Vec<Diagnostic> },
+    Response { id: u64, path: PathBuf, response: LspResponse },
+    /// A request that failed or could not be sent
+    Error { id: u64, path: PathBuf, message: String },
+    Server(ServerInfo),
+}
+
+/// Servers are per language and workspace root
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerKey {
+    pub language: String,
+    pub root: PathBuf,
+}
+
+struct ServerSlot {
+    name: String,
+    /// None while waiting to restart, or after giving up
+    server: Option<LanguageServer>,
+    status: ServerStatus,
+    started: Instant,
+    /// Restarts since the server last ran long enough to count as stable
+    restarts: u32,
+    restart_at: Option<Instant>,
+    /// Requests made before the server was initialized
+    waiting: Vec<(u64, PathBuf, LspRequest)>,
+}
+
+/// An open file and its text as the server has it, kept so the file can
+/// be opened again in a restarted server
+struct Document {
+    uri: Url,
+    key: ServerKey,
+    version: i32,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RequestKind {
+    Hover,
+    Completion,
+    Definition,
+    References,
+    Rename,
+    Formatting,
+}
+
+struct PendingRequest {
+    id: u64,
+    path: PathBuf,
+    kind: RequestKind,
+}
+
+/// Language servers for the files the editor has open
+///
+/// Files are opened in the server configured for their language, one per
+/// workspace root, started on first use. Servers that exit are started
+/// again with a growing delay, and are given the open files again, until
+/// they have exited `MAX_RESTARTS` times in a row without running for a
+/// minute. Nothing blocks: commands are sent as they come and `poll`
+/// collects what the servers sent back.
+pub struct LspClient {
+    /// By language id
+    configs: HashMap<String, ServerConfig>,
+    servers: HashMap<ServerKey, ServerSlot>,
+    /// By absolute path
+    documents: HashMap<PathBuf, Document>,
+    pending: HashMap<(ServerKey, i64), PendingRequest>,
+    events: Vec<LspEvent>,
+}
+
+impl Default for LspClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LspClient {
+    /// A client with rust-analyzer for Rust
+    pub fn new() -> Self {
+        Self {
+            configs: HashMap::from([("rust".to_string(), ServerConfig::rust_analyzer())]),
+            servers: HashMap::new(),
+            documents: HashMap::new(),
+            pending: HashMap::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Use `config` for files in `language`, from the next server started
+    pub fn set_server(&mut self, language: impl Into<String>, config: ServerConfig) {
+        self.configs.insert(language.into(), config);
+    }
+
+    pub fn servers(&self) -> Vec<ServerInfo> {
+        let mut servers: Vec<ServerInfo> = self.servers.iter().map(|(key, slot)| slot.info(key)).collect();
+        servers.sort_by(|a, b| (&a.language, &a.root).cmp(&(&b.language, &b.root)));
+        servers
+    }
+
+    pub fn handle(&mut self, command: LspCommand) {
+        match command {
+            LspCommand::Open { path, language, text } => self.open(absolute(&path), language, text),
+            LspCommand::Change { path, changes } => self.change(&absolute(&path), changes),
+            LspCommand::Save { path } => self.save(&absolute(&path)),
+            LspCommand::Close { path } => self.close(&absolute(&path)),
+            LspCommand::Request { id, path, request } => self.request(id, absolute(&path), request),
+        }
+    }
+
+    /// Responses, diagnostics and server changes since the last call.
+    /// Also where crashed servers are noticed and restarted.
+    pub fn poll(&mut self) -> Vec<LspEvent> {
+        let keys: Vec<ServerKey> = self.servers.keys().cloned().collect();
+        for key in keys {
+            self.poll_server(&key);
+        }
+        std::mem::take(&mut self.events)
+    }
+
+    /// Stop a server and start it again straight away
+    pub fn restart(&mut self, language: &str, root: &Path) -> Result<(), String> {
+        let key = ServerKey { language: language.to_string(), root: root.to_path_buf() };
+        let Some(slot) = self.servers.get_mut(&key) else {
+            return Err(format!("No {} server for {}", language, root.display()));
+        };
+        slot.restarts = 0;
+        if let Some(server) = slot.server.take() {
+            info!("Restarting {} for {}", slot.name, root.display());
+            tokio::spawn(server.shutdown());
+        }
+        self.fail_pending(&key, "Server restarted");
+        self.start_server(&key);
+        Ok(())
+    }
+
+    /// Shut every server down
+    pub async fn shutdown(&mut self) {
+        let mut shutdowns = tokio::task::JoinSet::new();
+        for (_, slot) in self.servers.drain() {
+            if let Some(server) = slot.server {
+                shutdowns.spawn(server.shutdown());
+            }
+        }
+        while shutdowns.join_next().await.is_some() {}
+        self.documents.clear();
+        self.pending.clear();
+    }
+
+    fn open(&mut self, path: PathBuf, language: String, text: String) {
+        if self.documents.contains_key(&path) {
+            // Opened again, maybe with other text: treat it as a change
+            self.change(&path, vec![TextDocumentContentChangeEvent { range: None, range_length: None, text }]);
+            return;
+        }
+        let Some(config) = self.configs.get(&language) else {
+            return;
+        };
+        let Ok(uri) = Url::from_file_path(&path) else {
+            warn!("Cannot open {} in a language server: not an absolute path", path.display());
+            return;
+        };
+
+        // A server already covering the file's directory is used before
+        // looking for a workspace root of its own
+        let key = self.servers.keys()
+            .filter(|key| key.language == language && path.starts_with(&key.root))
+            .max_by_key(|key| key.root.components().count())
+            .cloned()
+            .unwrap_or_else(|| ServerKey { root: config.workspace_root(&path), language });
+
+        self.documents.insert(path.clone(), Document { uri, key: key.clone(), version: 0, text });
+        if self.servers.contains_key(&key) {
+            self.did_open(&path);
+        } else {
+            // The file is opened once the new server is initialized
+            self.start_server(&key);
+        }
+    }
+
+    fn change(&mut self, path: &Path, changes: Vec<TextDocumentContentChangeEvent>) {
+        let Some(document) = self.documents.get_mut(path) else {
+            return;
+        };
+        document.version += 1;
+        for change in &changes {
+            apply_change(&mut document.text, change);
+        }
+        let Some(server) = initialized(&mut self.servers, &document.key) else {
+            return;
+        };
+        let changes = match sync_kind(server.capabilities()) {
+            TextDocumentSyncKind::INCREMENTAL => changes,
+            TextDocumentSyncKind::FULL => vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: document.text.clone(),
+            }],
+            _ => return,
+        };
+        server.notify::<notification::DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier { uri: document.uri.clone(), version: document.version },
+            content_changes: changes,
+        });
+    }
+
+    fn save(&mut self, path: &Path) {
+        let Some(document) = self.documents.get(path) else {
+            return;
+        };
+        let Some(server) = initialized(&mut self.servers, &document.key) else {
+            return;
+        };
+        if sends_save(server.capabilities()) {
+            server.notify::<notification::DidSaveTextDocument>(DidSaveTextDocumentParams {
+                text_document: TextDocumentIdentifier { uri: document.uri.clone() },
+                text: None,
+            });
+        }
+    }
+
+    fn close(&mut self, path: &Path) {
+        // Servers keep running with no files open: starting rust-analyzer
+        // again costs more than leaving it idle
+        let Some(document) = self.documents.remove(path) else {
+            return;
+        };
+        let Some(server) = initialized(&mut self.servers, &document.key) else {
+            return;
+        };
+        if sends_open_close(server.capabilities()) {
+            server.notify::<notification::DidCloseTextDocument>(DidCloseTextDocumentParams {
+                text_document: TextDocumentIdentifier { uri: document.uri },
+            });
+        }
+    }
+
+    fn request(&mut self, id: u64, path: PathBuf, request: LspRequest) {
+        let Some(document) = self.documents.get(&path) else {
+            let message = format!("No language server for {}", path.display());
+            self.events.push(LspEvent::Error { id, path, message });
+            return;
+        };
+        let key = document.key.clone();
+        let slot = self.servers.get_mut(&key).expect("open documents have a server");
+        match (&slot.server, &slot.status) {
+            (Some(server), _) if server.capabilities().is_some() => self.send_request(&key, id, path, request),
+            (Some(_), _) => slot.waiting.push((id, path, request)),
+            (None, ServerStatus::Failed { message }) => {
+                let message = format!("{} is not running: {}", slot.name, message);
+                self.events.push(LspEvent::Error { id, path, message });
+            }
+            (None, _) => {
+                let message = format!("{} is restarting", slot.name);
+                self.events.push(LspEvent::Error { id, path, message });
+            }
+        }
+    }
+
+    fn send_request(&mut self, key: &ServerKey, id: u64, path: PathBuf, request: LspRequest) {
+        let uri = self.documents[&path].uri.clone();
+        let slot = self.servers.get_mut(key).expect("requests are for known servers");
+        let Some(server) = slot.server.as_mut() else {
+            return;
+        };
+        let capabilities = server.capabilities().cloned().unwrap_or_default();
+        let at = |position| TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position,
+        };
+
+        let (kind, server_id) = match request {
+            LspRequest::Hover { position } if supports_hover(&capabilities) => {
+                (RequestKind::Hover, server.request::<request::HoverRequest>(HoverParams {
+                    text_document_position_params: at(position),
+                    work_done_progress_params: Default::default(),
+                }))
+            }
+            LspRequest::Completion { position } if capabilities.completion_provider.is_some() => {
+                (RequestKind::Completion, server.request::<request::Completion>(CompletionParams {
+                    text_document_position: at(position),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                    context: None,
+                }))
+            }
+            LspRequest::Definition { position } if enabled(&capabilities.definition_provider) => {
+                (RequestKind::Definition, server.request::<request::GotoDefinition>(GotoDefinitionParams {
+                    text_document_position_params: at(position),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                }))
+            }
+            LspRequest::References { position, include_declaration } if enabled(&capabilities.references_provider) => {
+                (RequestKind::References, server.request::<request::References>(ReferenceParams {
+                    text_document_position: at(position),
+                    work_done_progress_params: Default::default(),
+                    partial_result_params: Default::default(),
+                    context: ReferenceContext { include_declaration },
+                }))
+            }
+            LspRequest::Rename { position, new_name } if enabled(&capabilities.rename_provider) => {
+                (RequestKind::Rename, server.request::<request::Rename>(RenameParams {
+                    text_document_position: at(position),
+                    new_name,
+                    work_done_progress_params: Default::default(),
+                }))
+            }
+            LspRequest::Formatting { options } if enabled(&capabilities.document_formatting_provider) => {
+                (RequestKind::Formatting, server.request::<request::Formatting>(DocumentFormattingParams {
+                    text_document: TextDocumentIdentifier { uri },
+                    options,
+                    work_done_progress_params: Default::default(),
+                }))
+            }
+            request => {
+                let message = format!("{} does not support {}", slot.name, request_name(&request));
+                self.events.push(LspEvent::Error { id, path, message });
+                return;
+            }
+        };
+        self.pending.insert((key.clone(), server_id), PendingRequest { id, path, kind });
+    }
+
+    fn poll_server(&mut self, key: &ServerKey) {
+        let Some(slot) = self.servers.get_mut(key) else {
+            return;
+        };
+        let Some(server) = slot.server.as_mut() else {
+            if slot.restart_at.is_some_and(|at| Instant::now() >= at) {
+                self.start_server(key);
+            }
+            return;
+        };
+        // Whatever the server sent before exiting is still handled
+        let messages = server.receive();
+        let exited = server.exit_status();
+        for message in messages {
+            self.handle_message(key, message);
+        }
+        if let Some(exit_code) = exited {
+            self.server_exited(key, exit_code);
+        }
+    }
+
+    fn handle_message(&mut self, key: &ServerKey, message: ServerMessage) {
+        match message {
+            ServerMessage::Initialized => {
+                let slot = self.servers.get_mut(key).expect("messages come from known servers");
+                info!("{} running for {}", slot.name, key.root.display());
+                slot.status = ServerStatus::Running;
+                let waiting = std::mem::take(&mut slot.waiting);
+                self.events.push(LspEvent::Server(slot.info(key)));
+
+                let paths: Vec<PathBuf> = self.documents.iter()
+                    .filter(|(_, document)| &document.key == key)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+                for path in paths {
+                    self.did_open(&path);
+                }
+                for (id, path, request) in waiting {
+                    self.send_request(key, id, path, request);
+                }
+            }
+            ServerMessage::Response { id, result } => {
+                let Some(pending) = self.pending.remove(&(key.clone(), id)) else {
+                    return;
+                };
+                let event = match response(pending.kind, result) {
+                    Ok(response) => LspEvent::Response { id: pending.id, path: pending.path, response },
+                    Err(e) => LspEvent::Error { id: pending.id, path: pending.path, message: e.message },
+                };
+                self.events.push(event);
+            }
+            ServerMessage::Notification { method, params } => self.handle_notification(key, &method, params),
+        }
+    }
+
+    fn handle_notification(&mut self, key: &ServerKey, method: &str, params: Value) {
+        let name = &self.servers[key].name;
+        match method {
+            notification::PublishDiagnostics::METHOD => {
+                let Ok(params) = serde_json::from_value::<PublishDiagnosticsParams>(params) else {
+                    warn!("Malformed diagnostics from {}", name);
+                    return;
+                };
+                if let Ok(path) = params.uri.to_file_path() {
+                    self.events.push(LspEvent::Diagnostics {
+                        path,
+                        version: params.version,
+                        diagnostics: params.diagnostics,
+                    });
+                }
+            }
+            notification::ShowMessage::METHOD | notification::LogMessage::METHOD => {
+                let Ok(params) = serde_json::from_value::<ShowMessageParams>(params) else {
+                    return;
+                };
+                match params.typ {
+                    MessageType::ERROR => warn!("{}: {}", name, params.message),
+                    MessageType::WARNING if method == notification::ShowMessage::METHOD => info!("{}: {}", name, params.message),
+                    _ => debug!("{}: {}", name, params.message),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn server_exited(&mut self, key: &ServerKey, exit_code: Option<i32>) {
+        let slot = self.servers.get_mut(key).expect("exits come from known servers");
+        slot.server = None;
+        if slot.started.elapsed() >= STABLE_RUNTIME {
+            slot.restarts = 0;
+        }
+
+        if slot.restarts >= MAX_RESTARTS {
+            warn!("{} for {} exited {} times, not restarting it", slot.name, key.root.display(), slot.restarts + 1);
+            slot.status = ServerStatus::Failed {
+                message: format!("Exited {} times in a row", slot.restarts + 1),
+            };
+        } else {
+            let delay = RESTART_DELAY * 2u32.pow(slot.restarts);
+            slot.restarts += 1;
+            warn!("{} for {} exited with {:?}, restarting in {:?}", slot.name, key.root.display(), exit_code, delay);
+            slot.restart_at = Some(Instant::now() + delay);
+            slot.status = ServerStatus::Restarting { exit_code, attempt: slot.restarts };
+        }
+        let waiting = std::mem::take(&mut slot.waiting);
+        let message = format!("{} exited", slot.name);
+        self.events.push(LspEvent::Server(slot.info(key)));
+        for (id, path, _) in waiting {
+            self.events.push(LspEvent::Error { id, path, message: message.clone() });
+        }
+        self.fail_pending(key, &message);
+    }
+
+    fn start_server(&mut self, key: &ServerKey) {
+        let config = self.configs.get(&key.language).expect("servers are started for configured languages");
+        let name = config.name().to_string();
+        let spawned = LanguageServer::spawn(config, &key.root);
+        let slot = self.servers.entry(key.clone()).or_insert_with(|| ServerSlot {
+            name,
+            server: None,
+            status: ServerStatus::Starting,
+            started: Instant::now(),
+            restarts: 0,
+            restart_at: None,
+            waiting: Vec::new(),
+        });
+        slot.restart_at = None;
+        match spawned {
+            Ok(server) => {
+                info!("Started {} for {}", slot.name, key.root.display());
+                slot.server = Some(server);
+                slot.started = Instant::now();
+                slot.status = ServerStatus::Starting;
+            }
+            Err(e) => {
+                // A server that cannot be started at all is not retried
+                warn!("{}", e);
+                slot.status = ServerStatus::Failed { message: e };
+            }
+        }
+        self.events.push(LspEvent::Server(slot.info(key)));
+    }
+
+    fn did_open(&mut self, path: &Path) {
+        let document = &self.documents[path];
+        let Some(server) = initialized(&mut self.servers, &document.key) else {
+            return;
+        };
+        if sends_open_close(server.capabilities()) {
+            server.notify::<notification::DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: document.uri.clone(),
+                    language_id: document.key.language.clone(),
+                    version: document.version,
+                    text: document.text.clone(),
+                },
+            });
+        }
+    }
+
+    fn fail_pending(&mut self, key: &ServerKey, message: &str) {
+        let ids: Vec<(ServerKey, i64)> = self.pending.keys().filter(|(server, _)| server == key).cloned().collect();
+        for id in ids {
+            if let Some(pending) = self.pending.remove(&id) {
+                self.events.push(LspEvent::Error { id: pending.id, path: pending.path, message: message.to_string() });
+            }
+        }
+    }
+}
+
+impl ServerSlot {
+    fn info(&self, key: &ServerKey) -> ServerInfo {
+        ServerInfo {
+            name: self.name.clone(),
+            language: key.language.clone(),
+            root: key.root.clone(),
+            status: self.status.clone(),
+        }
+    }
+}
+
+/// The server for `key` if it is running and initialized
+fn initialized<'a>(servers: &'a mut HashMap<ServerKey, ServerSlot>, key: &ServerKey) -> Option<&'a mut LanguageServer> {
+    servers.get_mut(key)?.server.as_mut().filter(|server| server.capabilities().is_some())
+}
+
+fn response(kind: RequestKind, result: Result<Value, ResponseError>) -> Result<LspResponse, ResponseError> {
+    Ok(match kind {
+        RequestKind::Hover => LspResponse::Hover(parse(result)?),
+        RequestKind::Completion => LspResponse::Completion(match parse(result)? {
+            Some(CompletionResponse::Array(items)) => items,
+            Some(CompletionResponse::List(list)) => list.items,
+            None => Vec::new(),
+        }),
+        RequestKind::Definition => LspResponse::Locations(match parse(result)? {
+            Some(GotoDefinitionResponse::Scalar(location)) => vec![location],
+            Some(GotoDefinitionResponse::Array(locations)) => locations,
+            Some(GotoDefinitionResponse::Link(links)) => links.into_iter()
+                .map(|link| Location { uri: link.target_uri, range: link.target_selection_range })
+                .collect(),
+            None => Vec::new(),
+        }),
+        RequestKind::References => LspResponse::Locations(parse::<Option<Vec<Location>>>(result)?.unwrap_or_default()),
+        RequestKind::Rename => LspResponse::Edit(parse::<Option<WorkspaceEdit>>(result)?.unwrap_or_default()),
+        RequestKind::Formatting => LspResponse::TextEdits(parse::<Option<Vec<TextEdit>>>(result)?.unwrap_or_default()),
+    })
+}
+
+fn request_name(request: &LspRequest) -> &'static str {
+    match request {
+        LspRequest::Hover { .. } => "hover",
+        LspRequest::Completion { .. } => "completion",
+        LspRequest::Definition { .. } => "go to definition",
+        LspRequest::References { .. } => "references",
+        LspRequest::Rename { .. } => "rename",
+        LspRequest::Formatting { .. } => "formatting",
+    }
+}
+
+fn sync_kind(capabilities: Option<&ServerCapabilities>) -> TextDocumentSyncKind {
+    match capabilities.and_then(|capabilities| capabilities.text_document_sync.as_ref()) {
+        Some(TextDocumentSyncCapability::Kind(kind)) => *kind,
+        Some(TextDocumentSyncCapability::Options(options)) => options.change.unwrap_or(TextDocumentSyncKind::NONE),
+        None => TextDocumentSyncKind::NONE,
+    }
+}
+
+fn sends_open_close(capabilities: Option<&ServerCapabilities>) -> bool {
+    match capabilities.and_then(|capabilities| capabilities.text_document_sync.as_ref()) {
+        Some(TextDocumentSyncCapability::Kind(kind)) => *kind != TextDocumentSyncKind::NONE,
+        Some(TextDocumentSyncCapability::Options(options)) => options.open_close == Some(true),
+        None => false,
+    }
+}
+
+fn sends_save(capabilities: Option<&ServerCapabilities>) -> bool {
+    match capabilities.and_then(|capabilities| capabilities.text_document_sync.as_ref()) {
+        Some(TextDocumentSyncCapability::Options(options)) => match &options.save {
+            Some(TextDocumentSyncSaveOptions::Supported(supported)) => *supported,
+            Some(TextDocumentSyncSaveOptions::SaveOptions(_)) => true,
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+fn supports_hover(capabilities: &ServerCapabilities) -> bool {
+    match &capabilities.hover_provider {
+        Some(HoverProviderCapability::Simple(supported)) => *supported,
+        Some(HoverProviderCapability::Options(_)) => true,
+        None => false,
+    }
+}
+
+fn enabled<T>(provider: &Option<OneOf<bool, T>>) -> bool {
+    matches!(provider, Some(OneOf::Left(true) | OneOf::Right(_)))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Apply a change to a copy of a document's text
+fn apply_change(text: &mut String, change: &TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = offset(text, range.start);
+            let end = offset(text, range.end).max(start);
+            text.replace_range(start..end, &change.text);
+        }
+        None => text.clone_from(&change.text),
+    }
+}
+
+/// Byte offset of an LSP position, clamped to the text
+fn offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(newline) => line_start += newline + 1,
+            None => return text.len(),
+        }
+    }
+    let line = &text[line_start..];
+    let line = &line[..line.find('\n').unwrap_or(line.len())];
+    let mut units = 0;
+    for (index, ch) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + index;
+        }
+        units += ch.len_utf16();
+    }
+    line_start + line.len()
+}
@@ -1,3 +1,13 @@
 mod plugin;
+mod transport;
+mod server;
+mod client;
+mod packets;
 
 pub use plugin::LspClientPlugin;
+pub use server::{LanguageServer, ServerConfig, ServerMessage, ResponseError};
+pub use client::{LspClient, LspCommand, LspEvent, ServerKey};
+pub use packets::{LspRequest, LspResponse, ServerInfo, ServerStatus};
+
+/// The protocol types the packets speak in
+pub use lsp_types;
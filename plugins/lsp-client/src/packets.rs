@@ -0,0 +1,152 @@
+//! Packet types and messages on the LSP client plugin's channel. The
+//! browser gets the list of running servers there; editor-core sends its
+//! open files and requests there and gets diagnostics and answers back on
+//! its own channel. Messages are JSON. Editor-core keeps a copy of the
+//! editor messages in its packets module, so the two must change together.
+
+use lsp_types::{
+    CompletionItem, Diagnostic, FormattingOptions, Hover, Location, Position, TextDocumentContentChangeEvent,
+    TextEdit, WorkspaceEdit,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Messages from browser to server (1-99)
+/// Sent on (re)connect to get the server list
+pub const PACKET_TYPE_ATTACH: u16 = 1;
+pub const PACKET_TYPE_RESTART: u16 = 2;
+
+// Messages from server to browser (100-199)
+pub const PACKET_TYPE_SERVERS: u16 = 100;
+pub const PACKET_TYPE_ERROR: u16 = 101;
+
+// Messages from editor-core (200-299)
+pub const PACKET_TYPE_DOCUMENT_OPEN: u16 = 200;
+pub const PACKET_TYPE_DOCUMENT_CHANGE: u16 = 201;
+pub const PACKET_TYPE_DOCUMENT_SAVE: u16 = 202;
+pub const PACKET_TYPE_DOCUMENT_CLOSE: u16 = 203;
+pub const PACKET_TYPE_REQUEST: u16 = 204;
+
+// Messages to editor-core, on its channel (300-399)
+pub const PACKET_TYPE_LSP_DIAGNOSTICS: u16 = 300;
+pub const PACKET_TYPE_LSP_RESPONSE: u16 = 301;
+pub const PACKET_TYPE_LSP_ERROR: u16 = 302;
+pub const PACKET_TYPE_LSP_SERVER: u16 = 303;
+
+/// Restart a server by its language and root, as listed
+#[derive(Debug, Clone, Deserialize)]
+pub struct RestartMessage {
+    pub language: String,
+    pub root: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServersMessage {
+    pub servers: Vec<ServerInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorMessage {
+    pub message: String,
+}
+
+/// Open a file in its language's server. Paths are files on disk; relative
+/// ones are taken from the working directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentOpenMessage {
+    pub path: PathBuf,
+    pub language: String,
+    pub text: String,
+}
+
+/// Changes in order, each in the coordinates the one before left
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentChangeMessage {
+    pub path: PathBuf,
+    pub changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+/// Save, close
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentMessage {
+    pub path: PathBuf,
+}
+
+/// A question about an open file; the answer carries `id`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub request: LspRequest,
+}
+
+/// A question about an open file. Positions are LSP positions, with
+/// UTF-16 columns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LspRequest {
+    Hover { position: Position },
+    Completion { position: Position },
+    Definition { position: Position },
+    References { position: Position, include_declaration: bool },
+    Rename { position: Position, new_name: String },
+    Formatting { options: FormattingOptions },
+}
+
+/// Answers, by the kind of request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "result", rename_all = "snake_case")]
+pub enum LspResponse {
+    Hover(Option<Hover>),
+    Completion(Vec<CompletionItem>),
+    /// Definitions and references
+    Locations(Vec<Location>),
+    /// Renames; empty when there is nothing to change
+    Edit(WorkspaceEdit),
+    /// Formatting, against the text as it was when asked
+    TextEdits(Vec<TextEdit>),
+}
+
+/// The full set for a file, replacing any sent before. Paths from here on
+/// are absolute.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsMessage {
+    pub path: PathBuf,
+    pub version: Option<i32>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub response: LspResponse,
+}
+
+/// A request that failed or could not be sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestErrorMessage {
+    pub id: u64,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// A language server and how it is doing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub name: String,
+    pub language: String,
+    pub root: PathBuf,
+    pub status: ServerStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ServerStatus {
+    Starting,
+    Running,
+    /// Exited unexpectedly; starting again after a delay
+    Restarting { exit_code: Option<i32>, attempt: u32 },
+    /// Could not be started, or kept exiting
+    Failed { message: String },
+}
@@ -1,27 +1,162 @@
 use async_trait::async_trait;
 use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle};
-use tracing::{info, debug};
+use playground_core_types::Priority;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, debug, warn};
+
+use crate::client::{LspClient, LspCommand, LspEvent};
+use crate::packets::*;
+use crate::server::ServerConfig;
 
 pub struct LspClientPlugin {
     channel_id: Option<u16>,
+    /// Where diagnostics and answers go, when editor-core is registered
+    editor_channel: Option<u16>,
     systems_manager: Handle<SystemsManager>,
-    // LSP-specific fields
-    // e.g., language_servers: HashMap<String, LspConnection>
+    /// Language servers and the files open in them
+    client: LspClient,
 }
 
 impl LspClientPlugin {
     pub fn new(systems_manager: Handle<SystemsManager>) -> Self {
         Self {
             channel_id: None,
+            editor_channel: None,
             systems_manager,
+            client: LspClient::new(),
         }
     }
-    
+
+    /// Use `config` for files in `language`, replacing the default
+    /// rust-analyzer for "rust"
+    pub fn with_server(mut self, language: impl Into<String>, config: ServerConfig) -> Self {
+        self.client.set_server(language, config);
+        self
+    }
+
     async fn setup(&mut self) -> LogicResult<()> {
-        // Initialize LSP client infrastructure
+        // Servers start when the first file in their language is opened
         debug!("LSP client plugin setting up language server connections");
         Ok(())
     }
+
+    async fn handle_packet(&mut self, packet_type: u16, data: Vec<u8>) {
+        match packet_type {
+            PACKET_TYPE_ATTACH => self.send_servers().await,
+            PACKET_TYPE_RESTART => {
+                if let Some(message) = self.decode::<RestartMessage>(packet_type, &data)
+                    && let Err(message) = self.client.restart(&message.language, &message.root)
+                {
+                    self.send(PACKET_TYPE_ERROR, &ErrorMessage { message }).await;
+                }
+            }
+            PACKET_TYPE_DOCUMENT_OPEN => {
+                if let Some(DocumentOpenMessage { path, language, text }) = self.decode(packet_type, &data) {
+                    self.client.handle(LspCommand::Open { path, language, text });
+                }
+            }
+            PACKET_TYPE_DOCUMENT_CHANGE => {
+                if let Some(DocumentChangeMessage { path, changes }) = self.decode(packet_type, &data) {
+                    self.client.handle(LspCommand::Change { path, changes });
+                }
+            }
+            PACKET_TYPE_DOCUMENT_SAVE => {
+                if let Some(DocumentMessage { path }) = self.decode(packet_type, &data) {
+                    self.client.handle(LspCommand::Save { path });
+                }
+            }
+            PACKET_TYPE_DOCUMENT_CLOSE => {
+                if let Some(DocumentMessage { path }) = self.decode(packet_type, &data) {
+                    self.client.handle(LspCommand::Close { path });
+                }
+            }
+            PACKET_TYPE_REQUEST => {
+                if let Some(RequestMessage { id, path, request }) = self.decode(packet_type, &data) {
+                    self.client.handle(LspCommand::Request { id, path, request });
+                }
+            }
+            _ => debug!("Unknown packet type {} received on lsp-client channel", packet_type),
+        }
+    }
+
+    /// Send editor-core what the servers sent back
+    async fn send_events(&mut self) {
+        let events = self.client.poll();
+        let servers_changed = events.iter().any(|event| matches!(event, LspEvent::Server(_)));
+        for event in events {
+            match event {
+                LspEvent::Diagnostics { path, version, diagnostics } => {
+                    self.send_to_editor(PACKET_TYPE_LSP_DIAGNOSTICS, &DiagnosticsMessage { path, version, diagnostics }).await;
+                }
+                LspEvent::Response { id, path, response } => {
+                    self.send_to_editor(PACKET_TYPE_LSP_RESPONSE, &ResponseMessage { id, path, response }).await;
+                }
+                LspEvent::Error { id, path, message } => {
+                    self.send_to_editor(PACKET_TYPE_LSP_ERROR, &RequestErrorMessage { id, path, message }).await;
+                }
+                LspEvent::Server(server) => self.send_to_editor(PACKET_TYPE_LSP_SERVER, &server).await,
+            }
+        }
+        if servers_changed {
+            self.send_servers().await;
+        }
+    }
+
+    async fn send_servers(&self) {
+        self.send(PACKET_TYPE_SERVERS, &ServersMessage { servers: self.client.servers() }).await;
+    }
+
+    async fn send<T: Serialize>(&self, packet_type: u16, message: &T) {
+        if let Some(channel_id) = self.channel_id {
+            self.send_on(channel_id, packet_type, message).await;
+        }
+    }
+
+    /// Results go straight onto editor-core's channel, without passing
+    /// through any browser
+    async fn send_to_editor<T: Serialize>(&self, packet_type: u16, message: &T) {
+        let (Some(channel_id), Some(data)) = (self.editor_channel, self.encode(packet_type, message)) else {
+            return;
+        };
+
+        let networking = self.systems_manager.networking();
+        let net = networking.read().await;
+        if let Err(e) = net.send_to_plugin("lsp-client", channel_id, packet_type, data).await {
+            warn!("Failed to send lsp-client packet {} to editor-core: {}", packet_type, e);
+        }
+    }
+
+    async fn send_on<T: Serialize>(&self, channel_id: u16, packet_type: u16, message: &T) {
+        let Some(data) = self.encode(packet_type, message) else {
+            return;
+        };
+
+        let networking = self.systems_manager.networking();
+        let net = networking.read().await;
+        if let Err(e) = net.send_packet(channel_id, packet_type, data, Priority::High).await {
+            warn!("Failed to send lsp-client packet {}: {}", packet_type, e);
+        }
+    }
+
+    fn encode<T: Serialize>(&self, packet_type: u16, message: &T) -> Option<Vec<u8>> {
+        match serde_json::to_vec(message) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                warn!("Failed to serialize lsp-client packet {}: {}", packet_type, e);
+                None
+            }
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&self, packet_type: u16, data: &[u8]) -> Option<T> {
+        match serde_json::from_slice(data) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                warn!("Malformed lsp-client packet {}: {}", packet_type, e);
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -36,6 +171,12 @@ impl System for LspClientPlugin {
         
         info!("LSP Client Plugin initialized on dynamic channel {}", self.channel_id.unwrap());
         
+        // Diagnostics and answers go back to the plugin that owns the buffers
+        self.editor_channel = self.systems_manager.get_plugin_channel("editor-core").await;
+        if self.editor_channel.is_none() {
+            warn!("editor-core is not registered, language server results have nowhere to go");
+        }
+        
         // Plugin-specific initialization
         self.setup().await?;
         
@@ -44,13 +185,27 @@ impl System for LspClientPlugin {
     }
     
     async fn run(&mut self, _world: &World, _delta_time: f32) -> LogicResult<()> {
-        // Process LSP messages, handle completions, diagnostics, etc.
+        // Server list and restart requests from the browser, open files
+        // and requests from editor-core
+        if let Some(channel_id) = self.channel_id {
+            let packets = {
+                let networking = self.systems_manager.networking();
+                let net = networking.read().await;
+                net.receive_packets(channel_id).await.unwrap_or_default()
+            };
+            for packet in packets {
+                self.handle_packet(packet.packet_type, packet.data).await;
+            }
+        }
+
+        self.send_events().await;
         Ok(())
     }
     
     async fn cleanup(&mut self, _world: &World) -> LogicResult<()> {
         info!("LSP client plugin shutting down");
         // Disconnect from language servers
+        self.client.shutdown().await;
         Ok(())
     }
-}
\ No newline at end of file
+}
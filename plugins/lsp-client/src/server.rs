@@ -0,0 +1,395 @@
+use lsp_types::notification::{self, Notification};
+use lsp_types::request::{self, Request};
+use lsp_types::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::transport::{read_message, write_message};
+
+/// How long a server gets to answer `shutdown`, and then to exit
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// JSON-RPC error code for requests the client does not handle
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// How to start a language server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    /// Files that mark a workspace root, in order of preference. A file's
+    /// workspace is the nearest directory above it holding the first
+    /// marker that is found at all.
+    pub root_markers: Vec<String>,
+    /// Sent as `initializationOptions`
+    pub initialization_options: Option<Value>,
+}
+
+impl ServerConfig {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            root_markers: Vec::new(),
+            initialization_options: None,
+        }
+    }
+
+    /// rust-analyzer from the PATH. `Cargo.lock` comes first so member
+    /// crates share their Cargo workspace's server.
+    pub fn rust_analyzer() -> Self {
+        Self {
+            root_markers: vec!["Cargo.lock".to_string(), "Cargo.toml".to_string()],
+            ..Self::new("rust-analyzer")
+        }
+    }
+
+    /// Workspace root for an absolute file path, or the file's directory
+    /// when no marker is found
+    pub fn workspace_root(&self, file: &Path) -> PathBuf {
+        let directory = file.parent().unwrap_or(file);
+        self.root_markers.iter()
+            .find_map(|marker| directory.ancestors().find(|dir| dir.join(marker).is_file()))
+            .unwrap_or(directory)
+            .to_path_buf()
+    }
+
+    /// Short name for logs and status, such as `rust-analyzer`
+    pub fn name(&self) -> &str {
+        Path::new(&self.command).file_name().and_then(|name| name.to_str()).unwrap_or(&self.command)
+    }
+}
+
+/// An error response from a server
+#[derive(Debug, Clone)]
+pub struct ResponseError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+/// A message from a server that the client has to act on. Requests from
+/// the server are answered by `LanguageServer` itself.
+#[derive(Debug)]
+pub enum ServerMessage {
+    /// The server answered `initialize` and has been sent `initialized`
+    Initialized,
+    Response { id: i64, result: Result<Value, ResponseError> },
+    Notification { method: String, params: Value },
+}
+
+/// A language server process spoken to over stdio
+///
+/// `spawn` sends `initialize` straight away. Nothing else should be sent
+/// until `receive` has returned `ServerMessage::Initialized`.
+pub struct LanguageServer {
+    name: String,
+    child: Child,
+    outgoing: mpsc::UnboundedSender<Value>,
+    incoming: mpsc::UnboundedReceiver<Value>,
+    next_id: i64,
+    initialize_id: i64,
+    /// Set once the server has answered `initialize`
+    capabilities: Option<ServerCapabilities>,
+}
+
+impl LanguageServer {
+    /// Start a server for the workspace at `root`, which must be absolute
+    pub fn spawn(config: &ServerConfig, root: &Path) -> Result<Self, String> {
+        let name = config.name().to_string();
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", config.command, e))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        // Writes and reads each get a task, so a slow server never blocks
+        // the plugin; both end when the process closes its pipes
+        let (outgoing, mut to_server) = mpsc::unbounded_channel::<Value>();
+        let writer_name = name.clone();
+        tokio::spawn(async move {
+            while let Some(message) = to_server.recv().await {
+                if let Err(e) = write_message(&mut stdin, &message).await {
+                    debug!("Stopped writing to {}: {}", writer_name, e);
+                    break;
+                }
+            }
+        });
+
+        let (from_server, incoming) = mpsc::unbounded_channel();
+        let reader_name = name.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                match read_message(&mut reader).await {
+                    Ok(Some(message)) => {
+                        if from_server.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        warn!("Stopped reading from {}: {}", reader_name, e);
+                        break;
+                    }
+                }
+            }
+        });
+
+        let stderr_name = name.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                debug!("{}: {}", stderr_name, line);
+            }
+        });
+
+        let mut server = Self {
+            name,
+            child,
+            outgoing,
+            incoming,
+            next_id: 0,
+            initialize_id: 0,
+            capabilities: None,
+        };
+        server.initialize_id = server.next_id();
+        let params = serde_json::to_value(initialize_params(config, root)?).map_err(|e| e.to_string())?;
+        server.send(message(Some(server.initialize_id.into()), request::Initialize::METHOD, params));
+        Ok(server)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// What the server can do, once it has been initialized
+    pub fn capabilities(&self) -> Option<&ServerCapabilities> {
+        self.capabilities.as_ref()
+    }
+
+    /// Send a request, returning the id its response will carry
+    pub fn request<R: Request>(&mut self, params: R::Params) -> i64 {
+        let id = self.next_id();
+        self.send(message(Some(id.into()), R::METHOD, json!(params)));
+        id
+    }
+
+    pub fn notify<N: Notification>(&mut self, params: N::Params) {
+        self.send(message(None, N::METHOD, json!(params)));
+    }
+
+    /// Responses and notifications received since the last call
+    pub fn receive(&mut self) -> Vec<ServerMessage> {
+        let mut received = Vec::new();
+        while let Ok(mut message) = self.incoming.try_recv() {
+            let method = message.get("method").and_then(Value::as_str).map(str::to_string);
+            let params = message.get_mut("params").map(Value::take).unwrap_or(Value::Null);
+            match (method, message.get("id").cloned()) {
+                (Some(method), Some(id)) => self.answer(id, &method, &params),
+                (Some(method), None) => received.push(ServerMessage::Notification { method, params }),
+                (None, Some(id)) => {
+                    let Some(id) = id.as_i64() else {
+                        continue;
+                    };
+                    let result = match message.get("error") {
+                        Some(error) => Err(ResponseError {
+                            code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+                            message: error.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+                        }),
+                        None => Ok(message.get_mut("result").map(Value::take).unwrap_or(Value::Null)),
+                    };
+                    if id == self.initialize_id {
+                        if self.initialized(result) {
+                            received.push(ServerMessage::Initialized);
+                        }
+                    } else {
+                        received.push(ServerMessage::Response { id, result });
+                    }
+                }
+                (None, None) => debug!("Ignoring message without method or id from {}", self.name),
+            }
+        }
+        received
+    }
+
+    /// The exit code once the process has exited; `Some(None)` when it was
+    /// killed by a signal
+    pub fn exit_status(&mut self) -> Option<Option<i32>> {
+        self.child.try_wait().ok().flatten().map(|status| status.code())
+    }
+
+    /// Ask the server to shut down and exit, killing it if it does not
+    pub async fn shutdown(mut self) {
+        if self.capabilities.is_some() {
+            let id = self.request::<request::Shutdown>(());
+            let incoming = &mut self.incoming;
+            let answered = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+                while let Some(message) = incoming.recv().await {
+                    if message.get("method").is_none() && message.get("id").and_then(Value::as_i64) == Some(id) {
+                        break;
+                    }
+                }
+            }).await;
+            if answered.is_err() {
+                debug!("{} did not answer shutdown", self.name);
+            }
+            self.notify::<notification::Exit>(());
+        }
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, self.child.wait()).await.is_err() {
+            warn!("{} did not exit, killing it", self.name);
+            let _ = self.child.kill().await;
+        }
+    }
+
+    fn next_id(&mut self) -> i64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn send(&mut self, message: Value) {
+        let _ = self.outgoing.send(message);
+    }
+
+    fn initialized(&mut self, result: Result<Value, ResponseError>) -> bool {
+        match parse::<InitializeResult>(result) {
+            Ok(result) => {
+                debug!("{} initialized", self.name);
+                self.capabilities = Some(result.capabilities);
+                self.notify::<notification::Initialized>(InitializedParams {});
+                true
+            }
+            Err(e) => {
+                // Without capabilities the server is no use; stopping it
+                // lets the client treat this like any other crash
+                warn!("{} failed to initialize: {}", self.name, e);
+                let _ = self.child.start_kill();
+                false
+            }
+        }
+    }
+
+    /// Answer a request from the server
+    fn answer(&mut self, id: Value, method: &str, params: &Value) {
+        let result = match method {
+            request::WorkspaceConfiguration::METHOD => {
+                // No settings of our own; servers fall back to their defaults
+                let items = params.get("items").and_then(Value::as_array).map_or(0, Vec::len);
+                Ok(Value::Array(vec![Value::Null; items]))
+            }
+            request::WorkDoneProgressCreate::METHOD
+            | request::RegisterCapability::METHOD
+            | request::UnregisterCapability::METHOD => Ok(Value::Null),
+            request::ApplyWorkspaceEdit::METHOD => Ok(json!({
+                "applied": false,
+                "failureReason": "Edits from the server are not supported",
+            })),
+            _ => Err(json!({ "code": METHOD_NOT_FOUND, "message": format!("Unhandled method {}", method) })),
+        };
+        let mut response = json!({ "jsonrpc": "2.0", "id": id });
+        match result {
+            Ok(result) => response["result"] = result,
+            Err(error) => response["error"] = error,
+        }
+        self.send(response);
+    }
+}
+
+/// A response's result as the type its request returns
+pub fn parse<T: DeserializeOwned>(result: Result<Value, ResponseError>) -> Result<T, ResponseError> {
+    serde_json::from_value(result?).map_err(|e| ResponseError { code: 0, message: format!("Unexpected response: {}", e) })
+}
+
+fn message(id: Option<Value>, method: &str, params: Value) -> Value {
+    let mut message = json!({ "jsonrpc": "2.0", "method": method });
+    if let Some(id) = id {
+        message["id"] = id;
+    }
+    if !params.is_null() {
+        message["params"] = params;
+    }
+    message
+}
+
+fn initialize_params(config: &ServerConfig, root: &Path) -> Result<InitializeParams, String> {
+    let uri = Url::from_directory_path(root).map_err(|_| format!("{} is not an absolute path", root.display()))?;
+    let name = root.file_name().map_or_else(|| root.display().to_string(), |name| name.to_string_lossy().into_owned());
+    Ok(InitializeParams {
+        process_id: Some(std::process::id()),
+        initialization_options: config.initialization_options.clone(),
+        capabilities: client_capabilities(),
+        workspace_folders: Some(vec![WorkspaceFolder { uri, name }]),
+        client_info: Some(ClientInfo {
+            name: "android-playground".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+        ..InitializeParams::default()
+    })
+}
+
+/// What the editor supports. Positions are UTF-16, the protocol default.
+fn client_capabilities() -> ClientCapabilities {
+    ClientCapabilities {
+        workspace: Some(WorkspaceClientCapabilities {
+            configuration: Some(true),
+            workspace_folders: Some(true),
+            workspace_edit: Some(WorkspaceEditClientCapabilities {
+                document_changes: Some(true),
+                ..WorkspaceEditClientCapabilities::default()
+            }),
+            ..WorkspaceClientCapabilities::default()
+        }),
+        text_document: Some(TextDocumentClientCapabilities {
+            synchronization: Some(TextDocumentSyncClientCapabilities {
+                did_save: Some(true),
+                ..TextDocumentSyncClientCapabilities::default()
+            }),
+            hover: Some(HoverClientCapabilities {
+                content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                ..HoverClientCapabilities::default()
+            }),
+            completion: Some(CompletionClientCapabilities {
+                completion_item: Some(CompletionItemCapability {
+                    documentation_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
+                    ..CompletionItemCapability::default()
+                }),
+                ..CompletionClientCapabilities::default()
+            }),
+            definition: Some(GotoCapability { link_support: Some(true), ..GotoCapability::default() }),
+            references: Some(ReferenceClientCapabilities::default()),
+            rename: Some(RenameClientCapabilities::default()),
+            formatting: Some(DocumentFormattingClientCapabilities::default()),
+            publish_diagnostics: Some(PublishDiagnosticsClientCapabilities {
+                version_support: Some(true),
+                ..PublishDiagnosticsClientCapabilities::default()
+            }),
+            ..TextDocumentClientCapabilities::default()
+        }),
+        ..ClientCapabilities::default()
+    }
+}
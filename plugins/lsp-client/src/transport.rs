@@ -0,0 +1,46 @@
+//! JSON-RPC messages framed the way language servers speak over stdio: a
+//! `Content-Length` header, a blank line, then that many bytes of JSON.
+
+use serde_json::Value;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// The next message, or None at end of stream
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            // Stray blank lines between messages are skipped
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            let value = value.trim().parse::<usize>()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Bad Content-Length: {}", e)))?;
+            length = Some(value);
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    writer.write_all(format!("Content-Length: {}\r\n\r\n", body.len()).as_bytes()).await?;
+    writer.write_all(&body).await?;
+    writer.flush().await
+}
@@ -0,0 +1,323 @@
+//! `LspClient` against `mock-lsp-server`, which publishes a diagnostic for
+//! every `error` and `warning`, answers requests from the words in the open
+//! files, and exits when a change inserts `@crash`.
+
+use playground_plugins_lsp_client::lsp_types::{
+    Diagnostic, DiagnosticSeverity, FormattingOptions, HoverContents, Location, Position, Range,
+    TextDocumentContentChangeEvent, TextEdit, Url,
+};
+use playground_plugins_lsp_client::{
+    LspClient, LspCommand, LspEvent, LspRequest, LspResponse, ServerConfig, ServerStatus,
+};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A client running the mock server for the `mock` language, and the
+/// events it has sent that no test step has looked at yet
+struct Session {
+    client: LspClient,
+    events: VecDeque<LspEvent>,
+}
+
+impl Session {
+    fn new() -> Self {
+        let mut client = LspClient::new();
+        client.set_server("mock", ServerConfig::new(env!("CARGO_BIN_EXE_mock-lsp-server")));
+        Self { client, events: VecDeque::new() }
+    }
+
+    fn open(&mut self, path: &Path, text: &str) {
+        self.client.handle(LspCommand::Open {
+            path: path.to_path_buf(),
+            language: "mock".to_string(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Replace `range` with `text` in one incremental change
+    fn change(&mut self, path: &Path, range: Range, text: &str) {
+        self.client.handle(LspCommand::Change {
+            path: path.to_path_buf(),
+            changes: vec![TextDocumentContentChangeEvent { range: Some(range), range_length: None, text: text.to_string() }],
+        });
+    }
+
+    /// Poll until an event matches, dropping the ones before it
+    async fn wait_for(&mut self, matches: impl Fn(&LspEvent) -> bool) -> LspEvent {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            while let Some(event) = self.events.pop_front() {
+                if matches(&event) {
+                    return event;
+                }
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for a language server event");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.events.extend(self.client.poll());
+        }
+    }
+
+    /// Send a request and wait for its answer
+    async fn request(&mut self, id: u64, path: &Path, request: LspRequest) -> LspResponse {
+        self.client.handle(LspCommand::Request { id, path: path.to_path_buf(), request });
+        let answered = |event: &LspEvent| match event {
+            LspEvent::Response { id: answered, .. } | LspEvent::Error { id: answered, .. } => *answered == id,
+            _ => false,
+        };
+        match self.wait_for(answered).await {
+            LspEvent::Response { response, .. } => response,
+            LspEvent::Error { message, .. } => panic!("request {} failed: {}", id, message),
+            _ => unreachable!(),
+        }
+    }
+
+    async fn wait_for_status(&mut self, matches: impl Fn(&ServerStatus) -> bool) -> ServerStatus {
+        match self.wait_for(|event| matches!(event, LspEvent::Server(server) if matches(&server.status))).await {
+            LspEvent::Server(server) => server.status,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The next diagnostics, with the document version they are for
+    async fn wait_for_diagnostics(&mut self) -> (Option<i32>, Vec<Diagnostic>) {
+        match self.wait_for(|event| matches!(event, LspEvent::Diagnostics { .. })).await {
+            LspEvent::Diagnostics { version, diagnostics, .. } => (version, diagnostics),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// An empty directory of its own for each test, the mock server's root
+fn workspace() -> PathBuf {
+    let root = std::env::temp_dir().join(format!("lsp-client-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&root).unwrap();
+    root
+}
+
+fn range(line: u32, start: u32, end: u32) -> Range {
+    Range::new(Position::new(line, start), Position::new(line, end))
+}
+
+fn location(path: &Path, range: Range) -> Location {
+    Location::new(Url::from_file_path(path).unwrap(), range)
+}
+
+fn ranges(diagnostics: &[Diagnostic]) -> Vec<(Range, Option<DiagnosticSeverity>)> {
+    diagnostics.iter().map(|diagnostic| (diagnostic.range, diagnostic.severity)).collect()
+}
+
+#[tokio::test]
+async fn initializes_and_shuts_down() {
+    let root = workspace();
+    let mut session = Session::new();
+    session.open(&root.join("main.txt"), "fine");
+
+    assert_eq!(session.wait_for_status(|_| true).await, ServerStatus::Starting);
+    session.wait_for_status(|status| *status == ServerStatus::Running).await;
+    let servers = session.client.servers();
+    assert_eq!(servers.len(), 1);
+    assert_eq!(servers[0].name, "mock-lsp-server");
+    assert_eq!(servers[0].language, "mock");
+    assert_eq!(servers[0].root, root);
+
+    tokio::time::timeout(TIMEOUT, session.client.shutdown()).await.expect("the server shuts down");
+    assert!(session.client.servers().is_empty());
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn publishes_diagnostics_for_opened_files() {
+    let root = workspace();
+    let mut session = Session::new();
+    session.open(&root.join("main.txt"), "an error and\na warning");
+
+    let (version, diagnostics) = session.wait_for_diagnostics().await;
+    assert_eq!(version, Some(0));
+    assert_eq!(ranges(&diagnostics), vec![
+        (range(0, 3, 8), Some(DiagnosticSeverity::ERROR)),
+        (range(1, 2, 9), Some(DiagnosticSeverity::WARNING)),
+    ]);
+    assert_eq!(diagnostics[0].message, "found error");
+    assert_eq!(diagnostics[1].message, "found warning");
+    assert_eq!(diagnostics[0].source.as_deref(), Some("mock"));
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn sends_changes_incrementally() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "fine text\nmore");
+    let (version, diagnostics) = session.wait_for_diagnostics().await;
+    assert_eq!(version, Some(0));
+    assert!(diagnostics.is_empty());
+
+    // The server applies each range to its own copy, so the diagnostics
+    // only land here if the changes arrived as ranges in order
+    session.change(&path, range(1, 0, 0), "error ");
+    let (version, diagnostics) = session.wait_for_diagnostics().await;
+    assert_eq!(version, Some(1));
+    assert_eq!(ranges(&diagnostics), vec![(range(1, 0, 5), Some(DiagnosticSeverity::ERROR))]);
+
+    session.change(&path, range(0, 0, 4), "warning");
+    let (version, diagnostics) = session.wait_for_diagnostics().await;
+    assert_eq!(version, Some(2));
+    assert_eq!(ranges(&diagnostics), vec![
+        (range(0, 0, 7), Some(DiagnosticSeverity::WARNING)),
+        (range(1, 0, 5), Some(DiagnosticSeverity::ERROR)),
+    ]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn restarts_an_exited_server_after_a_delay() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "error");
+    session.wait_for_status(|status| *status == ServerStatus::Running).await;
+    session.wait_for_diagnostics().await;
+
+    let crashed = Instant::now();
+    session.change(&path, range(0, 5, 5), " @crash");
+    let status = session.wait_for_status(|status| matches!(status, ServerStatus::Restarting { .. })).await;
+    assert_eq!(status, ServerStatus::Restarting { exit_code: Some(1), attempt: 1 });
+
+    session.wait_for_status(|status| *status == ServerStatus::Running).await;
+    assert!(crashed.elapsed() >= Duration::from_millis(500), "restarted after {:?}", crashed.elapsed());
+
+    // The new server is given the file as it is now
+    let (version, diagnostics) = session.wait_for_diagnostics().await;
+    assert_eq!(version, Some(1));
+    assert_eq!(ranges(&diagnostics), vec![(range(0, 0, 5), Some(DiagnosticSeverity::ERROR))]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn hovers_the_word_under_the_cursor() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "let alpha = 1");
+
+    let LspResponse::Hover(Some(hover)) = session.request(1, &path, LspRequest::Hover { position: Position::new(0, 6) }).await else {
+        panic!("expected a hover");
+    };
+    let HoverContents::Markup(contents) = hover.contents else {
+        panic!("expected markup, got {:?}", hover.contents);
+    };
+    assert_eq!(contents.value, "`alpha`");
+    assert_eq!(hover.range, Some(range(0, 4, 9)));
+
+    // Nothing under the cursor
+    let response = session.request(2, &path, LspRequest::Hover { position: Position::new(0, 10) }).await;
+    assert!(matches!(response, LspResponse::Hover(None)), "got {:?}", response);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn completes_what_is_typed() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "alpine alpha beta
+al");
+
+    let LspResponse::Completion(items) = session.request(1, &path, LspRequest::Completion { position: Position::new(1, 2) }).await else {
+        panic!("expected completions");
+    };
+    let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, vec!["alpha", "alpine"]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn goes_to_the_definition() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "value = 1
+print value");
+
+    let LspResponse::Locations(locations) = session.request(1, &path, LspRequest::Definition { position: Position::new(1, 8) }).await else {
+        panic!("expected locations");
+    };
+    assert_eq!(locations, vec![location(&path, range(0, 0, 5))]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn finds_references_in_every_open_file() {
+    let root = workspace();
+    let mut session = Session::new();
+    let (first, second) = (root.join("a.txt"), root.join("b.txt"));
+    session.open(&first, "shared here");
+    session.open(&second, "and\nshared there");
+
+    let request = LspRequest::References { position: Position::new(0, 2), include_declaration: true };
+    let LspResponse::Locations(locations) = session.request(1, &first, request).await else {
+        panic!("expected locations");
+    };
+    assert_eq!(locations, vec![location(&first, range(0, 0, 6)), location(&second, range(1, 0, 6))]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn renames_across_open_files() {
+    let root = workspace();
+    let mut session = Session::new();
+    let (first, second) = (root.join("a.txt"), root.join("b.txt"));
+    session.open(&first, "old old");
+    session.open(&second, "keep old");
+
+    let request = LspRequest::Rename { position: Position::new(0, 0), new_name: "new".to_string() };
+    let LspResponse::Edit(edit) = session.request(1, &first, request).await else {
+        panic!("expected a workspace edit");
+    };
+    let changes = edit.changes.expect("edits by file");
+    let edit = |range| TextEdit::new(range, "new".to_string());
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[&Url::from_file_path(&first).unwrap()], vec![edit(range(0, 0, 3)), edit(range(0, 4, 7))]);
+    assert_eq!(changes[&Url::from_file_path(&second).unwrap()], vec![edit(range(0, 5, 8))]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[tokio::test]
+async fn formats_the_document() {
+    let root = workspace();
+    let mut session = Session::new();
+    let path = root.join("main.txt");
+    session.open(&path, "one  \ntwo\nthree \t");
+
+    let options = FormattingOptions { tab_size: 4, insert_spaces: true, ..Default::default() };
+    let LspResponse::TextEdits(edits) = session.request(1, &path, LspRequest::Formatting { options }).await else {
+        panic!("expected text edits");
+    };
+    assert_eq!(edits, vec![
+        TextEdit::new(range(0, 3, 5), String::new()),
+        TextEdit::new(range(2, 5, 7), String::new()),
+    ]);
+
+    session.client.shutdown().await;
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -1,3 +1,6 @@
 mod plugin;
+mod session;
+mod packets;
 
 pub use plugin::TerminalPlugin;
+pub use session::{SessionConfig, TerminalSession};
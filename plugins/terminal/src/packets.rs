@@ -0,0 +1,81 @@
+//! Packet types and messages on the terminal plugin's channel. Messages are
+//! JSON; every one names the session it is about.
+
+use serde::{Deserialize, Serialize};
+
+// Messages from browser to server (1-99)
+pub const PACKET_TYPE_OPEN: u16 = 1;
+pub const PACKET_TYPE_INPUT: u16 = 2;
+pub const PACKET_TYPE_RESIZE: u16 = 3;
+pub const PACKET_TYPE_CLOSE: u16 = 4;
+/// Sent on (re)connect to get the session list and a snapshot of each session
+pub const PACKET_TYPE_ATTACH: u16 = 5;
+
+// Messages from server to browser (100-199)
+pub const PACKET_TYPE_OUTPUT: u16 = 100;
+pub const PACKET_TYPE_SESSIONS: u16 = 101;
+/// A session's screen, scrollback and modes as escape sequences, sent in
+/// place of the output the browser missed
+pub const PACKET_TYPE_SNAPSHOT: u16 = 102;
+pub const PACKET_TYPE_EXITED: u16 = 103;
+pub const PACKET_TYPE_ERROR: u16 = 104;
+
+/// The shell, its arguments, environment and working directory come from
+/// the plugin's `SessionConfig`, never from the browser
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenMessage {
+    pub session: String,
+    #[serde(default)]
+    pub cols: Option<u16>,
+    #[serde(default)]
+    pub rows: Option<u16>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InputMessage {
+    pub session: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResizeMessage {
+    pub session: String,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloseMessage {
+    pub session: String,
+}
+
+/// Output and snapshots
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputMessage {
+    pub session: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionsMessage {
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub session: String,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExitedMessage {
+    pub session: String,
+    pub exit_code: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorMessage {
+    pub session: String,
+    pub message: String,
+}
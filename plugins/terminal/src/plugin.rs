@@ -1,12 +1,21 @@
 use async_trait::async_trait;
 use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle};
-use tracing::{info, debug};
+use playground_core_types::Priority;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use tracing::{info, debug, warn};
+
+use crate::packets::*;
+use crate::session::{SessionConfig, TerminalSession};
 
 pub struct TerminalPlugin {
     channel_id: Option<u16>,
     systems_manager: Handle<SystemsManager>,
-    // Terminal-specific fields would go here
-    // e.g., terminal_sessions: HashMap<SessionId, TerminalSession>
+    /// Open sessions by name. They outlive browser connections, so a
+    /// browser that reconnects picks them up where they were.
+    sessions: BTreeMap<String, TerminalSession>,
+    /// How sessions the browser opens are started
+    config: SessionConfig,
 }
 
 impl TerminalPlugin {
@@ -14,14 +23,193 @@ impl TerminalPlugin {
         Self {
             channel_id: None,
             systems_manager,
+            sessions: BTreeMap::new(),
+            config: SessionConfig::default(),
         }
     }
-    
+
+    /// Shell, arguments, working directory and environment of every
+    /// session, and the size used when an open request leaves it out
+    pub fn with_config(mut self, config: SessionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     async fn setup(&mut self) -> LogicResult<()> {
-        // Initialize terminal subsystem
+        // Sessions start when the browser opens them
         debug!("Terminal plugin setting up PTY support");
         Ok(())
     }
+
+    async fn handle_packet(&mut self, packet_type: u16, data: Vec<u8>) {
+        match packet_type {
+            PACKET_TYPE_OPEN => {
+                if let Some(message) = self.decode::<OpenMessage>(packet_type, &data) {
+                    self.open_session(message).await;
+                }
+            }
+            PACKET_TYPE_INPUT => {
+                if let Some(message) = self.decode::<InputMessage>(packet_type, &data) {
+                    let result = match self.sessions.get(&message.session) {
+                        Some(session) => session.write(message.data.as_bytes()),
+                        None => Err("No such session".to_string()),
+                    };
+                    if let Err(e) = result {
+                        self.send_error(&message.session, e).await;
+                    }
+                }
+            }
+            PACKET_TYPE_RESIZE => {
+                if let Some(message) = self.decode::<ResizeMessage>(packet_type, &data) {
+                    let result = match self.sessions.get_mut(&message.session) {
+                        Some(session) => session.resize(message.cols.max(1), message.rows.max(1)),
+                        None => Err("No such session".to_string()),
+                    };
+                    if let Err(e) = result {
+                        self.send_error(&message.session, e).await;
+                    }
+                }
+            }
+            PACKET_TYPE_CLOSE => {
+                if let Some(message) = self.decode::<CloseMessage>(packet_type, &data) {
+                    if let Some(session) = self.sessions.remove(&message.session) {
+                        info!("Closing terminal session '{}'", message.session);
+                        session.close();
+                        self.send_sessions().await;
+                    }
+                }
+            }
+            PACKET_TYPE_ATTACH => self.attach().await,
+            _ => debug!("Unknown packet type {} received on terminal channel", packet_type),
+        }
+    }
+
+    async fn open_session(&mut self, message: OpenMessage) {
+        // Opening a session that exists attaches to it instead
+        if !self.sessions.contains_key(&message.session) {
+            // What runs, and where, is server configuration; the browser
+            // only picks the size
+            let config = SessionConfig {
+                cols: message.cols.unwrap_or(self.config.cols).max(1),
+                rows: message.rows.unwrap_or(self.config.rows).max(1),
+                ..self.config.clone()
+            };
+            match TerminalSession::spawn(message.session.clone(), &config) {
+                Ok(session) => {
+                    info!("Opened terminal session '{}'", message.session);
+                    self.sessions.insert(message.session.clone(), session);
+                }
+                Err(e) => {
+                    warn!("Failed to open terminal session '{}': {}", message.session, e);
+                    self.send_error(&message.session, e).await;
+                    return;
+                }
+            }
+        }
+        self.send_sessions().await;
+        self.send_snapshot(&message.session).await;
+    }
+
+    /// Bring a (re)connected browser up to date
+    async fn attach(&mut self) {
+        // Output not yet forwarded belongs in the snapshots
+        for session in self.sessions.values_mut() {
+            session.read_output();
+        }
+        self.send_sessions().await;
+        let names: Vec<String> = self.sessions.keys().cloned().collect();
+        for name in names {
+            self.send_snapshot(&name).await;
+        }
+    }
+
+    /// Forward new output and errors, and report shells that exited
+    async fn pump_sessions(&mut self) {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        let mut exited = Vec::new();
+        for (name, session) in &mut self.sessions {
+            if let Some(data) = session.read_output() {
+                output.push(OutputMessage { session: name.clone(), data });
+            }
+            errors.extend(session.take_errors().into_iter().map(|error| (name.clone(), error)));
+            if let Some(code) = session.exit_code() {
+                exited.push((name.clone(), code));
+            }
+        }
+
+        for message in output {
+            self.send(PACKET_TYPE_OUTPUT, &message).await;
+        }
+        for (name, error) in errors {
+            self.send_error(&name, error).await;
+        }
+        if exited.is_empty() {
+            return;
+        }
+        for (name, code) in exited {
+            // Output written just before exiting may still be in flight
+            if let Some(mut session) = self.sessions.remove(&name) {
+                if let Some(data) = session.read_output() {
+                    self.send(PACKET_TYPE_OUTPUT, &OutputMessage { session: name.clone(), data }).await;
+                }
+                session.close();
+            }
+            info!("Terminal session '{}' exited with code {}", name, code);
+            self.send(PACKET_TYPE_EXITED, &ExitedMessage { session: name, exit_code: Some(code) }).await;
+        }
+        self.send_sessions().await;
+    }
+
+    async fn send_sessions(&self) {
+        let sessions = self.sessions.iter()
+            .map(|(name, session)| {
+                let (cols, rows) = session.size();
+                SessionInfo { session: name.clone(), cols, rows }
+            })
+            .collect();
+        self.send(PACKET_TYPE_SESSIONS, &SessionsMessage { sessions }).await;
+    }
+
+    async fn send_snapshot(&self, name: &str) {
+        if let Some(session) = self.sessions.get(name) {
+            let message = OutputMessage { session: name.to_string(), data: session.snapshot() };
+            self.send(PACKET_TYPE_SNAPSHOT, &message).await;
+        }
+    }
+
+    async fn send_error(&self, session: &str, message: String) {
+        self.send(PACKET_TYPE_ERROR, &ErrorMessage { session: session.to_string(), message }).await;
+    }
+
+    async fn send<T: Serialize>(&self, packet_type: u16, message: &T) {
+        let Some(channel_id) = self.channel_id else {
+            return;
+        };
+        let data = match serde_json::to_vec(message) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to serialize terminal packet {}: {}", packet_type, e);
+                return;
+            }
+        };
+
+        let networking = self.systems_manager.networking();
+        let net = networking.read().await;
+        if let Err(e) = net.send_packet(channel_id, packet_type, data, Priority::High).await {
+            warn!("Failed to send terminal packet {}: {}", packet_type, e);
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&self, packet_type: u16, data: &[u8]) -> Option<T> {
+        match serde_json::from_slice(data) {
+            Ok(message) => Some(message),
+            Err(e) => {
+                warn!("Malformed terminal packet {}: {}", packet_type, e);
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -29,28 +217,44 @@ impl System for TerminalPlugin {
     fn name(&self) -> &'static str {
         "TerminalPlugin"
     }
-    
+
     async fn initialize(&mut self, _world: &World) -> LogicResult<()> {
         // Request dynamic channel allocation
         self.channel_id = Some(self.systems_manager.register_plugin("terminal").await?);
-        
+
         info!("Terminal Plugin initialized on dynamic channel {}", self.channel_id.unwrap());
-        
+
         // Plugin-specific initialization
         self.setup().await?;
-        
+
         info!("Terminal plugin initialized successfully");
         Ok(())
     }
-    
+
     async fn run(&mut self, _world: &World, _delta_time: f32) -> LogicResult<()> {
-        // Process terminal output, handle input, etc.
+        // Input, resizes and session requests from the browser
+        if let Some(channel_id) = self.channel_id {
+            let packets = {
+                let networking = self.systems_manager.networking();
+                let net = networking.read().await;
+                net.receive_packets(channel_id).await.unwrap_or_default()
+            };
+            for packet in packets {
+                self.handle_packet(packet.packet_type, packet.data).await;
+            }
+        }
+
+        self.pump_sessions().await;
         Ok(())
     }
-    
+
     async fn cleanup(&mut self, _world: &World) -> LogicResult<()> {
         info!("Terminal plugin shutting down");
         // Close any open terminal sessions
+        for (name, session) in std::mem::take(&mut self.sessions) {
+            debug!("Closing terminal session '{}'", name);
+            session.close();
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
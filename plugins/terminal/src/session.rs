@@ -0,0 +1,243 @@
+use playground_systems_ui::terminal::Terminal;
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::mpsc as std_mpsc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// How often the pty thread checks whether the shell exited while idle
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How to start a session
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    /// The user's login shell when None
+    pub shell: Option<String>,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            shell: None,
+            args: Vec::new(),
+            working_dir: None,
+            env: HashMap::new(),
+            cols: 80,
+            rows: 24,
+        }
+    }
+}
+
+/// Work for the thread that owns the pty and the shell
+enum PtyRequest {
+    Input(Vec<u8>),
+    Resize { cols: u16, rows: u16 },
+    Close,
+}
+
+/// Reported by the pty threads
+enum PtyEvent {
+    Output(Vec<u8>),
+    Failed(String),
+    Exited(u32),
+}
+
+/// A shell running on a pseudo-terminal
+///
+/// Writes, resizes and waiting for the shell block, so the pty, its writer
+/// and the child process live on a thread of their own, driven through
+/// `PtyRequest`s; reads have a second thread. Output also goes through a
+/// terminal emulator, whose screen is what late-attaching browsers get.
+pub struct TerminalSession {
+    name: String,
+    requests: std_mpsc::Sender<PtyRequest>,
+    events: mpsc::UnboundedReceiver<PtyEvent>,
+    /// Start of a UTF-8 sequence split across reads
+    partial: Vec<u8>,
+    screen: Terminal,
+    size: (u16, u16),
+    exit_code: Option<u32>,
+    errors: Vec<String>,
+}
+
+impl TerminalSession {
+    pub fn spawn(name: String, config: &SessionConfig) -> Result<Self, String> {
+        let size = PtySize { rows: config.rows, cols: config.cols, pixel_width: 0, pixel_height: 0 };
+        let pair = native_pty_system().openpty(size)
+            .map_err(|e| format!("Failed to open pty: {}", e))?;
+
+        let mut command = match &config.shell {
+            Some(shell) => CommandBuilder::new(shell),
+            None => CommandBuilder::new_default_prog(),
+        };
+        command.args(&config.args);
+        if let Some(dir) = &config.working_dir {
+            command.cwd(dir);
+        }
+        command.env("TERM", "xterm-256color");
+        command.env("COLORTERM", "truecolor");
+        for (key, value) in &config.env {
+            command.env(key, value);
+        }
+
+        let mut child = pair.slave.spawn_command(command)
+            .map_err(|e| format!("Failed to start shell: {}", e))?;
+        // Our copy of the slave would keep the pty open after the shell exits
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader()
+            .map_err(|e| format!("Failed to read pty: {}", e))?;
+        let mut writer = pair.master.take_writer()
+            .map_err(|e| format!("Failed to write pty: {}", e))?;
+        let master = pair.master;
+
+        let (event_sender, events) = mpsc::unbounded_channel();
+
+        // Reads end when the pty closes
+        let output = event_sender.clone();
+        std::thread::Builder::new()
+            .name(format!("pty-read-{}", name))
+            .spawn(move || {
+                let mut buffer = [0u8; 8192];
+                loop {
+                    match reader.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => {
+                            if output.send(PtyEvent::Output(buffer[..read].to_vec())).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start pty reader: {}", e))?;
+
+        // Ends when the shell exits or the session is closed or dropped
+        let (requests, pending) = std_mpsc::channel();
+        std::thread::Builder::new()
+            .name(format!("pty-{}", name))
+            .spawn(move || {
+                loop {
+                    match pending.recv_timeout(EXIT_POLL_INTERVAL) {
+                        Ok(PtyRequest::Input(data)) => {
+                            if let Err(e) = writer.write_all(&data).and_then(|_| writer.flush()) {
+                                let _ = event_sender.send(PtyEvent::Failed(format!("Failed to write to shell: {}", e)));
+                            }
+                        }
+                        Ok(PtyRequest::Resize { cols, rows }) => {
+                            if let Err(e) = master.resize(PtySize { rows, cols, pixel_width: 0, pixel_height: 0 }) {
+                                let _ = event_sender.send(PtyEvent::Failed(format!("Failed to resize pty: {}", e)));
+                            }
+                        }
+                        Ok(PtyRequest::Close) | Err(std_mpsc::RecvTimeoutError::Disconnected) => {
+                            if !matches!(child.try_wait(), Ok(Some(_))) {
+                                let _ = child.kill();
+                                let _ = child.wait();
+                            }
+                            break;
+                        }
+                        Err(std_mpsc::RecvTimeoutError::Timeout) => {}
+                    }
+
+                    if let Ok(Some(status)) = child.try_wait() {
+                        let _ = event_sender.send(PtyEvent::Exited(status.exit_code()));
+                        break;
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start pty thread: {}", e))?;
+
+        Ok(Self {
+            screen: Terminal::with_size(Uuid::new_v4(), config.cols as usize, config.rows as usize),
+            name,
+            requests,
+            events,
+            partial: Vec::new(),
+            size: (config.cols, config.rows),
+            exit_code: None,
+            errors: Vec::new(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `(cols, rows)`
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Send keystrokes to the shell. Write failures show up in `take_errors`.
+    pub fn write(&self, data: &[u8]) -> Result<(), String> {
+        self.requests.send(PtyRequest::Input(data.to_vec()))
+            .map_err(|_| "The shell has exited".to_string())
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<(), String> {
+        self.requests.send(PtyRequest::Resize { cols, rows })
+            .map_err(|_| "The shell has exited".to_string())?;
+        self.screen.resize(cols as usize, rows as usize);
+        self.size = (cols, rows);
+        Ok(())
+    }
+
+    /// Output since the last call, also applied to the session's screen.
+    /// None when there is nothing new.
+    pub fn read_output(&mut self) -> Option<String> {
+        let mut bytes = std::mem::take(&mut self.partial);
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                PtyEvent::Output(chunk) => bytes.extend_from_slice(&chunk),
+                PtyEvent::Failed(error) => self.errors.push(error),
+                PtyEvent::Exited(code) => self.exit_code = Some(code),
+            }
+        }
+
+        // Hold back an incomplete character at the end for the next read
+        let complete = match std::str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => bytes.len(),
+        };
+        self.partial = bytes.split_off(complete);
+        if bytes.is_empty() {
+            return None;
+        }
+
+        self.screen.feed(&bytes);
+        // The browser's emulator answers the shell's queries
+        self.screen.take_responses();
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Escape sequences that draw the session as it is now, scrollback and
+    /// modes included, on an emulator of the same size
+    pub fn snapshot(&self) -> String {
+        self.screen.snapshot()
+    }
+
+    /// Failed writes and resizes since the last call
+    pub fn take_errors(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Exit code once the shell has exited, as of the last `read_output`
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    /// Stop the shell and release the pty. Killing and reaping the shell
+    /// happens on the pty thread.
+    pub fn close(self) {
+        let _ = self.requests.send(PtyRequest::Close);
+    }
+}
@@ -0,0 +1,80 @@
+//! Theme directory
+//!
+//! Every `*.json` file in the directory is a theme named after its file
+//! stem, so `solarized.json` registers "solarized". A filesystem watcher
+//! records which files change; the plugin picks them up on its next run.
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+pub struct ThemeDirectory {
+    path: PathBuf,
+    changed: Arc<Mutex<HashSet<PathBuf>>>,
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl ThemeDirectory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            changed: Arc::new(Mutex::new(HashSet::new())),
+            watcher: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every theme in the directory as (name, file contents)
+    pub fn read_all(&self) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+        let mut themes = Vec::new();
+        for entry in std::fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            if let Some(name) = theme_name(&path) {
+                themes.push((name, std::fs::read(&path)?));
+            }
+        }
+        themes.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(themes)
+    }
+
+    /// Start recording changed theme files
+    pub fn watch(&mut self) -> notify::Result<()> {
+        let changed = self.changed.clone();
+        let mut watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
+            match res {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    let mut changed = changed.lock().unwrap();
+                    changed.extend(event.paths.into_iter().filter(|path| theme_name(path).is_some()));
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Theme watcher error: {}", e),
+            }
+        })?;
+        watcher.watch(&self.path, RecursiveMode::NonRecursive)?;
+        self.watcher = Some(watcher);
+        Ok(())
+    }
+
+    /// Themes whose files changed since the last call. A file that can't
+    /// be read (e.g. deleted again) is skipped.
+    pub fn take_changed(&self) -> Vec<(String, Vec<u8>)> {
+        let paths = std::mem::take(&mut *self.changed.lock().unwrap());
+        let mut themes: Vec<(String, Vec<u8>)> = paths.into_iter()
+            .filter_map(|path| Some((theme_name(&path)?, std::fs::read(&path).ok()?)))
+            .collect();
+        themes.sort_by(|a, b| a.0.cmp(&b.0));
+        themes
+    }
+}
+
+fn theme_name(path: &Path) -> Option<String> {
+    if path.extension()? != "json" {
+        return None;
+    }
+    path.file_stem()?.to_str().map(str::to_string)
+}
@@ -1,3 +1,5 @@
 mod plugin;
+mod directory;
 
 pub use plugin::ThemeManagerPlugin;
+pub use directory::ThemeDirectory;
@@ -1,12 +1,14 @@
 use async_trait::async_trait;
 use playground_systems_logic::{System, World, LogicResult, SystemsManager, Handle};
-use tracing::{info, debug};
+use std::path::PathBuf;
+use tracing::{info, debug, warn};
+
+use crate::directory::ThemeDirectory;
 
 pub struct ThemeManagerPlugin {
     channel_id: Option<u16>,
     systems_manager: Handle<SystemsManager>,
-    // ThemeManagerPlugin-specific fields
-    // e.g., language_servers: HashMap<String, LspConnection>
+    directory: Option<ThemeDirectory>,
 }
 
 impl ThemeManagerPlugin {
@@ -14,43 +16,105 @@ impl ThemeManagerPlugin {
         Self {
             channel_id: None,
             systems_manager,
+            directory: None,
         }
     }
-    
+
+    /// Register the themes in `directory` with the UI on startup, and
+    /// reload each one whenever its file changes
+    pub fn with_themes(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(ThemeDirectory::new(directory));
+        self
+    }
+
     async fn setup(&mut self) -> LogicResult<()> {
-        // Initialize LSP client infrastructure
         debug!("theme manager plugin setting up theme-manager components");
+
+        let Some(directory) = &mut self.directory else {
+            return Ok(());
+        };
+
+        match directory.read_all() {
+            Ok(themes) => {
+                let loaded = register_all(&self.systems_manager, themes).await;
+                info!("Loaded {} themes from {}", loaded, directory.path().display());
+            }
+            Err(e) => warn!("Failed to read themes from {}: {}", directory.path().display(), e),
+        }
+
+        // Without a watcher themes still load, they just don't reload
+        if let Err(e) = directory.watch() {
+            warn!("Theme hot reload disabled: {}", e);
+        }
         Ok(())
     }
 }
 
+/// Register themes, retrying the ones that fail while others succeed so a
+/// theme can come before the base it extends. Returns how many registered.
+async fn register_all(systems_manager: &SystemsManager, mut themes: Vec<(String, Vec<u8>)>) -> usize {
+    let mut loaded = 0;
+    let mut ui = systems_manager.ui_interface();
+    loop {
+        let attempted = themes.len();
+        let mut failed = Vec::new();
+        for (name, data) in themes {
+            match ui.register_theme(&name, &data).await {
+                Ok(()) => loaded += 1,
+                Err(e) => failed.push((name, data, e)),
+            }
+        }
+
+        if failed.is_empty() || failed.len() == attempted {
+            for (name, _, e) in failed {
+                warn!("Failed to load theme '{}': {}", name, e);
+            }
+            return loaded;
+        }
+        themes = failed.into_iter().map(|(name, data, _)| (name, data)).collect();
+    }
+}
+
 #[async_trait]
 impl System for ThemeManagerPlugin {
     fn name(&self) -> &'static str {
         "ThemeManagerPlugin"
     }
-    
+
     async fn initialize(&mut self, _world: &World) -> LogicResult<()> {
         // Request dynamic channel allocation
         self.channel_id = Some(self.systems_manager.register_plugin("theme-manager").await?);
-        
+
         info!("ThemeManager Plugin initialized on dynamic channel {}", self.channel_id.unwrap());
-        
+
         // Plugin-specific initialization
         self.setup().await?;
-        
+
         info!("theme manager plugin initialized successfully");
         Ok(())
     }
-    
+
     async fn run(&mut self, _world: &World, _delta_time: f32) -> LogicResult<()> {
-        // Process LSP messages, handle completions, diagnostics, etc.
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+
+        // A theme that fails to parse, e.g. saved mid-edit, keeps its
+        // previous version until the next change
+        let mut ui = self.systems_manager.ui_interface();
+        for (name, data) in directory.take_changed() {
+            match ui.register_theme(&name, &data).await {
+                Ok(()) => info!("Reloaded theme '{}'", name),
+                Err(e) => warn!("Failed to reload theme '{}': {}", name, e),
+            }
+        }
         Ok(())
     }
-    
+
     async fn cleanup(&mut self, _world: &World) -> LogicResult<()> {
         info!("theme manager plugin shutting down");
-        // Clean up theme-manager resources
+        // Dropping the directory stops its watcher
+        self.directory = None;
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,98 @@
+use crate::components::AgentId;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+
+/// Number of denials kept in memory
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+/// A tool call that was refused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    /// None when the call did not name an agent
+    pub agent_id: Option<AgentId>,
+    pub agent_name: Option<String>,
+    pub tool_name: String,
+    pub reason: String,
+}
+
+/// Records denied MCP tool calls, in memory and optionally as JSON lines on disk
+pub struct AuditLog {
+    entries: RwLock<VecDeque<AuditEntry>>,
+    persist_path: Option<PathBuf>,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(VecDeque::new()),
+            persist_path: None,
+        }
+    }
+
+    pub fn with_persistence(path: PathBuf) -> Self {
+        Self {
+            entries: RwLock::new(VecDeque::new()),
+            persist_path: Some(path),
+        }
+    }
+
+    pub async fn record_denial(
+        &self,
+        agent_id: Option<AgentId>,
+        agent_name: Option<String>,
+        tool_name: &str,
+        reason: &str,
+    ) {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            agent_id,
+            agent_name,
+            tool_name: tool_name.to_string(),
+            reason: reason.to_string(),
+        };
+
+        tracing::warn!(
+            "Denied MCP tool call '{}' for agent {:?}: {}",
+            entry.tool_name,
+            entry.agent_name.as_deref().unwrap_or("<unknown>"),
+            entry.reason
+        );
+
+        if let Some(path) = &self.persist_path {
+            if let Err(e) = append_line(path, &entry).await {
+                tracing::error!("Failed to write audit log {}: {}", path.display(), e);
+            }
+        }
+
+        let mut entries = self.entries.write().await;
+        entries.push_back(entry);
+        while entries.len() > MAX_AUDIT_ENTRIES {
+            entries.pop_front();
+        }
+    }
+
+    /// Most recent denials, oldest first
+    pub async fn recent(&self, count: usize) -> Vec<AuditEntry> {
+        let entries = self.entries.read().await;
+        let skip = entries.len().saturating_sub(count);
+        entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+async fn append_line(path: &PathBuf, entry: &AuditEntry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
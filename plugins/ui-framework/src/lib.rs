@@ -1,6 +1,7 @@
 mod plugin;
 mod panel_manager;
 mod mcp_handler;
+mod audit;
 mod browser_bridge;
 mod ui_state;
 mod components;
@@ -16,4 +17,6 @@ pub use components::*;
 pub use channel_manager::ChannelManager;
 pub use message_system::MessageSystem;
 pub use websocket_handler::WebSocketHandler;
-pub use render_bridge::{RenderBridge, UiUpdate};
\ No newline at end of file
+pub use render_bridge::{RenderBridge, UiUpdate};
+pub use audit::{AuditLog, AuditEntry};
+pub use mcp_handler::CallerIdentity;
\ No newline at end of file
@@ -3,28 +3,116 @@ use crate::panel_manager::{PanelManager, PanelType};
 use crate::browser_bridge::BrowserBridge;
 use crate::ui_state::UiState;
 use crate::orchestrator::Orchestrator;
+use crate::audit::AuditLog;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::io::{AsyncRead, AsyncReadExt};
 use uuid::Uuid;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use tokio::fs;
+use playground_systems_logic::{PacketSender, SystemsManager};
 
 /// Handles MCP tool calls and routes them to appropriate UI components
+///
+/// Every call is attributed to the agent bound to the caller's authenticated
+/// session and checked against that agent's `AgentPermissions`. File access is
+/// confined to the agent's worktree; denied calls are recorded in the audit log.
 pub struct McpHandler {
     ui_state: Arc<RwLock<UiState>>,
     browser_bridge: Arc<BrowserBridge>,
     panel_manager: Arc<RwLock<PanelManager>>,
     orchestrator: Option<Arc<RwLock<Orchestrator>>>,
+    audit_log: Arc<AuditLog>,
+    command_policy: CommandPolicy,
+    /// Auth token (issued by the networking layer) to the agent it acts as
+    sessions: RwLock<HashMap<String, AgentId>>,
+    /// Agent for local calls that carry no session token
+    local_operator: RwLock<Option<AgentId>>,
+    /// Set when the networking layer requires authentication; new agents
+    /// then get a token of their own from it
+    token_issuer: RwLock<Option<Arc<SystemsManager>>>,
+}
+
+/// How the transport that delivered a tool call identified its sender
+#[derive(Debug, Clone)]
+pub enum CallerIdentity {
+    /// The auth token of the connection the call arrived on
+    Session(String),
+    /// A local connection without a token, only trusted while authentication is off
+    Local,
+    /// Another plugin in this process; not bound to any agent
+    Plugin(String),
+}
+
+impl CallerIdentity {
+    /// Identify the sender of an inbound packet by how the networking layer
+    /// authenticated its connection. Nothing in the payload is consulted.
+    pub fn from_sender(sender: &PacketSender) -> Self {
+        match sender {
+            PacketSender::Client { token: Some(token), .. } => CallerIdentity::Session(token.clone()),
+            PacketSender::Client { token: None, .. } => CallerIdentity::Local,
+            PacketSender::Plugin(name) => CallerIdentity::Plugin(name.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpToolCall {
+    /// Agent the call is made on behalf of (None for unattributed calls)
+    pub agent_id: Option<AgentId>,
     pub tool_name: String,
     pub params: serde_json::Value,
 }
 
+/// Channel name agents send their tool calls on
+pub const UI_FRAMEWORK_CHANNEL: &str = "ui-framework";
+
+/// Limits applied to `execute_command`
+#[derive(Debug, Clone)]
+pub struct CommandPolicy {
+    /// The command is killed once this elapses
+    pub timeout: Duration,
+    /// Maximum bytes kept from each of stdout and stderr
+    pub max_output_bytes: usize,
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(60),
+            max_output_bytes: 256 * 1024,
+        }
+    }
+}
+
+/// What a tool needs from the calling agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolAccess {
+    /// UI-only tools anyone may call
+    Open,
+    /// Any registered agent (reading its worktree, reporting its own tasks)
+    Registered,
+    ModifyFiles,
+    ExecuteCommands,
+    CreateWorktrees,
+    AssignTasks,
+}
+
+fn required_access(tool_name: &str) -> ToolAccess {
+    match tool_name {
+        "read_file" | "complete_task" => ToolAccess::Registered,
+        "save_file" => ToolAccess::ModifyFiles,
+        "execute_command" => ToolAccess::ExecuteCommands,
+        "create_worker" => ToolAccess::CreateWorktrees,
+        "create_task" | "assign_task" => ToolAccess::AssignTasks,
+        _ => ToolAccess::Open,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
     pub success: bool,
@@ -42,20 +130,94 @@ impl McpHandler {
             browser_bridge,
             panel_manager,
             orchestrator: None,
+            audit_log: Arc::new(AuditLog::new()),
+            command_policy: CommandPolicy::default(),
+            sessions: RwLock::new(HashMap::new()),
+            local_operator: RwLock::new(None),
+            token_issuer: RwLock::new(None),
         }
     }
     
     pub fn set_orchestrator(&mut self, orchestrator: Arc<RwLock<Orchestrator>>) {
         self.orchestrator = Some(orchestrator);
     }
+    
+    pub fn set_command_policy(&mut self, policy: CommandPolicy) {
+        self.command_policy = policy;
+    }
+    
+    pub fn set_audit_log(&mut self, audit_log: Arc<AuditLog>) {
+        self.audit_log = audit_log;
+    }
+    
+    pub fn audit_log(&self) -> Arc<AuditLog> {
+        self.audit_log.clone()
+    }
 
-    pub async fn handle_tool_call(&self, tool_name: &str, params: serde_json::Value) -> Result<ToolResult> {
+    /// Let calls authenticated with `token` act as `agent_id`
+    pub async fn bind_session(&self, token: String, agent_id: AgentId) {
+        self.sessions.write().await.insert(token, agent_id);
+    }
+    
+    pub async fn unbind_session(&self, token: &str) {
+        self.sessions.write().await.remove(token);
+    }
+    
+    /// Issue a session token for every agent created from now on
+    pub async fn enable_session_tokens(&self, systems_manager: Arc<SystemsManager>) {
+        *self.token_issuer.write().await = Some(systems_manager);
+    }
+    
+    /// Have the networking layer issue an auth token with `scopes` and bind it
+    /// to `agent_id`, so connections presenting it act as that agent.
+    /// Returns `None` while session tokens are off.
+    pub async fn issue_session(&self, agent_id: AgentId, scopes: Vec<String>) -> Result<Option<String>> {
+        let Some(issuer) = self.token_issuer.read().await.clone() else {
+            return Ok(None);
+        };
+        let token = issuer.issue_auth_token("ui-framework", scopes).await
+            .map_err(|e| anyhow!("Failed to issue a token for agent {:?}: {}", agent_id, e))?;
+        self.bind_session(token.clone(), agent_id).await;
+        Ok(Some(token))
+    }
+    
+    /// Attribute token-less local calls to `agent_id`. Leave unset when the
+    /// networking layer requires authentication.
+    pub async fn set_local_operator(&self, agent_id: Option<AgentId>) {
+        *self.local_operator.write().await = agent_id;
+    }
+    
+    async fn resolve_caller(&self, caller: &CallerIdentity) -> Option<AgentId> {
+        match caller {
+            CallerIdentity::Session(token) => self.sessions.read().await.get(token).copied(),
+            CallerIdentity::Local => *self.local_operator.read().await,
+            CallerIdentity::Plugin(_) => None,
+        }
+    }
+    
+    /// Handle a tool call on behalf of the agent bound to `caller`
+    pub async fn handle_tool_call(
+        &self,
+        caller: &CallerIdentity,
+        tool_name: &str,
+        params: serde_json::Value,
+    ) -> Result<ToolResult> {
+        let agent_id = self.resolve_caller(caller).await;
+        
         let tool_call = McpToolCall {
+            agent_id,
             tool_name: tool_name.to_string(),
             params,
         };
         
-        let result = self.handle_tool_call_internal(tool_call).await?;
+        let agent = match agent_id {
+            Some(id) => self.ui_state.read().await.channel_manager.read().await.get_agent(&id),
+            None => None,
+        };
+        
+        self.authorize(&tool_call, agent.as_ref()).await?;
+        
+        let result = self.handle_tool_call_internal(tool_call, agent.as_ref()).await?;
         
         Ok(ToolResult {
             success: true,
@@ -63,11 +225,96 @@ impl McpHandler {
         })
     }
     
-    async fn handle_tool_call_internal(&self, tool_call: McpToolCall) -> Result<serde_json::Value> {
-        tracing::debug!("Handling MCP tool call: {}", tool_call.tool_name);
+    /// Check the tool against the calling agent's permissions, auditing denials
+    async fn authorize(&self, tool_call: &McpToolCall, agent: Option<&AgentComponent>) -> Result<()> {
+        let access = required_access(&tool_call.tool_name);
+        if access == ToolAccess::Open {
+            return Ok(());
+        }
+        
+        let reason = match agent {
+            None if tool_call.agent_id.is_some() => Some("unknown agent".to_string()),
+            None => Some("caller is not bound to an agent".to_string()),
+            Some(agent) => {
+                let permissions = &agent.permissions;
+                let allowed = match access {
+                    ToolAccess::Open | ToolAccess::Registered => true,
+                    ToolAccess::ModifyFiles => permissions.can_modify_files,
+                    ToolAccess::ExecuteCommands => permissions.can_execute_commands,
+                    ToolAccess::CreateWorktrees => permissions.can_create_worktrees,
+                    ToolAccess::AssignTasks => permissions.can_assign_tasks,
+                };
+                (!allowed).then(|| format!("agent lacks {:?} permission", access))
+            }
+        };
+        
+        match reason {
+            Some(reason) => Err(self.deny(tool_call.agent_id, agent, &tool_call.tool_name, reason).await),
+            None => Ok(()),
+        }
+    }
+    
+    /// Record a denied call and build the error returned to the caller
+    async fn deny(
+        &self,
+        agent_id: Option<AgentId>,
+        agent: Option<&AgentComponent>,
+        tool_name: &str,
+        reason: String,
+    ) -> anyhow::Error {
+        self.audit_log.record_denial(
+            agent_id,
+            agent.map(|a| a.name.clone()),
+            tool_name,
+            &reason,
+        ).await;
+        anyhow!("Permission denied for '{}': {}", tool_name, reason)
+    }
+    
+    /// Resolve `path` inside the agent's worktree after canonicalization.
+    /// Relative paths are taken relative to the worktree. Paths that don't
+    /// exist yet are resolved through their closest existing ancestor.
+    async fn resolve_in_worktree(
+        &self,
+        agent: Option<&AgentComponent>,
+        tool_name: &str,
+        path: &str,
+    ) -> Result<PathBuf> {
+        let Some(agent) = agent else {
+            return Err(self.deny(None, None, tool_name, "file access requires an agent".to_string()).await);
+        };
+        let Some(worktree) = &agent.worktree_path else {
+            return Err(self.deny(Some(agent.id), Some(agent), tool_name, "agent has no worktree".to_string()).await);
+        };
+        
+        let worktree = fs::canonicalize(worktree).await?;
+        let requested = Path::new(path);
+        let candidate = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            worktree.join(requested)
+        };
+        
+        let resolved = match canonicalize_partial(&candidate).await {
+            Some(resolved) if resolved.starts_with(&worktree) => resolved,
+            _ => {
+                let reason = format!("'{}' is outside worktree {}", path, worktree.display());
+                return Err(self.deny(Some(agent.id), Some(agent), tool_name, reason).await);
+            }
+        };
+        
+        Ok(resolved)
+    }
+    
+    async fn handle_tool_call_internal(
+        &self,
+        tool_call: McpToolCall,
+        agent: Option<&AgentComponent>,
+    ) -> Result<serde_json::Value> {
+        tracing::debug!("Handling MCP tool call: {} (agent {:?})", tool_call.tool_name, tool_call.agent_id);
         
         match tool_call.tool_name.as_str() {
-            "show_file" => self.handle_show_file(tool_call.params).await,
+            "show_file" => self.handle_show_file(agent, tool_call.params).await,
             "update_editor" => self.handle_update_editor(tool_call.params).await,
             "show_terminal_output" => self.handle_show_terminal_output(tool_call.params).await,
             "update_file_tree" => self.handle_update_file_tree(tool_call.params).await,
@@ -76,18 +323,18 @@ impl McpHandler {
             "update_status_bar" => self.handle_update_status_bar(tool_call.params).await,
             "show_notification" => self.handle_show_notification(tool_call.params).await,
             "show_chat_message" => self.handle_show_chat_message(tool_call.params).await,
-            "execute_command" => self.handle_execute_command(tool_call.params).await,
-            "save_file" => self.handle_save_file(tool_call.params).await,
-            "read_file" => self.handle_read_file(tool_call.params).await,
+            "execute_command" => self.handle_execute_command(agent, tool_call.params).await,
+            "save_file" => self.handle_save_file(agent, tool_call.params).await,
+            "read_file" => self.handle_read_file(agent, tool_call.params).await,
             "create_task" => self.handle_create_task(tool_call.params).await,
             "create_worker" => self.handle_create_worker(tool_call.params).await,
             "assign_task" => self.handle_assign_task(tool_call.params).await,
-            "complete_task" => self.handle_complete_task(tool_call.params).await,
+            "complete_task" => self.handle_complete_task(agent, tool_call.params).await,
             _ => Err(anyhow!("Unknown MCP tool: {}", tool_call.tool_name)),
         }
     }
 
-    async fn handle_show_file(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn handle_show_file(&self, agent: Option<&AgentComponent>, params: serde_json::Value) -> Result<serde_json::Value> {
         #[derive(Deserialize)]
         struct ShowFileParams {
             path: String,
@@ -97,10 +344,10 @@ impl McpHandler {
         
         let mut params: ShowFileParams = serde_json::from_value(params)?;
         
-        // If content not provided, read from file system
+        // If content not provided, read from the agent's worktree
         if params.content.is_none() {
-            let path = PathBuf::from(&params.path);
-            if path.exists() {
+            let path = self.resolve_in_worktree(agent, "show_file", &params.path).await?;
+            if path.is_file() {
                 params.content = Some(fs::read_to_string(&path).await?);
             } else {
                 return Err(anyhow!("File not found: {}", params.path));
@@ -377,7 +624,7 @@ impl McpHandler {
         }))
     }
 
-    async fn handle_execute_command(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn handle_execute_command(&self, agent: Option<&AgentComponent>, params: serde_json::Value) -> Result<serde_json::Value> {
         #[derive(Deserialize)]
         struct ExecuteCommandParams {
             command: String,
@@ -386,36 +633,33 @@ impl McpHandler {
         
         let params: ExecuteCommandParams = serde_json::from_value(params)?;
         
-        // This is used for context switching and git operations
-        use tokio::process::Command;
-        
-        let mut cmd = Command::new("sh");
-        cmd.arg("-c").arg(&params.command);
-        
-        if let Some(dir) = &params.working_directory {
-            cmd.current_dir(dir);
+        // Commands run inside the agent's worktree unless a subdirectory is given
+        let working_directory = self.resolve_in_worktree(
+            agent,
+            "execute_command",
+            params.working_directory.as_deref().unwrap_or("."),
+        ).await?;
+        if !working_directory.is_dir() {
+            return Err(anyhow!("Not a directory: {}", working_directory.display()));
         }
         
-        let output = cmd.output().await?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        // This is used for context switching and git operations
+        let output = run_command(&params.command, &working_directory, &self.command_policy).await?;
         
         // Parse command to see if it's a context switch
         if params.command.contains("claude --continue") {
-            // Extract worktree from working_directory
-            if let Some(dir) = params.working_directory {
-                tracing::info!("Context switch to: {}", dir);
-                
-                // Update UI state with new context
-                let ui_state = self.ui_state.write().await;
-                // TODO: Add current_worktree field to ui_state
-                
-                // Send notification
-                self.browser_bridge.show_notification(
-                    "Context Switched".to_string(),
-                    format!("Switched to worktree: {}", dir)
-                ).await?;
-            }
+            let dir = working_directory.display().to_string();
+            tracing::info!("Context switch to: {}", dir);
+            
+            // Update UI state with new context
+            let ui_state = self.ui_state.write().await;
+            // TODO: Add current_worktree field to ui_state
+            
+            // Send notification
+            self.browser_bridge.show_notification(
+                "Context Switched".to_string(),
+                format!("Switched to worktree: {}", dir)
+            ).await?;
         }
         
         // Handle git worktree commands
@@ -424,14 +668,16 @@ impl McpHandler {
         }
         
         Ok(serde_json::json!({
-            "success": output.status.success(),
-            "stdout": stdout,
-            "stderr": stderr,
-            "exit_code": output.status.code().unwrap_or(-1),
+            "success": output.exit_code == Some(0),
+            "stdout": String::from_utf8_lossy(&output.stdout),
+            "stderr": String::from_utf8_lossy(&output.stderr),
+            "exit_code": output.exit_code.unwrap_or(-1),
+            "timed_out": output.timed_out,
+            "truncated": output.truncated,
         }))
     }
     
-    async fn handle_save_file(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn handle_save_file(&self, agent: Option<&AgentComponent>, params: serde_json::Value) -> Result<serde_json::Value> {
         #[derive(Deserialize)]
         struct SaveFileParams {
             path: String,
@@ -440,7 +686,7 @@ impl McpHandler {
         }
         
         let params: SaveFileParams = serde_json::from_value(params)?;
-        let path = PathBuf::from(&params.path);
+        let path = self.resolve_in_worktree(agent, "save_file", &params.path).await?;
         
         // Create parent directories if requested
         if params.create_directories.unwrap_or(false) {
@@ -467,17 +713,17 @@ impl McpHandler {
         }))
     }
     
-    async fn handle_read_file(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn handle_read_file(&self, agent: Option<&AgentComponent>, params: serde_json::Value) -> Result<serde_json::Value> {
         #[derive(Deserialize)]
         struct ReadFileParams {
             path: String,
         }
         
         let params: ReadFileParams = serde_json::from_value(params)?;
-        let path = PathBuf::from(&params.path);
+        let path = self.resolve_in_worktree(agent, "read_file", &params.path).await?;
         
         // Check if file exists
-        if !path.exists() {
+        if !path.is_file() {
             return Err(anyhow!("File not found: {}", params.path));
         }
         
@@ -557,11 +803,15 @@ impl McpHandler {
         if let Some(orchestrator) = &self.orchestrator {
             let orchestrator = orchestrator.read().await;
             let agent_id = orchestrator.create_worker(params.name.clone(), worktree_path).await?;
+            // The worker connects with this token; its calls are checked
+            // against its own permissions rather than the creator's
+            let token = self.issue_session(agent_id, vec![format!("channel:{}", UI_FRAMEWORK_CHANNEL)]).await?;
             
             Ok(serde_json::json!({
                 "success": true,
                 "agent_id": agent_id,
                 "name": params.name,
+                "token": token,
             }))
         } else {
             Err(anyhow!("Orchestrator not initialized"))
@@ -590,10 +840,9 @@ impl McpHandler {
         }
     }
     
-    async fn handle_complete_task(&self, params: serde_json::Value) -> Result<serde_json::Value> {
+    async fn handle_complete_task(&self, agent: Option<&AgentComponent>, params: serde_json::Value) -> Result<serde_json::Value> {
         #[derive(Deserialize)]
         struct CompleteTaskParams {
-            agent_id: String,
             task_id: String,
             success: bool,
             output: String,
@@ -603,7 +852,9 @@ impl McpHandler {
         
         let params: CompleteTaskParams = serde_json::from_value(params)?;
         
-        let agent_id = AgentId(Uuid::parse_str(&params.agent_id)?);
+        // Agents may only report their own tasks
+        let agent_id = agent.map(|a| a.id)
+            .ok_or_else(|| anyhow!("complete_task requires an agent"))?;
         let task_id = TaskId(Uuid::parse_str(&params.task_id)?);
         
         let result = TaskResult {
@@ -629,4 +880,112 @@ impl McpHandler {
             Err(anyhow!("Orchestrator not initialized"))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Canonicalize a path that may not exist yet: the closest existing ancestor is
+/// canonicalized and the remaining components appended. Returns None if the
+/// remainder contains `..` or anything other than plain names.
+async fn canonicalize_partial(path: &Path) -> Option<PathBuf> {
+    let mut existing = path.to_path_buf();
+    let mut remainder = Vec::new();
+    
+    loop {
+        if let Ok(canonical) = fs::canonicalize(&existing).await {
+            let mut resolved = canonical;
+            for component in remainder.iter().rev() {
+                resolved.push(component);
+            }
+            return Some(resolved);
+        }
+        
+        match existing.components().next_back() {
+            Some(Component::Normal(name)) => remainder.push(name.to_os_string()),
+            _ => return None,
+        }
+        if !existing.pop() {
+            return None;
+        }
+    }
+}
+
+struct CommandOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    /// None if the process was killed or terminated by a signal
+    exit_code: Option<i32>,
+    timed_out: bool,
+    truncated: bool,
+}
+
+/// Run a shell command, killing it after the policy timeout and keeping at
+/// most `max_output_bytes` of each output stream
+async fn run_command(command: &str, working_directory: &Path, policy: &CommandPolicy) -> Result<CommandOutput> {
+    use std::process::Stdio;
+    use tokio::process::Command;
+    
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(working_directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let limit = policy.max_output_bytes;
+    
+    let finished = tokio::time::timeout(policy.timeout, async {
+        let ((stdout, stdout_truncated), (stderr, stderr_truncated)) =
+            tokio::join!(read_capped(stdout, limit), read_capped(stderr, limit));
+        let status = child.wait().await;
+        (stdout, stderr, stdout_truncated || stderr_truncated, status)
+    }).await;
+    
+    match finished {
+        Ok((stdout, stderr, truncated, status)) => Ok(CommandOutput {
+            stdout,
+            stderr,
+            exit_code: status?.code(),
+            timed_out: false,
+            truncated,
+        }),
+        Err(_) => {
+            let _ = child.kill().await;
+            Ok(CommandOutput {
+                stdout: Vec::new(),
+                stderr: format!("Command timed out after {}s", policy.timeout.as_secs()).into_bytes(),
+                exit_code: None,
+                timed_out: true,
+                truncated: false,
+            })
+        }
+    }
+}
+
+/// Read a stream to the end, keeping only the first `limit` bytes.
+/// The rest is drained so the child never blocks on a full pipe.
+async fn read_capped<R: AsyncRead + Unpin>(reader: Option<R>, limit: usize) -> (Vec<u8>, bool) {
+    let Some(mut reader) = reader else {
+        return (Vec::new(), false);
+    };
+    
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut chunk = [0u8; 8192];
+    
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let room = limit.saturating_sub(kept.len());
+                kept.extend_from_slice(&chunk[..n.min(room)]);
+                truncated |= n > room;
+            }
+        }
+    }
+    
+    (kept, truncated)
+}
@@ -11,6 +11,7 @@ pub const PACKET_TYPE_UI_EVENT: u16 = 4;
 pub const PACKET_TYPE_TOUCH_EVENT: u16 = 5;
 pub const PACKET_TYPE_KEY_EVENT: u16 = 6;
 pub const PACKET_TYPE_RESIZE: u16 = 7;
+pub const PACKET_TYPE_ACCESSIBILITY_ACTION: u16 = 8;
 
 // Messages from server to browser (100-199)
 pub const PACKET_TYPE_RENDER_BATCH: u16 = 100;
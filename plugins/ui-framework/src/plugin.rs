@@ -3,13 +3,14 @@ use playground_systems_logic::{System, World, LogicResult, SystemsManager, UiInt
 use playground_core_types::{
     Priority, Shared, shared, Handle, handle,
 };
+use playground_systems_ui::messages::AccessibilityActionMessage;
 use std::collections::HashMap;
 use tokio::sync::RwLock;
 // Note: Using SystemsManager logging instead of tracing
 use uuid;
 
 use crate::panel_manager::PanelManager;
-use crate::mcp_handler::McpHandler;
+use crate::mcp_handler::{McpHandler, CallerIdentity};
 use crate::browser_bridge::BrowserBridge;
 use crate::ui_state::UiState;
 use crate::orchestrator::Orchestrator;
@@ -23,6 +24,9 @@ pub struct UiFrameworkPlugin {
     ui_state: Shared<UiState>,
     orchestrator: Shared<Orchestrator>,
     channel_id: Option<u16>,
+    /// Set when the networking layer requires authentication; token-less
+    /// tool calls are then denied instead of acting as the local operator
+    require_session: bool,
     systems_manager: Handle<playground_systems_logic::SystemsManager>,
 }
 
@@ -67,9 +71,40 @@ impl UiFrameworkPlugin {
             ui_state,
             orchestrator,
             channel_id: None,
+            require_session: false,
             systems_manager,
         }
     }
+    
+    pub fn with_session_auth(mut self, require_session: bool) -> Self {
+        self.require_session = require_session;
+        self
+    }
+    
+    /// Register the human operator. Without session auth, token-less local
+    /// tool calls act as it; with it, the operator gets an auth token of its
+    /// own and new agents are issued tokens when they are created.
+    async fn register_operator(&self) -> LogicResult<()> {
+        let workspace = std::env::current_dir().ok();
+        let operator = self.ui_state.write().await
+            .register_human_agent("Operator".to_string(), workspace).await
+            .map_err(|e| playground_systems_logic::LogicError::InitializationFailed(e.to_string()))?;
+        
+        if !self.require_session {
+            self.mcp_handler.set_local_operator(Some(operator)).await;
+            return Ok(());
+        }
+        
+        self.mcp_handler.enable_session_tokens(self.systems_manager.clone()).await;
+        let token = self.mcp_handler.issue_session(operator, vec!["*".to_string()]).await
+            .map_err(|e| playground_systems_logic::LogicError::InitializationFailed(e.to_string()))?
+            .ok_or_else(|| playground_systems_logic::LogicError::InitializationFailed(
+                "Session tokens are not enabled".to_string()
+            ))?;
+        self.systems_manager.log_component("plugins/ui-framework", playground_systems_logic::LogLevel::Info,
+            format!("Operator token: {} (connect with ?token=...)", token)).await;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -115,6 +150,7 @@ impl System for UiFrameworkPlugin {
         self.systems_manager.log_component("plugins/ui-framework", playground_systems_logic::LogLevel::Info,
             "Registering MCP tools...".to_string()).await;
         self.register_mcp_tools().await?;
+        self.register_operator().await?;
         self.systems_manager.log_component("plugins/ui-framework", playground_systems_logic::LogLevel::Info,
             "MCP tools registered".to_string()).await;
         
@@ -160,7 +196,8 @@ impl System for UiFrameworkPlugin {
             // Check our dynamically allocated channel
             if let Ok(packets) = net.receive_packets(channel_id).await {
                 for packet in packets {
-                    self.handle_packet(packet.packet_type, packet.data).await;
+                    let caller = CallerIdentity::from_sender(&packet.sender);
+                    self.handle_packet(&caller, packet.packet_type, packet.data).await;
                 }
             }
         }
@@ -235,27 +272,29 @@ impl UiFrameworkPlugin {
         });
     }
     
-    async fn handle_packet(&self, packet_type: u16, data: Vec<u8>) {
+    /// Handle a packet from `caller`, as identified by the networking layer
+    async fn handle_packet(&self, caller: &CallerIdentity, packet_type: u16, data: Vec<u8>) {
         use crate::packet_types::*;
         
         match packet_type {
-            PACKET_TYPE_MCP_TOOL_CALL => self.handle_mcp_tool_call(data).await,
+            PACKET_TYPE_MCP_TOOL_CALL => self.handle_mcp_tool_call(caller, data).await,
             PACKET_TYPE_PANEL_UPDATE => self.handle_panel_update(data).await,
             PACKET_TYPE_CHAT_MESSAGE => self.handle_chat_message(data).await,
+            PACKET_TYPE_ACCESSIBILITY_ACTION => self.handle_accessibility_action(data).await,
             _ => {
                 // debug!("Unknown packet type {} received on UI Framework channel", packet_type);
             }
         }
     }
     
-    async fn handle_mcp_tool_call(&self, data: Vec<u8>) {
+    async fn handle_mcp_tool_call(&self, caller: &CallerIdentity, data: Vec<u8>) {
         match serde_json::from_slice::<serde_json::Value>(&data) {
             Ok(msg) => {
                 if let (Some(tool_name), Some(params)) = (
                     msg.get("tool_name").and_then(|v| v.as_str()),
                     msg.get("params")
                 ) {
-                    match self.mcp_handler.handle_tool_call(tool_name, params.clone()).await {
+                    match self.mcp_handler.handle_tool_call(caller, tool_name, params.clone()).await {
                         Ok(result) => {
                             // debug!("Tool call succeeded: {:?}", result);
                         }
@@ -299,7 +338,26 @@ impl UiFrameworkPlugin {
         }
     }
     
-    async fn handle_browser_message(&self, data: Vec<u8>) {
+    /// A screen reader activated an element of the browser's mirrored
+    /// accessibility tree
+    async fn handle_accessibility_action(&self, data: Vec<u8>) {
+        let message: AccessibilityActionMessage = match serde_json::from_slice(&data) {
+            Ok(message) => message,
+            Err(e) => {
+                self.systems_manager.log_component("plugins/ui-framework", playground_systems_logic::LogLevel::Error,
+                    format!("[UI-FW] Invalid accessibility action: {}", e)).await;
+                return;
+            }
+        };
+
+        let mut ui_interface = self.systems_manager.ui_interface();
+        if let Err(e) = ui_interface.perform_accessibility_action(message.element, message.action).await {
+            self.systems_manager.log_component("plugins/ui-framework", playground_systems_logic::LogLevel::Error,
+                format!("[UI-FW] Accessibility action failed: {}", e)).await;
+        }
+    }
+    
+    async fn handle_browser_message(&self, caller: &CallerIdentity, data: Vec<u8>) {
         // Parse and handle messages from the browser
         match serde_json::from_slice::<serde_json::Value>(&data) {
             Ok(msg) => {
@@ -313,7 +371,7 @@ impl UiFrameworkPlugin {
                                 msg.get("tool_name").and_then(|v| v.as_str()),
                                 msg.get("params")
                             ) {
-                                match self.mcp_handler.handle_tool_call(tool_name, params.clone()).await {
+                                match self.mcp_handler.handle_tool_call(caller, tool_name, params.clone()).await {
                                     Ok(result) => {
                                         // debug!("Tool call succeeded: {:?}", result);
                                     }
@@ -138,13 +138,13 @@ impl UiState {
     // Agent Operations
     // ========================================================================
 
-    pub async fn register_human_agent(&mut self, name: String) -> Result<AgentId> {
+    pub async fn register_human_agent(&mut self, name: String, worktree: Option<std::path::PathBuf>) -> Result<AgentId> {
         let agent = AgentComponent {
             id: AgentId::new(),
             name,
             agent_type: AgentType::Human,
             status: AgentStatus::Idle,
-            worktree_path: None,
+            worktree_path: worktree,
             permissions: AgentPermissions {
                 can_execute_commands: true,
                 can_modify_files: true,
@@ -9,7 +9,7 @@ use uuid::Uuid;
 use crate::browser_bridge::{BrowserBridge, BrowserUpdate};
 use crate::channel_manager::ChannelManager;
 use crate::message_system::MessageSystem;
-use crate::mcp_handler::McpHandler;
+use crate::mcp_handler::{McpHandler, CallerIdentity};
 use crate::ui_state::UiState;
 
 /// Handles WebSocket communication for the UI Framework Plugin
@@ -183,12 +183,17 @@ impl WebSocketHandler {
         Ok(())
     }
     
-    /// Handle MCP tool call from LLM
-    pub async fn handle_mcp_tool_call(&self, tool_name: &str, params: serde_json::Value) -> Result<()> {
+    /// Handle MCP tool call from LLM, acting as the agent bound to `caller`
+    pub async fn handle_mcp_tool_call(
+        &self,
+        caller: &CallerIdentity,
+        tool_name: &str,
+        params: serde_json::Value,
+    ) -> Result<()> {
         debug!("Handling MCP tool call: {}", tool_name);
         
         // Forward to MCP handler
-        self.mcp_handler.handle_tool_call(tool_name, params).await?;
+        self.mcp_handler.handle_tool_call(caller, tool_name, params).await?;
         
         // Get any browser updates that resulted
         let updates = self.browser_bridge.flush_updates().await?;
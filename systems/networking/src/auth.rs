@@ -0,0 +1,409 @@
+//! Token authentication for WebSocket and MCP clients
+//!
+//! Authentication is on unless the `auth_enabled` server option is set to
+//! false. Clients obtain a token in one of these ways:
+//! - Pairing: a short code is printed to the console when the server starts.
+//!   `POST /auth/pair {"code": "123456"}` exchanges it for a full-access token.
+//!   Codes are single use and rotate after too many failed attempts. Each
+//!   origin is throttled with exponential backoff after repeated failures, and
+//!   after `MAX_PAIRING_ROTATIONS` failure-driven rotations pairing is locked
+//!   until an operator issues a new code.
+//! - Pre-shared token file (`auth_token_file` server option): one token per
+//!   line followed by its scopes, e.g. `s3cret channel:ui channel:editor tool:read_file`.
+//!   `#` starts a comment; a token without scopes gets full access.
+//! - Issued by a plugin (`NetworkingSystem::issue_auth_token`) for a client it
+//!   starts itself, such as an agent process; the plugin hands the token over.
+//!
+//! Scopes are `channel:<name>`, `tool:<name>`, `resource:<scheme>` (`file`,
+//! `console`, `ecs`), `prompt:<name>`, `<kind>:*` or `*`.
+//! WebSocket clients pass their token as `/ws?token=...`; MCP clients use
+//! `Authorization: Bearer ...` (or `?token=` for SSE).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use axum::{
+    Router,
+    routing::post,
+    extract::{State, ConnectInfo},
+    http::{HeaderMap, StatusCode, header::AUTHORIZATION},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use playground_core_types::{Handle, handle, Shared, shared, CoreResult, CoreError};
+use playground_core_console::LogLevel;
+
+/// Scope granting access to everything
+pub const FULL_ACCESS: &str = "*";
+
+const PAIRING_CODE_TTL: Duration = Duration::from_secs(600);
+const MAX_PAIRING_FAILURES: u32 = 5;
+/// Failure-driven rotations before pairing locks until an operator re-issues a code
+const MAX_PAIRING_ROTATIONS: u32 = 3;
+/// Failures an origin may make before it has to back off
+const MAX_ORIGIN_FAILURES: u32 = 3;
+const ORIGIN_BACKOFF_BASE: Duration = Duration::from_secs(2);
+const ORIGIN_BACKOFF_MAX: Duration = Duration::from_secs(300);
+const AUTH_LOG_COMPONENT: &str = "networking.auth";
+
+/// What an authenticated client may access
+#[derive(Debug, Clone)]
+pub struct AuthGrant {
+    /// Where the token came from ("pairing", "token-file", "anonymous")
+    pub source: String,
+    pub scopes: Vec<String>,
+}
+
+impl AuthGrant {
+    pub fn allows_channel(&self, channel: &str) -> bool {
+        self.allows("channel", channel)
+    }
+
+    pub fn allows_tool(&self, tool: &str) -> bool {
+        self.allows("tool", tool)
+    }
+
+    /// Resources are granted by URI scheme, e.g. `resource:file` for workspace files
+    pub fn allows_resource(&self, uri: &str) -> bool {
+        let scheme = uri.split_once("://").map(|(scheme, _)| scheme).unwrap_or(uri);
+        self.allows("resource", scheme)
+    }
+
+    pub fn allows_prompt(&self, prompt: &str) -> bool {
+        self.allows("prompt", prompt)
+    }
+
+    fn allows(&self, kind: &str, name: &str) -> bool {
+        self.scopes.iter().any(|scope| {
+            if scope == FULL_ACCESS {
+                return true;
+            }
+            match scope.split_once(':') {
+                Some((scope_kind, pattern)) if scope_kind == kind => {
+                    pattern == "*" || pattern == name
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+struct PairingCode {
+    code: String,
+    issued_at: Instant,
+    failures: u32,
+}
+
+#[derive(Default)]
+struct PairingState {
+    current: Option<PairingCode>,
+    /// Rotations caused by failed attempts since an operator last issued a code
+    rotations: u32,
+    /// Set once `rotations` reaches the limit; no code is valid until re-issued
+    locked: bool,
+}
+
+/// Failed pairing attempts from one origin
+struct OriginAttempts {
+    failures: u32,
+    blocked_until: Option<Instant>,
+}
+
+pub struct AuthManager {
+    /// When disabled every client gets an anonymous full-access grant
+    enabled: bool,
+    tokens: Shared<HashMap<String, Handle<AuthGrant>>>,
+    pairing: Shared<PairingState>,
+    attempts: Shared<HashMap<String, OriginAttempts>>,
+}
+
+impl AuthManager {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            tokens: shared(HashMap::new()),
+            pairing: shared(PairingState::default()),
+            attempts: shared(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Load pre-shared tokens, returning how many were added
+    pub async fn load_token_file(&self, path: &Path) -> CoreResult<usize> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let mut tokens = self.tokens.write().await;
+        let mut loaded = 0;
+
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let mut parts = line.split_whitespace();
+            let Some(token) = parts.next() else { continue };
+
+            let mut scopes: Vec<String> = parts.map(str::to_string).collect();
+            if scopes.is_empty() {
+                scopes.push(FULL_ACCESS.to_string());
+            }
+
+            tokens.insert(token.to_string(), handle(AuthGrant {
+                source: "token-file".to_string(),
+                scopes,
+            }));
+            loaded += 1;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Generate a new pairing code and print it to the console.
+    /// This is the operator entry point and also lifts a pairing lock.
+    pub async fn issue_pairing_code(&self) -> String {
+        let mut pairing = self.pairing.write().await;
+        pairing.rotations = 0;
+        pairing.locked = false;
+        Self::replace_code(&mut pairing).await
+    }
+
+    async fn replace_code(pairing: &mut PairingState) -> String {
+        let value = u32::from_le_bytes(uuid::Uuid::new_v4().as_bytes()[..4].try_into().unwrap_or_default());
+        let code = format!("{:06}", value % 1_000_000);
+
+        pairing.current = Some(PairingCode {
+            code: code.clone(),
+            issued_at: Instant::now(),
+            failures: 0,
+        });
+
+        let _ = playground_core_console::write_line(&format!(
+            "Pairing code: {} (valid for {} minutes)", code, PAIRING_CODE_TTL.as_secs() / 60
+        )).await;
+
+        code
+    }
+
+    /// Exchange a pairing code for a full-access token
+    pub async fn pair(&self, code: &str, origin: &str) -> CoreResult<String> {
+        if let Some(retry_in) = self.origin_backoff(origin).await {
+            log_denied(format!("Throttled pairing attempt from {}", origin)).await;
+            return Err(CoreError::InvalidState(format!(
+                "Too many pairing attempts, retry in {}s", retry_in.as_secs().max(1)
+            )));
+        }
+
+        let mut pairing = self.pairing.write().await;
+
+        if pairing.locked {
+            drop(pairing);
+            log_denied(format!("Pairing attempt from {} while pairing is locked", origin)).await;
+            return Err(CoreError::InvalidState(
+                "Pairing is locked, ask the operator for a new code".to_string()
+            ));
+        }
+
+        let accepted = match pairing.current.as_mut() {
+            Some(current) if current.issued_at.elapsed() < PAIRING_CODE_TTL => {
+                if constant_time_eq(current.code.as_bytes(), code.as_bytes()) {
+                    true
+                } else {
+                    current.failures += 1;
+                    false
+                }
+            }
+            _ => false,
+        };
+
+        if !accepted {
+            let (exhausted, expired) = pairing.current.as_ref()
+                .map(|current| {
+                    (current.failures >= MAX_PAIRING_FAILURES, current.issued_at.elapsed() >= PAIRING_CODE_TTL)
+                })
+                .unwrap_or((false, false));
+
+            if exhausted {
+                pairing.rotations += 1;
+                if pairing.rotations >= MAX_PAIRING_ROTATIONS {
+                    pairing.current = None;
+                    pairing.locked = true;
+                } else {
+                    Self::replace_code(&mut pairing).await;
+                }
+            } else if expired {
+                Self::replace_code(&mut pairing).await;
+            }
+            let locked = pairing.locked;
+            drop(pairing);
+
+            self.record_failure(origin).await;
+            log_denied(format!("Invalid pairing code from {}", origin)).await;
+            if locked && exhausted {
+                log_denied("Pairing locked after repeated failures until a new code is issued".to_string()).await;
+            }
+            return Err(CoreError::PermissionDenied("Invalid pairing code".to_string()));
+        }
+
+        // Codes are single use
+        pairing.current = None;
+        drop(pairing);
+        self.attempts.write().await.remove(origin);
+
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.tokens.write().await.insert(token.clone(), handle(AuthGrant {
+            source: "pairing".to_string(),
+            scopes: vec![FULL_ACCESS.to_string()],
+        }));
+
+        let _ = playground_core_console::log_component(
+            AUTH_LOG_COMPONENT,
+            LogLevel::Info,
+            format!("Paired new client from {}", origin),
+        ).await;
+
+        // Print a fresh code for the next device
+        self.issue_pairing_code().await;
+
+        Ok(token)
+    }
+
+    /// Remaining backoff for an origin, if it is currently blocked
+    async fn origin_backoff(&self, origin: &str) -> Option<Duration> {
+        let attempts = self.attempts.read().await;
+        let blocked_until = attempts.get(origin)?.blocked_until?;
+        blocked_until.checked_duration_since(Instant::now())
+    }
+
+    /// Count a failed attempt; past `MAX_ORIGIN_FAILURES` the origin is
+    /// blocked for a backoff that doubles with every further failure
+    async fn record_failure(&self, origin: &str) {
+        let mut attempts = self.attempts.write().await;
+        let entry = attempts.entry(origin.to_string()).or_insert(OriginAttempts {
+            failures: 0,
+            blocked_until: None,
+        });
+        entry.failures += 1;
+
+        if entry.failures >= MAX_ORIGIN_FAILURES {
+            let exponent = (entry.failures - MAX_ORIGIN_FAILURES).min(16);
+            let backoff = ORIGIN_BACKOFF_BASE.saturating_mul(1 << exponent).min(ORIGIN_BACKOFF_MAX);
+            entry.blocked_until = Some(Instant::now() + backoff);
+        }
+    }
+
+    /// Resolve a token to its grant. Failures are logged with `origin`.
+    pub async fn authenticate(&self, token: Option<&str>, origin: &str) -> CoreResult<Handle<AuthGrant>> {
+        if !self.enabled {
+            return Ok(handle(AuthGrant {
+                source: "anonymous".to_string(),
+                scopes: vec![FULL_ACCESS.to_string()],
+            }));
+        }
+
+        let Some(token) = token else {
+            log_denied(format!("Missing token from {}", origin)).await;
+            return Err(CoreError::PermissionDenied("Authentication required".to_string()));
+        };
+
+        let grant = self.tokens.read().await.get(token).cloned();
+        match grant {
+            Some(grant) => Ok(grant),
+            None => {
+                log_denied(format!("Unknown token from {}", origin)).await;
+                Err(CoreError::PermissionDenied("Invalid token".to_string()))
+            }
+        }
+    }
+
+    /// Authenticate an HTTP request by its bearer token, falling back to `query_token`
+    pub async fn authenticate_request(
+        &self,
+        headers: &HeaderMap,
+        query_token: Option<&str>,
+        origin: &str,
+    ) -> CoreResult<Handle<AuthGrant>> {
+        let bearer = headers.get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+
+        self.authenticate(bearer.or(query_token), origin).await
+    }
+
+    /// Create a token with `scopes`, recorded with `source` in its grant
+    pub async fn issue_token(&self, source: &str, scopes: Vec<String>) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.tokens.write().await.insert(token.clone(), handle(AuthGrant {
+            source: source.to_string(),
+            scopes,
+        }));
+        token
+    }
+
+    pub async fn revoke(&self, token: &str) -> CoreResult<()> {
+        if self.tokens.write().await.remove(token).is_none() {
+            return Err(CoreError::NotFound("Token not found".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn handle_pair(
+        State(auth): State<Arc<AuthManager>>,
+        ConnectInfo(peer): ConnectInfo<SocketAddr>,
+        Json(request): Json<PairRequest>,
+    ) -> Response {
+        match auth.pair(&request.code, &peer.ip().to_string()).await {
+            Ok(token) => Json(json!({
+                "token": token,
+                "scopes": [FULL_ACCESS],
+            })).into_response(),
+            Err(e @ CoreError::InvalidState(_)) => {
+                (StatusCode::TOO_MANY_REQUESTS, Json(json!({ "error": e.to_string() }))).into_response()
+            }
+            Err(e) => (StatusCode::UNAUTHORIZED, Json(json!({ "error": e.to_string() }))).into_response(),
+        }
+    }
+
+    pub fn router(&self) -> Router {
+        Router::new()
+            .route("/pair", post(Self::handle_pair))
+            .with_state(Arc::new(self.clone()))
+    }
+}
+
+impl Clone for AuthManager {
+    fn clone(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            tokens: self.tokens.clone(),
+            pairing: self.pairing.clone(),
+            attempts: self.attempts.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PairRequest {
+    code: String,
+}
+
+/// Record a rejected access attempt in the console log
+pub async fn log_denied(message: String) {
+    let _ = playground_core_console::log_component(AUTH_LOG_COMPONENT, LogLevel::Warning, message).await;
+}
+
+/// JSON body returned for rejected HTTP requests
+pub fn unauthorized_response(error: &CoreError) -> Response {
+    let body: Value = json!({ "error": error.to_string() });
+    (StatusCode::UNAUTHORIZED, Json(body)).into_response()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
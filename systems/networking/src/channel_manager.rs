@@ -1,12 +1,15 @@
 use std::collections::{HashMap, HashSet};
 use playground_core_server::ConnectionId;
-use playground_core_types::{Shared, shared, CoreResult, CoreError};
+use playground_core_types::{Handle, Shared, shared, CoreResult, CoreError};
 use crate::types::ChannelManifest;
+use crate::auth::{AuthGrant, log_denied};
 
 pub struct ChannelManager {
     channels: Shared<HashMap<u16, String>>,
     name_to_channel: Shared<HashMap<String, u16>>,
     subscriptions: Shared<HashMap<u16, HashSet<ConnectionId>>>,
+    /// Scopes of each authenticated connection
+    grants: Shared<HashMap<ConnectionId, Handle<AuthGrant>>>,
 }
 
 impl ChannelManager {
@@ -15,6 +18,7 @@ impl ChannelManager {
             channels: shared(HashMap::new()),
             name_to_channel: shared(HashMap::new()),
             subscriptions: shared(HashMap::new()),
+            grants: shared(HashMap::new()),
         };
         
         // Register default control channel
@@ -73,7 +77,32 @@ impl ChannelManager {
         channels.contains_key(&channel)
     }
     
+    /// Record the scopes a connection authenticated with
+    pub async fn authorize(&self, connection: ConnectionId, grant: Handle<AuthGrant>) {
+        self.grants.write().await.insert(connection, grant);
+    }
+
+    /// Check a connection's scopes against a channel, logging denials
+    pub async fn check_access(&self, channel: u16, connection: ConnectionId) -> CoreResult<()> {
+        let name = self.channels.read().await.get(&channel).cloned()
+            .ok_or_else(|| CoreError::NotFound(format!("Channel {} not found", channel)))?;
+
+        // Any authenticated connection may use the control channel
+        let allowed = self.grants.read().await.get(&connection)
+            .map(|grant| channel == 0 || grant.allows_channel(&name))
+            .unwrap_or(false);
+
+        if !allowed {
+            log_denied(format!("Connection {} denied access to channel '{}'", connection.0, name)).await;
+            return Err(CoreError::PermissionDenied(format!("No access to channel '{}'", name)));
+        }
+
+        Ok(())
+    }
+
     pub async fn subscribe(&self, channel: u16, connection: ConnectionId) -> CoreResult<()> {
+        self.check_access(channel, connection).await?;
+
         let mut subs = self.subscriptions.write().await;
         subs.entry(channel)
             .or_insert_with(HashSet::new)
@@ -89,6 +118,38 @@ impl ChannelManager {
         Ok(())
     }
     
+    /// Move every subscription of `from` to `to` (used when a session is resumed).
+    /// Channels the new connection's scopes don't cover are dropped.
+    pub async fn transfer_subscriptions(&self, from: ConnectionId, to: ConnectionId) {
+        let channels = self.channels.read().await;
+        let mut grants = self.grants.write().await;
+        grants.remove(&from);
+        let grant = grants.get(&to).cloned();
+
+        let mut subs = self.subscriptions.write().await;
+        for (channel, connections) in subs.iter_mut() {
+            if connections.remove(&from) {
+                let allowed = match (&grant, channels.get(channel)) {
+                    (Some(grant), Some(name)) => *channel == 0 || grant.allows_channel(name),
+                    _ => false,
+                };
+                if allowed {
+                    connections.insert(to);
+                }
+            }
+        }
+    }
+
+    /// Drop every subscription and the grant held by a connection
+    pub async fn remove_connection(&self, connection: ConnectionId) {
+        self.grants.write().await.remove(&connection);
+
+        let mut subs = self.subscriptions.write().await;
+        for connections in subs.values_mut() {
+            connections.remove(&connection);
+        }
+    }
+
     pub async fn get_subscribers(&self, channel: u16) -> Vec<ConnectionId> {
         let subs = self.subscriptions.read().await;
         subs.get(&channel)
@@ -0,0 +1,101 @@
+//! Inbound packets for server-side plugins
+//!
+//! The WebSocket reader queues client packets on a channel the connection's
+//! grant allows. The sender carries the connection's auth token, so plugins
+//! identify callers by how they authenticated, never by the payload.
+//!
+//! Plugins reach each other through the same queues: `send_to_plugin`
+//! pushes a packet onto the receiving plugin's channel with a
+//! `PacketSender::Plugin` sender, and it never goes out over WebSocket.
+//! `send_packet` only writes to connected clients.
+//!
+//! The plugin that owns the channel drains its queue with `receive_packets`
+//! once per frame.
+
+use std::collections::{HashMap, VecDeque};
+use playground_core_types::{Shared, shared};
+use crate::types::IncomingPacket;
+
+/// Packets kept per channel before new ones are dropped
+const MAX_QUEUED_PACKETS: usize = 4096;
+
+pub struct PacketInbox {
+    queues: Shared<HashMap<u16, VecDeque<IncomingPacket>>>,
+}
+
+impl PacketInbox {
+    pub fn new() -> Self {
+        Self {
+            queues: shared(HashMap::new()),
+        }
+    }
+
+    /// Queue a packet for its channel. Returns false if the queue is full
+    /// and the packet was dropped.
+    pub async fn push(&self, packet: IncomingPacket) -> bool {
+        let mut queues = self.queues.write().await;
+        let queue = queues.entry(packet.channel_id).or_default();
+        if queue.len() >= MAX_QUEUED_PACKETS {
+            return false;
+        }
+        queue.push_back(packet);
+        true
+    }
+
+    /// Take every packet queued for a channel, oldest first
+    pub async fn drain(&self, channel: u16) -> Vec<IncomingPacket> {
+        self.queues.write().await
+            .get_mut(&channel)
+            .map(|queue| queue.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    pub async fn queued(&self, channel: u16) -> usize {
+        self.queues.read().await.get(&channel).map(VecDeque::len).unwrap_or(0)
+    }
+}
+
+impl Default for PacketInbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PacketSender;
+
+    fn packet(channel_id: u16, packet_type: u16, sender: PacketSender) -> IncomingPacket {
+        IncomingPacket { channel_id, packet_type, data: vec![packet_type as u8], sender }
+    }
+
+    #[tokio::test]
+    async fn drains_each_channel_in_order() {
+        let inbox = PacketInbox::new();
+        let client = PacketSender::Client { connection: 7, token: Some("secret".to_string()) };
+        inbox.push(packet(1000, 1, client.clone())).await;
+        inbox.push(packet(1001, 2, PacketSender::Plugin("lsp-client".to_string()))).await;
+        inbox.push(packet(1000, 3, client.clone())).await;
+
+        let drained = inbox.drain(1000).await;
+        assert_eq!(drained.iter().map(|p| p.packet_type).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(drained.iter().all(|p| p.sender == client));
+        assert!(inbox.drain(1000).await.is_empty());
+
+        let other = inbox.drain(1001).await;
+        assert_eq!(other.len(), 1);
+        assert_eq!(other[0].sender, PacketSender::Plugin("lsp-client".to_string()));
+    }
+
+    #[tokio::test]
+    async fn drops_packets_past_the_limit() {
+        let inbox = PacketInbox::new();
+        for _ in 0..MAX_QUEUED_PACKETS {
+            assert!(inbox.push(packet(1000, 1, PacketSender::Plugin("a".to_string()))).await);
+        }
+        assert!(!inbox.push(packet(1000, 2, PacketSender::Plugin("a".to_string()))).await);
+        assert_eq!(inbox.queued(1000).await, MAX_QUEUED_PACKETS);
+        assert!(inbox.push(packet(1001, 1, PacketSender::Plugin("a".to_string()))).await);
+    }
+}
@@ -2,9 +2,14 @@ pub mod types;
 pub mod state;
 pub mod server;
 pub mod websocket;
+pub mod session;
+pub mod inbox;
+pub mod auth;
 pub mod channel_manager;
 pub mod batcher;
 pub mod mcp;
+pub mod mcp_resources;
+pub mod mcp_prompts;
 pub mod networking_system;
 pub mod vtable_handlers;
 pub mod registration;
@@ -12,6 +17,9 @@ pub mod registration;
 // Main export is the NetworkingSystem
 pub use networking_system::NetworkingSystem;
 // Re-export commonly used types
-pub use types::{Packet, Priority, ClientInfo, ClientStatus, ChannelManifest, McpTool, LogLevel};
+pub use types::{
+    Packet, IncomingPacket, PacketSender, Priority, ClientInfo, ClientStatus, ChannelManifest, LogLevel,
+    McpTool, McpResource, McpResourceContents, McpPrompt, McpPromptArgument,
+};
 // Export registration for system initialization
 pub use registration::initialize;
\ No newline at end of file
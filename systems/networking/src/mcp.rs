@@ -1,17 +1,32 @@
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
 use axum::{
     Router,
     routing::{get, post},
-    extract::State,
-    response::{IntoResponse, Response, sse::{Event, Sse}},
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response, sse::{Event, KeepAlive, Sse}},
     Json,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use futures_util::stream::{self, Stream};
-use playground_core_types::{Shared, shared, CoreResult, CoreError};
-use crate::types::McpTool;
+use futures_util::stream::{self, Stream, StreamExt};
+use tokio::sync::broadcast;
+use playground_core_types::{Handle, handle, Shared, shared, CoreResult, CoreError};
+use playground_core_ecs::World;
+use crate::types::{McpTool, McpPrompt};
+use crate::mcp_resources::ResourceRegistry;
+use crate::mcp_prompts::PromptRegistry;
+use crate::auth::{AuthManager, AuthGrant, log_denied, unauthorized_response};
+
+/// How often subscribed resources are checked for changes
+const RESOURCE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Names the event stream a request belongs to. The stream's response
+/// carries it, and so does its `connection.ready` event.
+const SESSION_HEADER: &str = "mcp-session-id";
 
 /// MCP request structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +44,37 @@ pub struct McpResponse {
     pub error: Option<McpError>,
 }
 
+/// Token passed in the query string by clients that can't set headers (SSE)
+#[derive(Debug, Default, Deserialize)]
+pub struct AuthQuery {
+    pub token: Option<String>,
+}
+
+/// A server-initiated notification and who it is for
+#[derive(Debug, Clone)]
+struct Notification {
+    /// Session ids, None for every session
+    sessions: Option<Vec<String>>,
+    message: Value,
+}
+
+/// Closes a session's subscriptions when its event stream is dropped
+struct SessionGuard {
+    id: String,
+    sessions: Shared<HashSet<String>>,
+    resources: Handle<ResourceRegistry>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let (id, sessions, resources) = (self.id.clone(), self.sessions.clone(), self.resources.clone());
+        tokio::spawn(async move {
+            sessions.write().await.remove(&id);
+            resources.remove_session(&id).await;
+        });
+    }
+}
+
 /// MCP error structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpError {
@@ -40,40 +86,102 @@ pub struct McpError {
 pub struct McpServer {
     enabled: bool,
     tools: Shared<HashMap<String, McpTool>>,
+    resources: Handle<ResourceRegistry>,
+    prompts: Handle<PromptRegistry>,
+    /// Server-initiated notifications, fanned out to the SSE clients they
+    /// are for
+    notifications: broadcast::Sender<Notification>,
+    /// Ids of the open event streams
+    sessions: Shared<HashSet<String>>,
+    /// Every endpoint requires a token; tools, resources and prompts also
+    /// check its scopes
+    auth: Handle<AuthManager>,
 }
 
 impl McpServer {
-    pub async fn new(enabled: bool) -> CoreResult<Self> {
+    pub async fn new(enabled: bool, auth: Handle<AuthManager>) -> CoreResult<Self> {
+        let (notifications, _) = broadcast::channel(256);
+
         Ok(Self {
             enabled,
             tools: shared(HashMap::new()),
+            resources: handle(ResourceRegistry::new()),
+            prompts: handle(PromptRegistry::new()),
+            notifications,
+            sessions: shared(HashSet::new()),
+            auth,
         })
     }
     
     async fn handle_sse_request(
         State(server): State<Arc<McpServer>>,
-    ) -> Sse<impl Stream<Item = Result<Event, std::convert::Infallible>>> {
+        headers: HeaderMap,
+        Query(query): Query<AuthQuery>,
+    ) -> Response {
+        if let Err(e) = server.auth.authenticate_request(&headers, query.token.as_deref(), "mcp sse").await {
+            return unauthorized_response(&e);
+        }
+        
+        // Subscriptions made with this id last as long as the stream
+        let session = uuid::Uuid::new_v4().simple().to_string();
+        server.sessions.write().await.insert(session.clone());
+        let guard = SessionGuard {
+            id: session.clone(),
+            sessions: server.sessions.clone(),
+            resources: server.resources.clone(),
+        };
+        
         // Send initial connection event
         let initial = Event::default()
             .event("message")
             .data(json!({
                 "jsonrpc": "2.0",
                 "method": "connection.ready",
-                "params": {}
+                "params": { "sessionId": session }
             }).to_string());
         
-        // Create stream with initial event
-        let stream = stream::once(async move {
-            Ok(initial)
+        // Follow the initial event with every notification for this session
+        // broadcast after connecting
+        let receiver = server.notifications.subscribe();
+        let notifications = stream::unfold((receiver, guard), |(mut receiver, guard)| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) => {
+                        if notification.sessions.as_ref().is_some_and(|sessions| !sessions.contains(&guard.id)) {
+                            continue;
+                        }
+                        let event = Event::default()
+                            .event("message")
+                            .data(notification.message.to_string());
+                        return Some((Ok::<_, std::convert::Infallible>(event), (receiver, guard)));
+                    }
+                    // A slow client missed some notifications, keep streaming the newest ones
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
         });
         
-        Sse::new(stream)
+        let stream = stream::once(async move {
+            Ok::<_, std::convert::Infallible>(initial)
+        }).chain(notifications);
+        
+        let mut response = Sse::new(stream).keep_alive(KeepAlive::default()).into_response();
+        if let Ok(value) = HeaderValue::from_str(&session) {
+            response.headers_mut().insert(SESSION_HEADER, value);
+        }
+        response
     }
     
     async fn handle_list_tools(
         State(server): State<Arc<McpServer>>,
+        headers: HeaderMap,
     ) -> Response {
-        let tools = server.list_tools().await;
+        let grant = match server.auth.authenticate_request(&headers, None, "mcp tools/list").await {
+            Ok(grant) => grant,
+            Err(e) => return unauthorized_response(&e),
+        };
+        let tools = server.list_tools_for(&grant).await;
         
         let tools_json: Vec<Value> = tools.into_iter().map(|tool| {
             json!({
@@ -90,9 +198,14 @@ impl McpServer {
     
     async fn handle_call_tool(
         State(server): State<Arc<McpServer>>,
+        headers: HeaderMap,
         Json(request): Json<McpRequest>,
     ) -> Response {
-        match server.handle_request(request).await {
+        let grant = match server.auth.authenticate_request(&headers, None, "mcp tools/call").await {
+            Ok(grant) => grant,
+            Err(e) => return unauthorized_response(&e),
+        };
+        match server.handle_request(request, &grant, session_id(&headers)).await {
             Ok(response) => Json(response).into_response(),
             Err(e) => {
                 let error_response = McpResponse {
@@ -108,6 +221,43 @@ impl McpServer {
             }
         }
     }
+    
+    async fn handle_rpc(
+        State(server): State<Arc<McpServer>>,
+        headers: HeaderMap,
+        Json(request): Json<McpRequest>,
+    ) -> Response {
+        let grant = match server.auth.authenticate_request(&headers, None, "mcp rpc").await {
+            Ok(grant) => grant,
+            Err(e) => return unauthorized_response(&e),
+        };
+        let id = request.id.clone();
+        match server.handle_request(request, &grant, session_id(&headers)).await {
+            Ok(response) => Json(response).into_response(),
+            Err(e) => {
+                let error_response = McpResponse {
+                    id,
+                    result: None,
+                    error: Some(McpError {
+                        code: error_code(&e),
+                        message: e.to_string(),
+                        data: None,
+                    }),
+                };
+                Json(error_response).into_response()
+            }
+        }
+    }
+}
+
+/// JSON-RPC error code for a core error
+fn error_code(error: &CoreError) -> i32 {
+    match error {
+        CoreError::InvalidInput(_) => -32602,
+        CoreError::NotFound(_) => -32002,
+        CoreError::PermissionDenied(_) => -32003,
+        _ => -32603,
+    }
 }
 
 impl McpServer {
@@ -136,7 +286,9 @@ impl McpServer {
         Ok(())
     }
     
-    pub async fn handle_request(&self, request: McpRequest) -> CoreResult<McpResponse> {
+    /// Handle one request. `session` is the event stream the client named,
+    /// which resource subscriptions belong to.
+    pub async fn handle_request(&self, request: McpRequest, grant: &AuthGrant, session: Option<&str>) -> CoreResult<McpResponse> {
         if !self.enabled {
             return Err(CoreError::Generic("MCP server is disabled".to_string()));
         }
@@ -144,7 +296,7 @@ impl McpServer {
         // Parse the method to determine what to do
         match request.method.as_str() {
             "tools/list" => {
-                let tools = self.list_tools().await;
+                let tools = self.list_tools_for(grant).await;
                 let tools_json: Vec<Value> = tools.into_iter().map(|tool| {
                     json!({
                         "name": tool.name,
@@ -165,6 +317,11 @@ impl McpServer {
                 let tool_name = params["name"].as_str().ok_or_else(|| CoreError::InvalidInput("Missing tool name".to_string()))?;
                 let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
                 
+                if !grant.allows_tool(tool_name) {
+                    log_denied(format!("MCP client ({}) denied call to tool '{}'", grant.source, tool_name)).await;
+                    return Err(CoreError::PermissionDenied(format!("No access to tool '{}'", tool_name)));
+                }
+                
                 // Get the tool
                 let tools = self.tools.read().await;
                 let tool = tools.get(tool_name)
@@ -183,6 +340,132 @@ impl McpServer {
                     error: None,
                 })
             }
+            "initialize" => {
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {
+                            "tools": {},
+                            "resources": { "subscribe": true, "listChanged": true },
+                            "prompts": { "listChanged": true },
+                        },
+                        "serverInfo": {
+                            "name": "android-playground",
+                            "version": env!("CARGO_PKG_VERSION"),
+                        },
+                    })),
+                    error: None,
+                })
+            }
+            "resources/list" => {
+                let resources = self.resources.list().await.into_iter()
+                    .filter(|resource| grant.allows_resource(&resource.uri));
+                let resources_json: Vec<Value> = resources.map(|resource| {
+                    json!({
+                        "uri": resource.uri,
+                        "name": resource.name,
+                        "description": resource.description,
+                        "mimeType": resource.mime_type,
+                    })
+                }).collect();
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({ "resources": resources_json })),
+                    error: None,
+                })
+            }
+            "resources/read" => {
+                let uri = resource_uri(&request.params)?;
+                check_resource_access(grant, &uri).await?;
+                let contents = self.resources.read(&uri).await?;
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "contents": [{
+                            "uri": contents.uri,
+                            "mimeType": contents.mime_type,
+                            "text": contents.text,
+                        }]
+                    })),
+                    error: None,
+                })
+            }
+            "resources/subscribe" => {
+                let uri = resource_uri(&request.params)?;
+                check_resource_access(grant, &uri).await?;
+                let session = self.open_session(session).await?;
+                self.resources.subscribe(session, &uri).await?;
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            }
+            "resources/unsubscribe" => {
+                let uri = resource_uri(&request.params)?;
+                check_resource_access(grant, &uri).await?;
+                let session = self.open_session(session).await?;
+                self.resources.unsubscribe(session, &uri).await?;
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({})),
+                    error: None,
+                })
+            }
+            "prompts/list" => {
+                let prompts = self.prompts.list().await.into_iter()
+                    .filter(|prompt| grant.allows_prompt(&prompt.name));
+                let prompts_json: Vec<Value> = prompts.map(|prompt| {
+                    let arguments: Vec<Value> = prompt.arguments.iter().map(|argument| {
+                        json!({
+                            "name": argument.name,
+                            "description": argument.description,
+                            "required": argument.required,
+                        })
+                    }).collect();
+                    
+                    json!({
+                        "name": prompt.name,
+                        "description": prompt.description,
+                        "arguments": arguments,
+                    })
+                }).collect();
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({ "prompts": prompts_json })),
+                    error: None,
+                })
+            }
+            "prompts/get" => {
+                let params = request.params.ok_or_else(|| CoreError::InvalidInput("Missing params".to_string()))?;
+                let name = params["name"].as_str().ok_or_else(|| CoreError::InvalidInput("Missing prompt name".to_string()))?;
+                let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+                
+                if !grant.allows_prompt(name) {
+                    log_denied(format!("MCP client ({}) denied prompt '{}'", grant.source, name)).await;
+                    return Err(CoreError::PermissionDenied(format!("No access to prompt '{}'", name)));
+                }
+                
+                let (description, text) = self.prompts.render(name, &arguments).await?;
+                
+                Ok(McpResponse {
+                    id: request.id,
+                    result: Some(json!({
+                        "description": description,
+                        "messages": [{
+                            "role": "user",
+                            "content": { "type": "text", "text": text }
+                        }]
+                    })),
+                    error: None,
+                })
+            }
             _ => {
                 Err(CoreError::InvalidInput(format!("Unknown method: {}", request.method)))
             }
@@ -194,13 +477,122 @@ impl McpServer {
             .route("/", get(Self::handle_sse_request))
             .route("/tools/list", post(Self::handle_list_tools))
             .route("/tools/call", post(Self::handle_call_tool))
+            .route("/rpc", post(Self::handle_rpc))
+            .route("/resources/list", post(Self::handle_rpc))
+            .route("/resources/read", post(Self::handle_rpc))
+            .route("/resources/subscribe", post(Self::handle_rpc))
+            .route("/resources/unsubscribe", post(Self::handle_rpc))
+            .route("/prompts/list", post(Self::handle_rpc))
+            .route("/prompts/get", post(Self::handle_rpc))
             .with_state(Arc::new(self.clone()))
     }
     
+    /// Start polling subscribed resources, pushing `notifications/resources/updated`
+    /// for every change. The task ends once the server has been dropped.
+    pub fn start_resource_watcher(&self) {
+        let resources = Arc::downgrade(&self.resources);
+        let notifications = self.notifications.clone();
+        
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RESOURCE_POLL_INTERVAL);
+            
+            loop {
+                interval.tick().await;
+                
+                let Some(resources) = resources.upgrade() else {
+                    break;
+                };
+                
+                for (uri, sessions) in resources.poll_changes().await {
+                    let _ = notifications.send(Notification {
+                        sessions: Some(sessions),
+                        message: resource_updated_notification(&uri),
+                    });
+                }
+            }
+        });
+    }
+    
+    /// Push an update notification for a resource changed outside of polling
+    /// (e.g. a file-browser watcher event) to the sessions subscribed to it
+    pub async fn notify_resource_updated(&self, uri: &str) {
+        let sessions = self.resources.subscribers(uri).await;
+        if !sessions.is_empty() {
+            let _ = self.notifications.send(Notification {
+                sessions: Some(sessions),
+                message: resource_updated_notification(uri),
+            });
+        }
+    }
+    
+    /// The session a subscription request names, if its stream is open
+    async fn open_session<'a>(&self, session: Option<&'a str>) -> CoreResult<&'a str> {
+        let session = session.ok_or_else(|| CoreError::InvalidInput(
+            "Subscriptions need the Mcp-Session-Id of an open event stream".to_string()
+        ))?;
+        if !self.sessions.read().await.contains(session) {
+            return Err(CoreError::NotFound(format!("No open MCP session '{}'", session)));
+        }
+        Ok(session)
+    }
+    
+    pub async fn add_workspace_root(&self, name: String, root: PathBuf) -> CoreResult<()> {
+        self.resources.add_workspace_root(name, root).await?;
+        self.notify_list_changed("notifications/resources/list_changed");
+        Ok(())
+    }
+    
+    pub async fn remove_workspace_root(&self, name: &str) -> CoreResult<()> {
+        self.resources.remove_workspace_root(name).await?;
+        self.notify_list_changed("notifications/resources/list_changed");
+        Ok(())
+    }
+    
+    pub async fn attach_world(&self, world: Handle<World>) {
+        self.resources.attach_world(world).await;
+        self.notify_list_changed("notifications/resources/list_changed");
+    }
+    
+    pub async fn register_prompt(&self, prompt: McpPrompt) -> CoreResult<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        
+        self.prompts.register(prompt).await?;
+        self.notify_list_changed("notifications/prompts/list_changed");
+        Ok(())
+    }
+    
+    pub async fn unregister_prompt(&self, name: &str) -> CoreResult<()> {
+        self.prompts.unregister(name).await?;
+        self.notify_list_changed("notifications/prompts/list_changed");
+        Ok(())
+    }
+    
+    fn notify_list_changed(&self, method: &str) {
+        let _ = self.notifications.send(Notification {
+            sessions: None,
+            message: json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": {}
+            }),
+        });
+    }
+    
     pub async fn list_tools(&self) -> Vec<McpTool> {
         let tools = self.tools.read().await;
         tools.values().cloned().collect()
     }
+    
+    /// Tools the grant's scopes allow calling
+    pub async fn list_tools_for(&self, grant: &AuthGrant) -> Vec<McpTool> {
+        let tools = self.tools.read().await;
+        tools.values()
+            .filter(|tool| grant.allows_tool(&tool.name))
+            .cloned()
+            .collect()
+    }
 }
 
 impl Clone for McpServer {
@@ -208,6 +600,38 @@ impl Clone for McpServer {
         Self {
             enabled: self.enabled,
             tools: self.tools.clone(),
+            resources: self.resources.clone(),
+            prompts: self.prompts.clone(),
+            notifications: self.notifications.clone(),
+            sessions: self.sessions.clone(),
+            auth: self.auth.clone(),
         }
     }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER).and_then(|value| value.to_str().ok())
+}
+
+fn resource_uri(params: &Option<Value>) -> CoreResult<String> {
+    params.as_ref()
+        .and_then(|p| p["uri"].as_str())
+        .map(|uri| uri.to_string())
+        .ok_or_else(|| CoreError::InvalidInput("Missing resource uri".to_string()))
+}
+
+async fn check_resource_access(grant: &AuthGrant, uri: &str) -> CoreResult<()> {
+    if !grant.allows_resource(uri) {
+        log_denied(format!("MCP client ({}) denied resource '{}'", grant.source, uri)).await;
+        return Err(CoreError::PermissionDenied(format!("No access to resource '{}'", uri)));
+    }
+    Ok(())
+}
+
+fn resource_updated_notification(uri: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/resources/updated",
+        "params": { "uri": uri }
+    })
 }
\ No newline at end of file
@@ -0,0 +1,75 @@
+//! MCP prompts - reusable prompt templates published by plugins
+
+use std::collections::HashMap;
+use serde_json::Value;
+use playground_core_types::{Shared, shared, CoreResult, CoreError};
+use crate::types::McpPrompt;
+
+pub struct PromptRegistry {
+    prompts: Shared<HashMap<String, McpPrompt>>,
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        Self {
+            prompts: shared(HashMap::new()),
+        }
+    }
+
+    pub async fn register(&self, prompt: McpPrompt) -> CoreResult<()> {
+        let mut prompts = self.prompts.write().await;
+
+        if prompts.contains_key(&prompt.name) {
+            return Err(CoreError::InvalidInput(format!("Prompt '{}' already registered", prompt.name)));
+        }
+
+        prompts.insert(prompt.name.clone(), prompt);
+        Ok(())
+    }
+
+    pub async fn unregister(&self, name: &str) -> CoreResult<()> {
+        let mut prompts = self.prompts.write().await;
+
+        if prompts.remove(name).is_none() {
+            return Err(CoreError::NotFound(format!("Prompt '{}' not found", name)));
+        }
+
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Vec<McpPrompt> {
+        let prompts = self.prompts.read().await;
+        let mut list: Vec<McpPrompt> = prompts.values().cloned().collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// Render a prompt, returning its description and the filled-in template.
+    /// Every `{{name}}` placeholder is replaced by the matching argument;
+    /// optional arguments that were not supplied render as empty strings.
+    pub async fn render(&self, name: &str, arguments: &Value) -> CoreResult<(String, String)> {
+        let prompts = self.prompts.read().await;
+        let prompt = prompts.get(name)
+            .ok_or_else(|| CoreError::NotFound(format!("Prompt '{}' not found", name)))?;
+
+        let mut text = prompt.template.clone();
+        for argument in &prompt.arguments {
+            let value = match arguments.get(&argument.name) {
+                Some(Value::String(s)) => s.clone(),
+                Some(Value::Null) | None => {
+                    if argument.required {
+                        return Err(CoreError::InvalidInput(format!(
+                            "Prompt '{}' requires argument '{}'", name, argument.name
+                        )));
+                    }
+                    String::new()
+                }
+                Some(other) => other.to_string(),
+            };
+
+            text = text.replace(&format!("{{{{{}}}}}", argument.name), &value);
+        }
+
+        Ok((prompt.description.clone(), text))
+    }
+}
@@ -0,0 +1,419 @@
+//! MCP resources - read-only views of engine state for AI/LLM clients
+//!
+//! Three kinds of resources are exposed:
+//! - `file://` URIs for files below a workspace root registered by the file-browser plugin
+//! - `console://logs/recent` for the most recent core/console log entries
+//! - `ecs://world/stats` for statistics about the ECS world
+//!
+//! Subscriptions belong to an MCP session, the event stream a client opened.
+//! Subscribed URIs are fingerprinted and polled by the MCP server, which pushes
+//! `notifications/resources/updated` to the sessions subscribed to a URI
+//! whenever its fingerprint changes.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use playground_core_types::{Handle, Shared, shared, CoreResult, CoreError};
+use playground_core_ecs::{World, WorldStats};
+use crate::types::{McpResource, McpResourceContents};
+
+/// URI of the recent console log resource
+pub const CONSOLE_LOGS_URI: &str = "console://logs/recent";
+/// URI of the ECS world statistics resource
+pub const WORLD_STATS_URI: &str = "ecs://world/stats";
+
+const FILE_SCHEME: &str = "file://";
+const RECENT_LOG_COUNT: usize = 100;
+const MAX_LISTED_FILES: usize = 500;
+const MAX_FILE_SIZE: u64 = 1024 * 1024; // 1MB
+
+/// Directories never listed as workspace resources
+const SKIPPED_DIRECTORIES: &[&str] = &["target", "node_modules"];
+
+pub struct ResourceRegistry {
+    /// Workspace roots by name, stored canonicalized
+    workspace_roots: Shared<HashMap<String, PathBuf>>,
+    /// World used for the stats resource (attached when the server starts)
+    world: Shared<Option<Handle<World>>>,
+    /// Subscribed URIs, by URI
+    subscriptions: Shared<HashMap<String, Subscription>>,
+}
+
+/// A URI at least one session is subscribed to
+struct Subscription {
+    /// What the resource looked like when last checked
+    fingerprint: String,
+    /// Session ids
+    subscribers: HashSet<String>,
+}
+
+impl ResourceRegistry {
+    pub fn new() -> Self {
+        Self {
+            workspace_roots: shared(HashMap::new()),
+            world: shared(None),
+            subscriptions: shared(HashMap::new()),
+        }
+    }
+
+    pub async fn add_workspace_root(&self, name: String, root: PathBuf) -> CoreResult<()> {
+        let root = tokio::fs::canonicalize(&root).await
+            .map_err(|e| CoreError::InvalidInput(format!("Invalid workspace root {:?}: {}", root, e)))?;
+
+        let mut roots = self.workspace_roots.write().await;
+        if roots.contains_key(&name) {
+            return Err(CoreError::InvalidInput(format!("Workspace '{}' already registered", name)));
+        }
+
+        roots.insert(name, root);
+        Ok(())
+    }
+
+    pub async fn remove_workspace_root(&self, name: &str) -> CoreResult<()> {
+        let mut roots = self.workspace_roots.write().await;
+
+        if roots.remove(name).is_none() {
+            return Err(CoreError::NotFound(format!("Workspace '{}' not found", name)));
+        }
+
+        Ok(())
+    }
+
+    pub async fn attach_world(&self, world: Handle<World>) {
+        *self.world.write().await = Some(world);
+    }
+
+    /// List every resource currently available
+    pub async fn list(&self) -> Vec<McpResource> {
+        let mut resources = vec![
+            McpResource {
+                uri: CONSOLE_LOGS_URI.to_string(),
+                name: "Recent logs".to_string(),
+                description: Some(format!("The last {} console log entries", RECENT_LOG_COUNT)),
+                mime_type: "application/json".to_string(),
+            },
+        ];
+
+        if self.world.read().await.is_some() {
+            resources.push(McpResource {
+                uri: WORLD_STATS_URI.to_string(),
+                name: "World statistics".to_string(),
+                description: Some("Entity, component, system and query counts of the ECS world".to_string()),
+                mime_type: "application/json".to_string(),
+            });
+        }
+
+        let roots: Vec<(String, PathBuf)> = self.workspace_roots.read().await
+            .iter()
+            .map(|(name, root)| (name.clone(), root.clone()))
+            .collect();
+
+        for (workspace, root) in roots {
+            for path in list_workspace_files(&root, MAX_LISTED_FILES).await {
+                let relative = path.strip_prefix(&root).unwrap_or(&path);
+                resources.push(McpResource {
+                    uri: format!("{}{}", FILE_SCHEME, path.display()),
+                    name: relative.display().to_string(),
+                    description: Some(format!("File in workspace '{}'", workspace)),
+                    mime_type: mime_type_for(&path).to_string(),
+                });
+            }
+        }
+
+        resources
+    }
+
+    /// Read the current contents of a resource
+    pub async fn read(&self, uri: &str) -> CoreResult<McpResourceContents> {
+        let (mime_type, text) = match uri {
+            CONSOLE_LOGS_URI => {
+                let logs = playground_core_console::get_recent_logs(RECENT_LOG_COUNT).await?;
+                ("application/json", serde_json::to_string_pretty(&logs)?)
+            }
+            WORLD_STATS_URI => {
+                let stats = self.world_stats().await?;
+                ("application/json", serde_json::to_string_pretty(&stats)?)
+            }
+            _ => {
+                let path = self.resolve_file(uri).await?;
+                let metadata = tokio::fs::metadata(&path).await?;
+                if metadata.len() > MAX_FILE_SIZE {
+                    return Err(CoreError::InvalidInput(format!(
+                        "Resource '{}' is larger than {} bytes", uri, MAX_FILE_SIZE
+                    )));
+                }
+
+                let bytes = tokio::fs::read(&path).await?;
+                let text = String::from_utf8(bytes)
+                    .map_err(|_| CoreError::InvalidInput(format!("Resource '{}' is not a text file", uri)))?;
+                (mime_type_for(&path), text)
+            }
+        };
+
+        Ok(McpResourceContents {
+            uri: uri.to_string(),
+            mime_type: mime_type.to_string(),
+            text,
+        })
+    }
+
+    /// Subscribe a session to a resource
+    pub async fn subscribe(&self, session: &str, uri: &str) -> CoreResult<()> {
+        let fingerprint = self.fingerprint(uri).await?;
+        self.subscriptions.write().await
+            .entry(uri.to_string())
+            .or_insert_with(|| Subscription { fingerprint, subscribers: HashSet::new() })
+            .subscribers
+            .insert(session.to_string());
+        Ok(())
+    }
+
+    /// Unsubscribe a session; other sessions subscribed to the URI keep
+    /// their subscriptions
+    pub async fn unsubscribe(&self, session: &str, uri: &str) -> CoreResult<()> {
+        let mut subscriptions = self.subscriptions.write().await;
+        let removed = subscriptions.get_mut(uri)
+            .is_some_and(|subscription| subscription.subscribers.remove(session));
+        if !removed {
+            return Err(CoreError::NotFound(format!("No subscription for '{}'", uri)));
+        }
+
+        if subscriptions.get(uri).is_some_and(|subscription| subscription.subscribers.is_empty()) {
+            subscriptions.remove(uri);
+        }
+        Ok(())
+    }
+
+    /// Drop every subscription of a session, e.g. when its stream closes
+    pub async fn remove_session(&self, session: &str) {
+        self.subscriptions.write().await.retain(|_, subscription| {
+            subscription.subscribers.remove(session);
+            !subscription.subscribers.is_empty()
+        });
+    }
+
+    /// Sessions subscribed to a URI
+    pub async fn subscribers(&self, uri: &str) -> Vec<String> {
+        self.subscriptions.read().await
+            .get(uri)
+            .map(|subscription| subscription.subscribers.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Re-fingerprint all subscribed resources and return the URIs that
+    /// changed, with the sessions subscribed to them. Resources that
+    /// disappeared are reported once with an empty fingerprint.
+    pub async fn poll_changes(&self) -> Vec<(String, Vec<String>)> {
+        let uris: Vec<String> = self.subscriptions.read().await.keys().cloned().collect();
+        let mut changed = Vec::new();
+
+        for uri in uris {
+            let fingerprint = self.fingerprint(&uri).await.unwrap_or_default();
+
+            let mut subscriptions = self.subscriptions.write().await;
+            if let Some(subscription) = subscriptions.get_mut(&uri) && subscription.fingerprint != fingerprint {
+                subscription.fingerprint = fingerprint;
+                changed.push((uri, subscription.subscribers.iter().cloned().collect()));
+            }
+        }
+
+        changed
+    }
+
+    /// Cheap change detector for a resource, compared between polls
+    async fn fingerprint(&self, uri: &str) -> CoreResult<String> {
+        match uri {
+            CONSOLE_LOGS_URI => {
+                let logs = playground_core_console::get_recent_logs(RECENT_LOG_COUNT).await?;
+                let last = logs.last()
+                    .map(|entry| format!("{}:{}", entry.timestamp, entry.message.len()))
+                    .unwrap_or_default();
+                Ok(format!("{}:{}", logs.len(), last))
+            }
+            WORLD_STATS_URI => {
+                let stats = self.world_stats().await?;
+                Ok(serde_json::to_string(&stats)?)
+            }
+            _ => {
+                let path = self.resolve_file(uri).await?;
+                let metadata = tokio::fs::metadata(&path).await?;
+                let modified = metadata.modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|duration| duration.as_nanos())
+                    .unwrap_or(0);
+                Ok(format!("{}:{}", metadata.len(), modified))
+            }
+        }
+    }
+
+    /// Map a `file://` URI to a path, rejecting anything outside the workspace roots
+    async fn resolve_file(&self, uri: &str) -> CoreResult<PathBuf> {
+        let raw = uri.strip_prefix(FILE_SCHEME)
+            .ok_or_else(|| CoreError::NotFound(format!("Unknown resource '{}'", uri)))?;
+
+        let path = tokio::fs::canonicalize(raw).await
+            .map_err(|_| CoreError::NotFound(format!("Resource '{}' not found", uri)))?;
+
+        let roots = self.workspace_roots.read().await;
+        if !roots.values().any(|root| path.starts_with(root)) {
+            return Err(CoreError::PermissionDenied(format!("'{}' is outside the workspace", uri)));
+        }
+
+        if !path.is_file() {
+            return Err(CoreError::InvalidInput(format!("Resource '{}' is not a file", uri)));
+        }
+
+        Ok(path)
+    }
+
+    async fn world_stats(&self) -> CoreResult<WorldStats> {
+        let world = self.world.read().await.clone()
+            .ok_or(CoreError::NotInitialized)?;
+
+        let entity_count = world.entities.read().await.len();
+        let component_count = world.component_registry.read().await.len();
+        let system_count = world.systems.read().await.len();
+        let event_count = world.event_queue.read().await.len();
+        let storage_count = world.storages.read().await.len();
+        let query_count = world.queries.read().await.len();
+
+        Ok(WorldStats {
+            entity_count,
+            component_count,
+            system_count,
+            event_count,
+            storage_count,
+            query_count,
+            total_memory_bytes: 0,
+        })
+    }
+}
+
+impl Default for ResourceRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Breadth-first listing of the files below `root`, skipping hidden and build directories
+async fn list_workspace_files(root: &Path, limit: usize) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = std::collections::VecDeque::from([root.to_path_buf()]);
+
+    while let Some(dir) = pending.pop_front() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut children = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            children.push(entry);
+        }
+        children.sort_by_key(|entry| entry.file_name());
+
+        for entry in children {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with('.') || SKIPPED_DIRECTORIES.contains(&name.as_ref()) {
+                continue;
+            }
+
+            match entry.file_type().await {
+                Ok(file_type) if file_type.is_dir() => pending.push_back(entry.path()),
+                Ok(file_type) if file_type.is_file() => {
+                    files.push(entry.path());
+                    if files.len() >= limit {
+                        return files;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    files
+}
+
+fn mime_type_for(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "rs" => "text/x-rust",
+        "toml" => "application/toml",
+        "json" => "application/json",
+        "md" => "text/markdown",
+        "js" => "text/javascript",
+        "html" => "text/html",
+        "css" => "text/css",
+        _ => "text/plain",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A registry with one workspace holding `main.rs`, and the file's URI
+    async fn workspace() -> (ResourceRegistry, PathBuf, String) {
+        let root = std::env::temp_dir().join(format!("mcp-resources-test-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&root).await.unwrap();
+        tokio::fs::write(root.join("main.rs"), "fn main() {}").await.unwrap();
+
+        let registry = ResourceRegistry::new();
+        registry.add_workspace_root("test".to_string(), root.clone()).await.unwrap();
+        let file = tokio::fs::canonicalize(root.join("main.rs")).await.unwrap();
+        (registry, root, format!("{}{}", FILE_SCHEME, file.display()))
+    }
+
+    async fn subscribers(registry: &ResourceRegistry, uri: &str) -> Vec<String> {
+        let mut sessions = registry.subscribers(uri).await;
+        sessions.sort();
+        sessions
+    }
+
+    #[tokio::test]
+    async fn unsubscribing_leaves_other_sessions_subscribed() {
+        let (registry, root, uri) = workspace().await;
+        registry.subscribe("a", &uri).await.unwrap();
+        registry.subscribe("b", &uri).await.unwrap();
+        assert_eq!(subscribers(&registry, &uri).await, vec!["a", "b"]);
+
+        registry.unsubscribe("a", &uri).await.unwrap();
+        assert_eq!(subscribers(&registry, &uri).await, vec!["b"]);
+        // Only a session's own subscriptions can be removed
+        assert!(registry.unsubscribe("a", &uri).await.is_err());
+
+        registry.unsubscribe("b", &uri).await.unwrap();
+        assert!(registry.subscribers(&uri).await.is_empty());
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changes_are_reported_to_subscribed_sessions() {
+        let (registry, root, uri) = workspace().await;
+        registry.subscribe("a", &uri).await.unwrap();
+        assert!(registry.poll_changes().await.is_empty());
+
+        tokio::fs::write(root.join("main.rs"), "fn main() { println!(); }").await.unwrap();
+        let changed = registry.poll_changes().await;
+        assert_eq!(changed, vec![(uri.clone(), vec!["a".to_string()])]);
+        // Reported once per change
+        assert!(registry.poll_changes().await.is_empty());
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn closing_a_session_drops_its_subscriptions() {
+        let (registry, root, uri) = workspace().await;
+        registry.subscribe("a", &uri).await.unwrap();
+        registry.subscribe("b", &uri).await.unwrap();
+
+        registry.remove_session("a").await;
+        assert_eq!(subscribers(&registry, &uri).await, vec!["b"]);
+        registry.remove_session("b").await;
+
+        tokio::fs::write(root.join("main.rs"), "changed").await.unwrap();
+        assert!(registry.poll_changes().await.is_empty());
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
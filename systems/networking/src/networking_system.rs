@@ -3,8 +3,10 @@
 //! This system manages networking initialization and provides helper functions
 //! for other systems. The actual server/client logic is in vtable_handlers.rs
 
-use playground_core_types::CoreResult;
-use crate::types::{WebSocketConfig, McpTool, Packet, LogLevel};
+use std::path::PathBuf;
+use playground_core_types::{CoreResult, CoreError};
+use crate::types::{WebSocketConfig, McpTool, McpPrompt, Packet, LogLevel, IncomingPacket, PacketSender};
+use crate::state::NETWORK_STATE;
 
 /// High-level networking system
 pub struct NetworkingSystem {
@@ -32,6 +34,12 @@ impl NetworkingSystem {
         Ok(())
     }
     
+    /// Set a server option (`auth_enabled`, `auth_token_file`, ...) before
+    /// the server starts (helper for applications)
+    pub async fn set_server_option(&self, key: &str, value: serde_json::Value) {
+        NETWORK_STATE.server_options.write().await.insert(key.to_string(), value);
+    }
+    
     /// Register an MCP tool (helper for plugins)
     pub async fn register_mcp_tool(&self, _tool: McpTool) -> CoreResult<()> {
         // This now goes through the VTable handlers
@@ -39,6 +47,79 @@ impl NetworkingSystem {
         Ok(())
     }
     
+    /// Expose a workspace directory as MCP `file://` resources (helper for plugins)
+    pub async fn register_mcp_workspace(&self, name: String, root: PathBuf) -> CoreResult<()> {
+        let server = NETWORK_STATE.server_impl.read().await.clone()
+            .ok_or_else(|| CoreError::InvalidState("Server not running".to_string()))?;
+        server.mcp.add_workspace_root(name, root).await
+    }
+    
+    /// Publish a reusable MCP prompt template (helper for plugins)
+    pub async fn register_mcp_prompt(&self, prompt: McpPrompt) -> CoreResult<()> {
+        let server = NETWORK_STATE.server_impl.read().await.clone()
+            .ok_or_else(|| CoreError::InvalidState("Server not running".to_string()))?;
+        server.mcp.register_prompt(prompt).await
+    }
+    
+    /// Tell subscribed MCP clients that a resource changed (helper for plugins)
+    pub async fn notify_mcp_resource_updated(&self, uri: &str) {
+        if let Some(server) = NETWORK_STATE.server_impl.read().await.clone() {
+            server.mcp.notify_resource_updated(uri).await;
+        }
+    }
+    
+    /// Print a fresh pairing code, unlocking pairing after repeated failures
+    pub async fn issue_pairing_code(&self) -> CoreResult<String> {
+        let server = NETWORK_STATE.server_impl.read().await.clone()
+            .ok_or_else(|| CoreError::InvalidState("Server not running".to_string()))?;
+        if !server.auth.is_enabled() {
+            return Err(CoreError::InvalidState("Authentication is disabled".to_string()));
+        }
+        Ok(server.auth.issue_pairing_code().await)
+    }
+    
+    /// Create an auth token for a client the calling plugin starts itself
+    /// (helper for plugins). Fails while authentication is disabled, since
+    /// the token would never be checked.
+    pub async fn issue_auth_token(&self, source: &str, scopes: Vec<String>) -> CoreResult<String> {
+        let server = NETWORK_STATE.server_impl.read().await.clone()
+            .ok_or_else(|| CoreError::InvalidState("Server not running".to_string()))?;
+        if !server.auth.is_enabled() {
+            return Err(CoreError::InvalidState("Authentication is disabled".to_string()));
+        }
+        Ok(server.auth.issue_token(source, scopes).await)
+    }
+    
+    /// Invalidate a token created with `issue_auth_token` (helper for plugins)
+    pub async fn revoke_auth_token(&self, token: &str) -> CoreResult<()> {
+        let server = NETWORK_STATE.server_impl.read().await.clone()
+            .ok_or_else(|| CoreError::InvalidState("Server not running".to_string()))?;
+        server.auth.revoke(token).await
+    }
+    
+    /// Take the packets queued for a channel: client packets whose sender's
+    /// grant allowed it, and packets from `send_to_plugin` (helper for plugins)
+    pub async fn receive_packets(&self, channel: u16) -> CoreResult<Vec<IncomingPacket>> {
+        Ok(NETWORK_STATE.inbox.drain(channel).await)
+    }
+    
+    /// Queue a packet for the plugin that owns `channel`, which takes it
+    /// from `receive_packets` like a client packet. Nothing is sent over
+    /// WebSocket. `from` is the sending plugin's channel name (helper for
+    /// plugins).
+    pub async fn send_to_plugin(&self, from: &str, channel: u16, packet_type: u16, data: Vec<u8>) -> CoreResult<()> {
+        let queued = NETWORK_STATE.inbox.push(IncomingPacket {
+            channel_id: channel,
+            packet_type,
+            data,
+            sender: PacketSender::Plugin(from.to_string()),
+        }).await;
+        if !queued {
+            return Err(CoreError::Network(format!("Inbox for channel {} is full", channel)));
+        }
+        Ok(())
+    }
+    
     /// Send a packet (helper for plugins)
     pub async fn send_packet(&self, _packet: Packet) -> CoreResult<()> {
         // This now goes through the VTable handlers
@@ -58,4 +139,29 @@ impl NetworkingSystem {
         
         println!("[{}] {}: {}", component, level_str, message);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn plugin_packets_loop_back_to_the_receiving_plugin() {
+        let networking = NetworkingSystem::new().await.unwrap();
+        // Channels no other test uses, as the inbox is process-wide
+        let (editor, lsp) = (64001, 64002);
+
+        networking.send_to_plugin("editor-core", lsp, 204, b"request".to_vec()).await.unwrap();
+        networking.send_to_plugin("lsp-client", editor, 300, b"diagnostics".to_vec()).await.unwrap();
+
+        let received = networking.receive_packets(lsp).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!((received[0].packet_type, received[0].data.as_slice()), (204, b"request".as_slice()));
+        assert_eq!(received[0].sender, PacketSender::Plugin("editor-core".to_string()));
+
+        let received = networking.receive_packets(editor).await.unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].sender, PacketSender::Plugin("lsp-client".to_string()));
+        assert!(networking.receive_packets(lsp).await.unwrap().is_empty());
+    }
+}
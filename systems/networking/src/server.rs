@@ -11,6 +11,7 @@ use crate::websocket::WebSocketHandler;
 use crate::channel_manager::ChannelManager;
 use crate::batcher::FrameBatcher;
 use crate::mcp::McpServer;
+use crate::auth::AuthManager;
 use crate::types::WebSocketConfig;
 
 /// WebSocket/HTTP server implementation
@@ -24,6 +25,8 @@ pub struct NetworkServer {
     pub batcher: Handle<FrameBatcher>,
     /// MCP server for AI/LLM integration
     pub mcp: Handle<McpServer>,
+    /// Token authentication shared by WebSocket and MCP endpoints
+    pub auth: Handle<AuthManager>,
     /// Server configuration
     pub config: Shared<ServerConfig>,
     /// WebSocket-specific configuration
@@ -40,12 +43,13 @@ pub struct NetworkServer {
 
 impl NetworkServer {
     pub async fn new(ws_config: WebSocketConfig) -> CoreResult<Handle<Self>> {
-        let websocket = handle(WebSocketHandler::new().await
-            .map_err(|e| CoreError::Generic(e.to_string()))?);
+        let auth = handle(AuthManager::new(false));
         let channel_manager = handle(ChannelManager::new().await
             .map_err(|e| CoreError::Generic(e.to_string()))?);
+        let websocket = handle(WebSocketHandler::new(ServerConfig::default(), channel_manager.clone(), auth.clone()).await
+            .map_err(|e| CoreError::Generic(e.to_string()))?);
         let batcher = handle(FrameBatcher::new(ws_config.frame_rate));
-        let mcp = handle(McpServer::new(ws_config.mcp_enabled).await
+        let mcp = handle(McpServer::new(ws_config.mcp_enabled, auth.clone()).await
             .map_err(|e| CoreError::Generic(e.to_string()))?);
         
         Ok(handle(Self {
@@ -53,6 +57,7 @@ impl NetworkServer {
             channel_manager,
             batcher,
             mcp,
+            auth,
             config: shared(ServerConfig::default()),
             ws_config,
             stats: shared(ServerStats::default()),
@@ -0,0 +1,220 @@
+//! Resumable WebSocket sessions
+//!
+//! Every connection is bound to a session identified by an opaque token. Data
+//! packets sent to a session are numbered (starting at 1) and kept in a bounded
+//! replay buffer until the client acknowledges them. When a mobile browser drops
+//! its socket, the session is detached rather than destroyed: packets keep
+//! accumulating in the buffer, and channel subscriptions stay registered under
+//! the old connection id. A client reconnecting to `/ws?session=<token>&ack=<seq>`
+//! is rebound to the session, gets its subscriptions back and receives every
+//! packet after `seq` before any new traffic.
+//!
+//! Session control packets travel on the control channel (0) and are never
+//! sequenced:
+//! - `SESSION_INFO` (server → client): bincode `SessionInfo`, always the first packet
+//! - `SESSION_ACK` (client → server): u64 little-endian, highest sequence received
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use playground_core_types::{Shared, shared};
+
+/// Control packet type carrying `SessionInfo` to the client
+pub const SESSION_INFO: u16 = 20;
+/// Control packet type carrying the client's last received sequence number
+pub const SESSION_ACK: u16 = 21;
+
+/// Maximum number of unacknowledged packets kept per session
+const REPLAY_BUFFER_PACKETS: usize = 1024;
+/// Maximum number of unacknowledged bytes kept per session
+const REPLAY_BUFFER_BYTES: usize = 4 * 1024 * 1024;
+/// How long a detached session can be resumed
+pub const SESSION_RESUME_WINDOW: Duration = Duration::from_secs(300);
+
+/// Sent to the client when a connection is bound to a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub token: String,
+    /// Whether subscriptions were restored from an earlier connection
+    pub resumed: bool,
+    /// Sequence number of the next data packet the client will receive
+    pub next_sequence: u64,
+}
+
+pub struct Session {
+    pub token: String,
+    /// Connection currently (or most recently) bound to the session
+    pub connection: usize,
+    /// Set while no socket is attached
+    pub detached_at: Option<Instant>,
+    replay: VecDeque<(u64, Bytes)>,
+    replay_bytes: usize,
+    next_sequence: u64,
+}
+
+impl Session {
+    fn new(token: String, connection: usize) -> Self {
+        Self {
+            token,
+            connection,
+            detached_at: None,
+            replay: VecDeque::new(),
+            replay_bytes: 0,
+            next_sequence: 1,
+        }
+    }
+
+    /// Number a data packet and keep it for replay, evicting the oldest
+    /// packets once the buffer is full
+    pub fn record(&mut self, data: Bytes) -> u64 {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.replay_bytes += data.len();
+        self.replay.push_back((sequence, data));
+
+        while self.replay.len() > REPLAY_BUFFER_PACKETS || self.replay_bytes > REPLAY_BUFFER_BYTES {
+            match self.replay.pop_front() {
+                Some((_, dropped)) => self.replay_bytes -= dropped.len(),
+                None => break,
+            }
+        }
+
+        sequence
+    }
+
+    /// Drop every buffered packet up to and including `sequence`
+    pub fn acknowledge(&mut self, sequence: u64) {
+        while let Some((front, _)) = self.replay.front() {
+            if *front > sequence {
+                break;
+            }
+            if let Some((_, dropped)) = self.replay.pop_front() {
+                self.replay_bytes -= dropped.len();
+            }
+        }
+    }
+
+    /// Packets the client has not seen yet, given the last sequence it acknowledged.
+    /// Returns `None` if some of them have already been evicted from the buffer.
+    pub fn replay_after(&self, last_acked: u64) -> Option<Vec<Bytes>> {
+        if last_acked >= self.next_sequence {
+            return None;
+        }
+
+        let oldest = self.replay.front()
+            .map(|(sequence, _)| *sequence)
+            .unwrap_or(self.next_sequence);
+        if oldest > last_acked + 1 {
+            return None;
+        }
+
+        Some(self.replay.iter()
+            .filter(|(sequence, _)| *sequence > last_acked)
+            .map(|(_, data)| data.clone())
+            .collect())
+    }
+
+    pub fn next_sequence(&self) -> u64 {
+        self.next_sequence
+    }
+}
+
+pub struct SessionManager {
+    sessions: Shared<HashMap<String, Shared<Session>>>,
+    /// Connection id to session token, including detached connections
+    connections: Shared<HashMap<usize, String>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: shared(HashMap::new()),
+            connections: shared(HashMap::new()),
+        }
+    }
+
+    /// Start a fresh session for a new connection
+    pub async fn create(&self, connection: usize) -> Shared<Session> {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let session = shared(Session::new(token.clone(), connection));
+
+        self.sessions.write().await.insert(token.clone(), session.clone());
+        self.connections.write().await.insert(connection, token);
+
+        session
+    }
+
+    pub async fn find(&self, token: &str) -> Option<Shared<Session>> {
+        self.sessions.read().await.get(token).cloned()
+    }
+
+    pub async fn session_for(&self, connection: usize) -> Option<Shared<Session>> {
+        let token = self.connections.read().await.get(&connection).cloned()?;
+        self.find(&token).await
+    }
+
+    /// Move a session to a new connection. The caller must hold the session
+    /// lock. Returns false if the session expired before the lock was taken,
+    /// in which case it is left unbound.
+    pub async fn rebind(&self, session: &mut Session, connection: usize) -> bool {
+        if !self.sessions.read().await.contains_key(&session.token) {
+            return false;
+        }
+
+        let mut connections = self.connections.write().await;
+        connections.remove(&session.connection);
+        connections.insert(connection, session.token.clone());
+
+        session.connection = connection;
+        session.detached_at = None;
+        true
+    }
+
+    /// Connection ids of every session, attached or detached
+    pub async fn bound_connections(&self) -> Vec<usize> {
+        self.connections.read().await.keys().copied().collect()
+    }
+
+    /// Mark the session of a closed connection as resumable
+    pub async fn detach(&self, connection: usize) {
+        if let Some(session) = self.session_for(connection).await {
+            let mut session = session.write().await;
+            if session.connection == connection {
+                session.detached_at = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Drop sessions that have been detached for longer than the resume window.
+    /// Returns the connection ids they were bound to.
+    pub async fn expire(&self) -> Vec<usize> {
+        // Session locks are taken before the map locks elsewhere, so never
+        // wait on a session while holding a map lock
+        let all: Vec<Shared<Session>> = self.sessions.read().await.values().cloned().collect();
+
+        let mut expired = Vec::new();
+        for session in all {
+            // Checked and removed under the session lock `rebind` is called
+            // with, so a client resuming at the same time either keeps the
+            // session or finds it gone, never half removed
+            let session = session.write().await;
+            let stale = session.detached_at
+                .is_some_and(|detached_at| detached_at.elapsed() >= SESSION_RESUME_WINDOW);
+            if !stale {
+                continue;
+            }
+
+            self.sessions.write().await.remove(&session.token);
+            self.connections.write().await.remove(&session.connection);
+            expired.push(session.connection);
+        }
+
+        expired
+    }
+
+    pub async fn session_count(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+}
@@ -10,6 +10,7 @@ use playground_core_server::ConnectionId;
 use once_cell::sync::Lazy;
 
 use crate::server::NetworkServer;
+use crate::inbox::PacketInbox;
 
 /// Global network state using Lazy initialization
 pub static NETWORK_STATE: Lazy<NetworkState> = Lazy::new(|| NetworkState {
@@ -18,6 +19,8 @@ pub static NETWORK_STATE: Lazy<NetworkState> = Lazy::new(|| NetworkState {
     client_entity: shared(None),
     connection_entities: shared(HashMap::new()),
     connection_senders: shared(HashMap::new()),
+    inbox: handle(PacketInbox::new()),
+    server_options: shared(HashMap::new()),
 });
 
 /// Network state that bridges ECS entities with network implementation
@@ -36,6 +39,13 @@ pub struct NetworkState {
 
     /// Map of connection IDs to their WebSocket senders (implementation detail)
     pub connection_senders: Shared<HashMap<ConnectionId, tokio::sync::mpsc::Sender<Vec<u8>>>>,
+
+    /// Inbound packets per channel, for the plugins that own them
+    pub inbox: Handle<PacketInbox>,
+
+    /// Server options set by the application before startup; they override
+    /// `ServerConfig::options` when the server starts
+    pub server_options: Shared<HashMap<String, serde_json::Value>>,
 }
 
 impl NetworkState {
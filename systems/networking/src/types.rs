@@ -18,6 +18,26 @@ pub struct Packet {
     pub payload: Vec<u8>,
 }
 
+/// A packet waiting for the plugin that owns its channel
+#[derive(Debug, Clone)]
+pub struct IncomingPacket {
+    pub channel_id: u16,
+    pub packet_type: u16,
+    pub data: Vec<u8>,
+    /// Who sent it, as established by the server rather than the payload
+    pub sender: PacketSender,
+}
+
+/// Origin of an `IncomingPacket`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketSender {
+    /// A WebSocket client; `token` is the auth token it connected with,
+    /// `None` while authentication is disabled
+    Client { connection: usize, token: Option<String> },
+    /// Another plugin in this process, by its registered channel name
+    Plugin(String),
+}
+
 /// WebSocket packet priority levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Priority {
@@ -82,6 +102,41 @@ pub struct McpTool {
     pub handler_channel: u16,
 }
 
+/// MCP resource descriptor advertised through `resources/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub mime_type: String,
+}
+
+/// Contents of a single MCP resource returned by `resources/read`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpResourceContents {
+    pub uri: String,
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// MCP prompt template published by a plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPrompt {
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<McpPromptArgument>,
+    /// Template text, `{{argument}}` placeholders are substituted on `prompts/get`
+    pub template: String,
+}
+
+/// Argument accepted by an MCP prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpPromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    pub required: bool,
+}
+
 /// Log level for console logging (temporary until we use command processor)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
@@ -24,10 +24,11 @@ use std::net::SocketAddr;
 
 use crate::server::NetworkServer;
 use crate::types::WebSocketConfig;
-use crate::websocket::WebSocketHandler;
+use crate::websocket::{WebSocketHandler, CLOSE_GOING_AWAY};
 use crate::channel_manager::ChannelManager;
 use crate::batcher::FrameBatcher;
 use crate::mcp::McpServer;
+use crate::auth::AuthManager;
 use crate::state::NETWORK_STATE;
 
 // Helper functions for VTableResponse
@@ -101,11 +102,14 @@ pub async fn handle_client_operations(operation: String, payload: Bytes) -> VTab
 // Server operation implementations
 
 async fn handle_server_start(payload: Bytes) -> VTableResponse {
-    let config: ServerConfig = match bincode::deserialize(&payload) {
+    let mut config: ServerConfig = match bincode::deserialize(&payload) {
         Ok(c) => c,
         Err(e) => return error_response(format!("Failed to deserialize config: {}", e)),
     };
 
+    // Options the application set through `NetworkingSystem::set_server_option`
+    config.options.extend(NETWORK_STATE.server_options.read().await.clone());
+
     // Create server entity with components
     let server_entity = match server_api::start_server(config.clone()).await {
         Ok(entity) => entity,
@@ -125,19 +129,32 @@ async fn handle_server_start(payload: Bytes) -> VTableResponse {
     };
 
     // Create actual network server components
-    let websocket = handle(match WebSocketHandler::new().await {
-        Ok(ws) => ws,
-        Err(e) => return error_response(format!("Failed to create WebSocket handler: {}", e)),
-    });
+    // Authentication is on unless an application explicitly turns it off
+    // with the `auth_enabled` option
+    let auth_enabled = config.options.get("auth_enabled")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true);
+    let auth = handle(AuthManager::new(auth_enabled));
+
+    if let Some(path) = config.options.get("auth_token_file").and_then(|value| value.as_str()) {
+        if let Err(e) = auth.load_token_file(std::path::Path::new(path)).await {
+            return error_response(format!("Failed to load token file {}: {}", path, e));
+        }
+    }
 
     let channel_manager = handle(match ChannelManager::new().await {
         Ok(cm) => cm,
         Err(e) => return error_response(format!("Failed to create channel manager: {}", e)),
     });
 
+    let websocket = handle(match WebSocketHandler::new(config.clone(), channel_manager.clone(), auth.clone()).await {
+        Ok(ws) => ws,
+        Err(e) => return error_response(format!("Failed to create WebSocket handler: {}", e)),
+    });
+
     let batcher = handle(FrameBatcher::new(ws_config.frame_rate));
 
-    let mcp = handle(match McpServer::new(ws_config.mcp_enabled).await {
+    let mcp = handle(match McpServer::new(ws_config.mcp_enabled, auth.clone()).await {
         Ok(m) => m,
         Err(e) => return error_response(format!("Failed to create MCP server: {}", e)),
     });
@@ -148,6 +165,7 @@ async fn handle_server_start(payload: Bytes) -> VTableResponse {
         channel_manager,
         batcher: batcher.clone(),
         mcp: mcp.clone(),
+        auth: auth.clone(),
         config: shared(config.clone()),
         ws_config: ws_config.clone(),
         stats: shared(ServerStats::default()),
@@ -165,6 +183,9 @@ async fn handle_server_start(payload: Bytes) -> VTableResponse {
         Err(e) => return error_response(format!("Failed to get world: {}", e)),
     };
 
+    // Expose world statistics as an MCP resource
+    mcp.attach_world(world.clone()).await;
+
     // Get and modify the component through World
     if let Ok(mut state_comp) = server_entity.get_component::<ServerState>().await {
         let mut modified = state_comp;
@@ -173,24 +194,19 @@ async fn handle_server_start(payload: Bytes) -> VTableResponse {
         let _ = server_entity.add_component(modified).await;
     }
 
-    // Start batch processing if enabled
-    #[cfg(feature = "batching")]
-    if config.enable_batching {
-        let batcher_clone = batcher.clone();
-        tokio::spawn(async move {
-            batcher_clone.start_batch_loop().await;
-        });
-    }
-
     // Create Axum router
     let ws_routes = Router::new()
         .route("/ws", get(crate::websocket::websocket_handler))
         .with_state(websocket);
 
+    // Push change notifications for subscribed MCP resources
+    mcp.start_resource_watcher();
+
     let app = Router::new()
         .route("/", get(|| async { "Playground Server" }))
         .merge(ws_routes)
-        .nest("/mcp", mcp.router());
+        .nest("/mcp", mcp.router())
+        .nest("/auth", auth.router());
 
     // Create shutdown channel
     let (tx, rx) = tokio::sync::oneshot::channel();
@@ -210,9 +226,32 @@ async fn handle_server_start(payload: Bytes) -> VTableResponse {
         *server_impl.running.write().await = true;
     }
 
+    // Print the first pairing code once the server is reachable
+    if auth.is_enabled() {
+        auth.issue_pairing_code().await;
+    }
+
+    // Deliver batched messages every frame until the server stops
+    #[cfg(feature = "batching")]
+    if config.enable_batching {
+        let server_clone = server_impl.clone();
+        tokio::spawn(async move {
+            run_flush_loop(server_clone).await;
+        });
+    }
+
+    // Expire detached sessions even when no other connection closes
+    {
+        let server_clone = server_impl.clone();
+        tokio::spawn(async move {
+            run_session_reaper(server_clone).await;
+        });
+    }
+
     // Spawn server task
     tokio::spawn(async move {
-        let _ = axum::serve(listener, app)
+        // Pairing throttles by peer address
+        let _ = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(async {
                 rx.await.ok();
             })
@@ -229,14 +268,25 @@ async fn handle_server_stop() -> VTableResponse {
         None => return error_response("Server not running".to_string()),
     };
 
+    // Refuse new connections while draining
+    server_impl.websocket.stop_accepting().await;
+
+    // Mark as not running so the flush loop exits
+    *server_impl.running.write().await = false;
+
+    // Deliver anything still queued, then close every connection.
+    // Close frames queue behind pending messages so nothing is dropped.
+    #[cfg(feature = "batching")]
+    flush_batched_messages(&server_impl).await;
+    server_impl.websocket.close_all(CLOSE_GOING_AWAY, "Server shutting down").await;
+    let shutdown_grace = server_impl.config.read().await.connection_timeout.min(SHUTDOWN_DRAIN_TIMEOUT);
+    server_impl.websocket.wait_for_drain(shutdown_grace).await;
+
     // Send shutdown signal
     if let Some(tx) = server_impl.shutdown_signal.write().await.take() {
         let _ = tx.send(());
     }
 
-    // Mark as not running
-    *server_impl.running.write().await = false;
-
     // Update server entity's state
     if let Some(server_entity) = NETWORK_STATE.server_entity.read().await.as_ref() {
         if let Ok(state_comp) = server_entity.get_component::<ServerState>().await {
@@ -257,6 +307,54 @@ async fn handle_server_stop() -> VTableResponse {
     success_response(None)
 }
 
+/// Upper bound on how long `stop` waits for clients to acknowledge close frames
+const SHUTDOWN_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often detached sessions are checked against the resume window
+const SESSION_EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+async fn run_session_reaper(server_impl: Handle<NetworkServer>) {
+    let mut interval = tokio::time::interval(SESSION_EXPIRY_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if !*server_impl.running.read().await {
+            break;
+        }
+
+        server_impl.websocket.expire_sessions().await;
+    }
+}
+
+/// Send every message queued in the batcher to its connection
+#[cfg(feature = "batching")]
+async fn flush_batched_messages(server_impl: &NetworkServer) {
+    for (connection, message) in server_impl.batcher.get_message_batch().await {
+        let data = match bincode::serialize(&message) {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        let _ = server_impl.websocket.send_bytes(connection.0, data).await;
+    }
+}
+
+#[cfg(feature = "batching")]
+async fn run_flush_loop(server_impl: Handle<NetworkServer>) {
+    let batch_interval = server_impl.config.read().await.batch_interval;
+    let mut interval = tokio::time::interval(batch_interval.max(std::time::Duration::from_millis(1)));
+
+    loop {
+        interval.tick().await;
+
+        if !*server_impl.running.read().await {
+            break;
+        }
+
+        flush_batched_messages(&server_impl).await;
+    }
+}
+
 async fn handle_send_to(payload: Bytes) -> VTableResponse {
     #[derive(serde::Deserialize)]
     struct SendToPayload {
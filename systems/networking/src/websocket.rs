@@ -2,172 +2,374 @@
 //!
 //! This module handles WebSocket connections without using old trait systems.
 //! All functionality is exposed through VTable handlers in vtable_handlers.rs
+//!
+//! Each connection runs a reader and a writer half. The writer also drives the
+//! connection lifecycle: it sends ping heartbeats every `keep_alive_interval`
+//! and closes connections that have been silent for `connection_timeout`.
+//!
+//! Connections are bound to resumable sessions (see session.rs); data sent to a
+//! connection is recorded in its session's replay buffer before it is queued.
+//!
+//! Packets a client sends on a plugin channel are checked against the
+//! connection's grant and queued in the channel's inbox (see inbox.rs), tagged
+//! with the token the connection authenticated with.
 
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeSet};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use bytes::Bytes;
 use axum::{
-    extract::{ws::{WebSocket, WebSocketUpgrade, Message}, State},
-    response::Response,
+    extract::{ws::{WebSocket, WebSocketUpgrade, Message, CloseFrame}, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
-use futures_util::{SinkExt, StreamExt};
-use playground_core_types::{Shared, shared, CoreResult, CoreError};
-use playground_core_server::{ConnectionId, ConnectionInfo, ConnectionStatus};
-use tokio::sync::mpsc;
-use std::time::Instant;
-use crate::types::{Packet, Priority, ClientInfo, ClientStatus, ConnectionHandle};
+use futures_util::{SinkExt, StreamExt, stream::{SplitSink, SplitStream}};
+use serde::Deserialize;
+use playground_core_types::{Handle, handle, Shared, shared, CoreResult, CoreError};
+use playground_core_server::{ConnectionId, ConnectionInfo, ConnectionStatus, ServerConfig};
+use tokio::sync::{mpsc, Semaphore, OwnedSemaphorePermit};
+use crate::types::{Packet, Priority, ClientInfo, ClientStatus, IncomingPacket, PacketSender};
+use crate::state::NETWORK_STATE;
+use crate::channel_manager::ChannelManager;
+use crate::session::{SessionManager, SessionInfo, SESSION_INFO, SESSION_ACK};
+use crate::auth::{AuthManager, AuthGrant, unauthorized_response};
+
+/// Close code sent when the server shuts down or drops an idle connection
+pub const CLOSE_GOING_AWAY: u16 = 1001;
+
+/// How long to wait for the client's close acknowledgement after sending a close frame
+const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(1);
+/// Lower bound for the heartbeat/idle check interval
+const MIN_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
 /// WebSocket handler for managing connections
 pub struct WebSocketHandler {
     connections: Shared<HashMap<usize, ConnectionState>>,
     next_connection_id: Shared<usize>,
+    /// Limits and timeouts applied to every connection
+    config: Shared<ServerConfig>,
+    /// One permit per allowed connection; an upgrade holds its permit until
+    /// the socket closes, so concurrent upgrades cannot exceed `max_connections`
+    slots: Handle<Semaphore>,
+    /// Cleared during graceful shutdown so new upgrades are rejected
+    accepting: Shared<bool>,
+    /// Resumable sessions and their replay buffers
+    sessions: Handle<SessionManager>,
+    /// Subscriptions are moved to the new connection when a session resumes
+    channel_manager: Handle<ChannelManager>,
+    /// Every upgrade must present a valid token
+    auth: Handle<AuthManager>,
+}
+
+/// Query parameters of the upgrade request:
+/// `/ws?token=<auth token>&session=<session token>&ack=<seq>`
+#[derive(Debug, Default, Deserialize)]
+pub struct ConnectParams {
+    pub token: Option<String>,
+    pub session: Option<String>,
+    pub ack: Option<u64>,
 }
 
 struct ConnectionState {
-    handle: ConnectionHandle,
+    info: ClientInfo,
     sender: mpsc::Sender<Message>,
 }
 
 impl WebSocketHandler {
-    pub async fn new() -> CoreResult<Self> {
+    pub async fn new(
+        config: ServerConfig,
+        channel_manager: Handle<ChannelManager>,
+        auth: Handle<AuthManager>,
+    ) -> CoreResult<Self> {
+        // 0 = unlimited
+        let permits = match config.max_connections {
+            0 => Semaphore::MAX_PERMITS,
+            max => max.min(Semaphore::MAX_PERMITS),
+        };
+
         Ok(Self {
             connections: shared(HashMap::new()),
             next_connection_id: shared(1),
+            config: shared(config),
+            slots: handle(Semaphore::new(permits)),
+            accepting: shared(true),
+            sessions: handle(SessionManager::new()),
+            channel_manager,
+            auth,
         })
     }
-    
-    pub async fn add_connection(&self, mut conn: ConnectionHandle) -> CoreResult<()> {
-        let (tx, mut rx) = mpsc::channel(100);
-        
-        let conn_id = conn.id;
-        let state = ConnectionState {
-            handle: ConnectionHandle {
-                id: conn_id,
-                sender: conn.sender.clone(),
-                info: conn.info.clone(),
-            },
-            sender: tx,
-        };
-        
-        {
-            let mut connections = self.connections.write().await;
-            connections.insert(conn_id, state);
-        }
-        
-        // Spawn task to handle outgoing messages for this connection
-        tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                // Convert to bytes and send via the connection's sender
-                if let Message::Binary(data) = msg {
-                    let _ = conn.sender.send(Bytes::from(data)).await;
-                }
-            }
-        });
-        
+
+    pub async fn add_connection(&self, info: ClientInfo, sender: mpsc::Sender<Message>) -> CoreResult<()> {
+        let mut connections = self.connections.write().await;
+        connections.insert(info.id, ConnectionState { info, sender });
         Ok(())
     }
-    
+
     pub async fn remove_connection(&self, id: usize) -> CoreResult<()> {
         let mut connections = self.connections.write().await;
         connections.remove(&id);
         Ok(())
     }
-    
+
     pub async fn connection_count(&self) -> usize {
         let connections = self.connections.read().await;
         connections.len()
     }
-    
+
     pub async fn store_connection(&self, info: ConnectionInfo) {
         // Convert core ConnectionInfo to our ClientInfo
         // This is used by vtable_handlers.rs
     }
-    
+
     pub async fn remove_connection_by_core_id(&self, id: ConnectionId) {
         let mut connections = self.connections.write().await;
         connections.remove(&id.0);
     }
-    
+
     pub async fn get_all_connections(&self) -> Vec<ConnectionInfo> {
         let connections = self.connections.read().await;
-        connections.iter().map(|(id, state)| {
-            let mut metadata = HashMap::new();
-            metadata.insert("ip".to_string(), state.handle.info.ip_address.clone());
-            if let Some(ref ua) = state.handle.info.user_agent {
-                metadata.insert("user_agent".to_string(), ua.clone());
-            }
-            
-            ConnectionInfo {
-                id: ConnectionId(*id),
-                established_at: 0, // Would need to track this properly
-                last_activity: 0,
-                bytes_sent: state.handle.info.bytes_sent,
-                bytes_received: state.handle.info.bytes_received,
-                messages_sent: state.handle.info.messages_sent,
-                messages_received: state.handle.info.messages_received,
-                status: match state.handle.info.status {
-                    ClientStatus::Connected => ConnectionStatus::Connected,
-                    ClientStatus::Connecting => ConnectionStatus::Connecting,
-                    ClientStatus::Disconnecting => ConnectionStatus::Disconnecting,
-                    ClientStatus::Disconnected => ConnectionStatus::Disconnected,
-                },
-                metadata,
-            }
-        }).collect()
+        connections.iter()
+            .map(|(id, state)| connection_info(ConnectionId(*id), &state.info))
+            .collect()
     }
-    
+
     pub async fn get_connection(&self, id: ConnectionId) -> Option<ConnectionInfo> {
         let connections = self.connections.read().await;
-        connections.get(&id.0).map(|state| {
-            let mut metadata = HashMap::new();
-            metadata.insert("ip".to_string(), state.handle.info.ip_address.clone());
-            if let Some(ref ua) = state.handle.info.user_agent {
-                metadata.insert("user_agent".to_string(), ua.clone());
-            }
-            
-            ConnectionInfo {
-                id,
-                established_at: 0,
-                last_activity: 0,
-                bytes_sent: state.handle.info.bytes_sent,
-                bytes_received: state.handle.info.bytes_received,
-                messages_sent: state.handle.info.messages_sent,
-                messages_received: state.handle.info.messages_received,
-                status: match state.handle.info.status {
-                    ClientStatus::Connected => ConnectionStatus::Connected,
-                    ClientStatus::Connecting => ConnectionStatus::Connecting,
-                    ClientStatus::Disconnecting => ConnectionStatus::Disconnecting,
-                    ClientStatus::Disconnected => ConnectionStatus::Disconnected,
-                },
-                metadata,
-            }
-        })
+        connections.get(&id.0).map(|state| connection_info(id, &state.info))
     }
-    
+
+    /// Send a packet to every connection. Detached sessions are included so
+    /// the packet is buffered for replay when they resume.
     pub async fn broadcast(&self, packet: Packet) -> CoreResult<()> {
-        let connections = self.connections.read().await;
         let binary_data = serialize_packet(&packet)?;
-        let message = Message::Binary(Bytes::from(binary_data));
-        
-        for (_, conn) in connections.iter() {
-            let _ = conn.sender.send(message.clone()).await;
+        let mut conn_ids: BTreeSet<usize> = self.connections.read().await.keys().copied().collect();
+        conn_ids.extend(self.sessions.bound_connections().await);
+
+        for conn_id in conn_ids {
+            let _ = self.send_bytes(conn_id, binary_data.clone()).await;
         }
-        
+
         Ok(())
     }
-    
+
     pub async fn send_to(&self, conn_id: usize, packet: Packet) -> CoreResult<()> {
-        let connections = self.connections.read().await;
-        
-        if let Some(conn) = connections.get(&conn_id) {
-            let binary_data = serialize_packet(&packet)?;
-            let message = Message::Binary(Bytes::from(binary_data));
-            conn.sender.send(message).await
-                .map_err(|e| CoreError::Network(e.to_string()))?;
+        let binary_data = serialize_packet(&packet)?;
+        self.send_bytes(conn_id, binary_data).await
+    }
+
+    /// Queue an already-encoded binary frame for a connection.
+    /// Frames for a detached session are only buffered for replay.
+    pub async fn send_bytes(&self, conn_id: usize, data: Vec<u8>) -> CoreResult<()> {
+        let data = Bytes::from(data);
+
+        let Some(session) = self.sessions.session_for(conn_id).await else {
+            if let Some(sender) = self.sender_for(conn_id).await {
+                sender.send(Message::Binary(data)).await
+                    .map_err(|e| CoreError::Network(e.to_string()))?;
+            }
+            return Ok(());
+        };
+
+        // Hold the session lock until the frame is queued so sequence numbers
+        // match the order in which the client receives frames
+        let mut session = session.write().await;
+        session.record(data.clone());
+
+        if session.detached_at.is_none() {
+            if let Some(sender) = self.sender_for(conn_id).await {
+                // A closed socket is fine - the frame is replayed on resume
+                let _ = sender.send(Message::Binary(data)).await;
+            }
         }
-        
+
         Ok(())
     }
+
+    async fn sender_for(&self, conn_id: usize) -> Option<mpsc::Sender<Message>> {
+        let connections = self.connections.read().await;
+        connections.get(&conn_id).map(|conn| conn.sender.clone())
+    }
+
+    /// Bind a new connection to a session and register it.
+    ///
+    /// A valid token resumes its session: subscriptions move to the new
+    /// connection and unacknowledged packets are queued after `SESSION_INFO`.
+    /// Otherwise (no token, unknown token, or packets already evicted) a fresh
+    /// session is created and the client has to subscribe again.
+    async fn bind_session(
+        &self,
+        info: ClientInfo,
+        sender: mpsc::Sender<Message>,
+        grant: Handle<AuthGrant>,
+        resume: ConnectParams,
+    ) {
+        let conn_id = info.id;
+        self.channel_manager.authorize(ConnectionId(conn_id), grant).await;
+
+        if let Some(token) = resume.session {
+            if let Some(session) = self.sessions.find(&token).await {
+                let mut session = session.write().await;
+                let last_acked = resume.ack.unwrap_or(0);
+
+                let previous = session.connection;
+                let replay = session.replay_after(last_acked);
+                // A session that expired while we waited for its lock is
+                // not resumed
+                if let Some(replay) = replay && self.sessions.rebind(&mut session, conn_id).await {
+                    session.acknowledge(last_acked);
+                    self.channel_manager
+                        .transfer_subscriptions(ConnectionId(previous), ConnectionId(conn_id))
+                        .await;
+
+                    // The old socket may not have noticed it is dead yet
+                    if let Some(old) = self.sender_for(previous).await {
+                        let _ = old.send(close_message(CLOSE_GOING_AWAY, "Session resumed elsewhere")).await;
+                    }
+
+                    let _ = self.add_connection(info, sender.clone()).await;
+                    let _ = sender.send(session_info_message(&SessionInfo {
+                        token,
+                        resumed: true,
+                        next_sequence: last_acked + 1,
+                    })).await;
+
+                    for data in replay {
+                        let _ = sender.send(Message::Binary(data)).await;
+                    }
+                    return;
+                }
+            }
+        }
+
+        let session = self.sessions.create(conn_id).await;
+        let session = session.read().await;
+
+        let _ = self.add_connection(info, sender.clone()).await;
+        let _ = sender.send(session_info_message(&SessionInfo {
+            token: session.token.clone(),
+            resumed: false,
+            next_sequence: session.next_sequence(),
+        })).await;
+    }
+
+    /// Queue a client packet for the plugin that owns its channel, if the
+    /// connection's grant covers that channel
+    async fn deliver(&self, conn_id: usize, token: Option<String>, packet: Packet) {
+        if self.channel_manager.check_access(packet.channel_id, ConnectionId(conn_id)).await.is_err() {
+            return;
+        }
+
+        let channel_id = packet.channel_id;
+        let queued = NETWORK_STATE.inbox.push(IncomingPacket {
+            channel_id,
+            packet_type: packet.packet_type,
+            data: packet.payload,
+            sender: PacketSender::Client { connection: conn_id, token },
+        }).await;
+
+        if !queued {
+            let _ = playground_core_console::log_component(
+                "networking.websocket",
+                playground_core_console::LogLevel::Warning,
+                format!("Inbox for channel {} is full, dropped packet from connection {}", channel_id, conn_id),
+            ).await;
+        }
+    }
+
+    async fn acknowledge(&self, conn_id: usize, sequence: u64) {
+        if let Some(session) = self.sessions.session_for(conn_id).await {
+            session.write().await.acknowledge(sequence);
+        }
+    }
+
+    /// Keep the session of a closed connection resumable and drop sessions
+    /// whose resume window has passed
+    async fn release_session(&self, conn_id: usize) {
+        self.sessions.detach(conn_id).await;
+        self.expire_sessions().await;
+    }
+
+    /// Drop sessions whose resume window has passed, along with the
+    /// subscriptions still registered under their connection ids
+    pub async fn expire_sessions(&self) {
+        for expired in self.sessions.expire().await {
+            self.channel_manager.remove_connection(ConnectionId(expired)).await;
+        }
+    }
+
+    pub async fn is_accepting(&self) -> bool {
+        *self.accepting.read().await
+    }
+
+    /// Reject all further upgrade requests (first step of a graceful stop)
+    pub async fn stop_accepting(&self) {
+        *self.accepting.write().await = false;
+    }
+
+    /// Queue a close frame on every connection. Close frames are queued behind
+    /// any pending outbound messages, so those are still delivered first.
+    pub async fn close_all(&self, code: u16, reason: &'static str) {
+        // Writers take the connections lock to record what they sent, so a
+        // full queue only drains once the lock is released
+        let senders: Vec<mpsc::Sender<Message>> = {
+            let mut connections = self.connections.write().await;
+            connections.values_mut()
+                .map(|conn| {
+                    conn.info.status = ClientStatus::Disconnecting;
+                    conn.sender.clone()
+                })
+                .collect()
+        };
+
+        for sender in senders {
+            let _ = sender.send(close_message(code, reason)).await;
+        }
+    }
+
+    /// Wait until every connection has closed, or the timeout expires.
+    /// Returns the number of connections still open.
+    pub async fn wait_for_drain(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let remaining = self.connection_count().await;
+            if remaining == 0 || Instant::now() >= deadline {
+                return remaining;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    /// Reserve a slot within `max_connections`, or `None` if the server is full.
+    /// The slot is released when the permit is dropped.
+    fn reserve_slot(&self) -> Option<OwnedSemaphorePermit> {
+        self.slots.clone().try_acquire_owned().ok()
+    }
+
+    async fn record_received(&self, conn_id: usize, bytes: usize) {
+        let mut connections = self.connections.write().await;
+        if let Some(conn) = connections.get_mut(&conn_id) {
+            conn.info.last_activity = Instant::now();
+            conn.info.messages_received += 1;
+            conn.info.bytes_received += bytes as u64;
+        }
+    }
+
+    async fn record_sent(&self, conn_id: usize, bytes: usize) {
+        let mut connections = self.connections.write().await;
+        if let Some(conn) = connections.get_mut(&conn_id) {
+            conn.info.messages_sent += 1;
+            conn.info.bytes_sent += bytes as u64;
+        }
+    }
+
+    /// Time since the client last sent anything (including pongs)
+    async fn idle_time(&self, conn_id: usize) -> Duration {
+        let connections = self.connections.read().await;
+        connections.get(&conn_id)
+            .map(|conn| conn.info.last_activity.elapsed())
+            .unwrap_or_default()
+    }
 }
 
 // Clone implementation for Arc wrapping
@@ -176,22 +378,57 @@ impl Clone for WebSocketHandler {
         Self {
             connections: self.connections.clone(),
             next_connection_id: self.next_connection_id.clone(),
+            config: self.config.clone(),
+            slots: self.slots.clone(),
+            accepting: self.accepting.clone(),
+            sessions: self.sessions.clone(),
+            channel_manager: self.channel_manager.clone(),
+            auth: self.auth.clone(),
         }
     }
 }
 
 /// Axum handler for WebSocket upgrade
+///
+/// Upgrades are refused with `401 Unauthorized` without a valid token, and
+/// with `503 Service Unavailable` while the server is draining or when
+/// `max_connections` has been reached.
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
+    Query(params): Query<ConnectParams>,
     State(handler): State<Arc<WebSocketHandler>>,
 ) -> Response {
-    ws.on_upgrade(move |socket| handle_socket(socket, handler))
+    let grant = match handler.auth.authenticate(params.token.as_deref(), "websocket upgrade").await {
+        Ok(grant) => grant,
+        Err(e) => return unauthorized_response(&e),
+    };
+
+    if !handler.is_accepting().await {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down").into_response();
+    }
+
+    let Some(slot) = handler.reserve_slot() else {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Too many connections").into_response();
+    };
+
+    let max_message_size = handler.config.read().await.max_message_size;
+    ws.max_message_size(max_message_size)
+        .on_upgrade(move |socket| handle_socket(socket, handler, grant, params, slot))
 }
 
-async fn handle_socket(socket: WebSocket, handler: Arc<WebSocketHandler>) {
-    let (mut sender, mut receiver) = socket.split();
-    let (tx, mut rx) = mpsc::channel::<Message>(100);
-    
+async fn handle_socket(
+    socket: WebSocket,
+    handler: Arc<WebSocketHandler>,
+    grant: Handle<AuthGrant>,
+    params: ConnectParams,
+    // Held for the lifetime of the connection
+    _slot: OwnedSemaphorePermit,
+) {
+    let (sender, receiver) = socket.split();
+
+    let config = handler.config.read().await.clone();
+    let (tx, rx) = mpsc::channel::<Message>(config.message_queue_size.max(1));
+
     // Generate connection ID
     let conn_id = {
         let mut next_id = handler.next_connection_id.write().await;
@@ -199,57 +436,121 @@ async fn handle_socket(socket: WebSocket, handler: Arc<WebSocketHandler>) {
         *next_id += 1;
         id
     };
-    
-    // Create connection handle
-    let (bytes_tx, mut bytes_rx) = mpsc::channel::<Bytes>(100);
-    let conn_handle = ConnectionHandle {
+
+    let now = Instant::now();
+    let info = ClientInfo {
         id: conn_id,
-        sender: bytes_tx,
-        info: ClientInfo {
-            id: conn_id,
-            connected_at: Instant::now(),
-            last_activity: Instant::now(),
-            messages_sent: 0,
-            messages_received: 0,
-            bytes_sent: 0,
-            bytes_received: 0,
-            ip_address: "unknown".to_string(),
-            user_agent: None,
-            status: ClientStatus::Connected,
-        },
+        connected_at: now,
+        last_activity: now,
+        messages_sent: 0,
+        messages_received: 0,
+        bytes_sent: 0,
+        bytes_received: 0,
+        ip_address: "unknown".to_string(),
+        user_agent: None,
+        status: ClientStatus::Connected,
     };
-    
+
+    // The writer must already be draining the queue while a resumed
+    // session's replay buffer is pushed into it
+    let mut writer = tokio::spawn(run_writer(sender, rx, handler.clone(), conn_id, config));
+
+    // Plugins see the token only when it was actually checked
+    let token = if handler.auth.is_enabled() { params.token.clone() } else { None };
+
     // Add connection
-    let _ = handler.add_connection(conn_handle).await;
-    
-    // Spawn task to handle outgoing messages
-    let handler_clone = handler.clone();
-    tokio::spawn(async move {
-        while let Some(msg) = rx.recv().await {
-            if sender.send(msg).await.is_err() {
-                break;
-            }
+    handler.bind_session(info, tx, grant, params).await;
+
+    let reader = run_reader(receiver, handler.clone(), conn_id, token);
+    tokio::pin!(reader);
+
+    tokio::select! {
+        _ = &mut writer => {
+            // We sent a close frame (or the socket failed); give the client a
+            // moment to acknowledge before dropping the socket
+            let _ = tokio::time::timeout(CLOSE_GRACE_PERIOD, &mut reader).await;
         }
-        let _ = handler_clone.remove_connection(conn_id).await;
-    });
-    
-    // Spawn task to convert bytes to messages
-    tokio::spawn(async move {
-        while let Some(bytes) = bytes_rx.recv().await {
-            let _ = tx.send(Message::Binary(bytes)).await;
+        _ = &mut reader => writer.abort(),
+    }
+
+    // Clean up connection
+    let _ = handler.remove_connection(conn_id).await;
+    handler.release_session(conn_id).await;
+}
+
+/// Send queued messages and heartbeats until the connection closes
+async fn run_writer(
+    mut sender: SplitSink<WebSocket, Message>,
+    mut rx: mpsc::Receiver<Message>,
+    handler: Arc<WebSocketHandler>,
+    conn_id: usize,
+    config: ServerConfig,
+) {
+    let check_interval = config.keep_alive_interval
+        .unwrap_or(config.connection_timeout / 2)
+        .max(MIN_CHECK_INTERVAL);
+    let mut heartbeat = tokio::time::interval(check_interval);
+    heartbeat.tick().await; // The first tick completes immediately
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => {
+                let Some(msg) = msg else { break };
+                let closing = matches!(msg, Message::Close(_));
+                let len = message_len(&msg);
+
+                if sender.send(msg).await.is_err() {
+                    break;
+                }
+                handler.record_sent(conn_id, len).await;
+
+                if closing {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if !config.connection_timeout.is_zero()
+                    && handler.idle_time(conn_id).await >= config.connection_timeout
+                {
+                    let _ = sender.send(close_message(CLOSE_GOING_AWAY, "Idle timeout")).await;
+                    break;
+                }
+
+                if config.keep_alive_interval.is_some()
+                    && sender.send(Message::Ping(Bytes::new())).await.is_err()
+                {
+                    break;
+                }
+            }
         }
-    });
-    
-    // Handle incoming messages
+    }
+}
+
+/// Handle incoming messages until the client closes the connection
+async fn run_reader(
+    mut receiver: SplitStream<WebSocket>,
+    handler: Arc<WebSocketHandler>,
+    conn_id: usize,
+    token: Option<String>,
+) {
     while let Some(Ok(msg)) = receiver.next().await {
+        // Any frame, including pongs, counts as activity
+        handler.record_received(conn_id, message_len(&msg)).await;
+
         match msg {
             Message::Binary(data) => {
                 // Parse packet and handle
                 match deserialize_packet(&data) {
-                    Ok(packet) => {
-                        // Route to appropriate handler based on channel
-                        // This is where incoming messages from clients are processed
-                        // The actual routing is handled by channel subscriptions
+                    Ok(packet) if packet.channel_id == 0 && packet.packet_type == SESSION_ACK => {
+                        if let Ok(sequence) = <[u8; 8]>::try_from(packet.payload.as_slice()) {
+                            handler.acknowledge(conn_id, u64::from_le_bytes(sequence)).await;
+                        }
+                    }
+                    Ok(packet) if packet.channel_id != 0 => {
+                        handler.deliver(conn_id, token.clone(), packet).await;
+                    }
+                    Ok(_) => {
+                        // Unknown control packet, ignore
                     }
                     Err(_) => {
                         // Invalid packet, ignore
@@ -260,9 +561,65 @@ async fn handle_socket(socket: WebSocket, handler: Arc<WebSocketHandler>) {
             _ => {}
         }
     }
-    
-    // Clean up connection
-    let _ = handler.remove_connection(conn_id).await;
+}
+
+fn session_info_message(info: &SessionInfo) -> Message {
+    let packet = Packet {
+        channel_id: 0,
+        packet_type: SESSION_INFO,
+        priority: Priority::Blocker,
+        payload: bincode::serialize(info).unwrap_or_default(),
+    };
+    Message::Binary(Bytes::from(serialize_packet(&packet).unwrap_or_default()))
+}
+
+fn close_message(code: u16, reason: &'static str) -> Message {
+    Message::Close(Some(CloseFrame {
+        code,
+        reason: reason.into(),
+    }))
+}
+
+fn message_len(msg: &Message) -> usize {
+    match msg {
+        Message::Text(text) => text.len(),
+        Message::Binary(data) | Message::Ping(data) | Message::Pong(data) => data.len(),
+        Message::Close(_) => 0,
+    }
+}
+
+/// Convert a monotonic timestamp to seconds since the UNIX epoch
+fn epoch_secs(instant: Instant) -> u64 {
+    SystemTime::now()
+        .checked_sub(instant.elapsed())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn connection_info(id: ConnectionId, info: &ClientInfo) -> ConnectionInfo {
+    let mut metadata = HashMap::new();
+    metadata.insert("ip".to_string(), info.ip_address.clone());
+    if let Some(ref ua) = info.user_agent {
+        metadata.insert("user_agent".to_string(), ua.clone());
+    }
+
+    ConnectionInfo {
+        id,
+        established_at: epoch_secs(info.connected_at),
+        last_activity: epoch_secs(info.last_activity),
+        bytes_sent: info.bytes_sent,
+        bytes_received: info.bytes_received,
+        messages_sent: info.messages_sent,
+        messages_received: info.messages_received,
+        status: match info.status {
+            ClientStatus::Connected => ConnectionStatus::Connected,
+            ClientStatus::Connecting => ConnectionStatus::Connecting,
+            ClientStatus::Disconnecting => ConnectionStatus::Disconnecting,
+            ClientStatus::Disconnected => ConnectionStatus::Disconnected,
+        },
+        metadata,
+    }
 }
 
 fn serialize_packet(packet: &Packet) -> CoreResult<Vec<u8>> {
@@ -280,7 +637,7 @@ fn deserialize_packet(data: &[u8]) -> CoreResult<Packet> {
     if data.len() < 12 {
         return Err(CoreError::InvalidInput("Packet too small".into()));
     }
-    
+
     let channel_id = u16::from_le_bytes([data[0], data[1]]);
     let packet_type = u16::from_le_bytes([data[2], data[3]]);
     let priority = match data[4] {
@@ -291,14 +648,14 @@ fn deserialize_packet(data: &[u8]) -> CoreResult<Packet> {
         4 => Priority::Blocker,
         _ => Priority::Medium,
     };
-    
+
     let payload_len = u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize;
     let payload = data[12..12 + payload_len].to_vec();
-    
+
     Ok(Packet {
         channel_id,
         packet_type,
         priority,
         payload,
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,323 @@
+//! Sprite batching and runtime texture atlases
+//!
+//! Small sprite textures are shelf-packed into the renderer's atlas pages
+//! (`SpriteAtlasStorage`) so sprites drawn from different textures can share
+//! a batch. `SpriteBatcher` sorts sprites by layer, sort order, material and
+//! texture, and merges each run with the same texture and material into one
+//! `DrawSprites` command.
+
+use playground_core_types::{CoreResult, CoreError};
+use playground_core_ecs::{Entity, EntityRef};
+use playground_core_rendering::{
+    AtlasPage, AtlasRegion, AtlasShelf, RenderCommand, RenderLayer, ResourceId, Sprite,
+    SpriteAtlasStorage, SpriteDraw, SpriteQuad, Texture, Transform2D, UInt, Vec2, Visibility,
+    ATLAS_PADDING, ATLAS_PAGE_SIZE, MAX_SPRITES_PER_BATCH,
+};
+use playground_core_rendering::resources::{TextureFormat, TextureInfo, TextureUsage};
+
+/// Reserve space for an image on the shortest shelf it fits on, returning
+/// the position of its first texel
+pub fn pack(page: &mut AtlasPage, width: UInt, height: UInt) -> Option<[UInt; 2]> {
+    let padded_width = width.checked_add(ATLAS_PADDING * 2)?;
+    let padded_height = height.checked_add(ATLAS_PADDING * 2)?;
+    if padded_width > page.width || padded_height > page.height {
+        return None;
+    }
+
+    let page_width = page.width;
+    let best = page.shelves.iter_mut()
+        .filter(|shelf| shelf.height >= padded_height && shelf.used_width + padded_width <= page_width)
+        .min_by_key(|shelf| shelf.height - padded_height);
+
+    let shelf = match best {
+        Some(shelf) => shelf,
+        None => {
+            let y = page.shelves.last().map(|shelf| shelf.y + shelf.height).unwrap_or(0);
+            if y + padded_height > page.height {
+                return None;
+            }
+            page.shelves.push(AtlasShelf { y, height: padded_height, used_width: 0 });
+            page.shelves.last_mut().expect("shelf was just pushed")
+        }
+    };
+
+    let x = shelf.used_width;
+    shelf.used_width += padded_width;
+    Some([x + ATLAS_PADDING, shelf.y + ATLAS_PADDING])
+}
+
+/// Copy an RGBA8 image into the page, or None if it doesn't fit
+pub fn insert(page: &mut AtlasPage, width: UInt, height: UInt, pixels: &[u8]) -> Option<AtlasRegion> {
+    if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+        return None;
+    }
+    let [x, y] = pack(page, width, height)?;
+
+    let padding = ATLAS_PADDING as i64;
+    for row in -padding..height as i64 + padding {
+        let source_y = row.clamp(0, height as i64 - 1) as usize;
+        for column in -padding..width as i64 + padding {
+            let source_x = column.clamp(0, width as i64 - 1) as usize;
+            let source = (source_y * width as usize + source_x) * 4;
+            let target_x = (x as i64 + column) as usize;
+            let target_y = (y as i64 + row) as usize;
+            let target = (target_y * page.width as usize + target_x) * 4;
+            page.pixels[target..target + 4].copy_from_slice(&pixels[source..source + 4]);
+        }
+    }
+
+    Some(AtlasRegion {
+        texture_id: page.texture_id,
+        uv_min: [x as f32 / page.width as f32, y as f32 / page.height as f32],
+        uv_max: [(x + width) as f32 / page.width as f32, (y + height) as f32 / page.height as f32],
+        width,
+        height,
+    })
+}
+
+/// Fraction of the page covered by shelves, for diagnostics
+pub fn occupancy(page: &AtlasPage) -> f32 {
+    let used: u64 = page.shelves.iter().map(|shelf| shelf.used_width as u64 * shelf.height as u64).sum();
+    used as f32 / (page.width as u64 * page.height as u64).max(1) as f32
+}
+
+/// Map UVs of the original texture into its atlas page
+pub fn map_uv(region: &AtlasRegion, uv: Vec2) -> Vec2 {
+    [
+        region.uv_min[0] + (region.uv_max[0] - region.uv_min[0]) * uv[0],
+        region.uv_min[1] + (region.uv_max[1] - region.uv_min[1]) * uv[1],
+    ]
+}
+
+/// Collects sprites for a frame and turns them into as few draws as possible.
+///
+/// Layers draw in ascending order, then sort order within a layer. Sprites
+/// with equal layer and sort order are grouped by material and texture, so
+/// their relative overlap order is not preserved.
+#[derive(Clone, Debug, Default)]
+pub struct SpriteBatcher {
+    sprites: Vec<SpriteDraw>,
+}
+
+impl SpriteBatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, sprite: SpriteDraw) {
+        self.sprites.push(sprite);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Sort and merge everything pushed so far, leaving the batcher empty
+    pub fn build(&mut self) -> Vec<RenderCommand> {
+        let mut sprites = std::mem::take(&mut self.sprites);
+        sprites.sort_by_key(|sprite| (sprite.layer, sprite.sort_order, sprite.material, sprite.texture_id));
+
+        let mut commands = Vec::new();
+        let mut current: Option<(ResourceId, Option<ResourceId>, Vec<SpriteQuad>)> = None;
+
+        for sprite in sprites {
+            match &mut current {
+                Some((material, texture_id, quads))
+                    if *material == sprite.material
+                        && *texture_id == sprite.texture_id
+                        && quads.len() < MAX_SPRITES_PER_BATCH =>
+                {
+                    quads.push(sprite.quad);
+                }
+                _ => {
+                    if let Some(batch) = current.take() {
+                        commands.push(draw_sprites(batch));
+                    }
+                    current = Some((sprite.material, sprite.texture_id, vec![sprite.quad]));
+                }
+            }
+        }
+        if let Some(batch) = current {
+            commands.push(draw_sprites(batch));
+        }
+
+        commands
+    }
+}
+
+fn draw_sprites((material, texture_id, sprites): (ResourceId, Option<ResourceId>, Vec<SpriteQuad>)) -> RenderCommand {
+    RenderCommand::DrawSprites { texture_id, material, sprites }
+}
+
+/// Describe a sprite entity. `transform.position` is the sprite's center;
+/// a negative scale flips it. Packed textures are drawn from `region`.
+pub fn sprite_draw(
+    sprite: &Sprite,
+    transform: &Transform2D,
+    layer: UInt,
+    texture: Option<&Texture>,
+    region: Option<&AtlasRegion>,
+) -> SpriteDraw {
+    let texture_size = texture
+        .map(|texture| [texture.width as f32, texture.height as f32])
+        .unwrap_or([1.0, 1.0]);
+
+    let (mut uv_min, mut uv_max, base_size) = match sprite.source_rect {
+        Some(rect) => (
+            [rect.x / texture_size[0], rect.y / texture_size[1]],
+            [(rect.x + rect.width) / texture_size[0], (rect.y + rect.height) / texture_size[1]],
+            [rect.width, rect.height],
+        ),
+        None => ([0.0, 0.0], [1.0, 1.0], texture_size),
+    };
+
+    if sprite.flip_x != (transform.scale[0] < 0.0) {
+        std::mem::swap(&mut uv_min[0], &mut uv_max[0]);
+    }
+    if sprite.flip_y != (transform.scale[1] < 0.0) {
+        std::mem::swap(&mut uv_min[1], &mut uv_max[1]);
+    }
+
+    let mut texture_id = texture.map(|texture| texture.gpu_resource_id);
+    if let Some(region) = region {
+        uv_min = map_uv(region, uv_min);
+        uv_max = map_uv(region, uv_max);
+        texture_id = Some(region.texture_id);
+    }
+
+    let size = [base_size[0] * transform.scale[0].abs(), base_size[1] * transform.scale[1].abs()];
+    SpriteDraw {
+        layer,
+        sort_order: sprite.sort_order,
+        material: sprite.material.unwrap_or(0),
+        texture_id,
+        quad: SpriteQuad {
+            position: [transform.position[0] - size[0] * 0.5, transform.position[1] - size[1] * 0.5],
+            size,
+            rotation: transform.rotation,
+            uv_min,
+            uv_max,
+            color: sprite.color,
+        },
+    }
+}
+
+/// Pack RGBA8 textures into the renderer's sprite atlases
+///
+/// Takes `(texture_id, width, height, pixels)` for each texture. Sprites
+/// using a packed texture are drawn from its atlas page by `batch_sprites`,
+/// so they can share batches. Already packed textures keep their region.
+/// Every page that changed is uploaded once. Packing for one renderer holds
+/// its atlas lock; other renderers don't wait.
+pub async fn pack_sprite_textures(renderer: EntityRef, textures: Vec<(ResourceId, UInt, UInt, Vec<u8>)>) -> CoreResult<Vec<AtlasRegion>> {
+    let entity = resolve(&renderer)?;
+    let _guard = playground_core_rendering::lock_sprite_atlas(renderer.clone()).await?;
+
+    let mut atlases = entity.get_component::<SpriteAtlasStorage>().await?;
+    let capabilities = playground_core_rendering::get_capabilities(renderer.clone()).await?;
+    let page_size = ATLAS_PAGE_SIZE.min(capabilities.max_texture_size);
+
+    let mut regions = Vec::with_capacity(textures.len());
+    let mut dirty = vec![false; atlases.pages.len()];
+
+    for (texture_id, width, height, pixels) in textures {
+        if let Some(region) = atlases.regions.get(&texture_id) {
+            regions.push(*region);
+            continue;
+        }
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+            return Err(CoreError::InvalidInput(format!(
+                "Texture {} needs {}x{} RGBA8 pixels, got {} bytes", texture_id, width, height, pixels.len()
+            )));
+        }
+        if width.max(height) + ATLAS_PADDING * 2 > page_size {
+            return Err(CoreError::InvalidInput(format!(
+                "Texture {} ({}x{}) doesn't fit a {}x{} atlas page", texture_id, width, height, page_size, page_size
+            )));
+        }
+
+        let mut packed = atlases.pages.iter_mut().enumerate()
+            .find_map(|(index, page)| insert(page, width, height, &pixels).map(|region| (index, region)));
+
+        if packed.is_none() {
+            let info = TextureInfo {
+                id: 0,
+                width: page_size,
+                height: page_size,
+                depth: 1,
+                format: TextureFormat::RGBA8,
+                usage: TextureUsage::default(),
+                mip_levels: 1,
+                array_layers: 1,
+                sample_count: 1,
+            };
+            let page_id = playground_core_rendering::create_texture(renderer.clone(), info).await?;
+            let mut page = AtlasPage::new(page_id, page_size, page_size);
+            let region = insert(&mut page, width, height, &pixels)
+                .ok_or_else(|| CoreError::InvalidState("Texture doesn't fit an empty atlas page".to_string()))?;
+            atlases.pages.push(page);
+            dirty.push(false);
+            packed = Some((atlases.pages.len() - 1, region));
+        }
+
+        let (index, region) = packed.expect("texture was packed");
+        dirty[index] = true;
+        atlases.regions.insert(texture_id, region);
+        regions.push(region);
+    }
+
+    for (page, _) in atlases.pages.iter().zip(&dirty).filter(|(_, dirty)| **dirty) {
+        playground_core_rendering::update_texture(renderer.clone(), page.texture_id, page.pixels.clone()).await?;
+    }
+
+    entity.remove_component::<SpriteAtlasStorage>().await?;
+    entity.add_component(atlases).await?;
+    Ok(regions)
+}
+
+/// Build batched draw commands for sprite entities
+///
+/// Reads each entity's `Sprite` and `Transform2D`, plus `RenderLayer` and
+/// `Visibility` when present. Returns the commands for `submit_frame`; the
+/// backend reports how many batches it drew in `RendererStats`.
+pub async fn batch_sprites(renderer: EntityRef, sprites: &[Entity]) -> CoreResult<Vec<RenderCommand>> {
+    let entity = resolve(&renderer)?;
+    let atlases = entity.get_component::<SpriteAtlasStorage>().await?;
+    let mut batcher = SpriteBatcher::new();
+
+    for sprite_entity in sprites {
+        if let Ok(visibility) = sprite_entity.get_component::<Visibility>().await
+            && (!visibility.visible || !visibility.visible_in_hierarchy)
+        {
+            continue;
+        }
+
+        let sprite = sprite_entity.get_component::<Sprite>().await?;
+        let transform = sprite_entity.get_component::<Transform2D>().await?;
+        let layer = match sprite_entity.get_component::<RenderLayer>().await {
+            Ok(layer) => layer.layer,
+            Err(_) => RenderLayer::default().layer,
+        };
+
+        let texture = match &sprite.texture {
+            Some(texture) => {
+                let texture_entity = texture.upgrade().ok_or(CoreError::InvalidEntity)?;
+                Some(texture_entity.get_component::<Texture>().await?)
+            }
+            None => None,
+        };
+        let region = texture.as_ref().and_then(|texture| atlases.regions.get(&texture.gpu_resource_id));
+
+        batcher.push(sprite_draw(&sprite, &transform, layer, texture.as_ref(), region));
+    }
+
+    Ok(batcher.build())
+}
+
+fn resolve(renderer: &EntityRef) -> CoreResult<Entity> {
+    renderer.upgrade().ok_or(CoreError::InvalidEntity)
+}
@@ -0,0 +1,109 @@
+//! RGBA8 framebuffer the rasterizer draws into
+
+use std::path::Path;
+use playground_core_types::CoreResult;
+use playground_core_rendering::ColorRGBA;
+use crate::png;
+
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// Straight (non-premultiplied) RGBA, row-major, top-left origin
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Resize and clear to transparent black
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width as usize * height as usize * 4];
+    }
+
+    pub fn clear(&mut self, color: ColorRGBA) {
+        let rgba = to_rgba8(color);
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = self.offset(x, y);
+        let mut rgba = [0; 4];
+        rgba.copy_from_slice(&self.pixels[offset..offset + 4]);
+        Some(rgba)
+    }
+
+    /// Source-over blend `color`, scaled by `coverage` (0..1), onto a pixel
+    pub fn blend(&mut self, x: u32, y: u32, color: ColorRGBA, coverage: f32) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let src_alpha = (color[3] * coverage).clamp(0.0, 1.0);
+        if src_alpha <= 0.0 {
+            return;
+        }
+
+        let offset = self.offset(x, y);
+        let dst = &mut self.pixels[offset..offset + 4];
+        let dst_alpha = dst[3] as f32 / 255.0;
+        let out_alpha = src_alpha + dst_alpha * (1.0 - src_alpha);
+
+        for channel in 0..3 {
+            let src = color[channel].clamp(0.0, 1.0);
+            let existing = dst[channel] as f32 / 255.0;
+            let blended = if out_alpha > 0.0 {
+                (src * src_alpha + existing * dst_alpha * (1.0 - src_alpha)) / out_alpha
+            } else {
+                0.0
+            };
+            dst[channel] = to_u8(blended);
+        }
+        dst[3] = to_u8(out_alpha);
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        png::encode_rgba(self.width, self.height, &self.pixels)
+    }
+
+    pub async fn save_png(&self, path: &Path) -> CoreResult<()> {
+        tokio::fs::write(path, self.to_png()).await?;
+        Ok(())
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
+
+pub fn to_rgba8(color: ColorRGBA) -> [u8; 4] {
+    [to_u8(color[0]), to_u8(color[1]), to_u8(color[2]), to_u8(color[3])]
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,397 @@
+//! Render graph compilation (framegraphs feature)
+//!
+//! core/rendering declares the graph; this module turns it into commands:
+//!
+//! 1. orders passes so every reader runs after all writers of its inputs,
+//!    with writers of the same target kept in registration order
+//! 2. culls passes that don't contribute to an imported target and aren't
+//!    marked as having side effects
+//! 3. assigns transient targets to physical targets, aliasing targets with the
+//!    same shape whose lifetimes don't overlap
+//! 4. emits the `RenderCommand` stream the rasterizer executes
+//!
+//! Compilation is pure. core/rendering's `compile_render_graph` binds the
+//! physical targets to real render targets between `compile` and `commands`.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use playground_core_types::{CoreResult, CoreError};
+use playground_core_rendering::{
+    RenderCommand, RenderGraph, CompiledRenderGraph, CompiledPass, TargetBinding,
+    TargetHandle, TargetSource, ResourceId, Viewport,
+};
+use playground_core_rendering::graph::same_shape;
+use playground_core_rendering::resources::{Attachment, RenderTargetInfo, LoadOp, StoreOp, Subpass};
+
+/// Order, cull and allocate. The graph itself is left untouched.
+pub fn compile(graph: &RenderGraph) -> CoreResult<CompiledRenderGraph> {
+    Compiler { graph }.compile()
+}
+
+/// Emit the command stream for a graph compiled from `graph`.
+/// `physical_ids` holds the render target for each of `physical_targets`.
+pub fn commands(graph: &RenderGraph, compiled: &CompiledRenderGraph, physical_ids: &[ResourceId]) -> CoreResult<Vec<RenderCommand>> {
+    if physical_ids.len() != compiled.physical_targets.len() {
+        return Err(invalid(format!(
+            "Render graph needs {} physical targets, got {}",
+            compiled.physical_targets.len(), physical_ids.len()
+        )));
+    }
+
+    let mut commands = Vec::new();
+    let mut bound: Option<Option<ResourceId>> = None;
+
+    for scheduled in &compiled.passes {
+        let pass = &graph.passes[scheduled.index];
+        let implicit = [Subpass {
+            color_attachments: vec![0],
+            depth_attachment: None,
+            input_attachments: Vec::new(),
+        }];
+        let subpasses = if pass.info.subpasses.is_empty() { &implicit[..] } else { &pass.info.subpasses[..] };
+        let mut loaded = vec![false; pass.info.attachments.len()];
+
+        for (subpass_index, subpass) in subpasses.iter().enumerate() {
+            // Backends bind one target at a time: the first color attachment, else depth
+            let Some(&attachment) = subpass.color_attachments.first().or(subpass.depth_attachment.as_ref()) else {
+                continue;
+            };
+            let binding = scheduled.outputs[attachment as usize];
+            let (target, size) = match binding {
+                TargetBinding::Physical(slot) => {
+                    let info = &compiled.physical_targets[slot];
+                    (Some(physical_ids[slot]), Some((info.width, info.height)))
+                }
+                TargetBinding::Imported(id) => (id, None),
+            };
+
+            if bound != Some(target) {
+                commands.push(RenderCommand::SetRenderTarget { target });
+                if let Some((width, height)) = size {
+                    commands.push(RenderCommand::SetViewport {
+                        viewport: Viewport { x: 0, y: 0, width, height },
+                    });
+                }
+                bound = Some(target);
+            }
+
+            let mut clear_color = None;
+            let mut clear_depth = None;
+            for &index in &subpass.color_attachments {
+                if !std::mem::replace(&mut loaded[index as usize], true)
+                    && matches!(pass.info.attachments[index as usize].load_op, LoadOp::Clear)
+                {
+                    clear_color = Some(pass.clear_color);
+                }
+            }
+            if let Some(index) = subpass.depth_attachment
+                && !std::mem::replace(&mut loaded[index as usize], true)
+                && matches!(pass.info.attachments[index as usize].load_op, LoadOp::Clear)
+            {
+                clear_depth = Some(pass.clear_depth);
+            }
+            if clear_color.is_some() || clear_depth.is_some() {
+                commands.push(RenderCommand::Clear { color: clear_color, depth: clear_depth, stencil: None });
+            }
+
+            if let Some(recorded) = pass.commands.get(subpass_index) {
+                commands.extend(recorded.iter().cloned());
+            }
+        }
+    }
+
+    if !matches!(bound, None | Some(None)) {
+        commands.push(RenderCommand::SetRenderTarget { target: None });
+    }
+    Ok(commands)
+}
+
+struct Compiler<'a> {
+    graph: &'a RenderGraph,
+}
+
+impl Compiler<'_> {
+    fn compile(&self) -> CoreResult<CompiledRenderGraph> {
+        self.validate()?;
+
+        let writers = self.writers();
+        let dependencies = self.dependencies(&writers);
+        let alive = self.live_passes(&dependencies);
+        let order = self.topological_order(&dependencies, &alive)?;
+        self.check_transient_contents(&order, &writers, &alive)?;
+
+        let (physical_targets, bindings) = self.allocate_targets(&order);
+
+        let passes = order.iter().map(|&index| {
+            let pass = &self.graph.passes[index];
+            CompiledPass {
+                name: pass.name.clone(),
+                index,
+                outputs: pass.outputs.iter()
+                    .map(|handle| bindings[handle.0].expect("outputs of live passes are bound"))
+                    .collect(),
+            }
+        }).collect();
+
+        let culled = self.graph.passes.iter().enumerate()
+            .filter(|(index, _)| !alive[*index])
+            .map(|(_, pass)| pass.name.clone())
+            .collect();
+
+        Ok(CompiledRenderGraph { passes, culled, physical_targets, bindings })
+    }
+
+    fn validate(&self) -> CoreResult<()> {
+        for pass in &self.graph.passes {
+            for handle in pass.inputs.iter().chain(&pass.outputs) {
+                if handle.0 >= self.graph.targets.len() {
+                    return Err(invalid(format!("Pass '{}' references unknown target {}", pass.name, handle.0)));
+                }
+            }
+            if pass.outputs.len() != pass.info.attachments.len() {
+                return Err(invalid(format!(
+                    "Pass '{}' has {} attachments but writes {} targets",
+                    pass.name, pass.info.attachments.len(), pass.outputs.len()
+                )));
+            }
+            if pass.outputs.is_empty() {
+                return Err(invalid(format!("Pass '{}' writes no targets", pass.name)));
+            }
+            if let Some(handle) = pass.inputs.iter().find(|handle| pass.outputs.contains(handle)) {
+                return Err(invalid(format!(
+                    "Pass '{}' samples '{}' while writing it; use an input attachment instead",
+                    pass.name, self.graph.targets[handle.0].name
+                )));
+            }
+
+            let subpasses = pass.info.subpasses.len().max(1);
+            if pass.commands.len() > subpasses {
+                return Err(invalid(format!(
+                    "Pass '{}' records commands for {} subpasses but declares {}",
+                    pass.name, pass.commands.len(), subpasses
+                )));
+            }
+            for subpass in &pass.info.subpasses {
+                let indices = subpass.color_attachments.iter()
+                    .chain(subpass.depth_attachment.iter())
+                    .chain(&subpass.input_attachments);
+                for &attachment in indices {
+                    if attachment as usize >= pass.info.attachments.len() {
+                        return Err(invalid(format!(
+                            "Pass '{}' subpass references attachment {} out of {}",
+                            pass.name, attachment, pass.info.attachments.len()
+                        )));
+                    }
+                }
+            }
+
+            for (attachment, handle) in pass.info.attachments.iter().zip(&pass.outputs) {
+                if let TargetSource::Transient(info) = &self.graph.targets[handle.0].source
+                    && attachment.samples != info.samples
+                {
+                    return Err(invalid(format!(
+                        "Pass '{}' attachment has {} samples but '{}' has {}",
+                        pass.name, attachment.samples, self.graph.targets[handle.0].name, info.samples
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writers of each target, in registration order
+    fn writers(&self) -> Vec<Vec<usize>> {
+        let mut writers = vec![Vec::new(); self.graph.targets.len()];
+        for (index, pass) in self.graph.passes.iter().enumerate() {
+            for handle in &pass.outputs {
+                if !writers[handle.0].contains(&index) {
+                    writers[handle.0].push(index);
+                }
+            }
+        }
+        writers
+    }
+
+    /// Passes each pass must run after
+    fn dependencies(&self, writers: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut dependencies = vec![Vec::new(); self.graph.passes.len()];
+        for (index, pass) in self.graph.passes.iter().enumerate() {
+            for handle in &pass.inputs {
+                dependencies[index].extend(writers[handle.0].iter().copied());
+            }
+            for handle in &pass.outputs {
+                let target_writers = &writers[handle.0];
+                let position = target_writers.iter().position(|&writer| writer == index)
+                    .expect("pass is a writer of its outputs");
+                if position > 0 {
+                    dependencies[index].push(target_writers[position - 1]);
+                }
+            }
+            dependencies[index].sort_unstable();
+            dependencies[index].dedup();
+        }
+        dependencies
+    }
+
+    /// Passes that write an imported target or have side effects, plus
+    /// everything they depend on
+    fn live_passes(&self, dependencies: &[Vec<usize>]) -> Vec<bool> {
+        let mut alive = vec![false; self.graph.passes.len()];
+        let mut stack: Vec<usize> = self.graph.passes.iter().enumerate()
+            .filter(|(_, pass)| {
+                pass.side_effects || pass.outputs.iter()
+                    .any(|handle| matches!(self.graph.targets[handle.0].source, TargetSource::Imported(_)))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        while let Some(index) = stack.pop() {
+            if alive[index] {
+                continue;
+            }
+            alive[index] = true;
+            stack.extend(dependencies[index].iter().copied().filter(|&dependency| !alive[dependency]));
+        }
+        alive
+    }
+
+    /// Kahn's algorithm; ties run in registration order so output is stable
+    fn topological_order(&self, dependencies: &[Vec<usize>], alive: &[bool]) -> CoreResult<Vec<usize>> {
+        let mut remaining = vec![0usize; self.graph.passes.len()];
+        let mut dependents = vec![Vec::new(); self.graph.passes.len()];
+        for (index, deps) in dependencies.iter().enumerate().filter(|(index, _)| alive[*index]) {
+            remaining[index] = deps.len();
+            for &dependency in deps {
+                dependents[dependency].push(index);
+            }
+        }
+
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..self.graph.passes.len())
+            .filter(|&index| alive[index] && remaining[index] == 0)
+            .map(Reverse)
+            .collect();
+
+        let mut order = Vec::new();
+        while let Some(Reverse(index)) = ready.pop() {
+            order.push(index);
+            for &dependent in &dependents[index] {
+                remaining[dependent] -= 1;
+                if remaining[dependent] == 0 {
+                    ready.push(Reverse(dependent));
+                }
+            }
+        }
+
+        let live_count = alive.iter().filter(|&&alive| alive).count();
+        if order.len() != live_count {
+            let stuck: Vec<&str> = (0..self.graph.passes.len())
+                .filter(|&index| alive[index] && !order.contains(&index))
+                .map(|index| self.graph.passes[index].name.as_str())
+                .collect();
+            return Err(CoreError::InvalidState(format!(
+                "Render graph has a dependency cycle between passes: {}", stuck.join(", ")
+            )));
+        }
+        Ok(order)
+    }
+
+    /// Transient targets start undefined and stop existing after their last
+    /// use, so loading before the first write or discarding data that is
+    /// read later is a graph error
+    fn check_transient_contents(&self, order: &[usize], writers: &[Vec<usize>], alive: &[bool]) -> CoreResult<()> {
+        for (target_index, target) in self.graph.targets.iter().enumerate() {
+            if !matches!(target.source, TargetSource::Transient(_)) {
+                continue;
+            }
+            let live_writers: Vec<usize> = writers[target_index].iter().copied()
+                .filter(|&writer| alive[writer])
+                .collect();
+
+            let read = order.iter().any(|&index| self.graph.passes[index].inputs.contains(&TargetHandle(target_index)));
+            if read && live_writers.is_empty() {
+                return Err(invalid(format!("Transient target '{}' is read but never written", target.name)));
+            }
+
+            if let Some(&first) = live_writers.first() {
+                let attachment = self.attachment_for(first, target_index);
+                if matches!(attachment.load_op, LoadOp::Load) {
+                    return Err(invalid(format!(
+                        "Pass '{}' loads transient target '{}' before anything wrote it",
+                        self.graph.passes[first].name, target.name
+                    )));
+                }
+            }
+
+            if read
+                && let Some(&last) = live_writers.last()
+                && matches!(self.attachment_for(last, target_index).store_op, StoreOp::DontCare)
+            {
+                return Err(invalid(format!(
+                    "Pass '{}' discards transient target '{}' but later passes read it",
+                    self.graph.passes[last].name, target.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn attachment_for(&self, pass: usize, target: usize) -> &Attachment {
+        let pass = &self.graph.passes[pass];
+        let position = pass.outputs.iter().position(|handle| handle.0 == target)
+            .expect("pass writes the target");
+        &pass.info.attachments[position]
+    }
+
+    /// Greedy interval allocation: transient targets are taken in order of
+    /// first use and reuse the first compatible physical target that is free
+    fn allocate_targets(&self, order: &[usize]) -> (Vec<RenderTargetInfo>, Vec<Option<TargetBinding>>) {
+        let mut lifetimes: Vec<Option<(usize, usize)>> = vec![None; self.graph.targets.len()];
+        for (position, &index) in order.iter().enumerate() {
+            let pass = &self.graph.passes[index];
+            for handle in pass.inputs.iter().chain(&pass.outputs) {
+                let lifetime = lifetimes[handle.0].get_or_insert((position, position));
+                lifetime.1 = position;
+            }
+        }
+
+        let mut transients: Vec<(usize, usize, usize)> = Vec::new();
+        let mut bindings = vec![None; self.graph.targets.len()];
+        for (target_index, target) in self.graph.targets.iter().enumerate() {
+            let Some((first, last)) = lifetimes[target_index] else { continue };
+            match &target.source {
+                TargetSource::Imported(id) => bindings[target_index] = Some(TargetBinding::Imported(*id)),
+                TargetSource::Transient(_) => transients.push((first, last, target_index)),
+            }
+        }
+        transients.sort();
+
+        // Physical target and the position of its last use so far
+        let mut physical: Vec<(RenderTargetInfo, usize)> = Vec::new();
+        for (first, last, target_index) in transients {
+            let TargetSource::Transient(info) = &self.graph.targets[target_index].source else { continue };
+            let slot = physical.iter().position(|(candidate, busy_until)| {
+                *busy_until < first && same_shape(candidate, info)
+            });
+            let slot = match slot {
+                Some(slot) => {
+                    physical[slot].1 = last;
+                    slot
+                }
+                None => {
+                    let mut info = info.clone();
+                    info.id = 0;
+                    info.is_default = false;
+                    physical.push((info, last));
+                    physical.len() - 1
+                }
+            };
+            bindings[target_index] = Some(TargetBinding::Physical(slot));
+        }
+
+        (physical.into_iter().map(|(info, _)| info).collect(), bindings)
+    }
+}
+
+fn invalid(message: String) -> CoreError {
+    CoreError::InvalidInput(message)
+}
@@ -0,0 +1,25 @@
+//! Headless software renderer
+//!
+//! Rasterizes core/rendering `RenderCommand`s on the CPU into an RGBA
+//! framebuffer, so UI and game rendering can run (and be verified against
+//! golden PNGs) on servers and in CI without a browser or GPU.
+
+// Module declarations
+pub mod png;
+pub mod framebuffer;
+pub mod rasterizer;
+pub mod raster3d;
+pub mod scene3d;
+pub mod batching;
+pub mod framegraph;
+pub mod renderer;
+pub mod registration;
+pub mod vtable_handlers;
+
+// Re-exports
+pub use framebuffer::Framebuffer;
+pub use rasterizer::{Rasterizer, FrameCounters};
+pub use batching::{SpriteBatcher, pack_sprite_textures, batch_sprites};
+pub use scene3d::{ScenePass, SceneView, MeshInstance, Frustum, extract_scene_3d};
+pub use renderer::SoftwareRenderer;
+pub use registration::{register, BACKEND_NAME, RENDERER_CHANNEL};
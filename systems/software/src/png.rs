@@ -0,0 +1,90 @@
+//! Minimal PNG encoder for framebuffer dumps
+//!
+//! Writes 8-bit RGBA images using uncompressed (stored) deflate blocks.
+//! Files are larger than a real encoder would produce, but the output is
+//! byte-for-byte deterministic, which is what golden-image comparisons need.
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Encode tightly packed RGBA8 pixels as a PNG file
+pub fn encode_rgba(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let stride = width as usize * 4;
+
+    // Every scanline is prefixed with filter type 0 (None)
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in pixels.chunks(stride).take(height as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit depth, RGBA, deflate, no filter, no interlace
+
+    let mut png = Vec::new();
+    png.extend_from_slice(&PNG_SIGNATURE);
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+
+    let mut crc = crc32_update(0xFFFF_FFFF, kind);
+    crc = crc32_update(crc, data);
+    out.extend_from_slice(&(crc ^ 0xFFFF_FFFF).to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    let mut out = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    if data.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+
+    let mut chunks = data.chunks(MAX_STORED_BLOCK).peekable();
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD_ADLER: u32 = 65521;
+    let mut a = 1u32;
+    let mut b = 0u32;
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD_ADLER;
+        b %= MOD_ADLER;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,273 @@
+//! Depth-buffered triangle rasterization for `DrawMesh`
+//!
+//! Triangles are clipped against the near plane in clip space, culled if
+//! back-facing (counter-clockwise is front), and filled at pixel centers with
+//! perspective-correct interpolation. Every covered pixel is shaded with
+//! `scene3d::shade`, so results match the GPU backends' lighting.
+//! Meshes are not anti-aliased.
+
+use playground_core_rendering::{
+    ColorRGB, Mat4, MeshMaterial, RenderError, RenderResult, SceneLight, Vec3, Vec4, Viewport,
+    MESH_VERTEX_STRIDE,
+};
+use crate::framebuffer::Framebuffer;
+use crate::scene3d::{
+    normal_matrix, normalize, shade, transform_point, transform_vec4, MAT4_IDENTITY,
+};
+
+/// Camera and lights set by `SetCamera3D` and `SetLights`
+#[derive(Clone)]
+pub struct SceneState {
+    pub view_projection: Mat4,
+    pub eye: Vec3,
+    pub ambient: ColorRGB,
+    pub lights: Vec<SceneLight>,
+}
+
+impl Default for SceneState {
+    fn default() -> Self {
+        Self {
+            view_projection: MAT4_IDENTITY,
+            eye: [0.0, 0.0, 0.0],
+            ambient: [0.0, 0.0, 0.0],
+            lights: Vec::new(),
+        }
+    }
+}
+
+/// Where a mesh draws: the color and depth buffers plus the active viewport
+/// and device-space clip rectangle `[min_x, min_y, max_x, max_y]`
+pub struct Target<'a> {
+    pub framebuffer: &'a mut Framebuffer,
+    pub depth: &'a mut [f32],
+    pub viewport: Viewport,
+    pub clip: [f32; 4],
+}
+
+/// A vertex after the model and view-projection transforms
+#[derive(Clone, Copy)]
+struct ClipVertex {
+    clip: Vec4,
+    world: Vec3,
+    normal: Vec3,
+}
+
+/// A vertex in device space, ready to fill
+#[derive(Clone, Copy)]
+struct ScreenVertex {
+    x: f32,
+    y: f32,
+    depth: f32,
+    inv_w: f32,
+    world: Vec3,
+    normal: Vec3,
+}
+
+/// Buffer contents and parameters of one `DrawMesh`
+pub struct MeshDraw<'a> {
+    pub vertices: &'a [u8],
+    pub indices: Option<&'a [u8]>,
+    pub vertex_count: u32,
+    pub index_count: u32,
+    pub model: &'a Mat4,
+    pub material: &'a MeshMaterial,
+}
+
+/// Draw a mesh and return the number of triangles submitted
+pub fn draw_mesh(target: &mut Target, scene: &SceneState, mesh: &MeshDraw) -> RenderResult<u32> {
+    let MeshDraw { vertices, indices, vertex_count, index_count, model, material } = *mesh;
+    let vertex_count = vertex_count as usize;
+    if vertices.len() < vertex_count * MESH_VERTEX_STRIDE {
+        return Err(RenderError::InvalidOperation(format!(
+            "Mesh needs {} bytes of vertices, buffer has {}", vertex_count * MESH_VERTEX_STRIDE, vertices.len()
+        )));
+    }
+
+    let order: Vec<usize> = match indices {
+        Some(indices) => {
+            let index_count = index_count as usize;
+            if indices.len() < index_count * 4 {
+                return Err(RenderError::InvalidOperation(format!(
+                    "Mesh needs {} bytes of indices, buffer has {}", index_count * 4, indices.len()
+                )));
+            }
+            let mut order = Vec::with_capacity(index_count);
+            for chunk in indices[..index_count * 4].chunks_exact(4) {
+                let index = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize;
+                if index >= vertex_count {
+                    return Err(RenderError::InvalidOperation(format!(
+                        "Mesh index {} is out of range for {} vertices", index, vertex_count
+                    )));
+                }
+                order.push(index);
+            }
+            order
+        }
+        None => (0..vertex_count).collect(),
+    };
+
+    let normals = normal_matrix(model);
+    let transformed: Vec<ClipVertex> = (0..vertex_count).map(|index| {
+        let offset = index * MESH_VERTEX_STRIDE;
+        let position = read_vec3(vertices, offset);
+        let normal = read_vec3(vertices, offset + 12);
+        let world = transform_point(model, position);
+        ClipVertex {
+            clip: transform_vec4(&scene.view_projection, [world[0], world[1], world[2], 1.0]),
+            world,
+            normal: normalize([
+                normals[0][0] * normal[0] + normals[0][1] * normal[1] + normals[0][2] * normal[2],
+                normals[1][0] * normal[0] + normals[1][1] * normal[1] + normals[1][2] * normal[2],
+                normals[2][0] * normal[0] + normals[2][1] * normal[1] + normals[2][2] * normal[2],
+            ]),
+        }
+    }).collect();
+
+    for triangle in order.chunks_exact(3) {
+        let corners = [transformed[triangle[0]], transformed[triangle[1]], transformed[triangle[2]]];
+        let polygon = clip_near(&corners);
+        if polygon.len() < 3 {
+            continue;
+        }
+        let screen: Vec<ScreenVertex> = polygon.iter().map(|vertex| to_screen(vertex, target.viewport)).collect();
+        for fan in 1..screen.len() - 1 {
+            fill_triangle(target, scene, material, [screen[0], screen[fan], screen[fan + 1]]);
+        }
+    }
+
+    Ok((order.len() / 3) as u32)
+}
+
+fn read_vec3(bytes: &[u8], offset: usize) -> Vec3 {
+    let read = |at: usize| f32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    [read(offset), read(offset + 4), read(offset + 8)]
+}
+
+/// Sutherland-Hodgman against the near plane (z >= -w)
+fn clip_near(triangle: &[ClipVertex; 3]) -> Vec<ClipVertex> {
+    let distance = |vertex: &ClipVertex| vertex.clip[2] + vertex.clip[3];
+    let mut output = Vec::with_capacity(4);
+
+    for index in 0..3 {
+        let current = triangle[index];
+        let next = triangle[(index + 1) % 3];
+        let (d_current, d_next) = (distance(&current), distance(&next));
+
+        if d_current >= 0.0 {
+            output.push(current);
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            let t = d_current / (d_current - d_next);
+            output.push(lerp_vertex(&current, &next, t));
+        }
+    }
+
+    // Points exactly on the plane with w = 0 can't be projected
+    output.retain(|vertex| vertex.clip[3] > f32::EPSILON);
+    output
+}
+
+fn lerp_vertex(a: &ClipVertex, b: &ClipVertex, t: f32) -> ClipVertex {
+    let lerp = |x: f32, y: f32| x + (y - x) * t;
+    ClipVertex {
+        clip: [lerp(a.clip[0], b.clip[0]), lerp(a.clip[1], b.clip[1]), lerp(a.clip[2], b.clip[2]), lerp(a.clip[3], b.clip[3])],
+        world: [lerp(a.world[0], b.world[0]), lerp(a.world[1], b.world[1]), lerp(a.world[2], b.world[2])],
+        normal: [lerp(a.normal[0], b.normal[0]), lerp(a.normal[1], b.normal[1]), lerp(a.normal[2], b.normal[2])],
+    }
+}
+
+fn to_screen(vertex: &ClipVertex, viewport: Viewport) -> ScreenVertex {
+    let inv_w = 1.0 / vertex.clip[3];
+    let ndc = [vertex.clip[0] * inv_w, vertex.clip[1] * inv_w, vertex.clip[2] * inv_w];
+    ScreenVertex {
+        x: viewport.x as f32 + (ndc[0] + 1.0) * 0.5 * viewport.width as f32,
+        // NDC y points up, framebuffer rows go down
+        y: viewport.y as f32 + (1.0 - ndc[1]) * 0.5 * viewport.height as f32,
+        depth: ndc[2] * 0.5 + 0.5,
+        inv_w,
+        world: vertex.world,
+        normal: vertex.normal,
+    }
+}
+
+fn edge(a: &ScreenVertex, b: &ScreenVertex, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+fn fill_triangle(target: &mut Target, scene: &SceneState, material: &MeshMaterial, v: [ScreenVertex; 3]) {
+    let area = edge(&v[0], &v[1], v[2].x, v[2].y);
+    // Counter-clockwise in NDC is clockwise once y is flipped, i.e. negative area
+    if area >= 0.0 {
+        return;
+    }
+
+    let [clip_min_x, clip_min_y, clip_max_x, clip_max_y] = target.clip;
+    let min_x = v.iter().map(|vertex| vertex.x).fold(f32::INFINITY, f32::min).max(clip_min_x).floor();
+    let max_x = v.iter().map(|vertex| vertex.x).fold(f32::NEG_INFINITY, f32::max).min(clip_max_x).ceil();
+    let min_y = v.iter().map(|vertex| vertex.y).fold(f32::INFINITY, f32::min).max(clip_min_y).floor();
+    let max_y = v.iter().map(|vertex| vertex.y).fold(f32::NEG_INFINITY, f32::max).min(clip_max_y).ceil();
+    if min_x >= max_x || min_y >= max_y {
+        return;
+    }
+
+    let translucent = material.base_color[3] < 1.0;
+    let width = target.framebuffer.width() as usize;
+    let face_normal = face_normal(&v);
+
+    for y in min_y as u32..max_y as u32 {
+        for x in min_x as u32..max_x as u32 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [
+                edge(&v[1], &v[2], px, py) / area,
+                edge(&v[2], &v[0], px, py) / area,
+                edge(&v[0], &v[1], px, py) / area,
+            ];
+            if weights.iter().any(|weight| *weight < 0.0) {
+                continue;
+            }
+
+            let depth = weights[0] * v[0].depth + weights[1] * v[1].depth + weights[2] * v[2].depth;
+            let index = y as usize * width + x as usize;
+            if !(0.0..=1.0).contains(&depth) || depth >= target.depth[index] {
+                continue;
+            }
+
+            // Perspective-correct weights
+            let perspective = [
+                weights[0] * v[0].inv_w,
+                weights[1] * v[1].inv_w,
+                weights[2] * v[2].inv_w,
+            ];
+            let total = perspective[0] + perspective[1] + perspective[2];
+            let interpolate = |attribute: fn(&ScreenVertex) -> Vec3| -> Vec3 {
+                let mut result = [0.0; 3];
+                for (vertex, weight) in v.iter().zip(perspective) {
+                    let value = attribute(vertex);
+                    for axis in 0..3 {
+                        result[axis] += value[axis] * weight / total;
+                    }
+                }
+                result
+            };
+
+            let world = interpolate(|vertex| vertex.world);
+            let mut normal = normalize(interpolate(|vertex| vertex.normal));
+            if normal == [0.0, 0.0, 0.0] {
+                normal = face_normal;
+            }
+
+            let color = shade(material, scene.ambient, &scene.lights, world, normal, scene.eye);
+            target.framebuffer.blend(x, y, color, 1.0);
+            // Translucent surfaces are depth tested but don't occlude
+            if !translucent {
+                target.depth[index] = depth;
+            }
+        }
+    }
+}
+
+fn face_normal(v: &[ScreenVertex; 3]) -> Vec3 {
+    let a = [v[1].world[0] - v[0].world[0], v[1].world[1] - v[0].world[1], v[1].world[2] - v[0].world[2]];
+    let b = [v[2].world[0] - v[0].world[0], v[2].world[1] - v[0].world[1], v[2].world[2] - v[0].world[2]];
+    normalize([a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]])
+}
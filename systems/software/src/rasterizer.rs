@@ -0,0 +1,640 @@
+//! CPU rasterization of 2D render commands
+//!
+//! Shapes are described in local (pre-transform) space. For every pixel in a
+//! shape's device-space bounds, sample points are mapped back through the
+//! inverse transform and tested against the shape, so rotated and scaled
+//! geometry rasterizes exactly like axis-aligned geometry. Coverage is the
+//! fraction of samples inside; `samples_per_axis` controls anti-aliasing.
+
+use std::collections::HashMap;
+use playground_core_rendering::{
+    RenderCommand, RenderError, RenderResult, ColorRGBA, Mat3, Vec2, ResourceId, Viewport,
+    SpriteQuad,
+};
+use crate::framebuffer::Framebuffer;
+use crate::raster3d::{self, MeshDraw, SceneState, Target};
+use crate::scene3d::mat4_multiply;
+
+const IDENTITY: Mat3 = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Outline width used for unfilled circles (matches systems/webgl)
+const CIRCLE_OUTLINE_WIDTH: f32 = 2.0;
+/// Triangle counts the WebGL backend would submit, for comparable stats
+const CIRCLE_SEGMENTS: u32 = 32;
+
+/// Placeholder glyph metrics until the font subsystem lands (matches systems/webgl)
+const GLYPH_WIDTH_FACTOR: f32 = 0.6;
+const GLYPH_ADVANCE_FACTOR: f32 = 1.2;
+
+/// Drawn in place of textures that were never loaded
+const MISSING_TEXTURE_COLOR: ColorRGBA = [1.0, 0.0, 1.0, 1.0];
+
+const WHITE: ColorRGBA = [1.0, 1.0, 1.0, 1.0];
+
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    /// Straight RGBA8, row-major
+    pub pixels: Vec<u8>,
+}
+
+/// Per-frame counters, folded into `RendererStats` by the renderer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameCounters {
+    pub draw_calls: u32,
+    pub triangles: u32,
+    pub state_changes: u32,
+    pub batches: u32,
+    pub batched_sprites: u32,
+}
+
+#[derive(Clone, Copy)]
+struct DeviceRect {
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+impl DeviceRect {
+    fn intersect(&self, other: &DeviceRect) -> DeviceRect {
+        DeviceRect {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.min_x >= self.max_x || self.min_y >= self.max_y
+    }
+}
+
+#[derive(Clone)]
+struct SavedState {
+    transform: Mat3,
+    clip_stack: Vec<DeviceRect>,
+}
+
+enum Shape {
+    Rect { min: Vec2, max: Vec2 },
+    /// Segment with butt caps
+    Line { start: Vec2, end: Vec2, half_width: f32 },
+    Disc { center: Vec2, radius: f32 },
+    Ring { center: Vec2, inner: f32, outer: f32 },
+}
+
+impl Shape {
+    fn bounds(&self) -> (Vec2, Vec2) {
+        match self {
+            Shape::Rect { min, max } => (*min, *max),
+            Shape::Line { start, end, half_width } => (
+                [start[0].min(end[0]) - half_width, start[1].min(end[1]) - half_width],
+                [start[0].max(end[0]) + half_width, start[1].max(end[1]) + half_width],
+            ),
+            Shape::Disc { center, radius } | Shape::Ring { center, outer: radius, .. } => (
+                [center[0] - radius, center[1] - radius],
+                [center[0] + radius, center[1] + radius],
+            ),
+        }
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Shape::Rect { min, max } => {
+                point[0] >= min[0] && point[0] < max[0] && point[1] >= min[1] && point[1] < max[1]
+            }
+            Shape::Line { start, end, half_width } => {
+                let dx = end[0] - start[0];
+                let dy = end[1] - start[1];
+                let length_sq = dx * dx + dy * dy;
+                if length_sq <= f32::EPSILON {
+                    return false;
+                }
+                let px = point[0] - start[0];
+                let py = point[1] - start[1];
+                let t = (px * dx + py * dy) / length_sq;
+                if !(0.0..=1.0).contains(&t) {
+                    return false;
+                }
+                let distance = (px * dy - py * dx).abs() / length_sq.sqrt();
+                distance <= *half_width
+            }
+            Shape::Disc { center, radius } => {
+                distance_sq(point, *center) <= radius * radius
+            }
+            Shape::Ring { center, inner, outer } => {
+                let d = distance_sq(point, *center);
+                d >= inner * inner && d <= outer * outer
+            }
+        }
+    }
+}
+
+enum Paint {
+    Solid(ColorRGBA),
+    Texture {
+        id: ResourceId,
+        origin: Vec2,
+        size: Vec2,
+        uv_min: Vec2,
+        uv_max: Vec2,
+        tint: ColorRGBA,
+    },
+}
+
+impl Paint {
+    fn color_at(&self, local: Vec2, textures: &HashMap<ResourceId, Texture>) -> ColorRGBA {
+        match self {
+            Paint::Solid(color) => *color,
+            Paint::Texture { id, origin, size, uv_min, uv_max, tint } => {
+                let Some(texture) = textures.get(id) else {
+                    return MISSING_TEXTURE_COLOR;
+                };
+                let s = if size[0] != 0.0 { (local[0] - origin[0]) / size[0] } else { 0.0 };
+                let t = if size[1] != 0.0 { (local[1] - origin[1]) / size[1] } else { 0.0 };
+                let u = uv_min[0] + (uv_max[0] - uv_min[0]) * s;
+                let v = uv_min[1] + (uv_max[1] - uv_min[1]) * t;
+                let texel = sample_nearest(texture, u, v);
+                [texel[0] * tint[0], texel[1] * tint[1], texel[2] * tint[2], texel[3] * tint[3]]
+            }
+        }
+    }
+}
+
+pub struct Rasterizer {
+    framebuffer: Framebuffer,
+    /// One value per pixel, 0 (near) to 1 (far)
+    depth: Vec<f32>,
+    textures: HashMap<ResourceId, Texture>,
+    buffers: HashMap<ResourceId, Vec<u8>>,
+    scene: SceneState,
+    samples_per_axis: u32,
+    viewport: Viewport,
+    transform: Mat3,
+    clip_stack: Vec<DeviceRect>,
+    state_stack: Vec<SavedState>,
+    /// Material of the last sprite batch, to count material switches
+    material: Option<ResourceId>,
+    counters: FrameCounters,
+}
+
+impl Rasterizer {
+    /// `samples` is the sample count per pixel; it is rounded down to a square (1, 4, 9, 16...)
+    pub fn new(width: u32, height: u32, samples: u32) -> Self {
+        Self {
+            framebuffer: Framebuffer::new(width, height),
+            depth: vec![1.0; width as usize * height as usize],
+            textures: HashMap::new(),
+            buffers: HashMap::new(),
+            scene: SceneState::default(),
+            samples_per_axis: ((samples.max(1) as f32).sqrt() as u32).max(1),
+            viewport: Viewport { x: 0, y: 0, width, height },
+            transform: IDENTITY,
+            clip_stack: Vec::new(),
+            state_stack: Vec::new(),
+            material: None,
+            counters: FrameCounters::default(),
+        }
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.framebuffer.resize(width, height);
+        self.depth = vec![1.0; width as usize * height as usize];
+        self.viewport = Viewport { x: 0, y: 0, width, height };
+    }
+
+    pub fn load_texture(&mut self, id: ResourceId, width: u32, height: u32, pixels: Vec<u8>) -> RenderResult<()> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(RenderError::ResourceCreationFailed(format!(
+                "Texture {} expects {} bytes of RGBA8 data, got {}", id, expected, pixels.len()
+            )));
+        }
+        self.textures.insert(id, Texture { width, height, pixels });
+        Ok(())
+    }
+
+    /// Replace the pixels of a loaded texture, keeping its size
+    pub fn update_texture(&mut self, id: ResourceId, pixels: Vec<u8>) -> RenderResult<()> {
+        let texture = self.textures.get_mut(&id).ok_or(RenderError::InvalidResource(id))?;
+        if pixels.len() != texture.pixels.len() {
+            return Err(RenderError::InvalidOperation(format!(
+                "Texture {} expects {} bytes of RGBA8 data, got {}", id, texture.pixels.len(), pixels.len()
+            )));
+        }
+        texture.pixels = pixels;
+        Ok(())
+    }
+
+    pub fn unload_texture(&mut self, id: ResourceId) -> RenderResult<()> {
+        self.textures.remove(&id).map(|_| ()).ok_or(RenderError::InvalidResource(id))
+    }
+
+    /// Bytes held by loaded textures
+    pub fn texture_memory(&self) -> usize {
+        self.textures.values().map(|texture| texture.pixels.len()).sum()
+    }
+
+    /// Mesh data lives in buffers; other buffer kinds are stored but unused
+    pub fn create_buffer(&mut self, id: ResourceId, size: usize) {
+        self.buffers.insert(id, vec![0; size]);
+    }
+
+    /// Overwrite a buffer from its first byte
+    pub fn update_buffer(&mut self, id: ResourceId, data: &[u8]) -> RenderResult<()> {
+        let buffer = self.buffers.get_mut(&id).ok_or(RenderError::InvalidResource(id))?;
+        if data.len() > buffer.len() {
+            return Err(RenderError::InvalidOperation(format!(
+                "{} bytes don't fit in buffer {} of {} bytes", data.len(), id, buffer.len()
+            )));
+        }
+        buffer[..data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    pub fn destroy_buffer(&mut self, id: ResourceId) {
+        self.buffers.remove(&id);
+    }
+
+    pub fn buffer_memory(&self) -> usize {
+        self.buffers.values().map(Vec::len).sum()
+    }
+
+    /// Reset transform, clip and state stacks and return the previous frame's counters
+    pub fn begin_frame(&mut self) -> FrameCounters {
+        self.transform = IDENTITY;
+        self.clip_stack.clear();
+        self.state_stack.clear();
+        self.material = None;
+        self.scene = SceneState::default();
+        std::mem::take(&mut self.counters)
+    }
+
+    pub fn counters(&self) -> FrameCounters {
+        self.counters
+    }
+
+    pub fn execute(&mut self, command: &RenderCommand) -> RenderResult<()> {
+        match command {
+            // No stencil buffer
+            RenderCommand::Clear { color, depth, .. } => {
+                if let Some(color) = color {
+                    self.framebuffer.clear(*color);
+                }
+                if let Some(depth) = depth {
+                    self.depth.fill(depth.clamp(0.0, 1.0));
+                }
+            }
+            RenderCommand::SetViewport { viewport } => {
+                self.viewport = *viewport;
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::DrawQuad { position, size, color } => {
+                let shape = Shape::Rect { min: *position, max: [position[0] + size[0], position[1] + size[1]] };
+                self.fill(&shape, &Paint::Solid(*color));
+                self.count_draw(2);
+            }
+            RenderCommand::DrawText { text, position, size, color } => {
+                self.draw_text(text, *position, *size, *color);
+            }
+            RenderCommand::DrawImage { texture_id, position, size, uv_min, uv_max } => {
+                let shape = Shape::Rect { min: *position, max: [position[0] + size[0], position[1] + size[1]] };
+                let paint = Paint::Texture {
+                    id: *texture_id,
+                    origin: *position,
+                    size: *size,
+                    uv_min: *uv_min,
+                    uv_max: *uv_max,
+                    tint: WHITE,
+                };
+                self.fill(&shape, &paint);
+                self.count_draw(2);
+            }
+            RenderCommand::DrawSprites { texture_id, material, sprites } => {
+                self.draw_sprites(*texture_id, *material, sprites);
+            }
+            RenderCommand::DrawLine { start, end, width, color } => {
+                let shape = Shape::Line { start: *start, end: *end, half_width: width * 0.5 };
+                self.fill(&shape, &Paint::Solid(*color));
+                self.count_draw(2);
+            }
+            RenderCommand::DrawCircle { center, radius, color, filled } => {
+                let (shape, triangles) = if *filled {
+                    (Shape::Disc { center: *center, radius: *radius }, CIRCLE_SEGMENTS)
+                } else {
+                    let half = CIRCLE_OUTLINE_WIDTH * 0.5;
+                    (Shape::Ring { center: *center, inner: (radius - half).max(0.0), outer: radius + half }, CIRCLE_SEGMENTS * 2)
+                };
+                self.fill(&shape, &Paint::Solid(*color));
+                self.count_draw(triangles);
+            }
+            RenderCommand::SetCamera3D { view, projection, position } => {
+                self.scene.view_projection = mat4_multiply(projection, view);
+                self.scene.eye = *position;
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::SetLights { ambient, lights } => {
+                self.scene.ambient = *ambient;
+                self.scene.lights = lights.clone();
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::DrawMesh { vertex_buffer, index_buffer, vertex_count, index_count, model, material } => {
+                let vertices = self.buffers.get(vertex_buffer).ok_or(RenderError::InvalidResource(*vertex_buffer))?;
+                let indices = match index_buffer {
+                    Some(id) => Some(self.buffers.get(id).ok_or(RenderError::InvalidResource(*id))?.as_slice()),
+                    None => None,
+                };
+                let clip = self.current_clip();
+                let mut target = Target {
+                    framebuffer: &mut self.framebuffer,
+                    depth: &mut self.depth,
+                    viewport: self.viewport,
+                    clip: [clip.min_x, clip.min_y, clip.max_x, clip.max_y],
+                };
+                let mesh = MeshDraw {
+                    vertices,
+                    indices,
+                    vertex_count: *vertex_count,
+                    index_count: *index_count,
+                    model,
+                    material,
+                };
+                let triangles = raster3d::draw_mesh(&mut target, &self.scene, &mesh)?;
+                self.count_draw(triangles);
+            }
+            RenderCommand::SetClipRect { position, size } => {
+                let local = Shape::Rect { min: *position, max: [position[0] + size[0], position[1] + size[1]] };
+                let rect = self.device_bounds(&local).intersect(&self.current_clip());
+                self.clip_stack.push(rect);
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::ClearClipRect => {
+                self.clip_stack.pop();
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::SetTransform { matrix } => {
+                self.transform = *matrix;
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::ResetTransform => {
+                self.transform = IDENTITY;
+                self.counters.state_changes += 1;
+            }
+            RenderCommand::PushState => {
+                self.state_stack.push(SavedState {
+                    transform: self.transform,
+                    clip_stack: self.clip_stack.clone(),
+                });
+            }
+            RenderCommand::PopState => {
+                if let Some(state) = self.state_stack.pop() {
+                    self.transform = state.transform;
+                    self.clip_stack = state.clip_stack;
+                    self.counters.state_changes += 1;
+                }
+            }
+            // Frame control is handled by the renderer; entity and GPU resource
+            // commands have no meaning for an immediate-mode CPU target
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn draw_text(&mut self, text: &str, position: Vec2, size: f32, color: ColorRGBA) {
+        let glyph_width = size * GLYPH_WIDTH_FACTOR;
+        let mut x = position[0];
+
+        for ch in text.chars() {
+            if !ch.is_whitespace() {
+                let shape = Shape::Rect { min: [x, position[1]], max: [x + glyph_width, position[1] + size] };
+                self.fill(&shape, &Paint::Solid(color));
+                self.count_draw(2);
+            }
+            x += glyph_width * GLYPH_ADVANCE_FACTOR;
+        }
+    }
+
+    /// One draw call for the whole batch; each quad is rotated about its center
+    fn draw_sprites(&mut self, texture_id: Option<ResourceId>, material: ResourceId, sprites: &[SpriteQuad]) {
+        if self.material != Some(material) {
+            self.material = Some(material);
+            self.counters.state_changes += 1;
+        }
+
+        let parent = self.transform;
+        for sprite in sprites {
+            let [width, height] = sprite.size;
+            let (sin, cos) = sprite.rotation.sin_cos();
+            let center = [sprite.position[0] + width * 0.5, sprite.position[1] + height * 0.5];
+            // Local quad spans [0, size]; rotate around its center, then place it
+            let local: Mat3 = [
+                [cos, -sin, center[0] - cos * width * 0.5 + sin * height * 0.5],
+                [sin, cos, center[1] - sin * width * 0.5 - cos * height * 0.5],
+                [0.0, 0.0, 1.0],
+            ];
+            self.transform = multiply(&parent, &local);
+
+            let shape = Shape::Rect { min: [0.0, 0.0], max: sprite.size };
+            let paint = match texture_id {
+                Some(id) => Paint::Texture {
+                    id,
+                    origin: [0.0, 0.0],
+                    size: sprite.size,
+                    uv_min: sprite.uv_min,
+                    uv_max: sprite.uv_max,
+                    tint: sprite.color,
+                },
+                None => Paint::Solid(sprite.color),
+            };
+            self.fill(&shape, &paint);
+        }
+        self.transform = parent;
+
+        self.counters.draw_calls += 1;
+        self.counters.triangles += sprites.len() as u32 * 2;
+        self.counters.batches += 1;
+        self.counters.batched_sprites += sprites.len() as u32;
+    }
+
+    fn count_draw(&mut self, triangles: u32) {
+        self.counters.draw_calls += 1;
+        self.counters.triangles += triangles;
+    }
+
+    fn fill(&mut self, shape: &Shape, paint: &Paint) {
+        for (x, y, amount, local) in self.coverage(shape) {
+            let color = paint.color_at(local, &self.textures);
+            self.framebuffer.blend(x, y, color, amount);
+        }
+    }
+
+    /// Compute per-pixel coverage for a shape without touching the framebuffer
+    fn coverage(&self, shape: &Shape) -> Vec<(u32, u32, f32, Vec2)> {
+        let Some(inverse) = invert(&self.device_transform()) else {
+            return Vec::new();
+        };
+
+        let area = self.device_bounds(shape).intersect(&self.current_clip());
+        if area.is_empty() {
+            return Vec::new();
+        }
+
+        let n = self.samples_per_axis;
+        let total = (n * n) as f32;
+        let mut covered = Vec::new();
+
+        for y in area.min_y.floor() as u32..area.max_y.ceil() as u32 {
+            for x in area.min_x.floor() as u32..area.max_x.ceil() as u32 {
+                let mut hits = 0;
+                for sy in 0..n {
+                    for sx in 0..n {
+                        let device = [
+                            x as f32 + (sx as f32 + 0.5) / n as f32,
+                            y as f32 + (sy as f32 + 0.5) / n as f32,
+                        ];
+                        if !inside(&area, device) {
+                            continue;
+                        }
+                        if shape.contains(apply_transform(&inverse, device)) {
+                            hits += 1;
+                        }
+                    }
+                }
+                if hits > 0 {
+                    let center = apply_transform(&inverse, [x as f32 + 0.5, y as f32 + 0.5]);
+                    covered.push((x, y, hits as f32 / total, center));
+                }
+            }
+        }
+
+        covered
+    }
+
+    fn device_bounds(&self, shape: &Shape) -> DeviceRect {
+        let transform = self.device_transform();
+        let (min, max) = shape.bounds();
+        let corners = [
+            apply_transform(&transform, [min[0], min[1]]),
+            apply_transform(&transform, [max[0], min[1]]),
+            apply_transform(&transform, [max[0], max[1]]),
+            apply_transform(&transform, [min[0], max[1]]),
+        ];
+
+        let mut rect = DeviceRect {
+            min_x: f32::INFINITY,
+            min_y: f32::INFINITY,
+            max_x: f32::NEG_INFINITY,
+            max_y: f32::NEG_INFINITY,
+        };
+        for corner in corners {
+            rect.min_x = rect.min_x.min(corner[0]);
+            rect.min_y = rect.min_y.min(corner[1]);
+            rect.max_x = rect.max_x.max(corner[0]);
+            rect.max_y = rect.max_y.max(corner[1]);
+        }
+        rect
+    }
+
+    /// Current transform followed by the viewport offset
+    fn device_transform(&self) -> Mat3 {
+        let mut transform = self.transform;
+        let [bottom_x, bottom_y, bottom_w] = transform[2];
+        transform[0] = [
+            transform[0][0] + self.viewport.x as f32 * bottom_x,
+            transform[0][1] + self.viewport.x as f32 * bottom_y,
+            transform[0][2] + self.viewport.x as f32 * bottom_w,
+        ];
+        transform[1] = [
+            transform[1][0] + self.viewport.y as f32 * bottom_x,
+            transform[1][1] + self.viewport.y as f32 * bottom_y,
+            transform[1][2] + self.viewport.y as f32 * bottom_w,
+        ];
+        transform
+    }
+
+    /// Innermost clip, always bounded by the viewport and the framebuffer
+    fn current_clip(&self) -> DeviceRect {
+        let viewport = DeviceRect {
+            min_x: self.viewport.x as f32,
+            min_y: self.viewport.y as f32,
+            max_x: (self.viewport.x + self.viewport.width).min(self.framebuffer.width()) as f32,
+            max_y: (self.viewport.y + self.viewport.height).min(self.framebuffer.height()) as f32,
+        };
+        match self.clip_stack.last() {
+            Some(clip) => clip.intersect(&viewport),
+            None => viewport,
+        }
+    }
+}
+
+fn inside(rect: &DeviceRect, point: Vec2) -> bool {
+    point[0] >= rect.min_x && point[0] < rect.max_x && point[1] >= rect.min_y && point[1] < rect.max_y
+}
+
+fn distance_sq(a: Vec2, b: Vec2) -> f32 {
+    let dx = a[0] - b[0];
+    let dy = a[1] - b[1];
+    dx * dx + dy * dy
+}
+
+/// Row-major product `a * b`: applies `b` first
+fn multiply(a: &Mat3, b: &Mat3) -> Mat3 {
+    let mut result = [[0.0; 3]; 3];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    result
+}
+
+/// Apply a row-major 2D affine matrix to a point
+fn apply_transform(matrix: &Mat3, point: Vec2) -> Vec2 {
+    [
+        matrix[0][0] * point[0] + matrix[0][1] * point[1] + matrix[0][2],
+        matrix[1][0] * point[0] + matrix[1][1] * point[1] + matrix[1][2],
+    ]
+}
+
+/// Inverse of the affine part of a matrix, or None if it is degenerate
+fn invert(matrix: &Mat3) -> Option<Mat3> {
+    let [a, b, tx] = matrix[0];
+    let [c, d, ty] = matrix[1];
+    let det = a * d - b * c;
+    if det.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    let inv_a = d / det;
+    let inv_b = -b / det;
+    let inv_c = -c / det;
+    let inv_d = a / det;
+    Some([
+        [inv_a, inv_b, -(inv_a * tx + inv_b * ty)],
+        [inv_c, inv_d, -(inv_c * tx + inv_d * ty)],
+        [0.0, 0.0, 1.0],
+    ])
+}
+
+fn sample_nearest(texture: &Texture, u: f32, v: f32) -> ColorRGBA {
+    if texture.width == 0 || texture.height == 0 {
+        return MISSING_TEXTURE_COLOR;
+    }
+
+    let x = ((u.clamp(0.0, 1.0) * texture.width as f32) as u32).min(texture.width - 1);
+    let y = ((v.clamp(0.0, 1.0) * texture.height as f32) as u32).min(texture.height - 1);
+    let offset = (y as usize * texture.width as usize + x as usize) * 4;
+    let texel = &texture.pixels[offset..offset + 4];
+    [
+        texel[0] as f32 / 255.0,
+        texel[1] as f32 / 255.0,
+        texel[2] as f32 / 255.0,
+        texel[3] as f32 / 255.0,
+    ]
+}
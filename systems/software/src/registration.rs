@@ -0,0 +1,32 @@
+//! VTable registration for systems/software
+
+use tokio::sync::mpsc;
+use playground_core_types::CoreResult;
+use playground_core_ecs::{VTableCommand, get_world};
+use crate::vtable_handlers;
+
+/// Backend name used in `RendererBackend::active_backend`
+pub const BACKEND_NAME: &str = "software";
+
+/// VTable channel the renderer listens on
+pub const RENDERER_CHANNEL: &str = "renderer.software";
+
+/// Register the software renderer with the World's VTable
+pub async fn register() -> CoreResult<()> {
+    let world = get_world().await?;
+
+    let (tx, mut rx) = mpsc::channel::<VTableCommand>(100);
+    world.vtable.register(RENDERER_CHANNEL.to_string(), tx).await?;
+
+    tokio::spawn(async move {
+        while let Some(cmd) = rx.recv().await {
+            let response = vtable_handlers::handle_renderer_operations(
+                cmd.operation,
+                cmd.payload
+            ).await;
+            let _ = cmd.response.send(response).await;
+        }
+    });
+
+    Ok(())
+}
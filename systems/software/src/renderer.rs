@@ -0,0 +1,228 @@
+//! Software renderer implementing the core/rendering contract
+
+use std::path::Path;
+use std::time::Instant;
+use playground_core_types::CoreResult;
+use playground_core_rendering::{
+    RenderCommand, RenderError, RenderResult, RendererCapabilities, RendererConfig,
+    RendererStats, ResourceId, UInt,
+};
+use playground_core_rendering::resources::{BufferInfo, TextureInfo, TextureFormat};
+use crate::framebuffer::Framebuffer;
+use crate::rasterizer::{FrameCounters, Rasterizer};
+
+/// Framebuffer size used until the first resize
+pub const DEFAULT_WIDTH: u32 = 1280;
+pub const DEFAULT_HEIGHT: u32 = 720;
+
+/// Largest framebuffer or texture edge the rasterizer accepts
+const MAX_TEXTURE_SIZE: UInt = 8192;
+
+pub struct SoftwareRenderer {
+    rasterizer: Rasterizer,
+    config: RendererConfig,
+    stats: RendererStats,
+    initialized: bool,
+    frame_started: Option<Instant>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_config(width, height, RendererConfig::default())
+    }
+
+    pub fn with_config(width: u32, height: u32, config: RendererConfig) -> Self {
+        Self {
+            rasterizer: Rasterizer::new(width, height, config.multisampling),
+            config,
+            stats: RendererStats::default(),
+            initialized: false,
+            frame_started: None,
+        }
+    }
+
+    pub fn initialize(&mut self, config: RendererConfig) -> RenderResult<()> {
+        if self.initialized {
+            return Err(RenderError::AlreadyInitialized);
+        }
+
+        let framebuffer = self.rasterizer.framebuffer();
+        self.rasterizer = Rasterizer::new(framebuffer.width(), framebuffer.height(), config.multisampling);
+        self.config = config;
+        self.stats = RendererStats::default();
+        self.initialized = true;
+        Ok(())
+    }
+
+    pub fn shutdown(&mut self) -> RenderResult<()> {
+        self.ensure_initialized()?;
+        self.initialized = false;
+        self.frame_started = None;
+        Ok(())
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    pub fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    pub fn capabilities(&self) -> RendererCapabilities {
+        RendererCapabilities {
+            max_texture_size: MAX_TEXTURE_SIZE,
+            max_render_targets: 0,
+            max_color_attachments: 1,
+            max_sample_count: 16,
+            supports_multisample: true,
+            supports_depth_texture: false,
+            ..RendererCapabilities::default()
+        }
+    }
+
+    pub fn stats(&self) -> RendererStats {
+        let mut stats = self.stats.clone();
+        stats.resource_memory = self.rasterizer.texture_memory()
+            + self.rasterizer.buffer_memory()
+            + self.rasterizer.framebuffer().pixels().len();
+        stats
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        self.rasterizer.framebuffer()
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) -> RenderResult<()> {
+        validate_size(width, height)?;
+        self.rasterizer.resize(width, height);
+        Ok(())
+    }
+
+    pub fn load_texture(&mut self, id: ResourceId, width: u32, height: u32, pixels: Vec<u8>) -> RenderResult<()> {
+        validate_size(width, height)?;
+        self.rasterizer.load_texture(id, width, height, pixels)
+    }
+
+    /// Allocate a cleared texture described by core/rendering's `create_texture`
+    pub fn create_texture(&mut self, info: &TextureInfo) -> RenderResult<()> {
+        if !matches!(info.format, TextureFormat::RGBA8 | TextureFormat::SRGBA8) {
+            return Err(RenderError::FeatureNotSupported(format!("{:?} textures", info.format)));
+        }
+        if info.depth > 1 || info.array_layers > 1 {
+            return Err(RenderError::FeatureNotSupported("3D and array textures".to_string()));
+        }
+        let pixels = vec![0; info.width as usize * info.height as usize * 4];
+        self.load_texture(info.id, info.width, info.height, pixels)
+    }
+
+    pub fn update_texture(&mut self, id: ResourceId, pixels: Vec<u8>) -> RenderResult<()> {
+        self.rasterizer.update_texture(id, pixels)
+    }
+
+    pub fn unload_texture(&mut self, id: ResourceId) -> RenderResult<()> {
+        self.rasterizer.unload_texture(id)
+    }
+
+    /// Allocate a zeroed buffer described by core/rendering's `create_buffer`
+    pub fn create_buffer(&mut self, info: &BufferInfo) -> RenderResult<()> {
+        self.rasterizer.create_buffer(info.id, info.size);
+        Ok(())
+    }
+
+    pub fn update_buffer(&mut self, id: ResourceId, data: &[u8]) -> RenderResult<()> {
+        self.rasterizer.update_buffer(id, data)
+    }
+
+    pub fn destroy_buffer(&mut self, id: ResourceId) {
+        self.rasterizer.destroy_buffer(id);
+    }
+
+    /// Execute one frame worth of commands: everything up to `Present`
+    /// (or the end of the list) is drawn as a single frame
+    pub fn submit_frame(&mut self, commands: &[RenderCommand]) -> RenderResult<()> {
+        self.ensure_initialized()?;
+
+        for command in commands {
+            match command {
+                RenderCommand::BeginFrame { .. } => self.begin_frame(),
+                RenderCommand::EndFrame | RenderCommand::Present => self.end_frame(),
+                _ => {
+                    if self.frame_started.is_none() {
+                        self.begin_frame();
+                    }
+                    self.rasterizer.execute(command)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Finish the current frame, if one is open
+    pub fn present(&mut self) -> RenderResult<()> {
+        self.ensure_initialized()?;
+        self.end_frame();
+        Ok(())
+    }
+
+    pub async fn save_png(&self, path: &Path) -> CoreResult<()> {
+        self.rasterizer.framebuffer().save_png(path).await
+    }
+
+    fn begin_frame(&mut self) {
+        // A frame left open is closed implicitly so its counters are not lost
+        self.end_frame();
+        self.rasterizer.begin_frame();
+        self.frame_started = Some(Instant::now());
+    }
+
+    fn end_frame(&mut self) {
+        let Some(started) = self.frame_started.take() else {
+            return;
+        };
+        let counters = self.rasterizer.counters();
+        self.record_frame(counters, started.elapsed().as_secs_f32() * 1000.0);
+    }
+
+    fn record_frame(&mut self, counters: FrameCounters, frame_time_ms: f32) {
+        let stats = &mut self.stats;
+        stats.frames_rendered += 1;
+        stats.draw_calls += counters.draw_calls as u64;
+        stats.draw_calls_per_frame = counters.draw_calls;
+        stats.triangles_rendered += counters.triangles as u64;
+        stats.triangles_per_frame = counters.triangles;
+        stats.state_changes += counters.state_changes as u64;
+        stats.state_changes_per_frame = counters.state_changes;
+        stats.batches_per_frame = counters.batches;
+        stats.batched_sprites_per_frame = counters.batched_sprites;
+
+        stats.last_frame_time_ms = frame_time_ms;
+        if stats.frames_rendered == 1 {
+            stats.min_frame_time_ms = frame_time_ms;
+            stats.max_frame_time_ms = frame_time_ms;
+            stats.average_frame_time_ms = frame_time_ms;
+        } else {
+            stats.min_frame_time_ms = stats.min_frame_time_ms.min(frame_time_ms);
+            stats.max_frame_time_ms = stats.max_frame_time_ms.max(frame_time_ms);
+            let frames = stats.frames_rendered as f32;
+            stats.average_frame_time_ms += (frame_time_ms - stats.average_frame_time_ms) / frames;
+        }
+    }
+
+    fn ensure_initialized(&self) -> RenderResult<()> {
+        if !self.initialized {
+            return Err(RenderError::NotInitialized);
+        }
+        Ok(())
+    }
+}
+
+fn validate_size(width: u32, height: u32) -> RenderResult<()> {
+    if width == 0 || height == 0 || width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+        return Err(RenderError::InvalidOperation(format!(
+            "Size {}x{} is outside 1..={}", width, height, MAX_TEXTURE_SIZE
+        )));
+    }
+    Ok(())
+}
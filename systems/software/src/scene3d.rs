@@ -0,0 +1,572 @@
+//! 3D scene extraction and forward lighting
+//!
+//! `ScenePass` collects the meshes and lights seen by one camera, culls
+//! meshes whose bounds fall outside the view frustum and emits the
+//! `RenderCommand`s for a forward pass; `extract_scene_3d` fills one from
+//! camera, mesh and light entities. `shade` is the lighting model every
+//! backend implements, so CPU and GPU output agree.
+//!
+//! Matrices are row-major and multiply column vectors, like `SetTransform`:
+//! translation lives in the last column. The camera looks down -Z and clip
+//! space follows OpenGL (z in [-w, w]).
+
+use serde::{Serialize, Deserialize};
+use playground_core_types::{CoreResult, CoreError};
+use playground_core_ecs::Entity;
+use playground_core_rendering::{
+    BoundingBox, BoundingSphere, ColorRGB, ColorRGBA, Float, Int, Mat3, Mat4, Quat, ResourceId,
+    UInt, Vec3, Vec4, RenderCommand, SceneLight, MeshMaterial,
+    Camera, Camera3D, Light, LightType, Material, Mesh, MeshRenderer, ProjectionType, RenderLayer,
+    Transform3D, UniformValue, Visibility,
+};
+
+pub const MAT4_IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// Math
+
+pub fn mat4_multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut result = [[0.0; 4]; 4];
+    for (row, result_row) in result.iter_mut().enumerate() {
+        for (column, value) in result_row.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[row][k] * b[k][column]).sum();
+        }
+    }
+    result
+}
+
+pub fn transform_point(matrix: &Mat4, point: Vec3) -> Vec3 {
+    let [x, y, z, w] = transform_vec4(matrix, [point[0], point[1], point[2], 1.0]);
+    if w != 0.0 && w != 1.0 {
+        [x / w, y / w, z / w]
+    } else {
+        [x, y, z]
+    }
+}
+
+pub fn transform_vec4(matrix: &Mat4, v: Vec4) -> Vec4 {
+    let mut result = [0.0; 4];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value = (0..4).map(|k| matrix[row][k] * v[k]).sum();
+    }
+    result
+}
+
+/// Rotate by the matrix's upper 3x3, ignoring translation
+pub fn transform_direction(matrix: &Mat4, direction: Vec3) -> Vec3 {
+    let [x, y, z, _] = transform_vec4(matrix, [direction[0], direction[1], direction[2], 0.0]);
+    [x, y, z]
+}
+
+/// Rotation matrix of a unit quaternion `[x, y, z, w]`
+pub fn quat_to_mat3(q: Quat) -> Mat3 {
+    let [x, y, z, w] = q;
+    [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - z * w), 2.0 * (x * z + y * w)],
+        [2.0 * (x * y + z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - x * w)],
+        [2.0 * (x * z - y * w), 2.0 * (y * z + x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ]
+}
+
+/// Local to world: scale, then rotate, then translate
+pub fn model_matrix(transform: &Transform3D) -> Mat4 {
+    let rotation = quat_to_mat3(normalize_quat(transform.rotation));
+    let [sx, sy, sz] = transform.scale;
+    let [tx, ty, tz] = transform.position;
+    [
+        [rotation[0][0] * sx, rotation[0][1] * sy, rotation[0][2] * sz, tx],
+        [rotation[1][0] * sx, rotation[1][1] * sy, rotation[1][2] * sz, ty],
+        [rotation[2][0] * sx, rotation[2][1] * sy, rotation[2][2] * sz, tz],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+/// World to view for a camera transform; the camera's scale is ignored
+pub fn view_matrix(transform: &Transform3D) -> Mat4 {
+    let rotation = quat_to_mat3(normalize_quat(transform.rotation));
+    let p = transform.position;
+    // Inverse of a rigid transform: transpose the rotation, rotate the translation back
+    let mut view = MAT4_IDENTITY;
+    for row in 0..3 {
+        for column in 0..3 {
+            view[row][column] = rotation[column][row];
+        }
+        view[row][3] = -(rotation[0][row] * p[0] + rotation[1][row] * p[1] + rotation[2][row] * p[2]);
+    }
+    view
+}
+
+pub fn projection_matrix(camera: &Camera3D) -> Mat4 {
+    let (near, far) = (camera.near, camera.far);
+    let depth = near - far;
+    match camera.projection {
+        ProjectionType::Perspective => {
+            let f = 1.0 / (camera.fov * 0.5).tan();
+            [
+                [f / camera.aspect, 0.0, 0.0, 0.0],
+                [0.0, f, 0.0, 0.0],
+                [0.0, 0.0, (far + near) / depth, 2.0 * far * near / depth],
+                [0.0, 0.0, -1.0, 0.0],
+            ]
+        }
+        ProjectionType::Orthographic => {
+            // ortho_size is the half-height of the view volume
+            let top = camera.ortho_size;
+            let right = top * camera.aspect;
+            [
+                [1.0 / right, 0.0, 0.0, 0.0],
+                [0.0, 1.0 / top, 0.0, 0.0],
+                [0.0, 0.0, 2.0 / depth, (far + near) / depth],
+                [0.0, 0.0, 0.0, 1.0],
+            ]
+        }
+    }
+}
+
+/// Inverse transpose of the upper 3x3, for transforming normals under
+/// non-uniform scale. Degenerate matrices fall back to the upper 3x3.
+pub fn normal_matrix(model: &Mat4) -> Mat3 {
+    let m = [
+        [model[0][0], model[0][1], model[0][2]],
+        [model[1][0], model[1][1], model[1][2]],
+        [model[2][0], model[2][1], model[2][2]],
+    ];
+    let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let cofactors = [
+        [cofactor(1, 2, 1, 2), -cofactor(1, 2, 0, 2), cofactor(1, 2, 0, 1)],
+        [-cofactor(0, 2, 1, 2), cofactor(0, 2, 0, 2), -cofactor(0, 2, 0, 1)],
+        [cofactor(0, 1, 1, 2), -cofactor(0, 1, 0, 2), cofactor(0, 1, 0, 1)],
+    ];
+    let det = m[0][0] * cofactors[0][0] + m[0][1] * cofactors[0][1] + m[0][2] * cofactors[0][2];
+    if det.abs() <= f32::EPSILON {
+        return m;
+    }
+    // inverse = adjugate / det = cofactorsᵀ / det, so its transpose is cofactors / det
+    cofactors.map(|row| row.map(|value| value / det))
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> Float {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn normalize(v: Vec3) -> Vec3 {
+    let length = dot(v, v).sqrt();
+    if length <= f32::EPSILON {
+        return [0.0, 0.0, 0.0];
+    }
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn normalize_quat(q: Quat) -> Quat {
+    let length = (q[0] * q[0] + q[1] * q[1] + q[2] * q[2] + q[3] * q[3]).sqrt();
+    if length <= f32::EPSILON {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+    [q[0] / length, q[1] / length, q[2] / length, q[3] / length]
+}
+
+// Culling
+
+/// World-space box around a local box after `model`
+pub fn world_bounds(bounds: &BoundingBox, model: &Mat4) -> BoundingBox {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for corner in 0..8 {
+        let local = [
+            if corner & 1 == 0 { bounds.min[0] } else { bounds.max[0] },
+            if corner & 2 == 0 { bounds.min[1] } else { bounds.max[1] },
+            if corner & 4 == 0 { bounds.min[2] } else { bounds.max[2] },
+        ];
+        let world = transform_point(model, local);
+        for axis in 0..3 {
+            min[axis] = min[axis].min(world[axis]);
+            max[axis] = max[axis].max(world[axis]);
+        }
+    }
+    BoundingBox { min, max }
+}
+
+pub fn bounding_sphere(bounds: &BoundingBox) -> BoundingSphere {
+    let center = [
+        (bounds.min[0] + bounds.max[0]) * 0.5,
+        (bounds.min[1] + bounds.max[1]) * 0.5,
+        (bounds.min[2] + bounds.max[2]) * 0.5,
+    ];
+    let half = sub(bounds.max, center);
+    BoundingSphere { center, radius: dot(half, half).sqrt() }
+}
+
+/// Six inward-facing planes `[a, b, c, d]` with `a*x + b*y + c*z + d >= 0` inside
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Frustum {
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes of a view-projection matrix (Gribb/Hartmann)
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let m = view_projection;
+        let combine = |row: usize, sign: f32| -> Vec4 {
+            let plane = [
+                m[3][0] + sign * m[row][0],
+                m[3][1] + sign * m[row][1],
+                m[3][2] + sign * m[row][2],
+                m[3][3] + sign * m[row][3],
+            ];
+            let length = (plane[0] * plane[0] + plane[1] * plane[1] + plane[2] * plane[2]).sqrt();
+            if length <= f32::EPSILON {
+                plane
+            } else {
+                plane.map(|value| value / length)
+            }
+        };
+        Self {
+            planes: [
+                combine(0, 1.0),  // left
+                combine(0, -1.0), // right
+                combine(1, 1.0),  // bottom
+                combine(1, -1.0), // top
+                combine(2, 1.0),  // near
+                combine(2, -1.0), // far
+            ],
+        }
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| {
+            plane[0] * sphere.center[0] + plane[1] * sphere.center[1] + plane[2] * sphere.center[2] + plane[3]
+                >= -sphere.radius
+        })
+    }
+
+    /// Conservative: boxes near a frustum corner may pass without being visible
+    pub fn intersects_box(&self, bounds: &BoundingBox) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner furthest along the plane normal
+            let x = if plane[0] >= 0.0 { bounds.max[0] } else { bounds.min[0] };
+            let y = if plane[1] >= 0.0 { bounds.max[1] } else { bounds.min[1] };
+            let z = if plane[2] >= 0.0 { bounds.max[2] } else { bounds.min[2] };
+            plane[0] * x + plane[1] * y + plane[2] * z + plane[3] >= 0.0
+        })
+    }
+}
+
+// Lighting
+
+/// Convert a `Light` component; ambient lights return None and are summed
+/// into the pass's ambient term instead
+pub fn scene_light(light: &Light) -> Option<SceneLight> {
+    let color = light.color.map(|channel| channel * light.intensity);
+    match light.light_type {
+        LightType::Ambient => None,
+        LightType::Directional { direction } => Some(SceneLight::Directional {
+            direction: normalize(direction),
+            color,
+        }),
+        LightType::Point { position, range, attenuation } => Some(SceneLight::Point {
+            position,
+            range,
+            attenuation,
+            color,
+        }),
+        LightType::Spot { position, direction, range, inner_angle, outer_angle, attenuation } => Some(SceneLight::Spot {
+            position,
+            direction: normalize(direction),
+            range,
+            inner_angle,
+            outer_angle,
+            attenuation,
+            color,
+        }),
+    }
+}
+
+fn attenuate(attenuation: Vec3, distance: Float, range: Float) -> Float {
+    if distance > range {
+        return 0.0;
+    }
+    let falloff = attenuation[0] + attenuation[1] * distance + attenuation[2] * distance * distance;
+    if falloff <= f32::EPSILON { 1.0 } else { 1.0 / falloff }
+}
+
+fn smoothstep(edge0: Float, edge1: Float, x: Float) -> Float {
+    if edge1 <= edge0 {
+        return if x >= edge1 { 1.0 } else { 0.0 };
+    }
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Blinn-Phong shading of one surface point. `normal` must be normalized.
+/// Returns straight (non-premultiplied) RGBA.
+pub fn shade(
+    material: &MeshMaterial,
+    ambient: ColorRGB,
+    lights: &[SceneLight],
+    position: Vec3,
+    normal: Vec3,
+    eye: Vec3,
+) -> ColorRGBA {
+    let view = normalize(sub(eye, position));
+    let mut diffuse = ambient;
+    let mut specular = [0.0; 3];
+
+    for light in lights {
+        let (to_light, color, amount) = match *light {
+            SceneLight::Directional { direction, color } => ([-direction[0], -direction[1], -direction[2]], color, 1.0),
+            SceneLight::Point { position: light_position, range, attenuation, color } => {
+                let offset = sub(light_position, position);
+                let distance = dot(offset, offset).sqrt();
+                (normalize(offset), color, attenuate(attenuation, distance, range))
+            }
+            SceneLight::Spot { position: light_position, direction, range, inner_angle, outer_angle, attenuation, color } => {
+                let offset = sub(light_position, position);
+                let distance = dot(offset, offset).sqrt();
+                let to_light = normalize(offset);
+                let cos_angle = -dot(to_light, direction);
+                let cone = smoothstep(outer_angle.cos(), inner_angle.cos(), cos_angle);
+                (to_light, color, attenuate(attenuation, distance, range) * cone)
+            }
+        };
+
+        let lambert = dot(normal, to_light);
+        if lambert <= 0.0 || amount <= 0.0 {
+            continue;
+        }
+        let half = normalize([to_light[0] + view[0], to_light[1] + view[1], to_light[2] + view[2]]);
+        let highlight = material.specular * dot(normal, half).max(0.0).powf(material.shininess.max(1.0));
+        for channel in 0..3 {
+            diffuse[channel] += color[channel] * lambert * amount;
+            specular[channel] += color[channel] * highlight * amount;
+        }
+    }
+
+    let base = material.base_color;
+    [
+        (base[0] * diffuse[0] + specular[0] + material.emissive[0]).clamp(0.0, 1.0),
+        (base[1] * diffuse[1] + specular[1] + material.emissive[1]).clamp(0.0, 1.0),
+        (base[2] * diffuse[2] + specular[2] + material.emissive[2]).clamp(0.0, 1.0),
+        base[3],
+    ]
+}
+
+/// Material parameters from `Material` uniforms: `base_color` (Float3 or
+/// Float4), `emissive` (Float3), `specular` and `shininess` (Float)
+pub fn mesh_material(material: &Material) -> MeshMaterial {
+    let mut result = MeshMaterial::default();
+    match material.uniforms.get("base_color") {
+        Some(UniformValue::Float4(color)) => result.base_color = *color,
+        Some(UniformValue::Float3(color)) => result.base_color = [color[0], color[1], color[2], 1.0],
+        _ => {}
+    }
+    if let Some(UniformValue::Float3(emissive)) = material.uniforms.get("emissive") {
+        result.emissive = *emissive;
+    }
+    if let Some(UniformValue::Float(specular)) = material.uniforms.get("specular") {
+        result.specular = *specular;
+    }
+    if let Some(UniformValue::Float(shininess)) = material.uniforms.get("shininess") {
+        result.shininess = *shininess;
+    }
+    result
+}
+
+// Extraction
+
+/// What a camera sees from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SceneView {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub position: Vec3,
+}
+
+impl SceneView {
+    pub fn from_camera(camera: &Camera3D, transform: &Transform3D) -> Self {
+        Self {
+            view: view_matrix(transform),
+            projection: projection_matrix(camera),
+            position: transform.position,
+        }
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        mat4_multiply(&self.projection, &self.view)
+    }
+}
+
+/// A mesh to draw
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshInstance {
+    pub vertex_buffer: ResourceId,
+    pub index_buffer: Option<ResourceId>,
+    pub vertex_count: UInt,
+    pub index_count: UInt,
+    pub model: Mat4,
+    /// Local-space bounds
+    pub bounds: BoundingBox,
+    pub material: MeshMaterial,
+    pub sort_order: Int,
+}
+
+/// One camera's forward pass
+#[derive(Debug, Clone)]
+pub struct ScenePass {
+    view: SceneView,
+    frustum: Frustum,
+    ambient: ColorRGB,
+    lights: Vec<SceneLight>,
+    /// Visible meshes with their view-space depth
+    meshes: Vec<(MeshInstance, Float)>,
+    /// Meshes rejected by frustum culling so far
+    pub culled: usize,
+}
+
+impl ScenePass {
+    pub fn new(view: SceneView) -> Self {
+        Self {
+            frustum: Frustum::from_matrix(&view.view_projection()),
+            view,
+            ambient: [0.0, 0.0, 0.0],
+            lights: Vec::new(),
+            meshes: Vec::new(),
+            culled: 0,
+        }
+    }
+
+    pub fn add_light(&mut self, light: &Light) {
+        match scene_light(light) {
+            Some(light) => self.lights.push(light),
+            None => {
+                for channel in 0..3 {
+                    self.ambient[channel] += light.color[channel] * light.intensity;
+                }
+            }
+        }
+    }
+
+    /// Queue a mesh, returning false if it was culled
+    pub fn add_mesh(&mut self, mesh: MeshInstance) -> bool {
+        let bounds = world_bounds(&mesh.bounds, &mesh.model);
+        // The sphere test is cheaper and rejects most meshes; the box test is tighter
+        if !self.frustum.intersects_sphere(&bounding_sphere(&bounds)) || !self.frustum.intersects_box(&bounds) {
+            self.culled += 1;
+            return false;
+        }
+        let center = bounding_sphere(&bounds).center;
+        let depth = -transform_point(&self.view.view, center)[2];
+        self.meshes.push((mesh, depth));
+        true
+    }
+
+    pub fn visible(&self) -> usize {
+        self.meshes.len()
+    }
+
+    /// Commands for the pass. Opaque meshes draw front to back to save
+    /// shading; translucent ones draw back to front after them.
+    pub fn build(self, clear_color: Option<ColorRGBA>) -> Vec<RenderCommand> {
+        let (mut opaque, mut translucent): (Vec<_>, Vec<_>) = self.meshes.into_iter()
+            .partition(|(mesh, _)| mesh.material.base_color[3] >= 1.0);
+        opaque.sort_by(|(a, a_depth), (b, b_depth)| {
+            a.sort_order.cmp(&b.sort_order).then(a_depth.total_cmp(b_depth))
+        });
+        translucent.sort_by(|(a, a_depth), (b, b_depth)| {
+            a.sort_order.cmp(&b.sort_order).then(b_depth.total_cmp(a_depth))
+        });
+
+        let mut commands = vec![
+            RenderCommand::Clear { color: clear_color, depth: Some(1.0), stencil: None },
+            RenderCommand::SetCamera3D {
+                view: self.view.view,
+                projection: self.view.projection,
+                position: self.view.position,
+            },
+            RenderCommand::SetLights { ambient: self.ambient, lights: self.lights },
+        ];
+        commands.extend(opaque.into_iter().chain(translucent).map(|(mesh, _)| RenderCommand::DrawMesh {
+            vertex_buffer: mesh.vertex_buffer,
+            index_buffer: mesh.index_buffer,
+            vertex_count: mesh.vertex_count,
+            index_count: mesh.index_count,
+            model: mesh.model,
+            material: mesh.material,
+        }));
+        commands
+    }
+}
+
+/// Build a forward pass for a 3D camera
+///
+/// `camera` needs `Camera3D` and `Transform3D`; its `Camera` component, if
+/// any, supplies the clear color, viewport and layer mask. Each mesh entity
+/// needs `MeshRenderer` and `Transform3D`; `Visibility` and `RenderLayer` are
+/// honored when present. Meshes outside the view frustum are culled. Light
+/// entities need a `Light`. Returns the commands for `submit_frame`.
+pub async fn extract_scene_3d(camera: &Entity, meshes: &[Entity], lights: &[Entity]) -> CoreResult<Vec<RenderCommand>> {
+    let projection = camera.get_component::<Camera3D>().await?;
+    let camera_transform = camera.get_component::<Transform3D>().await?;
+    let settings = camera.get_component::<Camera>().await.unwrap_or_default();
+    if !settings.active {
+        return Ok(Vec::new());
+    }
+
+    let mut pass = ScenePass::new(SceneView::from_camera(&projection, &camera_transform));
+
+    for light_entity in lights {
+        pass.add_light(&light_entity.get_component::<Light>().await?);
+    }
+
+    for mesh_entity in meshes {
+        if let Ok(visibility) = mesh_entity.get_component::<Visibility>().await
+            && (!visibility.visible || !visibility.visible_in_hierarchy)
+        {
+            continue;
+        }
+        let layer = match mesh_entity.get_component::<RenderLayer>().await {
+            Ok(layer) => layer.layer,
+            Err(_) => RenderLayer::default().layer,
+        };
+        if settings.render_layers & 1u32.checked_shl(layer).unwrap_or(0) == 0 {
+            continue;
+        }
+
+        let renderer = mesh_entity.get_component::<MeshRenderer>().await?;
+        let transform = mesh_entity.get_component::<Transform3D>().await?;
+        let mesh = renderer.mesh.upgrade().ok_or(CoreError::InvalidEntity)?
+            .get_component::<Mesh>().await?;
+        let material = match renderer.material.upgrade() {
+            Some(material) => match material.get_component::<Material>().await {
+                Ok(material) => mesh_material(&material),
+                Err(_) => Default::default(),
+            },
+            None => Default::default(),
+        };
+
+        pass.add_mesh(MeshInstance {
+            vertex_buffer: mesh.gpu_resource_id,
+            index_buffer: mesh.index_buffer,
+            vertex_count: mesh.vertex_count,
+            index_count: mesh.index_count,
+            model: model_matrix(&transform),
+            bounds: mesh.bounds,
+            material,
+            sort_order: renderer.sort_order,
+        });
+    }
+
+    let mut commands = Vec::new();
+    if let Some(viewport) = settings.viewport {
+        commands.push(RenderCommand::SetViewport { viewport });
+    }
+    commands.extend(pass.build(settings.clear_color));
+    Ok(commands)
+}
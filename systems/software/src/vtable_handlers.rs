@@ -0,0 +1,207 @@
+//! VTable handlers for the software renderer
+//!
+//! Payloads are bincode. Operations mirror core/rendering's API functions,
+//! plus a few headless-only ones for loading textures and reading back pixels.
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use serde::{Serialize, de::DeserializeOwned};
+use playground_core_types::{Shared, shared};
+use playground_core_ecs::VTableResponse;
+use playground_core_rendering::{RenderCommand, RendererConfig, ResourceId, RenderGraph, CompiledRenderGraph};
+use playground_core_rendering::resources::{BufferInfo, TextureInfo};
+use crate::framegraph;
+use crate::renderer::{SoftwareRenderer, DEFAULT_WIDTH, DEFAULT_HEIGHT};
+
+/// The renderer instance behind the VTable channel
+pub static SOFTWARE_RENDERER: Lazy<Shared<SoftwareRenderer>> =
+    Lazy::new(|| shared(SoftwareRenderer::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)));
+
+fn error_response(msg: String) -> VTableResponse {
+    VTableResponse {
+        success: false,
+        payload: None,
+        error: Some(msg),
+    }
+}
+
+fn success_response(payload: Option<Bytes>) -> VTableResponse {
+    VTableResponse {
+        success: true,
+        payload,
+        error: None,
+    }
+}
+
+fn decode<T: DeserializeOwned>(payload: &Bytes) -> Result<T, VTableResponse> {
+    bincode::deserialize(payload)
+        .map_err(|e| error_response(format!("Failed to deserialize payload: {}", e)))
+}
+
+fn encode<T: Serialize>(value: &T) -> VTableResponse {
+    match bincode::serialize(value) {
+        Ok(bytes) => success_response(Some(Bytes::from(bytes))),
+        Err(e) => error_response(format!("Failed to serialize response: {}", e)),
+    }
+}
+
+/// Handle renderer operations
+pub async fn handle_renderer_operations(operation: String, payload: Bytes) -> VTableResponse {
+    match operation.as_str() {
+        "initialize" => {
+            let config: RendererConfig = match decode(&payload) {
+                Ok(config) => config,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.initialize(config) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "shutdown" => {
+            match SOFTWARE_RENDERER.write().await.shutdown() {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "get_capabilities" => encode(&SOFTWARE_RENDERER.read().await.capabilities()),
+        "get_stats" => encode(&SOFTWARE_RENDERER.read().await.stats()),
+        "submit_frame" => {
+            let commands: Vec<RenderCommand> = match decode(&payload) {
+                Ok(commands) => commands,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.submit_frame(&commands) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "present" => {
+            match SOFTWARE_RENDERER.write().await.present() {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "resize" => {
+            let (width, height): (u32, u32) = match decode(&payload) {
+                Ok(size) => size,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.resize(width, height) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "create_texture" => {
+            let info: TextureInfo = match decode(&payload) {
+                Ok(info) => info,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.create_texture(&info) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "update_texture" => {
+            let (id, pixels): (ResourceId, Vec<u8>) = match decode(&payload) {
+                Ok(update) => update,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.update_texture(id, pixels) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "load_texture" => {
+            let (id, width, height, pixels): (ResourceId, u32, u32, Vec<u8>) = match decode(&payload) {
+                Ok(texture) => texture,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.load_texture(id, width, height, pixels) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "unload_texture" | "destroy_texture" => {
+            let id: ResourceId = match decode(&payload) {
+                Ok(id) => id,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.unload_texture(id) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "create_buffer" => {
+            let info: BufferInfo = match decode(&payload) {
+                Ok(info) => info,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.create_buffer(&info) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "update_buffer" => {
+            let (id, data): (ResourceId, Vec<u8>) = match decode(&payload) {
+                Ok(update) => update,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.write().await.update_buffer(id, &data) {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "destroy_buffer" => {
+            let id: ResourceId = match decode(&payload) {
+                Ok(id) => id,
+                Err(response) => return response,
+            };
+            SOFTWARE_RENDERER.write().await.destroy_buffer(id);
+            success_response(None)
+        }
+        "read_pixels" => {
+            let renderer = SOFTWARE_RENDERER.read().await;
+            let framebuffer = renderer.framebuffer();
+            encode(&(framebuffer.width(), framebuffer.height(), framebuffer.pixels().to_vec()))
+        }
+        "encode_png" => encode(&SOFTWARE_RENDERER.read().await.framebuffer().to_png()),
+        "save_png" => {
+            let path: String = match decode(&payload) {
+                Ok(path) => path,
+                Err(response) => return response,
+            };
+            match SOFTWARE_RENDERER.read().await.save_png(path.as_ref()).await {
+                Ok(()) => success_response(None),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "compile_render_graph" => {
+            let graph: RenderGraph = match decode(&payload) {
+                Ok(graph) => graph,
+                Err(response) => return response,
+            };
+            match framegraph::compile(&graph) {
+                Ok(compiled) => encode(&compiled),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        "render_graph_commands" => {
+            let (graph, compiled, ids): (RenderGraph, CompiledRenderGraph, Vec<ResourceId>) = match decode(&payload) {
+                Ok(bound) => bound,
+                Err(response) => return response,
+            };
+            match framegraph::commands(&graph, &compiled, &ids) {
+                Ok(commands) => encode(&commands),
+                Err(e) => error_response(e.to_string()),
+            }
+        }
+        // GPU-only resources: core/rendering tracks them, the rasterizer has nothing to allocate
+        "compile_shader" | "destroy_shader"
+        | "create_pipeline" | "destroy_pipeline" | "bind_pipeline"
+        | "create_command_buffer" | "begin_recording" | "end_recording" | "submit_commands" => {
+            success_response(None)
+        }
+        _ => error_response(format!("Unknown renderer operation: {}", operation)),
+    }
+}
@@ -0,0 +1,213 @@
+//! Render graph compilation: pass ordering, culling, transient target
+//! aliasing and the emitted command stream.
+
+use playground_core_types::CoreError;
+use playground_core_rendering::{
+    GraphPass, RenderCommand, RenderGraph, TargetBinding, TargetHandle,
+};
+use playground_core_rendering::resources::{
+    Attachment, LoadOp, RenderPassInfo, RenderTargetInfo, StoreOp,
+};
+use playground_systems_software::framegraph;
+
+const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+fn target(width: u32, height: u32) -> RenderTargetInfo {
+    RenderTargetInfo {
+        width,
+        height,
+        has_depth: false,
+        is_default: false,
+        ..Default::default()
+    }
+}
+
+fn attachment(load_op: LoadOp, store_op: StoreOp) -> Attachment {
+    Attachment {
+        format: "RGBA8".to_string(),
+        samples: 1,
+        load_op,
+        store_op,
+        initial_layout: "undefined".to_string(),
+        final_layout: "shader_read".to_string(),
+    }
+}
+
+/// Pass that clears and stores each target it writes
+fn pass(name: &str, outputs: &[TargetHandle], inputs: &[TargetHandle]) -> GraphPass {
+    pass_with(name, outputs, inputs, LoadOp::Clear)
+}
+
+fn pass_with(name: &str, outputs: &[TargetHandle], inputs: &[TargetHandle], load_op: LoadOp) -> GraphPass {
+    let info = RenderPassInfo {
+        id: 0,
+        attachments: outputs.iter().map(|_| attachment(load_op, StoreOp::Store)).collect(),
+        subpasses: Vec::new(),
+    };
+    let mut pass = GraphPass::new(name, info);
+    for &output in outputs {
+        pass = pass.write(output);
+    }
+    for &input in inputs {
+        pass = pass.read(input);
+    }
+    pass
+}
+
+fn names(compiled: &playground_core_rendering::CompiledRenderGraph) -> Vec<&str> {
+    compiled.passes.iter().map(|pass| pass.name.as_str()).collect()
+}
+
+fn describe(command: &RenderCommand) -> String {
+    match command {
+        RenderCommand::SetRenderTarget { target } => format!("target {:?}", target),
+        RenderCommand::SetViewport { viewport } => format!("viewport {}x{}", viewport.width, viewport.height),
+        RenderCommand::Clear { color, depth, .. } => format!("clear {:?} {:?}", color.is_some(), depth),
+        RenderCommand::DrawQuad { position, .. } => format!("quad {}", position[0]),
+        other => format!("{:?}", other),
+    }
+}
+
+#[test]
+fn readers_run_after_writers() {
+    let mut graph = RenderGraph::new();
+    let scene = graph.create_target("scene", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("composite", &[screen], &[scene]));
+    graph.add_pass(pass("scene", &[scene], &[]));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    assert_eq!(names(&compiled), ["scene", "composite"]);
+    assert_eq!(compiled.passes[0].index, 1);
+    assert_eq!(compiled.passes[1].index, 0);
+}
+
+#[test]
+fn writers_of_one_target_keep_registration_order() {
+    let mut graph = RenderGraph::new();
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("background", &[screen], &[]));
+    graph.add_pass(pass_with("overlay", &[screen], &[], LoadOp::Load));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    assert_eq!(names(&compiled), ["background", "overlay"]);
+}
+
+#[test]
+fn passes_that_reach_no_import_are_culled() {
+    let mut graph = RenderGraph::new();
+    let unused = graph.create_target("unused", target(64, 64));
+    let debug = graph.create_target("debug", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("dead", &[unused], &[]));
+    graph.add_pass(pass("capture", &[debug], &[]).with_side_effects());
+    graph.add_pass(pass("main", &[screen], &[]));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    assert_eq!(names(&compiled), ["capture", "main"]);
+    assert_eq!(compiled.culled, ["dead"]);
+    assert!(compiled.bindings[unused.0].is_none());
+}
+
+#[test]
+fn transient_targets_alias_when_lifetimes_dont_overlap() {
+    let mut graph = RenderGraph::new();
+    let a = graph.create_target("a", target(64, 64));
+    let b = graph.create_target("b", target(64, 64));
+    let c = graph.create_target("c", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("first", &[a], &[]));
+    graph.add_pass(pass("second", &[b], &[a]));
+    graph.add_pass(pass("third", &[c], &[b]));
+    graph.add_pass(pass("present", &[screen], &[c]));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    // `a` is dead once `second` ran, so `c` reuses its storage; `b` overlaps both
+    assert_eq!(compiled.physical_targets.len(), 2);
+    assert_eq!(compiled.bindings[a.0], Some(TargetBinding::Physical(0)));
+    assert_eq!(compiled.bindings[b.0], Some(TargetBinding::Physical(1)));
+    assert_eq!(compiled.bindings[c.0], Some(TargetBinding::Physical(0)));
+    assert_eq!(compiled.bindings[screen.0], Some(TargetBinding::Imported(None)));
+    assert!(compiled.physical_targets.iter().all(|info| info.id == 0 && !info.is_default));
+}
+
+#[test]
+fn targets_of_different_shape_never_alias() {
+    let mut graph = RenderGraph::new();
+    let small = graph.create_target("small", target(32, 32));
+    let tiny = graph.create_target("tiny", target(16, 16));
+    let large = graph.create_target("large", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("small", &[small], &[]));
+    graph.add_pass(pass("tiny", &[tiny], &[small]));
+    graph.add_pass(pass("large", &[large], &[tiny]));
+    graph.add_pass(pass("present", &[screen], &[large]));
+
+    // `small` is free by the time `large` is written, but the shapes differ
+    let compiled = framegraph::compile(&graph).unwrap();
+    let sizes: Vec<(u32, u32)> = compiled.physical_targets.iter().map(|info| (info.width, info.height)).collect();
+    assert_eq!(sizes, [(32, 32), (16, 16), (64, 64)]);
+}
+
+#[test]
+fn dependency_cycles_are_rejected() {
+    let mut graph = RenderGraph::new();
+    let x = graph.create_target("x", target(64, 64));
+    let y = graph.create_target("y", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("a", &[x], &[y]));
+    graph.add_pass(pass("b", &[y, screen], &[x]));
+
+    match framegraph::compile(&graph) {
+        Err(CoreError::InvalidState(message)) => assert!(message.contains("a, b"), "{}", message),
+        other => panic!("expected a cycle error, got {:?}", other.map(|compiled| compiled.passes.len())),
+    }
+}
+
+#[test]
+fn loading_an_unwritten_transient_is_rejected() {
+    let mut graph = RenderGraph::new();
+    let scene = graph.create_target("scene", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass_with("scene", &[scene], &[], LoadOp::Load));
+    graph.add_pass(pass("present", &[screen], &[scene]));
+
+    assert!(matches!(framegraph::compile(&graph), Err(CoreError::InvalidInput(_))));
+}
+
+#[test]
+fn commands_bind_physical_targets_and_clear_once() {
+    let mut graph = RenderGraph::new();
+    let scene = graph.create_target("scene", target(64, 32));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("present", &[screen], &[scene])
+        .clear(WHITE, 1.0)
+        .commands(vec![RenderCommand::DrawQuad { position: [2.0, 0.0], size: [1.0, 1.0], color: WHITE }]));
+    graph.add_pass(pass("scene", &[scene], &[])
+        .commands(vec![RenderCommand::DrawQuad { position: [1.0, 0.0], size: [1.0, 1.0], color: WHITE }]));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    let commands = framegraph::commands(&graph, &compiled, &[42]).unwrap();
+    let described: Vec<String> = commands.iter().map(describe).collect();
+    assert_eq!(described, [
+        "target Some(42)",
+        "viewport 64x32",
+        "clear true None",
+        "quad 1",
+        "target None",
+        "clear true None",
+        "quad 2",
+    ]);
+}
+
+#[test]
+fn commands_need_an_id_per_physical_target() {
+    let mut graph = RenderGraph::new();
+    let scene = graph.create_target("scene", target(64, 64));
+    let screen = graph.import_target("screen", None);
+    graph.add_pass(pass("scene", &[scene], &[]));
+    graph.add_pass(pass("present", &[screen], &[scene]));
+
+    let compiled = framegraph::compile(&graph).unwrap();
+    assert!(matches!(framegraph::commands(&graph, &compiled, &[]), Err(CoreError::InvalidInput(_))));
+}
@@ -0,0 +1,141 @@
+//! Golden-image tests: a fixed command list is rasterized and the framebuffer
+//! compared pixel by pixel against a checked-in expected image.
+//!
+//! Expected images live in `tests/golden/*.txt` as a palette followed by one
+//! character per pixel, so diffs stay reviewable. Run with `UPDATE_GOLDEN=1`
+//! to rewrite a fixture from the current output after an intended change.
+
+use std::path::PathBuf;
+use playground_core_rendering::{RenderCommand, RendererConfig, ResourceId};
+use playground_systems_software::SoftwareRenderer;
+
+const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+const CYAN: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+
+const CHECKER: ResourceId = 1;
+
+struct Golden {
+    palette: Vec<(char, [u8; 4])>,
+    rows: Vec<String>,
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.txt", name))
+}
+
+fn load_golden(name: &str) -> Golden {
+    let text = std::fs::read_to_string(golden_path(name)).expect("golden fixture is missing");
+    let (header, body) = text.split_once("---\n").expect("golden fixture has no `---` separator");
+
+    let palette = header.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let symbol = parts.next().and_then(|s| s.chars().next()).expect("palette entry without a symbol");
+            let mut rgba = [0u8; 4];
+            for channel in &mut rgba {
+                *channel = parts.next().and_then(|v| v.parse().ok()).expect("palette entry needs 4 channels");
+            }
+            (symbol, rgba)
+        })
+        .collect();
+
+    Golden {
+        palette,
+        rows: body.lines().map(str::to_string).collect(),
+    }
+}
+
+/// Map every pixel to its palette symbol, `?` for colors outside the palette
+fn render_rows(renderer: &SoftwareRenderer, palette: &[(char, [u8; 4])]) -> Vec<String> {
+    let framebuffer = renderer.framebuffer();
+    (0..framebuffer.height())
+        .map(|y| {
+            (0..framebuffer.width())
+                .map(|x| {
+                    let pixel = framebuffer.pixel(x, y).unwrap();
+                    palette.iter()
+                        .find(|(_, rgba)| *rgba == pixel)
+                        .map(|(symbol, _)| *symbol)
+                        .unwrap_or('?')
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn assert_matches_golden(renderer: &SoftwareRenderer, name: &str) {
+    let golden = load_golden(name);
+    let actual = render_rows(renderer, &golden.palette);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let text = std::fs::read_to_string(golden_path(name)).unwrap();
+        let header = text.split_once("---\n").unwrap().0;
+        std::fs::write(golden_path(name), format!("{}---\n{}\n", header, actual.join("\n"))).unwrap();
+        return;
+    }
+
+    assert_eq!(
+        actual, golden.rows,
+        "\nframebuffer differs from tests/golden/{}.txt\nexpected:\n{}\nactual:\n{}\n",
+        name, golden.rows.join("\n"), actual.join("\n"),
+    );
+}
+
+fn renderer(width: u32, height: u32) -> SoftwareRenderer {
+    let mut renderer = SoftwareRenderer::new(width, height);
+    renderer.initialize(RendererConfig::default()).unwrap();
+    renderer
+}
+
+#[test]
+fn primitives() {
+    let mut renderer = renderer(16, 12);
+
+    // 2x2 texture: white, yellow / cyan, magenta
+    renderer.load_texture(CHECKER, 2, 2, vec![
+        255, 255, 255, 255, 255, 255, 0, 255,
+        0, 255, 255, 255, 255, 0, 255, 255,
+    ]).unwrap();
+
+    renderer.submit_frame(&[
+        RenderCommand::Clear { color: Some(BLACK), depth: None, stencil: None },
+        RenderCommand::DrawQuad { position: [2.0, 1.0], size: [5.0, 3.0], color: RED },
+        RenderCommand::DrawImage {
+            texture_id: CHECKER,
+            position: [9.0, 1.0],
+            size: [4.0, 4.0],
+            uv_min: [0.0, 0.0],
+            uv_max: [1.0, 1.0],
+        },
+        RenderCommand::DrawLine { start: [0.0, 6.0], end: [16.0, 6.0], width: 2.0, color: GREEN },
+        // Only the part inside the clip rectangle is drawn
+        RenderCommand::SetClipRect { position: [0.0, 8.0], size: [6.0, 4.0] },
+        RenderCommand::DrawQuad { position: [2.0, 7.0], size: [8.0, 5.0], color: BLUE },
+        RenderCommand::ClearClipRect,
+        RenderCommand::DrawQuad { position: [12.0, 9.0], size: [3.0, 2.0], color: CYAN },
+        RenderCommand::Present,
+    ]).unwrap();
+
+    assert_matches_golden(&renderer, "primitives");
+}
+
+#[test]
+fn primitives_are_deterministic_as_png() {
+    let draw = || {
+        let mut renderer = renderer(16, 12);
+        renderer.submit_frame(&[
+            RenderCommand::Clear { color: Some(BLACK), depth: None, stencil: None },
+            RenderCommand::DrawLine { start: [0.0, 0.0], end: [16.0, 12.0], width: 1.5, color: GREEN },
+            RenderCommand::Present,
+        ]).unwrap();
+        renderer.framebuffer().to_png()
+    };
+
+    let png = draw();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(png, draw());
+}
@@ -0,0 +1,156 @@
+//! 3D scene extraction rendered through the software rasterizer: a lit cube
+//! in front of the camera and a culled one behind it.
+
+use playground_core_rendering::{
+    BoundingBox, Camera3D, Light, LightType, MeshMaterial, ProjectionType, RenderCommand,
+    RendererConfig, ResourceId, Transform3D, Vec3, MESH_VERTEX_STRIDE,
+};
+use playground_core_rendering::resources::{BufferInfo, BufferUsage};
+use playground_systems_software::SoftwareRenderer;
+use playground_systems_software::scene3d::{model_matrix, MeshInstance, ScenePass, SceneView};
+
+const SIZE: u32 = 32;
+const CUBE: ResourceId = 1;
+const CUBE_VERTICES: u32 = 36;
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+/// Unit cube from -1 to 1 as 12 counter-clockwise triangles with flat normals
+fn cube_vertices() -> Vec<u8> {
+    let faces: [(Vec3, Vec3); 6] = [
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0]),
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0]),
+    ];
+
+    let mut bytes = Vec::with_capacity(CUBE_VERTICES as usize * MESH_VERTEX_STRIDE);
+    for (normal, u) in faces {
+        // u x v = normal, so (-u-v, u-v, u+v) winds counter-clockwise seen from outside
+        let v = cross(normal, u);
+        let corner = |su: f32, sv: f32| -> Vec3 {
+            [0, 1, 2].map(|axis| normal[axis] + u[axis] * su + v[axis] * sv)
+        };
+        let quad = [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)];
+        for index in [0, 1, 2, 0, 2, 3] {
+            for value in quad[index].iter().chain(&normal).chain(&[0.0, 0.0]) {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+fn cube_at(position: Vec3) -> MeshInstance {
+    MeshInstance {
+        vertex_buffer: CUBE,
+        index_buffer: None,
+        vertex_count: CUBE_VERTICES,
+        index_count: 0,
+        model: model_matrix(&Transform3D { position, ..Default::default() }),
+        bounds: BoundingBox { min: [-1.0, -1.0, -1.0], max: [1.0, 1.0, 1.0] },
+        material: MeshMaterial {
+            base_color: [1.0, 0.0, 0.0, 1.0],
+            emissive: [0.0, 0.0, 0.0],
+            specular: 0.0,
+            shininess: 1.0,
+        },
+        sort_order: 0,
+    }
+}
+
+fn light(light_type: LightType, intensity: f32) -> Light {
+    Light {
+        light_type,
+        color: [1.0, 1.0, 1.0],
+        intensity,
+        cast_shadows: false,
+        shadow_strength: 0.0,
+    }
+}
+
+/// Camera at the origin looking down -Z with a 90 degree field of view
+fn scene_pass() -> ScenePass {
+    let camera = Camera3D {
+        projection: ProjectionType::Perspective,
+        fov: std::f32::consts::FRAC_PI_2,
+        ortho_size: 1.0,
+        aspect: 1.0,
+        near: 0.1,
+        far: 100.0,
+    };
+    let mut pass = ScenePass::new(SceneView::from_camera(&camera, &Transform3D::default()));
+    pass.add_light(&light(LightType::Ambient, 0.2));
+    // Hits the camera-facing side at 0.6: the light comes from above and in front
+    pass.add_light(&light(LightType::Directional { direction: [0.0, -0.8, -0.6] }, 1.0));
+    pass
+}
+
+fn render(commands: &[RenderCommand]) -> SoftwareRenderer {
+    let mut renderer = SoftwareRenderer::new(SIZE, SIZE);
+    renderer.initialize(RendererConfig::default()).unwrap();
+
+    let vertices = cube_vertices();
+    renderer.create_buffer(&BufferInfo {
+        id: CUBE,
+        size: vertices.len(),
+        usage: BufferUsage { vertex: true, ..Default::default() },
+        mapped_at_creation: false,
+    }).unwrap();
+    renderer.update_buffer(CUBE, &vertices).unwrap();
+
+    renderer.submit_frame(commands).unwrap();
+    renderer.submit_frame(&[RenderCommand::Present]).unwrap();
+    renderer
+}
+
+fn mesh_draws(commands: &[RenderCommand]) -> usize {
+    commands.iter().filter(|command| matches!(command, RenderCommand::DrawMesh { .. })).count()
+}
+
+#[test]
+fn lit_cube_front_face() {
+    let mut pass = scene_pass();
+    assert!(pass.add_mesh(cube_at([0.0, 0.0, -5.0])));
+    let commands = pass.build(Some(BLUE));
+    assert_eq!(mesh_draws(&commands), 1);
+
+    let renderer = render(&commands);
+    let framebuffer = renderer.framebuffer();
+
+    // The front face at z = -4 spans NDC [-0.25, 0.25], pixels 12..20.
+    // Red base color lit by 0.2 ambient plus 0.6 directional.
+    for (x, y) in [(16, 16), (13, 13), (18, 13), (13, 18), (18, 18)] {
+        assert_eq!(framebuffer.pixel(x, y), Some([204, 0, 0, 255]), "pixel ({}, {})", x, y);
+    }
+    // Outside the cube only the clear color remains
+    for (x, y) in [(0, 0), (8, 16), (24, 16), (16, 8), (16, 24)] {
+        assert_eq!(framebuffer.pixel(x, y), Some([0, 0, 255, 255]), "pixel ({}, {})", x, y);
+    }
+}
+
+#[test]
+fn culled_cube_adds_no_draw() {
+    let mut pass = scene_pass();
+    assert!(pass.add_mesh(cube_at([0.0, 0.0, -5.0])));
+    // Behind the camera
+    assert!(!pass.add_mesh(cube_at([0.0, 0.0, 5.0])));
+    // Far off to the side
+    assert!(!pass.add_mesh(cube_at([50.0, 0.0, -5.0])));
+    assert_eq!(pass.culled, 2);
+    assert_eq!(pass.visible(), 1);
+
+    let commands = pass.build(Some(BLUE));
+    assert_eq!(mesh_draws(&commands), 1);
+
+    // The frame matches one with only the visible cube
+    let mut alone = scene_pass();
+    alone.add_mesh(cube_at([0.0, 0.0, -5.0]));
+    let expected = render(&alone.build(Some(BLUE)));
+    assert_eq!(render(&commands).framebuffer().pixels(), expected.framebuffer().pixels());
+}
@@ -0,0 +1,152 @@
+//! Android accessibility export
+//!
+//! The tree flattened into the shape of `AccessibilityNodeInfo`, for an
+//! `AccessibilityNodeProvider` that exposes UI elements as virtual views of
+//! the host view. Virtual view ids are assigned parents first, starting at
+//! 0; roots have the host (`HOST_VIEW_ID`) as parent.
+
+use serde::{Serialize, Deserialize};
+use playground_core_ui::{AccessibilityAction, SwipeDirection};
+use crate::components::AccessibilityRole;
+use crate::element::ElementId;
+use super::tree::{AccessibilityNode, AccessibilityTree};
+
+/// `View.NO_ID`, the id Android gives the host view itself
+pub const HOST_VIEW_ID: i32 = -1;
+
+// `AccessibilityNodeInfo` action constants
+pub const ACTION_FOCUS: i32 = 0x1;
+pub const ACTION_CLEAR_FOCUS: i32 = 0x2;
+pub const ACTION_CLICK: i32 = 0x10;
+pub const ACTION_LONG_CLICK: i32 = 0x20;
+pub const ACTION_SCROLL_FORWARD: i32 = 0x1000;
+pub const ACTION_SCROLL_BACKWARD: i32 = 0x2000;
+pub const ACTION_DISMISS: i32 = 0x100000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AndroidNodeInfo {
+    pub virtual_view_id: i32,
+    pub parent_id: i32,
+    pub element: ElementId,
+    pub class_name: String,
+    pub text: Option<String>,
+    pub content_description: Option<String>,
+    pub hint_text: Option<String>,
+    /// left, top, right, bottom in screen pixels
+    pub bounds: [i32; 4],
+    pub enabled: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub clickable: bool,
+    pub long_clickable: bool,
+    pub scrollable: bool,
+    pub checkable: bool,
+    pub checked: bool,
+    pub selected: bool,
+    pub editable: bool,
+    pub heading: bool,
+    pub actions: Vec<i32>,
+    pub children: Vec<i32>,
+}
+
+pub fn to_android(tree: &AccessibilityTree) -> Vec<AndroidNodeInfo> {
+    let mut nodes = Vec::new();
+    for root in &tree.roots {
+        flatten(root, HOST_VIEW_ID, &mut nodes);
+    }
+    nodes
+}
+
+fn flatten(node: &AccessibilityNode, parent_id: i32, nodes: &mut Vec<AndroidNodeInfo>) -> i32 {
+    let id = nodes.len() as i32;
+    nodes.push(node_info(node, id, parent_id));
+    let children = node.children.iter()
+        .map(|child| flatten(child, id, nodes))
+        .collect();
+    nodes[id as usize].children = children;
+    id
+}
+
+fn node_info(node: &AccessibilityNode, virtual_view_id: i32, parent_id: i32) -> AndroidNodeInfo {
+    let class_name = match node.role {
+        AccessibilityRole::Button => "android.widget.Button",
+        AccessibilityRole::Text | AccessibilityRole::Heading(_) => "android.widget.TextView",
+        AccessibilityRole::TextInput | AccessibilityRole::Terminal => "android.widget.EditText",
+        AccessibilityRole::Image => "android.widget.ImageView",
+        AccessibilityRole::List => "android.widget.ListView",
+        AccessibilityRole::Checkbox => "android.widget.CheckBox",
+        AccessibilityRole::Switch => "android.widget.Switch",
+        AccessibilityRole::Slider => "android.widget.SeekBar",
+        AccessibilityRole::TabList => "android.widget.TabWidget",
+        _ => "android.view.View",
+    };
+    let editable = matches!(node.role, AccessibilityRole::TextInput | AccessibilityRole::Terminal);
+    let state = &node.state;
+
+    // Editable text is the field's content; elsewhere it's what gets read
+    let (text, content_description) = if editable {
+        (node.value.clone(), node.label.clone())
+    } else {
+        (node.label.clone(), None)
+    };
+
+    let mut actions = Vec::new();
+    if state.focusable {
+        actions.push(if state.focused { ACTION_CLEAR_FOCUS } else { ACTION_FOCUS });
+    }
+    if state.clickable && !state.disabled {
+        actions.push(ACTION_CLICK);
+        actions.push(ACTION_LONG_CLICK);
+    }
+    if state.scrollable {
+        actions.push(ACTION_SCROLL_FORWARD);
+        actions.push(ACTION_SCROLL_BACKWARD);
+    }
+    if node.role == AccessibilityRole::Dialog {
+        actions.push(ACTION_DISMISS);
+    }
+
+    let bounds = node.bounds;
+    AndroidNodeInfo {
+        virtual_view_id,
+        parent_id,
+        element: node.element,
+        class_name: class_name.to_string(),
+        text,
+        content_description,
+        hint_text: node.hint.clone(),
+        bounds: [
+            bounds.x.round() as i32,
+            bounds.y.round() as i32,
+            (bounds.x + bounds.width).round() as i32,
+            (bounds.y + bounds.height).round() as i32,
+        ],
+        enabled: !state.disabled,
+        focusable: state.focusable,
+        focused: state.focused,
+        clickable: state.clickable,
+        long_clickable: state.clickable,
+        scrollable: state.scrollable,
+        checkable: state.checked.is_some(),
+        checked: state.checked.unwrap_or(false),
+        selected: state.selected,
+        editable,
+        heading: matches!(node.role, AccessibilityRole::Heading(_)),
+        actions,
+        children: Vec::new(),
+    }
+}
+
+/// The UI action for an `AccessibilityNodeInfo` action constant
+pub fn action_from_android(action: i32) -> Option<AccessibilityAction> {
+    match action {
+        ACTION_CLICK => Some(AccessibilityAction::Tap),
+        ACTION_LONG_CLICK => Some(AccessibilityAction::LongPress),
+        ACTION_FOCUS => Some(AccessibilityAction::Focus),
+        ACTION_CLEAR_FOCUS | ACTION_DISMISS => Some(AccessibilityAction::Escape),
+        // Forward reveals what's further down, as a swipe up would
+        ACTION_SCROLL_FORWARD => Some(AccessibilityAction::Scroll(SwipeDirection::Up)),
+        ACTION_SCROLL_BACKWARD => Some(AccessibilityAction::Scroll(SwipeDirection::Down)),
+        _ => None,
+    }
+}
@@ -0,0 +1,113 @@
+//! ARIA export for the browser client
+//!
+//! The client mirrors the tree as transparent DOM elements laid over the
+//! canvas, so screen readers can browse and activate them. Each node says
+//! which tag and ARIA role to use and carries the element id, which comes
+//! back with any accessibility action the client reports.
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use crate::components::AccessibilityRole;
+use crate::element::ElementId;
+use super::tree::{AccessibilityNode, AccessibilityTree};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AriaNode {
+    pub element: ElementId,
+    pub tag: String,
+    /// `aria-*`, `role` and `tabindex` attributes
+    pub attributes: BTreeMap<String, String>,
+    /// Text content, for nodes without an `aria-label`
+    pub text: Option<String>,
+    /// x, y, width, height in canvas pixels
+    pub bounds: [f32; 4],
+    pub focused: bool,
+    pub children: Vec<AriaNode>,
+}
+
+pub fn to_aria(tree: &AccessibilityTree) -> Vec<AriaNode> {
+    tree.roots.iter().map(aria_node).collect()
+}
+
+fn aria_node(node: &AccessibilityNode) -> AriaNode {
+    let mut attributes = BTreeMap::new();
+    let mut set = |name: &str, value: String| {
+        attributes.insert(name.to_string(), value);
+    };
+
+    let (tag, role) = match node.role {
+        AccessibilityRole::Generic => ("div", None),
+        AccessibilityRole::Button => ("div", Some("button")),
+        AccessibilityRole::Text => ("span", None),
+        AccessibilityRole::Heading(level) => {
+            set("aria-level", level.clamp(1, 6).to_string());
+            ("div", Some("heading"))
+        }
+        AccessibilityRole::TextInput => ("div", Some("textbox")),
+        AccessibilityRole::Link => ("div", Some("link")),
+        AccessibilityRole::Image => ("div", Some("img")),
+        AccessibilityRole::List => ("div", Some("list")),
+        AccessibilityRole::ListItem => ("div", Some("listitem")),
+        AccessibilityRole::Checkbox => ("div", Some("checkbox")),
+        AccessibilityRole::Switch => ("div", Some("switch")),
+        AccessibilityRole::Slider => ("div", Some("slider")),
+        AccessibilityRole::Tab => ("div", Some("tab")),
+        AccessibilityRole::TabList => ("div", Some("tablist")),
+        AccessibilityRole::Dialog => ("div", Some("dialog")),
+        AccessibilityRole::Toolbar => ("div", Some("toolbar")),
+        // Output is announced as it arrives
+        AccessibilityRole::Terminal => {
+            set("aria-live", "polite".to_string());
+            ("div", Some("log"))
+        }
+    };
+    if let Some(role) = role {
+        set("role", role.to_string());
+    }
+
+    // Text is read as content; anything else needs a label
+    let text = match node.role {
+        AccessibilityRole::Text => node.label.clone(),
+        _ => {
+            if let Some(label) = &node.label {
+                set("aria-label", label.clone());
+            }
+            None
+        }
+    };
+    if let Some(value) = &node.value {
+        set("aria-valuetext", value.clone());
+    }
+    if let Some(hint) = &node.hint {
+        set("aria-description", hint.clone());
+    }
+
+    let state = &node.state;
+    if state.focusable {
+        set("tabindex", "0".to_string());
+    } else if state.clickable {
+        set("tabindex", "-1".to_string());
+    }
+    if state.disabled {
+        set("aria-disabled", "true".to_string());
+    }
+    if let Some(checked) = state.checked {
+        set("aria-checked", checked.to_string());
+    }
+    if let Some(expanded) = state.expanded {
+        set("aria-expanded", expanded.to_string());
+    }
+    if state.selected {
+        set("aria-selected", "true".to_string());
+    }
+
+    AriaNode {
+        element: node.element,
+        tag: tag.to_string(),
+        attributes,
+        text,
+        bounds: [node.bounds.x, node.bounds.y, node.bounds.width, node.bounds.height],
+        focused: state.focused,
+        children: node.children.iter().map(aria_node).collect(),
+    }
+}
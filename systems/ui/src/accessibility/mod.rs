@@ -0,0 +1,7 @@
+pub mod tree;
+pub mod aria;
+pub mod android;
+
+pub use tree::{AccessibilityTree, AccessibilityNode, AccessibilityState};
+pub use aria::{AriaNode, to_aria};
+pub use android::{AndroidNodeInfo, to_android, action_from_android};
@@ -0,0 +1,237 @@
+//! Accessibility tree
+//!
+//! A semantic view of the element graph for screen readers. Hidden
+//! elements and their subtrees are left out, as are generic containers
+//! with nothing to say (no label, not focusable, clickable or scrollable);
+//! their children take their place. Children are in graph order, which is
+//! reading order.
+
+use std::collections::{HashMap, HashSet};
+use serde::{Serialize, Deserialize};
+use playground_core_ecs::World;
+use playground_core_types::{Shared, Handle};
+use crate::error::{UiError, UiResult};
+use crate::element::{ElementGraph, ElementId};
+use crate::components::{
+    UiElementComponent, UiLayoutComponent, UiStyleComponent, UiInputComponent, UiTextComponent,
+    UiAccessibilityComponent, AccessibilityRole, ElementBounds, Overflow,
+};
+use crate::input::focus::focus_index;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessibilityNode {
+    pub element: ElementId,
+    pub role: AccessibilityRole,
+    pub label: Option<String>,
+    pub value: Option<String>,
+    pub hint: Option<String>,
+    pub bounds: ElementBounds,
+    pub state: AccessibilityState,
+    pub children: Vec<AccessibilityNode>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessibilityState {
+    pub disabled: bool,
+    pub focusable: bool,
+    pub focused: bool,
+    pub clickable: bool,
+    pub scrollable: bool,
+    pub checked: Option<bool>,
+    pub expanded: Option<bool>,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessibilityTree {
+    pub roots: Vec<AccessibilityNode>,
+}
+
+/// An element seen while walking the graph, before children are attached.
+/// `node` is None for elements that are left out.
+struct Visited {
+    node: Option<AccessibilityNode>,
+    children: Vec<ElementId>,
+}
+
+impl AccessibilityTree {
+    pub async fn build(graph: &Shared<ElementGraph>, world: &Handle<World>) -> UiResult<Self> {
+        let roots = graph.read().await.roots();
+
+        let mut visited = HashMap::new();
+        let mut stack = roots.clone();
+        while let Some(element) = stack.pop() {
+            let Some(node) = describe(element, world).await? else {
+                continue;
+            };
+            let children = graph.read().await.get_children(element).cloned().unwrap_or_default();
+            stack.extend(children.iter().copied());
+            visited.insert(element, Visited { node, children });
+        }
+
+        Ok(Self {
+            roots: roots.iter().flat_map(|&root| assemble(root, &mut visited)).collect(),
+        })
+    }
+
+    /// Rebuild the tree after a frame. Only elements in `dirty` (the frame's
+    /// render-dirty set) or without cached nodes are described again; every
+    /// other subtree is reused from the graph's accessibility cache without
+    /// walking it.
+    pub async fn update(graph: &Shared<ElementGraph>, world: &Handle<World>, dirty: &HashSet<ElementId>) -> UiResult<Self> {
+        let roots = graph.read().await.roots();
+
+        let mut visited = HashMap::new();
+        let mut assembled: HashMap<ElementId, Vec<AccessibilityNode>> = HashMap::new();
+        // (element, children already assembled)
+        let mut stack: Vec<(ElementId, bool)> = roots.iter().map(|&root| (root, false)).collect();
+        while let Some((element, expanded)) = stack.pop() {
+            if expanded {
+                let nodes = attach(element, &mut visited, &mut assembled);
+                graph.write().await.store_accessibility(element, nodes.clone());
+                assembled.insert(element, nodes);
+                continue;
+            }
+
+            if !dirty.contains(&element)
+                && let Some(nodes) = graph.read().await.cached_accessibility(element)
+            {
+                assembled.insert(element, nodes.to_vec());
+                continue;
+            }
+
+            let Some(node) = describe(element, world).await? else {
+                graph.write().await.store_accessibility(element, Vec::new());
+                assembled.insert(element, Vec::new());
+                continue;
+            };
+            let children = graph.read().await.get_children(element).cloned().unwrap_or_default();
+            stack.push((element, true));
+            stack.extend(children.iter().map(|&child| (child, false)));
+            visited.insert(element, Visited { node, children });
+        }
+
+        Ok(Self {
+            roots: roots.iter().flat_map(|root| assembled.remove(root).unwrap_or_default()).collect(),
+        })
+    }
+
+    pub fn find(&self, element: ElementId) -> Option<&AccessibilityNode> {
+        self.nodes().into_iter().find(|node| node.element == element)
+    }
+
+    /// Every node, parents before their children
+    pub fn nodes(&self) -> Vec<&AccessibilityNode> {
+        let mut nodes = Vec::new();
+        let mut stack: Vec<&AccessibilityNode> = self.roots.iter().rev().collect();
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        nodes
+    }
+}
+
+/// Nodes standing for `element`: itself with its children attached, or its
+/// children when it is left out
+fn assemble(element: ElementId, visited: &mut HashMap<ElementId, Visited>) -> Vec<AccessibilityNode> {
+    let Some(entry) = visited.remove(&element) else {
+        return Vec::new();
+    };
+    let children: Vec<AccessibilityNode> = entry.children.iter()
+        .flat_map(|&child| assemble(child, visited))
+        .collect();
+
+    match entry.node {
+        Some(mut node) => {
+            node.children = children;
+            vec![node]
+        }
+        None => children,
+    }
+}
+
+/// Like `assemble`, for an element whose children were assembled already
+fn attach(
+    element: ElementId,
+    visited: &mut HashMap<ElementId, Visited>,
+    assembled: &mut HashMap<ElementId, Vec<AccessibilityNode>>,
+) -> Vec<AccessibilityNode> {
+    let Some(entry) = visited.remove(&element) else {
+        return Vec::new();
+    };
+    let children: Vec<AccessibilityNode> = entry.children.iter()
+        .flat_map(|child| assembled.remove(child).unwrap_or_default())
+        .collect();
+
+    match entry.node {
+        Some(mut node) => {
+            node.children = children;
+            vec![node]
+        }
+        None => children,
+    }
+}
+
+/// None when the element and its subtree are hidden, Some(None) when only
+/// the element itself is left out
+async fn describe(element: ElementId, world: &Handle<World>) -> UiResult<Option<Option<AccessibilityNode>>> {
+    let component = world.get_component::<UiElementComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    let style = world.get_component::<UiStyleComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    let accessibility = world.get_component::<UiAccessibilityComponent>(element).await
+        .unwrap_or_default();
+    if !component.visible || !style.visible || accessibility.hidden {
+        return Ok(None);
+    }
+
+    let layout = world.get_component::<UiLayoutComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    let clicks = world.get_component::<UiInputComponent>(element).await
+        .map(|input| input.on_click.is_some())
+        .unwrap_or(false);
+    let text = match world.get_component::<UiTextComponent>(element).await {
+        Ok(text) => Some(text.text),
+        Err(_) => component.text_content.clone(),
+    }.filter(|text| !text.is_empty());
+
+    let role = accessibility.role
+        .unwrap_or_else(|| AccessibilityRole::for_element_type(&component.element_type));
+    // An input's text is its value, not its name
+    let (label, value) = match role {
+        AccessibilityRole::TextInput => (accessibility.label, accessibility.value.or(text)),
+        _ => (accessibility.label.or(text), accessibility.value),
+    };
+
+    let state = AccessibilityState {
+        disabled: component.disabled,
+        focusable: focus_index(element, world).await?.is_some(),
+        focused: component.focused,
+        clickable: role.is_clickable() || clicks,
+        scrollable: matches!(style.overflow, Overflow::Scroll | Overflow::Auto),
+        checked: accessibility.checked,
+        expanded: accessibility.expanded,
+        selected: accessibility.selected,
+    };
+
+    let says_something = role != AccessibilityRole::Generic
+        || label.is_some()
+        || state.focusable
+        || state.clickable
+        || state.scrollable;
+    if !says_something {
+        return Ok(Some(None));
+    }
+
+    Ok(Some(Some(AccessibilityNode {
+        element,
+        role,
+        label,
+        value,
+        hint: accessibility.hint,
+        bounds: layout.bounds,
+        state,
+        children: Vec::new(),
+    })))
+}
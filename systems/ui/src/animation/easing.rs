@@ -0,0 +1,163 @@
+//! Easing curves
+//!
+//! The named curves are the CSS timing functions, so `EaseInOut` matches
+//! `ease-in-out` in a browser. Cubic beziers are solved for x with Newton's
+//! method, falling back to bisection where the slope is too flat.
+
+use playground_core_ui::EasingFunction;
+
+const NEWTON_ITERATIONS: usize = 8;
+const BISECTION_ITERATIONS: usize = 32;
+const EPSILON: f32 = 1e-6;
+
+/// Eased progress for linear progress `t` in 0..=1
+pub fn ease(easing: EasingFunction, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match easing {
+        EasingFunction::Linear => t,
+        EasingFunction::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+        EasingFunction::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+        EasingFunction::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+        EasingFunction::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+    }
+}
+
+/// One coordinate of a bezier from (0, 0) to (1, 1) with control points `p1` and `p2`
+fn bezier(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+}
+
+fn bezier_slope(p1: f32, p2: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    3.0 * u * u * p1 + 6.0 * u * t * (p2 - p1) + 3.0 * t * t * (1.0 - p2)
+}
+
+/// CSS `cubic-bezier(x1, y1, x2, y2)` at progress `x`
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    // x must stay monotonic, as in CSS
+    let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+    if x <= 0.0 || x >= 1.0 {
+        return x;
+    }
+
+    let mut t = x;
+    for _ in 0..NEWTON_ITERATIONS {
+        let error = bezier(x1, x2, t) - x;
+        if error.abs() < EPSILON {
+            return bezier(y1, y2, t);
+        }
+        let slope = bezier_slope(x1, x2, t);
+        if slope.abs() < EPSILON {
+            break;
+        }
+        t = (t - error / slope).clamp(0.0, 1.0);
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    t = x;
+    for _ in 0..BISECTION_ITERATIONS {
+        let value = bezier(x1, x2, t);
+        if (value - x).abs() < EPSILON {
+            break;
+        }
+        if value < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.0;
+    }
+    bezier(y1, y2, t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [EasingFunction; 5] = [
+        EasingFunction::Linear,
+        EasingFunction::EaseIn,
+        EasingFunction::EaseOut,
+        EasingFunction::EaseInOut,
+        EasingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    ];
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn curves_start_at_zero_and_end_at_one() {
+        for easing in CURVES {
+            assert_eq!(ease(easing, 0.0), 0.0, "{:?}", easing);
+            assert_eq!(ease(easing, 1.0), 1.0, "{:?}", easing);
+            // Progress outside 0..=1 is clamped
+            assert_eq!(ease(easing, -0.5), 0.0, "{:?}", easing);
+            assert_eq!(ease(easing, 1.5), 1.0, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn curves_never_go_backwards() {
+        for easing in CURVES {
+            let mut previous = 0.0;
+            for step in 1..=100 {
+                let value = ease(easing, step as f32 / 100.0);
+                assert!(value >= previous - 1e-4, "{:?} went back at {}", easing, step);
+                previous = value;
+            }
+        }
+    }
+
+    #[test]
+    fn linear_is_progress() {
+        for t in [0.1, 0.25, 0.5, 0.9] {
+            assert_eq!(ease(EasingFunction::Linear, t), t);
+        }
+    }
+
+    #[test]
+    fn named_curves_match_css() {
+        // Values of the CSS timing functions, as browsers compute them
+        assert_near(ease(EasingFunction::EaseIn, 0.5), 0.3153);
+        assert_near(ease(EasingFunction::EaseOut, 0.5), 0.6847);
+        assert_near(ease(EasingFunction::EaseInOut, 0.5), 0.5);
+        assert_near(ease(EasingFunction::EaseInOut, 0.25), 0.1291);
+        // `ease`
+        assert_near(ease(EasingFunction::CubicBezier(0.25, 0.1, 0.25, 1.0), 0.5), 0.8024);
+    }
+
+    #[test]
+    fn ease_in_out_is_symmetric() {
+        for step in 0..=20 {
+            let t = step as f32 / 20.0;
+            assert_near(ease(EasingFunction::EaseInOut, t) + ease(EasingFunction::EaseInOut, 1.0 - t), 1.0);
+        }
+    }
+
+    #[test]
+    fn ease_in_starts_slow_and_ease_out_starts_fast() {
+        for t in [0.1, 0.3, 0.6] {
+            assert!(ease(EasingFunction::EaseIn, t) < t);
+            assert!(ease(EasingFunction::EaseOut, t) > t);
+        }
+    }
+
+    #[test]
+    fn control_points_may_overshoot_in_y() {
+        // easeOutBack-style curve goes past 1 before settling
+        let back = EasingFunction::CubicBezier(0.34, 1.56, 0.64, 1.0);
+        assert!((1..100).any(|step| ease(back, step as f32 / 100.0) > 1.0));
+        assert_eq!(ease(back, 1.0), 1.0);
+    }
+
+    #[test]
+    fn flat_x_slopes_fall_back_to_bisection() {
+        // Both x control points at 0 or 1 make the slope vanish at the ends
+        let steep = EasingFunction::CubicBezier(1.0, 0.0, 0.0, 1.0);
+        assert_near(ease(steep, 0.5), 0.5);
+        assert!(ease(steep, 0.05) < 0.05);
+        assert!(ease(steep, 0.95) > 0.95);
+    }
+}
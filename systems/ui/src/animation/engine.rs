@@ -0,0 +1,370 @@
+//! Tween engine
+//!
+//! Tweens advance by the frame delta and are sampled once per frame. When
+//! two running animations set the same property of an element, the one
+//! started last wins.
+
+use playground_core_ui::{Animation, AnimationId, AnimatedProperty, AnimationRepeat, ElementId};
+use uuid::Uuid;
+use super::easing::ease;
+
+/// Interpolated value of one animated property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropertyValue {
+    Opacity(f32),
+    Position([f32; 2]),
+    Size([f32; 2]),
+    Scale(f32),
+    Rotation(f32),
+    Color([f32; 4]),
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+impl PropertyValue {
+    /// Value of `property` at eased progress `t`; t may overshoot 0..=1
+    pub fn sample(property: &AnimatedProperty, t: f32) -> Self {
+        match *property {
+            AnimatedProperty::Opacity(from, to) => Self::Opacity(lerp(from, to, t).clamp(0.0, 1.0)),
+            AnimatedProperty::Position(from_x, from_y, to_x, to_y) => {
+                Self::Position([lerp(from_x, to_x, t), lerp(from_y, to_y, t)])
+            }
+            AnimatedProperty::Size(from_width, from_height, to_width, to_height) => {
+                Self::Size([lerp(from_width, to_width, t).max(0.0), lerp(from_height, to_height, t).max(0.0)])
+            }
+            AnimatedProperty::Scale(from, to) => Self::Scale(lerp(from, to, t)),
+            AnimatedProperty::Rotation(from, to) => Self::Rotation(lerp(from, to, t)),
+            AnimatedProperty::Color(from, to) => {
+                let mut color = [0.0; 4];
+                for (channel, value) in color.iter_mut().enumerate() {
+                    *value = lerp(from[channel], to[channel], t).clamp(0.0, 1.0);
+                }
+                Self::Color(color)
+            }
+        }
+    }
+}
+
+/// A running animation on one element
+#[derive(Debug, Clone)]
+pub struct Tween {
+    pub element: ElementId,
+    pub animation: Animation,
+    /// Seconds since the animation started
+    pub elapsed: f32,
+}
+
+impl Tween {
+    /// Number of one-way plays, None for forever
+    fn legs(&self) -> Option<u32> {
+        match self.animation.repeat {
+            AnimationRepeat::None => Some(1),
+            AnimationRepeat::Count(count) => Some(count.max(1)),
+            AnimationRepeat::Infinite => None,
+            AnimationRepeat::PingPong(cycles) => Some(cycles.max(1) * 2),
+        }
+    }
+
+    fn ping_pong(&self) -> bool {
+        matches!(self.animation.repeat, AnimationRepeat::PingPong(_))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        let duration = self.animation.duration_ms as f32 / 1000.0;
+        match self.legs() {
+            Some(legs) => duration <= 0.0 || self.elapsed >= duration * legs as f32,
+            None => false,
+        }
+    }
+
+    /// Linear progress through the current play, in 0..=1
+    pub fn progress(&self) -> f32 {
+        let duration = self.animation.duration_ms as f32 / 1000.0;
+        if self.is_finished() {
+            // Ping-pong ends where it started
+            return if self.ping_pong() { 0.0 } else { 1.0 };
+        }
+        if duration <= 0.0 {
+            return 1.0;
+        }
+
+        let leg = (self.elapsed / duration).floor();
+        let local = self.elapsed / duration - leg;
+        if self.ping_pong() && leg as u32 % 2 == 1 {
+            1.0 - local
+        } else {
+            local
+        }
+    }
+
+    /// Current value of every animated property
+    pub fn sample(&self) -> Vec<PropertyValue> {
+        let t = ease(self.animation.easing, self.progress());
+        self.animation.properties.iter()
+            .map(|property| PropertyValue::sample(property, t))
+            .collect()
+    }
+}
+
+/// Result of advancing the engine by one frame
+#[derive(Debug, Default)]
+pub struct AnimationFrame {
+    /// Values to apply, in the order animations were started
+    pub samples: Vec<(ElementId, Vec<PropertyValue>)>,
+    /// Animations that reached their end this frame; their final values are
+    /// included in `samples`
+    pub completed: Vec<(AnimationId, ElementId)>,
+}
+
+pub struct AnimationEngine {
+    tweens: Vec<(AnimationId, Tween)>,
+}
+
+impl AnimationEngine {
+    pub fn new() -> Self {
+        Self {
+            tweens: Vec::new(),
+        }
+    }
+
+    pub fn start(&mut self, element: ElementId, animation: Animation) -> AnimationId {
+        let id = AnimationId(Uuid::new_v4());
+        self.tweens.push((id, Tween { element, animation, elapsed: 0.0 }));
+        id
+    }
+
+    /// Remove an animation, leaving its properties at their current values.
+    /// Returns the element it was running on.
+    pub fn stop(&mut self, id: AnimationId) -> Option<ElementId> {
+        let index = self.tweens.iter().position(|(tween_id, _)| *tween_id == id)?;
+        Some(self.tweens.remove(index).1.element)
+    }
+
+    /// Stop every animation on an element, e.g. when it is removed
+    pub fn stop_element(&mut self, element: ElementId) -> Vec<AnimationId> {
+        let mut stopped = Vec::new();
+        self.tweens.retain(|(id, tween)| {
+            let keep = tween.element != element;
+            if !keep {
+                stopped.push(*id);
+            }
+            keep
+        });
+        stopped
+    }
+
+    pub fn get(&self, id: AnimationId) -> Option<&Tween> {
+        self.tweens.iter().find(|(tween_id, _)| *tween_id == id).map(|(_, tween)| tween)
+    }
+
+    /// No animations are running, so frames need no animation work
+    pub fn is_idle(&self) -> bool {
+        self.tweens.is_empty()
+    }
+
+    /// Advance every animation by `delta_time` seconds and sample it
+    pub fn advance(&mut self, delta_time: f32) -> AnimationFrame {
+        let mut frame = AnimationFrame::default();
+        for (id, tween) in &mut self.tweens {
+            tween.elapsed += delta_time.max(0.0);
+            frame.samples.push((tween.element, tween.sample()));
+            if tween.is_finished() {
+                frame.completed.push((*id, tween.element));
+            }
+        }
+        self.tweens.retain(|(_, tween)| !tween.is_finished());
+        frame
+    }
+}
+
+impl Default for AnimationEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use playground_core_ui::EasingFunction;
+
+    fn animation(duration_ms: u32, properties: Vec<AnimatedProperty>, repeat: AnimationRepeat) -> Animation {
+        Animation { duration_ms, easing: EasingFunction::Linear, properties, repeat }
+    }
+
+    fn fade(duration_ms: u32, repeat: AnimationRepeat) -> Animation {
+        animation(duration_ms, vec![AnimatedProperty::Opacity(0.0, 1.0)], repeat)
+    }
+
+    fn element() -> ElementId {
+        ElementId(Uuid::new_v4())
+    }
+
+    #[test]
+    fn samples_every_property_at_the_eased_progress() {
+        let mut engine = AnimationEngine::new();
+        let panel = element();
+        engine.start(panel, animation(1000, vec![
+            AnimatedProperty::Position(0.0, 10.0, 100.0, 30.0),
+            AnimatedProperty::Size(50.0, 50.0, 0.0, 100.0),
+            AnimatedProperty::Color([0.0, 0.0, 0.0, 1.0], [1.0, 0.5, 0.0, 1.0]),
+            AnimatedProperty::Scale(1.0, 2.0),
+            AnimatedProperty::Rotation(0.0, 3.0),
+        ], AnimationRepeat::None));
+
+        let frame = engine.advance(0.25);
+        assert_eq!(frame.samples, vec![(panel, vec![
+            PropertyValue::Position([25.0, 15.0]),
+            PropertyValue::Size([37.5, 62.5]),
+            PropertyValue::Color([0.25, 0.125, 0.0, 1.0]),
+            PropertyValue::Scale(1.25),
+            PropertyValue::Rotation(0.75),
+        ])]);
+        assert!(frame.completed.is_empty());
+    }
+
+    #[test]
+    fn applies_the_easing_curve() {
+        let mut engine = AnimationEngine::new();
+        let mut eased = fade(1000, AnimationRepeat::None);
+        eased.easing = EasingFunction::EaseIn;
+        engine.start(element(), eased);
+
+        let frame = engine.advance(0.5);
+        assert_eq!(frame.samples[0].1, vec![PropertyValue::Opacity(ease(EasingFunction::EaseIn, 0.5))]);
+    }
+
+    #[test]
+    fn completes_once_at_the_end_values() {
+        let mut engine = AnimationEngine::new();
+        let panel = element();
+        let id = engine.start(panel, fade(200, AnimationRepeat::None));
+
+        assert!(engine.advance(0.1).completed.is_empty());
+        // Overshooting the end still lands exactly on the end values
+        let frame = engine.advance(0.5);
+        assert_eq!(frame.samples, vec![(panel, vec![PropertyValue::Opacity(1.0)])]);
+        assert_eq!(frame.completed, vec![(id, panel)]);
+
+        assert!(engine.is_idle());
+        assert!(engine.get(id).is_none());
+        let frame = engine.advance(0.1);
+        assert!(frame.samples.is_empty() && frame.completed.is_empty());
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_the_end() {
+        let mut engine = AnimationEngine::new();
+        let panel = element();
+        let id = engine.start(panel, fade(0, AnimationRepeat::None));
+
+        let frame = engine.advance(0.0);
+        assert_eq!(frame.samples, vec![(panel, vec![PropertyValue::Opacity(1.0)])]);
+        assert_eq!(frame.completed, vec![(id, panel)]);
+    }
+
+    #[test]
+    fn counted_repeats_restart_from_the_beginning() {
+        let mut engine = AnimationEngine::new();
+        let id = engine.start(element(), fade(1000, AnimationRepeat::Count(2)));
+
+        let frame = engine.advance(1.5);
+        assert_eq!(frame.samples[0].1, vec![PropertyValue::Opacity(0.5)]);
+        assert!(frame.completed.is_empty());
+        assert_eq!(engine.get(id).unwrap().progress(), 0.5);
+
+        let frame = engine.advance(0.5);
+        assert_eq!(frame.samples[0].1, vec![PropertyValue::Opacity(1.0)]);
+        assert_eq!(frame.completed.len(), 1);
+    }
+
+    #[test]
+    fn ping_pong_plays_back_and_ends_at_the_start() {
+        let mut engine = AnimationEngine::new();
+        engine.start(element(), fade(1000, AnimationRepeat::PingPong(1)));
+
+        assert_eq!(engine.advance(0.75).samples[0].1, vec![PropertyValue::Opacity(0.75)]);
+        // On the way back
+        assert_eq!(engine.advance(0.5).samples[0].1, vec![PropertyValue::Opacity(0.75)]);
+        assert_eq!(engine.advance(0.5).samples[0].1, vec![PropertyValue::Opacity(0.25)]);
+
+        let frame = engine.advance(0.5);
+        assert_eq!(frame.samples[0].1, vec![PropertyValue::Opacity(0.0)]);
+        assert_eq!(frame.completed.len(), 1);
+    }
+
+    #[test]
+    fn infinite_animations_never_complete() {
+        let mut engine = AnimationEngine::new();
+        engine.start(element(), fade(100, AnimationRepeat::Infinite));
+
+        for _ in 0..100 {
+            assert!(engine.advance(0.13).completed.is_empty());
+        }
+        assert!(!engine.is_idle());
+    }
+
+    #[test]
+    fn samples_in_start_order_so_the_latest_wins() {
+        let mut engine = AnimationEngine::new();
+        let panel = element();
+        engine.start(panel, animation(1000, vec![AnimatedProperty::Scale(1.0, 2.0)], AnimationRepeat::None));
+        engine.start(panel, animation(1000, vec![AnimatedProperty::Scale(1.0, 0.0)], AnimationRepeat::None));
+
+        let frame = engine.advance(0.5);
+        assert_eq!(frame.samples, vec![
+            (panel, vec![PropertyValue::Scale(1.5)]),
+            (panel, vec![PropertyValue::Scale(0.5)]),
+        ]);
+    }
+
+    #[test]
+    fn stopped_animations_are_not_sampled() {
+        let mut engine = AnimationEngine::new();
+        let (first, second) = (element(), element());
+        let stopped = engine.start(first, fade(1000, AnimationRepeat::None));
+        let on_second = [
+            engine.start(second, fade(1000, AnimationRepeat::None)),
+            engine.start(second, fade(500, AnimationRepeat::None)),
+        ];
+
+        assert_eq!(engine.stop(stopped), Some(first));
+        assert_eq!(engine.stop(stopped), None);
+        assert_eq!(engine.stop_element(second), on_second.to_vec());
+        assert!(engine.is_idle());
+        assert!(engine.advance(0.5).samples.is_empty());
+    }
+
+    #[test]
+    fn clamps_values_that_leave_their_range() {
+        let mut engine = AnimationEngine::new();
+        let mut overshoot = animation(1000, vec![
+            AnimatedProperty::Opacity(0.0, 1.0),
+            AnimatedProperty::Size(10.0, 10.0, 0.0, 0.0),
+        ], AnimationRepeat::None);
+        // Goes past both ends of the curve
+        overshoot.easing = EasingFunction::CubicBezier(0.5, -1.0, 0.5, 2.0);
+        engine.start(element(), overshoot);
+
+        for _ in 0..10 {
+            for value in engine.advance(0.09).samples[0].1.iter() {
+                match *value {
+                    PropertyValue::Opacity(opacity) => assert!((0.0..=1.0).contains(&opacity)),
+                    PropertyValue::Size([width, height]) => assert!(width >= 0.0 && height >= 0.0),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn negative_deltas_do_not_rewind() {
+        let mut engine = AnimationEngine::new();
+        let id = engine.start(element(), fade(1000, AnimationRepeat::None));
+        engine.advance(0.5);
+        engine.advance(-0.25);
+        assert_eq!(engine.get(id).unwrap().elapsed, 0.5);
+    }
+}
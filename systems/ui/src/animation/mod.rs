@@ -0,0 +1,12 @@
+//! Element animations
+//!
+//! `AnimationEngine` runs the tweens behind core/ui's `AnimatedRenderer`.
+//! Each frame it interpolates position, size, opacity, color, scale and
+//! rotation with the animation's easing, and reports animations that ended.
+//! It holds no state between animations, so an idle UI does no work here.
+
+pub mod easing;
+pub mod engine;
+
+pub use easing::ease;
+pub use engine::{AnimationEngine, AnimationFrame, PropertyValue, Tween};
@@ -5,9 +5,11 @@ use playground_core_ecs::{
     SystemCommandProcessor, SystemResponse, EcsResult, EcsError
 };
 use playground_core_ui::{
-    UiCommand, ElementUpdate, ImageSource, KeyboardType, HapticType
+    UiCommand, ElementUpdate, ImageSource, KeyboardType, HapticType, EventResult
 };
 use crate::system::UiSystem;
+use crate::accessibility::{to_aria, to_android};
+use crate::messages::AccessibilityActionMessage;
 use crate::types::{ElementStyle, ElementBounds};
 use bytes::Bytes;
 use serde_json;
@@ -93,6 +95,44 @@ impl SystemCommandProcessor for UiCommandProcessor {
                     error: None,
                 })
             },
+            "accessibility_tree" => {
+                // Payload names the format: "aria" for browsers, "android"
+                let format: String = serde_json::from_slice(&payload)
+                    .map_err(|e| EcsError::Generic(format!("Failed to deserialize tree format: {}", e)))?;
+                
+                let ui = self.ui_system.read().await;
+                let tree = ui.accessibility_tree().await
+                    .map_err(|e| EcsError::Generic(e.to_string()))?;
+                let response = match format.as_str() {
+                    "aria" => serde_json::to_vec(&to_aria(&tree)),
+                    "android" => serde_json::to_vec(&to_android(&tree)),
+                    _ => return Err(EcsError::Generic(format!("Unknown accessibility tree format: {}", format))),
+                }.map_err(|e| EcsError::Generic(e.to_string()))?;
+                
+                Ok(SystemResponse {
+                    success: true,
+                    payload: Some(Bytes::from(response)),
+                    error: None,
+                })
+            },
+            "accessibility_action" => {
+                let message: AccessibilityActionMessage = serde_json::from_slice(&payload)
+                    .map_err(|e| EcsError::Generic(format!("Failed to deserialize accessibility action: {}", e)))?;
+                
+                let ui = self.ui_system.read().await;
+                let result = ui.perform_accessibility_action(message.element, message.action).await
+                    .map_err(|e| EcsError::Generic(e.to_string()))?;
+                
+                // Whether anything responded to the action
+                let response_bytes = serde_json::to_vec(&(result != EventResult::Ignored))
+                    .map_err(|e| EcsError::Generic(e.to_string()))?;
+                
+                Ok(SystemResponse {
+                    success: true,
+                    payload: Some(Bytes::from(response_bytes)),
+                    error: None,
+                })
+            },
             "layout" => {
                 // Trigger layout calculation
                 let mut ui = self.ui_system.write().await;
@@ -117,6 +157,8 @@ impl SystemCommandProcessor for UiCommandProcessor {
             "get_element".to_string(),
             "get_root".to_string(),
             "layout".to_string(),
+            "accessibility_tree".to_string(),
+            "accessibility_action".to_string(),
         ]
     }
 }
@@ -241,11 +283,13 @@ impl UiCommandProcessor {
                 Ok(serde_json::json!({ "removed": id }))
             },
             UiCommand::Focus { id } => {
-                ui.input_manager.write().await.set_focused_element(Some(id.clone()));
+                ui.focus_element(Some(id.clone())).await
+                    .map_err(|e| EcsError::Generic(e.to_string()))?;
                 Ok(serde_json::json!({ "focused": id }))
             },
             UiCommand::Blur { id } => {
-                ui.input_manager.write().await.set_focused_element(None);
+                ui.focus_element(None).await
+                    .map_err(|e| EcsError::Generic(e.to_string()))?;
                 Ok(serde_json::json!({ "blurred": id }))
             },
             _ => {
@@ -0,0 +1,99 @@
+use playground_core_ecs::{ComponentData, ComponentId, EcsError, EcsResult};
+use serde::{Serialize, Deserialize};
+use async_trait::async_trait;
+use bytes::Bytes;
+
+/// What assistive technology is told about an element. Elements without
+/// this component still appear in the accessibility tree, with a role taken
+/// from their element type and their text as the label.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiAccessibilityComponent {
+    /// None derives the role from the element type
+    pub role: Option<AccessibilityRole>,
+    /// Name read out for the element; defaults to its text
+    pub label: Option<String>,
+    /// Current value of inputs, sliders and the like
+    pub value: Option<String>,
+    /// Longer description, read after the label
+    pub hint: Option<String>,
+    /// Checkboxes and switches
+    pub checked: Option<bool>,
+    /// Collapsible sections, menus
+    pub expanded: Option<bool>,
+    /// Tabs, list items
+    pub selected: bool,
+    /// Leave the element and its subtree out of the accessibility tree
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessibilityRole {
+    /// A container with no meaning of its own
+    Generic,
+    Button,
+    Text,
+    /// Heading level 1-6
+    Heading(u8),
+    TextInput,
+    Link,
+    Image,
+    List,
+    ListItem,
+    Checkbox,
+    Switch,
+    Slider,
+    Tab,
+    TabList,
+    Dialog,
+    Toolbar,
+    Terminal,
+}
+
+impl AccessibilityRole {
+    /// Role of an element that doesn't declare one
+    pub fn for_element_type(element_type: &str) -> Self {
+        match element_type {
+            "button" => AccessibilityRole::Button,
+            "text" | "label" => AccessibilityRole::Text,
+            "input" => AccessibilityRole::TextInput,
+            "image" => AccessibilityRole::Image,
+            "list" => AccessibilityRole::List,
+            "terminal" => AccessibilityRole::Terminal,
+            _ => AccessibilityRole::Generic,
+        }
+    }
+
+    /// Activated by a tap
+    pub fn is_clickable(self) -> bool {
+        matches!(
+            self,
+            AccessibilityRole::Button
+                | AccessibilityRole::Link
+                | AccessibilityRole::Checkbox
+                | AccessibilityRole::Switch
+                | AccessibilityRole::Tab
+        )
+    }
+}
+
+#[async_trait]
+impl ComponentData for UiAccessibilityComponent {
+    fn component_id() -> ComponentId {
+        "UiAccessibilityComponent".to_string()
+    }
+    
+    fn component_name() -> &'static str {
+        "UiAccessibilityComponent"
+    }
+    
+    async fn serialize(&self) -> EcsResult<Bytes> {
+        let data = bincode::serialize(self)
+            .map_err(|e| EcsError::SerializationFailed(e.to_string()))?;
+        Ok(Bytes::from(data))
+    }
+    
+    async fn deserialize(bytes: &Bytes) -> EcsResult<Self> {
+        bincode::deserialize(bytes)
+            .map_err(|e| EcsError::DeserializationFailed(e.to_string()))
+    }
+}
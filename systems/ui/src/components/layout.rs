@@ -10,15 +10,28 @@ pub struct UiLayoutComponent {
     pub margin: [f32; 4],
     pub layout_type: LayoutType,
     pub flex_direction: FlexDirection,
+    pub flex_wrap: FlexWrap,
     pub justify_content: JustifyContent,
     pub align_items: AlignItems,
+    /// Overrides the parent's `align_items` for this element
+    pub align_self: Option<AlignItems>,
+    pub flex_grow: f32,
+    pub flex_shrink: f32,
+    /// Main size before growing or shrinking; None uses `size`, then content
+    pub flex_basis: Option<f32>,
+    /// Space between rows (wrapped lines of a row, or items of a column)
+    pub row_gap: f32,
+    /// Space between columns (items of a row, or wrapped lines of a column)
+    pub column_gap: f32,
+    /// Relative pane sizes of a docking container; missing entries count as 1.0
+    pub split_ratios: Vec<f32>,
     pub position_type: PositionType,
     pub size: Size,
     pub min_size: Size,
     pub max_size: Size,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct ElementBounds {
     pub x: f32,
     pub y: f32,
@@ -26,6 +39,22 @@ pub struct ElementBounds {
     pub height: f32,
 }
 
+impl ElementBounds {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.x + self.width &&
+        y >= self.y && y <= self.y + self.height
+    }
+    
+    /// Overlap of two rectangles, empty (zero sized) if they don't meet
+    pub fn intersection(&self, other: &ElementBounds) -> ElementBounds {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        ElementBounds { x, y, width: (right - x).max(0.0), height: (bottom - y).max(0.0) }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum LayoutType {
     Flexbox,
@@ -41,6 +70,13 @@ pub enum FlexDirection {
     ColumnReverse,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FlexWrap {
+    NoWrap,
+    Wrap,
+    WrapReverse,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum JustifyContent {
     FlexStart,
@@ -81,8 +117,16 @@ impl Default for UiLayoutComponent {
             margin: [0.0; 4],
             layout_type: LayoutType::Flexbox,
             flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::NoWrap,
             justify_content: JustifyContent::FlexStart,
             align_items: AlignItems::FlexStart,
+            align_self: None,
+            flex_grow: 0.0,
+            flex_shrink: 1.0,
+            flex_basis: None,
+            row_gap: 0.0,
+            column_gap: 0.0,
+            split_ratios: Vec::new(),
             position_type: PositionType::Relative,
             size: Size { width: None, height: None },
             min_size: Size { width: None, height: None },
@@ -3,12 +3,14 @@ pub mod layout;
 pub mod style;
 pub mod input;
 pub mod text;
+pub mod accessibility;
 
 pub use element::UiElementComponent;
 pub use layout::{
-    UiLayoutComponent, ElementBounds, LayoutType, FlexDirection,
+    UiLayoutComponent, ElementBounds, LayoutType, FlexDirection, FlexWrap,
     JustifyContent, AlignItems, PositionType, Size
 };
-pub use style::{UiStyleComponent, FontWeight, TextAlign};
+pub use style::{UiStyleComponent, FontWeight, TextAlign, Overflow};
 pub use input::UiInputComponent;
-pub use text::UiTextComponent;
\ No newline at end of file
+pub use text::UiTextComponent;
+pub use accessibility::{UiAccessibilityComponent, AccessibilityRole};
\ No newline at end of file
@@ -2,6 +2,7 @@ use playground_core_ecs::{ComponentData, ComponentId, EcsError, EcsResult};
 use serde::{Serialize, Deserialize};
 use nalgebra::Vector4;
 use std::collections::HashMap;
+pub use playground_core_ui::Overflow;
 use async_trait::async_trait;
 use bytes::Bytes;
 
@@ -10,15 +11,27 @@ pub struct UiStyleComponent {
     pub background_color: Option<Vector4<f32>>,
     pub text_color: Option<Vector4<f32>>,
     pub border_color: Option<Vector4<f32>>,
+    /// Theme color tokens (`"primary"`, a custom token) that take the place
+    /// of the literal colors above, so the element follows theme changes
+    pub background_token: Option<String>,
+    pub text_token: Option<String>,
+    pub border_token: Option<String>,
     pub border_width: f32,
     pub border_radius: f32,
     pub opacity: f32,
+    /// Uniform scale about the element's center, applied when drawing
+    pub scale: f32,
+    /// Rotation in radians about the element's center, applied when drawing
+    pub rotation: f32,
     pub font_size: f32,
     pub font_family: Option<String>,
     pub font_weight: FontWeight,
     pub text_align: TextAlign,
     pub visible: bool,
+    /// Siblings are drawn and hit tested in ascending order
     pub z_index: i32,
+    /// Anything but `Visible` clips children to this element's bounds
+    pub overflow: Overflow,
     pub cursor: Option<String>,
     pub custom_styles: HashMap<String, String>,
 }
@@ -45,15 +58,21 @@ impl Default for UiStyleComponent {
             background_color: None,
             text_color: None,
             border_color: None,
+            background_token: None,
+            text_token: None,
+            border_token: None,
             border_width: 0.0,
             border_radius: 0.0,
             opacity: 1.0,
+            scale: 1.0,
+            rotation: 0.0,
             font_size: 14.0,
             font_family: None,
             font_weight: FontWeight::Normal,
             text_align: TextAlign::Left,
             visible: true,
             z_index: 0,
+            overflow: Overflow::Visible,
             cursor: None,
             custom_styles: HashMap::new(),
         }
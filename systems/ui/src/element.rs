@@ -1,14 +1,28 @@
 use playground_core_ui::ElementId;
 use playground_core_types::{Shared, shared};
+use playground_core_rendering::RenderCommand;
+use crate::accessibility::AccessibilityNode;
 use crate::error::{UiError, UiResult};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // ElementId is now from core/ui, not an alias
 
 // Element graph for managing UI hierarchy
+//
+// The graph also tracks which elements need work. An element in
+// `layout_dirty` must have its children laid out again; an element in
+// `render_dirty` has a subtree whose cached render commands are stale.
 pub struct ElementGraph {
     children: HashMap<ElementId, Vec<ElementId>>,
     parents: HashMap<ElementId, ElementId>,
+    layout_dirty: HashSet<ElementId>,
+    render_dirty: HashSet<ElementId>,
+    // Commands for each element's whole subtree, from the last frame it was drawn
+    render_cache: HashMap<ElementId, Vec<RenderCommand>>,
+    // Accessibility nodes standing for each element's subtree, from the last
+    // time the tree was built. Anything the tree shows also repaints, so a
+    // subtree that isn't render dirty can reuse them.
+    accessibility_cache: HashMap<ElementId, Vec<AccessibilityNode>>,
 }
 
 impl ElementGraph {
@@ -16,20 +30,27 @@ impl ElementGraph {
         Self {
             children: HashMap::new(),
             parents: HashMap::new(),
+            layout_dirty: HashSet::new(),
+            render_dirty: HashSet::new(),
+            render_cache: HashMap::new(),
+            accessibility_cache: HashMap::new(),
         }
     }
     
     pub fn add_child(&mut self, parent: ElementId, child: ElementId) -> UiResult<()> {
         // Remove from previous parent if exists
-        if let Some(old_parent) = self.parents.get(&child) {
-            if let Some(siblings) = self.children.get_mut(old_parent) {
+        if let Some(old_parent) = self.parents.get(&child).copied() {
+            if let Some(siblings) = self.children.get_mut(&old_parent) {
                 siblings.retain(|&id| id != child);
             }
+            self.mark_layout_dirty(old_parent);
         }
         
         // Add to new parent
         self.children.entry(parent).or_insert_with(Vec::new).push(child);
         self.parents.insert(child, parent);
+        self.mark_layout_dirty(parent);
+        self.mark_subtree_dirty(child);
         
         Ok(())
     }
@@ -39,6 +60,7 @@ impl ElementGraph {
             children.retain(|&id| id != child);
         }
         self.parents.remove(&child);
+        self.mark_layout_dirty(parent);
         Ok(())
     }
     
@@ -50,13 +72,26 @@ impl ElementGraph {
         self.parents.get(&child).copied()
     }
     
+    /// Elements with children but no parent, i.e. the tops of trees
+    pub fn roots(&self) -> Vec<ElementId> {
+        self.children.keys()
+            .filter(|element| !self.parents.contains_key(element))
+            .copied()
+            .collect()
+    }
+    
     pub fn remove_element(&mut self, element: ElementId) {
         // Remove as child from parent
         if let Some(parent) = self.parents.remove(&element) {
             if let Some(siblings) = self.children.get_mut(&parent) {
                 siblings.retain(|&id| id != element);
             }
+            self.mark_layout_dirty(parent);
         }
+        self.layout_dirty.remove(&element);
+        self.render_dirty.remove(&element);
+        self.render_cache.remove(&element);
+        self.accessibility_cache.remove(&element);
         
         // Remove all children recursively
         if let Some(children) = self.children.remove(&element) {
@@ -69,6 +104,103 @@ impl ElementGraph {
     pub fn iter_depth_first(&self, root: ElementId) -> DepthFirstIterator {
         DepthFirstIterator::new(self, root)
     }
+    
+    /// Element's size may have changed (text, style, children), so it and
+    /// its parent need layout
+    pub fn mark_layout_dirty(&mut self, element: ElementId) {
+        self.layout_dirty.insert(element);
+        if let Some(parent) = self.get_parent(element) {
+            self.layout_dirty.insert(parent);
+        }
+        self.mark_render_dirty(element);
+    }
+    
+    /// Element was resized or moved directly, not by its parent's layout, so
+    /// only its own children need layout
+    pub fn mark_children_dirty(&mut self, element: ElementId) {
+        self.layout_dirty.insert(element);
+        self.mark_render_dirty(element);
+    }
+    
+    /// Lay out and repaint everything below `root`, e.g. after a viewport change
+    pub fn mark_subtree_dirty(&mut self, root: ElementId) {
+        let subtree: Vec<ElementId> = self.iter_depth_first(root).collect();
+        self.layout_dirty.extend(subtree.iter().copied());
+        self.render_dirty.extend(subtree);
+        self.mark_render_dirty(root);
+    }
+    
+    /// Element looks different but keeps its size, e.g. hover or focus. Every
+    /// ancestor's cached commands include it, so they go stale too.
+    pub fn mark_render_dirty(&mut self, element: ElementId) {
+        let mut current = Some(element);
+        while let Some(id) = current {
+            // Ancestors of a dirty element are already dirty
+            if !self.render_dirty.insert(id) && id != element {
+                break;
+            }
+            current = self.get_parent(id);
+        }
+    }
+    
+    /// Drop every cached command, e.g. after a theme change
+    pub fn clear_render_cache(&mut self) {
+        self.render_cache.clear();
+    }
+    
+    pub fn is_layout_dirty(&self, element: ElementId) -> bool {
+        self.layout_dirty.contains(&element)
+    }
+    
+    pub fn is_render_dirty(&self, element: ElementId) -> bool {
+        self.render_dirty.contains(&element)
+    }
+    
+    /// Snapshot of the render-dirty set, taken before drawing clears it
+    pub fn render_dirty_elements(&self) -> HashSet<ElementId> {
+        self.render_dirty.clone()
+    }
+    
+    /// Accessibility nodes recorded for a subtree the last time it was built
+    pub fn cached_accessibility(&self, element: ElementId) -> Option<&[AccessibilityNode]> {
+        self.accessibility_cache.get(&element).map(Vec::as_slice)
+    }
+    
+    pub fn store_accessibility(&mut self, element: ElementId, nodes: Vec<AccessibilityNode>) {
+        self.accessibility_cache.insert(element, nodes);
+    }
+    
+    /// Commands recorded for a clean subtree, or None if it must be redrawn
+    pub fn cached_commands(&self, element: ElementId) -> Option<&[RenderCommand]> {
+        if self.render_dirty.contains(&element) {
+            return None;
+        }
+        self.render_cache.get(&element).map(Vec::as_slice)
+    }
+    
+    /// Record a freshly drawn subtree and mark it clean
+    pub fn store_commands(&mut self, element: ElementId, commands: Vec<RenderCommand>) {
+        self.render_dirty.remove(&element);
+        self.render_cache.insert(element, commands);
+    }
+    
+    /// Take the dirty set grouped by depth, so parents are laid out before
+    /// their children
+    pub fn take_layout_dirty(&mut self) -> Vec<Vec<ElementId>> {
+        let mut levels: Vec<Vec<ElementId>> = Vec::new();
+        for element in std::mem::take(&mut self.layout_dirty) {
+            let depth = self.ancestors(element).count();
+            if levels.len() <= depth {
+                levels.resize_with(depth + 1, Vec::new);
+            }
+            levels[depth].push(element);
+        }
+        levels
+    }
+    
+    fn ancestors(&self, element: ElementId) -> impl Iterator<Item = ElementId> + '_ {
+        std::iter::successors(self.get_parent(element), move |&id| self.get_parent(id))
+    }
 }
 
 pub struct DepthFirstIterator<'a> {
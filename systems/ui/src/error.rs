@@ -14,6 +14,9 @@ pub enum UiError {
     #[error("Theme not found: {0}")]
     ThemeNotFound(String),
     
+    #[error("Invalid theme: {0}")]
+    InvalidTheme(String),
+    
     #[error("Layout error: {0}")]
     LayoutError(String),
     
@@ -26,6 +29,12 @@ pub enum UiError {
     #[error("Terminal error: {0}")]
     TerminalError(String),
     
+    #[error("Font error: {0}")]
+    FontError(String),
+    
+    #[error("Animation not found: {0}")]
+    AnimationNotFound(String),
+    
     #[error("ECS error: {0}")]
     EcsError(String),
     
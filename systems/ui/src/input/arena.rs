@@ -0,0 +1,141 @@
+//! Gesture arena
+//!
+//! Recognizers that could claim the same touch sequence join an arena when
+//! it starts. Each one accepts once it is sure and rejects once it can't
+//! match. The first to accept wins and everyone else is rejected. A lone
+//! survivor also wins. If the sequence ends undecided, the arena is swept
+//! and the first surviving member in join order wins, so results never
+//! depend on timing between recognizers.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GestureKind {
+    Tap,
+    LongPress,
+    Pan,
+    Scale,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GestureArena {
+    members: Vec<GestureKind>,
+    winner: Option<GestureKind>,
+}
+
+impl GestureArena {
+    /// Open an arena; `members` are in priority order for sweeping
+    pub fn open(members: &[GestureKind]) -> Self {
+        Self {
+            members: members.to_vec(),
+            winner: None,
+        }
+    }
+
+    pub fn winner(&self) -> Option<GestureKind> {
+        self.winner
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.winner.is_none() && !self.members.is_empty()
+    }
+
+    /// Still competing, or already won
+    pub fn contains(&self, kind: GestureKind) -> bool {
+        self.members.contains(&kind)
+    }
+
+    /// Claim the sequence. Returns true if `kind` now holds it.
+    pub fn accept(&mut self, kind: GestureKind) -> bool {
+        if self.winner.is_none() && self.contains(kind) {
+            self.members.retain(|&member| member == kind);
+            self.winner = Some(kind);
+        }
+        self.winner == Some(kind)
+    }
+
+    /// Leave the arena; a lone survivor wins
+    pub fn reject(&mut self, kind: GestureKind) {
+        if self.winner.is_some() {
+            return;
+        }
+        self.members.retain(|&member| member != kind);
+        if let [last] = self.members[..] {
+            self.winner = Some(last);
+        }
+    }
+
+    /// Resolve an undecided arena in favour of its first member
+    pub fn sweep(&mut self) -> Option<GestureKind> {
+        if self.winner.is_none() && let Some(&first) = self.members.first() {
+            self.accept(first);
+        }
+        self.winner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [GestureKind; 4] = [GestureKind::Tap, GestureKind::LongPress, GestureKind::Pan, GestureKind::Scale];
+
+    #[test]
+    fn first_to_accept_wins() {
+        let mut arena = GestureArena::open(&ALL);
+        assert!(arena.is_open());
+        assert!(arena.accept(GestureKind::Pan));
+        assert_eq!(arena.winner(), Some(GestureKind::Pan));
+        assert!(!arena.is_open());
+
+        // Everyone else is out
+        assert!(!arena.accept(GestureKind::Scale));
+        assert!(!arena.contains(GestureKind::Tap));
+        // Accepting again is harmless
+        assert!(arena.accept(GestureKind::Pan));
+    }
+
+    #[test]
+    fn rejections_after_a_win_change_nothing() {
+        let mut arena = GestureArena::open(&ALL);
+        arena.accept(GestureKind::Tap);
+        arena.reject(GestureKind::Tap);
+        assert_eq!(arena.winner(), Some(GestureKind::Tap));
+    }
+
+    #[test]
+    fn lone_survivor_wins() {
+        let mut arena = GestureArena::open(&ALL);
+        arena.reject(GestureKind::Tap);
+        arena.reject(GestureKind::LongPress);
+        assert_eq!(arena.winner(), None);
+        arena.reject(GestureKind::Pan);
+        assert_eq!(arena.winner(), Some(GestureKind::Scale));
+    }
+
+    #[test]
+    fn rejected_members_cannot_accept() {
+        let mut arena = GestureArena::open(&ALL);
+        arena.reject(GestureKind::Tap);
+        assert!(!arena.accept(GestureKind::Tap));
+        assert!(arena.is_open());
+    }
+
+    #[test]
+    fn sweep_picks_the_first_survivor_in_join_order() {
+        let mut arena = GestureArena::open(&ALL);
+        arena.reject(GestureKind::Tap);
+        assert_eq!(arena.sweep(), Some(GestureKind::LongPress));
+
+        // A decided arena keeps its winner
+        let mut arena = GestureArena::open(&ALL);
+        arena.accept(GestureKind::Scale);
+        assert_eq!(arena.sweep(), Some(GestureKind::Scale));
+    }
+
+    #[test]
+    fn empty_arena_has_no_winner() {
+        let mut arena = GestureArena::default();
+        assert!(!arena.is_open());
+        assert!(!arena.accept(GestureKind::Tap));
+        assert_eq!(arena.sweep(), None);
+    }
+}
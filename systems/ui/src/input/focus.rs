@@ -0,0 +1,79 @@
+//! Focus order
+//!
+//! Tab order follows HTML: elements with a positive `tab_index` come first,
+//! ascending, then those with `tab_index` 0 or accepting input without an
+//! index, in tree order. A negative `tab_index` makes an element focusable
+//! by pointer only. Hidden and disabled elements are never focusable.
+
+use playground_core_ecs::World;
+use playground_core_types::{Shared, Handle};
+use crate::error::{UiError, UiResult};
+use crate::element::{ElementGraph, ElementId};
+use crate::components::{UiElementComponent, UiInputComponent, UiStyleComponent};
+
+/// Tab index of a focusable element, 0 when it accepts input without one
+pub async fn focus_index(element: ElementId, world: &Handle<World>) -> UiResult<Option<i32>> {
+    let component = world.get_component::<UiElementComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    if component.disabled {
+        return Ok(None);
+    }
+
+    // Elements without input never take focus
+    let Ok(input) = world.get_component::<UiInputComponent>(element).await else {
+        return Ok(None);
+    };
+    Ok(match input.tab_index {
+        Some(index) => Some(index),
+        None if input.accepts_input => Some(0),
+        None => None,
+    })
+}
+
+/// Elements Tab visits, in order
+pub async fn tab_order(graph: &Shared<ElementGraph>, world: &Handle<World>) -> UiResult<Vec<ElementId>> {
+    let mut positive = Vec::new();
+    let mut natural = Vec::new();
+
+    let mut stack = graph.read().await.roots();
+    stack.reverse();
+    while let Some(element) = stack.pop() {
+        let component = world.get_component::<UiElementComponent>(element).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        let style = world.get_component::<UiStyleComponent>(element).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        if !component.visible || !style.visible {
+            continue;
+        }
+
+        match focus_index(element, world).await? {
+            Some(index) if index > 0 => positive.push((index, element)),
+            Some(0) => natural.push(element),
+            _ => {}
+        }
+
+        if let Some(children) = graph.read().await.get_children(element) {
+            stack.extend(children.iter().rev().copied());
+        }
+    }
+
+    // Stable, so equal indices keep tree order
+    positive.sort_by_key(|(index, _)| *index);
+    Ok(positive.into_iter().map(|(_, element)| element).chain(natural).collect())
+}
+
+/// Element after `current` in tab order, wrapping around; before it when
+/// `reverse`. Starts from either end when nothing in the order has focus.
+pub fn next_focus(order: &[ElementId], current: Option<ElementId>, reverse: bool) -> Option<ElementId> {
+    if order.is_empty() {
+        return None;
+    }
+    let position = current.and_then(|current| order.iter().position(|&element| element == current));
+    let index = match (position, reverse) {
+        (Some(position), false) => (position + 1) % order.len(),
+        (Some(position), true) => (position + order.len() - 1) % order.len(),
+        (None, false) => 0,
+        (None, true) => order.len() - 1,
+    };
+    Some(order[index])
+}
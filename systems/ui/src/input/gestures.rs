@@ -1,13 +1,34 @@
+//! Multi-touch gesture recognition
+//!
+//! `GestureRecognizer` follows every pointer by `touch_id` and resolves each
+//! touch sequence (first finger down to last finger up) through a
+//! `GestureArena`: a tap, a long press, a one-finger pan or a two-finger
+//! scale. Pans end in a fling and, when fast and long enough, a swipe.
+//! Scales report pinch and rotation relative to when the fingers landed.
+//!
+//! Time is passed in rather than read from a clock, so a recorded or
+//! synthetic touch stream replays to the same gestures. `tick` must be
+//! called while fingers are down for long presses to fire without movement.
+
 use serde::{Serialize, Deserialize};
-use std::time::{Instant, Duration};
+use std::collections::{BTreeMap, VecDeque};
+use std::f32::consts::PI;
+use std::time::Duration;
+use super::arena::{GestureArena, GestureKind};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum GestureEvent {
     Tap { x: f32, y: f32 },
     DoubleTap { x: f32, y: f32 },
     LongPress { x: f32, y: f32 },
-    Pan { delta_x: f32, delta_y: f32 },
+    /// Movement since the previous pan event, or since the finger landed for
+    /// the first one; `x` and `y` are where the finger is now
+    Pan { x: f32, y: f32, delta_x: f32, delta_y: f32 },
+    /// Pan released with speed, in pixels per second
+    Fling { velocity_x: f32, velocity_y: f32 },
+    /// Scale since the fingers landed
     Pinch { scale: f32, center_x: f32, center_y: f32 },
+    /// Radians since the fingers landed, clockwise positive in screen space
     Rotate { angle: f32, center_x: f32, center_y: f32 },
     Swipe { direction: SwipeDirection, velocity: f32 },
 }
@@ -20,118 +41,588 @@ pub enum SwipeDirection {
     Right,
 }
 
+/// Thresholds; distances are in pixels and velocities in pixels per second
+#[derive(Debug, Clone)]
+pub struct GestureConfig {
+    /// Movement before a touch stops being a tap and becomes a pan
+    pub touch_slop: f32,
+    pub long_press_delay: Duration,
+    pub double_tap_interval: Duration,
+    /// Furthest apart two taps can be and still make a double tap
+    pub double_tap_slop: f32,
+    pub fling_min_velocity: f32,
+    pub swipe_min_velocity: f32,
+    pub swipe_min_distance: f32,
+    /// Span change, as a fraction, before pinch events start
+    pub scale_slop: f32,
+    /// Radians turned before rotate events start
+    pub rotate_slop: f32,
+    /// How far back velocity samples reach
+    pub velocity_window: Duration,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            touch_slop: 8.0,
+            long_press_delay: Duration::from_millis(500),
+            double_tap_interval: Duration::from_millis(300),
+            double_tap_slop: 24.0,
+            fling_min_velocity: 50.0,
+            swipe_min_velocity: 300.0,
+            swipe_min_distance: 50.0,
+            scale_slop: 0.05,
+            rotate_slop: 0.1,
+            velocity_window: Duration::from_millis(100),
+        }
+    }
+}
+
+/// One finger on the screen
+#[derive(Debug, Clone)]
+struct Pointer {
+    start: [f32; 2],
+    position: [f32; 2],
+    samples: VecDeque<(Duration, [f32; 2])>,
+}
+
+impl Pointer {
+    fn new(position: [f32; 2], time: Duration) -> Self {
+        Self {
+            start: position,
+            position,
+            samples: VecDeque::from([(time, position)]),
+        }
+    }
+
+    fn record(&mut self, position: [f32; 2], time: Duration, window: Duration) {
+        self.position = position;
+        self.samples.push_back((time, position));
+        while self.samples.len() > 2 && time.saturating_sub(self.samples[0].0) > window {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Average velocity over the recent samples
+    fn velocity(&self) -> [f32; 2] {
+        let (Some(&(first_time, first)), Some(&(last_time, last))) = (self.samples.front(), self.samples.back()) else {
+            return [0.0, 0.0];
+        };
+        let elapsed = last_time.saturating_sub(first_time).as_secs_f32();
+        if elapsed <= 0.0 {
+            return [0.0, 0.0];
+        }
+        [(last[0] - first[0]) / elapsed, (last[1] - first[1]) / elapsed]
+    }
+
+    fn travelled(&self) -> f32 {
+        distance(self.start, self.position)
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2)).sqrt()
+}
+
+/// Wrap an angle into (-PI, PI]
+fn normalize_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped <= -PI { wrapped + 2.0 * PI } else { wrapped }
+}
+
+/// Touch IDs, span, angle and midpoint of the two fingers a scale follows
+type PairGeometry = ((u32, u32), f32, f32, [f32; 2]);
+
+/// Two-finger state; scale and rotation carry over when the pair changes
+#[derive(Debug, Clone)]
+struct ScaleSession {
+    pair: (u32, u32),
+    start_span: f32,
+    start_angle: f32,
+    base_scale: f32,
+    base_angle: f32,
+    /// Latest totals, which become the base when the pair changes
+    scale: f32,
+    angle: f32,
+    pinching: bool,
+    rotating: bool,
+}
+
 pub struct GestureRecognizer {
-    last_tap_time: Option<Instant>,
-    last_tap_position: Option<[f32; 2]>,
-    press_start_time: Option<Instant>,
-    press_start_position: Option<[f32; 2]>,
-    double_tap_threshold: Duration,
-    long_press_threshold: Duration,
-    tap_distance_threshold: f32,
+    config: GestureConfig,
+    /// Ordered so the pinch pair is always the two lowest IDs
+    pointers: BTreeMap<u32, Pointer>,
+    arena: GestureArena,
+    sequence_start: Option<Duration>,
+    scale: Option<ScaleSession>,
+    /// Time and position of the last tap, for double taps
+    last_tap: Option<(Duration, [f32; 2])>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GestureRecognizer {
     pub fn new() -> Self {
+        Self::with_config(GestureConfig::default())
+    }
+
+    pub fn with_config(config: GestureConfig) -> Self {
         Self {
-            last_tap_time: None,
-            last_tap_position: None,
-            press_start_time: None,
-            press_start_position: None,
-            double_tap_threshold: Duration::from_millis(300),
-            long_press_threshold: Duration::from_millis(500),
-            tap_distance_threshold: 10.0,
-        }
-    }
-    
-    pub fn on_touch_start(&mut self, x: f32, y: f32) {
-        self.press_start_time = Some(Instant::now());
-        self.press_start_position = Some([x, y]);
-    }
-    
-    pub fn on_touch_end(&mut self, x: f32, y: f32) -> Option<GestureEvent> {
-        let Some(start_time) = self.press_start_time else {
-            return None;
-        };
-        
-        let Some(start_pos) = self.press_start_position else {
-            return None;
+            config,
+            pointers: BTreeMap::new(),
+            arena: GestureArena::default(),
+            sequence_start: None,
+            scale: None,
+            last_tap: None,
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Gesture holding the current touch sequence, if decided
+    pub fn active_gesture(&self) -> Option<GestureKind> {
+        self.arena.winner()
+    }
+
+    pub fn on_touch_start(&mut self, touch_id: u32, x: f32, y: f32, time: Duration) -> Vec<GestureEvent> {
+        if self.pointers.is_empty() {
+            self.arena = GestureArena::open(&[
+                GestureKind::Tap,
+                GestureKind::LongPress,
+                GestureKind::Pan,
+                GestureKind::Scale,
+            ]);
+            self.sequence_start = Some(time);
+            self.scale = None;
+        }
+        self.pointers.insert(touch_id, Pointer::new([x, y], time));
+
+        // A second finger can only mean a scale, unless something already won
+        if self.pointers.len() >= 2 && self.arena.accept(GestureKind::Scale) {
+            if self.scale.is_some() {
+                // A third finger may change which two are tracked
+                self.restart_scale();
+            } else {
+                self.start_scale();
+            }
+        }
+
+        Vec::new()
+    }
+
+    pub fn on_touch_move(&mut self, touch_id: u32, x: f32, y: f32, time: Duration) -> Vec<GestureEvent> {
+        let window = self.config.velocity_window;
+        let Some(pointer) = self.pointers.get_mut(&touch_id) else {
+            return Vec::new();
         };
-        
-        let elapsed = start_time.elapsed();
-        let distance = ((x - start_pos[0]).powi(2) + (y - start_pos[1]).powi(2)).sqrt();
-        
-        // Check if it's a tap (short duration and small movement)
-        if elapsed < self.long_press_threshold && distance < self.tap_distance_threshold {
-            // Check for double tap
-            if let (Some(last_time), Some(last_pos)) = (self.last_tap_time, self.last_tap_position) {
-                let tap_interval = Instant::now().duration_since(last_time);
-                let tap_distance = ((x - last_pos[0]).powi(2) + (y - last_pos[1]).powi(2)).sqrt();
-                
-                if tap_interval < self.double_tap_threshold && tap_distance < self.tap_distance_threshold {
-                    self.last_tap_time = None;
-                    self.last_tap_position = None;
-                    return Some(GestureEvent::DoubleTap { x, y });
+        let previous = pointer.position;
+        pointer.record([x, y], time, window);
+        let travelled = pointer.travelled();
+        let start = pointer.start;
+
+        let mut events = self.check_long_press(time);
+
+        if self.arena.is_open() && travelled > self.config.touch_slop {
+            self.arena.reject(GestureKind::Tap);
+            self.arena.reject(GestureKind::LongPress);
+            if self.pointers.len() == 1 && self.arena.accept(GestureKind::Pan) {
+                // The first pan covers the movement inside the slop too
+                events.push(GestureEvent::Pan { x, y, delta_x: x - start[0], delta_y: y - start[1] });
+                return events;
+            }
+        }
+
+        match self.arena.winner() {
+            Some(GestureKind::Pan) if self.pointers.len() == 1 => {
+                let (delta_x, delta_y) = (x - previous[0], y - previous[1]);
+                if delta_x != 0.0 || delta_y != 0.0 {
+                    events.push(GestureEvent::Pan { x, y, delta_x, delta_y });
                 }
             }
-            
-            // Single tap
-            self.last_tap_time = Some(Instant::now());
-            self.last_tap_position = Some([x, y]);
-            return Some(GestureEvent::Tap { x, y });
-        }
-        
-        // Check for long press
-        if elapsed >= self.long_press_threshold && distance < self.tap_distance_threshold {
-            return Some(GestureEvent::LongPress { x, y });
-        }
-        
-        // Reset state
-        self.press_start_time = None;
-        self.press_start_position = None;
-        
-        None
-    }
-    
-    pub fn on_touch_move(&mut self, x: f32, y: f32, prev_x: f32, prev_y: f32) -> Option<GestureEvent> {
-        let delta_x = x - prev_x;
-        let delta_y = y - prev_y;
-        
-        // Simple pan gesture
-        if delta_x.abs() > 0.1 || delta_y.abs() > 0.1 {
-            return Some(GestureEvent::Pan { delta_x, delta_y });
-        }
-        
-        None
-    }
-    
-    pub fn recognize_swipe(&self, start_x: f32, start_y: f32, end_x: f32, end_y: f32, duration: Duration) -> Option<GestureEvent> {
-        let delta_x = end_x - start_x;
-        let delta_y = end_y - start_y;
-        let distance = (delta_x.powi(2) + delta_y.powi(2)).sqrt();
-        
-        // Calculate velocity
-        let velocity = distance / duration.as_secs_f32();
-        
-        // Minimum velocity for swipe detection
-        if velocity < 100.0 {
-            return None;
-        }
-        
-        // Determine direction
-        let direction = if delta_x.abs() > delta_y.abs() {
-            if delta_x > 0.0 {
-                SwipeDirection::Right
-            } else {
-                SwipeDirection::Left
+            Some(GestureKind::Scale) => events.extend(self.update_scale()),
+            _ => {}
+        }
+
+        events
+    }
+
+    pub fn on_touch_end(&mut self, touch_id: u32, x: f32, y: f32, time: Duration) -> Vec<GestureEvent> {
+        let window = self.config.velocity_window;
+        let Some(mut pointer) = self.pointers.remove(&touch_id) else {
+            return Vec::new();
+        };
+        pointer.record([x, y], time, window);
+
+        let mut events = self.check_long_press(time);
+
+        if !self.pointers.is_empty() {
+            // Keep scaling with whichever fingers remain
+            if self.arena.winner() == Some(GestureKind::Scale) {
+                self.restart_scale();
             }
-        } else {
-            if delta_y > 0.0 {
+            return events;
+        }
+
+        match self.arena.sweep() {
+            Some(GestureKind::Tap) => events.push(self.tap(x, y, time)),
+            Some(GestureKind::Pan) => events.extend(self.release_pan(&pointer)),
+            _ => {}
+        }
+
+        self.sequence_start = None;
+        self.scale = None;
+        events
+    }
+
+    /// The system took the touch away, e.g. for a system gesture
+    pub fn on_touch_cancel(&mut self, touch_id: u32) {
+        self.pointers.remove(&touch_id);
+        if self.pointers.is_empty() {
+            self.arena = GestureArena::default();
+            self.sequence_start = None;
+            self.scale = None;
+        }
+    }
+
+    /// Advance time without movement so held fingers can become long presses
+    pub fn tick(&mut self, time: Duration) -> Vec<GestureEvent> {
+        self.check_long_press(time)
+    }
+
+    fn check_long_press(&mut self, time: Duration) -> Vec<GestureEvent> {
+        let Some(start) = self.sequence_start else {
+            return Vec::new();
+        };
+        if !self.arena.is_open()
+            || self.pointers.len() != 1
+            || time.saturating_sub(start) < self.config.long_press_delay
+        {
+            return Vec::new();
+        }
+
+        if self.arena.accept(GestureKind::LongPress) {
+            let position = self.pointers.values().next().map(|pointer| pointer.position).unwrap_or_default();
+            self.last_tap = None;
+            return vec![GestureEvent::LongPress { x: position[0], y: position[1] }];
+        }
+        Vec::new()
+    }
+
+    fn tap(&mut self, x: f32, y: f32, time: Duration) -> GestureEvent {
+        if let Some((last_time, last_position)) = self.last_tap.take()
+            && time.saturating_sub(last_time) <= self.config.double_tap_interval
+            && distance(last_position, [x, y]) <= self.config.double_tap_slop
+        {
+            return GestureEvent::DoubleTap { x, y };
+        }
+        self.last_tap = Some((time, [x, y]));
+        GestureEvent::Tap { x, y }
+    }
+
+    fn release_pan(&mut self, pointer: &Pointer) -> Vec<GestureEvent> {
+        let [velocity_x, velocity_y] = pointer.velocity();
+        let speed = (velocity_x.powi(2) + velocity_y.powi(2)).sqrt();
+        let mut events = Vec::new();
+
+        if speed >= self.config.fling_min_velocity {
+            events.push(GestureEvent::Fling { velocity_x, velocity_y });
+        }
+
+        let delta_x = pointer.position[0] - pointer.start[0];
+        let delta_y = pointer.position[1] - pointer.start[1];
+        if speed >= self.config.swipe_min_velocity && pointer.travelled() >= self.config.swipe_min_distance {
+            let direction = if delta_x.abs() > delta_y.abs() {
+                if delta_x > 0.0 { SwipeDirection::Right } else { SwipeDirection::Left }
+            } else if delta_y > 0.0 {
                 SwipeDirection::Down
             } else {
                 SwipeDirection::Up
+            };
+            events.push(GestureEvent::Swipe { direction, velocity: speed });
+        }
+
+        events
+    }
+
+    /// The two lowest touch IDs, with their span, angle and midpoint
+    fn pair(&self) -> Option<PairGeometry> {
+        let mut pointers = self.pointers.iter();
+        let (&first_id, first) = pointers.next()?;
+        let (&second_id, second) = pointers.next()?;
+        let (a, b) = (first.position, second.position);
+        let span = distance(a, b);
+        let angle = (b[1] - a[1]).atan2(b[0] - a[0]);
+        let center = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+        Some(((first_id, second_id), span, angle, center))
+    }
+
+    fn start_scale(&mut self) {
+        if let Some((pair, span, angle, _)) = self.pair() {
+            self.scale = Some(ScaleSession {
+                pair,
+                start_span: span,
+                start_angle: angle,
+                base_scale: 1.0,
+                base_angle: 0.0,
+                scale: 1.0,
+                angle: 0.0,
+                pinching: false,
+                rotating: false,
+            });
+        }
+    }
+
+    /// The pair changed; fold progress so far into the base and re-measure
+    fn restart_scale(&mut self) {
+        let Some((pair, span, angle, _)) = self.pair() else {
+            return;
+        };
+        let Some(session) = self.scale.as_mut() else {
+            return;
+        };
+        if session.pair == pair {
+            return;
+        }
+        session.pair = pair;
+        session.start_span = span;
+        session.start_angle = angle;
+        session.base_scale = session.scale;
+        session.base_angle = session.angle;
+    }
+
+    fn update_scale(&mut self) -> Vec<GestureEvent> {
+        let Some((pair, span, angle, center)) = self.pair() else {
+            return Vec::new();
+        };
+        if self.scale.as_ref().is_some_and(|session| session.pair != pair) {
+            self.restart_scale();
+        }
+        let (scale_slop, rotate_slop) = (self.config.scale_slop, self.config.rotate_slop);
+        let Some(session) = self.scale.as_mut() else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+        if session.start_span > 0.0 {
+            session.scale = session.base_scale * span / session.start_span;
+            session.pinching |= (session.scale - 1.0).abs() > scale_slop;
+            if session.pinching {
+                events.push(GestureEvent::Pinch {
+                    scale: session.scale,
+                    center_x: center[0],
+                    center_y: center[1],
+                });
             }
+        }
+
+        session.angle = session.base_angle + normalize_angle(angle - session.start_angle);
+        session.rotating |= session.angle.abs() > rotate_slop;
+        if session.rotating {
+            events.push(GestureEvent::Rotate {
+                angle: session.angle,
+                center_x: center[0],
+                center_y: center[1],
+            });
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// One finger down at `from` and up at `to`, `millis` later
+    fn stroke(recognizer: &mut GestureRecognizer, from: [f32; 2], to: [f32; 2], start: u64, millis: u64) -> Vec<GestureEvent> {
+        let mut events = recognizer.on_touch_start(1, from[0], from[1], ms(start));
+        events.extend(recognizer.on_touch_end(1, to[0], to[1], ms(start + millis)));
+        events
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn quick_release_is_a_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        // Wobble inside the touch slop
+        recognizer.on_touch_start(1, 10.0, 10.0, ms(0));
+        assert!(recognizer.on_touch_move(1, 14.0, 13.0, ms(40)).is_empty());
+        let events = recognizer.on_touch_end(1, 14.0, 13.0, ms(80));
+        assert_eq!(events, vec![GestureEvent::Tap { x: 14.0, y: 13.0 }]);
+        assert_eq!(recognizer.active_gesture(), Some(GestureKind::Tap));
+    }
+
+    #[test]
+    fn second_tap_nearby_and_soon_is_a_double_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        assert_eq!(stroke(&mut recognizer, [10.0, 10.0], [10.0, 10.0], 0, 50), vec![GestureEvent::Tap { x: 10.0, y: 10.0 }]);
+        assert_eq!(stroke(&mut recognizer, [20.0, 15.0], [20.0, 15.0], 200, 50), vec![GestureEvent::DoubleTap { x: 20.0, y: 15.0 }]);
+        // A third tap starts over rather than making another double tap
+        assert_eq!(stroke(&mut recognizer, [20.0, 15.0], [20.0, 15.0], 400, 50), vec![GestureEvent::Tap { x: 20.0, y: 15.0 }]);
+    }
+
+    #[test]
+    fn taps_too_late_or_too_far_apart_stay_single() {
+        let mut recognizer = GestureRecognizer::new();
+        stroke(&mut recognizer, [10.0, 10.0], [10.0, 10.0], 0, 50);
+        assert_eq!(stroke(&mut recognizer, [10.0, 10.0], [10.0, 10.0], 400, 50), vec![GestureEvent::Tap { x: 10.0, y: 10.0 }]);
+        assert_eq!(stroke(&mut recognizer, [100.0, 10.0], [100.0, 10.0], 500, 50), vec![GestureEvent::Tap { x: 100.0, y: 10.0 }]);
+    }
+
+    #[test]
+    fn holding_still_is_a_long_press() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 30.0, 40.0, ms(1000));
+        assert!(recognizer.tick(ms(1499)).is_empty());
+        assert_eq!(recognizer.tick(ms(1500)), vec![GestureEvent::LongPress { x: 30.0, y: 40.0 }]);
+        // Only once, and releasing is not a tap
+        assert!(recognizer.tick(ms(1600)).is_empty());
+        assert!(recognizer.on_touch_end(1, 30.0, 40.0, ms(1700)).is_empty());
+        assert_eq!(recognizer.active_gesture(), Some(GestureKind::LongPress));
+    }
+
+    #[test]
+    fn long_press_fires_on_a_late_move_without_ticks() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 30.0, 40.0, ms(0));
+        assert_eq!(recognizer.on_touch_move(1, 32.0, 40.0, ms(600)), vec![GestureEvent::LongPress { x: 32.0, y: 40.0 }]);
+    }
+
+    #[test]
+    fn moving_past_the_slop_pans_instead() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 0.0, ms(0));
+        assert!(recognizer.on_touch_move(1, 5.0, 0.0, ms(100)).is_empty());
+        // The first pan includes the movement inside the slop
+        assert_eq!(recognizer.on_touch_move(1, 20.0, 0.0, ms(200)), vec![GestureEvent::Pan { x: 20.0, y: 0.0, delta_x: 20.0, delta_y: 0.0 }]);
+        assert_eq!(recognizer.on_touch_move(1, 30.0, 5.0, ms(300)), vec![GestureEvent::Pan { x: 30.0, y: 5.0, delta_x: 10.0, delta_y: 5.0 }]);
+        // No movement, no event
+        assert!(recognizer.on_touch_move(1, 30.0, 5.0, ms(400)).is_empty());
+        // Long past the long-press delay, but the pan already won
+        assert!(recognizer.tick(ms(900)).is_empty());
+        assert!(recognizer.on_touch_end(1, 30.0, 5.0, ms(1000)).is_empty());
+        assert_eq!(recognizer.active_gesture(), Some(GestureKind::Pan));
+    }
+
+    #[test]
+    fn fast_release_flings_and_swipes() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 100.0, ms(0));
+        recognizer.on_touch_move(1, 40.0, 100.0, ms(20));
+        recognizer.on_touch_move(1, 80.0, 100.0, ms(40));
+        let events = recognizer.on_touch_end(1, 120.0, 100.0, ms(60));
+
+        // 120px in 60ms
+        assert_eq!(events.len(), 2);
+        let GestureEvent::Fling { velocity_x, velocity_y } = events[0] else {
+            panic!("expected a fling, got {:?}", events[0]);
+        };
+        assert_near(velocity_x, 2000.0);
+        assert_near(velocity_y, 0.0);
+        let GestureEvent::Swipe { direction, velocity } = events[1] else {
+            panic!("expected a swipe, got {:?}", events[1]);
+        };
+        assert_eq!(direction, SwipeDirection::Right);
+        assert_near(velocity, 2000.0);
+    }
+
+    #[test]
+    fn swipe_direction_follows_the_larger_axis() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 100.0, 200.0, ms(0));
+        recognizer.on_touch_move(1, 90.0, 140.0, ms(30));
+        let events = recognizer.on_touch_end(1, 80.0, 80.0, ms(60));
+        assert!(events.iter().any(|event| matches!(event, GestureEvent::Swipe { direction: SwipeDirection::Up, .. })));
+    }
+
+    #[test]
+    fn slow_release_neither_flings_nor_swipes() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 0.0, ms(0));
+        recognizer.on_touch_move(1, 20.0, 0.0, ms(500));
+        recognizer.on_touch_move(1, 21.0, 0.0, ms(1000));
+        assert!(recognizer.on_touch_end(1, 21.0, 0.0, ms(1100)).is_empty());
+    }
+
+    #[test]
+    fn spreading_two_fingers_pinches() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 100.0, 100.0, ms(0));
+        recognizer.on_touch_start(2, 200.0, 100.0, ms(10));
+        assert_eq!(recognizer.active_gesture(), Some(GestureKind::Scale));
+
+        // Inside the scale slop
+        assert!(recognizer.on_touch_move(2, 203.0, 100.0, ms(20)).is_empty());
+        assert_eq!(recognizer.on_touch_move(2, 300.0, 100.0, ms(30)), vec![GestureEvent::Pinch { scale: 2.0, center_x: 200.0, center_y: 100.0 }]);
+        assert_eq!(recognizer.on_touch_move(1, 250.0, 100.0, ms(40)), vec![GestureEvent::Pinch { scale: 0.5, center_x: 275.0, center_y: 100.0 }]);
+
+        recognizer.on_touch_end(1, 250.0, 100.0, ms(50));
+        assert!(recognizer.on_touch_end(2, 300.0, 100.0, ms(60)).is_empty());
+    }
+
+    #[test]
+    fn turning_two_fingers_rotates() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 0.0, ms(0));
+        recognizer.on_touch_start(2, 100.0, 0.0, ms(0));
+
+        // A quarter turn clockwise in screen space, keeping the span
+        let events = recognizer.on_touch_move(2, 0.0, 100.0, ms(20));
+        assert_eq!(events.len(), 1);
+        let GestureEvent::Rotate { angle, center_x, center_y } = events[0] else {
+            panic!("expected a rotation, got {:?}", events[0]);
         };
-        
-        Some(GestureEvent::Swipe { direction, velocity })
+        assert_near(angle, PI / 2.0);
+        assert_eq!((center_x, center_y), (0.0, 50.0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn scale_carries_over_when_the_pair_changes() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 0.0, ms(0));
+        recognizer.on_touch_start(2, 100.0, 0.0, ms(0));
+        recognizer.on_touch_start(3, 0.0, 500.0, ms(0));
+        recognizer.on_touch_move(2, 200.0, 0.0, ms(10));
+
+        // 1 and 3 become the pair, starting from the scale reached so far
+        recognizer.on_touch_end(2, 200.0, 0.0, ms(20));
+        let events = recognizer.on_touch_move(3, 0.0, 250.0, ms(30));
+        let GestureEvent::Pinch { scale, .. } = events[0] else {
+            panic!("expected a pinch, got {:?}", events[0]);
+        };
+        assert_near(scale, 1.0);
+    }
+
+    #[test]
+    fn second_finger_during_a_pan_keeps_panning() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 0.0, 0.0, ms(0));
+        recognizer.on_touch_move(1, 20.0, 0.0, ms(10));
+        recognizer.on_touch_start(2, 100.0, 100.0, ms(20));
+        assert_eq!(recognizer.active_gesture(), Some(GestureKind::Pan));
+        assert!(recognizer.on_touch_move(2, 200.0, 100.0, ms(30)).is_empty());
+    }
+
+    #[test]
+    fn cancelled_touches_recognize_nothing() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_touch_start(1, 10.0, 10.0, ms(0));
+        recognizer.on_touch_cancel(1);
+        assert!(recognizer.tick(ms(1000)).is_empty());
+        assert!(recognizer.on_touch_end(1, 10.0, 10.0, ms(1000)).is_empty());
+        assert_eq!(recognizer.active_gesture(), None);
+    }
+}
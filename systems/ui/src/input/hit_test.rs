@@ -0,0 +1,94 @@
+//! Pointer hit testing
+//!
+//! Elements are tested in the order they are drawn: children after their
+//! parent, siblings by ascending `z_index` and then graph order. The last
+//! element drawn under the pointer wins. Hidden elements and everything
+//! below them are skipped, and a parent whose overflow is not `Visible`
+//! clips its subtree to its bounds.
+
+use playground_core_ecs::World;
+use playground_core_types::{Shared, Handle};
+use crate::error::{UiError, UiResult};
+use crate::element::{ElementGraph, ElementId};
+use crate::components::{UiElementComponent, UiLayoutComponent, UiStyleComponent, ElementBounds, Overflow};
+
+/// Children of `element` in drawing order
+pub async fn children_in_paint_order(
+    element: ElementId,
+    graph: &Shared<ElementGraph>,
+    world: &Handle<World>,
+) -> UiResult<Vec<ElementId>> {
+    let children = graph.read().await.get_children(element).cloned().unwrap_or_default();
+
+    let mut ordered = Vec::with_capacity(children.len());
+    for child in children {
+        let style = world.get_component::<UiStyleComponent>(child).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        ordered.push((style.z_index, child));
+    }
+    // Stable, so equal z-indices keep graph order
+    ordered.sort_by_key(|(z_index, _)| *z_index);
+
+    Ok(ordered.into_iter().map(|(_, child)| child).collect())
+}
+
+/// Topmost visible element under (x, y), searching every tree in the graph
+pub async fn hit_test(
+    x: f32,
+    y: f32,
+    graph: &Shared<ElementGraph>,
+    world: &Handle<World>,
+) -> UiResult<Option<ElementId>> {
+    let roots = graph.read().await.roots();
+    for root in roots.into_iter().rev() {
+        if let Some(hit) = hit_subtree(root, x, y, None, graph, world).await? {
+            return Ok(Some(hit));
+        }
+    }
+    Ok(None)
+}
+
+async fn hit_subtree(
+    element: ElementId,
+    x: f32,
+    y: f32,
+    clip: Option<ElementBounds>,
+    graph: &Shared<ElementGraph>,
+    world: &Handle<World>,
+) -> UiResult<Option<ElementId>> {
+    let component = world.get_component::<UiElementComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    let style = world.get_component::<UiStyleComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?;
+    if !component.visible || !style.visible || style.opacity <= 0.0 {
+        return Ok(None);
+    }
+
+    let bounds = world.get_component::<UiLayoutComponent>(element).await
+        .map_err(|e| UiError::EcsError(e.to_string()))?
+        .bounds;
+    let inside_clip = clip.map_or(true, |clip| clip.contains(x, y));
+
+    let child_clip = if style.overflow == Overflow::Visible {
+        clip
+    } else {
+        Some(clip.map_or(bounds, |clip| clip.intersection(&bounds)))
+    };
+
+    // Last drawn is on top, so children are searched back to front. A clip
+    // that excludes the point rules out the whole subtree.
+    if child_clip.map_or(true, |clip| clip.contains(x, y)) {
+        let children = children_in_paint_order(element, graph, world).await?;
+        for child in children.into_iter().rev() {
+            if let Some(hit) = Box::pin(hit_subtree(child, x, y, child_clip, graph, world)).await? {
+                return Ok(Some(hit));
+            }
+        }
+    }
+
+    if inside_clip && bounds.contains(x, y) {
+        Ok(Some(element))
+    } else {
+        Ok(None)
+    }
+}
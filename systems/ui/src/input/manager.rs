@@ -1,10 +1,14 @@
-use playground_core_ecs::{World, EntityId};
+use playground_core_ecs::World;
 use playground_core_types::{Shared, Handle};
+use playground_core_ui::UiEvent as CoreUiEvent;
 use std::collections::HashMap;
 use crate::error::{UiError, UiResult};
 use crate::element::{ElementGraph, ElementId};
 use crate::components::{UiElementComponent, UiInputComponent, UiTextComponent};
 use super::event::{InputEvent, MouseButton, Key, Modifiers};
+use super::focus::{focus_index, tab_order, next_focus};
+use super::hit_test::hit_test;
+use super::propagation::EventDispatcher;
 
 pub struct InputManager {
     hovered_element: Option<ElementId>,
@@ -12,16 +16,22 @@ pub struct InputManager {
     pressed_element: Option<ElementId>,
     mouse_position: [f32; 2],
     touches: HashMap<u32, [f32; 2]>,
+    /// Listeners for events propagated through the element graph
+    pub dispatcher: EventDispatcher,
+    /// Queue shared with the UI system that Focus and Blur events go to
+    events: Shared<Vec<CoreUiEvent>>,
 }
 
 impl InputManager {
-    pub fn new() -> Self {
+    pub fn new(events: Shared<Vec<CoreUiEvent>>) -> Self {
         Self {
             hovered_element: None,
             focused_element: None,
             pressed_element: None,
             mouse_position: [0.0, 0.0],
             touches: HashMap::new(),
+            dispatcher: EventDispatcher::new(),
+            events,
         }
     }
     
@@ -51,10 +61,10 @@ impl InputManager {
                 }
             }
             InputEvent::KeyDown { key, modifiers } => {
-                self.handle_key_down(key, modifiers, world).await
+                self.handle_key_down(key, modifiers, graph, world).await
             }
             InputEvent::TextInput { text } => {
-                self.handle_text_input(text, world).await
+                self.handle_text_input(text, graph, world).await
             }
             InputEvent::TouchStart { id, x, y } => {
                 self.touches.insert(id, [x, y]);
@@ -76,6 +86,50 @@ impl InputManager {
         self.focused_element
     }
     
+    /// Move focus, updating the focused flags and raising Blur then Focus
+    pub async fn set_focus(
+        &mut self,
+        element: Option<ElementId>,
+        graph: &Shared<ElementGraph>,
+        world: &Handle<World>,
+    ) -> UiResult<()> {
+        if element == self.focused_element {
+            return Ok(());
+        }
+        
+        if let Some(old) = self.focused_element.take() {
+            let _ = world.update_component::<UiElementComponent>(old, |elem| {
+                elem.focused = false;
+            }).await;
+            graph.write().await.mark_render_dirty(old);
+            self.events.write().await.push(CoreUiEvent::Blur { element: old });
+        }
+        
+        if let Some(new) = element {
+            world.update_component::<UiElementComponent>(new, |elem| {
+                elem.focused = true;
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+            graph.write().await.mark_render_dirty(new);
+            self.events.write().await.push(CoreUiEvent::Focus { element: new });
+            self.focused_element = Some(new);
+        }
+        
+        Ok(())
+    }
+    
+    /// Focus the next element in tab order, or the previous when `reverse`
+    pub async fn focus_next(
+        &mut self,
+        reverse: bool,
+        graph: &Shared<ElementGraph>,
+        world: &Handle<World>,
+    ) -> UiResult<Option<ElementId>> {
+        let order = tab_order(graph, world).await?;
+        let next = next_focus(&order, self.focused_element, reverse);
+        self.set_focus(next, graph, world).await?;
+        Ok(next)
+    }
+    
     async fn update_hover(
         &mut self,
         x: f32,
@@ -83,7 +137,7 @@ impl InputManager {
         graph: &Shared<ElementGraph>,
         world: &Handle<World>,
     ) -> UiResult<bool> {
-        let hit = self.hit_test(x, y, graph, world).await?;
+        let hit = hit_test(x, y, graph, world).await?;
         
         if hit != self.hovered_element {
             // Clear old hover
@@ -91,6 +145,7 @@ impl InputManager {
                 let _ = world.update_component::<UiElementComponent>(old, |elem| {
                     elem.hovered = false;
                 }).await;
+                graph.write().await.mark_render_dirty(old);
             }
             
             // Set new hover
@@ -98,6 +153,7 @@ impl InputManager {
                 let _ = world.update_component::<UiElementComponent>(new, |elem| {
                     elem.hovered = true;
                 }).await;
+                graph.write().await.mark_render_dirty(new);
             }
             
             self.hovered_element = hit;
@@ -114,27 +170,14 @@ impl InputManager {
         graph: &Shared<ElementGraph>,
         world: &Handle<World>,
     ) -> UiResult<bool> {
-        let hit = self.hit_test(x, y, graph, world).await?;
+        let hit = hit_test(x, y, graph, world).await?;
         
         if let Some(element) = hit {
             self.pressed_element = Some(element);
             
-            // Update focus
-            if self.focused_element != Some(element) {
-                // Clear old focus
-                if let Some(old) = self.focused_element {
-                    let _ = world.update_component::<UiElementComponent>(old, |elem| {
-                        elem.focused = false;
-                    }).await;
-                }
-                
-                // Set new focus
-                let _ = world.update_component::<UiElementComponent>(element, |elem| {
-                    elem.focused = true;
-                }).await;
-                
-                self.focused_element = Some(element);
-            }
+            // Pressing anything that can't take focus blurs the focused element
+            let focusable = focus_index(element, world).await?.is_some();
+            self.set_focus(focusable.then_some(element), graph, world).await?;
             
             Ok(true)
         } else {
@@ -150,7 +193,7 @@ impl InputManager {
         world: &Handle<World>,
     ) -> UiResult<bool> {
         if let Some(pressed) = self.pressed_element {
-            let hit = self.hit_test(x, y, graph, world).await?;
+            let hit = hit_test(x, y, graph, world).await?;
             
             // Click event if released on same element
             if hit == Some(pressed) {
@@ -173,9 +216,16 @@ impl InputManager {
     async fn handle_key_down(
         &mut self,
         key: Key,
-        _modifiers: Modifiers,
+        modifiers: Modifiers,
+        graph: &Shared<ElementGraph>,
         world: &Handle<World>,
     ) -> UiResult<bool> {
+        // Tab moves focus whether or not anything is focused
+        if key == Key::Tab {
+            self.focus_next(modifiers.shift, graph, world).await?;
+            return Ok(true);
+        }
+        
         if let Some(focused) = self.focused_element {
             // Check if element accepts input - world is Handle<World> now
             let input = world.get_component::<UiInputComponent>(focused).await
@@ -183,18 +233,8 @@ impl InputManager {
             
             if input.accepts_input {
                 // Handle special keys
-                match key {
-                    Key::Tab => {
-                        // Tab navigation would go here
-                    }
-                    Key::Escape => {
-                        // Clear focus - world is Handle<World> now
-                        let _ = world.update_component::<UiElementComponent>(focused, |elem| {
-                            elem.focused = false;
-                        }).await;
-                        self.focused_element = None;
-                    }
-                    _ => {}
+                if key == Key::Escape {
+                    self.set_focus(None, graph, world).await?;
                 }
                 
                 Ok(true)
@@ -209,6 +249,7 @@ impl InputManager {
     async fn handle_text_input(
         &mut self,
         text: String,
+        graph: &Shared<ElementGraph>,
         world: &Handle<World>,
     ) -> UiResult<bool> {
         if let Some(focused) = self.focused_element {
@@ -233,6 +274,9 @@ impl InputManager {
                         elem.text_content = Some(new_text);
                     }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
                     
+                    // New text can change the element's size
+                    graph.write().await.mark_layout_dirty(focused);
+                    
                     return Ok(true);
                 }
             }
@@ -240,20 +284,4 @@ impl InputManager {
         
         Ok(false)
     }
-    
-    async fn hit_test(
-        &self,
-        _x: f32,
-        _y: f32,
-        _graph: &Shared<ElementGraph>,
-        _world: &Handle<World>,
-    ) -> UiResult<Option<ElementId>> {
-        // Simple hit test - find topmost element containing point
-        // In real implementation, would traverse tree in reverse order
-        
-        // For now, just return None
-        // Real implementation would check bounds
-        
-        Ok(None)
-    }
 }
\ No newline at end of file
@@ -1,7 +1,15 @@
 pub mod event;
 pub mod manager;
 pub mod gestures;
+pub mod arena;
+pub mod hit_test;
+pub mod focus;
+pub mod propagation;
 
 pub use event::{InputEvent, MouseButton, Key, Modifiers};
 pub use manager::InputManager;
-pub use gestures::{GestureRecognizer, GestureEvent};
\ No newline at end of file
+pub use gestures::{GestureRecognizer, GestureEvent, GestureConfig, SwipeDirection};
+pub use arena::{GestureArena, GestureKind};
+pub use hit_test::{hit_test, children_in_paint_order};
+pub use focus::{tab_order, next_focus};
+pub use propagation::{EventDispatcher, EventContext, EventPhase, ListenerKind, ListenerId, DispatchedEvent};
\ No newline at end of file
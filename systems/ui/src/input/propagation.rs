@@ -0,0 +1,223 @@
+//! Event propagation
+//!
+//! Events travel through the element graph like DOM events: a capture phase
+//! from the root down to the target's parent, the target itself, then a
+//! bubble phase back up to the root. Listeners are plain data: every event
+//! reaching one is recorded as a `DispatchedEvent` for its owner, and a
+//! `Consume` listener stops the event where it is.
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use playground_core_ui::{UiEvent, EventResult};
+use crate::element::{ElementGraph, ElementId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventPhase {
+    Capture,
+    Target,
+    Bubble,
+}
+
+/// Where an event is on its way through the graph
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EventContext {
+    pub target: ElementId,
+    pub current: ElementId,
+    pub phase: EventPhase,
+}
+
+/// How a listener affects the event it receives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListenerKind {
+    /// Only record the event
+    Observe,
+    /// Record the event and report it `Modified`
+    Modify,
+    /// Record the event and stop its propagation
+    Consume,
+}
+
+impl ListenerKind {
+    fn result(self) -> EventResult {
+        match self {
+            ListenerKind::Observe => EventResult::Ignored,
+            ListenerKind::Modify => EventResult::Modified,
+            ListenerKind::Consume => EventResult::Handled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ListenerId(u64);
+
+/// An event that reached a listener, reported back to its owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispatchedEvent {
+    pub listener: ListenerId,
+    pub event: UiEvent,
+    pub context: EventContext,
+}
+
+struct Listener {
+    id: ListenerId,
+    capture: bool,
+    kind: ListenerKind,
+}
+
+pub struct EventDispatcher {
+    listeners: HashMap<ElementId, Vec<Listener>>,
+    next_id: u64,
+    /// Deliveries since the last `take_dispatched`, oldest first
+    dispatched: Vec<DispatchedEvent>,
+}
+
+impl EventDispatcher {
+    pub fn new() -> Self {
+        Self {
+            listeners: HashMap::new(),
+            next_id: 0,
+            dispatched: Vec::new(),
+        }
+    }
+
+    /// Listen on `element`, during capture if `capture` and bubble otherwise.
+    /// Both kinds run when the element is the target.
+    pub fn add_listener(&mut self, element: ElementId, capture: bool, kind: ListenerKind) -> ListenerId {
+        let id = ListenerId(self.next_id);
+        self.next_id += 1;
+        self.listeners.entry(element).or_default().push(Listener { id, capture, kind });
+        id
+    }
+
+    pub fn remove_listener(&mut self, id: ListenerId) -> bool {
+        for listeners in self.listeners.values_mut() {
+            if let Some(index) = listeners.iter().position(|listener| listener.id == id) {
+                listeners.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn remove_element(&mut self, element: ElementId) {
+        self.listeners.remove(&element);
+    }
+
+    /// Deliveries recorded since the last call, oldest first
+    pub fn take_dispatched(&mut self) -> Vec<DispatchedEvent> {
+        std::mem::take(&mut self.dispatched)
+    }
+
+    /// Send `event` to `target` through its ancestors, recording each
+    /// listener it reaches. Returns `Handled` if a listener stopped it,
+    /// `Modified` if any listener changed it, and `Ignored` otherwise.
+    pub fn dispatch(&mut self, event: &UiEvent, target: ElementId, graph: &ElementGraph) -> EventResult {
+        // Root first
+        let mut path = vec![target];
+        while let Some(parent) = graph.get_parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        path.reverse();
+        let (ancestors, _) = path.split_at(path.len() - 1);
+
+        let mut steps = Vec::with_capacity(path.len() * 2);
+        steps.extend(ancestors.iter().map(|&element| (element, EventPhase::Capture)));
+        steps.push((target, EventPhase::Target));
+        steps.extend(ancestors.iter().rev().map(|&element| (element, EventPhase::Bubble)));
+
+        let mut result = EventResult::Ignored;
+        for (current, phase) in steps {
+            let Some(listeners) = self.listeners.get(&current) else {
+                continue;
+            };
+            let context = EventContext { target, current, phase };
+            for listener in listeners {
+                let runs = match phase {
+                    EventPhase::Capture => listener.capture,
+                    EventPhase::Target => true,
+                    EventPhase::Bubble => !listener.capture,
+                };
+                if !runs {
+                    continue;
+                }
+                self.dispatched.push(DispatchedEvent {
+                    listener: listener.id,
+                    event: event.clone(),
+                    context,
+                });
+                match listener.kind.result() {
+                    EventResult::Handled => return EventResult::Handled,
+                    EventResult::Modified => result = EventResult::Modified,
+                    EventResult::Ignored => {}
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Element an event is addressed to
+pub fn event_target(event: &UiEvent) -> Option<ElementId> {
+    match event {
+        UiEvent::TouchStart { element, .. }
+        | UiEvent::TouchMove { element, .. }
+        | UiEvent::TouchEnd { element, .. }
+        | UiEvent::TouchCancel { element, .. }
+        | UiEvent::Tap { element, .. }
+        | UiEvent::DoubleTap { element, .. }
+        | UiEvent::LongPress { element, .. }
+        | UiEvent::Swipe { element, .. }
+        | UiEvent::Pinch { element, .. }
+        | UiEvent::Rotate { element, .. }
+        | UiEvent::Scroll { element, .. }
+        | UiEvent::ScrollStart { element }
+        | UiEvent::ScrollEnd { element }
+        | UiEvent::TextInput { element, .. }
+        | UiEvent::TextChanged { element, .. }
+        | UiEvent::Focus { element }
+        | UiEvent::Blur { element }
+        | UiEvent::Show { element }
+        | UiEvent::Hide { element }
+        | UiEvent::Mount { element }
+        | UiEvent::Unmount { element }
+        | UiEvent::AnimationCompleted { element, .. }
+        | UiEvent::AnimationCancelled { element, .. }
+        | UiEvent::AccessibilityAction { element, .. } => Some(*element),
+        UiEvent::KeyDown { .. }
+        | UiEvent::KeyUp { .. }
+        | UiEvent::Resize { .. }
+        | UiEvent::OrientationChange { .. } => None,
+    }
+}
+
+/// Screen position of a pointer event, which is hit tested for its target
+pub fn pointer_position(event: &UiEvent) -> Option<(f32, f32)> {
+    match *event {
+        UiEvent::TouchStart { x, y, .. }
+        | UiEvent::TouchMove { x, y, .. }
+        | UiEvent::TouchEnd { x, y, .. }
+        | UiEvent::Tap { x, y, .. }
+        | UiEvent::DoubleTap { x, y, .. }
+        | UiEvent::LongPress { x, y, .. } => Some((x, y)),
+        UiEvent::Pinch { center_x, center_y, .. }
+        | UiEvent::Rotate { center_x, center_y, .. } => Some((center_x, center_y)),
+        _ => None,
+    }
+}
+
+/// The same event addressed to another element
+pub fn retarget(event: &UiEvent, target: ElementId) -> UiEvent {
+    let mut event = event.clone();
+    match &mut event {
+        UiEvent::TouchStart { element, .. }
+        | UiEvent::TouchMove { element, .. }
+        | UiEvent::TouchEnd { element, .. }
+        | UiEvent::Tap { element, .. }
+        | UiEvent::DoubleTap { element, .. }
+        | UiEvent::LongPress { element, .. }
+        | UiEvent::Pinch { element, .. }
+        | UiEvent::Rotate { element, .. } => *element = target,
+        _ => {}
+    }
+    event
+}
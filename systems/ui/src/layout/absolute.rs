@@ -17,7 +17,7 @@ impl AbsoluteLayout {
         _graph: &Shared<ElementGraph>,
         world: &Handle<World>,
         screen_size: [f32; 2],
-    ) -> UiResult<()> {
+    ) -> UiResult<Vec<EntityId>> {
         // Absolute positioning - elements use their set positions
         let layout = world.get_component::<UiLayoutComponent>(entity).await
             .map_err(|e| UiError::EcsError(e.to_string()))?;
@@ -42,6 +42,7 @@ impl AbsoluteLayout {
             }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
         }
         
-        Ok(())
+        // Children keep the positions they were given
+        Ok(Vec::new())
     }
 }
\ No newline at end of file
@@ -1,8 +1,150 @@
+//! Docking layout
+//!
+//! A docking container splits its content box into one pane per child,
+//! side by side for rows and stacked for columns. Pane sizes follow the
+//! container's `split_ratios`, with the flex gap along that axis used as the
+//! splitter between panes. Nesting docking containers gives IDE-style panel
+//! arrangements; `resize_split` moves a splitter and stores the new ratios.
+
 use playground_core_ecs::{World, EntityId};
 use playground_core_types::{Shared, Handle};
-use crate::error::UiResult;
+use crate::error::{UiError, UiResult};
 use crate::element::ElementGraph;
-use super::absolute::AbsoluteLayout;
+use crate::components::{UiLayoutComponent, ElementBounds, FlexDirection, PositionType};
+
+/// Size limits of one pane along the split axis
+#[derive(Debug, Clone, Copy)]
+pub struct SplitPane {
+    pub ratio: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl SplitPane {
+    fn from_layout(layout: &UiLayoutComponent, ratio: f32, axis: usize) -> Self {
+        let (min, max) = if axis == 0 {
+            (layout.min_size.width, layout.max_size.width)
+        } else {
+            (layout.min_size.height, layout.max_size.height)
+        };
+        Self {
+            ratio,
+            min: min.unwrap_or(0.0),
+            max: max.unwrap_or(f32::INFINITY),
+        }
+    }
+
+    fn clamp(&self, value: f32) -> f32 {
+        value.min(self.max).max(self.min)
+    }
+}
+
+/// Split axis and splitter width of a docking container
+fn split_axis(layout: &UiLayoutComponent) -> (usize, f32) {
+    match layout.flex_direction {
+        FlexDirection::Row | FlexDirection::RowReverse => (0, layout.column_gap),
+        FlexDirection::Column | FlexDirection::ColumnReverse => (1, layout.row_gap),
+    }
+}
+
+/// Ratio of pane `index`; panes without one get an equal share, and a pane
+/// collapsed to 0 stays collapsed
+fn ratio_at(ratios: &[f32], index: usize) -> f32 {
+    ratios.get(index).map_or(1.0, |ratio| ratio.max(0.0))
+}
+
+/// Pane sizes along the split axis. Space is shared by ratio; panes that hit
+/// their limits are fixed there and the rest is shared again.
+pub fn split_sizes(available: f32, panes: &[SplitPane]) -> Vec<f32> {
+    let mut sizes = vec![0.0; panes.len()];
+    let mut frozen = vec![false; panes.len()];
+
+    while frozen.iter().any(|frozen| !frozen) {
+        let fixed: f32 = sizes.iter().zip(&frozen).filter(|(_, frozen)| **frozen).map(|(size, _)| size).sum();
+        let total: f32 = panes.iter().zip(&frozen).filter(|(_, frozen)| !**frozen).map(|(pane, _)| pane.ratio).sum();
+        let free = (available - fixed).max(0.0);
+
+        let mut violation = 0.0;
+        // Panes that all have a ratio of 0 get nothing beyond their minimum
+        let share = |ratio: f32| if total > 0.0 { free * ratio / total } else { 0.0 };
+
+        let mut targets = sizes.clone();
+        for (index, pane) in panes.iter().enumerate().filter(|(index, _)| !frozen[*index]) {
+            let target = share(pane.ratio);
+            targets[index] = pane.clamp(target);
+            violation += targets[index] - target;
+        }
+
+        // Freeze the panes clamped in the direction of the total violation
+        for (index, frozen) in frozen.iter_mut().enumerate().filter(|(_, frozen)| !**frozen) {
+            let difference = targets[index] - share(panes[index].ratio);
+            if violation.abs() < f32::EPSILON
+                || (violation > 0.0 && difference > 0.0)
+                || (violation < 0.0 && difference < 0.0)
+            {
+                *frozen = true;
+            }
+            sizes[index] = targets[index];
+        }
+    }
+
+    sizes
+}
+
+/// Lay panes out in a content box of `size`, relative to its origin
+pub fn compute_split(size: [f32; 2], axis: usize, splitter: f32, panes: &[SplitPane]) -> Vec<ElementBounds> {
+    let splitters = splitter * panes.len().saturating_sub(1) as f32;
+    let sizes = split_sizes((size[axis] - splitters).max(0.0), panes);
+
+    let mut position = 0.0;
+    sizes.into_iter().map(|extent| {
+        let bounds = if axis == 0 {
+            ElementBounds { x: position, y: 0.0, width: extent, height: size[1] }
+        } else {
+            ElementBounds { x: 0.0, y: position, width: size[0], height: extent }
+        };
+        position += extent + splitter;
+        bounds
+    }).collect()
+}
+
+/// Move the splitter after pane `splitter` by `delta` pixels. The two
+/// neighbouring panes trade space within their limits; the others keep their
+/// size. Returns ratios proportional to the new pane sizes.
+pub fn resize_split(sizes: &[f32], panes: &[SplitPane], splitter: usize, delta: f32) -> Vec<f32> {
+    let mut sizes = sizes.to_vec();
+    if splitter + 1 < sizes.len() {
+        let (before, after) = (&panes[splitter], &panes[splitter + 1]);
+
+        // Largest move both panes allow
+        let delta = if delta > 0.0 {
+            delta.min((before.max - sizes[splitter]).min(sizes[splitter + 1] - after.min).max(0.0))
+        } else {
+            delta.max(-(sizes[splitter] - before.min).min(after.max - sizes[splitter + 1]).max(0.0))
+        };
+        sizes[splitter] += delta;
+        sizes[splitter + 1] -= delta;
+    }
+
+    let total: f32 = sizes.iter().sum();
+    if total > 0.0 {
+        sizes.iter().map(|size| size / total).collect()
+    } else {
+        vec![1.0; sizes.len()]
+    }
+}
+
+/// Index of the splitter under `point`, given the pane bounds it separates
+pub fn splitter_at(panes: &[ElementBounds], axis: usize, point: [f32; 2]) -> Option<usize> {
+    panes.windows(2).position(|pair| {
+        let (start, end) = if axis == 0 {
+            (pair[0].x + pair[0].width, pair[1].x)
+        } else {
+            (pair[0].y + pair[0].height, pair[1].y)
+        };
+        point[axis] >= start && point[axis] <= end
+    })
+}
 
 pub struct DockingLayout;
 
@@ -10,16 +152,216 @@ impl DockingLayout {
     pub fn new() -> Self {
         Self
     }
-    
+
+    /// Lay out the panes of `entity`, returning those whose bounds changed
     pub async fn calculate(
         &mut self,
         entity: EntityId,
         graph: &Shared<ElementGraph>,
         world: &Handle<World>,
-        screen_size: [f32; 2],
+        _screen_size: [f32; 2],
+    ) -> UiResult<Vec<EntityId>> {
+        let layout = world.get_component::<UiLayoutComponent>(entity).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        let bounds = layout.bounds;
+        let [top, right, bottom, left] = layout.padding;
+        let (axis, splitter) = split_axis(&layout);
+
+        let (panes, children, previous) = self.panes(entity, &layout, axis, graph, world).await?;
+        let size = [
+            (bounds.width - left - right).max(0.0),
+            (bounds.height - top - bottom).max(0.0),
+        ];
+
+        let mut changed = Vec::new();
+        let placed = compute_split(size, axis, splitter, &panes);
+        for ((child, old_bounds), rect) in children.into_iter().zip(previous).zip(placed) {
+            let new_bounds = ElementBounds {
+                x: bounds.x + left + rect.x,
+                y: bounds.y + top + rect.y,
+                width: rect.width,
+                height: rect.height,
+            };
+            if old_bounds == new_bounds {
+                continue;
+            }
+            world.update_component::<UiLayoutComponent>(child, |layout| {
+                layout.bounds = new_bounds;
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+            changed.push(child);
+        }
+
+        Ok(changed)
+    }
+
+    /// Drag the splitter after pane `splitter` of a docking container by
+    /// `delta` pixels, storing the resulting ratios on the container
+    pub async fn resize_split(
+        &mut self,
+        entity: EntityId,
+        splitter: usize,
+        delta: f32,
+        graph: &Shared<ElementGraph>,
+        world: &Handle<World>,
     ) -> UiResult<()> {
-        // Simplified docking layout
-        // For now, just use absolute layout
-        AbsoluteLayout::new().calculate(entity, graph, world, screen_size).await
+        let layout = world.get_component::<UiLayoutComponent>(entity).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        let (axis, _) = split_axis(&layout);
+
+        let (panes, children, previous) = self.panes(entity, &layout, axis, graph, world).await?;
+        if splitter + 1 >= children.len() {
+            return Err(UiError::LayoutError(format!(
+                "Splitter {} out of range for {} panes", splitter, children.len()
+            )));
+        }
+
+        let sizes: Vec<f32> = previous.iter()
+            .map(|bounds| if axis == 0 { bounds.width } else { bounds.height })
+            .collect();
+
+        let ratios = resize_split(&sizes, &panes, splitter, delta);
+        world.update_component::<UiLayoutComponent>(entity, |layout| {
+            layout.split_ratios = ratios;
+        }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Flow children of a docking container with their pane limits and
+    /// current bounds
+    async fn panes(
+        &self,
+        entity: EntityId,
+        layout: &UiLayoutComponent,
+        axis: usize,
+        graph: &Shared<ElementGraph>,
+        world: &Handle<World>,
+    ) -> UiResult<(Vec<SplitPane>, Vec<EntityId>, Vec<ElementBounds>)> {
+        let children = graph.read().await.get_children(entity).cloned().unwrap_or_default();
+
+        let mut panes = Vec::new();
+        let mut flow = Vec::new();
+        let mut bounds = Vec::new();
+        for child in children {
+            let child_layout = world.get_component::<UiLayoutComponent>(child).await
+                .map_err(|e| UiError::EcsError(e.to_string()))?;
+            if child_layout.position_type != PositionType::Relative {
+                continue;
+            }
+            panes.push(SplitPane::from_layout(&child_layout, ratio_at(&layout.split_ratios, flow.len()), axis));
+            flow.push(child);
+            bounds.push(child_layout.bounds);
+        }
+
+        Ok((panes, flow, bounds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(ratio: f32, min: f32) -> SplitPane {
+        SplitPane { ratio, min, max: f32::INFINITY }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> ElementBounds {
+        ElementBounds { x, y, width, height }
+    }
+
+    fn assert_sizes(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < 1e-3, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    /// The same panes with the ratios `resize_split` returned
+    fn with_ratios(panes: &[SplitPane], ratios: &[f32]) -> Vec<SplitPane> {
+        panes.iter().zip(ratios).map(|(pane, ratio)| SplitPane { ratio: *ratio, ..*pane }).collect()
+    }
+
+    #[test]
+    fn panes_split_by_ratio_with_splitters() {
+        let panes = [pane(1.0, 0.0), pane(2.0, 0.0)];
+        assert_eq!(compute_split([310.0, 100.0], 0, 10.0, &panes), [
+            rect(0.0, 0.0, 100.0, 100.0),
+            rect(110.0, 0.0, 200.0, 100.0),
+        ]);
+        assert_eq!(compute_split([100.0, 310.0], 1, 10.0, &panes), [
+            rect(0.0, 0.0, 100.0, 100.0),
+            rect(0.0, 110.0, 100.0, 200.0),
+        ]);
+    }
+
+    #[test]
+    fn panes_below_their_minimum_are_fixed_there() {
+        let panes = [pane(1.0, 100.0), pane(3.0, 0.0)];
+        // By ratio the first pane would get 75
+        assert_eq!(split_sizes(300.0, &panes), [100.0, 200.0]);
+    }
+
+    #[test]
+    fn resize_moves_space_between_neighbours() {
+        let panes = [pane(1.0, 80.0), pane(2.0, 80.0)];
+        let sizes = split_sizes(300.0, &panes);
+        assert_eq!(sizes, [100.0, 200.0]);
+
+        let ratios = resize_split(&sizes, &panes, 0, 50.0);
+        assert_sizes(&ratios, &[0.5, 0.5]);
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &ratios)), &[150.0, 150.0]);
+    }
+
+    #[test]
+    fn resize_clamps_at_the_minimum() {
+        let panes = [pane(1.0, 80.0), pane(2.0, 80.0)];
+        let sizes = [100.0, 200.0];
+
+        // Shrinking the first pane stops at its minimum of 80
+        let ratios = resize_split(&sizes, &panes, 0, -50.0);
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &ratios)), &[80.0, 220.0]);
+
+        // Growing it stops where the second pane reaches its minimum
+        let ratios = resize_split(&sizes, &panes, 0, 500.0);
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &ratios)), &[220.0, 80.0]);
+    }
+
+    #[test]
+    fn resize_clamps_at_the_maximum() {
+        let panes = [SplitPane { ratio: 1.0, min: 0.0, max: 120.0 }, pane(2.0, 0.0)];
+        let ratios = resize_split(&[100.0, 200.0], &panes, 0, 50.0);
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &ratios)), &[120.0, 180.0]);
+    }
+
+    #[test]
+    fn resize_leaves_other_panes_alone() {
+        let panes = [pane(1.0, 0.0), pane(1.0, 0.0), pane(1.0, 0.0)];
+        let ratios = resize_split(&[100.0, 100.0, 100.0], &panes, 1, 40.0);
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &ratios)), &[100.0, 140.0, 60.0]);
+    }
+
+    #[test]
+    fn collapsed_pane_stays_collapsed() {
+        let panes = [pane(1.0, 0.0), pane(2.0, 0.0)];
+        let ratios = resize_split(&[100.0, 200.0], &panes, 0, -500.0);
+        assert_sizes(&ratios, &[0.0, 1.0]);
+
+        // The ratios as the container stores and reads them back
+        let stored = [ratio_at(&ratios, 0), ratio_at(&ratios, 1)];
+        assert_sizes(&split_sizes(300.0, &with_ratios(&panes, &stored)), &[0.0, 300.0]);
+        assert_eq!(ratio_at(&ratios, 2), 1.0);
+    }
+
+    #[test]
+    fn panes_without_a_ratio_keep_their_minimum() {
+        let panes = [pane(0.0, 50.0), pane(0.0, 0.0)];
+        assert_eq!(split_sizes(300.0, &panes), [50.0, 0.0]);
+    }
+
+    #[test]
+    fn splitter_hit_testing() {
+        let panes = compute_split([310.0, 100.0], 0, 10.0, &[pane(1.0, 0.0), pane(2.0, 0.0)]);
+        assert_eq!(splitter_at(&panes, 0, [105.0, 50.0]), Some(0));
+        assert_eq!(splitter_at(&panes, 0, [50.0, 50.0]), None);
     }
-}
\ No newline at end of file
+}
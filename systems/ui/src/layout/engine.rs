@@ -2,46 +2,69 @@ use playground_core_ecs::{World, EntityId};
 use playground_core_types::{Shared, Handle};
 use crate::error::{UiError, UiResult};
 use crate::element::ElementGraph;
-use crate::components::{UiLayoutComponent, LayoutType};
+use crate::components::{UiLayoutComponent, LayoutType, ElementBounds};
+use crate::text::FontManager;
 use super::{flexbox::FlexboxLayout, absolute::AbsoluteLayout, docking::DockingLayout};
 
 pub struct LayoutEngine {
     flexbox: FlexboxLayout,
     absolute: AbsoluteLayout,
     docking: DockingLayout,
+    fonts: Shared<FontManager>,
 }
 
 impl LayoutEngine {
-    pub fn new() -> Self {
+    pub fn new(fonts: Shared<FontManager>) -> Self {
         Self {
             flexbox: FlexboxLayout::new(),
             absolute: AbsoluteLayout::new(),
             docking: DockingLayout::new(),
+            fonts,
         }
     }
     
+    /// Lay out the children of `entity`, returning those whose bounds changed.
+    /// A root element is sized to the screen first.
     pub async fn calculate_layout(
         &mut self,
         entity: EntityId,
         graph: &Shared<ElementGraph>,
         world: &Handle<World>,
         screen_size: [f32; 2],
-    ) -> UiResult<()> {
+    ) -> UiResult<Vec<EntityId>> {
+        if graph.read().await.get_parent(entity).is_none() {
+            let screen = ElementBounds { x: 0.0, y: 0.0, width: screen_size[0], height: screen_size[1] };
+            world.update_component::<UiLayoutComponent>(entity, |layout| {
+                layout.bounds = screen;
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+        }
+        
         let layout = world.get_component::<UiLayoutComponent>(entity).await
             .map_err(|e| UiError::EcsError(e.to_string()))?;
         
         match layout.layout_type {
             LayoutType::Flexbox => {
-                self.flexbox.calculate(entity, graph, world, screen_size).await?;
+                let fonts = self.fonts.read().await;
+                self.flexbox.calculate(entity, graph, world, &fonts, screen_size).await
             }
             LayoutType::Absolute => {
-                self.absolute.calculate(entity, graph, world, screen_size).await?;
+                self.absolute.calculate(entity, graph, world, screen_size).await
             }
             LayoutType::Docking => {
-                self.docking.calculate(entity, graph, world, screen_size).await?;
+                self.docking.calculate(entity, graph, world, screen_size).await
             }
         }
-        
-        Ok(())
+    }
+    
+    /// Move a splitter of a docking container by `delta` pixels
+    pub async fn resize_split(
+        &mut self,
+        entity: EntityId,
+        splitter: usize,
+        delta: f32,
+        graph: &Shared<ElementGraph>,
+        world: &Handle<World>,
+    ) -> UiResult<()> {
+        self.docking.resize_split(entity, splitter, delta, graph, world).await
     }
 }
\ No newline at end of file
@@ -1,8 +1,318 @@
+//! Flexbox layout
+//!
+//! `compute_flex` places items inside a container's content box following
+//! the CSS flexbox algorithm: lines are collected, flexible lengths are
+//! resolved with min/max freezing, then items are justified and aligned.
+//! Baseline alignment falls back to `FlexStart`, and wrapped lines are
+//! packed at the cross start. `FlexboxLayout::calculate` reads the
+//! components, runs it and writes child bounds back.
+
+use std::ops::Range;
 use playground_core_ecs::{World, EntityId};
 use playground_core_types::{Shared, Handle};
 use crate::error::{UiError, UiResult};
 use crate::element::ElementGraph;
-use crate::components::{UiLayoutComponent, ElementBounds};
+use crate::components::{
+    UiElementComponent, UiLayoutComponent, UiStyleComponent, ElementBounds,
+    FlexDirection, FlexWrap, JustifyContent, AlignItems, PositionType, Size,
+};
+use crate::text::{FontManager, TextStyle};
+
+/// Container properties, with `size` being its content box
+#[derive(Debug, Clone)]
+pub struct FlexContainer {
+    pub size: [f32; 2],
+    pub direction: FlexDirection,
+    pub wrap: FlexWrap,
+    pub justify_content: JustifyContent,
+    pub align_items: AlignItems,
+    pub row_gap: f32,
+    pub column_gap: f32,
+}
+
+impl FlexContainer {
+    pub fn from_layout(layout: &UiLayoutComponent, size: [f32; 2]) -> Self {
+        Self {
+            size,
+            direction: layout.flex_direction,
+            wrap: layout.flex_wrap,
+            justify_content: layout.justify_content,
+            align_items: layout.align_items,
+            row_gap: layout.row_gap,
+            column_gap: layout.column_gap,
+        }
+    }
+}
+
+/// Item properties; sizes are border boxes, margins are top, right, bottom, left
+#[derive(Debug, Clone)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    pub basis: Option<f32>,
+    pub size: Size,
+    pub min_size: Size,
+    pub max_size: Size,
+    pub margin: [f32; 4],
+    pub align_self: Option<AlignItems>,
+}
+
+impl FlexItem {
+    pub fn from_layout(layout: &UiLayoutComponent) -> Self {
+        Self {
+            grow: layout.flex_grow,
+            shrink: layout.flex_shrink,
+            basis: layout.flex_basis,
+            size: layout.size,
+            min_size: layout.min_size,
+            max_size: layout.max_size,
+            margin: layout.margin,
+            align_self: layout.align_self,
+        }
+    }
+}
+
+fn axis_value(size: &Size, axis: usize) -> Option<f32> {
+    if axis == 0 { size.width } else { size.height }
+}
+
+/// Margins at the start and end of an axis, in flow order
+fn axis_margins(margin: &[f32; 4], axis: usize, reverse: bool) -> (f32, f32) {
+    let (start, end) = if axis == 0 { (margin[3], margin[1]) } else { (margin[0], margin[2]) };
+    if reverse { (end, start) } else { (start, end) }
+}
+
+/// Per-item values along one axis
+struct AxisLimits {
+    min: f32,
+    max: f32,
+}
+
+impl AxisLimits {
+    fn new(item: &FlexItem, axis: usize) -> Self {
+        Self {
+            min: axis_value(&item.min_size, axis).unwrap_or(0.0),
+            max: axis_value(&item.max_size, axis).unwrap_or(f32::INFINITY),
+        }
+    }
+
+    /// Min wins over max, as in CSS
+    fn clamp(&self, value: f32) -> f32 {
+        value.min(self.max).max(self.min)
+    }
+}
+
+/// Lay items out in a container. `measure(index, width)` returns an item's
+/// content size when its border box is `width` wide, or unconstrained for
+/// None. Returned bounds are relative to the container's content box.
+pub fn compute_flex(
+    container: &FlexContainer,
+    items: &[FlexItem],
+    mut measure: impl FnMut(usize, Option<f32>) -> [f32; 2],
+) -> Vec<ElementBounds> {
+    let row = matches!(container.direction, FlexDirection::Row | FlexDirection::RowReverse);
+    let (main, cross) = if row { (0, 1) } else { (1, 0) };
+    let reverse_main = matches!(container.direction, FlexDirection::RowReverse | FlexDirection::ColumnReverse);
+    let reverse_cross = container.wrap == FlexWrap::WrapReverse;
+    let (main_gap, cross_gap) = if row {
+        (container.column_gap, container.row_gap)
+    } else {
+        (container.row_gap, container.column_gap)
+    };
+    let container_main = container.size[main];
+    let container_cross = container.size[cross];
+
+    let stretches = |item: &FlexItem| {
+        item.align_self.unwrap_or(container.align_items) == AlignItems::Stretch
+            && axis_value(&item.size, cross).is_none()
+    };
+
+    // Flex base and hypothetical main sizes
+    let main_limits: Vec<AxisLimits> = items.iter().map(|item| AxisLimits::new(item, main)).collect();
+    let cross_limits: Vec<AxisLimits> = items.iter().map(|item| AxisLimits::new(item, cross)).collect();
+    let main_margins: Vec<(f32, f32)> = items.iter().map(|item| axis_margins(&item.margin, main, reverse_main)).collect();
+    let cross_margins: Vec<(f32, f32)> = items.iter().map(|item| axis_margins(&item.margin, cross, reverse_cross)).collect();
+
+    let base: Vec<f32> = items.iter().enumerate().map(|(index, item)| {
+        item.basis.or(axis_value(&item.size, main)).unwrap_or_else(|| {
+            if row {
+                measure(index, None)[0]
+            } else {
+                // A column item's height depends on the width it gets
+                let width = axis_value(&item.size, 0).or_else(|| {
+                    stretches(item).then(|| container_cross - cross_margins[index].0 - cross_margins[index].1)
+                });
+                measure(index, width)[1]
+            }
+        })
+    }).collect();
+    let hypothetical: Vec<f32> = base.iter().zip(&main_limits).map(|(base, limits)| limits.clamp(*base)).collect();
+    let outer = |index: usize, size: f32| size + main_margins[index].0 + main_margins[index].1;
+
+    // Collect lines
+    let mut lines: Vec<Range<usize>> = Vec::new();
+    let mut line_start = 0;
+    let mut line_main = 0.0;
+    for index in 0..items.len() {
+        let size = outer(index, hypothetical[index]);
+        if container.wrap != FlexWrap::NoWrap && index > line_start && line_main + main_gap + size > container_main {
+            lines.push(line_start..index);
+            line_start = index;
+            line_main = size;
+        } else {
+            line_main += if index > line_start { main_gap + size } else { size };
+        }
+    }
+    if line_start < items.len() {
+        lines.push(line_start..items.len());
+    }
+
+    // Resolve flexible lengths per line
+    let mut main_sizes = hypothetical.clone();
+    for line in &lines {
+        let gaps = main_gap * (line.len() as f32 - 1.0);
+        let margins: f32 = line.clone().map(|index| main_margins[index].0 + main_margins[index].1).sum();
+        let available = container_main - gaps - margins;
+        let growing = line.clone().map(|index| hypothetical[index]).sum::<f32>() < available;
+
+        let mut frozen: Vec<bool> = line.clone().map(|index| {
+            let factor = if growing { items[index].grow } else { items[index].shrink };
+            factor <= 0.0
+                || (growing && base[index] > hypothetical[index])
+                || (!growing && base[index] < hypothetical[index])
+        }).collect();
+        let mut target: Vec<f32> = line.clone().map(|index| hypothetical[index]).collect();
+
+        while frozen.iter().any(|frozen| !frozen) {
+            let used: f32 = line.clone().enumerate()
+                .map(|(slot, index)| if frozen[slot] { target[slot] } else { base[index] })
+                .sum();
+            let free = available - used;
+
+            let unfrozen = || line.clone().enumerate().filter(|(slot, _)| !frozen[*slot]);
+            if growing {
+                let total: f32 = unfrozen().map(|(_, index)| items[index].grow).sum();
+                // Factors summing below one only take that share of the free space
+                let free = if total < 1.0 { free * total } else { free };
+                for (slot, index) in unfrozen().collect::<Vec<_>>() {
+                    target[slot] = base[index] + free * items[index].grow / total;
+                }
+            } else {
+                let total: f32 = unfrozen().map(|(_, index)| items[index].shrink * base[index]).sum();
+                for (slot, index) in unfrozen().collect::<Vec<_>>() {
+                    let share = if total > 0.0 { items[index].shrink * base[index] / total } else { 0.0 };
+                    target[slot] = base[index] + free * share;
+                }
+            }
+
+            // Clamp and freeze the violations in the direction of the total
+            let mut violation = 0.0;
+            let mut clamped = target.clone();
+            for (slot, index) in unfrozen().collect::<Vec<_>>() {
+                clamped[slot] = main_limits[index].clamp(target[slot]);
+                violation += clamped[slot] - target[slot];
+            }
+            for (slot, _) in unfrozen().collect::<Vec<_>>() {
+                let difference = clamped[slot] - target[slot];
+                if violation.abs() < f32::EPSILON
+                    || (violation > 0.0 && difference > 0.0)
+                    || (violation < 0.0 && difference < 0.0)
+                {
+                    frozen[slot] = true;
+                }
+                target[slot] = clamped[slot];
+            }
+        }
+
+        for (slot, index) in line.clone().enumerate() {
+            main_sizes[index] = target[slot];
+        }
+    }
+
+    // Cross sizes; stretched items wait for their line's size
+    let mut cross_sizes: Vec<Option<f32>> = items.iter().enumerate().map(|(index, item)| {
+        match axis_value(&item.size, cross) {
+            Some(size) => Some(cross_limits[index].clamp(size)),
+            None if stretches(item) => None,
+            None => {
+                let content = if row { measure(index, Some(main_sizes[index]))[1] } else { measure(index, None)[0] };
+                Some(cross_limits[index].clamp(content))
+            }
+        }
+    }).collect();
+
+    let line_cross: Vec<f32> = lines.iter().map(|line| {
+        if container.wrap == FlexWrap::NoWrap {
+            return container_cross;
+        }
+        line.clone().map(|index| {
+            let size = cross_sizes[index].unwrap_or_else(|| {
+                let content = if row { measure(index, Some(main_sizes[index]))[1] } else { measure(index, None)[0] };
+                cross_limits[index].clamp(content)
+            });
+            size + cross_margins[index].0 + cross_margins[index].1
+        }).fold(0.0, f32::max)
+    }).collect();
+
+    // Position items
+    let mut bounds = vec![ElementBounds { x: 0.0, y: 0.0, width: 0.0, height: 0.0 }; items.len()];
+    let mut cross_position = 0.0;
+    for (line, line_size) in lines.iter().zip(&line_cross) {
+        let count = line.len() as f32;
+        let used: f32 = line.clone().map(|index| outer(index, main_sizes[index])).sum::<f32>()
+            + main_gap * (count - 1.0);
+        let free = container_main - used;
+        let (mut position, spacing) = match container.justify_content {
+            JustifyContent::FlexStart => (0.0, 0.0),
+            JustifyContent::FlexEnd => (free, 0.0),
+            JustifyContent::Center => (free / 2.0, 0.0),
+            JustifyContent::SpaceBetween if free > 0.0 && count > 1.0 => (0.0, free / (count - 1.0)),
+            JustifyContent::SpaceBetween => (0.0, 0.0),
+            JustifyContent::SpaceAround if free > 0.0 => (free / count / 2.0, free / count),
+            JustifyContent::SpaceEvenly if free > 0.0 => (free / (count + 1.0), free / (count + 1.0)),
+            JustifyContent::SpaceAround | JustifyContent::SpaceEvenly => (free / 2.0, 0.0),
+        };
+
+        for index in line.clone() {
+            let (margin_start, margin_end) = main_margins[index];
+            let (cross_start, cross_end) = cross_margins[index];
+            let size = cross_sizes[index].unwrap_or_else(|| {
+                cross_limits[index].clamp(line_size - cross_start - cross_end)
+            });
+            cross_sizes[index] = Some(size);
+
+            let free_cross = line_size - size - cross_start - cross_end;
+            let offset = match items[index].align_self.unwrap_or(container.align_items) {
+                AlignItems::FlexStart | AlignItems::Stretch | AlignItems::Baseline => 0.0,
+                AlignItems::FlexEnd => free_cross,
+                AlignItems::Center => free_cross / 2.0,
+            };
+
+            let mut main_start = position + margin_start;
+            let mut cross_at = cross_position + cross_start + offset;
+            if reverse_main {
+                main_start = container_main - main_start - main_sizes[index];
+            }
+            if reverse_cross {
+                cross_at = container_cross - cross_at - size;
+            }
+
+            let mut origin = [0.0; 2];
+            let mut extent = [0.0; 2];
+            origin[main] = main_start;
+            origin[cross] = cross_at;
+            extent[main] = main_sizes[index];
+            extent[cross] = size;
+            bounds[index] = ElementBounds { x: origin[0], y: origin[1], width: extent[0], height: extent[1] };
+
+            position += margin_start + main_sizes[index] + margin_end + main_gap + spacing;
+        }
+
+        cross_position += line_size + cross_gap;
+    }
+
+    bounds
+}
 
 pub struct FlexboxLayout;
 
@@ -10,61 +320,382 @@ impl FlexboxLayout {
     pub fn new() -> Self {
         Self
     }
-    
+
+    /// Lay out the children of `entity`, returning those whose bounds changed
     pub async fn calculate(
         &mut self,
         entity: EntityId,
         graph: &Shared<ElementGraph>,
         world: &Handle<World>,
-        screen_size: [f32; 2],
-    ) -> UiResult<()> {
-        // Simplified flexbox layout - world is Handle<World> now
-        // Get the current layout for reading
+        fonts: &FontManager,
+        _screen_size: [f32; 2],
+    ) -> UiResult<Vec<EntityId>> {
         let layout = world.get_component::<UiLayoutComponent>(entity).await
             .map_err(|e| UiError::EcsError(e.to_string()))?;
-        
-        let mut bounds = layout.bounds;
-        let padding = layout.padding;
-        
-        // Update bounds if needed
-        let needs_update = bounds.width == 0.0 || bounds.height == 0.0;
-        if bounds.width == 0.0 {
-            bounds.width = screen_size[0];
-        }
-        if bounds.height == 0.0 {
-            bounds.height = screen_size[1];
-        }
-        
-        // Update the component if bounds changed
-        if needs_update {
-            world.update_component::<UiLayoutComponent>(entity, |l| {
-                l.bounds = bounds;
-            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+        let bounds = layout.bounds;
+        let [top, right, bottom, left] = layout.padding;
+
+        let children = graph.read().await.get_children(entity).cloned().unwrap_or_default();
+
+        // Absolutely positioned children keep their own bounds
+        let mut flow = Vec::new();
+        for child in children {
+            let child_layout = world.get_component::<UiLayoutComponent>(child).await
+                .map_err(|e| UiError::EcsError(e.to_string()))?;
+            if child_layout.position_type != PositionType::Relative {
+                continue;
+            }
+            let element = world.get_component::<UiElementComponent>(child).await
+                .map_err(|e| UiError::EcsError(e.to_string()))?;
+            let text = match element.text_content {
+                Some(text) => {
+                    let style = world.get_component::<UiStyleComponent>(child).await
+                        .map_err(|e| UiError::EcsError(e.to_string()))?;
+                    Some((text, style))
+                }
+                None => None,
+            };
+            flow.push((child, child_layout, text));
         }
-        
-        // Layout children
-        let graph_lock = graph.read().await;
-        if let Some(children) = graph_lock.get_children(entity) {
-            let mut child_y = bounds.y + padding[0];
-            
-            for &child in children {
-                // Calculate new bounds for child
-                let new_bounds = ElementBounds {
-                    x: bounds.x + padding[3],
-                    y: child_y,
-                    width: bounds.width - padding[1] - padding[3],
-                    height: 50.0, // Default height
-                };
-                
-                // Update child layout - world is Handle<World> now
-                world.update_component::<UiLayoutComponent>(child, |layout| {
-                    layout.bounds = new_bounds;
-                }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
-                
-                child_y += new_bounds.height + 10.0; // Spacing
+
+        let container = FlexContainer::from_layout(&layout, [
+            (bounds.width - left - right).max(0.0),
+            (bounds.height - top - bottom).max(0.0),
+        ]);
+        let items: Vec<FlexItem> = flow.iter().map(|(_, child_layout, _)| FlexItem::from_layout(child_layout)).collect();
+
+        // Content size is the child's text plus its padding
+        let placed = compute_flex(&container, &items, |index, width| {
+            let (_, child_layout, text) = &flow[index];
+            let [pad_top, pad_right, pad_bottom, pad_left] = child_layout.padding;
+            let horizontal = pad_left + pad_right;
+            let vertical = pad_top + pad_bottom;
+            match text {
+                Some((text, style)) => {
+                    let max_width = width.map(|width| (width - horizontal).max(0.0));
+                    let [text_width, text_height] = fonts.measure(text, &TextStyle::from_style(style, max_width));
+                    [text_width + horizontal, text_height + vertical]
+                }
+                None => [horizontal, vertical],
+            }
+        });
+
+        // Only children that moved or resized are written back
+        let mut changed = Vec::new();
+        for ((child, child_layout, _), rect) in flow.iter().zip(placed) {
+            let new_bounds = ElementBounds {
+                x: bounds.x + left + rect.x,
+                y: bounds.y + top + rect.y,
+                width: rect.width,
+                height: rect.height,
+            };
+            if child_layout.bounds == new_bounds {
+                continue;
             }
+            world.update_component::<UiLayoutComponent>(*child, |layout| {
+                layout.bounds = new_bounds;
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+            changed.push(*child);
         }
-        
-        Ok(())
+
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(size: [f32; 2], direction: FlexDirection, wrap: FlexWrap) -> FlexContainer {
+        FlexContainer {
+            size,
+            direction,
+            wrap,
+            justify_content: JustifyContent::FlexStart,
+            align_items: AlignItems::FlexStart,
+            row_gap: 0.0,
+            column_gap: 0.0,
+        }
+    }
+
+    fn row(width: f32, height: f32) -> FlexContainer {
+        container([width, height], FlexDirection::Row, FlexWrap::NoWrap)
+    }
+
+    fn size(width: Option<f32>, height: Option<f32>) -> Size {
+        Size { width, height }
+    }
+
+    fn item(width: f32, height: f32) -> FlexItem {
+        FlexItem {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: None,
+            size: size(Some(width), Some(height)),
+            min_size: size(None, None),
+            max_size: size(None, None),
+            margin: [0.0; 4],
+            align_self: None,
+        }
+    }
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> ElementBounds {
+        ElementBounds { x, y, width, height }
+    }
+
+    /// Every test item has a fixed size, so measuring is never needed
+    fn layout(container: &FlexContainer, items: &[FlexItem]) -> Vec<ElementBounds> {
+        compute_flex(container, items, |_, _| [0.0, 0.0])
+    }
+
+    #[test]
+    fn row_places_items_along_x() {
+        let items = [item(50.0, 20.0), item(30.0, 40.0)];
+        assert_eq!(layout(&row(200.0, 100.0), &items), [
+            rect(0.0, 0.0, 50.0, 20.0),
+            rect(50.0, 0.0, 30.0, 40.0),
+        ]);
+    }
+
+    #[test]
+    fn column_places_items_along_y_with_row_gap() {
+        let mut column = container([100.0, 200.0], FlexDirection::Column, FlexWrap::NoWrap);
+        column.row_gap = 8.0;
+        column.column_gap = 100.0;
+        let items = [item(50.0, 20.0), item(30.0, 40.0)];
+        assert_eq!(layout(&column, &items), [
+            rect(0.0, 0.0, 50.0, 20.0),
+            rect(0.0, 28.0, 30.0, 40.0),
+        ]);
+    }
+
+    #[test]
+    fn reversed_directions_start_at_the_main_end() {
+        let items = [item(50.0, 20.0), item(30.0, 40.0)];
+        let row_reverse = container([200.0, 100.0], FlexDirection::RowReverse, FlexWrap::NoWrap);
+        assert_eq!(layout(&row_reverse, &items), [
+            rect(150.0, 0.0, 50.0, 20.0),
+            rect(120.0, 0.0, 30.0, 40.0),
+        ]);
+
+        let column_reverse = container([100.0, 200.0], FlexDirection::ColumnReverse, FlexWrap::NoWrap);
+        assert_eq!(layout(&column_reverse, &items), [
+            rect(0.0, 180.0, 50.0, 20.0),
+            rect(0.0, 140.0, 30.0, 40.0),
+        ]);
+    }
+
+    #[test]
+    fn wrap_breaks_lines_and_packs_them_with_gaps() {
+        let mut wrapping = container([100.0, 100.0], FlexDirection::Row, FlexWrap::Wrap);
+        wrapping.column_gap = 10.0;
+        wrapping.row_gap = 5.0;
+        let items = [item(40.0, 10.0), item(40.0, 20.0), item(40.0, 10.0)];
+
+        // The third item would need 140 with the gaps, so it starts a line
+        // below the first one, which is as tall as its tallest item
+        assert_eq!(layout(&wrapping, &items), [
+            rect(0.0, 0.0, 40.0, 10.0),
+            rect(50.0, 0.0, 40.0, 20.0),
+            rect(0.0, 25.0, 40.0, 10.0),
+        ]);
+
+        wrapping.wrap = FlexWrap::WrapReverse;
+        assert_eq!(layout(&wrapping, &items), [
+            rect(0.0, 90.0, 40.0, 10.0),
+            rect(50.0, 80.0, 40.0, 20.0),
+            rect(0.0, 65.0, 40.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn no_wrap_keeps_overflowing_items_on_one_line() {
+        let mut items = [item(80.0, 10.0), item(80.0, 10.0)];
+        for item in &mut items {
+            item.shrink = 0.0;
+        }
+        assert_eq!(layout(&row(100.0, 50.0), &items), [
+            rect(0.0, 0.0, 80.0, 10.0),
+            rect(80.0, 0.0, 80.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn justify_content_distributes_free_space() {
+        let items = [item(40.0, 10.0), item(40.0, 10.0)];
+        // 120 of the 200 are free
+        let cases = [
+            (JustifyContent::FlexStart, [0.0, 40.0]),
+            (JustifyContent::FlexEnd, [120.0, 160.0]),
+            (JustifyContent::Center, [60.0, 100.0]),
+            (JustifyContent::SpaceBetween, [0.0, 160.0]),
+            (JustifyContent::SpaceAround, [30.0, 130.0]),
+            (JustifyContent::SpaceEvenly, [40.0, 120.0]),
+        ];
+
+        for (justify_content, [first, second]) in cases {
+            let mut container = row(200.0, 50.0);
+            container.justify_content = justify_content;
+            assert_eq!(layout(&container, &items), [
+                rect(first, 0.0, 40.0, 10.0),
+                rect(second, 0.0, 40.0, 10.0),
+            ], "{:?}", justify_content);
+        }
+    }
+
+    #[test]
+    fn align_items_positions_on_the_cross_axis() {
+        let items = [item(40.0, 20.0)];
+        // A no-wrap line is as tall as the container
+        let cases = [
+            (AlignItems::FlexStart, 0.0),
+            (AlignItems::FlexEnd, 80.0),
+            (AlignItems::Center, 40.0),
+            (AlignItems::Baseline, 0.0),
+            (AlignItems::Stretch, 0.0),
+        ];
+
+        for (align_items, y) in cases {
+            let mut container = row(200.0, 100.0);
+            container.align_items = align_items;
+            assert_eq!(layout(&container, &items), [rect(0.0, y, 40.0, 20.0)], "{:?}", align_items);
+        }
+    }
+
+    #[test]
+    fn stretch_fills_the_line_within_margins_and_limits() {
+        let mut container = row(200.0, 100.0);
+        container.align_items = AlignItems::Stretch;
+
+        let mut free = item(40.0, 0.0);
+        free.size.height = None;
+        free.margin = [10.0, 0.0, 5.0, 0.0];
+        let mut capped = item(40.0, 0.0);
+        capped.size.height = None;
+        capped.max_size.height = Some(50.0);
+
+        assert_eq!(layout(&container, &[free, capped]), [
+            rect(0.0, 10.0, 40.0, 85.0),
+            rect(40.0, 0.0, 40.0, 50.0),
+        ]);
+    }
+
+    #[test]
+    fn align_self_overrides_the_container() {
+        let mut container = row(200.0, 100.0);
+        container.align_items = AlignItems::Center;
+        let mut end = item(40.0, 20.0);
+        end.align_self = Some(AlignItems::FlexEnd);
+
+        assert_eq!(layout(&container, &[item(40.0, 20.0), end]), [
+            rect(0.0, 40.0, 40.0, 20.0),
+            rect(40.0, 80.0, 40.0, 20.0),
+        ]);
+    }
+
+    #[test]
+    fn grow_shares_free_space_by_factor() {
+        let mut a = item(50.0, 10.0);
+        a.grow = 1.0;
+        let mut b = item(50.0, 10.0);
+        b.grow = 3.0;
+
+        assert_eq!(layout(&row(300.0, 50.0), &[a, b]), [
+            rect(0.0, 0.0, 100.0, 10.0),
+            rect(100.0, 0.0, 200.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn grow_factors_below_one_take_only_their_share() {
+        let mut half = item(50.0, 10.0);
+        half.grow = 0.5;
+        assert_eq!(layout(&row(250.0, 50.0), &[half]), [rect(0.0, 0.0, 150.0, 10.0)]);
+    }
+
+    #[test]
+    fn grow_freezes_items_at_their_max() {
+        let mut capped = item(50.0, 10.0);
+        capped.grow = 1.0;
+        capped.max_size.width = Some(60.0);
+        let mut open = item(50.0, 10.0);
+        open.grow = 1.0;
+
+        // Both would reach 150; the capped item stops at 60 and the rest
+        // goes to the other one
+        assert_eq!(layout(&row(300.0, 50.0), &[capped, open]), [
+            rect(0.0, 0.0, 60.0, 10.0),
+            rect(60.0, 0.0, 240.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn shrink_is_weighted_by_base_size() {
+        let a = item(100.0, 10.0);
+        let mut b = item(100.0, 10.0);
+        b.shrink = 3.0;
+
+        assert_eq!(layout(&row(100.0, 50.0), &[a, b]), [
+            rect(0.0, 0.0, 75.0, 10.0),
+            rect(75.0, 0.0, 25.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn shrink_freezes_items_at_their_min() {
+        let a = item(100.0, 10.0);
+        let mut b = item(100.0, 10.0);
+        b.shrink = 3.0;
+        b.min_size.width = Some(50.0);
+
+        // `b` would shrink to 25, stops at 50 and `a` absorbs the rest
+        assert_eq!(layout(&row(100.0, 50.0), &[a, b]), [
+            rect(0.0, 0.0, 50.0, 10.0),
+            rect(50.0, 0.0, 50.0, 10.0),
+        ]);
+    }
+
+    #[test]
+    fn min_wins_over_max() {
+        let mut item = item(80.0, 10.0);
+        item.min_size.width = Some(60.0);
+        item.max_size.width = Some(40.0);
+        assert_eq!(layout(&row(200.0, 50.0), &[item]), [rect(0.0, 0.0, 60.0, 10.0)]);
+    }
+
+    #[test]
+    fn margins_and_gaps_separate_items() {
+        let mut container = row(200.0, 100.0);
+        container.column_gap = 10.0;
+        let mut spaced = item(40.0, 20.0);
+        spaced.margin = [5.0, 4.0, 6.0, 3.0];
+        let items = [spaced, item(40.0, 20.0)];
+
+        assert_eq!(layout(&container, &items), [
+            rect(3.0, 5.0, 40.0, 20.0),
+            rect(57.0, 0.0, 40.0, 20.0),
+        ]);
+
+        // Margins count as used space: 97 used, 103 free
+        container.justify_content = JustifyContent::FlexEnd;
+        assert_eq!(layout(&container, &items), [
+            rect(106.0, 5.0, 40.0, 20.0),
+            rect(160.0, 0.0, 40.0, 20.0),
+        ]);
+    }
+
+    #[test]
+    fn measure_sizes_items_without_explicit_size() {
+        let mut text = item(0.0, 0.0);
+        text.size = size(None, None);
+        let placed = compute_flex(&row(200.0, 100.0), &[text], |index, width| {
+            assert_eq!(index, 0);
+            // Unconstrained for the base size, then at the resolved width
+            match width {
+                None => [70.0, 12.0],
+                Some(width) => [width, 24.0],
+            }
+        });
+        assert_eq!(placed, [rect(0.0, 0.0, 70.0, 24.0)]);
     }
-}
\ No newline at end of file
+}
@@ -6,6 +6,9 @@ pub mod layout;
 pub mod input;
 pub mod theme;
 pub mod terminal;
+pub mod text;
+pub mod animation;
+pub mod accessibility;
 pub mod mobile;
 pub mod rendering;
 pub mod messages;
@@ -17,6 +20,8 @@ mod register;
 pub use error::{UiError, UiResult};
 pub use system::UiSystem;
 pub use element::ElementGraph;
+pub use text::{FontManager, FontId, TextLayout, TextStyle};
+pub use animation::{AnimationEngine, PropertyValue};
 pub use playground_core_ui::ElementId;
 pub use types::{
     ElementStyle, ElementBounds, FontWeight, TextAlign,
@@ -4,6 +4,9 @@ use bytes::{Bytes, BytesMut, BufMut, Buf};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use nalgebra::{Vector2, Vector4};
+use playground_core_ui::{AccessibilityAction, ElementId};
+use crate::accessibility::AriaNode;
+use crate::input::DispatchedEvent;
 use crate::error::{UiError, UiResult};
 
 /// UI system packet types
@@ -30,6 +33,8 @@ pub enum UiPacketType {
     LoadShader = 108,
     LoadTexture = 109,
     UnloadResource = 110,
+    AccessibilityTree = 111,
+    DispatchedEvents = 112,
     
     // Terminal-specific
     TerminalInput = 200,
@@ -61,6 +66,8 @@ impl TryFrom<u16> for UiPacketType {
             108 => Ok(UiPacketType::LoadShader),
             109 => Ok(UiPacketType::LoadTexture),
             110 => Ok(UiPacketType::UnloadResource),
+            111 => Ok(UiPacketType::AccessibilityTree),
+            112 => Ok(UiPacketType::DispatchedEvents),
             200 => Ok(UiPacketType::TerminalInput),
             201 => Ok(UiPacketType::TerminalOutput),
             202 => Ok(UiPacketType::TerminalConnect),
@@ -122,6 +129,27 @@ pub struct InputModifiers {
     pub meta: bool,
 }
 
+/// Accessibility action message, sent when a screen reader activates an
+/// element of the mirrored tree. The browser sends it as JSON on the UI
+/// Framework channel, which hands it to `perform_accessibility_action`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessibilityActionMessage {
+    pub element: ElementId,
+    pub action: AccessibilityAction,
+}
+
+/// Accessibility tree message, the ARIA nodes the client mirrors as DOM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessibilityTreeMessage {
+    pub nodes: Vec<AriaNode>,
+}
+
+/// Events that reached listeners during a frame, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispatchedEventsMessage {
+    pub events: Vec<DispatchedEvent>,
+}
+
 /// Terminal input message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TerminalInputMessage {
@@ -326,4 +354,36 @@ pub mod binary {
         String::from_utf8(bytes)
             .map_err(|e| UiError::SerializationError(format!("Invalid UTF-8: {}", e)))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use playground_core_ui::SwipeDirection;
+
+    #[test]
+    fn decodes_accessibility_actions_from_the_browser() {
+        // As app.js `sendAccessibilityAction` encodes them, with the element
+        // id taken from the ARIA node
+        let element = ElementId(Uuid::new_v4());
+        let tap = Bytes::from(format!(r#"{{"element":"{}","action":"Tap"}}"#, element.0));
+        let message: AccessibilityActionMessage = deserialize_message(&tap).unwrap();
+        assert_eq!(message.element, element);
+        assert!(matches!(message.action, AccessibilityAction::Tap));
+
+        let scroll = Bytes::from(format!(r#"{{"element":"{}","action":{{"Scroll":"Up"}}}}"#, element.0));
+        let message: AccessibilityActionMessage = deserialize_message(&scroll).unwrap();
+        assert!(matches!(message.action, AccessibilityAction::Scroll(SwipeDirection::Up)));
+
+        let unknown = Bytes::from(format!(r#"{{"element":"{}","action":"Shake"}}"#, element.0));
+        assert!(deserialize_message::<AccessibilityActionMessage>(&unknown).is_err());
+    }
+
+    #[test]
+    fn aria_element_ids_round_trip() {
+        let element = ElementId(Uuid::new_v4());
+        let sent = serialize_message(&AccessibilityActionMessage { element, action: AccessibilityAction::Focus }).unwrap();
+        let received: AccessibilityActionMessage = deserialize_message(&sent).unwrap();
+        assert_eq!(received.element, element);
+        assert!(matches!(received.action, AccessibilityAction::Focus));
+    }
+}
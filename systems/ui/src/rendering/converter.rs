@@ -1,6 +1,9 @@
+use std::borrow::Cow;
 use playground_core_rendering::{RenderCommand, RenderCommandBatch};
 use crate::components::{UiElementComponent, UiLayoutComponent, UiStyleComponent};
+use crate::terminal::Terminal;
 use crate::theme::Theme;
+use crate::text::FontManager;
 use crate::error::UiResult;
 use super::element_renderer;
 
@@ -8,19 +11,27 @@ pub fn ui_to_render_commands(
     element: &UiElementComponent,
     layout: &UiLayoutComponent,
     style: &UiStyleComponent,
+    terminal: Option<&Terminal>,
     theme: &Theme,
+    fonts: &mut FontManager,
     batch: &mut RenderCommandBatch,
 ) -> UiResult<()> {
     // Skip invisible elements
     if !style.visible || !element.visible {
         return Ok(());
     }
+    let style = &*resolve_tokens(style, theme);
     
-    // Apply opacity if needed
+    // Apply opacity and transform if needed
+    let transform = element_transform(layout, style);
     let has_opacity = style.opacity < 1.0;
-    if has_opacity {
+    let push_state = has_opacity || transform.is_some();
+    if push_state {
         batch.push(RenderCommand::PushState);
     }
+    if let Some(matrix) = transform {
+        batch.push(RenderCommand::SetTransform { matrix });
+    }
     
     // Clip rect for bounds
     if layout.bounds.width > 0.0 && layout.bounds.height > 0.0 {
@@ -78,10 +89,10 @@ pub fn ui_to_render_commands(
     
     // Element-specific rendering
     match element.element_type.as_str() {
-        "text" => element_renderer::render_text(element, layout, style, theme, batch)?,
-        "button" => element_renderer::render_button(element, layout, style, theme, batch)?,
-        "terminal" => element_renderer::render_terminal(element, layout, style, theme, batch)?,
-        "input" => element_renderer::render_input(element, layout, style, theme, batch)?,
+        "text" => element_renderer::render_text(element, layout, style, theme, fonts, batch)?,
+        "button" => element_renderer::render_button(element, layout, style, theme, fonts, batch)?,
+        "terminal" => element_renderer::render_terminal(element, layout, style, theme, fonts, terminal, batch)?,
+        "input" => element_renderer::render_input(element, layout, style, theme, fonts, batch)?,
         "panel" | "container" => {}, // Already rendered background
         "scrollview" => {
             // Scrollview is like a panel but with overflow handling
@@ -93,10 +104,79 @@ pub fn ui_to_render_commands(
     // Clear clip rect
     batch.push(RenderCommand::ClearClipRect);
     
+    // Focus ring, outside the bounds so it isn't clipped away
+    if element.focused {
+        render_focus_ring(layout, style, theme, batch);
+    }
+    
     // Restore state if we pushed
-    if has_opacity {
+    if push_state {
         batch.push(RenderCommand::PopState);
     }
     
     Ok(())
+}
+
+/// Style with its color tokens replaced by the theme's colors. A token the
+/// theme doesn't define leaves the literal color in place.
+pub fn resolve_tokens<'a>(style: &'a UiStyleComponent, theme: &Theme) -> Cow<'a, UiStyleComponent> {
+    if style.background_token.is_none() && style.text_token.is_none() && style.border_token.is_none() {
+        return Cow::Borrowed(style);
+    }
+    
+    let lookup = |token: &Option<String>| token.as_deref().and_then(|token| theme.colors.get(token));
+    let mut resolved = style.clone();
+    if let Some(color) = lookup(&style.background_token) {
+        resolved.background_color = Some(color);
+    }
+    if let Some(color) = lookup(&style.text_token) {
+        resolved.text_color = Some(color);
+    }
+    if let Some(color) = lookup(&style.border_token) {
+        resolved.border_color = Some(color);
+    }
+    Cow::Owned(resolved)
+}
+
+/// Width of the focus ring and its gap from the element's edge
+const FOCUS_RING_WIDTH: f32 = 2.0;
+const FOCUS_RING_OFFSET: f32 = 2.0;
+
+fn render_focus_ring(layout: &UiLayoutComponent, style: &UiStyleComponent, theme: &Theme, batch: &mut RenderCommandBatch) {
+    let inset = FOCUS_RING_OFFSET + FOCUS_RING_WIDTH / 2.0;
+    let left = layout.bounds.x - inset;
+    let top = layout.bounds.y - inset;
+    let right = layout.bounds.x + layout.bounds.width + inset;
+    let bottom = layout.bounds.y + layout.bounds.height + inset;
+    let ring = theme.colors.primary;
+    let color = [ring.x, ring.y, ring.z, ring.w * style.opacity];
+    
+    for (start, end) in [
+        ([left, top], [right, top]),
+        ([right, top], [right, bottom]),
+        ([right, bottom], [left, bottom]),
+        ([left, bottom], [left, top]),
+    ] {
+        batch.push(RenderCommand::DrawLine { start, end, width: FOCUS_RING_WIDTH, color });
+    }
+}
+
+/// Scale and rotation about the element's center, None when untransformed
+fn element_transform(layout: &UiLayoutComponent, style: &UiStyleComponent) -> Option<[[f32; 3]; 3]> {
+    if style.scale == 1.0 && style.rotation == 0.0 {
+        return None;
+    }
+    
+    let center_x = layout.bounds.x + layout.bounds.width / 2.0;
+    let center_y = layout.bounds.y + layout.bounds.height / 2.0;
+    let (sin, cos) = style.rotation.sin_cos();
+    let (a, b) = (cos * style.scale, -sin * style.scale);
+    let (c, d) = (sin * style.scale, cos * style.scale);
+    
+    // Translate to the origin, rotate and scale, translate back
+    Some([
+        [a, b, center_x - a * center_x - b * center_y],
+        [c, d, center_y - c * center_x - d * center_y],
+        [0.0, 0.0, 1.0],
+    ])
 }
\ No newline at end of file
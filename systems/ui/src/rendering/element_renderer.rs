@@ -1,6 +1,9 @@
 use playground_core_rendering::{RenderCommand, RenderCommandBatch};
-use crate::components::{UiElementComponent, UiLayoutComponent, UiStyleComponent, TextAlign};
+use crate::components::{UiElementComponent, UiLayoutComponent, UiStyleComponent, TextAlign, FontWeight};
+use crate::terminal::{Cell, CursorShape, Terminal};
+use crate::terminal::grid::ANSI_COLORS;
 use crate::theme::Theme;
+use crate::text::{FontManager, TextStyle};
 use crate::error::UiResult;
 
 pub fn render_text(
@@ -8,27 +11,23 @@ pub fn render_text(
     layout: &UiLayoutComponent,
     style: &UiStyleComponent,
     theme: &Theme,
+    fonts: &mut FontManager,
     batch: &mut RenderCommandBatch,
 ) -> UiResult<()> {
     if let Some(text) = &element.text_content {
         let text_color = style.text_color.unwrap_or(theme.colors.text);
         
-        // Calculate text position based on alignment
-        let text_x = match style.text_align {
-            TextAlign::Left => layout.bounds.x + layout.padding[3],
-            TextAlign::Center => layout.bounds.x + layout.bounds.width / 2.0,
-            TextAlign::Right => layout.bounds.x + layout.bounds.width - layout.padding[1],
-            TextAlign::Justify => layout.bounds.x + layout.padding[3],
-        };
-        
-        let text_y = layout.bounds.y + layout.padding[0] + style.font_size;
+        // Alignment happens inside the layout, against the content width
+        let [x, y, width, _] = content_box(layout);
+        let text_layout = fonts.layout(text, &TextStyle::from_style(style, Some(width)));
         
-        batch.push(RenderCommand::DrawText {
-            text: text.clone(),
-            position: [text_x, text_y],
-            size: style.font_size,
-            color: [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
-        });
+        for command in fonts.draw_text(
+            &text_layout,
+            [x, y],
+            [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
+        ) {
+            batch.push(command);
+        }
     }
     
     Ok(())
@@ -39,6 +38,7 @@ pub fn render_button(
     layout: &UiLayoutComponent,
     style: &UiStyleComponent,
     theme: &Theme,
+    fonts: &mut FontManager,
     batch: &mut RenderCommandBatch,
 ) -> UiResult<()> {
     // Button gets special hover/pressed styling
@@ -68,47 +68,219 @@ pub fn render_button(
         };
         
         // Center text in button
-        let text_x = layout.bounds.x + layout.bounds.width / 2.0;
-        let text_y = layout.bounds.y + layout.bounds.height / 2.0;
+        let [x, y, width, height] = content_box(layout);
+        let text_style = TextStyle {
+            align: TextAlign::Center,
+            ..TextStyle::from_style(style, Some(width))
+        };
+        let text_layout = fonts.layout(text, &text_style);
+        let text_y = y + (height - text_layout.height) / 2.0;
         
-        batch.push(RenderCommand::DrawText {
-            text: text.clone(),
-            position: [text_x, text_y],
-            size: style.font_size,
-            color: [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
-        });
+        for command in fonts.draw_text(
+            &text_layout,
+            [x, text_y],
+            [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
+        ) {
+            batch.push(command);
+        }
     }
     
     Ok(())
 }
 
 pub fn render_terminal(
-    _element: &UiElementComponent,
+    element: &UiElementComponent,
     layout: &UiLayoutComponent,
-    _style: &UiStyleComponent,
+    style: &UiStyleComponent,
     theme: &Theme,
+    fonts: &mut FontManager,
+    terminal: Option<&Terminal>,
     batch: &mut RenderCommandBatch,
 ) -> UiResult<()> {
-    // Terminal gets special background
-    let bg_color = theme.colors.editor_background;
+    let background = style.background_color.unwrap_or(theme.colors.editor_background);
+    let foreground = style.text_color.unwrap_or(theme.colors.text);
+    let with_opacity = |[r, g, b, a]: [f32; 4]| [r, g, b, a * style.opacity];
+    let default_bg = [background.x, background.y, background.z, background.w];
+    let default_fg = [foreground.x, foreground.y, foreground.z, foreground.w];
     
     batch.push(RenderCommand::DrawQuad {
         position: [layout.bounds.x, layout.bounds.y],
         size: [layout.bounds.width, layout.bounds.height],
-        color: [bg_color.x, bg_color.y, bg_color.z, bg_color.w],
+        color: with_opacity(default_bg),
     });
     
-    // Terminal content would be rendered here
-    // This would include terminal lines, cursor, etc.
+    let Some(terminal) = terminal else {
+        return Ok(());
+    };
+    
+    let text_style = terminal_text_style(style, theme);
+    let [cell_width, cell_height] = fonts.measure("M", &text_style);
+    let [x, y, _, _] = content_box(layout);
+    let palette = terminal_palette(theme);
+    let colors = |cell: &Cell| cell_colors(cell, default_fg, default_bg, &palette);
+    
+    for row in 0..terminal.rows() {
+        let cells = &terminal.visible_row(row).cells;
+        let top = y + row as f32 * cell_height;
+        
+        // Backgrounds, merged into runs of one color
+        let mut col = 0;
+        while col < cells.len() {
+            let start = col;
+            let (_, bg) = colors(&cells[col]);
+            while col < cells.len() && colors(&cells[col]).1 == bg {
+                col += 1;
+            }
+            if bg != default_bg {
+                batch.push(RenderCommand::DrawQuad {
+                    position: [x + start as f32 * cell_width, top],
+                    size: [(col - start) as f32 * cell_width, cell_height],
+                    color: with_opacity(bg),
+                });
+            }
+        }
+        
+        // Text, in runs of cells that share colors and attributes. A wide
+        // character ends its run, so what follows starts on its own cell
+        // whatever width the font gives the glyph.
+        let mut col = 0;
+        while col < cells.len() {
+            let start = col;
+            let first = cells[col];
+            let mut text = String::new();
+            while col < cells.len() && (cells[col].fg, cells[col].bg, cells[col].attrs) == (first.fg, first.bg, first.attrs) {
+                text.push(cells[col].ch);
+                col += cells[col].width.max(1) as usize;
+                if cells[col.min(cells.len()) - 1].width == 0 {
+                    break;
+                }
+            }
+            if first.attrs.hidden {
+                continue;
+            }
+            
+            let left = x + start as f32 * cell_width;
+            let right = x + col as f32 * cell_width;
+            let color = with_opacity(colors(&first).0);
+            if !text.trim().is_empty() {
+                let run_style = TextStyle {
+                    font_weight: if first.attrs.bold { FontWeight::Bold } else { text_style.font_weight },
+                    ..text_style.clone()
+                };
+                let text_layout = fonts.layout(&text, &run_style);
+                for command in fonts.draw_text(&text_layout, [left, top], color) {
+                    batch.push(command);
+                }
+            }
+            if first.attrs.underline {
+                let line_y = top + cell_height - 1.0;
+                batch.push(RenderCommand::DrawLine { start: [left, line_y], end: [right, line_y], width: 1.0, color });
+            }
+            if first.attrs.strikethrough {
+                let line_y = top + cell_height / 2.0;
+                batch.push(RenderCommand::DrawLine { start: [left, line_y], end: [right, line_y], width: 1.0, color });
+            }
+        }
+    }
+    
+    // The cursor belongs to the live screen, not to scrollback
+    if !terminal.modes().cursor_visible || terminal.display_offset() > 0 {
+        return Ok(());
+    }
+    let (row, col) = terminal.cursor();
+    let left = x + col as f32 * cell_width;
+    let top = y + row as f32 * cell_height;
+    let cursor = with_opacity([theme.colors.cursor.x, theme.colors.cursor.y, theme.colors.cursor.z, theme.colors.cursor.w]);
+    
+    if !element.focused {
+        // Hollow block while another element has focus
+        let (right, bottom) = (left + cell_width, top + cell_height);
+        for (start, end) in [
+            ([left, top], [right, top]),
+            ([right, top], [right, bottom]),
+            ([right, bottom], [left, bottom]),
+            ([left, bottom], [left, top]),
+        ] {
+            batch.push(RenderCommand::DrawLine { start, end, width: 1.0, color: cursor });
+        }
+        return Ok(());
+    }
+    
+    match terminal.cursor_shape() {
+        CursorShape::Block => {
+            // The character under the cursor, in the background color
+            let cell = terminal.visible_row(row).cells[col];
+            let width = cell_width * cell.width.max(1) as f32;
+            batch.push(RenderCommand::DrawQuad { position: [left, top], size: [width, cell_height], color: cursor });
+            if !cell.attrs.hidden && cell.ch != ' ' {
+                let text_layout = fonts.layout(&cell.ch.to_string(), &text_style);
+                for command in fonts.draw_text(&text_layout, [left, top], with_opacity(colors(&cell).1)) {
+                    batch.push(command);
+                }
+            }
+        }
+        CursorShape::Underline => batch.push(RenderCommand::DrawQuad {
+            position: [left, top + cell_height - 2.0],
+            size: [cell_width, 2.0],
+            color: cursor,
+        }),
+        CursorShape::Bar => batch.push(RenderCommand::DrawQuad {
+            position: [left, top],
+            size: [2.0, cell_height],
+            color: cursor,
+        }),
+    }
     
     Ok(())
 }
 
+/// Size of one terminal cell, for working out how many rows and columns
+/// fit in an element
+pub fn terminal_cell_size(style: &UiStyleComponent, theme: &Theme, fonts: &FontManager) -> [f32; 2] {
+    fonts.measure("M", &terminal_text_style(style, theme))
+}
+
+/// Terminals draw in the theme's font unless the element sets one, which
+/// should be monospace for the grid to line up
+fn terminal_text_style(style: &UiStyleComponent, theme: &Theme) -> TextStyle {
+    TextStyle {
+        font_family: Some(style.font_family.clone().unwrap_or_else(|| theme.typography.font_family.clone())),
+        align: TextAlign::Left,
+        ..TextStyle::from_style(style, None)
+    }
+}
+
+/// The 16 ANSI colors, each overridable by the theme color `ansi0` to
+/// `ansi15`
+fn terminal_palette(theme: &Theme) -> [[f32; 4]; 16] {
+    let mut palette = ANSI_COLORS;
+    for (index, entry) in palette.iter_mut().enumerate() {
+        if let Some(color) = theme.colors.get(&format!("ansi{}", index)) {
+            *entry = [color.x, color.y, color.z, color.w];
+        }
+    }
+    palette
+}
+
+/// Foreground and background of a cell after inverse and dim
+fn cell_colors(cell: &Cell, default_fg: [f32; 4], default_bg: [f32; 4], palette: &[[f32; 4]; 16]) -> ([f32; 4], [f32; 4]) {
+    let mut fg = cell.fg.to_rgba(default_fg, palette);
+    let mut bg = cell.bg.to_rgba(default_bg, palette);
+    if cell.attrs.inverse {
+        std::mem::swap(&mut fg, &mut bg);
+    }
+    if cell.attrs.dim {
+        fg = [fg[0] * 0.66, fg[1] * 0.66, fg[2] * 0.66, fg[3]];
+    }
+    (fg, bg)
+}
+
 pub fn render_input(
     element: &UiElementComponent,
     layout: &UiLayoutComponent,
     style: &UiStyleComponent,
     theme: &Theme,
+    fonts: &mut FontManager,
     batch: &mut RenderCommandBatch,
 ) -> UiResult<()> {
     // Input field background
@@ -124,30 +296,45 @@ pub fn render_input(
         color: [bg_color.x, bg_color.y, bg_color.z, bg_color.w * style.opacity],
     });
     
-    // Draw input text
-    if let Some(text) = &element.text_content {
-        let text_color = style.text_color.unwrap_or(theme.colors.text);
-        
-        batch.push(RenderCommand::DrawText {
-            text: text.clone(),
-            position: [layout.bounds.x + layout.padding[3], layout.bounds.y + layout.padding[0] + style.font_size],
-            size: style.font_size,
-            color: [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
-        });
+    // Input text stays on one line
+    let text = element.text_content.as_deref().unwrap_or("");
+    let [x, y, _, _] = content_box(layout);
+    let text_layout = fonts.layout(text, &TextStyle {
+        align: TextAlign::Left,
+        ..TextStyle::from_style(style, None)
+    });
+    
+    let text_color = style.text_color.unwrap_or(theme.colors.text);
+    for command in fonts.draw_text(
+        &text_layout,
+        [x, y],
+        [text_color.x, text_color.y, text_color.z, text_color.w * style.opacity],
+    ) {
+        batch.push(command);
     }
     
-    // Draw cursor if focused
+    // Draw cursor after the last character if focused
     if element.focused {
-        let cursor_x = layout.bounds.x + layout.padding[3] + 
-            (element.text_content.as_ref().map(|t| t.len()).unwrap_or(0) as f32 * style.font_size * 0.6);
+        let [caret_x, caret_y] = text_layout.caret_position(text.len());
+        let cursor_x = x + caret_x;
         
         batch.push(RenderCommand::DrawLine {
-            start: [cursor_x, layout.bounds.y + layout.padding[0]],
-            end: [cursor_x, layout.bounds.y + layout.bounds.height - layout.padding[2]],
+            start: [cursor_x, y + caret_y],
+            end: [cursor_x, y + caret_y + text_layout.line_height],
             width: 2.0,
             color: [theme.colors.cursor.x, theme.colors.cursor.y, theme.colors.cursor.z, theme.colors.cursor.w],
         });
     }
     
     Ok(())
-}
\ No newline at end of file
+}
+/// Bounds inside the padding as `[x, y, width, height]`
+fn content_box(layout: &UiLayoutComponent) -> [f32; 4] {
+    let [top, right, bottom, left] = layout.padding;
+    [
+        layout.bounds.x + left,
+        layout.bounds.y + top,
+        (layout.bounds.width - left - right).max(0.0),
+        (layout.bounds.height - top - bottom).max(0.0),
+    ]
+}
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use crate::accessibility::{AccessibilityTree, AndroidNodeInfo, to_aria, to_android};
+use crate::components::{UiLayoutComponent, UiAccessibilityComponent};
+use crate::input::GestureConfig;
+use crate::input::focus::focus_index;
+use crate::messages::{AccessibilityTreeMessage, UiPacketType, serialize_message};
+use playground_core_ui::{AccessibilityAction, ElementId, EventResult, SwipeDirection, UiEvent as CoreUiEvent};
+use playground_core_types::Priority;
+
+/// Share of the element's size one accessibility scroll moves by
+const SCROLL_PAGE: f32 = 0.8;
+
+impl UiSystem {
+    /// Set an element's accessibility metadata
+    pub async fn set_accessibility(&self, element: ElementId, accessibility: UiAccessibilityComponent) -> UiResult<()> {
+        self.world.add_component(element, accessibility).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?;
+        // Published with the next frame
+        self.element_graph.write().await.mark_render_dirty(element);
+        Ok(())
+    }
+
+    pub async fn accessibility_tree(&self) -> UiResult<AccessibilityTree> {
+        AccessibilityTree::build(&self.element_graph, &self.world).await
+    }
+
+    /// The tree as virtual views for Android's accessibility bridge
+    pub async fn android_accessibility_nodes(&self) -> UiResult<Vec<AndroidNodeInfo>> {
+        Ok(to_android(&self.accessibility_tree().await?))
+    }
+
+    /// Send the tree to the browser client as ARIA nodes, if it changed
+    /// since the last time. `dirty` is the frame's render-dirty set; only
+    /// those subtrees are described again. Without networking nothing is
+    /// sent and the tree goes out once networking is connected.
+    pub(super) async fn publish_accessibility_tree(&self, dirty: &HashSet<ElementId>) -> UiResult<()> {
+        let tree = AccessibilityTree::update(&self.element_graph, &self.world, dirty).await?;
+        let mut published = self.published_accessibility.write().await;
+        if published.as_ref() == Some(&tree) {
+            return Ok(());
+        }
+
+        let Some(ref networking) = self.networking_system else {
+            return Ok(());
+        };
+        let data = serialize_message(&AccessibilityTreeMessage { nodes: to_aria(&tree) })?;
+        networking.read().await
+            .send_packet(self.channel_id, UiPacketType::AccessibilityTree as u16, data.to_vec(), Priority::Normal)
+            .await
+            .map_err(|e| UiError::NetworkError(format!("Failed to send accessibility tree: {}", e)))?;
+
+        *published = Some(tree);
+        Ok(())
+    }
+
+    /// Route an action from a screen reader to an element. Listeners see
+    /// the `AccessibilityAction` event first; unless one handles it, the
+    /// action is performed as the equivalent touch, scroll or focus change.
+    pub async fn perform_accessibility_action(&self, element: ElementId, action: AccessibilityAction) -> UiResult<EventResult> {
+        let event = CoreUiEvent::AccessibilityAction { element, action: action.clone() };
+        let result = self.deliver(&event, element).await;
+        if result == EventResult::Handled {
+            return Ok(result);
+        }
+
+        let bounds = self.world.get_component::<UiLayoutComponent>(element).await
+            .map_err(|e| UiError::EcsError(e.to_string()))?
+            .bounds;
+        let x = bounds.x + bounds.width / 2.0;
+        let y = bounds.y + bounds.height / 2.0;
+
+        let fallback = match action {
+            AccessibilityAction::Tap => {
+                // As a touch would, activating focusable elements focuses them
+                if focus_index(element, &self.world).await?.is_some() {
+                    self.focus_element(Some(element)).await?;
+                }
+                CoreUiEvent::Tap { element, x, y }
+            }
+            AccessibilityAction::DoubleTap => CoreUiEvent::DoubleTap { element, x, y },
+            AccessibilityAction::LongPress => CoreUiEvent::LongPress {
+                element,
+                x,
+                y,
+                duration_ms: GestureConfig::default().long_press_delay.as_millis() as u64,
+            },
+            AccessibilityAction::Scroll(direction) => {
+                // Content follows the swipe, so swiping up scrolls down
+                let (delta_x, delta_y) = match direction {
+                    SwipeDirection::Up => (0.0, bounds.height * SCROLL_PAGE),
+                    SwipeDirection::Down => (0.0, -bounds.height * SCROLL_PAGE),
+                    SwipeDirection::Left => (bounds.width * SCROLL_PAGE, 0.0),
+                    SwipeDirection::Right => (-bounds.width * SCROLL_PAGE, 0.0),
+                };
+                CoreUiEvent::Scroll { element, delta_x, delta_y, content_offset_x: 0.0, content_offset_y: 0.0 }
+            }
+            AccessibilityAction::Focus => {
+                self.focus_element(Some(element)).await?;
+                return Ok(EventResult::Modified);
+            }
+            AccessibilityAction::Escape => {
+                self.focus_element(None).await?;
+                return Ok(EventResult::Modified);
+            }
+            // Only meaningful to listeners
+            AccessibilityAction::MagicTap | AccessibilityAction::Custom(_) => return Ok(result),
+        };
+
+        Ok(self.deliver(&fallback, element).await)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use crate::animation::PropertyValue;
+use crate::components::{UiLayoutComponent, UiStyleComponent};
+use playground_core_ui::{Animation, AnimationId, ElementId, UiEvent as CoreUiEvent};
+use nalgebra::Vector4;
+use std::time::Instant;
+
+impl UiSystem {
+    /// Start animating an element; values are applied from the next step
+    pub async fn animate(&mut self, element: ElementId, animation: Animation) -> UiResult<AnimationId> {
+        Ok(self.animations.write().await.start(element, animation))
+    }
+
+    /// Stop an animation where it is and raise `AnimationCancelled`
+    pub async fn cancel_animation(&mut self, id: AnimationId) -> UiResult<()> {
+        let element = self.animations.write().await.stop(id)
+            .ok_or_else(|| UiError::AnimationNotFound(format!("{:?}", id)))?;
+        self.pending_events.write().await.push(CoreUiEvent::AnimationCancelled { element, animation: id });
+        Ok(())
+    }
+
+    /// Frame work that comes before drawing, for `render` and `render_frame`:
+    /// deliver the events raised since the last frame, then advance
+    /// animations by the time since they last advanced. Completions are
+    /// delivered with the next frame.
+    pub(super) async fn advance_frame(&mut self) -> UiResult<()> {
+        self.dispatch_pending_events().await?;
+
+        let delta_time = self.last_animation_step
+            .map(|last| last.elapsed().as_secs_f32())
+            .unwrap_or(0.0);
+        self.step_animations(delta_time).await
+    }
+
+    /// Advance animations by `delta_time` seconds and apply their values.
+    /// Does nothing while no animation is running. Time passed here is not
+    /// counted again by the next frame.
+    pub(super) async fn step_animations(&mut self, delta_time: f32) -> UiResult<()> {
+        // Restarted while idle too, so a new animation starts from zero
+        // rather than with the time nothing was running
+        self.last_animation_step = Some(Instant::now());
+        let frame = {
+            let mut animations = self.animations.write().await;
+            if animations.is_idle() {
+                return Ok(());
+            }
+            animations.advance(delta_time)
+        };
+
+        for (element, values) in frame.samples {
+            self.apply_animated_values(element, &values).await?;
+        }
+
+        let mut events = self.pending_events.write().await;
+        for (animation, element) in frame.completed {
+            events.push(CoreUiEvent::AnimationCompleted { element, animation });
+        }
+
+        Ok(())
+    }
+
+    async fn apply_animated_values(&self, element: ElementId, values: &[PropertyValue]) -> UiResult<()> {
+        let moves = values.iter().any(|value| matches!(value, PropertyValue::Position(_) | PropertyValue::Size(_)));
+
+        if moves {
+            self.world.update_component::<UiLayoutComponent>(element, |layout| {
+                for value in values {
+                    match *value {
+                        PropertyValue::Position([x, y]) => {
+                            layout.bounds.x = x;
+                            layout.bounds.y = y;
+                        }
+                        PropertyValue::Size([width, height]) => {
+                            layout.bounds.width = width;
+                            layout.bounds.height = height;
+                        }
+                        _ => {}
+                    }
+                }
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+        }
+
+        if values.iter().any(|value| !matches!(value, PropertyValue::Position(_) | PropertyValue::Size(_))) {
+            self.world.update_component::<UiStyleComponent>(element, |style| {
+                for value in values {
+                    match *value {
+                        PropertyValue::Opacity(opacity) => style.opacity = opacity,
+                        PropertyValue::Scale(scale) => style.scale = scale,
+                        PropertyValue::Rotation(rotation) => style.rotation = rotation,
+                        PropertyValue::Color(color) => {
+                            // An animated color overrides the theme token
+                            style.background_token = None;
+                            style.background_color = Some(Vector4::new(color[0], color[1], color[2], color[3]));
+                        }
+                        _ => {}
+                    }
+                }
+            }).await.map_err(|e| UiError::EcsError(e.to_string()))?;
+        }
+
+        // Moving an element reflows its children; the rest only repaints
+        let mut graph = self.element_graph.write().await;
+        if moves {
+            graph.mark_children_dirty(element);
+        } else {
+            graph.mark_render_dirty(element);
+        }
+
+        Ok(())
+    }
+}
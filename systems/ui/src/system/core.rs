@@ -1,15 +1,19 @@
 use playground_core_rendering::Viewport;
 use playground_core_types::{Shared, shared};
 use playground_core_ecs::{System as EcsSystem, ExecutionStage, EcsResult};
-use playground_core_ui::ElementId;
+use playground_core_ui::{ElementId, UiEvent as CoreUiEvent};
 use crate::element::ElementGraph;
 use crate::internal_storage::InternalElementStorage;
 use crate::layout::LayoutEngine;
 use crate::input::InputManager;
 use crate::theme::{ThemeManager, ThemeId};
 use crate::terminal::TerminalManager;
+use crate::text::FontManager;
+use crate::animation::AnimationEngine;
 use crate::mobile::MobileFeatures;
+use crate::accessibility::AccessibilityTree;
 use std::collections::HashMap;
+use std::time::Instant;
 use uuid::Uuid;
 
 pub struct UiSystem {
@@ -30,6 +34,19 @@ pub struct UiSystem {
     pub(super) theme_manager: Shared<ThemeManager>,
     pub(super) current_theme: ThemeId,
     
+    // Fonts and glyph atlas, shared with layout for text measurement
+    pub(super) font_manager: Shared<FontManager>,
+    
+    // Running animations, and when they last advanced
+    pub(super) animations: Shared<AnimationEngine>,
+    pub(super) last_animation_step: Option<Instant>,
+    
+    // Events raised by the system itself, such as focus changes and finished animations
+    pub(super) pending_events: Shared<Vec<CoreUiEvent>>,
+    
+    // Accessibility tree last sent to the client
+    pub(super) published_accessibility: Shared<Option<AccessibilityTree>>,
+    
     // Terminal support
     pub(super) terminal_manager: Shared<TerminalManager>,
     pub(super) terminal_connections: Shared<HashMap<Uuid, ElementId>>,
@@ -51,14 +68,22 @@ pub struct UiSystem {
 
 impl UiSystem {
     pub fn new() -> Self {
+        let font_manager = shared(FontManager::new());
+        let pending_events = shared(Vec::new());
+        
         Self {
             storage: InternalElementStorage::new(),
             element_graph: shared(ElementGraph::new()),
             root_element: None,
-            layout_engine: shared(LayoutEngine::new()),
-            input_manager: shared(InputManager::new()),
+            layout_engine: shared(LayoutEngine::new(font_manager.clone())),
+            input_manager: shared(InputManager::new(pending_events.clone())),
             theme_manager: shared(ThemeManager::new()),
             current_theme: ThemeId::Dark,
+            font_manager,
+            animations: shared(AnimationEngine::new()),
+            last_animation_step: None,
+            pending_events,
+            published_accessibility: shared(None),
             terminal_manager: shared(TerminalManager::new()),
             terminal_connections: shared(HashMap::new()),
             mobile_features: shared(MobileFeatures::new()),
@@ -130,6 +155,8 @@ impl EcsSystem for UiSystem {
         // 3. Perform layout calculations
         // 4. Generate RenderCommandBatch for the render stage
         
+        // Animations advance with each rendered frame, see advance_frame
+        
         self.frame_id += 1;
         
         // TODO: Implement proper World querying when scheduler passes World reference
@@ -17,6 +17,11 @@ impl UiSystem {
         drop(theme_mgr);
         self.log("Info", "[UiSystem] Themes loaded".to_string()).await;
         
+        // Fonts the app loaded before initializing stay the default
+        if !self.font_manager.read().await.has_fonts() {
+            self.load_default_font().await;
+        }
+        
         self.log("Info", "[UiSystem] Creating root element...".to_string()).await;
         let root_id = self.storage.create_element(playground_core_ui::ElementType::Panel).await;
         self.storage.set_root(Some(root_id)).await;
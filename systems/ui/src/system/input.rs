@@ -0,0 +1,101 @@
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use crate::input::{hit_test, ListenerKind, ListenerId};
+use crate::input::focus::focus_index;
+use crate::input::propagation::{event_target, pointer_position, retarget};
+use crate::messages::{DispatchedEventsMessage, UiPacketType, serialize_message};
+use playground_core_ui::{ElementId, EventResult, UiEvent as CoreUiEvent};
+use playground_core_types::Priority;
+
+impl UiSystem {
+    /// Listen for events reaching `element`, in the capture phase if
+    /// `capture` and the bubble phase otherwise. Deliveries are reported
+    /// with the listener's id as `DispatchedEvents` packets each frame.
+    pub async fn add_event_listener(&self, element: ElementId, capture: bool, kind: ListenerKind) -> ListenerId {
+        self.input_manager.write().await.dispatcher.add_listener(element, capture, kind)
+    }
+
+    pub async fn remove_event_listener(&self, id: ListenerId) -> bool {
+        self.input_manager.write().await.dispatcher.remove_listener(id)
+    }
+
+    /// Topmost visible element at a screen position
+    pub async fn element_at(&self, x: f32, y: f32) -> UiResult<Option<ElementId>> {
+        hit_test(x, y, &self.element_graph, &self.world).await
+    }
+
+    /// Focus an element, or clear focus with None
+    pub async fn focus_element(&self, element: Option<ElementId>) -> UiResult<()> {
+        self.input_manager.write().await
+            .set_focus(element, &self.element_graph, &self.world).await
+    }
+
+    /// Move focus along the tab order, backwards when `reverse`
+    pub async fn focus_next(&self, reverse: bool) -> UiResult<Option<ElementId>> {
+        self.input_manager.write().await
+            .focus_next(reverse, &self.element_graph, &self.world).await
+    }
+
+    /// Propagate an event through the element graph. Pointer events are
+    /// delivered to whatever is under the pointer, and a touch that starts
+    /// on a focusable element focuses it.
+    pub async fn dispatch_event(&self, event: CoreUiEvent) -> UiResult<EventResult> {
+        let (event, target) = match pointer_position(&event) {
+            Some((x, y)) => match self.element_at(x, y).await? {
+                Some(hit) => (retarget(&event, hit), hit),
+                None => return Ok(EventResult::Ignored),
+            },
+            None => match event_target(&event) {
+                Some(target) => (event, target),
+                // Keyboard events go to the focused element
+                None => match self.input_manager.read().await.get_focused_element() {
+                    Some(focused) => (event, focused),
+                    None => return Ok(EventResult::Ignored),
+                },
+            },
+        };
+
+        if let CoreUiEvent::TouchStart { .. } = event {
+            let focusable = focus_index(target, &self.world).await?.is_some();
+            self.focus_element(focusable.then_some(target)).await?;
+        }
+
+        Ok(self.deliver(&event, target).await)
+    }
+
+    /// Deliver the events the system raised itself since the last frame
+    /// (focus changes, finished or cancelled animations), oldest first.
+    /// Events raised while delivering wait for the next frame.
+    pub(super) async fn dispatch_pending_events(&self) -> UiResult<()> {
+        let events = std::mem::take(&mut *self.pending_events.write().await);
+        for event in events {
+            self.dispatch_event(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Send the deliveries recorded since the last frame to their owners.
+    /// Without networking they are dropped, as nobody could receive them.
+    pub(super) async fn publish_dispatched_events(&self) -> UiResult<()> {
+        let events = self.input_manager.write().await.dispatcher.take_dispatched();
+        if events.is_empty() {
+            return Ok(());
+        }
+        let Some(ref networking) = self.networking_system else {
+            return Ok(());
+        };
+
+        let data = serialize_message(&DispatchedEventsMessage { events })?;
+        networking.read().await
+            .send_packet(self.channel_id, UiPacketType::DispatchedEvents as u16, data.to_vec(), Priority::High)
+            .await
+            .map_err(|e| UiError::NetworkError(format!("Failed to send dispatched events: {}", e)))
+    }
+
+    /// Propagate an event to `target` as is, without hit testing
+    pub(super) async fn deliver(&self, event: &CoreUiEvent, target: ElementId) -> EventResult {
+        let mut input = self.input_manager.write().await;
+        let graph = self.element_graph.read().await;
+        input.dispatcher.dispatch(event, target, &graph)
+    }
+}
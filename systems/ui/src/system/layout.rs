@@ -1,11 +1,19 @@
+use std::collections::HashSet;
 use crate::system::UiSystem;
-use crate::error::{UiError, UiResult};
+use crate::error::UiResult;
 use crate::element::ElementId;
 use playground_core_ecs::EntityId;
 
 impl UiSystem {
+    /// Element's text, style or children changed; re-layout it and its parent
     pub async fn mark_dirty(&mut self, element: ElementId) -> UiResult<()> {
-        self.dirty_elements.write().await.push(element);
+        self.element_graph.write().await.mark_layout_dirty(element);
+        Ok(())
+    }
+    
+    /// Element changed appearance without changing size; repaint only
+    pub async fn mark_render_dirty(&mut self, element: ElementId) -> UiResult<()> {
+        self.element_graph.write().await.mark_render_dirty(element);
         Ok(())
     }
     
@@ -13,35 +21,60 @@ impl UiSystem {
         self.update_layout().await
     }
     
+    /// Drag a splitter of a docking container and re-layout its panes
+    pub async fn resize_split(&mut self, element: ElementId, splitter: usize, delta: f32) -> UiResult<()> {
+        self.layout_engine.write().await
+            .resize_split(element, splitter, delta, &self.element_graph, &self.world).await?;
+        self.mark_dirty(element).await
+    }
+    
+    /// Lay out dirty elements, descending only into children whose bounds
+    /// changed. Clean subtrees are left untouched.
     pub(super) async fn update_layout(&mut self) -> UiResult<()> {
-        let dirty = self.dirty_elements.read().await.clone();
-        
-        if dirty.is_empty() {
+        let mut levels = self.element_graph.write().await.take_layout_dirty();
+        if levels.is_empty() {
             return Ok(());
         }
         
         let mut layout_engine = self.layout_engine.write().await;
+        let mut done = HashSet::new();
         
-        for entity in dirty {
-            layout_engine.calculate_layout(
-                entity,
-                &self.element_graph,
-                &self.world,
-                self.screen_size,
-            ).await?;
+        let mut depth = 0;
+        while depth < levels.len() {
+            for entity in std::mem::take(&mut levels[depth]) {
+                if !done.insert(entity) {
+                    continue;
+                }
+                
+                let changed = layout_engine.calculate_layout(
+                    entity,
+                    &self.element_graph,
+                    &self.world,
+                    self.screen_size,
+                ).await?;
+                if changed.is_empty() {
+                    continue;
+                }
+                
+                let mut graph = self.element_graph.write().await;
+                for &child in &changed {
+                    graph.mark_render_dirty(child);
+                }
+                drop(graph);
+                
+                if levels.len() == depth + 1 {
+                    levels.push(Vec::new());
+                }
+                levels[depth + 1].extend(changed);
+            }
+            depth += 1;
         }
         
         Ok(())
     }
     
     pub(super) async fn mark_subtree_dirty(&self, root: EntityId) -> UiResult<()> {
-        let graph = self.element_graph.read().await;
-        let mut dirty = self.dirty_elements.write().await;
-        
-        for element in graph.iter_depth_first(root) {
-            dirty.push(element);
-        }
-        
+        self.element_graph.write().await.mark_subtree_dirty(root);
         Ok(())
     }
-}
\ No newline at end of file
+}
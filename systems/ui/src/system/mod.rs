@@ -6,6 +6,12 @@ mod rendering;
 mod shaders;
 mod ui_renderer_impl;
 mod layout;
+mod text;
+mod animation;
+mod input;
+mod theme;
+mod accessibility;
+mod terminal;
 
 // Re-export the main type
 pub use core::UiSystem;
\ No newline at end of file
@@ -3,9 +3,11 @@ use crate::{
     messages::{RendererInitMessage, ViewportConfig, BlendMode, ShaderProgram, UiPacketType},
     theme::Theme,
     types::ElementBounds,
-    components::{UiElementComponent, UiLayoutComponent, UiStyleComponent},
+    components::{UiElementComponent, UiLayoutComponent, UiStyleComponent, Overflow},
+    input::children_in_paint_order,
     rendering::ui_to_render_commands,
     system::UiSystem,
+    text::FontManager,
 };
 use playground_core_rendering::{RenderCommandBatch, RenderCommand};
 use playground_core_types::Shared;
@@ -14,12 +16,22 @@ use playground_core_types::Priority;
 use playground_core_ecs::EntityId;
 
 impl UiSystem {
+    /// Append the commands for `entity` and its subtree. Clean subtrees
+    /// replay the commands recorded when they were last drawn.
     pub async fn render_element_tree(
         &self,
         entity: EntityId,
         batch: &mut RenderCommandBatch,
         theme: &Theme,
+        fonts: &mut FontManager,
     ) -> UiResult<()> {
+        if let Some(commands) = self.element_graph.read().await.cached_commands(entity) {
+            for command in commands {
+                batch.push(command.clone());
+            }
+            return Ok(());
+        }
+        
         // Get all components for this element - World handles its own locking
         let element = self.world.get_component::<UiElementComponent>(entity).await
             .map_err(|e| UiError::EcsError(e.to_string()))?;
@@ -28,16 +40,37 @@ impl UiSystem {
         let style = self.world.get_component::<UiStyleComponent>(entity).await
             .map_err(|e| UiError::EcsError(e.to_string()))?;
         
-        // Convert to render commands
-        ui_to_render_commands(&element, &layout, &style, theme, batch)?;
+        // Convert to render commands, collected separately so they can be cached
+        let mut subtree = RenderCommandBatch::new(self.frame_id);
+        let terminals = self.terminal_manager.read().await;
+        let terminal = match self.terminal_for_element(entity).await {
+            Some(id) => terminals.get(id),
+            None => None,
+        };
+        ui_to_render_commands(&element, &layout, &style, terminal, theme, fonts, &mut subtree)?;
+        drop(terminals);
+        
+        // Render children in z-index order, clipped unless overflow is visible
+        let clips = style.overflow != Overflow::Visible;
+        if clips {
+            subtree.push(RenderCommand::SetClipRect {
+                position: [layout.bounds.x, layout.bounds.y],
+                size: [layout.bounds.width, layout.bounds.height],
+            });
+        }
+        let children = children_in_paint_order(entity, &self.element_graph, &self.world).await?;
+        for child in children {
+            Box::pin(self.render_element_tree(child, &mut subtree, theme, fonts)).await?;
+        }
+        if clips {
+            subtree.push(RenderCommand::ClearClipRect);
+        }
         
-        // Render children
-        let graph = self.element_graph.read().await;
-        if let Some(children) = graph.get_children(entity) {
-            for &child in children {
-                Box::pin(self.render_element_tree(child, batch, theme)).await?;
-            }
+        let commands = subtree.commands().to_vec();
+        for command in &commands {
+            batch.push(command.clone());
         }
+        self.element_graph.write().await.store_commands(entity, commands);
         
         Ok(())
     }
@@ -90,11 +123,11 @@ impl UiSystem {
             }
         }
         
-        // First update layout for any dirty elements
-        self.update_layout().await?;
+        // Events and animations first, as they move and restyle elements
+        self.advance_frame().await?;
         
-        // Clear dirty list after layout update
-        self.dirty_elements.write().await.clear();
+        // Then update layout for any dirty elements
+        self.update_layout().await?;
         
         // Get the current theme
         let theme_mgr = self.theme_manager.read().await;
@@ -131,7 +164,8 @@ impl UiSystem {
                     ).await;
                 }
             }
-            self.render_element_tree(root, &mut batch, &theme).await?;
+            let mut fonts = self.font_manager.write().await;
+            self.render_element_tree(root, &mut batch, &theme, &mut fonts).await?;
         } else {
             // Log that we have no root
             if let Some(ref networking) = self.networking_system {
@@ -147,6 +181,9 @@ impl UiSystem {
             }
         }
         
+        // Glyphs rasterized for this frame must reach the client before the batch
+        self.upload_glyph_pages().await?;
+        
         // Send the batch through channel 10
         self.send_batch(&batch).await?;
         
@@ -0,0 +1,63 @@
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use playground_core_ui::ElementId;
+use uuid::Uuid;
+
+impl UiSystem {
+    pub async fn create_terminal(&self, cols: usize, rows: usize) -> UiResult<Uuid> {
+        let id = Uuid::new_v4();
+        let mut terminals = self.terminal_manager.write().await;
+        terminals.create_terminal(id).await?;
+        terminals.resize_terminal(id, cols, rows).await?;
+        Ok(id)
+    }
+
+    pub async fn destroy_terminal(&self, terminal: Uuid) -> UiResult<()> {
+        self.disconnect_terminal(terminal).await;
+        self.terminal_manager.write().await.destroy_terminal(terminal).await
+    }
+
+    /// Show `terminal` in `element`, which should be a "terminal" element
+    pub async fn connect_terminal(&self, terminal: Uuid, element: ElementId) -> UiResult<()> {
+        if self.terminal_manager.read().await.get(terminal).is_none() {
+            return Err(UiError::TerminalError(format!("Terminal {} not found", terminal)));
+        }
+        self.terminal_connections.write().await.insert(terminal, element);
+        self.element_graph.write().await.mark_render_dirty(element);
+        Ok(())
+    }
+
+    pub async fn disconnect_terminal(&self, terminal: Uuid) {
+        if let Some(element) = self.terminal_connections.write().await.remove(&terminal) {
+            self.element_graph.write().await.mark_render_dirty(element);
+        }
+    }
+
+    /// Feed program output to `terminal`, escape sequences included
+    pub async fn write_terminal(&self, terminal: Uuid, bytes: &[u8]) -> UiResult<()> {
+        self.terminal_manager.write().await.feed_terminal(terminal, bytes).await?;
+        self.redraw_terminal(terminal).await;
+        Ok(())
+    }
+
+    /// Scroll the view of `terminal` back through scrollback by `lines`, or
+    /// forward when negative
+    pub async fn scroll_terminal(&self, terminal: Uuid, lines: isize) -> UiResult<()> {
+        self.terminal_manager.write().await.get_mut(terminal)?.scroll_display(lines);
+        self.redraw_terminal(terminal).await;
+        Ok(())
+    }
+
+    /// The terminal shown in `element`, if any
+    pub(super) async fn terminal_for_element(&self, element: ElementId) -> Option<Uuid> {
+        self.terminal_connections.read().await.iter()
+            .find(|&(_, &connected)| connected == element)
+            .map(|(&terminal, _)| terminal)
+    }
+
+    async fn redraw_terminal(&self, terminal: Uuid) {
+        if let Some(&element) = self.terminal_connections.read().await.get(&terminal) {
+            self.element_graph.write().await.mark_render_dirty(element);
+        }
+    }
+}
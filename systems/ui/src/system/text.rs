@@ -0,0 +1,87 @@
+use std::path::Path;
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use crate::components::{FontWeight, UiStyleComponent};
+use crate::messages::{LoadTextureMessage, TextureFormat, UiPacketType};
+use crate::text::{FontId, TextStyle};
+use playground_core_types::Priority;
+
+/// Family the default font is registered under, which is what themes ask for
+const DEFAULT_FONT_FAMILY: &str = "monospace";
+
+/// Fonts tried at startup after `PLAYGROUND_FONT`, first found wins:
+/// Android's system monospace fonts, then the usual Linux and macOS ones
+const DEFAULT_FONT_PATHS: &[&str] = &[
+    "/system/fonts/DroidSansMono.ttf",
+    "/system/fonts/CutiveMono.ttf",
+    "/usr/share/fonts/truetype/dejavu/DejaVuSansMono.ttf",
+    "/usr/share/fonts/TTF/DejaVuSansMono.ttf",
+    "/usr/share/fonts/dejavu-sans-mono-fonts/DejaVuSansMono.ttf",
+    "/System/Library/Fonts/Menlo.ttc",
+];
+
+impl UiSystem {
+    /// Load the first default font that exists, so text is drawn from the
+    /// glyph atlas from the start. Returns None if there is none, leaving
+    /// text on the `DrawText` fallback until a font is loaded.
+    pub async fn load_default_font(&self) -> Option<FontId> {
+        let configured = std::env::var("PLAYGROUND_FONT").ok();
+        let candidates = configured.iter().map(String::as_str).chain(DEFAULT_FONT_PATHS.iter().copied());
+        for path in candidates {
+            match self.load_font_file(DEFAULT_FONT_FAMILY, FontWeight::Normal, path).await {
+                Ok(font) => {
+                    self.log("Info", format!("[UiSystem] Default font loaded from {}", path)).await;
+                    return Some(font);
+                }
+                Err(e) => self.log("Info", format!("[UiSystem] No default font at {}: {}", path, e)).await,
+            }
+        }
+        self.log("Warning", "[UiSystem] No default font found, text is drawn without glyphs".to_string()).await;
+        None
+    }
+
+    /// Load a TTF or OTF font from memory
+    pub async fn load_font(&self, family: &str, weight: FontWeight, bytes: Vec<u8>) -> UiResult<FontId> {
+        self.font_manager.write().await.load_font(family, weight, bytes)
+    }
+
+    /// Load a TTF or OTF font from disk
+    pub async fn load_font_file(&self, family: &str, weight: FontWeight, path: impl AsRef<Path>) -> UiResult<FontId> {
+        let bytes = tokio::fs::read(path.as_ref()).await
+            .map_err(|e| UiError::FontError(format!("{}: {}", path.as_ref().display(), e)))?;
+        self.load_font(family, weight, bytes).await
+    }
+
+    /// Size of text drawn with an element style, wrapped at `max_width`
+    pub async fn measure_text(&self, text: &str, style: &UiStyleComponent, max_width: Option<f32>) -> [f32; 2] {
+        self.font_manager.read().await.measure(text, &TextStyle::from_style(style, max_width))
+    }
+
+    /// Send glyph pages changed since the last upload as `LoadTexture` packets.
+    /// Without networking nothing is sent and the pages stay dirty, so they
+    /// go out with the first frame after networking is connected.
+    pub(super) async fn upload_glyph_pages(&self) -> UiResult<()> {
+        let Some(ref networking) = self.networking_system else {
+            return Ok(());
+        };
+        let networking = networking.read().await;
+
+        let mut fonts = self.font_manager.write().await;
+        for page in fonts.take_dirty_pages() {
+            let message = LoadTextureMessage {
+                id: page.texture_id,
+                width: page.width,
+                height: page.height,
+                format: TextureFormat::RGBA8,
+                data: page.pixels.clone(),
+            };
+            let data = bincode::serialize(&message)
+                .map_err(|e| UiError::SerializationError(e.to_string()))?;
+            networking.send_packet(self.channel_id, UiPacketType::LoadTexture as u16, data, Priority::High)
+                .await
+                .map_err(|e| UiError::NetworkError(format!("Failed to send glyph page: {}", e)))?;
+        }
+
+        Ok(())
+    }
+}
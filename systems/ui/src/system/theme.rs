@@ -0,0 +1,35 @@
+use crate::system::UiSystem;
+use crate::error::{UiError, UiResult};
+use crate::theme::ThemeId;
+
+impl UiSystem {
+    /// Register a theme file (see `theme::file`) as `name`. Re-registering
+    /// a name reloads it, along with the themes that extend it.
+    pub async fn register_theme(&mut self, name: &str, data: &[u8]) -> UiResult<ThemeId> {
+        let result = self.theme_manager.write().await.register_theme(name, data);
+        // The current theme may be the one reloaded, or built on it
+        self.element_graph.write().await.clear_render_cache();
+        result
+    }
+
+    pub async fn set_theme(&mut self, id: ThemeId) -> UiResult<()> {
+        self.theme_manager.write().await.set_current(id)?;
+        self.current_theme = id;
+        self.element_graph.write().await.clear_render_cache();
+        Ok(())
+    }
+
+    pub async fn set_theme_by_name(&mut self, name: &str) -> UiResult<()> {
+        let id = self.theme_manager.read().await.find(name)
+            .ok_or_else(|| UiError::ThemeNotFound(name.to_string()))?;
+        self.set_theme(id).await
+    }
+
+    /// Name of the current theme
+    pub async fn theme_name(&self) -> String {
+        let themes = self.theme_manager.read().await;
+        themes.get_theme(self.current_theme)
+            .map(|theme| theme.name.clone())
+            .unwrap_or_else(|_| self.current_theme.to_string())
+    }
+}
@@ -2,7 +2,7 @@ use crate::system::UiSystem;
 use crate::error::UiError;
 use async_trait::async_trait;
 use playground_core_ui::{
-    UiRenderer, UiElementWrapper, ElementId as CoreElementId, ElementType as CoreElementType,
+    UiRenderer, AnimatedRenderer, ThemedRenderer, Animation, AnimationId, UiElementWrapper, ElementId as CoreElementId, ElementType as CoreElementType,
     UiCommand, UiEvent as CoreUiEvent, ElementUpdate, EventResult, Orientation,
     error::{UiResult as CoreUiResult, UiError as CoreUiError},
 };
@@ -117,8 +117,8 @@ impl UiRenderer for UiSystem {
     }
     
     async fn handle_event(&mut self, event: CoreUiEvent) -> CoreUiResult<EventResult> {
-        // Convert core event to our internal event and handle
-        Ok(EventResult::Ignored)
+        self.dispatch_event(event).await
+            .map_err(|e| CoreUiError::EventHandlingFailed(e.to_string()))
     }
     
     async fn calculate_layout(&mut self) -> CoreUiResult<()> {
@@ -129,6 +129,11 @@ impl UiRenderer for UiSystem {
     async fn render_frame(&mut self, frame_id: u64) -> CoreUiResult<RenderCommandBatch> {
         self.frame_id = frame_id;
         
+        // Listeners see last frame's focus changes and animation ends,
+        // and animations move, before anything is drawn
+        self.advance_frame().await
+            .map_err(|e| CoreUiError::EventHandlingFailed(e.to_string()))?;
+        
         // Create render command batch
         let mut batch = RenderCommandBatch::new(frame_id);
         
@@ -145,10 +150,31 @@ impl UiRenderer for UiSystem {
                 .clone();
             drop(theme_mgr);
             
-            self.render_element_tree(root, &mut batch, &theme).await
+            // Anything the accessibility tree shows also repaints, so a
+            // frame replayed from cache has nothing new to publish, and only
+            // subtrees drawn again need describing
+            let (changed, dirty) = {
+                let graph = self.element_graph.read().await;
+                (graph.cached_commands(root).is_none(), graph.render_dirty_elements())
+            };
+            
+            let mut fonts = self.font_manager.write().await;
+            self.render_element_tree(root, &mut batch, &theme, &mut fonts).await
+                .map_err(|e| CoreUiError::RenderingFailed(e.to_string()))?;
+            drop(fonts);
+            
+            self.upload_glyph_pages().await
                 .map_err(|e| CoreUiError::RenderingFailed(e.to_string()))?;
+            
+            if changed {
+                self.publish_accessibility_tree(&dirty).await
+                    .map_err(|e| CoreUiError::RenderingFailed(e.to_string()))?;
+            }
         }
         
+        self.publish_dispatched_events().await
+            .map_err(|e| CoreUiError::EventHandlingFailed(e.to_string()))?;
+        
         Ok(batch)
     }
     
@@ -166,9 +192,11 @@ impl UiRenderer for UiSystem {
         };
         self.screen_size = [width, height];
         
-        // Update root element bounds
-        if let Some(_root) = self.root_entity {
-            // TODO: Update root layout bounds to match viewport
+        // Root takes the new screen size on its next layout; descendants are
+        // revisited only where their bounds change
+        if let Some(root) = self.root_entity {
+            self.mark_dirty(root).await
+                .map_err(|e| CoreUiError::LayoutFailed(e.to_string()))?;
         }
         
         Ok(())
@@ -216,4 +244,46 @@ impl UiRenderer for UiSystem {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Implement AnimatedRenderer trait from core/ui
+#[async_trait]
+impl AnimatedRenderer for UiSystem {
+    async fn animate_element(
+        &mut self,
+        element: CoreElementId,
+        animation: Animation,
+    ) -> CoreUiResult<AnimationId> {
+        self.animate(element, animation).await
+            .map_err(|e| CoreUiError::InvalidOperation(e.to_string()))
+    }
+    
+    async fn stop_animation(&mut self, animation_id: AnimationId) -> CoreUiResult<()> {
+        self.cancel_animation(animation_id).await
+            .map_err(|e| CoreUiError::InvalidOperation(e.to_string()))
+    }
+    
+    async fn update_animations(&mut self, delta_time: f32) -> CoreUiResult<()> {
+        self.step_animations(delta_time).await
+            .map_err(|e| CoreUiError::RenderingFailed(e.to_string()))
+    }
+}
+
+// Implement ThemedRenderer trait from core/ui
+#[async_trait]
+impl ThemedRenderer for UiSystem {
+    async fn set_theme(&mut self, theme_id: String) -> CoreUiResult<()> {
+        self.set_theme_by_name(&theme_id).await
+            .map_err(|e| CoreUiError::StyleError(e.to_string()))
+    }
+    
+    async fn get_theme(&self) -> String {
+        self.theme_name().await
+    }
+    
+    async fn register_theme(&mut self, theme_id: String, theme_data: Vec<u8>) -> CoreUiResult<()> {
+        UiSystem::register_theme(self, &theme_id, &theme_data).await
+            .map(|_| ())
+            .map_err(|e| CoreUiError::StyleError(e.to_string()))
+    }
+}
@@ -1,67 +1,1293 @@
+//! VT100/xterm terminal emulator
+//!
+//! Bytes from the program go through the `Parser` and update a grid of
+//! cells. Supported: cursor movement and save/restore, scroll regions,
+//! insert/delete of characters and lines, erase, tab stops, SGR with 16,
+//! 256 and 24-bit colors, the DEC line drawing character set, the
+//! alternate screen, and the modes full-screen programs switch (autowrap,
+//! origin, insert, application cursor keys, bracketed paste, mouse
+//! reporting). Wide (CJK) characters take two cells. Replies to status queries collect in `take_responses` for
+//! the caller to send back to the program.
+
+use unicode_width::UnicodeWidthChar;
 use uuid::Uuid;
+use super::grid::{Cell, Grid, Row, TerminalColor};
+use super::parser::{Parser, Perform};
+
+const DEFAULT_COLS: usize = 80;
+const DEFAULT_ROWS: usize = 24;
+const SCROLLBACK_LINES: usize = 1000;
+const TAB_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MouseMode {
+    #[default]
+    None,
+    /// Button presses and releases
+    Click,
+    /// Also motion while a button is held
+    Drag,
+    /// All motion
+    Motion,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TerminalModes {
+    pub insert: bool,
+    /// Cursor addressing is relative to the scroll region
+    pub origin: bool,
+    pub autowrap: bool,
+    /// Line feed also returns the carriage
+    pub linefeed_newline: bool,
+    pub cursor_visible: bool,
+    pub application_cursor: bool,
+    pub application_keypad: bool,
+    pub bracketed_paste: bool,
+    pub mouse: MouseMode,
+    /// Mouse reports use the SGR encoding
+    pub sgr_mouse: bool,
+}
+
+impl Default for TerminalModes {
+    fn default() -> Self {
+        Self {
+            insert: false,
+            origin: false,
+            autowrap: true,
+            linefeed_newline: false,
+            cursor_visible: true,
+            application_cursor: false,
+            application_keypad: false,
+            bracketed_paste: false,
+            mouse: MouseMode::None,
+            sgr_mouse: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Charset {
+    Ascii,
+    /// DEC special graphics, used for line drawing
+    DecSpecial,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    pen: Cell,
+    origin: bool,
+    autowrap: bool,
+    charsets: [Charset; 2],
+    active_charset: usize,
+}
 
 pub struct Terminal {
     id: Uuid,
-    lines: Vec<String>,
-    cursor_position: (usize, usize),
-    max_lines: usize,
+    parser: Parser,
+    screen: Screen,
 }
 
 impl Terminal {
     pub fn new(id: Uuid) -> Self {
+        Self::with_size(id, DEFAULT_COLS, DEFAULT_ROWS)
+    }
+
+    pub fn with_size(id: Uuid, cols: usize, rows: usize) -> Self {
         Self {
             id,
-            lines: vec![String::new()],
-            cursor_position: (0, 0),
-            max_lines: 1000,
+            parser: Parser::new(),
+            screen: Screen::new(cols.max(1), rows.max(1)),
         }
     }
-    
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Process output from the program
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.parser.advance(&mut self.screen, bytes);
+    }
+
     pub fn write(&mut self, text: String) {
-        // Simple terminal emulation
-        for ch in text.chars() {
-            match ch {
-                '\n' => {
-                    self.lines.push(String::new());
-                    self.cursor_position.0 += 1;
-                    self.cursor_position.1 = 0;
-                    
-                    // Limit lines
-                    if self.lines.len() > self.max_lines {
-                        self.lines.remove(0);
-                        if self.cursor_position.0 > 0 {
-                            self.cursor_position.0 -= 1;
-                        }
+        self.feed(text.as_bytes());
+    }
+
+    /// Scrollback and screen as text, one string per row, without the
+    /// blank rows below the last written one
+    pub fn read(&self) -> Vec<String> {
+        let grid = self.screen.grid();
+        let mut lines: Vec<String> = grid.scrollback().iter()
+            .chain((0..grid.rows()).map(|row| grid.row(row)))
+            .map(Row::text)
+            .collect();
+        let cursor_line = grid.scrollback().len() + self.screen.row;
+        while lines.len() > cursor_line + 1 && lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines
+    }
+
+    pub fn resize(&mut self, cols: usize, rows: usize) {
+        self.screen.resize(cols.max(1), rows.max(1));
+    }
+
+    pub fn cols(&self) -> usize {
+        self.screen.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.screen.rows
+    }
+
+    /// Cursor as `(row, col)` on the screen
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.screen.row, self.screen.col)
+    }
+
+    pub fn cursor_shape(&self) -> CursorShape {
+        self.screen.cursor_shape
+    }
+
+    pub fn modes(&self) -> &TerminalModes {
+        &self.screen.modes
+    }
+
+    /// Window title set by the program
+    pub fn title(&self) -> &str {
+        &self.screen.title
+    }
+
+    pub fn is_alternate_screen(&self) -> bool {
+        self.screen.alternate_active
+    }
+
+    /// The screen being shown, primary or alternate
+    pub fn grid(&self) -> &Grid {
+        self.screen.grid()
+    }
+
+    /// Row `row` of the view, which is scrolled `display_offset` rows back
+    /// into scrollback
+    pub fn visible_row(&self, row: usize) -> &Row {
+        self.screen.grid().visible_row(row, self.screen.display_offset)
+    }
+
+    pub fn display_offset(&self) -> usize {
+        self.screen.display_offset
+    }
+
+    /// Scroll the view back through scrollback by `lines`, or forward when
+    /// negative
+    pub fn scroll_display(&mut self, lines: isize) {
+        let limit = self.screen.grid().scrollback().len();
+        let offset = self.screen.display_offset as isize + lines;
+        self.screen.display_offset = offset.clamp(0, limit as isize) as usize;
+    }
+
+    /// Replies owed to the program, e.g. cursor position reports
+    pub fn take_responses(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.screen.responses)
+    }
+
+    /// Whether the bell rang since the last call
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.screen.bell)
+    }
+
+    /// Escape sequences that rebuild this terminal when fed to another one
+    /// of the same size: scrollback, both screens with their colors and
+    /// attributes, the scroll region, the modes programs switch, the cursor
+    /// and the pen. Lets a viewer that attaches late catch up without the
+    /// raw output, which may have scrolled past an alternate screen switch
+    /// or the SGR that colors what is on screen now.
+    pub fn snapshot(&self) -> String {
+        let screen = &self.screen;
+        // Reset, including the receiver's scrollback
+        let mut out = String::from("\x1bc\x1b[3J");
+
+        // Rows beyond the screen height scroll into scrollback as they go
+        let primary = &screen.primary;
+        let rows: Vec<&Row> = primary.scrollback().iter()
+            .chain((0..primary.rows()).map(|row| primary.row(row)))
+            .collect();
+        write_rows(&mut out, &rows);
+
+        if screen.alternate_active {
+            // 1049 saves the cursor the primary screen returns to
+            let (row, col) = screen.saved[0].map_or((0, 0), |saved| (saved.row, saved.col));
+            out.push_str(&format!("\x1b[{};{}H\x1b[?1049h\x1b[H", row + 1, col + 1));
+            let alternate = &screen.alternate;
+            let rows: Vec<&Row> = (0..alternate.rows()).map(|row| alternate.row(row)).collect();
+            write_rows(&mut out, &rows);
+        }
+
+        if screen.scroll_top != 0 || screen.scroll_bottom != screen.rows {
+            out.push_str(&format!("\x1b[{};{}r", screen.scroll_top + 1, screen.scroll_bottom));
+        }
+
+        let modes = &screen.modes;
+        let switches = [
+            (modes.insert, "\x1b[4h"),
+            (modes.linefeed_newline, "\x1b[20h"),
+            (modes.application_cursor, "\x1b[?1h"),
+            (modes.application_keypad, "\x1b="),
+            (modes.bracketed_paste, "\x1b[?2004h"),
+            (modes.mouse == MouseMode::Click, "\x1b[?1000h"),
+            (modes.mouse == MouseMode::Drag, "\x1b[?1002h"),
+            (modes.mouse == MouseMode::Motion, "\x1b[?1003h"),
+            (modes.sgr_mouse, "\x1b[?1006h"),
+            (!modes.cursor_visible, "\x1b[?25l"),
+            (!modes.autowrap, "\x1b[?7l"),
+        ];
+        for (on, sequence) in switches {
+            if on {
+                out.push_str(sequence);
+            }
+        }
+        match screen.cursor_shape {
+            CursorShape::Block => {}
+            CursorShape::Underline => out.push_str("\x1b[4 q"),
+            CursorShape::Bar => out.push_str("\x1b[6 q"),
+        }
+        if !screen.title.is_empty() {
+            out.push_str(&format!("\x1b]2;{}\x07", screen.title));
+        }
+
+        // Origin mode homes the cursor, and addresses it within the region
+        if modes.origin {
+            out.push_str("\x1b[?6h");
+            out.push_str(&format!("\x1b[{};{}H", screen.row - screen.scroll_top + 1, screen.col + 1));
+        } else {
+            out.push_str(&format!("\x1b[{};{}H", screen.row + 1, screen.col + 1));
+        }
+        out.push_str(&sgr(&screen.pen));
+        out
+    }
+}
+
+/// Terminal state the parser acts on
+struct Screen {
+    cols: usize,
+    rows: usize,
+    primary: Grid,
+    alternate: Grid,
+    alternate_active: bool,
+    row: usize,
+    col: usize,
+    /// The last column was written; the next character wraps first
+    pending_wrap: bool,
+    /// Colors and attributes for new characters
+    pen: Cell,
+    /// DECSC slots for the primary and alternate screens
+    saved: [Option<SavedCursor>; 2],
+    scroll_top: usize,
+    /// Exclusive
+    scroll_bottom: usize,
+    modes: TerminalModes,
+    tab_stops: Vec<bool>,
+    charsets: [Charset; 2],
+    active_charset: usize,
+    cursor_shape: CursorShape,
+    title: String,
+    bell: bool,
+    responses: Vec<u8>,
+    last_printed: Option<char>,
+    display_offset: usize,
+}
+
+impl Screen {
+    fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            primary: Grid::new(cols, rows, SCROLLBACK_LINES),
+            alternate: Grid::new(cols, rows, 0),
+            alternate_active: false,
+            row: 0,
+            col: 0,
+            pending_wrap: false,
+            pen: Cell::default(),
+            saved: [None, None],
+            scroll_top: 0,
+            scroll_bottom: rows,
+            modes: TerminalModes::default(),
+            tab_stops: default_tab_stops(cols),
+            charsets: [Charset::Ascii; 2],
+            active_charset: 0,
+            cursor_shape: CursorShape::default(),
+            title: String::new(),
+            bell: false,
+            responses: Vec::new(),
+            last_printed: None,
+            display_offset: 0,
+        }
+    }
+
+    fn grid(&self) -> &Grid {
+        if self.alternate_active { &self.alternate } else { &self.primary }
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        if self.alternate_active { &mut self.alternate } else { &mut self.primary }
+    }
+
+    fn blank(&self) -> Cell {
+        Cell::blank(self.pen.bg)
+    }
+
+    /// Full reset (RIS), keeping the size and scrollback
+    fn reset(&mut self) {
+        let primary = std::mem::replace(&mut self.primary, Grid::new(1, 1, 0));
+        *self = Self::new(self.cols, self.rows);
+        self.primary = primary;
+        self.primary.clear_rows(0, self.rows, Cell::default());
+    }
+
+    fn resize(&mut self, cols: usize, rows: usize) {
+        if cols == self.cols && rows == self.rows {
+            return;
+        }
+        // Shrinking pushes rows above the cursor into scrollback rather
+        // than losing the rows it is on
+        let shift = (self.row + 1).saturating_sub(rows);
+        let (primary_shift, alternate_shift) = if self.alternate_active { (0, shift) } else { (shift, 0) };
+        self.primary.resize(cols, rows, primary_shift);
+        self.alternate.resize(cols, rows, alternate_shift);
+
+        self.cols = cols;
+        self.rows = rows;
+        self.row = (self.row - shift).min(rows - 1);
+        self.col = self.col.min(cols - 1);
+        self.pending_wrap = false;
+        self.scroll_top = 0;
+        self.scroll_bottom = rows;
+        self.tab_stops = default_tab_stops(cols);
+        self.display_offset = self.display_offset.min(self.grid().scrollback().len());
+    }
+
+    fn goto(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.cols - 1);
+        self.pending_wrap = false;
+    }
+
+    /// CUP: `row` counts from the scroll region in origin mode
+    fn goto_origin(&mut self, row: usize, col: usize) {
+        if self.modes.origin {
+            let row = (self.scroll_top + row).min(self.scroll_bottom - 1);
+            self.goto(row, col);
+        } else {
+            self.goto(row, col);
+        }
+    }
+
+    fn scroll_up(&mut self, count: usize) {
+        let (top, bottom, blank) = (self.scroll_top, self.scroll_bottom, self.blank());
+        self.grid_mut().scroll_up(top, bottom, count, blank);
+        // Someone reading scrollback keeps their place
+        if self.display_offset > 0 && top == 0 {
+            self.display_offset = (self.display_offset + count).min(self.grid().scrollback().len());
+        }
+    }
+
+    fn scroll_down(&mut self, count: usize) {
+        let (top, bottom, blank) = (self.scroll_top, self.scroll_bottom, self.blank());
+        self.grid_mut().scroll_down(top, bottom, count, blank);
+    }
+
+    fn linefeed(&mut self) {
+        self.pending_wrap = false;
+        if self.row + 1 == self.scroll_bottom {
+            self.scroll_up(1);
+        } else if self.row + 1 < self.rows {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.pending_wrap = false;
+        if self.row == self.scroll_top {
+            self.scroll_down(1);
+        } else if self.row > 0 {
+            self.row -= 1;
+        }
+    }
+
+    fn tab_forward(&mut self, count: usize) {
+        for _ in 0..count {
+            self.col = (self.col + 1..self.cols)
+                .find(|&col| self.tab_stops[col])
+                .unwrap_or(self.cols - 1);
+        }
+        self.pending_wrap = false;
+    }
+
+    fn tab_backward(&mut self, count: usize) {
+        for _ in 0..count {
+            self.col = (0..self.col).rev()
+                .find(|&col| self.tab_stops[col])
+                .unwrap_or(0);
+        }
+        self.pending_wrap = false;
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved[self.alternate_active as usize] = Some(SavedCursor {
+            row: self.row,
+            col: self.col,
+            pen: self.pen,
+            origin: self.modes.origin,
+            autowrap: self.modes.autowrap,
+            charsets: self.charsets,
+            active_charset: self.active_charset,
+        });
+    }
+
+    fn restore_cursor(&mut self) {
+        match self.saved[self.alternate_active as usize] {
+            Some(saved) => {
+                self.pen = saved.pen;
+                self.modes.origin = saved.origin;
+                self.modes.autowrap = saved.autowrap;
+                self.charsets = saved.charsets;
+                self.active_charset = saved.active_charset;
+                self.goto(saved.row, saved.col);
+            }
+            None => {
+                self.pen = Cell::default();
+                self.modes.origin = false;
+                self.goto(0, 0);
+            }
+        }
+    }
+
+    fn set_alternate(&mut self, on: bool) {
+        if on == self.alternate_active {
+            return;
+        }
+        self.alternate_active = on;
+        if on {
+            self.alternate.clear_rows(0, self.rows, Cell::default());
+        }
+        self.display_offset = 0;
+    }
+
+    fn erase_display(&mut self, mode: usize) {
+        let (row, col, rows, cols, blank) = (self.row, self.col, self.rows, self.cols, self.blank());
+        let grid = self.grid_mut();
+        match mode {
+            0 => {
+                grid.clear_cells(row, col, cols, blank);
+                grid.clear_rows(row + 1, rows, blank);
+            }
+            1 => {
+                grid.clear_rows(0, row, blank);
+                grid.clear_cells(row, 0, col + 1, blank);
+            }
+            2 => grid.clear_rows(0, rows, blank),
+            3 => {
+                grid.clear_scrollback();
+                self.display_offset = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn erase_line(&mut self, mode: usize) {
+        let (row, col, cols, blank) = (self.row, self.col, self.cols, self.blank());
+        let grid = self.grid_mut();
+        match mode {
+            0 => grid.clear_cells(row, col, cols, blank),
+            1 => grid.clear_cells(row, 0, col + 1, blank),
+            2 => grid.clear_cells(row, 0, cols, blank),
+            _ => {}
+        }
+    }
+
+    fn insert_chars(&mut self, count: usize) {
+        let (row, col, blank) = (self.row, self.col, self.blank());
+        let cells = &mut self.grid_mut().row_mut(row).cells;
+        let count = count.min(cells.len() - col);
+        cells.truncate(cells.len() - count);
+        cells.splice(col..col, std::iter::repeat_n(blank, count));
+        self.pending_wrap = false;
+    }
+
+    fn delete_chars(&mut self, count: usize) {
+        let (row, col, blank) = (self.row, self.col, self.blank());
+        let cells = &mut self.grid_mut().row_mut(row).cells;
+        let count = count.min(cells.len() - col);
+        cells.drain(col..col + count);
+        cells.extend(std::iter::repeat_n(blank, count));
+        self.pending_wrap = false;
+    }
+
+    /// IL and DL work on the region below the cursor, and only inside the
+    /// scroll region
+    fn insert_lines(&mut self, count: usize) {
+        if self.row < self.scroll_top || self.row >= self.scroll_bottom {
+            return;
+        }
+        let (row, bottom, blank) = (self.row, self.scroll_bottom, self.blank());
+        self.grid_mut().scroll_down(row, bottom, count, blank);
+        self.col = 0;
+        self.pending_wrap = false;
+    }
+
+    fn delete_lines(&mut self, count: usize) {
+        if self.row < self.scroll_top || self.row >= self.scroll_bottom {
+            return;
+        }
+        let (row, bottom, blank) = (self.row, self.scroll_bottom, self.blank());
+        self.grid_mut().scroll_up(row, bottom, count, blank);
+        self.col = 0;
+        self.pending_wrap = false;
+    }
+
+    fn set_scroll_region(&mut self, top: usize, bottom: usize) {
+        let top = top.saturating_sub(1);
+        let bottom = if bottom == 0 { self.rows } else { bottom.min(self.rows) };
+        if top + 1 < bottom {
+            self.scroll_top = top;
+            self.scroll_bottom = bottom;
+            self.goto_origin(0, 0);
+        }
+    }
+
+    fn set_mode(&mut self, mode: u16, private: bool, on: bool) {
+        match (private, mode) {
+            (false, 4) => self.modes.insert = on,
+            (false, 20) => self.modes.linefeed_newline = on,
+            (true, 1) => self.modes.application_cursor = on,
+            (true, 6) => {
+                self.modes.origin = on;
+                self.goto_origin(0, 0);
+            }
+            (true, 7) => self.modes.autowrap = on,
+            (true, 25) => self.modes.cursor_visible = on,
+            (true, 47) | (true, 1047) => self.set_alternate(on),
+            (true, 1048) => if on { self.save_cursor() } else { self.restore_cursor() },
+            (true, 1049) => {
+                if on {
+                    self.save_cursor();
+                    self.set_alternate(true);
+                } else {
+                    self.set_alternate(false);
+                    self.restore_cursor();
+                }
+            }
+            (true, 1000) => self.modes.mouse = if on { MouseMode::Click } else { MouseMode::None },
+            (true, 1002) => self.modes.mouse = if on { MouseMode::Drag } else { MouseMode::None },
+            (true, 1003) => self.modes.mouse = if on { MouseMode::Motion } else { MouseMode::None },
+            (true, 1006) => self.modes.sgr_mouse = on,
+            (true, 2004) => self.modes.bracketed_paste = on,
+            _ => {}
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[Vec<u16>]) {
+        let mut i = 0;
+        while i < params.len() {
+            let param = &params[i];
+            let attrs = &mut self.pen.attrs;
+            match param[0] {
+                0 => self.pen = Cell::default(),
+                1 => attrs.bold = true,
+                2 => attrs.dim = true,
+                3 => attrs.italic = true,
+                // 4:0 turns underline off; other styles draw as underline
+                4 => attrs.underline = param.get(1).is_none_or(|&style| style != 0),
+                5 | 6 => attrs.blink = true,
+                7 => attrs.inverse = true,
+                8 => attrs.hidden = true,
+                9 => attrs.strikethrough = true,
+                21 => attrs.underline = true,
+                22 => {
+                    attrs.bold = false;
+                    attrs.dim = false;
+                }
+                23 => attrs.italic = false,
+                24 => attrs.underline = false,
+                25 => attrs.blink = false,
+                27 => attrs.inverse = false,
+                28 => attrs.hidden = false,
+                29 => attrs.strikethrough = false,
+                n @ 30..=37 => self.pen.fg = TerminalColor::Indexed((n - 30) as u8),
+                38 => {
+                    let (color, used) = extended_color(params, i);
+                    if let Some(color) = color {
+                        self.pen.fg = color;
                     }
+                    i += used;
                 }
-                '\r' => {
-                    self.cursor_position.1 = 0;
+                39 => self.pen.fg = TerminalColor::Default,
+                n @ 40..=47 => self.pen.bg = TerminalColor::Indexed((n - 40) as u8),
+                48 => {
+                    let (color, used) = extended_color(params, i);
+                    if let Some(color) = color {
+                        self.pen.bg = color;
+                    }
+                    i += used;
+                }
+                49 => self.pen.bg = TerminalColor::Default,
+                n @ 90..=97 => self.pen.fg = TerminalColor::Indexed((n - 90 + 8) as u8),
+                n @ 100..=107 => self.pen.bg = TerminalColor::Indexed((n - 100 + 8) as u8),
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+
+    /// Turn both halves of a wide character that covers `col` into blanks
+    /// in its colors, before one half is overwritten
+    fn split_wide(&mut self, row: usize, col: usize) {
+        let cells = &mut self.grid_mut().row_mut(row).cells;
+        let start = match cells[col].width {
+            0 if col > 0 => col - 1,
+            2 => col,
+            _ => return,
+        };
+        let end = (start + 2).min(cells.len());
+        for cell in &mut cells[start..end] {
+            *cell = Cell { ch: ' ', width: 1, ..*cell };
+        }
+    }
+
+    fn report(&mut self, reply: &str) {
+        self.responses.extend_from_slice(reply.as_bytes());
+    }
+}
+
+impl Perform for Screen {
+    fn print(&mut self, ch: char) {
+        let ch = match self.charsets[self.active_charset] {
+            Charset::Ascii => ch,
+            Charset::DecSpecial => dec_special(ch),
+        };
+
+        // Combining marks have no cell of their own to go in
+        let width = match ch.width() {
+            None | Some(0) => return,
+            Some(width) => width.min(self.cols),
+        };
+
+        // A wide character that doesn't fit in the last column wraps whole
+        if self.pending_wrap || self.col + width > self.cols {
+            if self.modes.autowrap {
+                let row = self.row;
+                self.grid_mut().row_mut(row).wrapped = true;
+                self.col = 0;
+                self.linefeed();
+            } else {
+                self.col = self.cols - width;
+            }
+            self.pending_wrap = false;
+        }
+
+        let (row, col, pen) = (self.row, self.col, self.pen);
+        if self.modes.insert {
+            let cells = &mut self.grid_mut().row_mut(row).cells;
+            cells.truncate(cells.len() - width);
+            cells.splice(col..col, std::iter::repeat_n(Cell::blank(pen.bg), width));
+        }
+        self.split_wide(row, col);
+        self.split_wide(row, col + width - 1);
+        *self.grid_mut().cell_mut(row, col) = Cell { ch, width: width as u8, ..pen };
+        if width == 2 {
+            *self.grid_mut().cell_mut(row, col + 1) = Cell { ch: ' ', width: 0, ..pen };
+        }
+
+        if col + width < self.cols {
+            self.col += width;
+        } else {
+            self.pending_wrap = true;
+        }
+        self.last_printed = Some(ch);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x07 => self.bell = true,
+            0x08 => {
+                self.col = self.col.saturating_sub(1);
+                self.pending_wrap = false;
+            }
+            0x09 => self.tab_forward(1),
+            0x0a..=0x0c => {
+                self.linefeed();
+                if self.modes.linefeed_newline {
+                    self.col = 0;
                 }
-                '\t' => {
-                    // Tab to next 4-space boundary
-                    let spaces = 4 - (self.cursor_position.1 % 4);
-                    for _ in 0..spaces {
-                        if let Some(line) = self.lines.get_mut(self.cursor_position.0) {
-                            line.push(' ');
-                        }
-                        self.cursor_position.1 += 1;
+            }
+            0x0d => {
+                self.col = 0;
+                self.pending_wrap = false;
+            }
+            0x0e => self.active_charset = 1,
+            0x0f => self.active_charset = 0,
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], private: Option<u8>, action: char) {
+        // First value of parameter `i`, with `default` for 0 or missing
+        let arg = |i: usize, default: usize| match params.get(i).map(|param| param[0] as usize) {
+            None | Some(0) => default,
+            Some(value) => value,
+        };
+        let n = arg(0, 1);
+
+        match (private, intermediates, action) {
+            (None, [], '@') => self.insert_chars(n),
+            (None, [], 'A') => {
+                let top = if self.row >= self.scroll_top { self.scroll_top } else { 0 };
+                self.goto(self.row.saturating_sub(n).max(top), self.col);
+            }
+            (None, [], 'B') | (None, [], 'e') => {
+                let bottom = if self.row < self.scroll_bottom { self.scroll_bottom - 1 } else { self.rows - 1 };
+                self.goto((self.row + n).min(bottom), self.col);
+            }
+            (None, [], 'C') | (None, [], 'a') => self.goto(self.row, self.col + n),
+            (None, [], 'D') => self.goto(self.row, self.col.saturating_sub(n)),
+            (None, [], 'E') => {
+                let bottom = if self.row < self.scroll_bottom { self.scroll_bottom - 1 } else { self.rows - 1 };
+                self.goto((self.row + n).min(bottom), 0);
+            }
+            (None, [], 'F') => {
+                let top = if self.row >= self.scroll_top { self.scroll_top } else { 0 };
+                self.goto(self.row.saturating_sub(n).max(top), 0);
+            }
+            (None, [], 'G') | (None, [], '`') => self.goto(self.row, n - 1),
+            (None, [], 'H') | (None, [], 'f') => self.goto_origin(arg(0, 1) - 1, arg(1, 1) - 1),
+            (None, [], 'I') => self.tab_forward(n),
+            (None, [], 'Z') => self.tab_backward(n),
+            (None, [], 'J') | (Some(b'?'), [], 'J') => self.erase_display(arg(0, 0)),
+            (None, [], 'K') | (Some(b'?'), [], 'K') => self.erase_line(arg(0, 0)),
+            (None, [], 'L') => self.insert_lines(n),
+            (None, [], 'M') => self.delete_lines(n),
+            (None, [], 'P') => self.delete_chars(n),
+            (None, [], 'S') => self.scroll_up(n),
+            (None, [], 'T') => self.scroll_down(n),
+            (None, [], 'X') => {
+                let (row, col, blank) = (self.row, self.col, self.blank());
+                self.grid_mut().clear_cells(row, col, col + n, blank);
+                self.pending_wrap = false;
+            }
+            (None, [], 'b') => {
+                if let Some(ch) = self.last_printed {
+                    for _ in 0..n.min(self.cols * self.rows) {
+                        self.print(ch);
                     }
                 }
-                ch => {
-                    if let Some(line) = self.lines.get_mut(self.cursor_position.0) {
-                        if self.cursor_position.1 >= line.len() {
-                            line.push(ch);
-                        } else {
-                            line.insert(self.cursor_position.1, ch);
-                        }
-                        self.cursor_position.1 += 1;
+            }
+            (None, [], 'c') if arg(0, 0) == 0 => self.report("\x1b[?1;2c"),
+            (None, [], 'd') => self.goto_origin(n - 1, self.col),
+            (None, [], 'g') => match arg(0, 0) {
+                0 => self.tab_stops[self.col] = false,
+                3 => self.tab_stops.fill(false),
+                _ => {}
+            },
+            (None, [], 'h') | (Some(b'?'), [], 'h') => {
+                for param in params {
+                    self.set_mode(param[0], private.is_some(), true);
+                }
+            }
+            (None, [], 'l') | (Some(b'?'), [], 'l') => {
+                for param in params {
+                    self.set_mode(param[0], private.is_some(), false);
+                }
+            }
+            (None, [], 'm') => self.select_graphic_rendition(params),
+            (None, [], 'n') => match arg(0, 0) {
+                5 => self.report("\x1b[0n"),
+                6 => {
+                    let row = if self.modes.origin { self.row - self.scroll_top } else { self.row };
+                    let reply = format!("\x1b[{};{}R", row + 1, self.col + 1);
+                    self.report(&reply);
+                }
+                _ => {}
+            },
+            (None, [], 'r') => self.set_scroll_region(arg(0, 1), arg(1, 0)),
+            (None, [], 's') => self.save_cursor(),
+            (None, [], 'u') => self.restore_cursor(),
+            (None, [b' '], 'q') => {
+                self.cursor_shape = match arg(0, 1) {
+                    3 | 4 => CursorShape::Underline,
+                    5 | 6 => CursorShape::Bar,
+                    _ => CursorShape::Block,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8) {
+        match (intermediates, byte) {
+            ([], b'7') => self.save_cursor(),
+            ([], b'8') => self.restore_cursor(),
+            ([], b'D') => self.linefeed(),
+            ([], b'E') => {
+                self.col = 0;
+                self.linefeed();
+            }
+            ([], b'H') => self.tab_stops[self.col] = true,
+            ([], b'M') => self.reverse_index(),
+            ([], b'c') => self.reset(),
+            ([], b'=') => self.modes.application_keypad = true,
+            ([], b'>') => self.modes.application_keypad = false,
+            ([b'('], designator) => self.charsets[0] = charset(designator),
+            ([b')'], designator) => self.charsets[1] = charset(designator),
+            ([b'#'], b'8') => {
+                // DECALN screen alignment pattern
+                self.scroll_top = 0;
+                self.scroll_bottom = self.rows;
+                let (rows, cols) = (self.rows, self.cols);
+                let grid = self.grid_mut();
+                for row in 0..rows {
+                    for col in 0..cols {
+                        *grid.cell_mut(row, col) = Cell { ch: 'E', ..Cell::default() };
                     }
                 }
+                self.goto(0, 0);
             }
+            _ => {}
         }
     }
-    
-    pub fn read(&self) -> Vec<String> {
-        self.lines.clone()
+
+    fn osc_dispatch(&mut self, params: &[&[u8]]) {
+        match params {
+            [b"0", title, ..] | [b"2", title, ..] => {
+                self.title = String::from_utf8_lossy(title).into_owned();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Print `rows` top to bottom from the cursor. A wrapped row is printed in
+/// full so the next one continues it through autowrap; other rows stop at
+/// their last non-default cell and end with a newline on the default pen.
+fn write_rows(out: &mut String, rows: &[&Row]) {
+    for (index, row) in rows.iter().enumerate() {
+        let end = if row.wrapped {
+            row.cells.len()
+        } else {
+            row.cells.iter().rposition(|cell| *cell != Cell::default()).map_or(0, |last| last + 1)
+        };
+
+        let mut pen = Cell::default();
+        for (col, cell) in row.cells[..end].iter().enumerate() {
+            // Printing the wide character already covered its spacer
+            if cell.width == 0 && col > 0 && row.cells[col - 1].width == 2 {
+                continue;
+            }
+            if (cell.fg, cell.bg, cell.attrs) != (pen.fg, pen.bg, pen.attrs) {
+                out.push_str(&sgr(cell));
+                pen = *cell;
+            }
+            out.push(cell.ch);
+        }
+        // A wrap or scroll fills the new row with the pen's background
+        out.push_str("\x1b[0m");
+        if end < row.cells.len() {
+            out.push_str("\x1b[K");
+        }
+
+        if !row.wrapped && index + 1 < rows.len() {
+            out.push_str("\r\n");
+        }
+    }
+}
+
+/// SGR selecting exactly `cell`'s colors and attributes
+fn sgr(cell: &Cell) -> String {
+    let mut params = vec!["0".to_string()];
+    let attrs = &cell.attrs;
+    let flags = [
+        (attrs.bold, "1"),
+        (attrs.dim, "2"),
+        (attrs.italic, "3"),
+        (attrs.underline, "4"),
+        (attrs.blink, "5"),
+        (attrs.inverse, "7"),
+        (attrs.hidden, "8"),
+        (attrs.strikethrough, "9"),
+    ];
+    params.extend(flags.iter().filter(|(on, _)| *on).map(|(_, code)| code.to_string()));
+    push_color(&mut params, cell.fg, 30, 90, 38);
+    push_color(&mut params, cell.bg, 40, 100, 48);
+    format!("\x1b[{}m", params.join(";"))
+}
+
+fn push_color(params: &mut Vec<String>, color: TerminalColor, base: u16, bright: u16, extended: u16) {
+    match color {
+        TerminalColor::Default => {}
+        TerminalColor::Indexed(index @ 0..=7) => params.push((base + index as u16).to_string()),
+        TerminalColor::Indexed(index @ 8..=15) => params.push((bright + index as u16 - 8).to_string()),
+        TerminalColor::Indexed(index) => params.push(format!("{};5;{}", extended, index)),
+        TerminalColor::Rgb(r, g, b) => params.push(format!("{};2;{};{};{}", extended, r, g, b)),
+    }
+}
+
+fn default_tab_stops(cols: usize) -> Vec<bool> {
+    (0..cols).map(|col| col > 0 && col % TAB_WIDTH == 0).collect()
+}
+
+fn charset(designator: u8) -> Charset {
+    match designator {
+        b'0' => Charset::DecSpecial,
+        _ => Charset::Ascii,
+    }
+}
+
+/// `38;5;n`, `38;2;r;g;b` or the colon forms `38:5:n`, `38:2:r:g:b` and
+/// `38:2:space:r:g:b`, starting at `params[i]`. Returns the color and how
+/// many following parameters the semicolon forms used.
+fn extended_color(params: &[Vec<u16>], i: usize) -> (Option<TerminalColor>, usize) {
+    let channel = |value: u16| value.min(255) as u8;
+    let param = &params[i];
+    if param.len() > 1 {
+        let color = match param[1] {
+            5 => param.get(2).map(|&index| TerminalColor::Indexed(channel(index))),
+            2 => {
+                let rgb = if param.len() >= 6 { &param[3..6] } else { &param[2..] };
+                (rgb.len() >= 3).then(|| TerminalColor::Rgb(channel(rgb[0]), channel(rgb[1]), channel(rgb[2])))
+            }
+            _ => None,
+        };
+        return (color, 0);
+    }
+
+    let value = |offset: usize| params.get(i + offset).map(|param| param[0]);
+    match value(1) {
+        Some(5) => (value(2).map(|index| TerminalColor::Indexed(channel(index))), 2),
+        Some(2) => match (value(2), value(3), value(4)) {
+            (Some(r), Some(g), Some(b)) => (Some(TerminalColor::Rgb(channel(r), channel(g), channel(b))), 4),
+            _ => (None, params.len()),
+        },
+        _ => (None, 0),
+    }
+}
+
+/// DEC special graphics for `ch`
+fn dec_special(ch: char) -> char {
+    match ch {
+        '_' => ' ',
+        '`' => '◆',
+        'a' => '▒',
+        'b' => '␉',
+        'c' => '␌',
+        'd' => '␍',
+        'e' => '␊',
+        'f' => '°',
+        'g' => '±',
+        'h' => '␤',
+        'i' => '␋',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terminal(cols: usize, rows: usize) -> Terminal {
+        Terminal::with_size(Uuid::nil(), cols, rows)
+    }
+
+    fn cells(grid: &Grid) -> Vec<(Vec<Cell>, bool)> {
+        grid.scrollback().iter()
+            .chain((0..grid.rows()).map(|row| grid.row(row)))
+            .map(|row| (row.cells.clone(), row.wrapped))
+            .collect()
+    }
+
+    /// Feed `source`'s snapshot to a fresh terminal and check it ends up the same
+    fn assert_restores(source: &Terminal) -> Terminal {
+        let mut copy = terminal(source.cols(), source.rows());
+        copy.feed(b"leftover output");
+        copy.feed(source.snapshot().as_bytes());
+
+        assert_eq!(cells(&copy.screen.primary), cells(&source.screen.primary));
+        assert_eq!(cells(copy.grid()), cells(source.grid()));
+        assert_eq!(copy.is_alternate_screen(), source.is_alternate_screen());
+        assert_eq!(copy.cursor(), source.cursor());
+        assert_eq!(copy.screen.pen, source.screen.pen);
+        assert_eq!((copy.screen.scroll_top, copy.screen.scroll_bottom), (source.screen.scroll_top, source.screen.scroll_bottom));
+        copy
+    }
+
+    #[test]
+    fn snapshot_restores_colors_and_scrollback() {
+        let mut source = terminal(10, 3);
+        source.feed(b"\x1b[1;31mred\x1b[0m plain\r\n");
+        source.feed(b"\x1b[38;5;208morange\x1b[48;2;1;2;3m bg\x1b[0m\r\n");
+        source.feed(b"\x1b[4;92mbright\x1b[0m\r\nfour\r\nfive\x1b[7m");
+        assert_eq!(source.grid().scrollback().len(), 2);
+
+        let copy = assert_restores(&source);
+        assert_eq!(copy.read(), source.read());
+    }
+
+    #[test]
+    fn snapshot_restores_wrapped_lines() {
+        let mut source = terminal(5, 3);
+        source.feed(b"abcdefgh\r\n\x1b[44m12345\x1b[0m67");
+
+        let copy = assert_restores(&source);
+        assert!(copy.grid().scrollback()[0].wrapped);
+        assert!(copy.grid().row(1).wrapped);
+        assert_eq!(copy.read(), vec!["abcde", "fgh", "12345", "67"]);
+    }
+
+    #[test]
+    fn snapshot_restores_the_alternate_screen() {
+        let mut source = terminal(20, 4);
+        source.feed(b"$ vim notes.txt\r\n");
+        source.feed(b"\x1b[?1049h\x1b[H\x1b[2;3r\x1b[?1h\x1b[?2004h\x1b[?25l");
+        source.feed(b"\x1b[1;1H\x1b[1mnotes\x1b[0m\x1b[4;1H\x1b[7m-- INSERT --\x1b[0m\x1b[2;5H");
+
+        let mut copy = assert_restores(&source);
+        assert!(copy.modes().application_cursor);
+        assert!(copy.modes().bracketed_paste);
+        assert!(!copy.modes().cursor_visible);
+
+        // Leaving the alternate screen goes back to the shell's cursor
+        source.feed(b"\x1b[?1049l");
+        copy.feed(b"\x1b[?1049l");
+        assert_eq!(copy.cursor(), source.cursor());
+        assert_eq!(copy.read(), source.read());
+    }
+
+    #[test]
+    fn snapshot_restores_origin_mode_cursor() {
+        let mut source = terminal(10, 6);
+        source.feed(b"\x1b[2;5r\x1b[?6h\x1b[3;4Hx");
+
+        let copy = assert_restores(&source);
+        assert!(copy.modes().origin);
+    }
+
+    fn row_text(terminal: &Terminal, row: usize) -> String {
+        terminal.grid().row(row).text()
+    }
+
+    fn cell(terminal: &Terminal, row: usize, col: usize) -> Cell {
+        terminal.grid().row(row).cells[col]
+    }
+
+    #[test]
+    fn sgr_sets_palette_and_truecolor() {
+        let mut terminal = terminal(20, 2);
+        terminal.feed(b"\x1b[31;102ma\x1b[38;5;208;48;5;17mb\x1b[38;2;10;20;30;48;2;40;50;60mc");
+        terminal.feed(b"\x1b[38:2::1:2:3md\x1b[38:5:99me\x1b[0;1;4mf");
+
+        assert_eq!((cell(&terminal, 0, 0).fg, cell(&terminal, 0, 0).bg), (TerminalColor::Indexed(1), TerminalColor::Indexed(10)));
+        assert_eq!((cell(&terminal, 0, 1).fg, cell(&terminal, 0, 1).bg), (TerminalColor::Indexed(208), TerminalColor::Indexed(17)));
+        assert_eq!((cell(&terminal, 0, 2).fg, cell(&terminal, 0, 2).bg), (TerminalColor::Rgb(10, 20, 30), TerminalColor::Rgb(40, 50, 60)));
+        assert_eq!(cell(&terminal, 0, 3).fg, TerminalColor::Rgb(1, 2, 3));
+        assert_eq!(cell(&terminal, 0, 4).fg, TerminalColor::Indexed(99));
+
+        let reset = cell(&terminal, 0, 5);
+        assert_eq!((reset.fg, reset.bg), (TerminalColor::Default, TerminalColor::Default));
+        assert!(reset.attrs.bold && reset.attrs.underline);
+    }
+
+    #[test]
+    fn truncated_truecolor_skips_the_rest_of_the_sgr() {
+        let mut terminal = terminal(10, 1);
+        terminal.feed(b"\x1b[32m\x1b[38;2;1;2ma");
+        assert_eq!(cell(&terminal, 0, 0).fg, TerminalColor::Indexed(2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn alternate_screen_keeps_the_primary_and_its_cursor() {
+        let mut terminal = terminal(10, 3);
+        terminal.feed(b"$ top\r\n$ ");
+        terminal.feed(b"\x1b[?1049h\x1b[2;2Hfull");
+        assert!(terminal.is_alternate_screen());
+        assert_eq!(row_text(&terminal, 0), "");
+        assert_eq!(row_text(&terminal, 1), " full");
+
+        terminal.feed(b"\x1b[?1049l");
+        assert!(!terminal.is_alternate_screen());
+        assert_eq!(terminal.read(), vec!["$ top", "$"]);
+        assert_eq!(terminal.cursor(), (1, 2));
+
+        // Entered again, the alternate screen starts out blank
+        terminal.feed(b"\x1b[?1049h");
+        assert_eq!(row_text(&terminal, 1), "");
+    }
+
+    #[test]
+    fn alternate_screen_has_no_scrollback() {
+        let mut terminal = terminal(10, 2);
+        terminal.feed(b"\x1b[?1049hone\r\ntwo\r\nthree");
+        assert!(terminal.grid().scrollback().is_empty());
+        assert_eq!((row_text(&terminal, 0), row_text(&terminal, 1)), ("two".to_string(), "three".to_string()));
+    }
+
+    #[test]
+    fn autowrap_waits_for_the_next_character() {
+        let mut terminal = terminal(4, 3);
+        terminal.feed(b"abcd");
+        // The cursor stays on the last column until something is printed
+        assert_eq!(terminal.cursor(), (0, 3));
+        terminal.feed(b"e");
+        assert_eq!(terminal.cursor(), (1, 1));
+        assert!(terminal.grid().row(0).wrapped);
+        assert_eq!(terminal.read(), vec!["abcd", "e"]);
+
+        // A carriage return cancels the pending wrap
+        let mut terminal = super::tests::terminal(4, 3);
+        terminal.feed(b"abcd\rX");
+        assert_eq!(terminal.read(), vec!["Xbcd"]);
+    }
+
+    #[test]
+    fn without_autowrap_the_last_column_is_overwritten() {
+        let mut terminal = terminal(4, 2);
+        terminal.feed(b"\x1b[?7labcdef");
+        assert_eq!(terminal.read(), vec!["abcf"]);
+        assert_eq!(terminal.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn scroll_region_confines_scrolling() {
+        let mut terminal = terminal(10, 5);
+        terminal.feed(b"header\r\n1\r\n2\r\n3\r\nfooter");
+        terminal.feed(b"\x1b[2;4r");
+        assert_eq!(terminal.cursor(), (0, 0));
+
+        // Line feeds at the bottom of the region scroll only the region,
+        // and nothing goes to scrollback
+        terminal.feed(b"\x1b[4;1H\n\n4");
+        assert_eq!(terminal.read(), vec!["header", "3", "", "4", "footer"]);
+        assert!(terminal.grid().scrollback().is_empty());
+
+        // Reverse index at the top scrolls the region down
+        terminal.feed(b"\x1b[2;1H\x1bM0");
+        assert_eq!(terminal.read(), vec!["header", "0", "3", "", "footer"]);
+
+        // Insert and delete line stay inside the region too
+        terminal.feed(b"\x1b[3;1H\x1b[M");
+        assert_eq!(terminal.read(), vec!["header", "0", "", "", "footer"]);
+        terminal.feed(b"\x1b[2;1H\x1b[2L");
+        assert_eq!(terminal.read(), vec!["header", "", "", "0", "footer"]);
+    }
+
+    #[test]
+    fn full_screen_scrolling_fills_scrollback() {
+        let mut terminal = terminal(10, 2);
+        terminal.feed(b"one\r\ntwo\r\nthree\r\nfour");
+        let scrollback: Vec<String> = terminal.grid().scrollback().iter().map(Row::text).collect();
+        assert_eq!(scrollback, vec!["one", "two"]);
+
+        terminal.scroll_display(5);
+        assert_eq!(terminal.display_offset(), 2);
+        assert_eq!(terminal.visible_row(0).text(), "one");
+    }
+
+    #[test]
+    fn origin_mode_addresses_the_scroll_region() {
+        let mut terminal = terminal(10, 6);
+        terminal.feed(b"\x1b[3;5r\x1b[?6h\x1b[1;1Hx\x1b[9;1Hy");
+        assert_eq!(row_text(&terminal, 2), "x");
+        // Clamped to the bottom of the region
+        assert_eq!(row_text(&terminal, 4), "y");
+    }
+
+    #[test]
+    fn device_status_reports_answer_queries() {
+        let mut terminal = terminal(10, 5);
+        terminal.feed(b"\x1b[5n\x1b[3;7H\x1b[6n");
+        assert_eq!(terminal.take_responses(), b"\x1b[0n\x1b[3;7R");
+        assert!(terminal.take_responses().is_empty());
+
+        // In origin mode the position is relative to the region
+        terminal.feed(b"\x1b[2;4r\x1b[?6h\x1b[2;3H\x1b[6n\x1b[c");
+        assert_eq!(terminal.take_responses(), b"\x1b[2;3R\x1b[?1;2c");
+    }
+
+    #[test]
+    fn wide_characters_take_two_cells() {
+        let mut terminal = terminal(6, 2);
+        terminal.feed("a中b".as_bytes());
+        assert_eq!(terminal.cursor(), (0, 4));
+        assert_eq!((cell(&terminal, 0, 1).ch, cell(&terminal, 0, 1).width), ('中', 2));
+        assert_eq!(cell(&terminal, 0, 2).width, 0);
+        assert_eq!(cell(&terminal, 0, 3).ch, 'b');
+        assert_eq!(terminal.read(), vec!["a中b"]);
+    }
+
+    #[test]
+    fn wide_character_wraps_whole_at_the_last_column() {
+        let mut terminal = terminal(5, 2);
+        terminal.feed("abcd文字".as_bytes());
+        assert!(terminal.grid().row(0).wrapped);
+        assert_eq!(terminal.read(), vec!["abcd", "文字"]);
+        assert_eq!(terminal.cursor(), (1, 4));
+    }
+
+    #[test]
+    fn overwriting_half_a_wide_character_blanks_the_other_half() {
+        let mut terminal = terminal(6, 1);
+        terminal.feed("中文\x1b[1;2Hx".as_bytes());
+        assert_eq!(terminal.read(), vec![" x文"]);
+        assert!(terminal.grid().row(0).cells[..2].iter().all(|cell| cell.width == 1));
+
+        terminal.feed("\x1b[1;3H字".as_bytes());
+        assert_eq!(terminal.read(), vec![" x字"]);
+        assert_eq!(cell(&terminal, 0, 4).width, 1);
+    }
+
+    #[test]
+    fn combining_marks_take_no_cell() {
+        let mut terminal = terminal(6, 1);
+        terminal.feed("e\u{301}x".as_bytes());
+        assert_eq!(terminal.read(), vec!["ex"]);
+        assert_eq!(terminal.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn snapshot_restores_wide_characters() {
+        let mut source = terminal(5, 3);
+        // One wide character ends on the last column, one doesn't fit there
+        source.feed("中a文字\r\nabcd\x1b[31m한국\x1b[0m".as_bytes());
+
+        let copy = assert_restores(&source);
+        assert_eq!(copy.read(), vec!["中a文", "字", "abcd", "한국"]);
+    }
+}
@@ -0,0 +1,344 @@
+//! Terminal cell grid
+//!
+//! The screen is a fixed number of rows of cells. Rows scrolled off the top
+//! of the primary screen go into a ring buffer of scrollback.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TerminalColor {
+    /// The terminal's default foreground or background
+    #[default]
+    Default,
+    /// One of the 256 palette entries
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl TerminalColor {
+    /// RGBA for this color, with `default` for `Default` and `palette`
+    /// supplying entries 0..16
+    pub fn to_rgba(self, default: [f32; 4], palette: &[[f32; 4]; 16]) -> [f32; 4] {
+        match self {
+            TerminalColor::Default => default,
+            TerminalColor::Indexed(index) => indexed_color(index, palette),
+            TerminalColor::Rgb(r, g, b) => [r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0],
+        }
+    }
+}
+
+/// xterm's 16 ANSI colors
+pub const ANSI_COLORS: [[f32; 4]; 16] = [
+    [0.000, 0.000, 0.000, 1.0],
+    [0.804, 0.000, 0.000, 1.0],
+    [0.000, 0.804, 0.000, 1.0],
+    [0.804, 0.804, 0.000, 1.0],
+    [0.000, 0.000, 0.933, 1.0],
+    [0.804, 0.000, 0.804, 1.0],
+    [0.000, 0.804, 0.804, 1.0],
+    [0.898, 0.898, 0.898, 1.0],
+    [0.498, 0.498, 0.498, 1.0],
+    [1.000, 0.000, 0.000, 1.0],
+    [0.000, 1.000, 0.000, 1.0],
+    [1.000, 1.000, 0.000, 1.0],
+    [0.361, 0.361, 1.000, 1.0],
+    [1.000, 0.000, 1.000, 1.0],
+    [0.000, 1.000, 1.000, 1.0],
+    [1.000, 1.000, 1.000, 1.0],
+];
+
+/// Entry `index` of the 256-color palette: the 16 ANSI colors, a 6x6x6
+/// color cube, then 24 grays
+fn indexed_color(index: u8, palette: &[[f32; 4]; 16]) -> [f32; 4] {
+    match index {
+        0..=15 => palette[index as usize],
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0.0 } else { (55.0 + value as f32 * 40.0) / 255.0 };
+            let cube = index - 16;
+            [level(cube / 36), level(cube / 6 % 6), level(cube % 6), 1.0]
+        }
+        _ => {
+            let gray = (8.0 + (index - 232) as f32 * 10.0) / 255.0;
+            [gray, gray, gray, 1.0]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CellAttributes {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub blink: bool,
+    pub inverse: bool,
+    pub hidden: bool,
+    pub strikethrough: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    /// Columns `ch` takes. A wide (CJK) character has width 2 and is
+    /// followed by a spacer cell of width 0.
+    pub width: u8,
+    pub fg: TerminalColor,
+    pub bg: TerminalColor,
+    pub attrs: CellAttributes,
+}
+
+impl Cell {
+    /// An empty cell, keeping the background so erased areas take the
+    /// current background color
+    pub fn blank(bg: TerminalColor) -> Self {
+        Self { ch: ' ', bg, ..Self::default() }
+    }
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            ch: ' ',
+            width: 1,
+            fg: TerminalColor::Default,
+            bg: TerminalColor::Default,
+            attrs: CellAttributes::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub cells: Vec<Cell>,
+    /// The line continues on the next row because it wrapped
+    pub wrapped: bool,
+}
+
+impl Row {
+    pub fn new(cols: usize, blank: Cell) -> Self {
+        Self { cells: vec![blank; cols], wrapped: false }
+    }
+
+    /// Row text without trailing blanks
+    pub fn text(&self) -> String {
+        let text: String = self.cells.iter()
+            .filter(|cell| cell.width > 0)
+            .map(|cell| cell.ch)
+            .collect();
+        text.trim_end().to_string()
+    }
+
+    fn resize(&mut self, cols: usize) {
+        // Lines aren't reflowed, so a wrap no longer lines up
+        if self.cells.len() != cols {
+            self.wrapped = false;
+        }
+        self.cells.resize(cols, Cell::default());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Grid {
+    cols: usize,
+    rows: Vec<Row>,
+    scrollback: VecDeque<Row>,
+    scrollback_limit: usize,
+}
+
+impl Grid {
+    pub fn new(cols: usize, rows: usize, scrollback_limit: usize) -> Self {
+        Self {
+            cols,
+            rows: (0..rows).map(|_| Row::new(cols, Cell::default())).collect(),
+            scrollback: VecDeque::new(),
+            scrollback_limit,
+        }
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn row(&self, row: usize) -> &Row {
+        &self.rows[row]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut Row {
+        &mut self.rows[row]
+    }
+
+    pub fn cell_mut(&mut self, row: usize, col: usize) -> &mut Cell {
+        &mut self.rows[row].cells[col]
+    }
+
+    /// Oldest first
+    pub fn scrollback(&self) -> &VecDeque<Row> {
+        &self.scrollback
+    }
+
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+    }
+
+    /// Row `row` of the screen as seen `offset` rows back into scrollback
+    pub fn visible_row(&self, row: usize, offset: usize) -> &Row {
+        let offset = offset.min(self.scrollback.len());
+        if row < offset {
+            &self.scrollback[self.scrollback.len() - offset + row]
+        } else {
+            &self.rows[row - offset]
+        }
+    }
+
+    /// Move rows `top..bottom` up by `count`, blanking the rows that open
+    /// at the bottom. Rows leaving the top of the screen go to scrollback.
+    pub fn scroll_up(&mut self, top: usize, bottom: usize, count: usize, blank: Cell) {
+        let count = count.min(bottom - top);
+        for _ in 0..count {
+            let row = self.rows.remove(top);
+            if top == 0 && self.scrollback_limit > 0 {
+                if self.scrollback.len() == self.scrollback_limit {
+                    self.scrollback.pop_front();
+                }
+                self.scrollback.push_back(row);
+            }
+            self.rows.insert(bottom - 1, Row::new(self.cols, blank));
+        }
+    }
+
+    /// Move rows `top..bottom` down by `count`, blanking the rows that open
+    /// at the top
+    pub fn scroll_down(&mut self, top: usize, bottom: usize, count: usize, blank: Cell) {
+        let count = count.min(bottom - top);
+        for _ in 0..count {
+            self.rows.remove(bottom - 1);
+            self.rows.insert(top, Row::new(self.cols, blank));
+        }
+    }
+
+    /// Blank columns `start..end` of `row`
+    pub fn clear_cells(&mut self, row: usize, start: usize, end: usize, blank: Cell) {
+        let end = end.min(self.cols);
+        if start < end {
+            self.rows[row].cells[start..end].fill(blank);
+        }
+    }
+
+    pub fn clear_rows(&mut self, start: usize, end: usize, blank: Cell) {
+        for row in start..end.min(self.rows.len()) {
+            self.rows[row] = Row::new(self.cols, blank);
+        }
+    }
+
+    /// Resize to `cols` by `rows`. `keep_bottom` rows at the top move to
+    /// scrollback instead of being cut from the bottom when the screen
+    /// shrinks, so the cursor's row stays on screen.
+    pub fn resize(&mut self, cols: usize, rows: usize, keep_bottom: usize) {
+        for row in self.rows.iter_mut().chain(self.scrollback.iter_mut()) {
+            row.resize(cols);
+        }
+        self.cols = cols;
+
+        if rows < self.rows.len() {
+            let shift = keep_bottom.min(self.rows.len() - rows);
+            for row in self.rows.drain(..shift) {
+                if self.scrollback_limit > 0 {
+                    if self.scrollback.len() == self.scrollback_limit {
+                        self.scrollback.pop_front();
+                    }
+                    self.scrollback.push_back(row);
+                }
+            }
+            self.rows.truncate(rows);
+        } else {
+            while self.rows.len() < rows {
+                self.rows.push(Row::new(cols, Cell::default()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid with each row holding its number
+    fn numbered(rows: usize, scrollback_limit: usize) -> Grid {
+        let mut grid = Grid::new(4, rows, scrollback_limit);
+        for row in 0..rows {
+            grid.cell_mut(row, 0).ch = char::from_digit(row as u32, 10).unwrap();
+        }
+        grid
+    }
+
+    fn texts(grid: &Grid) -> Vec<String> {
+        (0..grid.rows()).map(|row| grid.row(row).text()).collect()
+    }
+
+    #[test]
+    fn scrolling_from_the_top_fills_scrollback_up_to_its_limit() {
+        let mut grid = numbered(4, 2);
+        grid.scroll_up(0, 4, 3, Cell::default());
+        assert_eq!(texts(&grid), vec!["3", "", "", ""]);
+        let scrollback: Vec<String> = grid.scrollback().iter().map(Row::text).collect();
+        assert_eq!(scrollback, vec!["1", "2"]);
+
+        assert_eq!(grid.visible_row(0, 2).text(), "1");
+        assert_eq!(grid.visible_row(2, 2).text(), "3");
+        // Offsets past the scrollback stop at its start
+        assert_eq!(grid.visible_row(0, 10).text(), "1");
+    }
+
+    #[test]
+    fn scrolling_a_region_leaves_the_rest() {
+        let mut grid = numbered(5, 10);
+        let blank = Cell::blank(TerminalColor::Indexed(4));
+        grid.scroll_up(1, 4, 1, blank);
+        assert_eq!(texts(&grid), vec!["0", "2", "3", "", "4"]);
+        assert_eq!(grid.row(3).cells[0].bg, TerminalColor::Indexed(4));
+        assert!(grid.scrollback().is_empty());
+
+        grid.scroll_down(0, 3, 5, Cell::default());
+        assert_eq!(texts(&grid), vec!["", "", "", "", "4"]);
+    }
+
+    #[test]
+    fn clearing_clamps_to_the_row() {
+        let mut grid = Grid::new(4, 1, 0);
+        for col in 0..4 {
+            grid.cell_mut(0, col).ch = 'x';
+        }
+        grid.clear_cells(0, 2, 100, Cell::default());
+        grid.clear_cells(0, 3, 1, Cell::default());
+        assert_eq!(grid.row(0).text(), "xx");
+    }
+
+    #[test]
+    fn shrinking_keeps_the_cursor_rows() {
+        let mut grid = numbered(4, 10);
+        grid.resize(2, 2, 2);
+        assert_eq!(texts(&grid), vec!["2", "3"]);
+        assert_eq!(grid.scrollback().len(), 2);
+        assert_eq!(grid.cols(), 2);
+        assert!(grid.scrollback().iter().all(|row| row.cells.len() == 2));
+
+        grid.resize(3, 3, 0);
+        assert_eq!(texts(&grid), vec!["2", "3", ""]);
+    }
+
+    #[test]
+    fn palette_covers_ansi_cube_and_grays() {
+        let default = [0.5; 4];
+        assert_eq!(TerminalColor::Default.to_rgba(default, &ANSI_COLORS), default);
+        assert_eq!(TerminalColor::Indexed(9).to_rgba(default, &ANSI_COLORS), ANSI_COLORS[9]);
+        assert_eq!(TerminalColor::Indexed(16).to_rgba(default, &ANSI_COLORS), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(TerminalColor::Indexed(231).to_rgba(default, &ANSI_COLORS), [1.0, 1.0, 1.0, 1.0]);
+        let [r, g, b, _] = TerminalColor::Indexed(232).to_rgba(default, &ANSI_COLORS);
+        assert_eq!((r, g, b), (8.0 / 255.0, 8.0 / 255.0, 8.0 / 255.0));
+        assert_eq!(TerminalColor::Rgb(255, 0, 51).to_rgba(default, &ANSI_COLORS), [1.0, 0.0, 0.2, 1.0]);
+    }
+}
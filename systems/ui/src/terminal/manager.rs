@@ -34,6 +34,26 @@ impl TerminalManager {
         }
     }
     
+    /// Feed raw program output, escape sequences included
+    pub async fn feed_terminal(&mut self, id: Uuid, bytes: &[u8]) -> UiResult<()> {
+        self.get_mut(id)?.feed(bytes);
+        Ok(())
+    }
+    
+    pub async fn resize_terminal(&mut self, id: Uuid, cols: usize, rows: usize) -> UiResult<()> {
+        self.get_mut(id)?.resize(cols, rows);
+        Ok(())
+    }
+    
+    pub fn get(&self, id: Uuid) -> Option<&Terminal> {
+        self.terminals.get(&id)
+    }
+    
+    pub fn get_mut(&mut self, id: Uuid) -> UiResult<&mut Terminal> {
+        self.terminals.get_mut(&id)
+            .ok_or_else(|| UiError::TerminalError(format!("Terminal {} not found", id)))
+    }
+    
     pub async fn read_from_terminal(&self, id: Uuid) -> UiResult<Vec<String>> {
         if let Some(terminal) = self.terminals.get(&id) {
             Ok(terminal.read())
@@ -1,4 +1,8 @@
 pub mod manager;
 pub mod emulator;
+pub mod grid;
+pub mod parser;
 
-pub use manager::TerminalManager;
\ No newline at end of file
+pub use manager::TerminalManager;
+pub use emulator::{Terminal, TerminalModes, CursorShape, MouseMode};
+pub use grid::{Cell, CellAttributes, Grid, Row, TerminalColor};
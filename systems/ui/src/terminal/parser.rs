@@ -0,0 +1,390 @@
+//! Escape sequence parser
+//!
+//! A byte-at-a-time state machine after the DEC/ANSI parser described by
+//! Paul Williams, covering what xterm-compatible programs send: C0
+//! controls, ESC sequences, CSI sequences with private markers,
+//! intermediates and colon subparameters, and OSC strings terminated by BEL
+//! or ST. DCS, SOS, PM and APC strings are consumed and dropped. Printable
+//! text is decoded as UTF-8; malformed input prints U+FFFD.
+
+/// Receives what the parser recognizes
+pub trait Perform {
+    /// A printable character
+    fn print(&mut self, ch: char);
+
+    /// A C0 control code
+    fn execute(&mut self, byte: u8);
+
+    /// `CSI private? params intermediates action`. Every parameter holds at
+    /// least one value; omitted values are 0.
+    fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], private: Option<u8>, action: char);
+
+    /// `ESC intermediates final`
+    fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8);
+
+    /// `OSC params` with the `;`-separated fields
+    fn osc_dispatch(&mut self, params: &[&[u8]]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    CsiEntry,
+    CsiParam,
+    CsiIntermediate,
+    CsiIgnore,
+    OscString,
+    /// DCS, SOS, PM and APC bodies
+    StringIgnore,
+}
+
+const MAX_PARAMS: usize = 32;
+const MAX_SUBPARAMS: usize = 8;
+const MAX_INTERMEDIATES: usize = 2;
+const MAX_OSC_LEN: usize = 4096;
+
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    params: Vec<Vec<u16>>,
+    intermediates: Vec<u8>,
+    private: Option<u8>,
+    osc: Vec<u8>,
+    utf8: Vec<u8>,
+    utf8_len: usize,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: Vec::new(),
+            intermediates: Vec::new(),
+            private: None,
+            osc: Vec::new(),
+            utf8: Vec::new(),
+            utf8_len: 0,
+        }
+    }
+
+    pub fn advance<P: Perform>(&mut self, performer: &mut P, bytes: &[u8]) {
+        for &byte in bytes {
+            self.advance_byte(performer, byte);
+        }
+    }
+
+    fn advance_byte<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        if self.utf8_len > 0 {
+            if byte & 0xc0 == 0x80 {
+                self.utf8.push(byte);
+                if self.utf8.len() == self.utf8_len {
+                    let ch = std::str::from_utf8(&self.utf8).ok()
+                        .and_then(|text| text.chars().next())
+                        .unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.utf8.clear();
+                    self.utf8_len = 0;
+                    performer.print(ch);
+                }
+                return;
+            }
+            // Sequence cut short; this byte starts something new
+            self.utf8.clear();
+            self.utf8_len = 0;
+            performer.print(char::REPLACEMENT_CHARACTER);
+        }
+
+        // Transitions from anywhere
+        match byte {
+            0x18 | 0x1a => {
+                self.state = State::Ground;
+                return;
+            }
+            0x1b => {
+                if self.state == State::OscString {
+                    self.dispatch_osc(performer);
+                }
+                self.clear();
+                self.state = State::Escape;
+                return;
+            }
+            _ => {}
+        }
+
+        match self.state {
+            State::Ground => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x7e => performer.print(byte as char),
+                0x7f => {}
+                _ => self.start_utf8(performer, byte),
+            },
+            State::Escape => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x2f => {
+                    self.collect(byte);
+                    self.state = State::EscapeIntermediate;
+                }
+                b'[' => {
+                    self.params.push(vec![0]);
+                    self.state = State::CsiEntry;
+                }
+                b']' => self.state = State::OscString,
+                b'P' | b'X' | b'^' | b'_' => self.state = State::StringIgnore,
+                0x30..=0x7e => {
+                    performer.esc_dispatch(&self.intermediates, byte);
+                    self.state = State::Ground;
+                }
+                _ => {}
+            },
+            State::EscapeIntermediate => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x2f => self.collect(byte),
+                0x30..=0x7e => {
+                    performer.esc_dispatch(&self.intermediates, byte);
+                    self.state = State::Ground;
+                }
+                _ => {}
+            },
+            State::CsiEntry => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                b'<'..=b'?' => {
+                    self.private = Some(byte);
+                    self.state = State::CsiParam;
+                }
+                b'0'..=b'9' | b';' | b':' => {
+                    self.param(byte);
+                    self.state = State::CsiParam;
+                }
+                0x20..=0x2f => {
+                    self.collect(byte);
+                    self.state = State::CsiIntermediate;
+                }
+                0x40..=0x7e => self.dispatch_csi(performer, byte),
+                _ => {}
+            },
+            State::CsiParam => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                b'0'..=b'9' | b';' | b':' => self.param(byte),
+                b'<'..=b'?' => self.state = State::CsiIgnore,
+                0x20..=0x2f => {
+                    self.collect(byte);
+                    self.state = State::CsiIntermediate;
+                }
+                0x40..=0x7e => self.dispatch_csi(performer, byte),
+                _ => {}
+            },
+            State::CsiIntermediate => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x20..=0x2f => self.collect(byte),
+                0x30..=0x3f => self.state = State::CsiIgnore,
+                0x40..=0x7e => self.dispatch_csi(performer, byte),
+                _ => {}
+            },
+            State::CsiIgnore => match byte {
+                0x00..=0x1f => performer.execute(byte),
+                0x40..=0x7e => self.state = State::Ground,
+                _ => {}
+            },
+            State::OscString => match byte {
+                0x07 => {
+                    self.dispatch_osc(performer);
+                    self.state = State::Ground;
+                }
+                0x00..=0x1f => {}
+                _ => {
+                    if self.osc.len() < MAX_OSC_LEN {
+                        self.osc.push(byte);
+                    }
+                }
+            },
+            State::StringIgnore => {}
+        }
+    }
+
+    fn start_utf8<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        self.utf8_len = match byte {
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => {
+                performer.print(char::REPLACEMENT_CHARACTER);
+                return;
+            }
+        };
+        self.utf8.push(byte);
+    }
+
+    fn clear(&mut self) {
+        self.params.clear();
+        self.intermediates.clear();
+        self.private = None;
+        self.osc.clear();
+    }
+
+    fn collect(&mut self, byte: u8) {
+        if self.intermediates.len() < MAX_INTERMEDIATES {
+            self.intermediates.push(byte);
+        }
+    }
+
+    fn param(&mut self, byte: u8) {
+        if self.params.is_empty() {
+            self.params.push(vec![0]);
+        }
+        match byte {
+            b';' => {
+                if self.params.len() < MAX_PARAMS {
+                    self.params.push(vec![0]);
+                }
+            }
+            b':' => {
+                let last = self.params.last_mut().unwrap();
+                if last.len() < MAX_SUBPARAMS {
+                    last.push(0);
+                }
+            }
+            digit => {
+                let value = self.params.last_mut().unwrap().last_mut().unwrap();
+                *value = value.saturating_mul(10).saturating_add((digit - b'0') as u16);
+            }
+        }
+    }
+
+    fn dispatch_csi<P: Perform>(&mut self, performer: &mut P, byte: u8) {
+        if self.params.is_empty() {
+            self.params.push(vec![0]);
+        }
+        performer.csi_dispatch(&self.params, &self.intermediates, self.private, byte as char);
+        self.state = State::Ground;
+    }
+
+    fn dispatch_osc<P: Perform>(&mut self, performer: &mut P) {
+        let params: Vec<&[u8]> = self.osc.split(|&byte| byte == b';').collect();
+        performer.osc_dispatch(&params);
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Action {
+        Print(char),
+        Execute(u8),
+        Csi(Vec<Vec<u16>>, Vec<u8>, Option<u8>, char),
+        Esc(Vec<u8>, u8),
+        Osc(Vec<String>),
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<Action>);
+
+    impl Perform for Recorder {
+        fn print(&mut self, ch: char) {
+            self.0.push(Action::Print(ch));
+        }
+
+        fn execute(&mut self, byte: u8) {
+            self.0.push(Action::Execute(byte));
+        }
+
+        fn csi_dispatch(&mut self, params: &[Vec<u16>], intermediates: &[u8], private: Option<u8>, action: char) {
+            self.0.push(Action::Csi(params.to_vec(), intermediates.to_vec(), private, action));
+        }
+
+        fn esc_dispatch(&mut self, intermediates: &[u8], byte: u8) {
+            self.0.push(Action::Esc(intermediates.to_vec(), byte));
+        }
+
+        fn osc_dispatch(&mut self, params: &[&[u8]]) {
+            self.0.push(Action::Osc(params.iter().map(|param| String::from_utf8_lossy(param).into_owned()).collect()));
+        }
+    }
+
+    /// Everything `chunks` produce, fed one after another
+    fn parse(chunks: &[&[u8]]) -> Vec<Action> {
+        let mut parser = Parser::new();
+        let mut recorder = Recorder::default();
+        for chunk in chunks {
+            parser.advance(&mut recorder, chunk);
+        }
+        recorder.0
+    }
+
+    #[test]
+    fn prints_text_and_executes_controls() {
+        assert_eq!(parse(&[b"a\r\n\x7fb"]), vec![
+            Action::Print('a'),
+            Action::Execute(b'\r'),
+            Action::Execute(b'\n'),
+            Action::Print('b'),
+        ]);
+    }
+
+    #[test]
+    fn csi_collects_params_subparams_and_markers() {
+        assert_eq!(parse(&[b"\x1b[1;;38:2::1:2:3m\x1b[?1049h\x1b[ q\x1b[H"]), vec![
+            Action::Csi(vec![vec![1], vec![0], vec![38, 2, 0, 1, 2, 3]], vec![], None, 'm'),
+            Action::Csi(vec![vec![1049]], vec![], Some(b'?'), 'h'),
+            Action::Csi(vec![vec![0]], vec![b' '], None, 'q'),
+            Action::Csi(vec![vec![0]], vec![], None, 'H'),
+        ]);
+    }
+
+    #[test]
+    fn sequences_split_across_reads_still_parse() {
+        assert_eq!(parse(&[b"\x1b", b"[3", b"1m", "中".as_bytes().split_at(1).0, "中".as_bytes().split_at(1).1]), vec![
+            Action::Csi(vec![vec![31]], vec![], None, 'm'),
+            Action::Print('中'),
+        ]);
+    }
+
+    #[test]
+    fn malformed_utf8_prints_replacement_characters() {
+        assert_eq!(parse(&[b"\xe4\xb8a\xff"]), vec![
+            Action::Print(char::REPLACEMENT_CHARACTER),
+            Action::Print('a'),
+            Action::Print(char::REPLACEMENT_CHARACTER),
+        ]);
+    }
+
+    #[test]
+    fn osc_ends_with_bel_or_st() {
+        assert_eq!(parse(&[b"\x1b]0;one\x07\x1b]2;two\x1b\\x"]), vec![
+            Action::Osc(vec!["0".to_string(), "one".to_string()]),
+            Action::Osc(vec!["2".to_string(), "two".to_string()]),
+            Action::Esc(vec![], b'\\'),
+            Action::Print('x'),
+        ]);
+    }
+
+    #[test]
+    fn escape_and_charset_designation() {
+        assert_eq!(parse(&[b"\x1b7\x1b(0"]), vec![
+            Action::Esc(vec![], b'7'),
+            Action::Esc(vec![b'('], b'0'),
+        ]);
+    }
+
+    #[test]
+    fn device_control_strings_are_dropped() {
+        assert_eq!(parse(&[b"\x1bPq#0;2;0;0;0\x1b\\ok"]), vec![
+            Action::Esc(vec![], b'\\'),
+            Action::Print('o'),
+            Action::Print('k'),
+        ]);
+    }
+
+    #[test]
+    fn cancel_abandons_a_sequence() {
+        assert_eq!(parse(&[b"\x1b[12\x18m"]), vec![Action::Print('m')]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Glyph atlas: rasterized glyphs packed into shared RGBA pages
+//!
+//! Pages use core/rendering's atlas page layout and are shelf-packed by
+//! systems/software's packer, the one sprite atlases use. Texels are white
+//! with the glyph coverage in alpha, so a sprite's color tints the text.
+
+use std::collections::HashMap;
+use playground_core_rendering::ResourceId;
+use playground_core_rendering::batching::{AtlasPage, AtlasRegion};
+use playground_systems_software::batching::insert;
+use super::font::FontId;
+
+/// Width and height of glyph pages
+pub const GLYPH_PAGE_SIZE: u32 = 1024;
+
+/// First texture ID used for glyph pages. The UI owns the IDs from here up
+/// and sends pages to clients with `LoadTexture` packets.
+pub const GLYPH_TEXTURE_BASE: ResourceId = 0x1000_0000;
+
+/// Glyph sizes are rounded to quarter pixels so nearly equal sizes share bitmaps
+const SIZE_STEPS: f32 = 4.0;
+
+/// A glyph of one font at one size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font: FontId,
+    pub glyph_index: u16,
+    /// Size in quarter pixels
+    pub size: u32,
+}
+
+impl GlyphKey {
+    pub fn new(font: FontId, glyph_index: u16, size: f32) -> Self {
+        Self {
+            font,
+            glyph_index,
+            size: (size.max(0.0) * SIZE_STEPS).round() as u32,
+        }
+    }
+
+    /// Size the glyph is rasterized at
+    pub fn size_px(&self) -> f32 {
+        self.size as f32 / SIZE_STEPS
+    }
+}
+
+/// Where a rasterized glyph lives and how it sits on the baseline
+#[derive(Debug, Clone, Copy)]
+pub struct CachedGlyph {
+    /// None for glyphs without pixels, such as spaces, or too large for a page
+    pub region: Option<AtlasRegion>,
+    /// Top-left of the bitmap relative to the pen position on the baseline
+    pub offset: [f32; 2],
+    pub size: [f32; 2],
+}
+
+pub struct GlyphAtlas {
+    first_texture_id: ResourceId,
+    pages: Vec<AtlasPage>,
+    /// Pages changed since the last `take_dirty_pages`
+    dirty: Vec<bool>,
+    glyphs: HashMap<GlyphKey, CachedGlyph>,
+}
+
+impl GlyphAtlas {
+    pub fn new(first_texture_id: ResourceId) -> Self {
+        Self {
+            first_texture_id,
+            pages: Vec::new(),
+            dirty: Vec::new(),
+            glyphs: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &GlyphKey) -> Option<&CachedGlyph> {
+        self.glyphs.get(key)
+    }
+
+    /// Pack a glyph bitmap from fontdue: one coverage byte per pixel, top row first
+    pub fn insert(&mut self, key: GlyphKey, metrics: &fontdue::Metrics, coverage: &[u8]) -> CachedGlyph {
+        let (width, height) = (metrics.width as u32, metrics.height as u32);
+        let region = if width == 0 || height == 0 {
+            None
+        } else {
+            let pixels: Vec<u8> = coverage.iter().flat_map(|&alpha| [255, 255, 255, alpha]).collect();
+            self.pack(width, height, &pixels)
+        };
+
+        let glyph = CachedGlyph {
+            region,
+            offset: [metrics.xmin as f32, -(metrics.ymin + metrics.height as i32) as f32],
+            size: [width as f32, height as f32],
+        };
+        self.glyphs.insert(key, glyph);
+        glyph
+    }
+
+    fn pack(&mut self, width: u32, height: u32, pixels: &[u8]) -> Option<AtlasRegion> {
+        for (page, dirty) in self.pages.iter_mut().zip(&mut self.dirty) {
+            if let Some(region) = insert(page, width, height, pixels) {
+                *dirty = true;
+                return Some(region);
+            }
+        }
+
+        // Every page is full; glyphs too large for an empty page are dropped
+        let texture_id = self.first_texture_id + self.pages.len() as ResourceId;
+        let mut page = AtlasPage::new(texture_id, GLYPH_PAGE_SIZE, GLYPH_PAGE_SIZE);
+        let region = insert(&mut page, width, height, pixels)?;
+        self.pages.push(page);
+        self.dirty.push(true);
+        Some(region)
+    }
+
+    pub fn pages(&self) -> &[AtlasPage] {
+        &self.pages
+    }
+
+    pub fn glyph_count(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    /// Pages that need uploading, clearing their dirty flags
+    pub fn take_dirty_pages(&mut self) -> Vec<&AtlasPage> {
+        let dirty = std::mem::replace(&mut self.dirty, vec![false; self.pages.len()]);
+        self.pages.iter().zip(dirty).filter(|(_, dirty)| *dirty).map(|(page, _)| page).collect()
+    }
+
+    /// Forget every glyph. Pages keep their texture IDs and are re-uploaded
+    /// as glyphs are packed again.
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+        for (page, dirty) in self.pages.iter_mut().zip(&mut self.dirty) {
+            *page = AtlasPage::new(page.texture_id, page.width, page.height);
+            *dirty = true;
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Loaded fonts and the glyph cache they share
+
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use playground_core_rendering::{ColorRGBA, RenderCommand, ResourceId, SpriteQuad};
+use playground_core_rendering::batching::AtlasPage;
+use crate::components::FontWeight;
+use crate::error::{UiError, UiResult};
+use super::atlas::{CachedGlyph, GlyphAtlas, GlyphKey, GLYPH_TEXTURE_BASE};
+use super::layout::{Shaper, TextLayout, TextStyle};
+
+/// Index of a font in its `FontManager`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FontId(pub u32);
+
+/// Vertical metrics of a font at one size, in pixels. Descent is negative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+}
+
+impl FontMetrics {
+    /// Metrics used when no font is loaded
+    pub fn fallback(size: f32) -> Self {
+        Self {
+            ascent: size * 0.8,
+            descent: -size * 0.2,
+            line_gap: size * 0.2,
+        }
+    }
+
+    /// Distance between consecutive baselines
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+}
+
+struct LoadedFont {
+    family: String,
+    weight: FontWeight,
+    font: fontdue::Font,
+}
+
+pub struct FontManager {
+    fonts: Vec<LoadedFont>,
+    default_family: Option<String>,
+    atlas: GlyphAtlas,
+}
+
+impl FontManager {
+    pub fn new() -> Self {
+        Self::with_texture_base(GLYPH_TEXTURE_BASE)
+    }
+
+    /// Glyph pages take consecutive texture IDs starting at `first_texture_id`
+    pub fn with_texture_base(first_texture_id: ResourceId) -> Self {
+        Self {
+            fonts: Vec::new(),
+            default_family: None,
+            atlas: GlyphAtlas::new(first_texture_id),
+        }
+    }
+
+    /// Load a TTF or OTF font. Loading the same family and weight again
+    /// replaces the earlier font and keeps its ID.
+    pub fn load_font(&mut self, family: &str, weight: FontWeight, bytes: Vec<u8>) -> UiResult<FontId> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())
+            .map_err(|e| UiError::FontError(format!("{}: {}", family, e)))?;
+        let loaded = LoadedFont { family: family.to_string(), weight, font };
+
+        if let Some(index) = self.fonts.iter()
+            .position(|existing| existing.family.eq_ignore_ascii_case(family) && existing.weight == weight)
+        {
+            self.fonts[index] = loaded;
+            // Cached bitmaps belong to the old outlines
+            self.atlas.clear();
+            return Ok(FontId(index as u32));
+        }
+
+        self.fonts.push(loaded);
+        if self.default_family.is_none() {
+            self.default_family = Some(family.to_string());
+        }
+        Ok(FontId(self.fonts.len() as u32 - 1))
+    }
+
+    pub async fn load_font_file(&mut self, family: &str, weight: FontWeight, path: impl AsRef<Path>) -> UiResult<FontId> {
+        let path = path.as_ref();
+        let bytes = tokio::fs::read(path).await
+            .map_err(|e| UiError::FontError(format!("{}: {}", path.display(), e)))?;
+        self.load_font(family, weight, bytes)
+    }
+
+    /// Family used when a style names none or names one that isn't loaded.
+    /// Defaults to the first family loaded.
+    pub fn set_default_family(&mut self, family: &str) {
+        self.default_family = Some(family.to_string());
+    }
+
+    pub fn has_fonts(&self) -> bool {
+        !self.fonts.is_empty()
+    }
+
+    /// Pick the loaded font closest to a family and weight
+    pub fn resolve(&self, family: Option<&str>, weight: FontWeight) -> Option<FontId> {
+        let in_family = |name: &str| {
+            self.fonts.iter().enumerate()
+                .filter(|(_, font)| font.family.eq_ignore_ascii_case(name))
+                .collect::<Vec<_>>()
+        };
+
+        let mut candidates = family.map(in_family).unwrap_or_default();
+        if candidates.is_empty()
+            && let Some(default) = &self.default_family
+        {
+            candidates = in_family(default);
+        }
+        if candidates.is_empty() {
+            candidates = self.fonts.iter().enumerate().collect();
+        }
+
+        candidates.into_iter()
+            .min_by_key(|(_, font)| (weight_rank(font.weight) - weight_rank(weight)).abs())
+            .map(|(index, _)| FontId(index as u32))
+    }
+
+    pub fn metrics(&self, font: Option<FontId>, size: f32) -> FontMetrics {
+        self.shaper(font, size).metrics()
+    }
+
+    /// Break text into lines and position every glyph
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        let font = self.resolve(style.font_family.as_deref(), style.font_weight);
+        TextLayout::build(text, style, font, &self.shaper(font, style.font_size))
+    }
+
+    /// Width and height of text laid out with `style`
+    pub fn measure(&self, text: &str, style: &TextStyle) -> [f32; 2] {
+        let layout = self.layout(text, style);
+        [layout.width, layout.height]
+    }
+
+    /// Commands drawing `layout` with its top-left corner at `origin`.
+    ///
+    /// Glyphs not yet in the atlas are rasterized now; upload the pages from
+    /// `take_dirty_pages` before the commands are drawn.
+    pub fn draw_text(&mut self, layout: &TextLayout, origin: [f32; 2], color: ColorRGBA) -> Vec<RenderCommand> {
+        let Some(font) = layout.font else {
+            return layout.lines.iter()
+                .filter(|line| !layout.line_text(line).trim().is_empty())
+                .map(|line| RenderCommand::DrawText {
+                    text: layout.line_text(line).to_string(),
+                    position: [origin[0] + line.x, origin[1] + line.baseline - layout.ascent],
+                    size: layout.font_size,
+                    color,
+                })
+                .collect();
+        };
+
+        // One batch per atlas page, in the order pages are first used
+        let mut batches: Vec<(ResourceId, Vec<SpriteQuad>)> = Vec::new();
+        for glyph in &layout.glyphs {
+            if glyph.character.is_whitespace() {
+                continue;
+            }
+            let key = GlyphKey::new(font, glyph.glyph_index, layout.font_size);
+            let cached = self.glyph(key);
+            let Some(region) = cached.region else {
+                continue;
+            };
+
+            // Snap to whole pixels so glyph texels map one to one
+            let pen = [(origin[0] + glyph.x).round(), (origin[1] + glyph.baseline).round()];
+            let quad = SpriteQuad {
+                position: [pen[0] + cached.offset[0], pen[1] + cached.offset[1]],
+                size: cached.size,
+                rotation: 0.0,
+                uv_min: region.uv_min,
+                uv_max: region.uv_max,
+                color,
+            };
+            match batches.iter_mut().find(|(texture_id, _)| *texture_id == region.texture_id) {
+                Some((_, quads)) => quads.push(quad),
+                None => batches.push((region.texture_id, vec![quad])),
+            }
+        }
+
+        batches.into_iter()
+            .map(|(texture_id, sprites)| RenderCommand::DrawSprites {
+                texture_id: Some(texture_id),
+                material: 0,
+                sprites,
+            })
+            .collect()
+    }
+
+    pub fn atlas(&self) -> &GlyphAtlas {
+        &self.atlas
+    }
+
+    /// Glyph pages changed since the last call
+    pub fn take_dirty_pages(&mut self) -> Vec<&AtlasPage> {
+        self.atlas.take_dirty_pages()
+    }
+
+    fn glyph(&mut self, key: GlyphKey) -> CachedGlyph {
+        if let Some(glyph) = self.atlas.get(&key) {
+            return *glyph;
+        }
+        let font = &self.fonts[key.font.0 as usize].font;
+        let (metrics, coverage) = font.rasterize_indexed(key.glyph_index, key.size_px());
+        self.atlas.insert(key, &metrics, &coverage)
+    }
+
+    fn shaper(&self, font: Option<FontId>, size: f32) -> Shaper<'_> {
+        Shaper::new(font.map(|id| &self.fonts[id.0 as usize].font), size)
+    }
+}
+
+impl Default for FontManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn weight_rank(weight: FontWeight) -> i32 {
+    match weight {
+        FontWeight::Light => 300,
+        FontWeight::Normal => 400,
+        FontWeight::Bold => 700,
+        FontWeight::ExtraBold => 800,
+    }
+}
@@ -0,0 +1,315 @@
+//! Text measurement, line breaking and alignment
+
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::components::{FontWeight, TextAlign, UiStyleComponent};
+use super::font::{FontId, FontMetrics};
+
+/// Advance of one character, as a fraction of the font size, when no font
+/// is loaded. Matches the box glyphs backends draw for `DrawText`.
+pub const FALLBACK_ADVANCE: f32 = 0.72;
+
+/// Tabs advance by this many spaces
+const TAB_WIDTH: f32 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    pub font_family: Option<String>,
+    pub font_weight: FontWeight,
+    pub font_size: f32,
+    /// Multiplier on the font's own line height
+    pub line_height: f32,
+    /// Lines wrap at this width; None keeps each paragraph on one line
+    pub max_width: Option<f32>,
+    pub align: TextAlign,
+}
+
+impl TextStyle {
+    pub fn from_style(style: &UiStyleComponent, max_width: Option<f32>) -> Self {
+        Self {
+            font_family: style.font_family.clone(),
+            font_weight: style.font_weight,
+            font_size: style.font_size,
+            line_height: 1.0,
+            max_width,
+            align: style.text_align,
+        }
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family: None,
+            font_weight: FontWeight::Normal,
+            font_size: 14.0,
+            line_height: 1.0,
+            max_width: None,
+            align: TextAlign::Left,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PositionedGlyph {
+    pub character: char,
+    pub glyph_index: u16,
+    /// Byte offset of the character in the laid out text
+    pub byte_offset: usize,
+    /// Pen position relative to the layout's top-left corner
+    pub x: f32,
+    pub baseline: f32,
+    pub advance: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    /// Bytes of the line, without its line break
+    pub range: Range<usize>,
+    /// Indices into `TextLayout::glyphs`
+    pub glyphs: Range<usize>,
+    /// Offset from alignment
+    pub x: f32,
+    /// Width without trailing whitespace
+    pub width: f32,
+    pub baseline: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    pub text: String,
+    /// None when no font was loaded and fallback metrics were used
+    pub font: Option<FontId>,
+    pub font_size: f32,
+    pub ascent: f32,
+    pub line_height: f32,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    /// Widest line
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Glyph lookup, advances and kerning for one font at one size
+pub(crate) struct Shaper<'a> {
+    font: Option<&'a fontdue::Font>,
+    size: f32,
+}
+
+impl<'a> Shaper<'a> {
+    pub(crate) fn new(font: Option<&'a fontdue::Font>, size: f32) -> Self {
+        Self { font, size }
+    }
+
+    pub(crate) fn metrics(&self) -> FontMetrics {
+        self.font
+            .and_then(|font| font.horizontal_line_metrics(self.size))
+            .map(|metrics| FontMetrics {
+                ascent: metrics.ascent,
+                descent: metrics.descent,
+                line_gap: metrics.line_gap,
+            })
+            .unwrap_or_else(|| FontMetrics::fallback(self.size))
+    }
+
+    fn glyph(&self, character: char) -> (u16, f32) {
+        if character == '\t' {
+            return (self.glyph(' ').0, self.glyph(' ').1 * TAB_WIDTH);
+        }
+        match self.font {
+            Some(font) => {
+                let index = font.lookup_glyph_index(character);
+                (index, font.metrics_indexed(index, self.size).advance_width)
+            }
+            None if character.is_control() => (0, 0.0),
+            None => (0, self.size * FALLBACK_ADVANCE),
+        }
+    }
+
+    fn kern(&self, left: u16, right: u16) -> f32 {
+        self.font
+            .and_then(|font| font.horizontal_kern_indexed(left, right, self.size))
+            .unwrap_or(0.0)
+    }
+}
+
+/// The line being filled
+struct LineState {
+    start: usize,
+    first_glyph: usize,
+    pen: f32,
+    /// Pen position after the last non-whitespace glyph
+    content_width: f32,
+    previous: Option<u16>,
+}
+
+impl LineState {
+    fn new(start: usize, first_glyph: usize) -> Self {
+        Self { start, first_glyph, pen: 0.0, content_width: 0.0, previous: None }
+    }
+
+    fn is_empty(&self, glyphs: &[PositionedGlyph]) -> bool {
+        glyphs[self.first_glyph..].iter().all(|glyph| glyph.character.is_whitespace())
+    }
+}
+
+impl TextLayout {
+    pub(crate) fn build(text: &str, style: &TextStyle, font: Option<FontId>, shaper: &Shaper) -> Self {
+        let metrics = shaper.metrics();
+        let line_height = metrics.line_height() * style.line_height;
+        let mut layout = Self {
+            text: text.to_string(),
+            font,
+            font_size: style.font_size,
+            // Extra leading from the multiplier is split above and below the line
+            ascent: metrics.ascent + (line_height - metrics.line_height()) * 0.5,
+            line_height,
+            glyphs: Vec::new(),
+            lines: Vec::new(),
+            width: 0.0,
+            height: 0.0,
+        };
+
+        // Lines ending a paragraph are never justified
+        let mut paragraph_ends = Vec::new();
+        let mut paragraph_start = 0;
+        for paragraph in text.split('\n') {
+            let content = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+            layout.layout_paragraph(paragraph_start, content, style.max_width, shaper);
+            paragraph_ends.push(layout.lines.len() - 1);
+            paragraph_start += paragraph.len() + 1;
+        }
+
+        layout.width = layout.lines.iter().map(|line| line.width).fold(0.0, f32::max);
+        layout.height = layout.lines.len() as f32 * line_height;
+        layout.align(style.max_width.unwrap_or(layout.width), style.align, &paragraph_ends);
+        layout
+    }
+
+    fn layout_paragraph(&mut self, start: usize, paragraph: &str, max_width: Option<f32>, shaper: &Shaper) {
+        let mut line = LineState::new(start, self.glyphs.len());
+
+        for (offset, word) in paragraph.split_word_bound_indices() {
+            let offset = start + offset;
+            let is_space = word.chars().all(char::is_whitespace);
+
+            if let Some(max_width) = max_width
+                && !is_space
+                && !line.is_empty(&self.glyphs)
+                && line.pen + self.word_width(word, line.previous, shaper) > max_width
+            {
+                self.finish_line(&line, offset);
+                line = LineState::new(offset, self.glyphs.len());
+            }
+
+            for (char_offset, character) in word.char_indices() {
+                let (index, advance) = shaper.glyph(character);
+                let kern = line.previous.map(|previous| shaper.kern(previous, index)).unwrap_or(0.0);
+
+                // Words wider than a whole line break between characters
+                if let Some(max_width) = max_width
+                    && !character.is_whitespace()
+                    && !line.is_empty(&self.glyphs)
+                    && line.pen + kern + advance > max_width
+                {
+                    self.finish_line(&line, offset + char_offset);
+                    line = LineState::new(offset + char_offset, self.glyphs.len());
+                } else {
+                    line.pen += kern;
+                }
+
+                self.glyphs.push(PositionedGlyph {
+                    character,
+                    glyph_index: index,
+                    byte_offset: offset + char_offset,
+                    x: line.pen,
+                    baseline: 0.0,
+                    advance,
+                });
+                line.pen += advance;
+                if !character.is_whitespace() {
+                    line.content_width = line.pen;
+                }
+                line.previous = Some(index);
+            }
+        }
+
+        self.finish_line(&line, start + paragraph.len());
+    }
+
+    fn word_width(&self, word: &str, previous: Option<u16>, shaper: &Shaper) -> f32 {
+        let mut previous = previous;
+        let mut width = 0.0;
+        for character in word.chars() {
+            let (index, advance) = shaper.glyph(character);
+            width += previous.map(|previous| shaper.kern(previous, index)).unwrap_or(0.0) + advance;
+            previous = Some(index);
+        }
+        width
+    }
+
+    fn finish_line(&mut self, line: &LineState, end: usize) {
+        let baseline = self.lines.len() as f32 * self.line_height + self.ascent;
+        for glyph in &mut self.glyphs[line.first_glyph..] {
+            glyph.baseline = baseline;
+        }
+        self.lines.push(TextLine {
+            range: line.start..end,
+            glyphs: line.first_glyph..self.glyphs.len(),
+            x: 0.0,
+            width: line.content_width,
+            baseline,
+        });
+    }
+
+    fn align(&mut self, width: f32, align: TextAlign, paragraph_ends: &[usize]) {
+        for (index, line) in self.lines.iter_mut().enumerate() {
+            let slack = (width - line.width).max(0.0);
+            let glyphs = &mut self.glyphs[line.glyphs.clone()];
+
+            match align {
+                TextAlign::Left => {}
+                TextAlign::Center => line.x = slack * 0.5,
+                TextAlign::Right => line.x = slack,
+                TextAlign::Justify if paragraph_ends.contains(&index) => {}
+                TextAlign::Justify => {
+                    // Spread the slack over the gaps between words
+                    let content = glyphs.iter().rposition(|glyph| !glyph.character.is_whitespace()).map_or(0, |last| last + 1);
+                    let gaps = glyphs[..content].iter().filter(|glyph| glyph.character == ' ').count();
+                    if gaps > 0 {
+                        let extra = slack / gaps as f32;
+                        let mut shift = 0.0;
+                        for glyph in glyphs[..content].iter_mut() {
+                            glyph.x += shift;
+                            if glyph.character == ' ' {
+                                shift += extra;
+                            }
+                        }
+                        line.width += slack;
+                    }
+                }
+            }
+
+            for glyph in glyphs.iter_mut() {
+                glyph.x += line.x;
+            }
+        }
+    }
+
+    pub fn line_text(&self, line: &TextLine) -> &str {
+        &self.text[line.range.clone()]
+    }
+
+    /// Caret position for a byte offset: x and the top of its line
+    pub fn caret_position(&self, byte_offset: usize) -> [f32; 2] {
+        let Some(line) = self.lines.iter().rev().find(|line| line.range.start <= byte_offset) else {
+            return [0.0, 0.0];
+        };
+        let top = line.baseline - self.ascent;
+        let glyphs = &self.glyphs[line.glyphs.clone()];
+        match glyphs.iter().find(|glyph| glyph.byte_offset >= byte_offset) {
+            Some(glyph) => [glyph.x, top],
+            None => [glyphs.last().map_or(line.x, |glyph| glyph.x + glyph.advance), top],
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Font loading, glyph atlas and text layout
+//!
+//! `FontManager` loads TTF/OTF fonts, lays text out with kerning, line
+//! height and wrapping, and turns laid out text into `DrawSprites` quads
+//! sampling a glyph atlas. Until a font is loaded, layout falls back to the
+//! fixed advance the backends use for `DrawText`, and text is emitted as
+//! `DrawText` commands.
+
+pub mod atlas;
+pub mod font;
+pub mod layout;
+
+pub use atlas::{GlyphAtlas, GlyphKey, CachedGlyph, GLYPH_PAGE_SIZE, GLYPH_TEXTURE_BASE};
+pub use font::{FontManager, FontId, FontMetrics};
+pub use layout::{TextLayout, TextLine, TextStyle, PositionedGlyph, FALLBACK_ADVANCE};
@@ -88,4 +88,64 @@ impl ThemeColors {
             custom: HashMap::new(),
         }
     }
+    
+    /// Look up a color by field name, then among the custom tokens
+    pub fn get(&self, name: &str) -> Option<Vector4<f32>> {
+        match name {
+            "background" => Some(self.background),
+            "surface" => Some(self.surface),
+            "surface_variant" => Some(self.surface_variant),
+            "primary" => Some(self.primary),
+            "secondary" => Some(self.secondary),
+            "text" => Some(self.text),
+            "text_secondary" => Some(self.text_secondary),
+            "border" => Some(self.border),
+            "hover" => Some(self.hover),
+            "error" => Some(self.error),
+            "warning" => Some(self.warning),
+            "success" => Some(self.success),
+            "info" => Some(self.info),
+            "editor_background" => Some(self.editor_background),
+            "line_number" => Some(self.line_number),
+            "highlight" => Some(self.highlight),
+            "cursor" => Some(self.cursor),
+            "keyword" => Some(self.keyword),
+            "string" => Some(self.string),
+            "number" => Some(self.number),
+            "comment" => Some(self.comment),
+            "function" => Some(self.function),
+            "type_color" => Some(self.type_color),
+            _ => self.custom.get(name).copied(),
+        }
+    }
+    
+    /// Mutable access to a named field; custom tokens are not included
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Vector4<f32>> {
+        match name {
+            "background" => Some(&mut self.background),
+            "surface" => Some(&mut self.surface),
+            "surface_variant" => Some(&mut self.surface_variant),
+            "primary" => Some(&mut self.primary),
+            "secondary" => Some(&mut self.secondary),
+            "text" => Some(&mut self.text),
+            "text_secondary" => Some(&mut self.text_secondary),
+            "border" => Some(&mut self.border),
+            "hover" => Some(&mut self.hover),
+            "error" => Some(&mut self.error),
+            "warning" => Some(&mut self.warning),
+            "success" => Some(&mut self.success),
+            "info" => Some(&mut self.info),
+            "editor_background" => Some(&mut self.editor_background),
+            "line_number" => Some(&mut self.line_number),
+            "highlight" => Some(&mut self.highlight),
+            "cursor" => Some(&mut self.cursor),
+            "keyword" => Some(&mut self.keyword),
+            "string" => Some(&mut self.string),
+            "number" => Some(&mut self.number),
+            "comment" => Some(&mut self.comment),
+            "function" => Some(&mut self.function),
+            "type_color" => Some(&mut self.type_color),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
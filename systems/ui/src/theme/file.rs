@@ -0,0 +1,184 @@
+//! Theme files
+//!
+//! Themes are JSON documents that override parts of a base theme:
+//!
+//! ```json
+//! {
+//!     "extends": "Dark",
+//!     "colors": { "primary": "#268bd2", "cursor": "$accent" },
+//!     "typography": { "font_size_base": 15 },
+//!     "spacing": { "unit": 4 },
+//!     "borders": { "radius_medium": 6 },
+//!     "tokens": { "accent": "#b58900", "sidebar_width": 240 }
+//! }
+//! ```
+//!
+//! Colors are `#rgb`, `#rrggbb`, `#rrggbbaa`, an `[r, g, b, a]` array of
+//! 0..1 floats, or `$name` to reuse another color of the theme. `colors`
+//! only sets `ThemeColors` fields; `tokens` adds custom colors (to
+//! `colors.custom`) and numbers (to `tokens`). Anything left out is taken
+//! from `extends`, or from the dark theme when there is no base.
+
+use std::collections::{BTreeMap, HashMap};
+use nalgebra::Vector4;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::error::{UiError, UiResult};
+use super::types::Theme;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeFile {
+    /// Name of the theme this one is based on
+    #[serde(default)]
+    pub extends: Option<String>,
+    #[serde(default)]
+    pub colors: BTreeMap<String, ColorValue>,
+    #[serde(default)]
+    pub typography: Map<String, Value>,
+    #[serde(default)]
+    pub spacing: Map<String, Value>,
+    #[serde(default)]
+    pub borders: Map<String, Value>,
+    #[serde(default)]
+    pub tokens: BTreeMap<String, TokenValue>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ColorValue {
+    Rgba([f32; 4]),
+    /// A hex color or a `$name` reference
+    Text(String),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TokenValue {
+    Number(f32),
+    Color(ColorValue),
+}
+
+impl ThemeFile {
+    pub fn parse(data: &[u8]) -> UiResult<Self> {
+        serde_json::from_slice(data)
+            .map_err(|e| UiError::InvalidTheme(e.to_string()))
+    }
+
+    /// Build the theme `name` by applying this file on top of `base`
+    pub fn apply(&self, name: &str, base: &Theme) -> UiResult<Theme> {
+        let mut theme = base.clone();
+        theme.name = name.to_string();
+        theme.typography = merge_section("typography", &base.typography, &self.typography)?;
+        theme.spacing = merge_section("spacing", &base.spacing, &self.spacing)?;
+        theme.borders = merge_section("borders", &base.borders, &self.borders)?;
+
+        // Every color this file defines, so references can see each other
+        let mut pending: HashMap<&str, &ColorValue> = HashMap::new();
+        for (key, value) in &self.colors {
+            if theme.colors.field_mut(key).is_none() {
+                return Err(UiError::InvalidTheme(format!("unknown color '{}'; custom colors belong in tokens", key)));
+            }
+            pending.insert(key, value);
+        }
+        for (key, value) in &self.tokens {
+            if let TokenValue::Color(color) = value {
+                if pending.insert(key, color).is_some() {
+                    return Err(UiError::InvalidTheme(format!("token '{}' shadows a theme color", key)));
+                }
+            }
+        }
+
+        let mut resolved = Vec::with_capacity(pending.len());
+        for &key in pending.keys() {
+            let color = resolve_color(key, &pending, base, &mut Vec::new())?;
+            resolved.push((key, color));
+        }
+        for (key, color) in resolved {
+            match theme.colors.field_mut(key) {
+                Some(field) => *field = color,
+                None => {
+                    theme.colors.custom.insert(key.to_string(), color);
+                }
+            }
+        }
+
+        for (key, value) in &self.tokens {
+            if let TokenValue::Number(number) = *value {
+                theme.tokens.insert(key.clone(), number);
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// Value of color `name`, following `$` references through this file and
+/// then the base theme. `stack` holds the references being followed.
+fn resolve_color<'a>(
+    name: &'a str,
+    pending: &HashMap<&'a str, &'a ColorValue>,
+    base: &Theme,
+    stack: &mut Vec<&'a str>,
+) -> UiResult<Vector4<f32>> {
+    let Some(&value) = pending.get(name) else {
+        return base.colors.get(name)
+            .ok_or_else(|| UiError::InvalidTheme(format!("unknown color '{}'", name)));
+    };
+    if stack.contains(&name) {
+        stack.push(name);
+        return Err(UiError::InvalidTheme(format!("color reference cycle: {}", stack.join(" -> "))));
+    }
+
+    stack.push(name);
+    let color = match value {
+        ColorValue::Rgba([r, g, b, a]) => Vector4::new(*r, *g, *b, *a),
+        ColorValue::Text(text) => match text.strip_prefix('$') {
+            Some(reference) => resolve_color(reference, pending, base, stack)?,
+            None => parse_hex(text)
+                .ok_or_else(|| UiError::InvalidTheme(format!("invalid color '{}' for '{}'", text, name)))?,
+        },
+    };
+    stack.pop();
+    Ok(color)
+}
+
+/// `#rgb`, `#rrggbb` or `#rrggbbaa`
+pub fn parse_hex(text: &str) -> Option<Vector4<f32>> {
+    let digits = text.strip_prefix('#')?;
+    if !digits.is_ascii() {
+        return None;
+    }
+    let channel = |hex: &str| u8::from_str_radix(hex, 16).ok().map(|value| value as f32 / 255.0);
+
+    let [r, g, b, a] = match digits.len() {
+        3 => {
+            let mut rgb = digits.chars().map(|c| channel(&c.to_string().repeat(2)));
+            [rgb.next()??, rgb.next()??, rgb.next()??, 1.0]
+        }
+        6 | 8 => {
+            let alpha = if digits.len() == 8 { channel(&digits[6..8])? } else { 1.0 };
+            [channel(&digits[0..2])?, channel(&digits[2..4])?, channel(&digits[4..6])?, alpha]
+        }
+        _ => return None,
+    };
+    Some(Vector4::new(r, g, b, a))
+}
+
+/// Overlay the keys in `overrides` on `base`, rejecting unknown keys
+fn merge_section<T: Serialize + DeserializeOwned>(section: &str, base: &T, overrides: &Map<String, Value>) -> UiResult<T> {
+    let mut value = serde_json::to_value(base)
+        .map_err(|e| UiError::SerializationError(e.to_string()))?;
+    let fields = value.as_object_mut()
+        .ok_or_else(|| UiError::SerializationError(format!("{} is not an object", section)))?;
+
+    for (key, override_value) in overrides {
+        match fields.get_mut(key) {
+            Some(field) => *field = override_value.clone(),
+            None => return Err(UiError::InvalidTheme(format!("unknown {} field '{}'", section, key))),
+        }
+    }
+
+    serde_json::from_value(value)
+        .map_err(|e| UiError::InvalidTheme(format!("{}: {}", section, e)))
+}
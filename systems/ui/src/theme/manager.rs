@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 use crate::error::{UiError, UiResult};
+use super::file::ThemeFile;
 use super::types::{Theme, ThemeId};
 
 pub struct ThemeManager {
     themes: HashMap<ThemeId, Theme>,
+    /// Files of registered themes, kept to rebuild them when a base changes
+    sources: HashMap<ThemeId, ThemeFile>,
     current: ThemeId,
 }
 
@@ -15,6 +18,7 @@ impl ThemeManager {
         
         Self {
             themes,
+            sources: HashMap::new(),
             current: ThemeId::Dark,
         }
     }
@@ -31,6 +35,80 @@ impl ThemeManager {
         id
     }
     
+    /// Parse a theme file and register it as `name`. Registering a name
+    /// again replaces that theme in place and rebuilds the themes that
+    /// extend it; a dependent that no longer builds keeps its previous
+    /// version and the first such error is returned.
+    pub fn register_theme(&mut self, name: &str, data: &[u8]) -> UiResult<ThemeId> {
+        let file = ThemeFile::parse(data)?;
+
+        // Following the base chain must not lead back here
+        let mut base = file.extends.clone();
+        while let Some(base_name) = base {
+            if base_name == name {
+                return Err(UiError::InvalidTheme(format!("'{}' extends itself", name)));
+            }
+            let base_id = self.find(&base_name)
+                .ok_or_else(|| UiError::ThemeNotFound(base_name.clone()))?;
+            base = self.sources.get(&base_id).and_then(|source| source.extends.clone());
+        }
+
+        let theme = self.build(name, &file)?;
+        let id = match self.find(name) {
+            Some(id) => {
+                self.themes.insert(id, theme);
+                id
+            }
+            None => self.add_theme(theme),
+        };
+        self.sources.insert(id, file);
+
+        self.rebuild_dependents(name)?;
+        Ok(id)
+    }
+    
+    fn build(&self, name: &str, file: &ThemeFile) -> UiResult<Theme> {
+        match &file.extends {
+            Some(base) => {
+                let base = self.find(base)
+                    .and_then(|id| self.themes.get(&id))
+                    .ok_or_else(|| UiError::ThemeNotFound(base.clone()))?;
+                file.apply(name, base)
+            }
+            // The built-in dark theme, even if "Dark" has been replaced
+            None => file.apply(name, &Theme::dark()),
+        }
+    }
+    
+    fn rebuild_dependents(&mut self, name: &str) -> UiResult<()> {
+        let dependents: Vec<ThemeId> = self.sources.iter()
+            .filter(|(_, source)| source.extends.as_deref() == Some(name))
+            .map(|(&id, _)| id)
+            .collect();
+
+        let mut result = Ok(());
+        for id in dependents {
+            let dependent = self.themes[&id].name.clone();
+            match self.build(&dependent, &self.sources[&id]) {
+                Ok(theme) => {
+                    self.themes.insert(id, theme);
+                    if let Err(e) = self.rebuild_dependents(&dependent) {
+                        result = result.and(Err(e));
+                    }
+                }
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+        result
+    }
+    
+    /// Theme registered under `name`
+    pub fn find(&self, name: &str) -> Option<ThemeId> {
+        self.themes.iter()
+            .find(|(_, theme)| theme.name == name)
+            .map(|(&id, _)| id)
+    }
+    
     pub fn get_theme(&self, id: ThemeId) -> UiResult<&Theme> {
         self.themes.get(&id)
             .ok_or_else(|| UiError::ThemeNotFound(id.to_string()))
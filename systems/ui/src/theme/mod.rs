@@ -1,7 +1,9 @@
 pub mod types;
 pub mod colors;
+pub mod file;
 pub mod manager;
 
 pub use types::{Theme, ThemeId, ThemeTypography, ThemeSpacing, ThemeBorders};
 pub use colors::ThemeColors;
-pub use manager::ThemeManager;
\ No newline at end of file
+pub use file::{ThemeFile, ColorValue, TokenValue};
+pub use manager::ThemeManager;
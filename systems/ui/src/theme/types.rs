@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use super::colors::ThemeColors;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -31,6 +32,9 @@ pub struct Theme {
     pub typography: ThemeTypography,
     pub spacing: ThemeSpacing,
     pub borders: ThemeBorders,
+    /// Named numeric tokens; named color tokens live in `colors.custom`
+    #[serde(default)]
+    pub tokens: HashMap<String, f32>,
 }
 
 impl Theme {
@@ -41,6 +45,7 @@ impl Theme {
             typography: ThemeTypography::default(),
             spacing: ThemeSpacing::default(),
             borders: ThemeBorders::default(),
+            tokens: HashMap::new(),
         }
     }
     
@@ -51,6 +56,7 @@ impl Theme {
             typography: ThemeTypography::default(),
             spacing: ThemeSpacing::default(),
             borders: ThemeBorders::default(),
+            tokens: HashMap::new(),
         }
     }
 }